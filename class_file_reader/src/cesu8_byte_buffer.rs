@@ -1,6 +1,4 @@
 use crate::class_file_error::{ClassFileError, Result};
-use cesu8::from_java_cesu8;
-use std::i16;
 pub struct ByteBuffer<'a> {
     buffer: &'a [u8],
     pub position: usize,
@@ -29,6 +27,14 @@ impl<'a> ByteBuffer<'a> {
             .map(|bytes| u8::from_be_bytes(bytes.try_into().unwrap()))
     }
 
+    //读取当前位置的字节但不移动position
+    pub fn peek_u8(&self) -> Result<u8> {
+        self.buffer
+            .get(self.position)
+            .copied()
+            .ok_or(ClassFileError::UnexpectedEndOfData)
+    }
+
     pub fn read_i8(&mut self) -> Result<i8> {
         self.advance(std::mem::size_of::<i8>())
             .map(|bytes| i8::from_be_bytes(bytes.try_into().unwrap()))
@@ -81,21 +87,40 @@ impl<'a> ByteBuffer<'a> {
     }
 
     pub fn read_utf8(&mut self, len: usize) -> Result<String> {
-        self.advance(len)
-            .and_then(|bytes| {
-                from_java_cesu8(bytes).map_err(|_| ClassFileError::InvalidCesu8String)
-            })
-            .map(|cow_string| cow_string.into_owned())
+        self.advance(len).and_then(decode_modified_utf8)
     }
 
     pub fn read_bytes(&mut self, len: usize) -> Result<&'a [u8]> {
         self.advance(len)
     }
 
+    pub fn len(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffer.is_empty()
+    }
+
+    //剩余未读取的字节数
+    pub fn remaining(&self) -> usize {
+        self.buffer.len().saturating_sub(self.position)
+    }
+
     pub fn has_more_data(&self) -> bool {
         self.position < self.buffer.len()
     }
 
+    //跳过填充字节，使position对齐到boundary的整数倍，填充超出数据末尾时返回错误。boundary为0时不移动
+    pub fn align_to(&mut self, boundary: usize) -> Result<()> {
+        let aligned = self
+            .position
+            .checked_next_multiple_of(boundary)
+            .unwrap_or(self.position);
+        let padding = aligned - self.position;
+        self.advance(padding).map(|_| ())
+    }
+
     pub fn jump_to(&mut self, position: usize) {
         assert!(position <= self.buffer.len());
         self.position = position;
@@ -107,9 +132,81 @@ impl<'a> ByteBuffer<'a> {
     }
 }
 
+/// 解码class文件中的modified UTF-8字符串。
+/// 与标准UTF-8不同：NUL编码为0xC0 0x80，补充平面字符先拆成UTF-16代理对，每个代理各占3个字节。
+/// 不成对的代理替换为U+FFFD，其他非法的字节序列返回错误。
+/// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.4.7
+pub fn decode_modified_utf8(bytes: &[u8]) -> Result<String> {
+    //只有ASCII时两种编码相同
+    if bytes.iter().all(|b| (0x01..0x80).contains(b)) {
+        return Ok(String::from_utf8(bytes.to_vec()).unwrap());
+    }
+    let mut units: Vec<u16> = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    let continuation = |index: usize| match bytes.get(index) {
+        Some(b) if b & 0xc0 == 0x80 => Ok((b & 0x3f) as u16),
+        _ => Err(ClassFileError::InvalidCesu8String),
+    };
+    while index < bytes.len() {
+        let first = bytes[index];
+        let unit = match first {
+            0x01..=0x7f => {
+                index += 1;
+                first as u16
+            }
+            0xc0..=0xdf => {
+                let unit = ((first & 0x1f) as u16) << 6 | continuation(index + 1)?;
+                //除了NUL以外不允许超长编码
+                if unit != 0 && unit < 0x80 {
+                    return Err(ClassFileError::InvalidCesu8String);
+                }
+                index += 2;
+                unit
+            }
+            0xe0..=0xef => {
+                let unit = ((first & 0x0f) as u16) << 12
+                    | continuation(index + 1)? << 6
+                    | continuation(index + 2)?;
+                if unit < 0x800 {
+                    return Err(ClassFileError::InvalidCesu8String);
+                }
+                index += 3;
+                unit
+            }
+            //0x00、单独的后续字节以及4字节形式在modified UTF-8中都不合法
+            _ => return Err(ClassFileError::InvalidCesu8String),
+        };
+        units.push(unit);
+    }
+    //代理对在这里组合成补充平面字符
+    Ok(String::from_utf16_lossy(&units))
+}
+
+/// decode_modified_utf8的逆过程，把字符串编码为class文件使用的modified UTF-8。
+/// ClassFileBuilder写入常量池中的Utf8项时使用
+pub fn encode_modified_utf8(value: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(value.len());
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7f => bytes.push(unit as u8),
+            0x00..=0x7ff => {
+                bytes.push(0xc0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                bytes.push(0xe0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                bytes.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    bytes
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::cesu8_byte_buffer::ByteBuffer;
+    use crate::cesu8_byte_buffer::{decode_modified_utf8, encode_modified_utf8, ByteBuffer};
+    use crate::class_file_error::ClassFileError;
 
     #[test]
     fn buffer_works() {
@@ -120,4 +217,95 @@ mod tests {
         assert!(!buffer.has_more_data());
         assert!(buffer.read_u32().is_err());
     }
+
+    #[test]
+    fn align_from_each_offset() {
+        let mut data = vec![0xff; 4];
+        data.extend_from_slice(&(-2i32).to_be_bytes());
+        for (start, aligned) in [(0, 0), (1, 4), (2, 4), (3, 4)] {
+            let mut buffer = ByteBuffer::new(&data);
+            buffer.jump_to(start);
+            buffer.align_to(4).unwrap();
+            assert_eq!(buffer.position, aligned, "start {}", start);
+        }
+        let mut buffer = ByteBuffer::new(&data);
+        buffer.jump_to(1);
+        buffer.align_to(4).unwrap();
+        assert_eq!(buffer.read_i32().unwrap(), -2);
+        //已经对齐时不移动
+        buffer.align_to(4).unwrap();
+        assert_eq!(buffer.position, 8);
+        buffer.jump_to(1);
+        buffer.align_to(0).unwrap();
+        assert_eq!(buffer.position, 1);
+        //填充超出末尾
+        let mut buffer = ByteBuffer::new(&data[..6]);
+        buffer.jump_to(5);
+        assert_eq!(buffer.align_to(4), Err(ClassFileError::UnexpectedEndOfData));
+    }
+
+    #[test]
+    fn modified_utf8_round_trip() {
+        for value in [
+            "Hello",
+            "",
+            "a\0b",
+            "中文",
+            "emoji 😀 in 𝄞 text",
+            "\u{7ff}\u{800}\u{ffff}",
+        ] {
+            let bytes = encode_modified_utf8(value);
+            //编码结果中不会出现0x00以及4字节形式
+            assert!(bytes.iter().all(|b| *b != 0 && *b < 0xf0));
+            assert_eq!(decode_modified_utf8(&bytes).unwrap(), value);
+            let mut buffer = ByteBuffer::new(&bytes);
+            assert_eq!(buffer.read_utf8(bytes.len()).unwrap(), value);
+        }
+        assert_eq!(encode_modified_utf8("\0"), vec![0xc0, 0x80]);
+        //U+1F600 = 代理对 D83D DE00
+        assert_eq!(
+            encode_modified_utf8("😀"),
+            vec![0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80]
+        );
+    }
+
+    #[test]
+    fn modified_utf8_lone_surrogates() {
+        //单独的高位代理 D83D
+        assert_eq!(
+            decode_modified_utf8(&[b'a', 0xed, 0xa0, 0xbd, b'b']).unwrap(),
+            "a\u{fffd}b"
+        );
+        //单独的低位代理 DE00
+        assert_eq!(
+            decode_modified_utf8(&[0xed, 0xb8, 0x80]).unwrap(),
+            "\u{fffd}"
+        );
+        //顺序颠倒的代理对
+        assert_eq!(
+            decode_modified_utf8(&[0xed, 0xb8, 0x80, 0xed, 0xa0, 0xbd]).unwrap(),
+            "\u{fffd}\u{fffd}"
+        );
+    }
+
+    #[test]
+    fn modified_utf8_rejects_invalid_bytes() {
+        let invalid: [&[u8]; 6] = [
+            &[0x00],
+            &[0x80],
+            &[0xc3],
+            &[0xe4, 0xb8],
+            &[0xc1, 0x81],
+            //标准UTF-8的4字节形式
+            &[0xf0, 0x9f, 0x98, 0x80],
+        ];
+        for bytes in invalid {
+            assert_eq!(
+                decode_modified_utf8(bytes),
+                Err(ClassFileError::InvalidCesu8String),
+                "{:x?}",
+                bytes
+            );
+        }
+    }
 }
@@ -3,12 +3,10 @@ use crate::constant_pool::ConstantPool;
 use std::fmt::{Display, Formatter};
 
 use crate::attribute_info::{AttributeInfo, AttributeType};
-use crate::class_file_error::ClassFileError;
-use crate::class_file_error::Result;
+use crate::class_file_error::{ClassFileError, Result};
 use crate::field_info::FieldInfo;
 use crate::method_info::MethodInfo;
 use bitflags::bitflags;
-use cesu8::from_java_cesu8;
 bitflags! {
     /// Class flags
     /// https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.1-200-E.1
@@ -40,6 +38,24 @@ bitflags! {
     }
 }
 
+bitflags! {
+    /// InnerClasses属性中内部类的访问标记
+    /// https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.6-300-D.1-D.1
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct InnerClassAccessFlags: u16 {
+        const PUBLIC = 0x0001;
+        const PRIVATE = 0x0002;
+        const PROTECTED = 0x0004;
+        const STATIC = 0x0008;
+        const FINAL = 0x0010;
+        const INTERFACE = 0x0200;
+        const ABSTRACT = 0x0400;
+        const SYNTHETIC = 0x1000;
+        const ANNOTATION = 0x2000;
+        const ENUM = 0x4000;
+    }
+}
+
 impl Default for ClassAccessFlags {
     fn default() -> ClassAccessFlags {
         ClassAccessFlags::empty()
@@ -61,14 +77,19 @@ pub struct ClassFile {
 }
 
 impl ClassFile {
+    /// SourceFile属性的内容是指向常量池中Utf8常量的索引
+    /// https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.10
     pub fn source_file(&self) -> Result<Option<String>> {
         for info in &self.attribute_info {
             if let AttributeType::SourceFile = info.name {
-                return Ok(Some(
-                    from_java_cesu8(&info.info)
-                        .map_err(|_| ClassFileError::InvalidCesu8String)?
-                        .to_string(),
-                ));
+                let index: [u8; 2] = info.info.as_slice().try_into().map_err(|_| {
+                    ClassFileError::InvalidClassData(format!(
+                        "SourceFile attribute length should be 2, got {}",
+                        info.info.len()
+                    ))
+                })?;
+                let name = self.constant_pool.get_string(&u16::from_be_bytes(index))?;
+                return Ok(Some(name));
             }
         }
         Ok(None)
@@ -0,0 +1,571 @@
+//! 按指令序列生成class文件，生成的字节可以直接交给read_buffer。
+//! 主要用于测试：jsr/ret、wide、switch的填充等javac不会生成或者难以控制的字节码可以直接构造，
+//! 不需要另外编译并提交class文件
+//!
+//! ```
+//! use class_file_reader::class_file_builder::ClassFileBuilder;
+//! use class_file_reader::class_file_reader::read_buffer;
+//! use class_file_reader::instruction::Instruction::*;
+//!
+//! let bytes = ClassFileBuilder::new("Foo")
+//!     .method("add", "(II)I", &[Iload_0, Iload_1, Iadd, Ireturn])
+//!     .build();
+//! let class_file = read_buffer(&bytes).unwrap();
+//! assert_eq!(class_file.this_class_name, "Foo");
+//! ```
+use crate::class_file::ClassAccessFlags;
+use crate::constant_pool::{
+    ConstantPool, ConstantPoolEntry, ConstantPoolIndex, ConstantPoolPhysicalEntry,
+};
+use crate::field_info::FieldAccessFlags;
+use crate::instruction::{write_instructions, Instruction};
+use crate::method_info::MethodAccessFlags;
+
+//生成Java 8的class文件
+const MAJOR_VERSION: u16 = 52;
+
+struct FieldEntry {
+    access_flags: FieldAccessFlags,
+    name_index: ConstantPoolIndex,
+    descriptor_index: ConstantPoolIndex,
+}
+
+struct MethodEntry {
+    access_flags: MethodAccessFlags,
+    name_index: ConstantPoolIndex,
+    descriptor_index: ConstantPoolIndex,
+    //abstract和native方法没有Code属性
+    code: Option<CodeEntry>,
+}
+
+struct CodeEntry {
+    name_index: ConstantPoolIndex,
+    max_stack: u16,
+    max_locals: u16,
+    code: Vec<u8>,
+}
+
+/// class文件的构造器。类名、方法名等常量在添加时写入常量池，
+/// 指令中用到的常量池下标通过class、method_ref等方法得到，相同的常量只添加一次
+pub struct ClassFileBuilder {
+    constant_pool: ConstantPool,
+    access_flags: ClassAccessFlags,
+    this_class: ConstantPoolIndex,
+    //0表示没有父类，只有java/lang/Object是这样
+    super_class: ConstantPoolIndex,
+    interfaces: Vec<ConstantPoolIndex>,
+    fields: Vec<FieldEntry>,
+    methods: Vec<MethodEntry>,
+}
+
+impl ClassFileBuilder {
+    /// public类，父类是java/lang/Object
+    pub fn new(class_name: &str) -> ClassFileBuilder {
+        let mut builder = ClassFileBuilder {
+            constant_pool: ConstantPool::new(),
+            access_flags: ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER,
+            this_class: 0,
+            super_class: 0,
+            interfaces: Vec::new(),
+            fields: Vec::new(),
+            methods: Vec::new(),
+        };
+        builder.this_class = builder.class(class_name);
+        builder.super_class = builder.class("java/lang/Object");
+        builder
+    }
+
+    pub fn access_flags(&mut self, access_flags: ClassAccessFlags) -> &mut Self {
+        self.access_flags = access_flags;
+        self
+    }
+
+    /// None表示没有父类
+    pub fn super_class(&mut self, class_name: Option<&str>) -> &mut Self {
+        self.super_class = class_name.map_or(0, |name| self.class(name));
+        self
+    }
+
+    pub fn interface(&mut self, class_name: &str) -> &mut Self {
+        let index = self.class(class_name);
+        self.interfaces.push(index);
+        self
+    }
+
+    pub fn field(
+        &mut self,
+        access_flags: FieldAccessFlags,
+        name: &str,
+        descriptor: &str,
+    ) -> &mut Self {
+        let field = FieldEntry {
+            access_flags,
+            name_index: self.utf8(name),
+            descriptor_index: self.utf8(descriptor),
+        };
+        self.fields.push(field);
+        self
+    }
+
+    /// 添加public static方法
+    pub fn method(&mut self, name: &str, descriptor: &str, code: &[Instruction]) -> &mut Self {
+        self.method_with_flags(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::STATIC,
+            name,
+            descriptor,
+            code,
+        )
+    }
+
+    /// 添加方法。max_locals取参数和指令用到的局部变量中较大的一个；
+    /// 每条指令最多使操作数栈增加两个槽位，max_stack取指令条数的两倍
+    pub fn method_with_flags(
+        &mut self,
+        access_flags: MethodAccessFlags,
+        name: &str,
+        descriptor: &str,
+        code: &[Instruction],
+    ) -> &mut Self {
+        let has_code =
+            !access_flags.intersects(MethodAccessFlags::ABSTRACT | MethodAccessFlags::NATIVE);
+        let code = has_code.then(|| {
+            let is_static = access_flags.contains(MethodAccessFlags::STATIC);
+            let max_locals = code
+                .iter()
+                .filter_map(local_slots_end)
+                .fold(parameter_slots(descriptor, is_static), usize::max);
+            CodeEntry {
+                name_index: self.utf8("Code"),
+                max_stack: (code.len() * 2).min(u16::MAX as usize) as u16,
+                max_locals: max_locals.min(u16::MAX as usize) as u16,
+                code: write_instructions(code),
+            }
+        });
+        let method = MethodEntry {
+            access_flags,
+            name_index: self.utf8(name),
+            descriptor_index: self.utf8(descriptor),
+            code,
+        };
+        self.methods.push(method);
+        self
+    }
+
+    pub fn utf8(&mut self, value: &str) -> ConstantPoolIndex {
+        self.constant(ConstantPoolEntry::Utf8(value.to_string()))
+    }
+
+    pub fn class(&mut self, class_name: &str) -> ConstantPoolIndex {
+        let name_index = self.utf8(class_name);
+        self.constant(ConstantPoolEntry::ClassReference(name_index))
+    }
+
+    pub fn string(&mut self, value: &str) -> ConstantPoolIndex {
+        let utf8_index = self.utf8(value);
+        self.constant(ConstantPoolEntry::StringReference(utf8_index))
+    }
+
+    pub fn integer(&mut self, value: i32) -> ConstantPoolIndex {
+        self.constant(ConstantPoolEntry::Integer(value))
+    }
+
+    pub fn float(&mut self, value: f32) -> ConstantPoolIndex {
+        self.constant(ConstantPoolEntry::Float(value))
+    }
+
+    pub fn long(&mut self, value: i64) -> ConstantPoolIndex {
+        self.constant(ConstantPoolEntry::Long(value))
+    }
+
+    pub fn double(&mut self, value: f64) -> ConstantPoolIndex {
+        self.constant(ConstantPoolEntry::Double(value))
+    }
+
+    pub fn name_and_type(&mut self, name: &str, descriptor: &str) -> ConstantPoolIndex {
+        let name_index = self.utf8(name);
+        let descriptor_index = self.utf8(descriptor);
+        self.constant(ConstantPoolEntry::NameAndTypeDescriptor(
+            name_index,
+            descriptor_index,
+        ))
+    }
+
+    pub fn field_ref(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> ConstantPoolIndex {
+        let class_index = self.class(class_name);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.constant(ConstantPoolEntry::FieldReference(
+            class_index,
+            name_and_type,
+        ))
+    }
+
+    pub fn method_ref(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> ConstantPoolIndex {
+        let class_index = self.class(class_name);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.constant(ConstantPoolEntry::MethodReference(
+            class_index,
+            name_and_type,
+        ))
+    }
+
+    pub fn interface_method_ref(
+        &mut self,
+        class_name: &str,
+        name: &str,
+        descriptor: &str,
+    ) -> ConstantPoolIndex {
+        let class_index = self.class(class_name);
+        let name_and_type = self.name_and_type(name, descriptor);
+        self.constant(ConstantPoolEntry::InterfaceMethodReference(
+            class_index,
+            name_and_type,
+        ))
+    }
+
+    //已经有相同的常量时返回它的下标
+    fn constant(&mut self, entry: ConstantPoolEntry) -> ConstantPoolIndex {
+        let existing = self.constant_pool.entries.iter().position(
+            |physical| matches!(physical, ConstantPoolPhysicalEntry::Entry(e) if *e == entry),
+        );
+        match existing {
+            Some(position) => position as ConstantPoolIndex + 1,
+            None => {
+                let index = self.constant_pool.len() as ConstantPoolIndex + 1;
+                self.constant_pool.add(entry);
+                index
+            }
+        }
+    }
+
+    /// 按class文件的格式输出
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.1
+    pub fn build(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend(0xCAFEBABEu32.to_be_bytes());
+        bytes.extend(0u16.to_be_bytes());
+        bytes.extend(MAJOR_VERSION.to_be_bytes());
+        bytes.extend((self.constant_pool.len() as u16 + 1).to_be_bytes());
+        for entry in &self.constant_pool.entries {
+            if let ConstantPoolPhysicalEntry::Entry(entry) = entry {
+                entry.write_to_bytes(&mut bytes);
+            }
+        }
+        bytes.extend(self.access_flags.bits().to_be_bytes());
+        bytes.extend(self.this_class.to_be_bytes());
+        bytes.extend(self.super_class.to_be_bytes());
+        bytes.extend((self.interfaces.len() as u16).to_be_bytes());
+        for interface in &self.interfaces {
+            bytes.extend(interface.to_be_bytes());
+        }
+        bytes.extend((self.fields.len() as u16).to_be_bytes());
+        for field in &self.fields {
+            bytes.extend(field.access_flags.bits().to_be_bytes());
+            bytes.extend(field.name_index.to_be_bytes());
+            bytes.extend(field.descriptor_index.to_be_bytes());
+            bytes.extend(0u16.to_be_bytes());
+        }
+        bytes.extend((self.methods.len() as u16).to_be_bytes());
+        for method in &self.methods {
+            bytes.extend(method.access_flags.bits().to_be_bytes());
+            bytes.extend(method.name_index.to_be_bytes());
+            bytes.extend(method.descriptor_index.to_be_bytes());
+            match &method.code {
+                None => bytes.extend(0u16.to_be_bytes()),
+                Some(code) => {
+                    bytes.extend(1u16.to_be_bytes());
+                    write_code_attribute(&mut bytes, code);
+                }
+            }
+        }
+        //类没有属性
+        bytes.extend(0u16.to_be_bytes());
+        bytes
+    }
+}
+
+/// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.3
+/// 没有异常表和其他属性
+fn write_code_attribute(bytes: &mut Vec<u8>, code: &CodeEntry) {
+    bytes.extend(code.name_index.to_be_bytes());
+    bytes.extend((code.code.len() as u32 + 12).to_be_bytes());
+    bytes.extend(code.max_stack.to_be_bytes());
+    bytes.extend(code.max_locals.to_be_bytes());
+    bytes.extend((code.code.len() as u32).to_be_bytes());
+    bytes.extend_from_slice(&code.code);
+    bytes.extend(0u16.to_be_bytes());
+    bytes.extend(0u16.to_be_bytes());
+}
+
+//参数占用的局部变量槽位，long和double占两个，实例方法还有this
+fn parameter_slots(descriptor: &str, is_static: bool) -> usize {
+    let mut slots = if is_static { 0 } else { 1 };
+    let parameters = descriptor
+        .strip_prefix('(')
+        .and_then(|d| d.split(')').next())
+        .unwrap_or_default();
+    let mut chars = parameters.chars();
+    while let Some(c) = chars.next() {
+        let c = match c {
+            //数组都只占一个槽位，跳过所有维度和元素类型
+            '[' => chars.by_ref().find(|c| *c != '[').unwrap_or_default(),
+            c => {
+                if c == 'J' || c == 'D' {
+                    slots += 1;
+                }
+                c
+            }
+        };
+        if c == 'L' {
+            chars.by_ref().find(|c| *c == ';');
+        }
+        slots += 1;
+    }
+    slots
+}
+
+//指令访问的最后一个局部变量槽位之后的位置
+fn local_slots_end(instruction: &Instruction) -> Option<usize> {
+    let (index, size) = match instruction {
+        Instruction::Iload(n)
+        | Instruction::Fload(n)
+        | Instruction::Aload(n)
+        | Instruction::Istore(n)
+        | Instruction::Fstore(n)
+        | Instruction::Astore(n)
+        | Instruction::Ret(n) => (*n as usize, 1),
+        Instruction::Lload(n)
+        | Instruction::Dload(n)
+        | Instruction::Lstore(n)
+        | Instruction::Dstore(n) => (*n as usize, 2),
+        Instruction::IloadWide(n)
+        | Instruction::FloadWide(n)
+        | Instruction::AloadWide(n)
+        | Instruction::IstoreWide(n)
+        | Instruction::FstoreWide(n)
+        | Instruction::AstoreWide(n)
+        | Instruction::RetWide(n)
+        | Instruction::Iinc(n, _) => (*n as usize, 1),
+        Instruction::LloadWide(n)
+        | Instruction::DloadWide(n)
+        | Instruction::LstoreWide(n)
+        | Instruction::DstoreWide(n) => (*n as usize, 2),
+        Instruction::Iload_0
+        | Instruction::Fload_0
+        | Instruction::Aload_0
+        | Instruction::Istore_0
+        | Instruction::Fstore_0
+        | Instruction::Astore_0 => (0, 1),
+        Instruction::Iload_1
+        | Instruction::Fload_1
+        | Instruction::Aload_1
+        | Instruction::Istore_1
+        | Instruction::Fstore_1
+        | Instruction::Astore_1 => (1, 1),
+        Instruction::Iload_2
+        | Instruction::Fload_2
+        | Instruction::Aload_2
+        | Instruction::Istore_2
+        | Instruction::Fstore_2
+        | Instruction::Astore_2 => (2, 1),
+        Instruction::Iload_3
+        | Instruction::Fload_3
+        | Instruction::Aload_3
+        | Instruction::Istore_3
+        | Instruction::Fstore_3
+        | Instruction::Astore_3 => (3, 1),
+        Instruction::Lload_0
+        | Instruction::Dload_0
+        | Instruction::Lstore_0
+        | Instruction::Dstore_0 => (0, 2),
+        Instruction::Lload_1
+        | Instruction::Dload_1
+        | Instruction::Lstore_1
+        | Instruction::Dstore_1 => (1, 2),
+        Instruction::Lload_2
+        | Instruction::Dload_2
+        | Instruction::Lstore_2
+        | Instruction::Dstore_2 => (2, 2),
+        Instruction::Lload_3
+        | Instruction::Dload_3
+        | Instruction::Lstore_3
+        | Instruction::Dstore_3 => (3, 2),
+        _ => return None,
+    };
+    Some(index + size)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::attribute_info::AttributeType;
+    use crate::class_file::ClassAccessFlags;
+    use crate::class_file_builder::ClassFileBuilder;
+    use crate::class_file_reader::read_buffer;
+    use crate::constant_pool::ConstantPoolEntry;
+    use crate::field_info::FieldAccessFlags;
+    use crate::instruction::Instruction::*;
+    use crate::instruction::{read_instructions, Instruction, LookupSwitch, TableSwitch};
+    use crate::method_info::MethodAccessFlags;
+
+    //Code属性中的max_stack、max_locals和指令
+    fn read_code(info: &[u8]) -> (u16, u16, Vec<Instruction>) {
+        let max_stack = u16::from_be_bytes([info[0], info[1]]);
+        let max_locals = u16::from_be_bytes([info[2], info[3]]);
+        let length = u32::from_be_bytes([info[4], info[5], info[6], info[7]]) as usize;
+        let instructions = read_instructions(&info[8..8 + length])
+            .unwrap()
+            .into_iter()
+            .map(|(_, instruction)| instruction)
+            .collect();
+        assert_eq!(info.len(), 8 + length + 4);
+        (max_stack, max_locals, instructions)
+    }
+
+    #[test]
+    fn build_and_read_class() {
+        //nop使lookupswitch需要填充，tableswitch的位置由前面的指令长度决定
+        let switch_code = vec![
+            Nop,
+            Iload_0,
+            Lookupswitch(LookupSwitch {
+                default: 30,
+                pairs: vec![(-1, 30), (7, 32)],
+            }),
+            Iload_0,
+            Tableswitch(TableSwitch {
+                default: 20,
+                low: 0,
+                high: 1,
+                offsets: vec![20, 22],
+            }),
+            Iconst_0,
+            Ireturn,
+            Iconst_1,
+            Ireturn,
+        ];
+        let wide_code = vec![
+            Dconst_1,
+            DstoreWide(300),
+            Iinc(4, -128),
+            Iinc(4, 1000),
+            Lload_2,
+            Pop2,
+            Return,
+        ];
+        let mut builder = ClassFileBuilder::new("test/Built");
+        builder
+            .super_class(Some("java/lang/Number"))
+            .interface("java/lang/Runnable")
+            .field(FieldAccessFlags::PRIVATE, "count", "I")
+            .method("add", "(II)I", &[Iload_0, Iload_1, Iadd, Ireturn])
+            .method("select", "(I)I", &switch_code)
+            .method("wide", "(JI)V", &wide_code)
+            .method_with_flags(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::ABSTRACT,
+                "run",
+                "()V",
+                &[],
+            );
+        let string_index = builder.string("中文\0");
+        let method_index = builder.method_ref("test/Built", "add", "(II)I");
+        let long_index = builder.long(i64::MIN);
+        assert_eq!(builder.class("test/Built"), 2);
+        assert_eq!(
+            builder.method_ref("test/Built", "add", "(II)I"),
+            method_index
+        );
+
+        let class_file = read_buffer(&builder.build()).unwrap();
+        assert_eq!(class_file.this_class_name, "test/Built");
+        assert_eq!(
+            class_file.super_class_name.as_deref(),
+            Some("java/lang/Number")
+        );
+        assert_eq!(class_file.interface_names, vec!["java/lang/Runnable"]);
+        assert_eq!(
+            class_file.access_flags,
+            ClassAccessFlags::PUBLIC | ClassAccessFlags::SUPER
+        );
+        assert_eq!(class_file.field_info.len(), 1);
+        assert_eq!(class_file.field_info[0].name, "count");
+        assert_eq!(class_file.field_info[0].descriptor, "I");
+        let cp = &class_file.constant_pool;
+        assert_eq!(
+            *cp.get(&string_index).unwrap(),
+            ConstantPoolEntry::StringReference(string_index - 1)
+        );
+        assert_eq!(cp.get_string(&(string_index - 1)).unwrap(), "中文\0");
+        assert_eq!(
+            *cp.get(&long_index).unwrap(),
+            ConstantPoolEntry::Long(i64::MIN)
+        );
+        assert!(cp.get(&(long_index + 1)).is_err());
+
+        let methods: Vec<_> = class_file
+            .method_info
+            .iter()
+            .map(|m| (m.name.as_str(), m.descriptor.as_str(), m.attributes.len()))
+            .collect();
+        assert_eq!(
+            methods,
+            vec![
+                ("add", "(II)I", 1),
+                ("select", "(I)I", 1),
+                ("wide", "(JI)V", 1),
+                ("run", "()V", 0)
+            ]
+        );
+        let codes: Vec<_> = class_file
+            .method_info
+            .iter()
+            .filter_map(|m| m.attributes.first())
+            .map(|attribute| {
+                assert_eq!(attribute.name, AttributeType::Code);
+                read_code(&attribute.info)
+            })
+            .collect();
+        assert_eq!(codes[0], (8, 2, vec![Iload_0, Iload_1, Iadd, Ireturn]));
+        assert_eq!(codes[1], (18, 1, switch_code));
+        //long参数占0-1，int参数是2；double存储在300-301
+        assert_eq!(codes[2], (14, 302, wide_code));
+    }
+
+    #[test]
+    fn build_class_without_super() {
+        let mut builder = ClassFileBuilder::new("java/lang/Object");
+        builder
+            .super_class(None)
+            .access_flags(ClassAccessFlags::PUBLIC)
+            .method_with_flags(MethodAccessFlags::PUBLIC, "<init>", "()V", &[Return])
+            .method_with_flags(
+                MethodAccessFlags::PUBLIC | MethodAccessFlags::NATIVE,
+                "hashCode",
+                "()I",
+                &[],
+            )
+            .method_with_flags(
+                MethodAccessFlags::PUBLIC,
+                "equals",
+                "(Ljava/lang/Object;[[DJ)Z",
+                &[Iconst_0, Ireturn],
+            );
+        let class_file = read_buffer(&builder.build()).unwrap();
+        assert_eq!(class_file.super_class_name, None);
+        assert_eq!(class_file.access_flags, ClassAccessFlags::PUBLIC);
+        //实例方法的this占一个槽位
+        let (_, max_locals, _) = read_code(&class_file.method_info[0].attributes[0].info);
+        assert_eq!(max_locals, 1);
+        assert!(class_file.method_info[1].attributes.is_empty());
+        let (_, max_locals, _) = read_code(&class_file.method_info[2].attributes[0].info);
+        assert_eq!(max_locals, 5);
+    }
+}
@@ -118,8 +118,6 @@ fn read_field_info(buffer: &mut ByteBuffer, cp: &ConstantPool) -> Result<Vec<Fie
 ///     attribute_info attributes[attributes_count];
 /// }
 /// ```
-///
-
 fn read_one_field(buffer: &mut ByteBuffer, cp: &ConstantPool) -> Result<FieldInfo> {
     let access_flag = buffer.read_u16()?;
 
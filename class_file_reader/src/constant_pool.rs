@@ -1,4 +1,4 @@
-use crate::cesu8_byte_buffer::ByteBuffer;
+use crate::cesu8_byte_buffer::{encode_modified_utf8, ByteBuffer};
 use crate::class_file_error::{ClassFileError, Result};
 use std::fmt::{Display, Formatter};
 pub type ConstantPoolIndex = u16;
@@ -72,6 +72,45 @@ impl ConstantPoolEntry {
         }
     }
 
+    /// read_from_bytes的逆过程，写入tag和后续的信息
+    pub fn write_to_bytes(&self, bytes: &mut Vec<u8>) {
+        match self {
+            ConstantPoolEntry::Utf8(value) => {
+                let encoded = encode_modified_utf8(value);
+                bytes.push(1);
+                bytes.extend((encoded.len() as u16).to_be_bytes());
+                bytes.extend(encoded);
+            }
+            ConstantPoolEntry::Integer(value) => write_tagged(bytes, 3, &value.to_be_bytes()),
+            ConstantPoolEntry::Float(value) => write_tagged(bytes, 4, &value.to_be_bytes()),
+            ConstantPoolEntry::Long(value) => write_tagged(bytes, 5, &value.to_be_bytes()),
+            ConstantPoolEntry::Double(value) => write_tagged(bytes, 6, &value.to_be_bytes()),
+            ConstantPoolEntry::ClassReference(index) => {
+                write_tagged(bytes, 7, &index.to_be_bytes())
+            }
+            ConstantPoolEntry::StringReference(index) => {
+                write_tagged(bytes, 8, &index.to_be_bytes())
+            }
+            ConstantPoolEntry::FieldReference(f1, f2) => write_tagged_pair(bytes, 9, *f1, *f2),
+            ConstantPoolEntry::MethodReference(f1, f2) => write_tagged_pair(bytes, 10, *f1, *f2),
+            ConstantPoolEntry::InterfaceMethodReference(f1, f2) => {
+                write_tagged_pair(bytes, 11, *f1, *f2)
+            }
+            ConstantPoolEntry::NameAndTypeDescriptor(f1, f2) => {
+                write_tagged_pair(bytes, 12, *f1, *f2)
+            }
+            ConstantPoolEntry::MethodHandler(kind, index) => {
+                bytes.extend([15, *kind]);
+                bytes.extend(index.to_be_bytes());
+            }
+            ConstantPoolEntry::MethodType(index) => write_tagged(bytes, 16, &index.to_be_bytes()),
+            ConstantPoolEntry::Dynamic(f1, f2) => write_tagged_pair(bytes, 17, *f1, *f2),
+            ConstantPoolEntry::InvokeDynamic(f1, f2) => write_tagged_pair(bytes, 18, *f1, *f2),
+            ConstantPoolEntry::Module(index) => write_tagged(bytes, 19, &index.to_be_bytes()),
+            ConstantPoolEntry::Package(index) => write_tagged(bytes, 20, &index.to_be_bytes()),
+        }
+    }
+
     fn read_utf8(buffer: &mut ByteBuffer) -> Result<ConstantPoolEntry> {
         let result = buffer.read_u16()?;
         buffer
@@ -80,6 +119,17 @@ impl ConstantPoolEntry {
     }
 }
 
+fn write_tagged(bytes: &mut Vec<u8>, tag: u8, info: &[u8]) {
+    bytes.push(tag);
+    bytes.extend_from_slice(info);
+}
+
+fn write_tagged_pair(bytes: &mut Vec<u8>, tag: u8, f1: ConstantPoolIndex, f2: ConstantPoolIndex) {
+    bytes.push(tag);
+    bytes.extend(f1.to_be_bytes());
+    bytes.extend(f2.to_be_bytes());
+}
+
 //面向32位计算机设计的。所以double和long会占用两个字节，使用空占位符占位，
 #[derive(Debug)]
 pub enum ConstantPoolPhysicalEntry {
@@ -3,13 +3,52 @@ use crate::class_file_error::{ClassFileError, Result};
 
 //https://docs.oracle.com/javase/specs/jvms/se7/html/jvms-6.html#jvms-6.5
 
+//lookupswitch的操作数。 跳转偏移都是相对于该指令操作码所在的位置
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct LookupSwitch {
+    pub default: i32,
+    pub pairs: Vec<(i32, i32)>,
+}
+
+impl LookupSwitch {
+    pub fn branch_offset(&self, key: i32) -> i32 {
+        self.pairs
+            .iter()
+            .find(|(matched, _)| *matched == key)
+            .map_or(self.default, |(_, offset)| *offset)
+    }
+}
+
+//tableswitch的操作数。 offsets对应low..=high
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TableSwitch {
+    pub default: i32,
+    pub low: i32,
+    pub high: i32,
+    pub offsets: Vec<i32>,
+}
+
+impl TableSwitch {
+    pub fn branch_offset(&self, index: i32) -> i32 {
+        if index < self.low || index > self.high {
+            self.default
+        } else {
+            self.offsets[(index as i64 - self.low as i64) as usize]
+        }
+    }
+}
+
+/// 解码后的指令，变体名和规范中的助记符一致（首字母大写）。
+/// 操作数保持class文件中的宽度：局部变量下标和bipush是u8，常量池下标是u16，分支偏移是有符号数
 #[allow(non_camel_case_types)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum Instruction {
     Aaload,
     Aastore,
     Aconst_null,
     Aload(u8),
+    //wide前缀的形式，局部变量下标是u16
+    AloadWide(u16),
     Aload_0,
     Aload_1,
     Aload_2,
@@ -18,6 +57,7 @@ pub enum Instruction {
     Areturn,
     Arraylength,
     Astore(u8),
+    AstoreWide(u16),
     Astore_0,
     Astore_1,
     Astore_2,
@@ -41,6 +81,7 @@ pub enum Instruction {
     Dconst_1,
     Ddiv,
     Dload(u8),
+    DloadWide(u16),
     Dload_0,
     Dload_1,
     Dload_2,
@@ -50,6 +91,7 @@ pub enum Instruction {
     Drem,
     Dreturn,
     Dstore(u8),
+    DstoreWide(u16),
     Dstore_0,
     Dstore_1,
     Dstore_2,
@@ -74,6 +116,7 @@ pub enum Instruction {
     Fconst_2,
     Fdiv,
     Fload(u8),
+    FloadWide(u16),
     Fload_0,
     Fload_1,
     Fload_2,
@@ -83,6 +126,7 @@ pub enum Instruction {
     Frem,
     Freturn,
     Fstore(u8),
+    FstoreWide(u16),
     Fstore_0,
     Fstore_1,
     Fstore_2,
@@ -126,8 +170,10 @@ pub enum Instruction {
     Ifle(i16),
     Ifnonnull(i16),
     Ifnull(i16),
-    Iinc(u8, i8),
+    //wide形式的iinc使用u16的下标和i16的增量，普通形式读取后扩展
+    Iinc(u16, i16),
     Iload(u8),
+    IloadWide(u16),
     Iload_0,
     Iload_1,
     Iload_2,
@@ -146,6 +192,7 @@ pub enum Instruction {
     Ishl,
     Ishr,
     Istore(u8),
+    IstoreWide(u16),
     Istore_0,
     Istore_1,
     Istore_2,
@@ -170,19 +217,21 @@ pub enum Instruction {
     Ldc2_w(u16),
     Ldiv,
     Lload(u8),
+    LloadWide(u16),
     Lload_0,
     Lload_1,
     Lload_2,
     Lload_3,
-    Lmut,
+    Lmul,
     Lneg,
-    Lookupswitch,
+    Lookupswitch(LookupSwitch),
     Lor,
     Lrem,
     Lreturn,
     Lshl,
     Lshr,
     Lstore(u8),
+    LstoreWide(u16),
     Lstore_0,
     Lstore_1,
     Lstore_2,
@@ -201,15 +250,23 @@ pub enum Instruction {
     Putfield(u16),
     Putstatic(u16),
     Ret(u8),
+    RetWide(u16),
     Return,
     Saload,
     Sastore,
     Sipush(i16),
     Swap,
-    Tableswitch,
-    Wide,
+    Tableswitch(TableSwitch),
+}
+
+impl Instruction {
+    #[deprecated(note = "use Instruction::Lmul")]
+    #[allow(non_upper_case_globals)]
+    pub const Lmut: Instruction = Instruction::Lmul;
 }
 
+/// 从buffer的当前位置解码一条指令，buffer移动到下一条指令的位置。
+/// tableswitch/lookupswitch的填充按buffer的位置对齐，所以buffer的起点必须是方法code的起点
 pub fn read_one_instruction(buffer: &mut ByteBuffer) -> Result<Instruction> {
     let op_code = buffer.read_u8()?;
     let instruction = match op_code {
@@ -333,7 +390,7 @@ pub fn read_one_instruction(buffer: &mut ByteBuffer) -> Result<Instruction> {
         0x9e => Instruction::Ifle(buffer.read_i16()?),
         0xc7 => Instruction::Ifnonnull(buffer.read_i16()?),
         0xc6 => Instruction::Ifnull(buffer.read_i16()?),
-        0x84 => Instruction::Iinc(buffer.read_u8()?, buffer.read_i8()?),
+        0x84 => Instruction::Iinc(buffer.read_u8()? as u16, buffer.read_i8()? as i16),
         0x15 => Instruction::Iload(buffer.read_u8()?),
         0x1a => Instruction::Iload_0,
         0x1b => Instruction::Iload_1,
@@ -398,10 +455,9 @@ pub fn read_one_instruction(buffer: &mut ByteBuffer) -> Result<Instruction> {
         0x1f => Instruction::Lload_1,
         0x20 => Instruction::Lload_2,
         0x21 => Instruction::Lload_3,
-        0x69 => Instruction::Lmut,
+        0x69 => Instruction::Lmul,
         0x75 => Instruction::Lneg,
-        //TODO 实现Lookupswitch
-        0xab => Instruction::Lookupswitch,
+        0xab => Instruction::Lookupswitch(read_lookup_switch(buffer)?),
         0x81 => Instruction::Lor,
         0x71 => Instruction::Lrem,
         0xad => Instruction::Lreturn,
@@ -440,11 +496,8 @@ pub fn read_one_instruction(buffer: &mut ByteBuffer) -> Result<Instruction> {
         0x56 => Instruction::Sastore,
         0x11 => Instruction::Sipush(buffer.read_i16()?),
         0x5f => Instruction::Swap,
-        //TODO
-        0xaa => Instruction::Tableswitch,
-        //TODO 实现Wide的一些指令
-        0xc4 => Instruction::Wide,
-
+        0xaa => Instruction::Tableswitch(read_table_switch(buffer)?),
+        0xc4 => read_wide(buffer)?,
         op_code => {
             return Err(ClassFileError::InvalidCode(format!(
                 "Invalid Op Code {op_code}"
@@ -453,3 +506,946 @@ pub fn read_one_instruction(buffer: &mut ByteBuffer) -> Result<Instruction> {
     };
     Ok(instruction)
 }
+
+/// 解码整个方法的code，返回每条指令和它的操作码所在的地址
+pub fn read_instructions(code: &[u8]) -> Result<Vec<(usize, Instruction)>> {
+    let mut buffer = ByteBuffer::new(code);
+    let mut instructions = Vec::new();
+    while buffer.has_more_data() {
+        let pc = buffer.position;
+        instructions.push((pc, read_one_instruction(&mut buffer)?));
+    }
+    Ok(instructions)
+}
+
+/// 把指令编码后追加到code，是read_one_instruction的逆过程。
+/// code必须从方法code的起点开始，tableswitch/lookupswitch按code的长度填充对齐。
+/// iinc的下标和增量都在u8/i8范围内时使用普通形式，否则加上wide前缀
+pub fn write_instruction(code: &mut Vec<u8>, instruction: &Instruction) {
+    match instruction {
+        Instruction::Aaload => code.push(0x32),
+        Instruction::Aastore => code.push(0x53),
+        Instruction::Aconst_null => code.push(0x1),
+        Instruction::Aload(operand) => {
+            code.push(0x19);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Aload_0 => code.push(0x2a),
+        Instruction::Aload_1 => code.push(0x2b),
+        Instruction::Aload_2 => code.push(0x2c),
+        Instruction::Aload_3 => code.push(0x2d),
+        Instruction::Anewarray(operand) => {
+            code.push(0xbd);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Areturn => code.push(0xb0),
+        Instruction::Arraylength => code.push(0xbe),
+        Instruction::Astore(operand) => {
+            code.push(0x3a);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Astore_0 => code.push(0x4b),
+        Instruction::Astore_1 => code.push(0x4c),
+        Instruction::Astore_2 => code.push(0x4d),
+        Instruction::Astore_3 => code.push(0x4e),
+        Instruction::Athrow => code.push(0xbf),
+        Instruction::Baload => code.push(0x33),
+        Instruction::Bastore => code.push(0x54),
+        Instruction::Bipush(operand) => {
+            code.push(0x10);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Caload => code.push(0x34),
+        Instruction::Castore => code.push(0x55),
+        Instruction::Checkcast(operand) => {
+            code.push(0xc0);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::D2f => code.push(0x90),
+        Instruction::D2i => code.push(0x8e),
+        Instruction::D2l => code.push(0x8f),
+        Instruction::Dadd => code.push(0x63),
+        Instruction::Daload => code.push(0x31),
+        Instruction::Dastore => code.push(0x52),
+        Instruction::Dcmpg => code.push(0x98),
+        Instruction::Dcmpl => code.push(0x97),
+        Instruction::Dconst_0 => code.push(0xe),
+        Instruction::Dconst_1 => code.push(0xf),
+        Instruction::Ddiv => code.push(0x6f),
+        Instruction::Dload(operand) => {
+            code.push(0x18);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Dload_0 => code.push(0x26),
+        Instruction::Dload_1 => code.push(0x27),
+        Instruction::Dload_2 => code.push(0x28),
+        Instruction::Dload_3 => code.push(0x29),
+        Instruction::Dmul => code.push(0x6b),
+        Instruction::Dneg => code.push(0x77),
+        Instruction::Drem => code.push(0x73),
+        Instruction::Dreturn => code.push(0xaf),
+        Instruction::Dstore(operand) => {
+            code.push(0x39);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Dstore_0 => code.push(0x47),
+        Instruction::Dstore_1 => code.push(0x48),
+        Instruction::Dstore_2 => code.push(0x49),
+        Instruction::Dstore_3 => code.push(0x4a),
+        Instruction::Dsub => code.push(0x67),
+        Instruction::Dup => code.push(0x59),
+        Instruction::Dup_x1 => code.push(0x5a),
+        Instruction::Dup_x2 => code.push(0x5b),
+        Instruction::Dup2 => code.push(0x5c),
+        Instruction::Dup2_x1 => code.push(0x5d),
+        Instruction::Dup2_x2 => code.push(0x5e),
+        Instruction::F2d => code.push(0x8d),
+        Instruction::F2i => code.push(0x8b),
+        Instruction::F2l => code.push(0x8c),
+        Instruction::Fadd => code.push(0x62),
+        Instruction::Faload => code.push(0x30),
+        Instruction::Fastore => code.push(0x51),
+        Instruction::Fcmpl => code.push(0x95),
+        Instruction::Fcmpg => code.push(0x96),
+        Instruction::Fconst_0 => code.push(0xb),
+        Instruction::Fconst_1 => code.push(0xc),
+        Instruction::Fconst_2 => code.push(0xd),
+        Instruction::Fdiv => code.push(0x6e),
+        Instruction::Fload(operand) => {
+            code.push(0x17);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Fload_0 => code.push(0x22),
+        Instruction::Fload_1 => code.push(0x23),
+        Instruction::Fload_2 => code.push(0x24),
+        Instruction::Fload_3 => code.push(0x25),
+        Instruction::Fmul => code.push(0x6a),
+        Instruction::Fneg => code.push(0x76),
+        Instruction::Frem => code.push(0x72),
+        Instruction::Freturn => code.push(0xae),
+        Instruction::Fstore(operand) => {
+            code.push(0x38);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Fstore_0 => code.push(0x43),
+        Instruction::Fstore_1 => code.push(0x44),
+        Instruction::Fstore_2 => code.push(0x45),
+        Instruction::Fstore_3 => code.push(0x46),
+        Instruction::Fsub => code.push(0x66),
+        Instruction::Getfield(operand) => {
+            code.push(0xb4);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Getstatic(operand) => {
+            code.push(0xb2);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Goto(operand) => {
+            code.push(0xa7);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Goto_w(operand) => {
+            code.push(0xc8);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::I2b => code.push(0x91),
+        Instruction::I2c => code.push(0x92),
+        Instruction::I2d => code.push(0x87),
+        Instruction::I2f => code.push(0x86),
+        Instruction::I2l => code.push(0x85),
+        Instruction::I2s => code.push(0x93),
+        Instruction::Iadd => code.push(0x60),
+        Instruction::Iaload => code.push(0x2e),
+        Instruction::Iand => code.push(0x7e),
+        Instruction::Iastore => code.push(0x4f),
+        Instruction::Iconst_m1 => code.push(0x2),
+        Instruction::Iconst_0 => code.push(0x3),
+        Instruction::Iconst_1 => code.push(0x4),
+        Instruction::Iconst_2 => code.push(0x5),
+        Instruction::Iconst_3 => code.push(0x6),
+        Instruction::Iconst_4 => code.push(0x7),
+        Instruction::Iconst_5 => code.push(0x8),
+        Instruction::Idiv => code.push(0x6c),
+        Instruction::If_acmpeq(operand) => {
+            code.push(0xa5);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_acmpne(operand) => {
+            code.push(0xa6);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmpeq(operand) => {
+            code.push(0x9f);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmpne(operand) => {
+            code.push(0xa0);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmplt(operand) => {
+            code.push(0xa1);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmpge(operand) => {
+            code.push(0xa2);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmpgt(operand) => {
+            code.push(0xa3);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::If_icmple(operand) => {
+            code.push(0xa4);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifeq(operand) => {
+            code.push(0x99);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifne(operand) => {
+            code.push(0x9a);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Iflt(operand) => {
+            code.push(0x9b);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifge(operand) => {
+            code.push(0x9c);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifgt(operand) => {
+            code.push(0x9d);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifle(operand) => {
+            code.push(0x9e);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifnonnull(operand) => {
+            code.push(0xc7);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ifnull(operand) => {
+            code.push(0xc6);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Iload(operand) => {
+            code.push(0x15);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Iload_0 => code.push(0x1a),
+        Instruction::Iload_1 => code.push(0x1b),
+        Instruction::Iload_2 => code.push(0x1c),
+        Instruction::Iload_3 => code.push(0x1d),
+        Instruction::Imul => code.push(0x68),
+        Instruction::Ineg => code.push(0x74),
+        Instruction::Instanceof(operand) => {
+            code.push(0xc1);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Invokespecial(operand) => {
+            code.push(0xb7);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Invokestatic(operand) => {
+            code.push(0xb8);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Invokevirtual(operand) => {
+            code.push(0xb6);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ior => code.push(0x80),
+        Instruction::Irem => code.push(0x70),
+        Instruction::Ireturn => code.push(0xac),
+        Instruction::Ishl => code.push(0x78),
+        Instruction::Ishr => code.push(0x7a),
+        Instruction::Istore(operand) => {
+            code.push(0x36);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Istore_0 => code.push(0x3b),
+        Instruction::Istore_1 => code.push(0x3c),
+        Instruction::Istore_2 => code.push(0x3d),
+        Instruction::Istore_3 => code.push(0x3e),
+        Instruction::Isub => code.push(0x64),
+        Instruction::Iushr => code.push(0x7c),
+        Instruction::Ixor => code.push(0x82),
+        Instruction::Jsr(operand) => {
+            code.push(0xa8);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Jsr_w(operand) => {
+            code.push(0xc9);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::L2d => code.push(0x8a),
+        Instruction::L2f => code.push(0x89),
+        Instruction::L2i => code.push(0x88),
+        Instruction::Ladd => code.push(0x61),
+        Instruction::Laload => code.push(0x2f),
+        Instruction::Land => code.push(0x7f),
+        Instruction::Lastore => code.push(0x50),
+        Instruction::Lcmp => code.push(0x94),
+        Instruction::Lconst_0 => code.push(0x9),
+        Instruction::Lconst_1 => code.push(0xa),
+        Instruction::Ldc(operand) => {
+            code.push(0x12);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ldc_w(operand) => {
+            code.push(0x13);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ldc2_w(operand) => {
+            code.push(0x14);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ldiv => code.push(0x6d),
+        Instruction::Lload(operand) => {
+            code.push(0x16);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Lload_0 => code.push(0x1e),
+        Instruction::Lload_1 => code.push(0x1f),
+        Instruction::Lload_2 => code.push(0x20),
+        Instruction::Lload_3 => code.push(0x21),
+        Instruction::Lmul => code.push(0x69),
+        Instruction::Lneg => code.push(0x75),
+        Instruction::Lor => code.push(0x81),
+        Instruction::Lrem => code.push(0x71),
+        Instruction::Lreturn => code.push(0xad),
+        Instruction::Lshl => code.push(0x79),
+        Instruction::Lshr => code.push(0x7b),
+        Instruction::Lstore(operand) => {
+            code.push(0x37);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Lstore_0 => code.push(0x3f),
+        Instruction::Lstore_1 => code.push(0x40),
+        Instruction::Lstore_2 => code.push(0x41),
+        Instruction::Lstore_3 => code.push(0x42),
+        Instruction::Lsub => code.push(0x65),
+        Instruction::Lushr => code.push(0x7d),
+        Instruction::Lxor => code.push(0x83),
+        Instruction::Monitorenter => code.push(0xc2),
+        Instruction::Monitorexit => code.push(0xc3),
+        Instruction::New(operand) => {
+            code.push(0xbb);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::NewArray(operand) => {
+            code.push(0xbc);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Nop => code.push(0x0),
+        Instruction::Pop => code.push(0x57),
+        Instruction::Pop2 => code.push(0x58),
+        Instruction::Putfield(operand) => {
+            code.push(0xb5);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Putstatic(operand) => {
+            code.push(0xb3);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Ret(operand) => {
+            code.push(0xa9);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Return => code.push(0xb1),
+        Instruction::Saload => code.push(0x35),
+        Instruction::Sastore => code.push(0x56),
+        Instruction::Sipush(operand) => {
+            code.push(0x11);
+            code.extend(operand.to_be_bytes());
+        }
+        Instruction::Swap => code.push(0x5f),
+        Instruction::Iinc(index, delta) => match (u8::try_from(*index), i8::try_from(*delta)) {
+            (Ok(index), Ok(delta)) => code.extend([0x84, index, delta as u8]),
+            _ => {
+                code.extend([0xc4, 0x84]);
+                code.extend(index.to_be_bytes());
+                code.extend(delta.to_be_bytes());
+            }
+        },
+        Instruction::Invokedynamic(index) => {
+            code.push(0xba);
+            code.extend(index.to_be_bytes());
+            code.extend([0, 0]);
+        }
+        Instruction::Invokeinterface(index, count) => {
+            code.push(0xb9);
+            code.extend(index.to_be_bytes());
+            code.extend([*count, 0]);
+        }
+        Instruction::Multianewarray(index, dimensions) => {
+            code.push(0xc5);
+            code.extend(index.to_be_bytes());
+            code.push(*dimensions);
+        }
+        Instruction::Lookupswitch(lookup_switch) => {
+            code.push(0xab);
+            write_switch_padding(code);
+            code.extend(lookup_switch.default.to_be_bytes());
+            code.extend((lookup_switch.pairs.len() as i32).to_be_bytes());
+            for (matched, offset) in &lookup_switch.pairs {
+                code.extend(matched.to_be_bytes());
+                code.extend(offset.to_be_bytes());
+            }
+        }
+        Instruction::Tableswitch(table_switch) => {
+            code.push(0xaa);
+            write_switch_padding(code);
+            for value in [table_switch.default, table_switch.low, table_switch.high] {
+                code.extend(value.to_be_bytes());
+            }
+            for offset in &table_switch.offsets {
+                code.extend(offset.to_be_bytes());
+            }
+        }
+        Instruction::AloadWide(index) => write_wide(code, 0x19, *index),
+        Instruction::AstoreWide(index) => write_wide(code, 0x3a, *index),
+        Instruction::DloadWide(index) => write_wide(code, 0x18, *index),
+        Instruction::DstoreWide(index) => write_wide(code, 0x39, *index),
+        Instruction::FloadWide(index) => write_wide(code, 0x17, *index),
+        Instruction::FstoreWide(index) => write_wide(code, 0x38, *index),
+        Instruction::IloadWide(index) => write_wide(code, 0x15, *index),
+        Instruction::IstoreWide(index) => write_wide(code, 0x36, *index),
+        Instruction::LloadWide(index) => write_wide(code, 0x16, *index),
+        Instruction::LstoreWide(index) => write_wide(code, 0x37, *index),
+        Instruction::RetWide(index) => write_wide(code, 0xa9, *index),
+    }
+}
+
+/// 依次编码方法的所有指令
+pub fn write_instructions(instructions: &[Instruction]) -> Vec<u8> {
+    let mut code = Vec::new();
+    for instruction in instructions {
+        write_instruction(&mut code, instruction);
+    }
+    code
+}
+
+fn write_wide(code: &mut Vec<u8>, op_code: u8, index: u16) {
+    code.extend([0xc4, op_code]);
+    code.extend(index.to_be_bytes());
+}
+
+//操作码之后填充0，使操作数按照4字节对齐
+fn write_switch_padding(code: &mut Vec<u8>) {
+    while !code.len().is_multiple_of(4) {
+        code.push(0);
+    }
+}
+
+//wide之后是被扩展的指令，局部变量下标改为u16，iinc的增量改为i16
+//https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.wide
+fn read_wide(buffer: &mut ByteBuffer) -> Result<Instruction> {
+    let op_code = buffer.read_u8()?;
+    let instruction = match op_code {
+        0x15 => Instruction::IloadWide(buffer.read_u16()?),
+        0x16 => Instruction::LloadWide(buffer.read_u16()?),
+        0x17 => Instruction::FloadWide(buffer.read_u16()?),
+        0x18 => Instruction::DloadWide(buffer.read_u16()?),
+        0x19 => Instruction::AloadWide(buffer.read_u16()?),
+        0x36 => Instruction::IstoreWide(buffer.read_u16()?),
+        0x37 => Instruction::LstoreWide(buffer.read_u16()?),
+        0x38 => Instruction::FstoreWide(buffer.read_u16()?),
+        0x39 => Instruction::DstoreWide(buffer.read_u16()?),
+        0x3a => Instruction::AstoreWide(buffer.read_u16()?),
+        0xa9 => Instruction::RetWide(buffer.read_u16()?),
+        0x84 => Instruction::Iinc(buffer.read_u16()?, buffer.read_i16()?),
+        op_code => {
+            return Err(ClassFileError::InvalidCode(format!(
+                "Invalid Op Code {op_code} after wide"
+            )));
+        }
+    };
+    Ok(instruction)
+}
+
+fn read_lookup_switch(buffer: &mut ByteBuffer) -> Result<LookupSwitch> {
+    //操作码之后有0-3个字节的填充，使得后续操作数按照4字节对齐。buffer的起点即方法code的起点
+    buffer.align_to(4)?;
+    let default = buffer.read_i32()?;
+    let npairs = buffer.read_i32()?;
+    if npairs < 0 {
+        return Err(ClassFileError::InvalidCode(format!(
+            "Invalid lookupswitch npairs {npairs}"
+        )));
+    }
+    //先检查长度，避免按损坏的npairs分配过大的空间
+    if npairs as usize * 8 > buffer.remaining() {
+        return Err(ClassFileError::UnexpectedEndOfData);
+    }
+    let mut pairs = Vec::with_capacity(npairs as usize);
+    for _ in 0..npairs {
+        let matched = buffer.read_i32()?;
+        let offset = buffer.read_i32()?;
+        pairs.push((matched, offset));
+    }
+    Ok(LookupSwitch { default, pairs })
+}
+
+fn read_table_switch(buffer: &mut ByteBuffer) -> Result<TableSwitch> {
+    //同lookupswitch的填充
+    buffer.align_to(4)?;
+    let default = buffer.read_i32()?;
+    let low = buffer.read_i32()?;
+    let high = buffer.read_i32()?;
+    if low > high {
+        return Err(ClassFileError::InvalidCode(format!(
+            "Invalid tableswitch range {low}..{high}"
+        )));
+    }
+    let count = (high as i64 - low as i64 + 1) as usize;
+    if count * 4 > buffer.remaining() {
+        return Err(ClassFileError::UnexpectedEndOfData);
+    }
+    let mut offsets = Vec::with_capacity(count);
+    for _ in 0..count {
+        offsets.push(buffer.read_i32()?);
+    }
+    Ok(TableSwitch {
+        default,
+        low,
+        high,
+        offsets,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cesu8_byte_buffer::ByteBuffer;
+    use crate::instruction::{
+        read_instructions, read_one_instruction, write_instruction, write_instructions,
+        Instruction, LookupSwitch, TableSwitch,
+    };
+
+    #[test]
+    fn read_switch_with_padding() {
+        //nop占1个字节，lookupswitch操作码在1，需要填充2个字节
+        let mut code = vec![0x00, 0xab, 0x00, 0x00];
+        code.extend_from_slice(&20i32.to_be_bytes());
+        code.extend_from_slice(&2i32.to_be_bytes());
+        code.extend_from_slice(&2112i32.to_be_bytes());
+        code.extend_from_slice(&8i32.to_be_bytes());
+        code.extend_from_slice(&(-5i32).to_be_bytes());
+        code.extend_from_slice(&12i32.to_be_bytes());
+        //tableswitch操作码在28，需要填充3个字节
+        code.extend_from_slice(&[0xaa, 0x00, 0x00, 0x00]);
+        code.extend_from_slice(&30i32.to_be_bytes());
+        code.extend_from_slice(&1i32.to_be_bytes());
+        code.extend_from_slice(&2i32.to_be_bytes());
+        code.extend_from_slice(&10i32.to_be_bytes());
+        code.extend_from_slice(&20i32.to_be_bytes());
+        code.push(0xb1);
+
+        let mut buffer = ByteBuffer::new(&code);
+        assert_eq!(read_one_instruction(&mut buffer).unwrap(), Instruction::Nop);
+        let lookup_switch = LookupSwitch {
+            default: 20,
+            pairs: vec![(2112, 8), (-5, 12)],
+        };
+        assert_eq!(
+            read_one_instruction(&mut buffer).unwrap(),
+            Instruction::Lookupswitch(lookup_switch.clone())
+        );
+        assert_eq!(lookup_switch.branch_offset(-5), 12);
+        assert_eq!(lookup_switch.branch_offset(7), 20);
+
+        assert_eq!(buffer.position, 28);
+        let table_switch = TableSwitch {
+            default: 30,
+            low: 1,
+            high: 2,
+            offsets: vec![10, 20],
+        };
+        assert_eq!(
+            read_one_instruction(&mut buffer).unwrap(),
+            Instruction::Tableswitch(table_switch.clone())
+        );
+        assert_eq!(table_switch.branch_offset(2), 20);
+        assert_eq!(table_switch.branch_offset(0), 30);
+        assert_eq!(table_switch.branch_offset(i32::MAX), 30);
+        assert_eq!(
+            read_one_instruction(&mut buffer).unwrap(),
+            Instruction::Return
+        );
+        assert!(!buffer.has_more_data());
+    }
+
+    #[test]
+    fn read_wide_iinc() {
+        //iinc 3, -1; wide iinc 300, 1000
+        let code = [0x84, 0x03, 0xff, 0xc4, 0x84, 0x01, 0x2c, 0x03, 0xe8];
+        let mut buffer = ByteBuffer::new(&code);
+        assert_eq!(
+            read_one_instruction(&mut buffer).unwrap(),
+            Instruction::Iinc(3, -1)
+        );
+        assert_eq!(
+            read_one_instruction(&mut buffer).unwrap(),
+            Instruction::Iinc(300, 1000)
+        );
+        assert!(!buffer.has_more_data());
+    }
+
+    #[test]
+    fn read_wide_load_store() {
+        //wide iload 300; wide istore 65535; iload 44
+        let code = [0xc4, 0x15, 0x01, 0x2c, 0xc4, 0x36, 0xff, 0xff, 0x15, 0x2c];
+        assert_eq!(
+            read_instructions(&code).unwrap(),
+            vec![
+                (0, Instruction::IloadWide(300)),
+                (4, Instruction::IstoreWide(u16::MAX)),
+                (8, Instruction::Iload(44)),
+            ]
+        );
+        //只有局部变量指令和iinc可以使用wide前缀
+        for code in [&[0xc4, 0x60][..], &[0xc4, 0xc4, 0x15, 0x01, 0x2c], &[0xc4]] {
+            assert!(read_one_instruction(&mut ByteBuffer::new(code)).is_err());
+        }
+    }
+
+    //每个操作码配上构造的操作数，以及期望的解码结果。switch的填充取决于操作码所在的地址pc
+    fn opcode_table(pc: usize) -> Vec<(Vec<u8>, Instruction)> {
+        let padding = vec![0u8; (4 - (pc + 1) % 4) % 4];
+        let mut table_switch = vec![0xaa];
+        table_switch.extend_from_slice(&padding);
+        for value in [-8i32, -1, 0, 3, 4] {
+            table_switch.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut lookup_switch = vec![0xab];
+        lookup_switch.extend_from_slice(&padding);
+        for value in [-8i32, 1, i32::MIN, 16] {
+            lookup_switch.extend_from_slice(&value.to_be_bytes());
+        }
+        let mut table = vec![
+            (vec![0x0], Instruction::Nop),
+            (vec![0x1], Instruction::Aconst_null),
+            (vec![0x2], Instruction::Iconst_m1),
+            (vec![0x3], Instruction::Iconst_0),
+            (vec![0x4], Instruction::Iconst_1),
+            (vec![0x5], Instruction::Iconst_2),
+            (vec![0x6], Instruction::Iconst_3),
+            (vec![0x7], Instruction::Iconst_4),
+            (vec![0x8], Instruction::Iconst_5),
+            (vec![0x9], Instruction::Lconst_0),
+            (vec![0xa], Instruction::Lconst_1),
+            (vec![0xb], Instruction::Fconst_0),
+            (vec![0xc], Instruction::Fconst_1),
+            (vec![0xd], Instruction::Fconst_2),
+            (vec![0xe], Instruction::Dconst_0),
+            (vec![0xf], Instruction::Dconst_1),
+            (vec![0x10, 0xfe], Instruction::Bipush(254)),
+            (vec![0x11, 0x80, 0x01], Instruction::Sipush(-32767)),
+            (vec![0x12, 0xfe], Instruction::Ldc(254)),
+            (vec![0x13, 0x81, 0x02], Instruction::Ldc_w(0x8102)),
+            (vec![0x14, 0x81, 0x02], Instruction::Ldc2_w(0x8102)),
+            (vec![0x15, 0xfe], Instruction::Iload(254)),
+            (vec![0x16, 0xfe], Instruction::Lload(254)),
+            (vec![0x17, 0xfe], Instruction::Fload(254)),
+            (vec![0x18, 0xfe], Instruction::Dload(254)),
+            (vec![0x19, 0xfe], Instruction::Aload(254)),
+            (vec![0x1a], Instruction::Iload_0),
+            (vec![0x1b], Instruction::Iload_1),
+            (vec![0x1c], Instruction::Iload_2),
+            (vec![0x1d], Instruction::Iload_3),
+            (vec![0x1e], Instruction::Lload_0),
+            (vec![0x1f], Instruction::Lload_1),
+            (vec![0x20], Instruction::Lload_2),
+            (vec![0x21], Instruction::Lload_3),
+            (vec![0x22], Instruction::Fload_0),
+            (vec![0x23], Instruction::Fload_1),
+            (vec![0x24], Instruction::Fload_2),
+            (vec![0x25], Instruction::Fload_3),
+            (vec![0x26], Instruction::Dload_0),
+            (vec![0x27], Instruction::Dload_1),
+            (vec![0x28], Instruction::Dload_2),
+            (vec![0x29], Instruction::Dload_3),
+            (vec![0x2a], Instruction::Aload_0),
+            (vec![0x2b], Instruction::Aload_1),
+            (vec![0x2c], Instruction::Aload_2),
+            (vec![0x2d], Instruction::Aload_3),
+            (vec![0x2e], Instruction::Iaload),
+            (vec![0x2f], Instruction::Laload),
+            (vec![0x30], Instruction::Faload),
+            (vec![0x31], Instruction::Daload),
+            (vec![0x32], Instruction::Aaload),
+            (vec![0x33], Instruction::Baload),
+            (vec![0x34], Instruction::Caload),
+            (vec![0x35], Instruction::Saload),
+            (vec![0x36, 0xfe], Instruction::Istore(254)),
+            (vec![0x37, 0xfe], Instruction::Lstore(254)),
+            (vec![0x38, 0xfe], Instruction::Fstore(254)),
+            (vec![0x39, 0xfe], Instruction::Dstore(254)),
+            (vec![0x3a, 0xfe], Instruction::Astore(254)),
+            (vec![0x3b], Instruction::Istore_0),
+            (vec![0x3c], Instruction::Istore_1),
+            (vec![0x3d], Instruction::Istore_2),
+            (vec![0x3e], Instruction::Istore_3),
+            (vec![0x3f], Instruction::Lstore_0),
+            (vec![0x40], Instruction::Lstore_1),
+            (vec![0x41], Instruction::Lstore_2),
+            (vec![0x42], Instruction::Lstore_3),
+            (vec![0x43], Instruction::Fstore_0),
+            (vec![0x44], Instruction::Fstore_1),
+            (vec![0x45], Instruction::Fstore_2),
+            (vec![0x46], Instruction::Fstore_3),
+            (vec![0x47], Instruction::Dstore_0),
+            (vec![0x48], Instruction::Dstore_1),
+            (vec![0x49], Instruction::Dstore_2),
+            (vec![0x4a], Instruction::Dstore_3),
+            (vec![0x4b], Instruction::Astore_0),
+            (vec![0x4c], Instruction::Astore_1),
+            (vec![0x4d], Instruction::Astore_2),
+            (vec![0x4e], Instruction::Astore_3),
+            (vec![0x4f], Instruction::Iastore),
+            (vec![0x50], Instruction::Lastore),
+            (vec![0x51], Instruction::Fastore),
+            (vec![0x52], Instruction::Dastore),
+            (vec![0x53], Instruction::Aastore),
+            (vec![0x54], Instruction::Bastore),
+            (vec![0x55], Instruction::Castore),
+            (vec![0x56], Instruction::Sastore),
+            (vec![0x57], Instruction::Pop),
+            (vec![0x58], Instruction::Pop2),
+            (vec![0x59], Instruction::Dup),
+            (vec![0x5a], Instruction::Dup_x1),
+            (vec![0x5b], Instruction::Dup_x2),
+            (vec![0x5c], Instruction::Dup2),
+            (vec![0x5d], Instruction::Dup2_x1),
+            (vec![0x5e], Instruction::Dup2_x2),
+            (vec![0x5f], Instruction::Swap),
+            (vec![0x60], Instruction::Iadd),
+            (vec![0x61], Instruction::Ladd),
+            (vec![0x62], Instruction::Fadd),
+            (vec![0x63], Instruction::Dadd),
+            (vec![0x64], Instruction::Isub),
+            (vec![0x65], Instruction::Lsub),
+            (vec![0x66], Instruction::Fsub),
+            (vec![0x67], Instruction::Dsub),
+            (vec![0x68], Instruction::Imul),
+            (vec![0x69], Instruction::Lmul),
+            (vec![0x6a], Instruction::Fmul),
+            (vec![0x6b], Instruction::Dmul),
+            (vec![0x6c], Instruction::Idiv),
+            (vec![0x6d], Instruction::Ldiv),
+            (vec![0x6e], Instruction::Fdiv),
+            (vec![0x6f], Instruction::Ddiv),
+            (vec![0x70], Instruction::Irem),
+            (vec![0x71], Instruction::Lrem),
+            (vec![0x72], Instruction::Frem),
+            (vec![0x73], Instruction::Drem),
+            (vec![0x74], Instruction::Ineg),
+            (vec![0x75], Instruction::Lneg),
+            (vec![0x76], Instruction::Fneg),
+            (vec![0x77], Instruction::Dneg),
+            (vec![0x78], Instruction::Ishl),
+            (vec![0x79], Instruction::Lshl),
+            (vec![0x7a], Instruction::Ishr),
+            (vec![0x7b], Instruction::Lshr),
+            (vec![0x7c], Instruction::Iushr),
+            (vec![0x7d], Instruction::Lushr),
+            (vec![0x7e], Instruction::Iand),
+            (vec![0x7f], Instruction::Land),
+            (vec![0x80], Instruction::Ior),
+            (vec![0x81], Instruction::Lor),
+            (vec![0x82], Instruction::Ixor),
+            (vec![0x83], Instruction::Lxor),
+            (vec![0x85], Instruction::I2l),
+            (vec![0x86], Instruction::I2f),
+            (vec![0x87], Instruction::I2d),
+            (vec![0x88], Instruction::L2i),
+            (vec![0x89], Instruction::L2f),
+            (vec![0x8a], Instruction::L2d),
+            (vec![0x8b], Instruction::F2i),
+            (vec![0x8c], Instruction::F2l),
+            (vec![0x8d], Instruction::F2d),
+            (vec![0x8e], Instruction::D2i),
+            (vec![0x8f], Instruction::D2l),
+            (vec![0x90], Instruction::D2f),
+            (vec![0x91], Instruction::I2b),
+            (vec![0x92], Instruction::I2c),
+            (vec![0x93], Instruction::I2s),
+            (vec![0x94], Instruction::Lcmp),
+            (vec![0x95], Instruction::Fcmpl),
+            (vec![0x96], Instruction::Fcmpg),
+            (vec![0x97], Instruction::Dcmpl),
+            (vec![0x98], Instruction::Dcmpg),
+            (vec![0x99, 0x80, 0x01], Instruction::Ifeq(-32767)),
+            (vec![0x9a, 0x80, 0x01], Instruction::Ifne(-32767)),
+            (vec![0x9b, 0x80, 0x01], Instruction::Iflt(-32767)),
+            (vec![0x9c, 0x80, 0x01], Instruction::Ifge(-32767)),
+            (vec![0x9d, 0x80, 0x01], Instruction::Ifgt(-32767)),
+            (vec![0x9e, 0x80, 0x01], Instruction::Ifle(-32767)),
+            (vec![0x9f, 0x80, 0x01], Instruction::If_icmpeq(-32767)),
+            (vec![0xa0, 0x80, 0x01], Instruction::If_icmpne(-32767)),
+            (vec![0xa1, 0x80, 0x01], Instruction::If_icmplt(-32767)),
+            (vec![0xa2, 0x80, 0x01], Instruction::If_icmpge(-32767)),
+            (vec![0xa3, 0x80, 0x01], Instruction::If_icmpgt(-32767)),
+            (vec![0xa4, 0x80, 0x01], Instruction::If_icmple(-32767)),
+            (vec![0xa5, 0x80, 0x01], Instruction::If_acmpeq(-32767)),
+            (vec![0xa6, 0x80, 0x01], Instruction::If_acmpne(-32767)),
+            (vec![0xa7, 0x80, 0x01], Instruction::Goto(-32767)),
+            (vec![0xa8, 0x81, 0x02], Instruction::Jsr(0x8102)),
+            (vec![0xa9, 0xfe], Instruction::Ret(254)),
+            (vec![0xac], Instruction::Ireturn),
+            (vec![0xad], Instruction::Lreturn),
+            (vec![0xae], Instruction::Freturn),
+            (vec![0xaf], Instruction::Dreturn),
+            (vec![0xb0], Instruction::Areturn),
+            (vec![0xb1], Instruction::Return),
+            (vec![0xb2, 0x81, 0x02], Instruction::Getstatic(0x8102)),
+            (vec![0xb3, 0x81, 0x02], Instruction::Putstatic(0x8102)),
+            (vec![0xb4, 0x81, 0x02], Instruction::Getfield(0x8102)),
+            (vec![0xb5, 0x81, 0x02], Instruction::Putfield(0x8102)),
+            (vec![0xb6, 0x81, 0x02], Instruction::Invokevirtual(0x8102)),
+            (vec![0xb7, 0x81, 0x02], Instruction::Invokespecial(0x8102)),
+            (vec![0xb8, 0x81, 0x02], Instruction::Invokestatic(0x8102)),
+            (vec![0xbb, 0x81, 0x02], Instruction::New(0x8102)),
+            (vec![0xbc, 0xfe], Instruction::NewArray(254)),
+            (vec![0xbd, 0x81, 0x02], Instruction::Anewarray(0x8102)),
+            (vec![0xbe], Instruction::Arraylength),
+            (vec![0xbf], Instruction::Athrow),
+            (vec![0xc0, 0x81, 0x02], Instruction::Checkcast(0x8102)),
+            (vec![0xc1, 0x81, 0x02], Instruction::Instanceof(0x8102)),
+            (vec![0xc2], Instruction::Monitorenter),
+            (vec![0xc3], Instruction::Monitorexit),
+            (
+                vec![0xc5, 0x81, 0x02, 0x03],
+                Instruction::Multianewarray(0x8102, 3),
+            ),
+            (vec![0xc6, 0x80, 0x01], Instruction::Ifnull(-32767)),
+            (vec![0xc7, 0x80, 0x01], Instruction::Ifnonnull(-32767)),
+            (
+                vec![0xc8, 0x80, 0x00, 0x00, 0x01],
+                Instruction::Goto_w(i32::MIN + 1),
+            ),
+            (
+                vec![0xc9, 0x80, 0x00, 0x00, 0x01],
+                Instruction::Jsr_w(0x8000_0001),
+            ),
+            (vec![0x84, 0xfe, 0x80], Instruction::Iinc(254, -128)),
+            (
+                vec![0xb9, 0x81, 0x02, 0x03, 0x00],
+                Instruction::Invokeinterface(0x8102, 3),
+            ),
+            (
+                vec![0xba, 0x81, 0x02, 0x00, 0x00],
+                Instruction::Invokedynamic(0x8102),
+            ),
+            (
+                vec![0xc4, 0x84, 0x01, 0x2c, 0x03, 0xe8],
+                Instruction::Iinc(300, 1000),
+            ),
+            (vec![0xc4, 0x15, 0x81, 0x02], Instruction::IloadWide(0x8102)),
+            (vec![0xc4, 0x16, 0x81, 0x02], Instruction::LloadWide(0x8102)),
+            (vec![0xc4, 0x17, 0x81, 0x02], Instruction::FloadWide(0x8102)),
+            (vec![0xc4, 0x18, 0x81, 0x02], Instruction::DloadWide(0x8102)),
+            (vec![0xc4, 0x19, 0x81, 0x02], Instruction::AloadWide(0x8102)),
+            (
+                vec![0xc4, 0x36, 0x81, 0x02],
+                Instruction::IstoreWide(0x8102),
+            ),
+            (
+                vec![0xc4, 0x37, 0x81, 0x02],
+                Instruction::LstoreWide(0x8102),
+            ),
+            (
+                vec![0xc4, 0x38, 0x81, 0x02],
+                Instruction::FstoreWide(0x8102),
+            ),
+            (
+                vec![0xc4, 0x39, 0x81, 0x02],
+                Instruction::DstoreWide(0x8102),
+            ),
+            (
+                vec![0xc4, 0x3a, 0x81, 0x02],
+                Instruction::AstoreWide(0x8102),
+            ),
+            (vec![0xc4, 0xa9, 0x81, 0x02], Instruction::RetWide(0x8102)),
+        ];
+        table.push((
+            table_switch,
+            Instruction::Tableswitch(TableSwitch {
+                default: -8,
+                low: -1,
+                high: 0,
+                offsets: vec![3, 4],
+            }),
+        ));
+        table.push((
+            lookup_switch,
+            Instruction::Lookupswitch(LookupSwitch {
+                default: -8,
+                pairs: vec![(i32::MIN, 16)],
+            }),
+        ));
+        table
+    }
+
+    #[test]
+    fn read_every_opcode() {
+        let table = opcode_table(0);
+        for op_code in 0x00..=0xc9u8 {
+            assert!(
+                table.iter().any(|(code, _)| code[0] == op_code),
+                "opcode {:#x} is not covered",
+                op_code
+            );
+        }
+        for (code, expected) in table {
+            let mut buffer = ByteBuffer::new(&code);
+            let instruction = read_one_instruction(&mut buffer).unwrap();
+            assert_eq!(instruction, expected, "{:x?}", code);
+            assert_eq!(buffer.position, code.len(), "{:?}", expected);
+            //编码是解码的逆过程
+            let mut written = Vec::new();
+            write_instruction(&mut written, &expected);
+            assert_eq!(written, code, "{:?}", expected);
+        }
+        //breakpoint和impdep1/impdep2是保留的操作码，不会出现在class文件中
+        for op_code in 0xca..=0xffu8 {
+            assert!(read_one_instruction(&mut ByteBuffer::new(&[op_code])).is_err());
+        }
+    }
+
+    //xorshift，保证每次运行的序列相同
+    fn next_random(state: &mut u64) -> u64 {
+        *state ^= *state << 13;
+        *state ^= *state >> 7;
+        *state ^= *state << 17;
+        *state
+    }
+
+    #[test]
+    fn read_random_instruction_sequences() {
+        let mut state = 0x2545_f491_4f6c_dd1d;
+        for _ in 0..200 {
+            let mut code = Vec::new();
+            let mut expected = Vec::new();
+            for _ in 0..64 {
+                let mut table = opcode_table(code.len());
+                let index = next_random(&mut state) as usize % table.len();
+                let (bytes, instruction) = table.swap_remove(index);
+                expected.push((code.len(), instruction));
+                code.extend_from_slice(&bytes);
+            }
+            assert_eq!(read_instructions(&code).unwrap(), expected);
+            let instructions: Vec<Instruction> = expected.into_iter().map(|(_, i)| i).collect();
+            assert_eq!(write_instructions(&instructions), code);
+        }
+
+        //任意字节只能解码成功或者返回错误
+        for _ in 0..2000 {
+            let length = next_random(&mut state) as usize % 32;
+            let code: Vec<u8> = (0..length).map(|_| next_random(&mut state) as u8).collect();
+            let _ = read_instructions(&code);
+        }
+    }
+}
@@ -1,6 +1,9 @@
 pub mod attribute_info;
 pub mod cesu8_byte_buffer;
 pub mod class_file;
+// 测试用的类文件生成工具，不属于正式API
+#[cfg(any(test, feature = "test-support"))]
+pub mod class_file_builder;
 pub mod class_file_error;
 pub mod class_file_reader;
 pub mod class_file_version;
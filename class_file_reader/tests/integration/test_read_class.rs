@@ -11,6 +11,29 @@ fn test_read_class() {
     );
     assert_eq!(class.version, ClassFileVersion::Jdk21);
     assert_eq!(class.this_class_name, "HelloWorld");
+    assert_eq!(class.source_file().unwrap().unwrap(), "HelloWorld.java");
     assert_eq!(class.super_class_name.unwrap(), "java/lang/Object");
     assert_eq!(class.method_info.len(), 2);
 }
+
+#[test]
+fn test_read_modified_utf8_constants() {
+    use class_file_reader::constant_pool::{ConstantPoolEntry, ConstantPoolPhysicalEntry};
+    let class = read_buffer(include_bytes!("../resources/Utf8Constants.class")).unwrap();
+    let strings: Vec<&str> = class
+        .constant_pool
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            ConstantPoolPhysicalEntry::Entry(ConstantPoolEntry::Utf8(value)) => {
+                Some(value.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert!(strings.contains(&"emoji 😀!"));
+    assert!(strings.contains(&"a\0b"));
+    assert!(strings.contains(&"中文"));
+    //javac会保留单独的代理，解码时替换为U+FFFD
+    assert!(strings.contains(&"x\u{fffd}y"));
+}
@@ -0,0 +1,6 @@
+public class Utf8Constants {
+    static final String EMOJI = "emoji 😀!";
+    static final String NUL = "a\u0000b";
+    static final String LONE_SURROGATE = "x\uD83Dy";
+    static final String CJK = "中文";
+}
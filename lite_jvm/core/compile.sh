@@ -0,0 +1,12 @@
+#!/bin/sh
+# 编译内置的核心类，结果由embedded_core.rs通过include_bytes!嵌入
+# 用空的bootclasspath编译，保证只依赖core/src中的类
+set -e
+cd "$(dirname "$0")"
+rm -rf classes
+mkdir -p classes
+EMPTY=$(mktemp -d)
+find src -name '*.java' > "$EMPTY/sources"
+javac -source 8 -target 8 -encoding UTF-8 -nowarn -Xlint:-options \
+    -bootclasspath "$EMPTY" -sourcepath src -d classes @"$EMPTY/sources"
+rm -rf "$EMPTY"
@@ -0,0 +1,64 @@
+package java.io;
+
+//方法体为空，System.out/err的输出由虚拟机的native实现
+public class PrintStream {
+    public void print(boolean b) {
+    }
+
+    public void print(char c) {
+    }
+
+    public void print(int i) {
+    }
+
+    public void print(long l) {
+    }
+
+    public void print(float f) {
+    }
+
+    public void print(double d) {
+    }
+
+    public void print(char[] s) {
+    }
+
+    public void print(String s) {
+    }
+
+    public void print(Object obj) {
+    }
+
+    public void println() {
+    }
+
+    public void println(boolean x) {
+    }
+
+    public void println(char x) {
+    }
+
+    public void println(int x) {
+    }
+
+    public void println(long x) {
+    }
+
+    public void println(float x) {
+    }
+
+    public void println(double x) {
+    }
+
+    public void println(char[] x) {
+    }
+
+    public void println(String x) {
+    }
+
+    public void println(Object x) {
+    }
+
+    public void flush() {
+    }
+}
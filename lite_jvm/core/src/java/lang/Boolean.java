@@ -0,0 +1,40 @@
+package java.lang;
+
+public final class Boolean {
+    public static final Boolean TRUE = new Boolean(true);
+    public static final Boolean FALSE = new Boolean(false);
+
+    private final boolean value;
+
+    public Boolean(boolean value) {
+        this.value = value;
+    }
+
+    public static Boolean valueOf(boolean b) {
+        return b ? TRUE : FALSE;
+    }
+
+    public static boolean parseBoolean(String s) {
+        return s != null && s.toLowerCase().equals("true");
+    }
+
+    public static String toString(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public boolean booleanValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return value ? 1231 : 1237;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Boolean) && value == ((Boolean) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
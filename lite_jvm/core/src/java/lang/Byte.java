@@ -0,0 +1,56 @@
+package java.lang;
+
+public final class Byte extends Number {
+    public static final byte MIN_VALUE = (byte) -(1 << (8 - 1));
+    public static final byte MAX_VALUE = (byte) ((1 << (8 - 1)) - 1);
+
+    private final byte value;
+
+    public Byte(byte value) {
+        this.value = value;
+    }
+
+    public static Byte valueOf(byte value) {
+        return new Byte(value);
+    }
+
+    public static byte parseByte(String s) {
+        int i = Integer.parseInt(s);
+        if (i < MIN_VALUE || i > MAX_VALUE) {
+            throw new NumberFormatException("Value out of range. Value:\"" + s + "\" Radix:10");
+        }
+        return (byte) i;
+    }
+
+    public static String toString(byte value) {
+        return Integer.toString(value);
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Byte) && value == ((Byte) obj).value;
+    }
+
+    public String toString() {
+        return Integer.toString(value);
+    }
+}
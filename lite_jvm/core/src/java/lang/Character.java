@@ -0,0 +1,92 @@
+package java.lang;
+
+public final class Character {
+    public static final char MIN_VALUE = '\u0000';
+    public static final char MAX_VALUE = '￿';
+    public static final int MIN_RADIX = 2;
+    public static final int MAX_RADIX = 36;
+
+    private final char value;
+
+    public Character(char value) {
+        this.value = value;
+    }
+
+    public static Character valueOf(char c) {
+        return new Character(c);
+    }
+
+    public char charValue() {
+        return value;
+    }
+
+    //只处理ASCII范围内的字符
+    public static boolean isDigit(char ch) {
+        return ch >= '0' && ch <= '9';
+    }
+
+    public static boolean isLetter(char ch) {
+        return (ch >= 'a' && ch <= 'z') || (ch >= 'A' && ch <= 'Z');
+    }
+
+    public static boolean isLetterOrDigit(char ch) {
+        return isLetter(ch) || isDigit(ch);
+    }
+
+    public static boolean isWhitespace(char ch) {
+        return ch == ' ' || (ch >= '\t' && ch <= '\r') || (ch >= '\u001C' && ch <= '\u001F');
+    }
+
+    public static boolean isUpperCase(char ch) {
+        return ch >= 'A' && ch <= 'Z';
+    }
+
+    public static boolean isLowerCase(char ch) {
+        return ch >= 'a' && ch <= 'z';
+    }
+
+    public static char toUpperCase(char ch) {
+        return isLowerCase(ch) ? (char) (ch - 'a' + 'A') : ch;
+    }
+
+    public static char toLowerCase(char ch) {
+        return isUpperCase(ch) ? (char) (ch - 'A' + 'a') : ch;
+    }
+
+    public static int digit(char ch, int radix) {
+        int value;
+        if (ch >= '0' && ch <= '9') {
+            value = ch - '0';
+        } else if (ch >= 'a' && ch <= 'z') {
+            value = ch - 'a' + 10;
+        } else if (ch >= 'A' && ch <= 'Z') {
+            value = ch - 'A' + 10;
+        } else {
+            return -1;
+        }
+        return (radix >= MIN_RADIX && radix <= MAX_RADIX && value < radix) ? value : -1;
+    }
+
+    public static char forDigit(int digit, int radix) {
+        if (digit >= radix || digit < 0 || radix < MIN_RADIX || radix > MAX_RADIX) {
+            return '\0';
+        }
+        return (char) (digit < 10 ? '0' + digit : 'a' - 10 + digit);
+    }
+
+    public static String toString(char c) {
+        return String.valueOf(c);
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Character) && value == ((Character) obj).value;
+    }
+
+    public String toString() {
+        return String.valueOf(value);
+    }
+}
@@ -0,0 +1,52 @@
+package java.lang;
+
+public final class Class<T> {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    //name和classLoader由虚拟机在创建Class对象时直接写入，name是点分隔的类名
+    private transient String name;
+    private final Object classLoader;
+
+    private Class(Object loader) {
+        classLoader = loader;
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public String getSimpleName() {
+        String name = getName();
+        return name.substring(name.lastIndexOf('.') + 1);
+    }
+
+    public String toString() {
+        return (isInterface() ? "interface " : (isPrimitive() ? "" : "class ")) + getName();
+    }
+
+    public native int hashCode();
+
+    public native Class<? super T> getSuperclass();
+
+    public native Class<?>[] getInterfaces();
+
+    public native boolean isInterface();
+
+    public native boolean isArray();
+
+    public native boolean isPrimitive();
+
+    public native int getModifiers();
+
+    public boolean desiredAssertionStatus() {
+        return desiredAssertionStatus0(this);
+    }
+
+    private static native boolean desiredAssertionStatus0(Class<?> clazz);
+
+    static native Class<?> getPrimitiveClass(String name);
+}
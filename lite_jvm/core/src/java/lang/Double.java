@@ -0,0 +1,78 @@
+package java.lang;
+
+public final class Double extends Number {
+    public static final double POSITIVE_INFINITY = 1.0D / 0.0D;
+    public static final double NEGATIVE_INFINITY = -1.0D / 0.0D;
+    public static final double NaN = 0.0D / 0.0D;
+    public static final double MIN_VALUE = 4.9e-324;
+    public static final double MAX_VALUE = 1.7976931348623157e+308;
+
+    private final double value;
+
+    public Double(double value) {
+        this.value = value;
+    }
+
+    public static Double valueOf(double value) {
+        return new Double(value);
+    }
+
+    //格式和JDK一致，由虚拟机实现
+    public static native String toString(double value);
+
+    public static boolean isNaN(double v) {
+        return v != v;
+    }
+
+    public static boolean isInfinite(double v) {
+        return v == POSITIVE_INFINITY || v == NEGATIVE_INFINITY;
+    }
+
+    public static native long doubleToRawLongBits(double value);
+
+    public static native double longBitsToDouble(long bits);
+
+    public static long doubleToLongBits(double value) {
+        return isNaN(value) ? 0x7ff8000000000000L : doubleToRawLongBits(value);
+    }
+
+    public static int compare(double d1, double d2) {
+        if (d1 < d2) {
+            return -1;
+        }
+        if (d1 > d2) {
+            return 1;
+        }
+        long thisBits = doubleToLongBits(d1);
+        long anotherBits = doubleToLongBits(d2);
+        return (thisBits == anotherBits ? 0 : (thisBits < anotherBits ? -1 : 1));
+    }
+
+    public boolean isNaN() {
+        return isNaN(value);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return (long) value;
+    }
+
+    public float floatValue() {
+        return (float) value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Double) && doubleToLongBits(((Double) obj).value) == doubleToLongBits(value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,28 @@
+package java.lang;
+
+public class ExceptionInInitializerError extends LinkageError {
+    //和JDK 8一样，虚拟机包装<clinit>抛出的异常时同时写入exception和cause
+    private Throwable exception;
+
+    public ExceptionInInitializerError() {
+        initCause(null);
+    }
+
+    public ExceptionInInitializerError(Throwable thrown) {
+        initCause(null);
+        this.exception = thrown;
+    }
+
+    public ExceptionInInitializerError(String message) {
+        super(message);
+        initCause(null);
+    }
+
+    public Throwable getException() {
+        return exception;
+    }
+
+    public Throwable getCause() {
+        return exception;
+    }
+}
@@ -0,0 +1,78 @@
+package java.lang;
+
+public final class Float extends Number {
+    public static final float POSITIVE_INFINITY = 1.0F / 0.0F;
+    public static final float NEGATIVE_INFINITY = -1.0F / 0.0F;
+    public static final float NaN = 0.0F / 0.0F;
+    public static final float MIN_VALUE = 1.4e-45f;
+    public static final float MAX_VALUE = 3.4028235e+38f;
+
+    private final float value;
+
+    public Float(float value) {
+        this.value = value;
+    }
+
+    public static Float valueOf(float value) {
+        return new Float(value);
+    }
+
+    //格式和JDK一致，由虚拟机实现
+    public static native String toString(float value);
+
+    public static boolean isNaN(float v) {
+        return v != v;
+    }
+
+    public static boolean isInfinite(float v) {
+        return v == POSITIVE_INFINITY || v == NEGATIVE_INFINITY;
+    }
+
+    public static native int floatToRawIntBits(float value);
+
+    public static native float intBitsToFloat(int bits);
+
+    public static int floatToIntBits(float value) {
+        return isNaN(value) ? 0x7fc00000 : floatToRawIntBits(value);
+    }
+
+    public static int compare(float d1, float d2) {
+        if (d1 < d2) {
+            return -1;
+        }
+        if (d1 > d2) {
+            return 1;
+        }
+        int thisBits = floatToIntBits(d1);
+        int anotherBits = floatToIntBits(d2);
+        return (thisBits == anotherBits ? 0 : (thisBits < anotherBits ? -1 : 1));
+    }
+
+    public boolean isNaN() {
+        return isNaN(value);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return (long) value;
+    }
+
+    public float floatValue() {
+        return (float) value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Float) && floatToIntBits(((Float) obj).value) == floatToIntBits(value);
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
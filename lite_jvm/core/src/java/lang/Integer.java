@@ -0,0 +1,90 @@
+package java.lang;
+
+public final class Integer extends Number {
+    public static final int MIN_VALUE = 0x80000000;
+    public static final int MAX_VALUE = 0x7fffffff;
+
+    private static final Integer[] CACHE = new Integer[256];
+
+    static {
+        for (int i = 0; i < CACHE.length; i++) {
+            CACHE[i] = new Integer(i - 128);
+        }
+    }
+
+    private final int value;
+
+    public Integer(int value) {
+        this.value = value;
+    }
+
+    public static Integer valueOf(int i) {
+        if (i >= -128 && i <= 127) {
+            return CACHE[i + 128];
+        }
+        return new Integer(i);
+    }
+
+    public static Integer valueOf(String s) {
+        return valueOf(parseInt(s));
+    }
+
+    public static int parseInt(String s) {
+        return parseInt(s, 10);
+    }
+
+    public static int parseInt(String s, int radix) {
+        if (s == null) {
+            throw new NumberFormatException("null");
+        }
+        long result = Long.parseLong(s, radix);
+        if (result < MIN_VALUE || result > MAX_VALUE) {
+            throw new NumberFormatException("For input string: \"" + s + "\"");
+        }
+        return (int) result;
+    }
+
+    public static String toString(int i) {
+        return Long.toString(i);
+    }
+
+    public static String toHexString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 4);
+    }
+
+    public static String toBinaryString(int i) {
+        return Long.toUnsignedString(i & 0xffffffffL, 1);
+    }
+
+    public static int compare(int x, int y) {
+        return (x < y) ? -1 : ((x == y) ? 0 : 1);
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Integer) && value == ((Integer) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,125 @@
+package java.lang;
+
+public final class Long extends Number {
+    public static final long MIN_VALUE = 0x8000000000000000L;
+    public static final long MAX_VALUE = 0x7fffffffffffffffL;
+
+    private final long value;
+
+    public Long(long value) {
+        this.value = value;
+    }
+
+    public static Long valueOf(long l) {
+        return new Long(l);
+    }
+
+    public static long parseLong(String s) {
+        return parseLong(s, 10);
+    }
+
+    public static long parseLong(String s, int radix) {
+        if (s == null) {
+            throw new NumberFormatException("null");
+        }
+        int length = s.length();
+        int i = 0;
+        boolean negative = false;
+        if (length > 0 && (s.charAt(0) == '-' || s.charAt(0) == '+')) {
+            negative = s.charAt(0) == '-';
+            i++;
+        }
+        if (i == length) {
+            throw forInputString(s);
+        }
+        //按负数累加，避免MIN_VALUE溢出
+        long limit = negative ? MIN_VALUE : -MAX_VALUE;
+        long result = 0;
+        for (; i < length; i++) {
+            int digit = Character.digit(s.charAt(i), radix);
+            if (digit < 0 || result < limit / radix) {
+                throw forInputString(s);
+            }
+            result *= radix;
+            if (result < limit + digit) {
+                throw forInputString(s);
+            }
+            result -= digit;
+        }
+        return negative ? result : -result;
+    }
+
+    private static NumberFormatException forInputString(String s) {
+        return new NumberFormatException("For input string: \"" + s + "\"");
+    }
+
+    public static String toString(long i) {
+        if (i == 0) {
+            return "0";
+        }
+        char[] buf = new char[20];
+        int pos = buf.length;
+        boolean negative = i < 0;
+        //按负数取余，避免MIN_VALUE取反溢出
+        long n = negative ? i : -i;
+        while (n != 0) {
+            buf[--pos] = (char) ('0' - (n % 10));
+            n /= 10;
+        }
+        if (negative) {
+            buf[--pos] = '-';
+        }
+        return new String(buf, pos, buf.length - pos);
+    }
+
+    public static String toHexString(long i) {
+        return toUnsignedString(i, 4);
+    }
+
+    public static String toBinaryString(long i) {
+        return toUnsignedString(i, 1);
+    }
+
+    static String toUnsignedString(long i, int shift) {
+        char[] buf = new char[64];
+        int pos = buf.length;
+        long mask = (1L << shift) - 1;
+        do {
+            buf[--pos] = Character.forDigit((int) (i & mask), 1 << shift);
+            i >>>= shift;
+        } while (i != 0);
+        return new String(buf, pos, buf.length - pos);
+    }
+
+    public static int compare(long x, long y) {
+        return (x < y) ? -1 : ((x == y) ? 0 : 1);
+    }
+
+    public int intValue() {
+        return (int) value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return (int) (value ^ (value >>> 32));
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Long) && value == ((Long) obj).value;
+    }
+
+    public String toString() {
+        return toString(value);
+    }
+}
@@ -0,0 +1,34 @@
+package java.lang;
+
+public final class Math {
+    private Math() {
+    }
+
+    public static int abs(int a) {
+        return (a < 0) ? -a : a;
+    }
+
+    public static long abs(long a) {
+        return (a < 0) ? -a : a;
+    }
+
+    public static double abs(double a) {
+        return (a <= 0.0D) ? 0.0D - a : a;
+    }
+
+    public static int max(int a, int b) {
+        return (a >= b) ? a : b;
+    }
+
+    public static long max(long a, long b) {
+        return (a >= b) ? a : b;
+    }
+
+    public static int min(int a, int b) {
+        return (a <= b) ? a : b;
+    }
+
+    public static long min(long a, long b) {
+        return (a <= b) ? a : b;
+    }
+}
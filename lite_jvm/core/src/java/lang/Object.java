@@ -0,0 +1,26 @@
+package java.lang;
+
+public class Object {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    public final native Class<?> getClass();
+
+    public native int hashCode();
+
+    public boolean equals(Object obj) {
+        return this == obj;
+    }
+
+    protected native Object clone() throws CloneNotSupportedException;
+
+    public String toString() {
+        return getClass().getName() + "@" + Integer.toHexString(hashCode());
+    }
+
+    protected void finalize() throws Throwable {
+    }
+}
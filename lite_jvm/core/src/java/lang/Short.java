@@ -0,0 +1,56 @@
+package java.lang;
+
+public final class Short extends Number {
+    public static final short MIN_VALUE = (short) -(1 << (16 - 1));
+    public static final short MAX_VALUE = (short) ((1 << (16 - 1)) - 1);
+
+    private final short value;
+
+    public Short(short value) {
+        this.value = value;
+    }
+
+    public static Short valueOf(short value) {
+        return new Short(value);
+    }
+
+    public static short parseShort(String s) {
+        int i = Integer.parseInt(s);
+        if (i < MIN_VALUE || i > MAX_VALUE) {
+            throw new NumberFormatException("Value out of range. Value:\"" + s + "\" Radix:10");
+        }
+        return (short) i;
+    }
+
+    public static String toString(short value) {
+        return Integer.toString(value);
+    }
+
+    public int intValue() {
+        return value;
+    }
+
+    public long longValue() {
+        return value;
+    }
+
+    public float floatValue() {
+        return value;
+    }
+
+    public double doubleValue() {
+        return value;
+    }
+
+    public int hashCode() {
+        return value;
+    }
+
+    public boolean equals(Object obj) {
+        return (obj instanceof Short) && value == ((Short) obj).value;
+    }
+
+    public String toString() {
+        return Integer.toString(value);
+    }
+}
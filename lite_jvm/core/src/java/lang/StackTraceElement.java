@@ -0,0 +1,46 @@
+package java.lang;
+
+public final class StackTraceElement {
+    //由虚拟机在记录调用栈时直接写入
+    private String declaringClass;
+    private String methodName;
+    private String fileName;
+    private int lineNumber;
+
+    public StackTraceElement(String declaringClass, String methodName, String fileName, int lineNumber) {
+        this.declaringClass = declaringClass;
+        this.methodName = methodName;
+        this.fileName = fileName;
+        this.lineNumber = lineNumber;
+    }
+
+    public String getClassName() {
+        return declaringClass;
+    }
+
+    public String getMethodName() {
+        return methodName;
+    }
+
+    public String getFileName() {
+        return fileName;
+    }
+
+    public int getLineNumber() {
+        return lineNumber;
+    }
+
+    public String toString() {
+        String location;
+        if (lineNumber == -2) {
+            location = "(Native Method)";
+        } else if (fileName == null) {
+            location = "(Unknown Source)";
+        } else if (lineNumber >= 0) {
+            location = "(" + fileName + ":" + lineNumber + ")";
+        } else {
+            location = "(" + fileName + ")";
+        }
+        return declaringClass + "." + methodName + location;
+    }
+}
@@ -0,0 +1,246 @@
+package java.lang;
+
+public final class String {
+    //虚拟机创建字符串对象时直接写入value和hash
+    private final char[] value;
+    private int hash;
+
+    public String() {
+        this.value = new char[0];
+    }
+
+    public String(char[] value) {
+        this(value, 0, value.length);
+    }
+
+    public String(char[] value, int offset, int count) {
+        if (offset < 0 || count < 0 || offset > value.length - count) {
+            throw new StringIndexOutOfBoundsException(offset + count);
+        }
+        this.value = new char[count];
+        System.arraycopy(value, offset, this.value, 0, count);
+    }
+
+    public String(String original) {
+        this.value = original.value;
+        this.hash = original.hash;
+    }
+
+    public int length() {
+        return value.length;
+    }
+
+    public boolean isEmpty() {
+        return value.length == 0;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= value.length) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        return value[index];
+    }
+
+    public char[] toCharArray() {
+        char[] result = new char[value.length];
+        System.arraycopy(value, 0, result, 0, value.length);
+        return result;
+    }
+
+    public boolean equals(Object anObject) {
+        if (this == anObject) {
+            return true;
+        }
+        if (!(anObject instanceof String)) {
+            return false;
+        }
+        String other = (String) anObject;
+        if (other.value.length != value.length) {
+            return false;
+        }
+        for (int i = 0; i < value.length; i++) {
+            if (value[i] != other.value[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int hashCode() {
+        int h = hash;
+        if (h == 0) {
+            for (int i = 0; i < value.length; i++) {
+                h = 31 * h + value[i];
+            }
+            hash = h;
+        }
+        return h;
+    }
+
+    public int compareTo(String another) {
+        int limit = Math.min(value.length, another.value.length);
+        for (int i = 0; i < limit; i++) {
+            if (value[i] != another.value[i]) {
+                return value[i] - another.value[i];
+            }
+        }
+        return value.length - another.value.length;
+    }
+
+    public boolean startsWith(String prefix) {
+        return regionMatches(0, prefix);
+    }
+
+    public boolean endsWith(String suffix) {
+        return regionMatches(value.length - suffix.value.length, suffix);
+    }
+
+    private boolean regionMatches(int offset, String other) {
+        if (offset < 0 || offset > value.length - other.value.length) {
+            return false;
+        }
+        for (int i = 0; i < other.value.length; i++) {
+            if (value[offset + i] != other.value[i]) {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    public int indexOf(int ch) {
+        return indexOf(ch, 0);
+    }
+
+    public int indexOf(int ch, int fromIndex) {
+        for (int i = Math.max(fromIndex, 0); i < value.length; i++) {
+            if (value[i] == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int lastIndexOf(int ch) {
+        for (int i = value.length - 1; i >= 0; i--) {
+            if (value[i] == ch) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public int indexOf(String str) {
+        for (int i = 0; i <= value.length - str.value.length; i++) {
+            if (regionMatches(i, str)) {
+                return i;
+            }
+        }
+        return -1;
+    }
+
+    public boolean contains(String s) {
+        return indexOf(s) >= 0;
+    }
+
+    public String substring(int beginIndex) {
+        return substring(beginIndex, value.length);
+    }
+
+    public String substring(int beginIndex, int endIndex) {
+        if (beginIndex < 0) {
+            throw new StringIndexOutOfBoundsException(beginIndex);
+        }
+        if (endIndex > value.length) {
+            throw new StringIndexOutOfBoundsException(endIndex);
+        }
+        if (endIndex < beginIndex) {
+            throw new StringIndexOutOfBoundsException(endIndex - beginIndex);
+        }
+        return new String(value, beginIndex, endIndex - beginIndex);
+    }
+
+    public String concat(String str) {
+        if (str.value.length == 0) {
+            return this;
+        }
+        char[] buf = new char[value.length + str.value.length];
+        System.arraycopy(value, 0, buf, 0, value.length);
+        System.arraycopy(str.value, 0, buf, value.length, str.value.length);
+        return new String(buf);
+    }
+
+    public String replace(char oldChar, char newChar) {
+        char[] buf = toCharArray();
+        for (int i = 0; i < buf.length; i++) {
+            if (buf[i] == oldChar) {
+                buf[i] = newChar;
+            }
+        }
+        return new String(buf);
+    }
+
+    public String trim() {
+        int begin = 0;
+        int end = value.length;
+        while (begin < end && value[begin] <= ' ') {
+            begin++;
+        }
+        while (begin < end && value[end - 1] <= ' ') {
+            end--;
+        }
+        return (begin > 0 || end < value.length) ? substring(begin, end) : this;
+    }
+
+    //只处理ASCII字符
+    public String toUpperCase() {
+        char[] buf = toCharArray();
+        for (int i = 0; i < buf.length; i++) {
+            buf[i] = Character.toUpperCase(buf[i]);
+        }
+        return new String(buf);
+    }
+
+    public String toLowerCase() {
+        char[] buf = toCharArray();
+        for (int i = 0; i < buf.length; i++) {
+            buf[i] = Character.toLowerCase(buf[i]);
+        }
+        return new String(buf);
+    }
+
+    public String toString() {
+        return this;
+    }
+
+    public static String valueOf(Object obj) {
+        return (obj == null) ? "null" : obj.toString();
+    }
+
+    public static String valueOf(char[] data) {
+        return new String(data);
+    }
+
+    public static String valueOf(boolean b) {
+        return b ? "true" : "false";
+    }
+
+    public static String valueOf(char c) {
+        return new String(new char[]{c});
+    }
+
+    public static String valueOf(int i) {
+        return Integer.toString(i);
+    }
+
+    public static String valueOf(long l) {
+        return Long.toString(l);
+    }
+
+    public static String valueOf(float f) {
+        return Float.toString(f);
+    }
+
+    public static String valueOf(double d) {
+        return Double.toString(d);
+    }
+}
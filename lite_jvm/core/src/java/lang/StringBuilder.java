@@ -0,0 +1,147 @@
+package java.lang;
+
+public final class StringBuilder {
+    private char[] value;
+    private int count;
+
+    public StringBuilder() {
+        this(16);
+    }
+
+    public StringBuilder(int capacity) {
+        value = new char[capacity];
+    }
+
+    public StringBuilder(String str) {
+        this(str.length() + 16);
+        append(str);
+    }
+
+    public int length() {
+        return count;
+    }
+
+    public char charAt(int index) {
+        if (index < 0 || index >= count) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        return value[index];
+    }
+
+    public void setCharAt(int index, char ch) {
+        if (index < 0 || index >= count) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        value[index] = ch;
+    }
+
+    public void setLength(int newLength) {
+        if (newLength < 0) {
+            throw new StringIndexOutOfBoundsException(newLength);
+        }
+        ensureCapacity(newLength);
+        for (int i = count; i < newLength; i++) {
+            value[i] = '\0';
+        }
+        count = newLength;
+    }
+
+    private void ensureCapacity(int minimumCapacity) {
+        if (minimumCapacity > value.length) {
+            int newCapacity = Math.max(value.length * 2 + 2, minimumCapacity);
+            char[] newValue = new char[newCapacity];
+            System.arraycopy(value, 0, newValue, 0, count);
+            value = newValue;
+        }
+    }
+
+    public StringBuilder append(String str) {
+        if (str == null) {
+            str = "null";
+        }
+        int len = str.length();
+        ensureCapacity(count + len);
+        for (int i = 0; i < len; i++) {
+            value[count + i] = str.charAt(i);
+        }
+        count += len;
+        return this;
+    }
+
+    public StringBuilder append(Object obj) {
+        return append(String.valueOf(obj));
+    }
+
+    public StringBuilder append(StringBuilder sb) {
+        return append(String.valueOf(sb));
+    }
+
+    public StringBuilder append(char[] str) {
+        return append(new String(str));
+    }
+
+    public StringBuilder append(boolean b) {
+        return append(String.valueOf(b));
+    }
+
+    public StringBuilder append(char c) {
+        ensureCapacity(count + 1);
+        value[count++] = c;
+        return this;
+    }
+
+    public StringBuilder append(int i) {
+        return append(Integer.toString(i));
+    }
+
+    public StringBuilder append(long l) {
+        return append(Long.toString(l));
+    }
+
+    public StringBuilder append(float f) {
+        return append(Float.toString(f));
+    }
+
+    public StringBuilder append(double d) {
+        return append(Double.toString(d));
+    }
+
+    public StringBuilder insert(int offset, String str) {
+        if (offset < 0 || offset > count) {
+            throw new StringIndexOutOfBoundsException(offset);
+        }
+        if (str == null) {
+            str = "null";
+        }
+        int len = str.length();
+        ensureCapacity(count + len);
+        System.arraycopy(value, offset, value, offset + len, count - offset);
+        for (int i = 0; i < len; i++) {
+            value[offset + i] = str.charAt(i);
+        }
+        count += len;
+        return this;
+    }
+
+    public StringBuilder deleteCharAt(int index) {
+        if (index < 0 || index >= count) {
+            throw new StringIndexOutOfBoundsException(index);
+        }
+        System.arraycopy(value, index + 1, value, index, count - index - 1);
+        count--;
+        return this;
+    }
+
+    public StringBuilder reverse() {
+        for (int i = 0, j = count - 1; i < j; i++, j--) {
+            char c = value[i];
+            value[i] = value[j];
+            value[j] = c;
+        }
+        return this;
+    }
+
+    public String toString() {
+        return new String(value, 0, count);
+    }
+}
@@ -0,0 +1,39 @@
+package java.lang;
+
+import java.io.PrintStream;
+
+public final class System {
+    private static native void registerNatives();
+
+    static {
+        registerNatives();
+    }
+
+    //PrintStream的方法体为空，输出由虚拟机的print/println native完成
+    public static final PrintStream out = new PrintStream();
+    public static final PrintStream err = new PrintStream();
+
+    private System() {
+    }
+
+    public static native long currentTimeMillis();
+
+    public static native long nanoTime();
+
+    public static native void arraycopy(Object src, int srcPos, Object dest, int destPos, int length);
+
+    //没有Runtime和Shutdown，由虚拟机直接结束执行
+    public static native void exit(int status);
+
+    public static int identityHashCode(Object x) {
+        return x == null ? 0 : x.hashCode();
+    }
+
+    public static String getProperty(String key) {
+        return null;
+    }
+
+    public static String lineSeparator() {
+        return "\n";
+    }
+}
@@ -0,0 +1,90 @@
+package java.lang;
+
+public class Throwable {
+    private static final StackTraceElement[] UNASSIGNED_STACK = new StackTraceElement[0];
+
+    //fillInStackTrace(int)记录的调用栈
+    private transient Object backtrace;
+    private String detailMessage;
+    private Throwable cause = this;
+    private StackTraceElement[] stackTrace = UNASSIGNED_STACK;
+
+    public Throwable() {
+        fillInStackTrace();
+    }
+
+    public Throwable(String message) {
+        fillInStackTrace();
+        detailMessage = message;
+    }
+
+    public Throwable(String message, Throwable cause) {
+        fillInStackTrace();
+        detailMessage = message;
+        this.cause = cause;
+    }
+
+    public Throwable(Throwable cause) {
+        fillInStackTrace();
+        detailMessage = (cause == null ? null : cause.toString());
+        this.cause = cause;
+    }
+
+    public String getMessage() {
+        return detailMessage;
+    }
+
+    public String getLocalizedMessage() {
+        return getMessage();
+    }
+
+    public Throwable getCause() {
+        return (cause == this ? null : cause);
+    }
+
+    public Throwable initCause(Throwable cause) {
+        if (this.cause != this) {
+            throw new IllegalStateException("Can't overwrite cause", this);
+        }
+        if (cause == this) {
+            throw new IllegalArgumentException("Self-causation not permitted", this);
+        }
+        this.cause = cause;
+        return this;
+    }
+
+    public String toString() {
+        String s = getClass().getName();
+        String message = getLocalizedMessage();
+        return (message != null) ? (s + ": " + message) : s;
+    }
+
+    public Throwable fillInStackTrace() {
+        fillInStackTrace(0);
+        return this;
+    }
+
+    private native Throwable fillInStackTrace(int dummy);
+
+    public StackTraceElement[] getStackTrace() {
+        if (stackTrace.length == 0 && backtrace != null) {
+            stackTrace = (StackTraceElement[]) backtrace;
+        }
+        StackTraceElement[] copy = new StackTraceElement[stackTrace.length];
+        System.arraycopy(stackTrace, 0, copy, 0, stackTrace.length);
+        return copy;
+    }
+
+    public void printStackTrace() {
+        System.err.println(this);
+        StackTraceElement[] trace = getStackTrace();
+        for (int i = 0; i < trace.length; i++) {
+            System.err.println("\tat " + trace[i]);
+        }
+        Throwable cause = getCause();
+        if (cause != null) {
+            System.err.print("Caused by: ");
+            cause.printStackTrace();
+        }
+    }
+}
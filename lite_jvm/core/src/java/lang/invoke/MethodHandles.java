@@ -0,0 +1,30 @@
+package java.lang.invoke;
+
+public class MethodHandles {
+    private MethodHandles() {
+    }
+
+    public static final class Lookup {
+        public static final int PUBLIC = 1;
+        public static final int PRIVATE = 2;
+        public static final int PROTECTED = 4;
+        public static final int PACKAGE = 8;
+
+        //由虚拟机在调用引导方法时直接写入
+        private final Class<?> lookupClass;
+        private final int allowedModes;
+
+        private Lookup(Class<?> lookupClass, int allowedModes) {
+            this.lookupClass = lookupClass;
+            this.allowedModes = allowedModes;
+        }
+
+        public Class<?> lookupClass() {
+            return lookupClass;
+        }
+
+        public int lookupModes() {
+            return allowedModes;
+        }
+    }
+}
@@ -0,0 +1,30 @@
+package lite.jvm;
+
+//不依赖JUnit的断言，由虚拟机注册的native实现，失败时抛出java.lang.AssertionError
+public final class Assert {
+    private Assert() {
+    }
+
+    public static native void assertEquals(int expected, int actual);
+
+    public static native void assertEquals(long expected, long actual);
+
+    public static void assertEquals(Object expected, Object actual) {
+        if (expected == null ? actual == null : expected.equals(actual)) {
+            return;
+        }
+        failNotEquals(String.valueOf(expected), String.valueOf(actual));
+    }
+
+    public static native void assertTrue(boolean condition);
+
+    public static native void assertFalse(boolean condition);
+
+    public static native void assertNull(Object object);
+
+    public static native void assertNotNull(Object object);
+
+    public static native void fail(String message);
+
+    private static native void failNotEquals(String expected, String actual);
+}
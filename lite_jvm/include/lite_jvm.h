@@ -0,0 +1,54 @@
+/*
+ * lite_jvm的C接口，需要开启ffi feature编译cdylib：
+ *   cargo rustc -p lite_jvm --release --features ffi --crate-type cdylib
+ *
+ * 每个LiteJvm持有一个虚拟机和一个调用栈，同一个实例不能在多个线程中同时使用。
+ * 函数返回状态码，失败时通过litejvm_last_error_message读取错误信息。
+ */
+#ifndef LITE_JVM_H
+#define LITE_JVM_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define LITEJVM_OK 0
+/* 空指针、非UTF-8字符串或者描述符和参数不一致 */
+#define LITEJVM_INVALID_ARGUMENT (-1)
+/* 方法抛出了没有被捕获的Java异常 */
+#define LITEJVM_EXCEPTION (-2)
+/* 类加载失败、找不到方法等虚拟机内部错误 */
+#define LITEJVM_ERROR (-3)
+#define LITEJVM_PANIC (-4)
+
+typedef struct LiteJvm LiteJvm;
+
+/* 创建虚拟机，heap_size是堆的字节数。失败时返回NULL */
+LiteJvm *litejvm_create(size_t heap_size);
+
+/* 添加类路径，格式和java -cp相同 */
+int litejvm_add_classpath(LiteJvm *vm, const char *path);
+
+/*
+ * 调用参数和返回值都是int的静态方法，class使用内部名称，例如java/lang/Math。
+ * boolean、byte、char、short按int传递，成功时返回值写入out。nargs为0时args可以是NULL
+ */
+int litejvm_invoke_static_int(LiteJvm *vm, const char *class_name, const char *method,
+                              const char *descriptor, const int *args, int nargs, int *out);
+
+/*
+ * 最近一次调用失败时的错误信息，Java异常包括调用栈。成功或者没有调用过时返回NULL。
+ * 返回的字符串由vm持有，下一次调用vm上的函数后失效
+ */
+const char *litejvm_last_error_message(const LiteJvm *vm);
+
+/* 销毁虚拟机，vm为NULL时什么也不做 */
+void litejvm_destroy(LiteJvm *vm);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LITE_JVM_H */
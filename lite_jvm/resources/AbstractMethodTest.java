@@ -0,0 +1,27 @@
+public class AbstractMethodTest {
+    //The shipped AbstractMethodTest$Shape.class comes from a later version
+    //where area() is abstract and has no body. Square was compiled against
+    //this version and never overrides area(), so calling it must fail with
+    //AbstractMethodError instead of running missing code.
+    interface Shape {
+        default int area() {
+            return 1;
+        }
+    }
+
+    static class Square implements Shape {
+    }
+
+    public static int callArea() {
+        return new Square().area();
+    }
+
+    public static int callAreaThroughInterface() {
+        Shape shape = new Square();
+        try {
+            return shape.area();
+        } catch (AbstractMethodError e) {
+            return -1;
+        }
+    }
+}
@@ -0,0 +1,107 @@
+public class AccessorTest {
+    private int count;
+    private long total;
+    private float ratio;
+    private double weight;
+    private String name;
+    private boolean flag;
+
+    public int getCount() {
+        return count;
+    }
+
+    public void setCount(int count) {
+        this.count = count;
+    }
+
+    public long getTotal() {
+        return total;
+    }
+
+    public void setTotal(long total) {
+        this.total = total;
+    }
+
+    public float getRatio() {
+        return ratio;
+    }
+
+    public void setRatio(float ratio) {
+        this.ratio = ratio;
+    }
+
+    public double getWeight() {
+        return weight;
+    }
+
+    public void setWeight(double weight) {
+        this.weight = weight;
+    }
+
+    public String getName() {
+        return name;
+    }
+
+    public void setName(String name) {
+        this.name = name;
+    }
+
+    public boolean isFlag() {
+        return flag;
+    }
+
+    public void setFlag(boolean flag) {
+        this.flag = flag;
+    }
+
+    public AccessorTest self() {
+        return this;
+    }
+
+    public int minus100() {
+        return -100;
+    }
+
+    public long one() {
+        return 1L;
+    }
+
+    public Object nothing() {
+        return null;
+    }
+
+    public static double second(long ignored, double value) {
+        return value;
+    }
+
+    //静态方法中的aload_0可能是null，不走快速路径
+    public static int countOf(AccessorTest accessor) {
+        return accessor.count;
+    }
+
+    public static AccessorTest create() {
+        return new AccessorTest();
+    }
+
+    public static int nullReceiver() {
+        AccessorTest accessor = null;
+        try {
+            return accessor.getCount();
+        } catch (NullPointerException e) {
+            return -1;
+        }
+    }
+
+    public static long run(int n) {
+        AccessorTest accessor = new AccessorTest();
+        for (int i = 0; i < n; i++) {
+            accessor.setCount(i);
+            accessor.setTotal(accessor.getTotal() + accessor.getCount());
+            accessor.setWeight(accessor.getWeight() + 0.5);
+            accessor.setRatio(accessor.getRatio() + 0.25f);
+            accessor.setFlag(!accessor.isFlag());
+        }
+        long result = accessor.getTotal() + (long) accessor.getWeight() + (long) accessor.getRatio();
+        return accessor.isFlag() ? result : -result;
+    }
+}
@@ -0,0 +1,42 @@
+public class ArrayClassTest {
+    static String intArrayName() {
+        return new int[0].getClass().getName();
+    }
+
+    static String stringArrayName() {
+        return new String[0].getClass().getName();
+    }
+
+    static boolean sameClass() {
+        return new int[1].getClass() == new int[2].getClass();
+    }
+
+    static int cloneSum() {
+        int[] a = {1, 2, 3};
+        int[] b = a.clone();
+        b[0] = 10;
+        return a[0] + b[0] + b.length;
+    }
+
+    static boolean equalsSelf() {
+        int[] a = new int[2];
+        int[] b = new int[2];
+        return a.equals(a) && !a.equals(b);
+    }
+
+    static boolean hashCodeStable() {
+        int[] a = new int[1];
+        Object o = a;
+        return a.hashCode() == o.hashCode();
+    }
+
+    static String arrayToString() {
+        return new int[1].toString();
+    }
+
+    static boolean instanceOfChecks() {
+        Object o = new int[1];
+        return o instanceof int[] && o instanceof Cloneable && o instanceof java.io.Serializable
+                && !(o instanceof long[]);
+    }
+}
@@ -0,0 +1,40 @@
+//二维数组的元素是数组，aastore和aaload需要支持数组类型的值
+public class ArrayOfArrays {
+    public static int store() {
+        int[][] a = new int[2][];
+        a[0] = new int[3];
+        return a[1] == null ? 1 : 0;
+    }
+
+    public static int load() {
+        int[][] a = new int[2][];
+        a[0] = new int[3];
+        a[0][2] = 7;
+        return a[0].length + a[0][2];
+    }
+
+    public static Object[] nested() {
+        Object[] a = new Object[1];
+        a[0] = new String[1];
+        return a;
+    }
+
+    //String[]当作Object[]使用时只能存放String，其他引用抛出ArrayStoreException
+    public static int covariantStore() {
+        Object[] strings = new String[1];
+        strings[0] = "ok";
+        int caught = 0;
+        try {
+            strings[0] = Integer.valueOf(1);
+        } catch (ArrayStoreException e) {
+            caught++;
+        }
+        Object[][] tables = new String[1][];
+        try {
+            tables[0] = new Object[1];
+        } catch (ArrayStoreException e) {
+            caught++;
+        }
+        return strings[0] == "ok" && tables[0] == null ? caught : -1;
+    }
+}
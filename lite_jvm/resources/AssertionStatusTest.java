@@ -0,0 +1,13 @@
+//javac为包含assert的类生成$assertionsDisabled，在<clinit>中通过desiredAssertionStatus设置
+public class AssertionStatusTest {
+    static int check(int value) {
+        assert value > 0 : "value must be positive";
+        return value;
+    }
+
+    static boolean enabled() {
+        boolean enabled = false;
+        assert enabled = true;
+        return enabled;
+    }
+}
@@ -0,0 +1,23 @@
+public class BudgetTest {
+    static int counter;
+    static boolean caught;
+
+    //预算耗尽不是Java异常，catch (Throwable)也捕获不到
+    public static void spin() {
+        try {
+            while (true) {
+                counter++;
+            }
+        } catch (Throwable t) {
+            caught = true;
+        }
+    }
+
+    static int leaf(int x) {
+        return x * 2 + 1;
+    }
+
+    public static int chain() {
+        return leaf(1) + leaf(2);
+    }
+}
@@ -0,0 +1,26 @@
+public class CallTest {
+    int base;
+
+    public CallTest(int base) {
+        this.base = base;
+    }
+
+    public static int add(int a, int b) {
+        return a + b;
+    }
+
+    public int offset(int value) {
+        return base + value;
+    }
+}
+
+class ScaledCallTest extends CallTest {
+    public ScaledCallTest(int base) {
+        super(base);
+    }
+
+    @Override
+    public int offset(int value) {
+        return base * value;
+    }
+}
@@ -0,0 +1,3 @@
+//和CircularB互相继承，用来测试ClassCircularityError。CircularA.class先按CircularB extends Object编译
+public class CircularA extends CircularB {
+}
@@ -0,0 +1,3 @@
+//和CircularA互相继承，单独编译后替换
+public class CircularB extends CircularA {
+}
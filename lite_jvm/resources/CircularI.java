@@ -0,0 +1,3 @@
+//和CircularJ互相继承的接口
+public interface CircularI extends CircularJ {
+}
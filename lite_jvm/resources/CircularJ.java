@@ -0,0 +1,3 @@
+//和CircularI互相继承的接口，单独编译后替换
+public interface CircularJ extends CircularI {
+}
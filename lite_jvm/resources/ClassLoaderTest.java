@@ -0,0 +1,5 @@
+public class ClassLoaderTest {
+    public static Class<?> load(String name, ClassLoader loader) throws ClassNotFoundException {
+        return Class.forName(name, true, loader);
+    }
+}
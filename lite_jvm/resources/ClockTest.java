@@ -0,0 +1,15 @@
+public class ClockTest {
+    static long elapsedAroundSleep(long millis) throws InterruptedException {
+        long start = System.nanoTime();
+        Thread.sleep(millis);
+        return System.nanoTime() - start;
+    }
+
+    static long millis() {
+        return System.currentTimeMillis();
+    }
+
+    static long nanos() {
+        return System.nanoTime();
+    }
+}
@@ -0,0 +1,57 @@
+import java.lang.invoke.MethodHandles;
+
+/**
+ * ldc加载动态计算常量(CONSTANT_Dynamic)的测试。
+ * javac不会生成CONSTANT_Dynamic，编译后对class文件做了修改：
+ * 常量池追加了引导方法的MethodHandle和Dynamic常量，增加了BootstrapMethods属性，
+ * 各方法中的getstatic P_XXX替换为ldc_w对应的Dynamic常量：
+ * <pre>
+ * P_NULL_OBJECT -> ConstantBootstraps.nullConstant, _:Ljava/lang/Object;
+ * P_NULL_STRING -> ConstantBootstraps.nullConstant, _:Ljava/lang/String;
+ * P_INT_CLASS   -> ConstantBootstraps.primitiveClass, I:Ljava/lang/Class;
+ * P_ANSWER      -> CondyTest.computeAnswer, answer:I
+ * P_GREETING    -> ConstantBootstraps.getStaticFinal(CondyTest.class), GREETING:Ljava/lang/String;
+ * </pre>
+ */
+public class CondyTest {
+    static final String GREETING = new String("hello condy");
+    static int bootstrapCalls;
+    static Class<?> lookupClass;
+
+    static Object P_NULL_OBJECT;
+    static String P_NULL_STRING;
+    static Class<?> P_INT_CLASS;
+    static int P_ANSWER;
+    static String P_GREETING;
+
+    static Object computeAnswer(MethodHandles.Lookup lookup, String name, Class<?> type) {
+        bootstrapCalls++;
+        lookupClass = lookup.lookupClass();
+        return 42;
+    }
+
+    static Object nullObject() {
+        return P_NULL_OBJECT;
+    }
+
+    static String nullString() {
+        return P_NULL_STRING;
+    }
+
+    static Class<?> intClass() {
+        return P_INT_CLASS;
+    }
+
+    static int answer() {
+        return P_ANSWER;
+    }
+
+    //同一个常量只调用一次引导方法
+    static int answerTwice() {
+        return answer() + answer() + bootstrapCalls;
+    }
+
+    static String greeting() {
+        return P_GREETING;
+    }
+}
@@ -0,0 +1,11 @@
+public class ConstantHolder {
+    public static final int VALUE = 42;
+    public static final String NAME = "constant";
+    public static int counter;
+    public static boolean initialized;
+
+    static {
+        counter = 1;
+        initialized = true;
+    }
+}
@@ -0,0 +1,15 @@
+//ConstantReader编译时ConstantHolder.VALUE/NAME还不是常量，所以保留了getstatic，
+//之后ConstantHolder重新编译成static final常量（和修改了依赖但没有重新编译使用方一样）
+public class ConstantReader {
+    static int readValue() {
+        return ConstantHolder.VALUE;
+    }
+
+    static String readName() {
+        return ConstantHolder.NAME;
+    }
+
+    static int readCounter() {
+        return ConstantHolder.counter;
+    }
+}
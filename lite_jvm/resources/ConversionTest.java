@@ -0,0 +1,14 @@
+public class ConversionTest {
+    static long i2l(int v) { return v; }
+    static float i2f(int v) { return v; }
+    static double i2d(int v) { return v; }
+    static int l2i(long v) { return (int) v; }
+    static float l2f(long v) { return v; }
+    static double l2d(long v) { return v; }
+    static int f2i(float v) { return (int) v; }
+    static long f2l(float v) { return (long) v; }
+    static double f2d(float v) { return v; }
+    static int d2i(double v) { return (int) v; }
+    static long d2l(double v) { return (long) v; }
+    static float d2f(double v) { return (float) v; }
+}
@@ -0,0 +1,48 @@
+public class DefaultMethodTest {
+    interface Greeter {
+        default int greet() {
+            return 1;
+        }
+    }
+
+    interface LoudGreeter extends Greeter {
+        default int greet() {
+            return 2;
+        }
+    }
+
+    interface Named {
+        int id();
+
+        default int idPlusGreet() {
+            return id() + 100;
+        }
+    }
+
+    static class Plain implements Greeter {
+    }
+
+    //Greeter写在前面，但是LoudGreeter更具体
+    static class Loud implements Greeter, LoudGreeter, Named {
+        public int id() {
+            return 5;
+        }
+    }
+
+    public static int plainGreet() {
+        return new Plain().greet();
+    }
+
+    public static int loudGreet() {
+        return new Loud().greet();
+    }
+
+    public static int loudGreetViaInterface() {
+        Greeter greeter = new Loud();
+        return greeter.greet();
+    }
+
+    public static int inheritedDefaultCallingAbstract() {
+        return new Loud().idPlusGreet();
+    }
+}
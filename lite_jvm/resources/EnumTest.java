@@ -0,0 +1,53 @@
+public class EnumTest {
+    enum Color {
+        RED, GREEN, BLUE
+    }
+
+    public static Color green() {
+        return Color.GREEN;
+    }
+
+    public static int valuesLength() {
+        return Color.values().length;
+    }
+
+    public static boolean valuesIsCopy() {
+        Color[] first = Color.values();
+        first[0] = Color.BLUE;
+        return Color.values()[0] == Color.RED;
+    }
+
+    public static int ordinalOf(int index) {
+        return Color.values()[index].ordinal();
+    }
+
+    public static String nameOf(int index) {
+        return Color.values()[index].name();
+    }
+
+    public static int valueOfOrdinal(String name) {
+        return Color.valueOf(name).ordinal();
+    }
+
+    public static boolean valueOfRejects(String name) {
+        try {
+            Color.valueOf(name);
+            return false;
+        } catch (IllegalArgumentException e) {
+            return true;
+        }
+    }
+
+    public static int describe(Color color) {
+        switch (color) {
+            case RED:
+                return 10;
+            case GREEN:
+                return 20;
+            case BLUE:
+                return 30;
+            default:
+                return -1;
+        }
+    }
+}
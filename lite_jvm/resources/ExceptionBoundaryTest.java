@@ -0,0 +1,64 @@
+//异常表的范围是[start_pc, end_pc)，fail()的调用分别位于范围的起点、最后一条指令、end_pc和start_pc之前
+public class ExceptionBoundaryTest {
+    static int calls;
+    static int value;
+
+    static void fail() {
+        calls++;
+        throw new NullPointerException();
+    }
+
+    //invokestatic位于start_pc
+    static int atStart() {
+        try {
+            fail();
+        } catch (NullPointerException e) {
+            return -1;
+        }
+        return 0;
+    }
+
+    //invokestatic是范围内的最后一条指令
+    static int atEndMinusOne() {
+        try {
+            value++;
+            fail();
+        } catch (NullPointerException e) {
+            return -1;
+        }
+        return 0;
+    }
+
+    //finally内联在try块之后，第一条invokestatic位于end_pc，不能被any处理器捕获
+    static int atEnd() {
+        try {
+            value++;
+        } finally {
+            fail();
+        }
+        return 0;
+    }
+
+    //invokestatic紧挨在start_pc之前
+    static int beforeStart() {
+        fail();
+        try {
+            value++;
+        } catch (NullPointerException e) {
+            return -1;
+        }
+        return 0;
+    }
+
+    //类型不匹配的处理器即使范围包含抛出位置也要跳过
+    static int catchTypeOrder() {
+        try {
+            fail();
+        } catch (IllegalStateException e) {
+            return -1;
+        } catch (RuntimeException e) {
+            return -2;
+        }
+        return 0;
+    }
+}
@@ -0,0 +1,26 @@
+//thrownAtEndPc的异常表在编译后被修改，end_pc指向调用fail()的invokestatic指令
+public class ExceptionRangeTest {
+    static int marker;
+
+    public static int thrownInsideRange() {
+        try {
+            marker = 1;
+            return fail();
+        } catch (IllegalStateException e) {
+            return -1;
+        }
+    }
+
+    public static int thrownAtEndPc() {
+        try {
+            marker = 2;
+            return fail();
+        } catch (IllegalStateException e) {
+            return -1;
+        }
+    }
+
+    static int fail() {
+        throw new IllegalStateException();
+    }
+}
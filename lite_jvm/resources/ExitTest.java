@@ -0,0 +1,20 @@
+public class ExitTest {
+    static int before;
+    static int after;
+    static boolean finallyRan;
+
+    //System.exit不会返回，finally块也不会执行
+    public static void main(String[] args) {
+        before = 1;
+        try {
+            System.exit(3);
+            after = 1;
+        } finally {
+            finallyRan = true;
+        }
+    }
+
+    public static int answer() {
+        return 42;
+    }
+}
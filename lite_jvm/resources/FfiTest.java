@@ -0,0 +1,21 @@
+//通过C接口调用的静态方法
+public class FfiTest {
+    public static int add(int a, int b) {
+        return a + b;
+    }
+
+    public static int divide(int a, int b) {
+        if (b == 0) {
+            throw new IllegalArgumentException("divisor is zero");
+        }
+        return a / b;
+    }
+
+    public static boolean isPositive(int value) {
+        return value > 0;
+    }
+
+    public static long square(long value) {
+        return value * value;
+    }
+}
@@ -0,0 +1,37 @@
+public class FieldShadowTest {
+    static class Base {
+        private int value;
+
+        void setBaseValue(int v) {
+            value = v;
+        }
+
+        int baseValue() {
+            return value;
+        }
+    }
+
+    static class Sub extends Base {
+        //与父类的私有字段同名，占用独立的槽位
+        private int value;
+
+        void setSubValue(int v) {
+            value = v;
+        }
+
+        int subValue() {
+            return value;
+        }
+    }
+
+    public static Sub create() {
+        Sub sub = new Sub();
+        sub.setBaseValue(1);
+        sub.setSubValue(2);
+        return sub;
+    }
+
+    public static int combined(Sub sub) {
+        return sub.baseValue() * 10 + sub.subValue();
+    }
+}
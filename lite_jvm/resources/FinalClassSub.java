@@ -0,0 +1,3 @@
+//见FinalMethodOverride.java，FinalClassBase重新编译为final类
+public class FinalClassSub extends FinalClassBase {
+}
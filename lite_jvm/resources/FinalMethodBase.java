@@ -0,0 +1,9 @@
+public class FinalMethodBase {
+    public final int value() {
+        return 1;
+    }
+
+    private final int secret() {
+        return 1;
+    }
+}
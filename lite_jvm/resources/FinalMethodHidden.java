@@ -0,0 +1,6 @@
+//父类的private final方法不会被覆盖，可以声明同名方法
+public class FinalMethodHidden extends FinalMethodBase {
+    int secret() {
+        return 2;
+    }
+}
@@ -0,0 +1,7 @@
+//编译时FinalMethodBase.value()和FinalClassBase都还不是final，之后重新编译为final，
+//这里的覆盖和继承在链接时应该被拒绝
+public class FinalMethodOverride extends FinalMethodBase {
+    public int value() {
+        return 2;
+    }
+}
@@ -0,0 +1,16 @@
+public class FinalizeTest {
+    static int finalizedCount;
+    static FinalizeTest kept;
+
+    protected void finalize() {
+        finalizedCount++;
+    }
+
+    static void allocateGarbage() {
+        new FinalizeTest();
+    }
+
+    static void allocateKept() {
+        kept = new FinalizeTest();
+    }
+}
@@ -0,0 +1,29 @@
+public class GetInterfacesTest {
+    interface First {
+    }
+
+    interface Second {
+    }
+
+    interface Third extends First {
+    }
+
+    static class Both implements First, Second {
+    }
+
+    //只返回直接实现的接口，不包括父类和父接口实现的接口
+    static class Child extends Both implements Third {
+    }
+
+    public static Class<?>[] interfacesOfBoth() {
+        return Both.class.getInterfaces();
+    }
+
+    public static Class<?>[] interfacesOfChild() {
+        return Child.class.getInterfaces();
+    }
+
+    public static int countOfObject() {
+        return Object.class.getInterfaces().length;
+    }
+}
@@ -0,0 +1,21 @@
+public class HeapDumpTest {
+    static HeapDumpTest head;
+
+    HeapDumpTest next;
+    int value;
+    String name;
+    int[] data;
+    Object[] refs;
+
+    static void build(int count) {
+        for (int i = count; i > 0; i--) {
+            HeapDumpTest node = new HeapDumpTest();
+            node.value = i;
+            node.name = "node";
+            node.data = new int[]{i, i * 2};
+            node.next = head;
+            head = node;
+        }
+        head.refs = new Object[]{head.next, "x"};
+    }
+}
@@ -0,0 +1,17 @@
+public class InitErrorTest {
+    static int initCount = 0;
+
+    static class Broken {
+        static String name;
+        static int length;
+
+        static {
+            InitErrorTest.initCount++;
+            length = name.length();
+        }
+    }
+
+    public static int readBroken() {
+        return Broken.length;
+    }
+}
@@ -0,0 +1,61 @@
+public class InterfaceInitTest {
+    static int counter;
+
+    static int next() {
+        return ++counter;
+    }
+
+    //static final字段的值不是常量表达式，需要在<clinit>中赋值
+    interface Constants {
+        int ORDER = next();
+    }
+
+    interface Parent {
+        int ORDER = next();
+    }
+
+    interface WithDefault extends Parent {
+        int ORDER = next();
+
+        default int defaultOrder() {
+            return ORDER;
+        }
+    }
+
+    interface Child extends Parent {
+        int ORDER = next();
+    }
+
+    static class Base {
+        static int ORDER = next();
+    }
+
+    static class Impl extends Base implements Constants, WithDefault {
+        static int ORDER = next();
+
+        int constantsOrder() {
+            return Constants.ORDER;
+        }
+    }
+
+    static int[] run() {
+        //初始化Impl时先初始化Base和WithDefault，Constants和Parent没有默认方法，不会被初始化
+        Impl impl = new Impl();
+        int afterImpl = counter;
+        //接口初始化时不初始化父接口
+        int childOrder = Child.ORDER;
+        int afterChild = counter;
+        int constantsOrder = impl.constantsOrder();
+        int parentOrder = Parent.ORDER;
+        return new int[] {
+            Base.ORDER,
+            impl.defaultOrder(),
+            Impl.ORDER,
+            afterImpl,
+            childOrder,
+            afterChild,
+            constantsOrder,
+            parentOrder
+        };
+    }
+}
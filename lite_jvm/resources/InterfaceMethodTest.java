@@ -0,0 +1,51 @@
+public class InterfaceMethodTest {
+    interface Shape {
+        static int twice(int x) {
+            return x * 2;
+        }
+
+        private int base() {
+            return 10;
+        }
+
+        private static int offset() {
+            return 1;
+        }
+
+        default int area() {
+            return base() + twice(size()) + offset();
+        }
+
+        int size();
+    }
+
+    static class Square implements Shape {
+        public int size() {
+            return 3;
+        }
+
+        //Shape.super.area()编译为invokespecial InterfaceMethodref
+        public int area() {
+            return Shape.super.area() * 100;
+        }
+    }
+
+    static class Circle implements Shape {
+        public int size() {
+            return 4;
+        }
+    }
+
+    public static int defaultArea() {
+        Shape shape = new Circle();
+        return shape.area();
+    }
+
+    public static int superArea() {
+        return new Square().area();
+    }
+
+    public static int staticHelper() {
+        return Shape.twice(21);
+    }
+}
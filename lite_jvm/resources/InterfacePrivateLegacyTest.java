@@ -0,0 +1,19 @@
+//使用--release 10编译，私有接口方法通过invokespecial调用，JDK 11之后改为invokeinterface
+public class InterfacePrivateLegacyTest {
+    interface Counter {
+        private int step() {
+            return 5;
+        }
+
+        default int next(int value) {
+            return value + step();
+        }
+    }
+
+    static class Impl implements Counter {
+    }
+
+    public static int next(int value) {
+        return new Impl().next(value);
+    }
+}
@@ -0,0 +1,67 @@
+import java.util.ArrayList;
+import java.util.Iterator;
+import java.util.List;
+
+public class InterfaceTest {
+    interface Counter {
+        int count();
+
+        default int countTwice() {
+            return count() * 2;
+        }
+    }
+
+    interface NamedCounter extends Counter {
+    }
+
+    static class FixedCounter implements NamedCounter {
+        public int count() {
+            return 21;
+        }
+    }
+
+    interface Accumulator {
+        long add(long base, int delta, double scale);
+    }
+
+    static class ScaledAccumulator implements Accumulator {
+        public long add(long base, int delta, double scale) {
+            return base + (long) (delta * scale);
+        }
+    }
+
+    public static int sumByIterator(int n) {
+        List<Integer> list = new ArrayList<>();
+        for (int i = 1; i <= n; i++) {
+            list.add(i);
+        }
+        int sum = 0;
+        Iterator<Integer> iterator = list.iterator();
+        while (iterator.hasNext()) {
+            sum += iterator.next();
+        }
+        return sum;
+    }
+
+    public static long sumByForEach(int n) {
+        List<Long> list = new ArrayList<>();
+        for (int i = 1; i <= n; i++) {
+            list.add((long) i * 1000000000L);
+        }
+        long sum = 0;
+        for (long value : list) {
+            sum += value;
+        }
+        return sum;
+    }
+
+    public static int callInheritedDefault() {
+        NamedCounter counter = new FixedCounter();
+        return counter.countTwice();
+    }
+
+    public static long callWideArguments() {
+        Accumulator accumulator = new ScaledAccumulator();
+        return accumulator.add(1L << 40, 3, 2.5);
+    }
+}
@@ -0,0 +1,37 @@
+public class MathTest {
+    public static double unary(int op, double a) {
+        switch (op) {
+            case 0: return Math.sin(a);
+            case 1: return Math.cos(a);
+            case 2: return Math.tan(a);
+            case 3: return Math.asin(a);
+            case 4: return Math.acos(a);
+            case 5: return Math.atan(a);
+            case 6: return Math.exp(a);
+            case 7: return Math.log(a);
+            case 8: return Math.log10(a);
+            case 9: return Math.sqrt(a);
+            case 10: return Math.cbrt(a);
+            case 11: return Math.floor(a);
+            case 12: return Math.ceil(a);
+            case 13: return Math.rint(a);
+            case 14: return Math.abs(a);
+            case 15: return StrictMath.sinh(a);
+            case 16: return StrictMath.cosh(a);
+            case 17: return StrictMath.tanh(a);
+            case 18: return StrictMath.expm1(a);
+            case 19: return StrictMath.log1p(a);
+            default: throw new IllegalArgumentException();
+        }
+    }
+
+    public static double binary(int op, double a, double b) {
+        switch (op) {
+            case 0: return Math.atan2(a, b);
+            case 1: return Math.pow(a, b);
+            case 2: return Math.hypot(a, b);
+            case 3: return Math.IEEEremainder(a, b);
+            default: throw new IllegalArgumentException();
+        }
+    }
+}
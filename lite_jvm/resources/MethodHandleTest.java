@@ -0,0 +1,61 @@
+import java.lang.invoke.MethodHandle;
+import java.util.function.IntBinaryOperator;
+import java.util.function.ToIntFunction;
+
+//class文件中读取ADD和GET_VALUE的getstatic被改写为ldc_w，加载handles()中方法引用对应的MethodHandle常量：
+//ADD是REF_invokeStatic MethodHandleTest.add:(II)I，GET_VALUE是REF_invokeVirtual MethodHandleTest.getValue:()I
+public class MethodHandleTest {
+    static MethodHandle ADD;
+    static MethodHandle GET_VALUE;
+
+    static class Sub extends MethodHandleTest {
+        Sub(int value) {
+            super(value);
+        }
+    }
+
+    private final int value;
+
+    MethodHandleTest(int value) {
+        this.value = value;
+    }
+
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    int getValue() {
+        return value;
+    }
+
+    static int invokeExactAdd(int a, int b) throws Throwable {
+        return (int) ADD.invokeExact(a, b);
+    }
+
+    //调用点描述符(II)J和(II)I不一致
+    static long invokeExactAddAsLong(int a, int b) throws Throwable {
+        return (long) ADD.invokeExact(a, b);
+    }
+
+    static int invokeExactGetValue(int value) throws Throwable {
+        return (int) GET_VALUE.invokeExact(new MethodHandleTest(value));
+    }
+
+    //invokeExact不允许参数类型的宽化
+    static int invokeExactGetValueOnSub(int value) throws Throwable {
+        return (int) GET_VALUE.invokeExact(new Sub(value));
+    }
+
+    static int invokeGetValueOnSub(int value) throws Throwable {
+        return (int) GET_VALUE.invoke(new Sub(value));
+    }
+
+    static int invokeGetValueOnObject(int value) throws Throwable {
+        return (int) GET_VALUE.invoke((Object) new MethodHandleTest(value));
+    }
+
+    static void handles() {
+        IntBinaryOperator add = MethodHandleTest::add;
+        ToIntFunction<MethodHandleTest> getValue = MethodHandleTest::getValue;
+    }
+}
@@ -0,0 +1,18 @@
+public class MissingNativeTest {
+    static native int missing();
+
+    static int callMissing(int depth) {
+        if (depth > 0) {
+            return callMissing(depth - 1) + 1;
+        }
+        return missing();
+    }
+
+    static int ok() {
+        return 42;
+    }
+}
+
+class MissingNativeInit {
+    static int value = MissingNativeTest.missing();
+}
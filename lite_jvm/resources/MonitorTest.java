@@ -0,0 +1,45 @@
+public class MonitorTest {
+    private static int counter;
+    private int value;
+
+    public static synchronized int next() {
+        counter++;
+        return counter;
+    }
+
+    public synchronized int add(int delta) {
+        value += delta;
+        return value;
+    }
+
+    public static int nested(int times) {
+        int result = 0;
+        Object lock = new Object();
+        for (int i = 0; i < times; i++) {
+            synchronized (lock) {
+                synchronized (MonitorTest.class) {
+                    result += next();
+                }
+            }
+        }
+        return result;
+    }
+
+    public static synchronized int fail() {
+        throw new IllegalStateException("fail");
+    }
+
+    public static void spin() {
+        synchronized (MonitorTest.class) {
+            while (true) {
+                counter++;
+            }
+        }
+    }
+
+    public static void exitInside(int status) {
+        synchronized (MonitorTest.class) {
+            System.exit(status);
+        }
+    }
+}
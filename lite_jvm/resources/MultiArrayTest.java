@@ -0,0 +1,44 @@
+public class MultiArrayTest {
+    //multianewarray
+    static int grid(int rows, int columns) {
+        int[][] grid = new int[rows][columns];
+        int sum = 0;
+        for (int i = 0; i < rows; i++) {
+            for (int j = 0; j < columns; j++) {
+                grid[i][j] = i * 10 + j;
+            }
+        }
+        for (int[] row : grid) {
+            for (int value : row) {
+                sum += value;
+            }
+        }
+        return sum + grid.length * 1000 + grid[rows - 1].length * 100;
+    }
+
+    //只指定外层两维的长度，最内层为null
+    static String[][][] partial(int a, int b) {
+        return new String[a][b][];
+    }
+
+    //anewarray的元素类型本身是数组类
+    static String[][] jagged() {
+        String[][] rows = new String[2][];
+        rows[0] = new String[]{"a"};
+        rows[1] = new String[]{"b", "c"};
+        return rows;
+    }
+
+    static boolean isObjectArray(Object value) {
+        return value instanceof Object[];
+    }
+
+    static int negative(int a, int b) {
+        try {
+            long[][] values = new long[a][b];
+            return values.length;
+        } catch (NegativeArraySizeException e) {
+            return -1;
+        }
+    }
+}
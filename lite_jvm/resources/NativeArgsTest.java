@@ -0,0 +1,21 @@
+public class NativeArgsTest {
+    //long和double参数在native的args中各占一个元素
+    static native long shift(long value, int bits);
+
+    native double scale(int a, double factor, long offset);
+
+    static long callShift(long value, int bits) {
+        return shift(value, bits) + 1;
+    }
+
+    static double callScale(int a, double factor, long offset) {
+        return new NativeArgsTest().scale(a, factor, offset);
+    }
+
+    static long copyLongs() {
+        long[] src = {1L, 2L, 3L};
+        long[] dst = new long[5];
+        System.arraycopy(src, 0, dst, 1, 3);
+        return dst[0] + dst[1] * 10 + dst[2] * 100 + dst[3] * 1000 + dst[4] * 10000;
+    }
+}
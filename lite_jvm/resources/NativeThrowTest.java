@@ -0,0 +1,44 @@
+//native方法抛出的异常由调用方的异常表捕获
+public class NativeThrowTest {
+    static native int check(int value);
+
+    static int recover(int value) {
+        try {
+            return check(value);
+        } catch (IllegalStateException e) {
+            return -1;
+        }
+    }
+
+    //按父类捕获，并且读取native设置的异常信息
+    static String message(int value) {
+        try {
+            check(value);
+            return "ok";
+        } catch (RuntimeException e) {
+            return e.getMessage();
+        }
+    }
+
+    static int retry(int value) {
+        int attempts = 0;
+        while (true) {
+            try {
+                attempts++;
+                return check(value + attempts) * 100 + attempts;
+            } catch (IllegalStateException e) {
+                if (attempts > 5) {
+                    throw e;
+                }
+            }
+        }
+    }
+
+    static int uncaught(int value) {
+        try {
+            return check(value);
+        } catch (IllegalArgumentException e) {
+            return -2;
+        }
+    }
+}
@@ -0,0 +1,20 @@
+public class NestedTest {
+    static class StaticNested {
+    }
+
+    class Inner {
+    }
+
+    public Runnable anonymous() {
+        return new Runnable() {
+            public void run() {
+            }
+        };
+    }
+
+    public Object local() {
+        class Local {
+        }
+        return new Local();
+    }
+}
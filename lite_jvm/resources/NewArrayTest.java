@@ -0,0 +1,72 @@
+//badAtype中newarray的atype在编译后从10(T_INT)改成了3，用于测试无效的atype
+public class NewArrayTest {
+    static int lengths() {
+        return new boolean[1].length + new char[2].length + new float[3].length
+                + new double[4].length + new byte[5].length + new short[6].length
+                + new int[7].length + new long[8].length;
+    }
+
+    static int badAtype() {
+        return new int[1].length;
+    }
+
+    //参数已经是窄类型，javac不会在xastore前生成i2b/i2c/i2s，由测试直接传入超出范围的int
+    static boolean[] storeBoolean(boolean value) {
+        boolean[] array = new boolean[1];
+        array[0] = value;
+        return array;
+    }
+
+    static byte[] storeByte(byte value) {
+        byte[] array = new byte[1];
+        array[0] = value;
+        return array;
+    }
+
+    static char[] storeChar(char value) {
+        char[] array = new char[1];
+        array[0] = value;
+        return array;
+    }
+
+    static short[] storeShort(short value) {
+        short[] array = new short[1];
+        array[0] = value;
+        return array;
+    }
+
+    static int loadByte(byte value) {
+        return storeByte(value)[0];
+    }
+
+    static boolean loadBoolean(boolean value) {
+        return storeBoolean(value)[0];
+    }
+
+    static int byteBoundaries() {
+        byte[] array = {Byte.MIN_VALUE, Byte.MAX_VALUE, -1};
+        return array[0] + array[1] * 1000 + array[2] * 1000000;
+    }
+
+    static int kind(Object value) {
+        if (value instanceof boolean[]) {
+            return 1;
+        }
+        if (value instanceof byte[]) {
+            return 2;
+        }
+        return 0;
+    }
+
+    static int booleanKind() {
+        return kind(new boolean[1]);
+    }
+
+    static int byteKind() {
+        return kind(new byte[1]);
+    }
+
+    static int charKind() {
+        return kind(new char[1]);
+    }
+}
@@ -0,0 +1,5 @@
+public class NoSourceFileTest {
+    public static int fail(String value) {
+        return value.length();
+    }
+}
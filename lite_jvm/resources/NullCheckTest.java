@@ -0,0 +1,34 @@
+public class NullCheckTest {
+    //ifnonnull，向前跳转
+    static int isNull(Object value) {
+        return value == null ? 1 : 0;
+    }
+
+    //ifnull，向前跳转
+    static int isNonNull(Object value) {
+        if (value != null) {
+            return 1;
+        }
+        return 0;
+    }
+
+    //do-while的条件在循环末尾，ifnonnull向后跳转
+    static int countUntilNull(Object[] values) {
+        int i = 0;
+        Object value;
+        do {
+            value = values[i++];
+        } while (value != null);
+        return i;
+    }
+
+    //ifnull向后跳转
+    static int skipNulls(Object[] values) {
+        int i = 0;
+        Object value;
+        do {
+            value = values[i++];
+        } while (value == null);
+        return i;
+    }
+}
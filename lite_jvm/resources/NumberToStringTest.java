@@ -0,0 +1,27 @@
+public class NumberToStringTest {
+    public static String intToString(int i) {
+        return Integer.toString(i);
+    }
+
+    public static String longToString(long l) {
+        return Long.toString(l);
+    }
+
+    public static String doubleToString(double d) {
+        return Double.toString(d);
+    }
+
+    public static String floatToString(float f) {
+        return Float.toString(f);
+    }
+
+    //String.valueOf和包装类的toString()最终都调用静态的toString
+    public static String boxed(int i, double d) {
+        return String.valueOf(i) + "," + Double.valueOf(d).toString();
+    }
+
+    //结果不放入字符串常量池，每次都是新对象
+    public static boolean sameInstance(int i) {
+        return Integer.toString(i) == Integer.toString(i);
+    }
+}
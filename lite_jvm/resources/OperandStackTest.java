@@ -0,0 +1,13 @@
+/**
+ * 操作数栈溢出检查的测试。编译后把overPush的max_stack从2改为1，
+ * 第二条iload_1压栈时超出max_stack
+ */
+public class OperandStackTest {
+    static int add(int a, int b) {
+        return a + b;
+    }
+
+    static int overPush(int a, int b) {
+        return a + b;
+    }
+}
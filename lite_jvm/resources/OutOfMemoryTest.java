@@ -0,0 +1,30 @@
+//堆空间不足时抛出OutOfMemoryError，可以被捕获
+public class OutOfMemoryTest {
+    static int[] retained;
+
+    public static int[] allocate(int size) {
+        return new int[size];
+    }
+
+    public static int catchOutOfMemory(int size) {
+        try {
+            return allocate(size).length;
+        } catch (OutOfMemoryError e) {
+            return -1;
+        }
+    }
+
+    //不断分配小数组直到堆被占满，没有GC，分配过的空间不会被回收
+    public static void fillHeap() {
+        try {
+            while (true) {
+                retained = new int[4];
+            }
+        } catch (OutOfMemoryError e) {
+        }
+    }
+
+    public static String loadString() {
+        return "a string literal that has not been allocated yet";
+    }
+}
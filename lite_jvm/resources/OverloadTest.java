@@ -0,0 +1,47 @@
+public class OverloadTest {
+    private int base;
+
+    public OverloadTest(int base) {
+        this.base = base;
+    }
+
+    public static int foo(int value) {
+        return value + 1;
+    }
+
+    public static long foo(long value) {
+        return value + 2;
+    }
+
+    public static String foo(String value) {
+        return value.concat("!");
+    }
+
+    public static int describe(Object value) {
+        return 1;
+    }
+
+    public static int describe(CharSequence value) {
+        return 2;
+    }
+
+    public static int count(int[] values) {
+        return values.length;
+    }
+
+    public static int count(String[] values) {
+        return -values.length;
+    }
+
+    public int add(int value) {
+        return base + value;
+    }
+
+    public long add(long value) {
+        return base + value;
+    }
+
+    public int length(String value) {
+        return base + value.length();
+    }
+}
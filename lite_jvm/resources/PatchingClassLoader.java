@@ -0,0 +1,35 @@
+//优先由自身定义target类，其余的类委派给引导类加载器
+public class PatchingClassLoader extends ClassLoader {
+    private final String target;
+    private final byte[] bytes;
+    public int loadRequests;
+
+    public PatchingClassLoader(String target, byte[] bytes) {
+        super(null);
+        this.target = target;
+        this.bytes = bytes;
+    }
+
+    @Override
+    public Class<?> loadClass(String name) throws ClassNotFoundException {
+        loadRequests++;
+        if (name.equals(target)) {
+            Class<?> loaded = findLoadedClass(name);
+            return loaded != null ? loaded : findClass(name);
+        }
+        return super.loadClass(name);
+    }
+
+    @Override
+    protected Class<?> findClass(String name) throws ClassNotFoundException {
+        if (name.equals(target)) {
+            return defineClass(bytes, 0, bytes.length);
+        }
+        throw new ClassNotFoundException(name);
+    }
+
+    //按指定的类名和范围定义bytes中的类
+    public Class<?> defineRange(String name, int offset, int length) {
+        return defineClass(name, bytes, offset, length);
+    }
+}
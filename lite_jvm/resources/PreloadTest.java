@@ -0,0 +1,18 @@
+public class PreloadTest extends PreloadBase implements Runnable {
+    static int initialized = Integer.parseInt("1");
+    static final String GREETING = "hello";
+
+    public void run() {
+    }
+}
+
+class PreloadBase {
+    static int baseInitialized = Integer.parseInt("2");
+}
+
+//编译后删除了PreloadMissing.class，用于测试父类缺失
+class PreloadMissing {
+}
+
+class PreloadMissingSuper extends PreloadMissing {
+}
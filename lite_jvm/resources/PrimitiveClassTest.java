@@ -0,0 +1,52 @@
+//基本类型的Class对象和包装类的Class对象不同
+public class PrimitiveClassTest {
+    static int count;
+
+    public static String intName() {
+        return int.class.getName();
+    }
+
+    public static String voidName() {
+        return void.class.getName();
+    }
+
+    public static boolean intIsPrimitive() {
+        return int.class.isPrimitive();
+    }
+
+    public static boolean integerIsPrimitive() {
+        return Integer.class.isPrimitive();
+    }
+
+    public static boolean distinctFromWrapper() {
+        return int.class != Integer.class;
+    }
+
+    public static String describe(Class<?> c) {
+        return c + " " + c.getModifiers() + " " + (c.getSuperclass() == null) + " "
+                + c.isArray() + " " + c.isInterface() + " " + c.getInterfaces().length;
+    }
+
+    public static String describeLong() {
+        return describe(long.class);
+    }
+
+    public static String describeRunnable() {
+        return describe(Runnable.class);
+    }
+
+    //反射得到的字段类型和int.class是同一个对象
+    public static boolean fieldTypeIsIntClass() throws NoSuchFieldException {
+        return PrimitiveClassTest.class.getDeclaredField("count").getType() == int.class;
+    }
+
+    //基本类型没有字段
+    public static String primitiveFieldMissing() {
+        try {
+            int.class.getDeclaredField("value");
+            return "found";
+        } catch (NoSuchFieldException e) {
+            return e.getMessage();
+        }
+    }
+}
@@ -0,0 +1,12 @@
+import java.io.PrintStream;
+
+//只有System.out/err由虚拟机输出，自己创建的PrintStream执行原来的字节码
+public class PrintStreamTest {
+    public static void print() {
+        PrintStream stream = new PrintStream();
+        stream.println("hidden");
+        stream.flush();
+        System.out.println("shown");
+        System.out.flush();
+    }
+}
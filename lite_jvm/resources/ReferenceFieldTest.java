@@ -0,0 +1,30 @@
+//数组可以赋值给Object、Cloneable和Serializable类型的字段和引用数组元素
+public class ReferenceFieldTest {
+    Object obj;
+    Cloneable cloneable;
+    static java.io.Serializable serializable;
+
+    public static ReferenceFieldTest create(int[] values) {
+        ReferenceFieldTest test = new ReferenceFieldTest();
+        test.obj = values;
+        test.cloneable = values;
+        serializable = values;
+        return test;
+    }
+
+    public static int total(ReferenceFieldTest test) {
+        return ((int[]) test.obj)[0] + ((int[]) test.cloneable).length + ((int[]) serializable)[1];
+    }
+
+    public static void clear(ReferenceFieldTest test) {
+        test.obj = null;
+        test.cloneable = null;
+    }
+
+    public static Object[] wrap(int[] values) {
+        Object[] result = new Object[2];
+        result[0] = values;
+        result[1] = "text";
+        return result;
+    }
+}
@@ -0,0 +1,57 @@
+import java.lang.reflect.Modifier;
+
+//接口、抽象父类和具体子类组成的继承关系，通过Class的native方法遍历
+public class ReflectHierarchyTest {
+    interface Shape {
+        double area();
+    }
+
+    static abstract class AbstractShape implements Shape, Comparable<Shape> {
+        public int compareTo(Shape other) {
+            return Double.compare(area(), other.area());
+        }
+    }
+
+    private static final class Square extends AbstractShape {
+        public double area() {
+            return 4;
+        }
+    }
+
+    static String describe(Class<?> c) {
+        StringBuilder builder = new StringBuilder(c.getName());
+        Class<?> superclass = c.getSuperclass();
+        builder.append(" super=").append(superclass == null ? "null" : superclass.getName());
+        builder.append(" interfaces=[");
+        Class<?>[] interfaces = c.getInterfaces();
+        for (int i = 0; i < interfaces.length; i++) {
+            if (i > 0) {
+                builder.append(',');
+            }
+            builder.append(interfaces[i].getName());
+        }
+        builder.append("] interface=").append(c.isInterface());
+        builder.append(" array=").append(c.isArray());
+        builder.append(" modifiers=").append(c.getModifiers());
+        return builder.toString();
+    }
+
+    //从子类一直走到Object
+    static String chain() {
+        StringBuilder builder = new StringBuilder();
+        for (Class<?> c = new Square().getClass(); c != null; c = c.getSuperclass()) {
+            builder.append(c.getName()).append(';');
+        }
+        return builder.toString();
+    }
+
+    static String hierarchy() {
+        return describe(Square.class) + "\n" + describe(AbstractShape.class) + "\n" + describe(Shape.class)
+                + "\n" + describe(Object.class) + "\n" + describe(Square[].class) + "\n" + describe(int[][].class);
+    }
+
+    static boolean isAbstractPublic(Class<?> c) {
+        int modifiers = c.getModifiers();
+        return Modifier.isAbstract(modifiers) && Modifier.isPublic(modifiers);
+    }
+}
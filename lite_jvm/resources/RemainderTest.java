@@ -0,0 +1,9 @@
+public class RemainderTest {
+    static double drem(double a, double b) {
+        return a % b;
+    }
+
+    static float frem(float a, float b) {
+        return a % b;
+    }
+}
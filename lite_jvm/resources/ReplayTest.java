@@ -0,0 +1,8 @@
+public class ReplayTest {
+    public static int fibonacci(int n) {
+        if (n < 2) {
+            return n;
+        }
+        return fibonacci(n - 1) + fibonacci(n - 2);
+    }
+}
@@ -0,0 +1,41 @@
+public class RethrowTest {
+    private String name;
+
+    int inner() {
+        return name.length();
+    }
+
+    int middle() {
+        return inner();
+    }
+
+    int outer() {
+        try {
+            return middle();
+        } catch (NullPointerException e) {
+            throw e;
+        }
+    }
+
+    static void fail() {
+        throw new IllegalStateException();
+    }
+
+    static void propagate() {
+        fail();
+    }
+
+    static String[] constructedTrace() {
+        try {
+            propagate();
+        } catch (IllegalStateException e) {
+            StackTraceElement[] stackTrace = e.getStackTrace();
+            String[] methodNames = new String[stackTrace.length];
+            for (int i = 0; i < stackTrace.length; i++) {
+                methodNames[i] = stackTrace[i].getMethodName();
+            }
+            return methodNames;
+        }
+        return null;
+    }
+}
@@ -0,0 +1,25 @@
+/**
+ * jsr/ret和跳转目标检查的测试。javac不再生成jsr/ret，编译后替换了各方法的Code：
+ * <pre>
+ * validSubroutine: 0 iconst_5; 1 istore_0; 2 jsr 8; 5 iload_0; 6 ireturn; 7 nop;
+ *                  8 astore_1; 9 iinc 0 10; 12 ret 1              返回15
+ * retPastEnd:      0 goto 6; 3 astore_1; 4 ret 1; 6 jsr 3         返回地址9等于code长度
+ * gotoOutside:     0 goto 100
+ * gotoNegative:    0 nop; 1 goto -4(偏移量-5)
+ * </pre>
+ */
+public class ReturnAddressTest {
+    static int validSubroutine() {
+        return 0;
+    }
+
+    static int retPastEnd() {
+        return 0;
+    }
+
+    static void gotoOutside() {
+    }
+
+    static void gotoNegative() {
+    }
+}
@@ -0,0 +1,27 @@
+import lite.jvm.Assert;
+
+//run_test_class执行的测试类：两个通过、一个失败，helper不是测试方法
+public class SelfTest {
+    public static void testAdd() {
+        Assert.assertEquals(3, 1 + 2);
+        Assert.assertEquals(10L, 4L + 6L);
+    }
+
+    public static void testStrings() {
+        Assert.assertEquals("ab", "a".concat("b"));
+        Assert.assertTrue("abc".startsWith("a"));
+        Assert.assertNull(null);
+    }
+
+    public static void testBroken() {
+        Assert.assertEquals(3, helper());
+    }
+
+    public static int helper() {
+        return 4;
+    }
+
+    public void testInstance() {
+        Assert.fail("instance methods are not tests");
+    }
+}
@@ -0,0 +1,9 @@
+public class ShiftTest {
+    static int iushr(int value, int distance) {
+        return value >>> distance;
+    }
+
+    static long lushr(long value, int distance) {
+        return value >>> distance;
+    }
+}
@@ -0,0 +1,33 @@
+interface StaticInterface {
+    static int foo() {
+        return 42;
+    }
+
+    static int twice(int x) {
+        return foo() * x;
+    }
+}
+
+class StaticParent {
+    static int parentStatic() {
+        return 7;
+    }
+}
+
+class StaticChild extends StaticParent {
+}
+
+public class StaticInterfaceTest {
+    static int callFoo() {
+        return StaticInterface.foo();
+    }
+
+    static int callTwice() {
+        return StaticInterface.twice(3);
+    }
+
+    //编译为invokestatic StaticChild.parentStatic，需要沿父类解析
+    static int callInherited() {
+        return StaticChild.parentStatic();
+    }
+}
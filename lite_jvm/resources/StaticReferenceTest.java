@@ -0,0 +1,41 @@
+public class StaticReferenceTest {
+    static final String[] NAMES = {"a", "b"};
+    static Holder holder;
+    static int finalizedCount;
+
+    static {
+        holder = new Holder(42);
+    }
+
+    static class Holder {
+        int value;
+
+        Holder(int value) {
+            this.value = value;
+        }
+
+        @Override
+        protected void finalize() {
+            finalizedCount++;
+        }
+    }
+
+    //分配大量不被引用的数组，静态字段引用的对象不受影响
+    public static int churn(int times) {
+        int total = 0;
+        for (int i = 0; i < times; i++) {
+            int[] garbage = new int[4];
+            garbage[0] = i;
+            total += garbage.length;
+        }
+        return total;
+    }
+
+    public static String joinNames() {
+        return NAMES[0] + NAMES[1];
+    }
+
+    public static int holderValue() {
+        return holder.value;
+    }
+}
@@ -0,0 +1,20 @@
+public class StaticWideTest {
+    static int before = 1;
+    static long longValue;
+    static double doubleValue;
+    static int after = 2;
+
+    public static long storeLong() {
+        longValue = 0x1122334455667788L;
+        return longValue;
+    }
+
+    public static double storeDouble() {
+        doubleValue = 3.141592653589793;
+        return doubleValue;
+    }
+
+    public static int neighbours() {
+        return before * 10 + after;
+    }
+}
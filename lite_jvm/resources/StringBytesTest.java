@@ -0,0 +1,25 @@
+import java.io.UnsupportedEncodingException;
+
+//byte[]和String之间按编码名称转换
+public class StringBytesTest {
+    public static String roundTrip(String text, String charsetName) throws UnsupportedEncodingException {
+        return new String(text.getBytes(charsetName), charsetName);
+    }
+
+    public static int utf8Length(String text) throws UnsupportedEncodingException {
+        return text.getBytes("UTF-8").length;
+    }
+
+    public static String defaultCharset(String text) {
+        byte[] bytes = text.getBytes();
+        return new String(bytes, 1, bytes.length - 2);
+    }
+
+    public static String fromBytes(byte[] bytes, String charsetName) throws UnsupportedEncodingException {
+        return new String(bytes, 0, bytes.length, charsetName);
+    }
+
+    public static String slice(byte[] bytes, int offset, int length) {
+        return new String(bytes, offset, length);
+    }
+}
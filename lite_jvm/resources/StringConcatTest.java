@@ -0,0 +1,49 @@
+public class StringConcatTest {
+    static class Point {
+        final int x;
+        final int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        @Override
+        public String toString() {
+            return "(" + x + "," + y + ")";
+        }
+    }
+
+    public static String mixed(int x) {
+        Object obj = new Point(x, -x);
+        return "a" + x + 1.5 + obj;
+    }
+
+    public static String primitives(long l, char c, boolean b, float f, Object nothing) {
+        return l + "|" + c + "|" + b + "|" + f + "|" + nothing;
+    }
+
+    //字面量中包含\u0001时javac通过常量参数传入
+    public static String withMarker(String s) {
+        return "tag\u0001" + s;
+    }
+
+    //分别拼接的高低代理组成一个补充平面字符
+    public static String surrogates(char high, char low) {
+        return "" + high + low;
+    }
+
+    //拼接的结果每次都是新对象
+    public static boolean sameInstance(int x) {
+        return ("a" + x) == ("a" + x);
+    }
+
+    public static int repeat(int n) {
+        int length = 0;
+        for (int i = 0; i < n; i++) {
+            String s = "a" + i;
+            length += s.length();
+        }
+        return length;
+    }
+}
@@ -0,0 +1,13 @@
+/**
+ * 方法缺少Code属性的测试。编译后删除了value方法的Code属性，
+ * 访问标志不变，既不是abstract也不是native
+ */
+public class StrippedCodeTest {
+    static int value() {
+        return 1;
+    }
+
+    public static int callValue() {
+        return value();
+    }
+}
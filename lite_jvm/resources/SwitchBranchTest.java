@@ -0,0 +1,88 @@
+//switch后面紧跟跳转指令，switch之前的指令长度不同，对齐填充分别为0到3个字节
+public class SwitchBranchTest {
+    static int pad0(int k) {
+        k += 1000;
+        switch (k) {
+            case 1000: return 0;
+            case 1001: return 1;
+            case 1002: return 2;
+            default: return k < 0 ? -1 : 3;
+        }
+    }
+
+    static int pad1(int k) {
+        int a;
+        switch (-k) {
+            case 0: a = 10; break;
+            case -1: a = 11; break;
+            case -2: a = 12; break;
+            default: a = -1;
+        }
+        if (a > 10) {
+            return a * 2;
+        }
+        return a;
+    }
+
+    static int pad2(int k) {
+        switch (k) {
+            case 0: k = 10; break;
+            case 1: k = 11; break;
+            case 2: k = 12; break;
+            default: k = -1;
+        }
+        return k > 10 ? k * 2 : k;
+    }
+
+    static int pad3(int k) {
+        int a = 7;
+        switch (k) {
+            case 0: a = 10; break;
+            case 1: a = 11; break;
+            case 2: a = 12; break;
+        }
+        return a > 10 ? a * 2 : a;
+    }
+
+    static int loop(int n) {
+        int sum = 0;
+        for (int i = 0; i < n; i++) {
+            switch (i % 4) {
+                case 0: sum += 1; break;
+                case 1: sum += 10; break;
+                case 2: sum += 100; break;
+                default: sum += 1000;
+            }
+        }
+        return sum;
+    }
+
+    static int lookup(int k) {
+        int r;
+        switch (k) {
+            case -100: r = 1; break;
+            case 5: r = 2; break;
+            case 100000: r = 3; break;
+            default: r = 0;
+        }
+        if (r == 0) {
+            return -1;
+        }
+        return r;
+    }
+
+    static int throwing(int k) {
+        try {
+            switch (k) {
+                case 0: throw new IllegalStateException();
+                case 1: return 1;
+                case 2: return 2;
+                default: throw new IllegalArgumentException();
+            }
+        } catch (IllegalStateException e) {
+            return -1;
+        } catch (IllegalArgumentException e) {
+            return -2;
+        }
+    }
+}
@@ -0,0 +1,22 @@
+public class SwitchTest {
+    public static int hash(String s) {
+        return s.hashCode();
+    }
+
+    public static int select(String s) {
+        switch (s) {
+            case "Aa":
+                return 1;
+            case "BB":
+                return 2;
+            case "apple":
+                return 3;
+            case "banana":
+                return 4;
+            case "cherry":
+                return 5;
+            default:
+                return -1;
+        }
+    }
+}
@@ -0,0 +1,9 @@
+public class TracingTest {
+    static int a(Object o) {
+        return b(o) + 1;
+    }
+
+    static int b(Object o) {
+        return o.hashCode() * 0;
+    }
+}
@@ -0,0 +1,6 @@
+//测试类转换：转换函数去掉类的ACC_FINAL标志
+public final class TransformTarget {
+    public static int value() {
+        return 1;
+    }
+}
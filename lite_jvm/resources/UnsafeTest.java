@@ -0,0 +1,32 @@
+import java.util.concurrent.atomic.AtomicInteger;
+import java.util.concurrent.atomic.AtomicLong;
+import java.util.concurrent.atomic.AtomicReference;
+
+public class UnsafeTest {
+    static int atomicIncrement() {
+        AtomicInteger counter = new AtomicInteger(5);
+        counter.incrementAndGet();
+        counter.getAndAdd(10);
+        return counter.incrementAndGet();
+    }
+
+    static long atomicLong() {
+        AtomicLong counter = new AtomicLong(1L << 40);
+        counter.addAndGet(2);
+        return counter.get();
+    }
+
+    static boolean compareAndSet() {
+        AtomicInteger value = new AtomicInteger(1);
+        boolean first = value.compareAndSet(1, 2);
+        boolean second = value.compareAndSet(1, 3);
+        return first && !second && value.get() == 2;
+    }
+
+    static String atomicReference() {
+        AtomicReference<String> reference = new AtomicReference<>("a");
+        reference.compareAndSet("a", "b");
+        reference.compareAndSet("a", "c");
+        return reference.get();
+    }
+}
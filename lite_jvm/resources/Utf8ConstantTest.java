@@ -0,0 +1,22 @@
+//常量池中的补充平面字符按modified UTF-8编码为两个3字节的代理，NUL编码为0xC0 0x80
+public class Utf8ConstantTest {
+    public static String emoji() {
+        return "emoji 😀!";
+    }
+
+    public static String withNul() {
+        return "a\0b";
+    }
+
+    public static int emojiLength() {
+        return emoji().length();
+    }
+
+    public static int highSurrogate() {
+        return emoji().charAt(6);
+    }
+
+    public static int lowSurrogate() {
+        return emoji().charAt(7);
+    }
+}
@@ -0,0 +1,25 @@
+//可变参数方法，测试按方法名调用时打包参数
+public class VarargsTest {
+    public static int foo(int... values) {
+        int sum = 0;
+        for (int value : values) {
+            sum += value;
+        }
+        return sum;
+    }
+
+    public static String join(String separator, Object... parts) {
+        StringBuilder builder = new StringBuilder();
+        for (int i = 0; i < parts.length; i++) {
+            if (i > 0) {
+                builder.append(separator);
+            }
+            builder.append(parts[i]);
+        }
+        return builder.toString();
+    }
+
+    public int count(String... names) {
+        return names.length;
+    }
+}
@@ -0,0 +1,22 @@
+public class VerifyTest {
+    Object value;
+    static Object staticValue;
+
+    //The aconst_null in the following methods is patched to iconst_0 in the
+    //shipped class file, so an int is stored into an Object field or array.
+    public static void putFieldInt(VerifyTest test) {
+        test.value = null;
+    }
+
+    public static void putStaticInt() {
+        staticValue = null;
+    }
+
+    public static void storeIntIntoObjectArray(Object[] array) {
+        array[0] = null;
+    }
+
+    public static Object readValue(VerifyTest test) {
+        return test.value;
+    }
+}
@@ -0,0 +1,5 @@
+public class VersionedHelper {
+    public static int base() {
+        return 100;
+    }
+}
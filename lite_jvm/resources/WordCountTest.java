@@ -0,0 +1,33 @@
+//long和double参数各占两个字，max_stack和invokeinterface的count都按字计算
+interface WordCountSink {
+    void accept(long a, double b, long c, double d);
+}
+
+public class WordCountTest implements WordCountSink {
+    static double total;
+
+    public void accept(long a, double b, long c, double d) {
+        total = a + b + c + d;
+    }
+
+    static void record(long a, double b, long c, double d) {
+        total = a * b * c * d;
+    }
+
+    static double callStatic() {
+        record(1L, 2.0, 3L, 4.0);
+        return total;
+    }
+
+    static double callInterface() {
+        WordCountSink sink = new WordCountTest();
+        sink.accept(1L, 2.0, 3L, 4.0);
+        return total;
+    }
+
+    static long[] duplicate(long[] values) {
+        values[0] += 5L;
+        long last = values[1]++;
+        return new long[] {values[0], values[1], last};
+    }
+}
@@ -0,0 +1,12 @@
+public class Versioned {
+    static int calls;
+
+    public static int version() {
+        calls++;
+        return VersionedHelper.base() + 2;
+    }
+
+    public int instanceVersion() {
+        return 2;
+    }
+}
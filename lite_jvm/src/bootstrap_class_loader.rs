@@ -4,6 +4,7 @@ use crate::jvm_error::{VmError, VmExecResult};
 use crate::loaded_class::ClassRef;
 use class_file_reader::class_file::ClassFile;
 use class_file_reader::class_file_reader::read_buffer;
+use indexmap::IndexSet;
 use std::collections::HashMap;
 
 ///实现BootstrapClassLoader。
@@ -34,22 +35,53 @@ pub trait ClassLoader<'a> {
     fn registry_class(&mut self, class: ClassRef<'a>);
 }
 
+/// 类文件的转换函数，参数是类名和读取到的字节码，返回解析时使用的字节码
+pub type ClassTransformer = Box<dyn Fn(&str, Vec<u8>) -> Vec<u8>>;
+
 #[derive(Default)]
 pub struct BootstrapClassLoader<'a> {
     class_finder: ClassFinder,
     loaded_class: HashMap<String, ClassRef<'a>>,
+    class_transformer: Option<ClassTransformer>,
+    //正在加载父类和接口的类，按开始加载的顺序排列
+    loading: IndexSet<String>,
 }
 impl<'a> BootstrapClassLoader<'a> {
     pub fn exist(&self, class_name: &str) -> bool {
-        self.loaded_class.get(class_name).is_some()
+        self.loaded_class.contains_key(class_name)
     }
     pub fn find_loaded_class(&mut self, class_name: &str) -> Option<&mut ClassRef<'a>> {
         self.loaded_class.get_mut(class_name)
     }
 
+    pub fn loaded_classes(&self) -> impl Iterator<Item = ClassRef<'a>> + '_ {
+        self.loaded_class.values().copied()
+    }
+
     pub fn add_class_path(&mut self, path: Box<dyn ClassPath>) {
         self.class_finder.class_paths.push(path);
     }
+
+    pub fn set_class_transformer(&mut self, transformer: Option<ClassTransformer>) {
+        self.class_transformer = transformer;
+    }
+
+    /// 标记开始加载该类，直到调用end_loading。父类或接口的加载又回到正在加载的类时，
+    /// 返回从该类开始的循环，例如[A, B, A]
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.3.5
+    pub(crate) fn begin_loading(&mut self, class_name: &str) -> VmExecResult<()> {
+        if let Some(index) = self.loading.get_index_of(class_name) {
+            let mut chain: Vec<String> = self.loading.iter().skip(index).cloned().collect();
+            chain.push(class_name.to_string());
+            return Err(VmError::ClassCircularity(chain));
+        }
+        self.loading.insert(class_name.to_string());
+        Ok(())
+    }
+
+    pub(crate) fn end_loading(&mut self, class_name: &str) {
+        self.loading.shift_remove(class_name);
+    }
 }
 
 impl<'a> ClassLoader<'a> for BootstrapClassLoader<'a> {
@@ -57,10 +89,14 @@ impl<'a> ClassLoader<'a> for BootstrapClassLoader<'a> {
         match self.loaded_class.get(name) {
             Some(v) => Ok(AlreadyLoaded(v)),
             None => {
-                let new_class_file = self.class_finder.find_class(name).map(|bytes| {
-                    read_buffer(&bytes).map_err(|e| VmError::ReadClassBytesError(e.to_string()))
-                })?;
-                Ok(NewLoaded(new_class_file?))
+                let mut bytes = self.class_finder.find_class(name)?;
+                //在解析之前交给转换函数，例如插入日志等字节码改写
+                if let Some(transformer) = &self.class_transformer {
+                    bytes = transformer(name, bytes);
+                }
+                let new_class_file =
+                    read_buffer(&bytes).map_err(|e| VmError::ReadClassBytesError(e.to_string()))?;
+                Ok(NewLoaded(new_class_file))
             }
         }
     }
@@ -1,10 +1,13 @@
 /// 设计上需要定义一个类查找其
+use crate::embedded_core::EmbeddedCorePath;
 use crate::jvm_error::{VmError, VmExecResult};
 use std::cell::RefCell;
+#[cfg(any(test, feature = "test-support"))]
+use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::fs;
 use std::fs::File;
-use std::io::{BufReader, Read};
+use std::io::{BufReader, Read, Seek};
 use std::path::PathBuf;
 use zip::result::ZipError;
 use zip::ZipArchive;
@@ -15,12 +18,16 @@ pub struct ClassFinder {
 }
 impl ClassFinder {
     //查找class,如果查找失败则返回ClassNotFoundException
+    //所有类路径都找不到时最后查找内置的核心类
     pub fn find_class(&self, name: &str) -> VmExecResult<Vec<u8>> {
         for class_path in &self.class_paths {
             if let Some(v) = class_path.find_class(name)? {
                 return Ok(v);
             }
         }
+        if let Some(v) = EmbeddedCorePath.find_class(name)? {
+            return Ok(v);
+        }
         Err(VmError::ClassNotFoundException(String::from(name)))
     }
 }
@@ -111,22 +118,106 @@ impl JarFileClassPath {
 impl ClassPath for JarFileClassPath {
     fn find_class(&self, class_name: &str) -> VmExecResult<Option<Vec<u8>>> {
         let class_file_name = class_name.to_string() + ".class";
-        match self.zip.borrow_mut().by_name(&class_file_name) {
-            Ok(mut zip_file) => {
-                let mut buffer: Vec<u8> = Vec::with_capacity(zip_file.size() as usize);
-                zip_file
-                    .read_to_end(&mut buffer)
-                    .map_err(|e| VmError::ReadClassBytesError(e.to_string()))?;
-                Ok(Some(buffer))
-            }
-            Err(ZipError::FileNotFound) => Ok(None),
-            Err(e) => Err(VmError::ReadClassBytesError(e.to_string())),
+        read_zip_entry(&mut self.zip.borrow_mut(), &class_file_name)
+    }
+}
+
+fn read_zip_entry<R: Read + Seek>(
+    zip: &mut ZipArchive<R>,
+    entry_name: &str,
+) -> VmExecResult<Option<Vec<u8>>> {
+    match zip.by_name(entry_name) {
+        Ok(mut zip_file) => {
+            let mut buffer: Vec<u8> = Vec::with_capacity(zip_file.size() as usize);
+            zip_file
+                .read_to_end(&mut buffer)
+                .map_err(|e| VmError::ReadClassBytesError(e.to_string()))?;
+            Ok(Some(buffer))
+        }
+        Err(ZipError::FileNotFound) => Ok(None),
+        Err(e) => Err(VmError::ReadClassBytesError(e.to_string())),
+    }
+}
+
+//JDK 9之后不再提供rt.jar，平台类按模块打包在jmods目录下的.jmod文件中。
+//jmod文件以4字节的magic(JM 0x01 0x00)开头，后面是zip内容，类文件位于classes/目录下。
+//https://openjdk.org/jeps/261
+const JMOD_MAGIC: [u8; 4] = [0x4a, 0x4d, 0x01, 0x00];
+
+pub struct JmodClassPath {
+    jmod_file_path: String,
+    zip: RefCell<ZipArchive<BufReader<File>>>,
+}
+
+impl Debug for JmodClassPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "JmodClassPath => {}", &self.jmod_file_path)
+    }
+}
+
+impl JmodClassPath {
+    pub fn new(path: &str) -> VmExecResult<JmodClassPath> {
+        let jmod_file_path = if let Ok(abs_path) = fs::canonicalize(PathBuf::from(path)) {
+            abs_path
+        } else {
+            return Err(VmError::JarFileNotExist(path.to_string()));
+        };
+        let mut file =
+            File::open(&jmod_file_path).map_err(|e| VmError::ReadJarFileError(e.to_string()))?;
+        let mut magic = [0u8; 4];
+        file.read_exact(&mut magic)
+            .map_err(|e| VmError::ReadJarFileError(e.to_string()))?;
+        if magic != JMOD_MAGIC {
+            return Err(VmError::ReadJarFileError(format!(
+                "{} is not a jmod file",
+                jmod_file_path.to_string_lossy()
+            )));
         }
+        //zip中的偏移量不包含magic，ZipArchive会根据中央目录的位置修正
+        let zip = ZipArchive::new(BufReader::new(file))
+            .map_err(|e| VmError::ReadJarFileError(e.to_string()))?;
+        Ok(Self {
+            jmod_file_path: jmod_file_path.to_string_lossy().to_string(),
+            zip: RefCell::new(zip),
+        })
+    }
+}
+
+impl ClassPath for JmodClassPath {
+    fn find_class(&self, class_name: &str) -> VmExecResult<Option<Vec<u8>>> {
+        let class_file_name = format!("classes/{}.class", class_name);
+        read_zip_entry(&mut self.zip.borrow_mut(), &class_file_name)
+    }
+}
+//内存中的类，例如测试中用ClassFileBuilder生成的字节码
+#[cfg(any(test, feature = "test-support"))]
+#[derive(Default)]
+pub struct MemoryClassPath {
+    classes: HashMap<String, Vec<u8>>,
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl MemoryClassPath {
+    pub fn new() -> MemoryClassPath {
+        MemoryClassPath::default()
+    }
+
+    pub fn add_class(&mut self, class_name: &str, bytes: Vec<u8>) -> &mut Self {
+        self.classes.insert(class_name.to_string(), bytes);
+        self
+    }
+}
+
+#[cfg(any(test, feature = "test-support"))]
+impl ClassPath for MemoryClassPath {
+    fn find_class(&self, class_name: &str) -> VmExecResult<Option<Vec<u8>>> {
+        Ok(self.classes.get(class_name).cloned())
     }
 }
+
 #[allow(unused_imports)]
 mod tests {
-    use crate::class_finder::{ClassPath, FileSystemClassPath, JarFileClassPath};
+    use crate::class_finder::{ClassPath, FileSystemClassPath, JarFileClassPath, JmodClassPath};
     use class_file_reader::class_file_reader::read_buffer;
 
     #[test]
@@ -150,4 +241,18 @@ mod tests {
         let not_exist = result.find_class("Hello").unwrap();
         assert!(not_exist.is_none());
     }
+
+    #[test]
+    fn test_jmod_class_finding() {
+        let result = JmodClassPath::new("./resources/java.base.jmod").unwrap();
+        let object_file = result.find_class("java/lang/Object").unwrap();
+        assert!(object_file.is_some());
+        let parsed_files = read_buffer(&object_file.unwrap()).unwrap();
+        assert_eq!(parsed_files.this_class_name, "java/lang/Object");
+        let not_exist = result.find_class("java/lang/String").unwrap();
+        assert!(not_exist.is_none());
+
+        //jar包没有jmod的magic
+        assert!(JmodClassPath::new("./resources/rt.jar").is_err());
+    }
 }
@@ -0,0 +1,183 @@
+//! 基本类型转换指令的实现，按照规范逐条处理NaN、无穷大和溢出，不依赖`as`的默认行为。
+//! Rust的f32/f64运算没有扩展精度，结果与FP-strict一致，ACC_STRICT方法不需要单独处理。
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.4
+
+//拓宽转换，不会丢失数值大小
+pub(crate) fn i2l(value: i32) -> i64 {
+    i64::from(value)
+}
+
+pub(crate) fn i2d(value: i32) -> f64 {
+    f64::from(value)
+}
+
+pub(crate) fn f2d(value: f32) -> f64 {
+    f64::from(value)
+}
+
+//int/long转为浮点数时可能丢失精度，使用IEEE 754 round to nearest
+//https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2f
+pub(crate) fn i2f(value: i32) -> f32 {
+    value as f32
+}
+
+pub(crate) fn l2f(value: i64) -> f32 {
+    value as f32
+}
+
+pub(crate) fn l2d(value: i64) -> f64 {
+    value as f64
+}
+
+//窄化转换只保留低32位
+pub(crate) fn l2i(value: i64) -> i32 {
+    value as i32
+}
+
+//double转float使用round to nearest，超出范围得到无穷大，NaN保持NaN
+//https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.d2f
+pub(crate) fn d2f(value: f64) -> f32 {
+    value as f32
+}
+
+//浮点数转整数：NaN得到0，向零取整，超出范围时取目标类型的最大或最小值
+//https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.d2i
+macro_rules! generate_float_to_integer {
+    ($name:ident, $from:ty, $to:ty) => {
+        pub(crate) fn $name(value: $from) -> $to {
+            if value.is_nan() {
+                0
+            } else if value >= <$to>::MAX as $from {
+                <$to>::MAX
+            } else if value <= <$to>::MIN as $from {
+                <$to>::MIN
+            } else {
+                value.trunc() as $to
+            }
+        }
+    };
+}
+
+generate_float_to_integer!(f2i, f32, i32);
+generate_float_to_integer!(f2l, f32, i64);
+generate_float_to_integer!(d2i, f64, i32);
+generate_float_to_integer!(d2l, f64, i64);
+
+mod tests {
+
+    #[test]
+    fn test_conversion_opcodes() {
+        use crate::jvm_values::Value::{Double, Float, Int, Long};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //(方法名, 描述符, 参数, 规范规定的结果)
+        let cases = [
+            //NaN得到0，±无穷大和超出范围的值取最大或最小值，其余向零取整
+            ("d2i", "(D)I", Double(f64::NAN), Int(0)),
+            ("d2i", "(D)I", Double(f64::INFINITY), Int(i32::MAX)),
+            ("d2i", "(D)I", Double(f64::NEG_INFINITY), Int(i32::MIN)),
+            ("d2i", "(D)I", Double(2147483647.5), Int(2147483647)),
+            ("d2i", "(D)I", Double(-2147483648.5), Int(-2147483648)),
+            ("d2i", "(D)I", Double(1e12), Int(i32::MAX)),
+            ("d2i", "(D)I", Double(-1e12), Int(i32::MIN)),
+            ("d2i", "(D)I", Double(-1.9), Int(-1)),
+            ("d2i", "(D)I", Double(-0.0), Int(0)),
+            ("d2l", "(D)J", Double(f64::NAN), Long(0)),
+            ("d2l", "(D)J", Double(f64::INFINITY), Long(i64::MAX)),
+            ("d2l", "(D)J", Double(f64::NEG_INFINITY), Long(i64::MIN)),
+            ("d2l", "(D)J", Double(9223372036854775808.0), Long(i64::MAX)),
+            ("d2l", "(D)J", Double(-1e19), Long(i64::MIN)),
+            (
+                "d2l",
+                "(D)J",
+                Double(9007199254740992.0),
+                Long(9007199254740992),
+            ),
+            ("d2l", "(D)J", Double(1.5), Long(1)),
+            ("f2i", "(F)I", Float(f32::NAN), Int(0)),
+            ("f2i", "(F)I", Float(f32::INFINITY), Int(i32::MAX)),
+            ("f2i", "(F)I", Float(f32::NEG_INFINITY), Int(i32::MIN)),
+            ("f2i", "(F)I", Float(3e9), Int(i32::MAX)),
+            ("f2i", "(F)I", Float(-3e9), Int(i32::MIN)),
+            ("f2i", "(F)I", Float(-2.5), Int(-2)),
+            ("f2i", "(F)I", Float(-0.0), Int(0)),
+            ("f2l", "(F)J", Float(f32::NAN), Long(0)),
+            ("f2l", "(F)J", Float(f32::INFINITY), Long(i64::MAX)),
+            ("f2l", "(F)J", Float(f32::NEG_INFINITY), Long(i64::MIN)),
+            ("f2l", "(F)J", Float(1e19), Long(i64::MAX)),
+            ("f2l", "(F)J", Float(-1e19), Long(i64::MIN)),
+            ("f2l", "(F)J", Float(2.5), Long(2)),
+            //d2f超出范围得到无穷大，过小的值得到带符号的0，NaN保持NaN
+            ("d2f", "(D)F", Double(f64::NAN), Float(f32::NAN)),
+            ("d2f", "(D)F", Double(f64::INFINITY), Float(f32::INFINITY)),
+            (
+                "d2f",
+                "(D)F",
+                Double(f64::NEG_INFINITY),
+                Float(f32::NEG_INFINITY),
+            ),
+            ("d2f", "(D)F", Double(f64::MAX), Float(f32::INFINITY)),
+            ("d2f", "(D)F", Double(-1e39), Float(f32::NEG_INFINITY)),
+            ("d2f", "(D)F", Double(1e-50), Float(0.0)),
+            ("d2f", "(D)F", Double(-1e-50), Float(-0.0)),
+            ("d2f", "(D)F", Double(16777217.0), Float(16777216.0)),
+            //拓宽转换保留NaN、无穷大和-0.0
+            ("f2d", "(F)D", Float(f32::NAN), Double(f64::NAN)),
+            (
+                "f2d",
+                "(F)D",
+                Float(f32::NEG_INFINITY),
+                Double(f64::NEG_INFINITY),
+            ),
+            ("f2d", "(F)D", Float(-0.0), Double(-0.0)),
+            ("f2d", "(F)D", Float(0.1), Double(0.10000000149011612)),
+            ("i2l", "(I)J", Int(i32::MIN), Long(-2147483648)),
+            ("i2d", "(I)D", Int(i32::MIN), Double(-2147483648.0)),
+            //int/long转为浮点数按round to nearest
+            ("i2f", "(I)F", Int(16777217), Float(16777216.0)),
+            ("i2f", "(I)F", Int(i32::MAX), Float(2147483648.0)),
+            ("l2f", "(J)F", Long(16777217), Float(16777216.0)),
+            ("l2f", "(J)F", Long(i64::MAX), Float(9223372036854775808.0)),
+            (
+                "l2d",
+                "(J)D",
+                Long(9007199254740993),
+                Double(9007199254740992.0),
+            ),
+            (
+                "l2d",
+                "(J)D",
+                Long(i64::MIN),
+                Double(-9223372036854775808.0),
+            ),
+            //l2i只保留低32位
+            ("l2i", "(J)I", Long(2147483648), Int(i32::MIN)),
+            ("l2i", "(J)I", Long(0x1_0000_0005), Int(5)),
+            ("l2i", "(J)I", Long(-1), Int(-1)),
+        ];
+        for (method_name, descriptor, arg, expected) in cases {
+            let result = vm
+                .call(
+                    call_stack,
+                    "ConversionTest",
+                    method_name,
+                    descriptor,
+                    None,
+                    vec![arg.clone()],
+                )
+                .unwrap()
+                .unwrap();
+            //比较位模式，区分NaN和±0.0
+            assert!(
+                result.bitwise_eq(&expected),
+                "{}({:?}) = {:?}, expected {:?}",
+                method_name,
+                arg,
+                result,
+                expected
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+//! 动态计算常量(CONSTANT_Dynamic)。ldc时调用引导方法得到常量值，结果按常量池项缓存，引导方法只调用一次。
+//! ConstantBootstraps依赖java.lang.invoke的内部实现，其中的引导方法直接在这里计算。
+//! https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-5.html#jvms-5.4.3.6
+//! https://docs.oracle.com/en/java/javase/11/docs/api/java.base/java/lang/invoke/ConstantBootstraps.html
+use crate::java_exception::MethodCallError;
+use crate::jvm_error::VmError;
+use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+use crate::loaded_class::ClassRef;
+use crate::runtime_attribute_info::BootstrapMethod;
+use crate::runtime_constant_pool::{MethodHandlerKind, RuntimeConstantPoolEntry};
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+
+pub(crate) const CONSTANT_BOOTSTRAPS: &str = "java/lang/invoke/ConstantBootstraps";
+
+//字段描述符对应的Class名称，基本类型使用int、long等名称
+fn type_class_name(descriptor: &str) -> &str {
+    match descriptor {
+        "B" => "byte",
+        "C" => "char",
+        "D" => "double",
+        "F" => "float",
+        "I" => "int",
+        "J" => "long",
+        "S" => "short",
+        "Z" => "boolean",
+        "V" => "void",
+        descriptor if descriptor.starts_with('L') => &descriptor[1..descriptor.len() - 1],
+        descriptor => descriptor,
+    }
+}
+
+fn is_primitive(descriptor: &str) -> bool {
+    !descriptor.starts_with('L') && !descriptor.starts_with('[')
+}
+
+//常量类型对应的Class对象，基本类型不经过类加载器
+fn type_class_object<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_ref: ClassRef<'a>,
+    descriptor: &str,
+) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+    let class_name = type_class_name(descriptor);
+    if is_primitive(descriptor) {
+        let class_object = vm.get_primitive_class_object(call_stack, class_name)?;
+        Ok(class_object.ok_or_else(|| VmError::InvalidDescriptor(descriptor.to_string()))?)
+    } else {
+        vm.resolve_class_object(call_stack, class_ref, class_name)
+    }
+}
+
+/// 调用引导方法计算常量值。引导方法的参数依次是Lookup、常量名称、常量类型和静态参数
+pub(crate) fn bootstrap<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_ref: ClassRef<'a>,
+    bootstrap_method: &BootstrapMethod,
+    name: &str,
+    descriptor: &str,
+) -> Result<Value<'a>, MethodCallError<'a>> {
+    if bootstrap_method.kind != MethodHandlerKind::InvokeStatic {
+        return Err(VmError::LinkageError(format!(
+            "unsupported bootstrap method kind {:?} for dynamic constant {}",
+            bootstrap_method.kind, name
+        ))
+        .into());
+    }
+    let mut static_args = Vec::with_capacity(bootstrap_method.args.len());
+    for index in &bootstrap_method.args {
+        static_args.push(static_argument(vm, call_stack, class_ref, *index)?);
+    }
+    if bootstrap_method.class_name == CONSTANT_BOOTSTRAPS {
+        return constant_bootstraps(
+            vm,
+            call_stack,
+            class_ref,
+            bootstrap_method,
+            name,
+            descriptor,
+            static_args,
+        );
+    }
+    let bootstrap_class_ref =
+        vm.resolve_class(call_stack, class_ref, &bootstrap_method.class_name)?;
+    let method_ref = bootstrap_class_ref.get_method(
+        &bootstrap_method.method_name,
+        &bootstrap_method.method_descriptor,
+    )?;
+    let name_object = vm.new_java_lang_string_object(call_stack, name)?;
+    let type_object = type_class_object(vm, call_stack, class_ref, descriptor)?;
+    let lookup = vm.new_java_lang_invoke_lookup(call_stack, class_ref)?;
+    let mut args = vec![
+        Value::ObjectRef(lookup),
+        Value::ObjectRef(name_object),
+        Value::ObjectRef(type_object),
+    ];
+    args.extend(static_args);
+    let result = vm
+        .invoke_method(
+            call_stack,
+            bootstrap_class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            args,
+        )?
+        .unwrap_or(Value::Null);
+    //引导方法返回的基本类型常量是包装类对象，需要拆箱
+    match result {
+        Value::ObjectRef(object_ref) if is_primitive(descriptor) => {
+            Ok(object_ref.get_field_by_name("value")?)
+        }
+        value => Ok(value),
+    }
+}
+
+//静态参数按ldc的规则解析为值
+fn static_argument<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_ref: ClassRef<'a>,
+    index: u16,
+) -> Result<Value<'a>, MethodCallError<'a>> {
+    let value = match class_ref.constant_pool.get(index)? {
+        RuntimeConstantPoolEntry::Integer(v) => Value::Int(*v),
+        RuntimeConstantPoolEntry::Float(v) => Value::Float(*v),
+        RuntimeConstantPoolEntry::Long(v) => Value::Long(*v),
+        RuntimeConstantPoolEntry::Double(v) => Value::Double(*v),
+        RuntimeConstantPoolEntry::StringReference(v) => {
+            Value::ObjectRef(vm.new_java_lang_string_object(call_stack, v)?)
+        }
+        RuntimeConstantPoolEntry::ClassReference(v) => {
+            Value::ObjectRef(vm.resolve_class_object(call_stack, class_ref, v)?)
+        }
+        RuntimeConstantPoolEntry::MethodHandler(kind, class_name, name, descriptor) => {
+            Value::ObjectRef(vm.new_java_lang_invoke_method_handler(
+                call_stack, kind, class_name, name, descriptor,
+            )?)
+        }
+        RuntimeConstantPoolEntry::Dynamic(..) => {
+            vm.resolve_dynamic_constant(call_stack, class_ref, index)?
+        }
+        other => {
+            return Err(VmError::LinkageError(format!(
+                "unsupported bootstrap method argument {}",
+                other
+            ))
+            .into())
+        }
+    };
+    Ok(value)
+}
+
+fn constant_bootstraps<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    class_ref: ClassRef<'a>,
+    bootstrap_method: &BootstrapMethod,
+    name: &str,
+    descriptor: &str,
+    static_args: Vec<Value<'a>>,
+) -> Result<Value<'a>, MethodCallError<'a>> {
+    match bootstrap_method.method_name.as_str() {
+        "nullConstant" => {
+            if is_primitive(descriptor) {
+                let exception = vm.new_exception(
+                    call_stack,
+                    "java/lang/IllegalArgumentException",
+                    &format!("not reference: {}", type_class_name(descriptor)),
+                )?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+            Ok(Value::Null)
+        }
+        //常量名称是基本类型的描述符，例如I
+        "primitiveClass" => {
+            let class_object = type_class_object(vm, call_stack, class_ref, name)?;
+            Ok(Value::ObjectRef(class_object))
+        }
+        //没有静态参数时，字段声明在常量类型中
+        "getStaticFinal" => {
+            let declaring_class = match static_args.first() {
+                Some(Value::ObjectRef(class_object)) => vm
+                    .get_class_ref_of_class_object(call_stack, *class_object)?
+                    .name
+                    .clone(),
+                _ => type_class_name(descriptor).to_string(),
+            };
+            let value = vm.get_static_field_by_class_name(call_stack, &declaring_class, name)?;
+            Ok(value.cloned().ok_or_else(|| {
+                VmError::FieldNotFoundException(format!("{}.{}", declaring_class, name))
+            })?)
+        }
+        method_name => Err(VmError::LinkageError(format!(
+            "unsupported bootstrap method {}.{}",
+            CONSTANT_BOOTSTRAPS, method_name
+        ))
+        .into()),
+    }
+}
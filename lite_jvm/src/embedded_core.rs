@@ -0,0 +1,109 @@
+//! 内置的最小核心类库，没有rt.jar或jmod时也能运行简单的程序。
+//! 类文件由core/compile.sh根据core/src中的Java源码生成，编译时通过include_bytes!嵌入。
+//! 这些类只实现了常用的方法，BootstrapClassLoader在所有ClassPath都找不到时才会使用。
+use crate::class_finder::ClassPath;
+use crate::jvm_error::VmExecResult;
+
+macro_rules! embedded_classes {
+    ($($name:literal,)*) => {
+        &[$(($name, include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/core/classes/", $name, ".class")))),*]
+    };
+}
+
+const EMBEDDED_CLASSES: &[(&str, &[u8])] = embedded_classes!(
+    "java/io/PrintStream",
+    "java/io/Serializable",
+    "java/lang/AbstractMethodError",
+    "java/lang/ArithmeticException",
+    "java/lang/ArrayIndexOutOfBoundsException",
+    "java/lang/ArrayStoreException",
+    "java/lang/AssertionError",
+    "java/lang/Boolean",
+    "java/lang/Byte",
+    "java/lang/Character",
+    "java/lang/Class",
+    "java/lang/ClassCastException",
+    "java/lang/ClassCircularityError",
+    "java/lang/ClassNotFoundException",
+    "java/lang/CloneNotSupportedException",
+    "java/lang/Cloneable",
+    "java/lang/Double",
+    "java/lang/Error",
+    "java/lang/Exception",
+    "java/lang/ExceptionInInitializerError",
+    "java/lang/Float",
+    "java/lang/IllegalArgumentException",
+    "java/lang/IllegalStateException",
+    "java/lang/IncompatibleClassChangeError",
+    "java/lang/IndexOutOfBoundsException",
+    "java/lang/Integer",
+    "java/lang/InterruptedException",
+    "java/lang/LinkageError",
+    "java/lang/Long",
+    "java/lang/Math",
+    "java/lang/NegativeArraySizeException",
+    "java/lang/NoClassDefFoundError",
+    "java/lang/NoSuchFieldError",
+    "java/lang/NoSuchFieldException",
+    "java/lang/NoSuchMethodError",
+    "java/lang/NoSuchMethodException",
+    "java/lang/NullPointerException",
+    "java/lang/Number",
+    "java/lang/NumberFormatException",
+    "java/lang/Object",
+    "java/lang/OutOfMemoryError",
+    "java/lang/ReflectiveOperationException",
+    "java/lang/RuntimeException",
+    "java/lang/Short",
+    "java/lang/StackOverflowError",
+    "java/lang/StackTraceElement",
+    "java/lang/String",
+    "java/lang/StringBuilder",
+    "java/lang/StringIndexOutOfBoundsException",
+    "java/lang/System",
+    "java/lang/Throwable",
+    "java/lang/UnsatisfiedLinkError",
+    "java/lang/UnsupportedOperationException",
+    "java/lang/VerifyError",
+    "java/lang/VirtualMachineError",
+    "java/lang/invoke/MethodHandles",
+    "java/lang/invoke/MethodHandles$Lookup",
+    "lite/jvm/Assert",
+);
+
+#[derive(Debug, Default)]
+pub struct EmbeddedCorePath;
+
+impl EmbeddedCorePath {
+    //内置的所有类名
+    pub fn class_names() -> impl Iterator<Item = &'static str> {
+        EMBEDDED_CLASSES.iter().map(|(name, _)| *name)
+    }
+}
+
+impl ClassPath for EmbeddedCorePath {
+    fn find_class(&self, class_name: &str) -> VmExecResult<Option<Vec<u8>>> {
+        Ok(EMBEDDED_CLASSES
+            .iter()
+            .find(|(name, _)| *name == class_name)
+            .map(|(_, bytes)| bytes.to_vec()))
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_embedded_core_class_finding() {
+        use crate::class_finder::ClassPath;
+        use crate::embedded_core::EmbeddedCorePath;
+        use class_file_reader::class_file_reader::read_buffer;
+        for class_name in EmbeddedCorePath::class_names() {
+            let bytes = EmbeddedCorePath.find_class(class_name).unwrap().unwrap();
+            let class_file = read_buffer(&bytes).unwrap();
+            assert_eq!(class_file.this_class_name, class_name);
+        }
+        assert!(EmbeddedCorePath
+            .find_class("java/util/ArrayList")
+            .unwrap()
+            .is_none());
+    }
+}
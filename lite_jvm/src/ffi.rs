@@ -0,0 +1,372 @@
+//! 供C/C++宿主程序嵌入虚拟机的C接口，声明见include/lite_jvm.h。
+//! 每个LiteJvm持有一个虚拟机和一个调用栈，同一个实例不能在多个线程中同时使用。
+//! 函数返回状态码，失败时的错误信息（包括格式化后的Java异常）通过litejvm_last_error_message读取。
+//! Rust代码中的panic在边界处捕获，不会展开到C代码中
+use crate::java_exception::MethodCallError;
+use crate::jvm_values::{PrimaryType, Value, ValueType};
+use crate::runtime_method_info::MethodDescriptor;
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+
+pub const LITEJVM_OK: c_int = 0;
+/// 空指针、非UTF-8字符串或者描述符和参数不一致
+pub const LITEJVM_INVALID_ARGUMENT: c_int = -1;
+/// 方法抛出了没有被捕获的Java异常
+pub const LITEJVM_EXCEPTION: c_int = -2;
+/// 类加载失败、找不到方法等虚拟机内部错误
+pub const LITEJVM_ERROR: c_int = -3;
+pub const LITEJVM_PANIC: c_int = -4;
+
+pub struct LiteJvm {
+    //调用栈引用虚拟机中的数据，先于虚拟机drop
+    call_stack: CallStack<'static>,
+    vm: VirtualMachine<'static>,
+    last_error: Option<CString>,
+}
+
+type FfiResult = Result<(), (c_int, String)>;
+
+fn invalid_argument(message: impl Into<String>) -> (c_int, String) {
+    (LITEJVM_INVALID_ARGUMENT, message.into())
+}
+
+fn panic_message(payload: Box<dyn std::any::Any + Send>) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string());
+    format!("panicked: {}", message)
+}
+
+//清除上一次的错误，执行f并记录新的错误信息
+fn with_vm(vm: *mut LiteJvm, f: impl FnOnce(&mut LiteJvm) -> FfiResult) -> c_int {
+    let Some(jvm) = (unsafe { vm.as_mut() }) else {
+        return LITEJVM_INVALID_ARGUMENT;
+    };
+    jvm.last_error = None;
+    let (status, message) = match catch_unwind(AssertUnwindSafe(|| f(&mut *jvm))) {
+        Ok(Ok(())) => return LITEJVM_OK,
+        Ok(Err(error)) => error,
+        Err(payload) => {
+            //panic时调用栈可能还留有栈帧
+            jvm.call_stack = jvm.vm.create_call_stack();
+            (LITEJVM_PANIC, panic_message(payload))
+        }
+    };
+    //错误信息中不应该有\0，有的话截断
+    let message = message.split('\0').next().unwrap_or_default();
+    jvm.last_error = CString::new(message).ok();
+    status
+}
+
+unsafe fn str_arg<'s>(value: *const c_char, name: &str) -> Result<&'s str, (c_int, String)> {
+    if value.is_null() {
+        return Err(invalid_argument(format!("{} is null", name)));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|_| invalid_argument(format!("{} is not valid UTF-8", name)))
+}
+
+//boolean、byte、char、short在操作数栈上都是int
+fn is_int_type(value_type: &ValueType) -> bool {
+    matches!(
+        value_type,
+        ValueType::Primary(
+            PrimaryType::Int
+                | PrimaryType::Boolean
+                | PrimaryType::Byte
+                | PrimaryType::Char
+                | PrimaryType::Short
+        )
+    )
+}
+
+fn call_error(vm: &VirtualMachine<'static>, error: MethodCallError<'static>) -> (c_int, String) {
+    let status = match error {
+        MethodCallError::ExceptionThrown(_) => LITEJVM_EXCEPTION,
+        _ => LITEJVM_ERROR,
+    };
+    (status, vm.format_error(error))
+}
+
+/// 创建虚拟机，heap_size是堆的字节数。失败时返回空指针
+#[no_mangle]
+pub extern "C" fn litejvm_create(heap_size: usize) -> *mut LiteJvm {
+    let result = catch_unwind(|| {
+        let vm = VirtualMachine::new(heap_size);
+        let call_stack = vm.create_call_stack();
+        Box::into_raw(Box::new(LiteJvm {
+            call_stack,
+            vm,
+            last_error: None,
+        }))
+    });
+    result.unwrap_or(ptr::null_mut())
+}
+
+/// 添加类路径，格式和java -cp相同
+///
+/// # Safety
+///
+/// vm必须是litejvm_create返回的指针，path是以\0结尾的字符串
+#[no_mangle]
+pub unsafe extern "C" fn litejvm_add_classpath(vm: *mut LiteJvm, path: *const c_char) -> c_int {
+    with_vm(vm, |jvm| {
+        let path = str_arg(path, "path")?;
+        jvm.vm
+            .add_class_path_str(path)
+            .map_err(|e| (LITEJVM_ERROR, e.to_string()))
+    })
+}
+
+/// 调用参数和返回值都是int的静态方法，class使用内部名称，例如java/lang/Math。
+/// boolean、byte、char、short按int传递，成功时返回值写入out
+///
+/// # Safety
+///
+/// vm必须是litejvm_create返回的指针，字符串以\0结尾，args至少有nargs个元素（nargs为0时可以是空指针），
+/// out指向可写的int
+#[no_mangle]
+pub unsafe extern "C" fn litejvm_invoke_static_int(
+    vm: *mut LiteJvm,
+    class: *const c_char,
+    method: *const c_char,
+    descriptor: *const c_char,
+    args: *const c_int,
+    nargs: c_int,
+    out: *mut c_int,
+) -> c_int {
+    with_vm(vm, |jvm| {
+        let class = str_arg(class, "class")?;
+        let method = str_arg(method, "method")?;
+        let descriptor = str_arg(descriptor, "descriptor")?;
+        let method_descriptor =
+            MethodDescriptor::new(descriptor).map_err(|e| invalid_argument(e.to_string()))?;
+        if !method_descriptor.args.iter().all(is_int_type) || !is_int_type(&method_descriptor.ret) {
+            return Err(invalid_argument(format!(
+                "{} does not take and return int values",
+                descriptor
+            )));
+        }
+        let nargs = usize::try_from(nargs)
+            .map_err(|_| invalid_argument(format!("negative nargs {}", nargs)))?;
+        if nargs != method_descriptor.args.len() {
+            return Err(invalid_argument(format!(
+                "{} expects {} arguments, got {}",
+                descriptor,
+                method_descriptor.args.len(),
+                nargs
+            )));
+        }
+        if (args.is_null() && nargs > 0) || out.is_null() {
+            return Err(invalid_argument("args or out is null"));
+        }
+        let args = match nargs {
+            0 => vec![],
+            _ => std::slice::from_raw_parts(args, nargs)
+                .iter()
+                .map(|arg| Value::Int(*arg))
+                .collect(),
+        };
+        let result = jvm
+            .vm
+            .call(&mut jvm.call_stack, class, method, descriptor, None, args);
+        match result {
+            Ok(Some(Value::Int(value))) => {
+                *out = value;
+                Ok(())
+            }
+            Ok(value) => Err((
+                LITEJVM_ERROR,
+                format!("unexpected return value {:?}", value),
+            )),
+            Err(e) => Err(call_error(&jvm.vm, e)),
+        }
+    })
+}
+
+/// 最近一次调用失败时的错误信息，成功或者没有调用过时返回空指针。
+/// 返回的字符串由vm持有，下一次调用vm上的函数后失效
+///
+/// # Safety
+///
+/// vm必须是litejvm_create返回的指针或者空指针
+#[no_mangle]
+pub unsafe extern "C" fn litejvm_last_error_message(vm: *const LiteJvm) -> *const c_char {
+    match vm.as_ref().and_then(|jvm| jvm.last_error.as_ref()) {
+        Some(message) => message.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// 销毁虚拟机，vm为空指针时什么也不做
+///
+/// # Safety
+///
+/// vm必须是litejvm_create返回的指针，并且没有被销毁过
+#[no_mangle]
+pub unsafe extern "C" fn litejvm_destroy(vm: *mut LiteJvm) {
+    if !vm.is_null() {
+        let _ = catch_unwind(AssertUnwindSafe(|| drop(Box::from_raw(vm))));
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_c_abi() {
+        use crate::ffi::*;
+        use std::ffi::{c_char, c_int, CStr, CString};
+
+        //通过和头文件一致的函数指针类型调用
+        let create: extern "C" fn(usize) -> *mut LiteJvm = litejvm_create;
+        let add_classpath: unsafe extern "C" fn(*mut LiteJvm, *const c_char) -> c_int =
+            litejvm_add_classpath;
+        let invoke_static_int: unsafe extern "C" fn(
+            *mut LiteJvm,
+            *const c_char,
+            *const c_char,
+            *const c_char,
+            *const c_int,
+            c_int,
+            *mut c_int,
+        ) -> c_int = litejvm_invoke_static_int;
+        let last_error_message: unsafe extern "C" fn(*const LiteJvm) -> *const c_char =
+            litejvm_last_error_message;
+        let destroy: unsafe extern "C" fn(*mut LiteJvm) = litejvm_destroy;
+
+        let c = |s: &str| CString::new(s).unwrap();
+        let error_message = |vm: *mut LiteJvm| unsafe {
+            let message = last_error_message(vm);
+            assert!(!message.is_null());
+            CStr::from_ptr(message).to_str().unwrap().to_string()
+        };
+        unsafe {
+            let vm = create(102400);
+            assert!(!vm.is_null());
+            assert_eq!(add_classpath(vm, c("./resources").as_ptr()), LITEJVM_OK);
+            assert_eq!(
+                add_classpath(vm, c("./resources/rt.jar").as_ptr()),
+                LITEJVM_OK
+            );
+            assert!(last_error_message(vm).is_null());
+
+            let class = c("FfiTest");
+            let mut out = 0;
+            let args = [3, 4];
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("add").as_ptr(),
+                c("(II)I").as_ptr(),
+                args.as_ptr(),
+                2,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_OK);
+            assert_eq!(out, 7);
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("isPositive").as_ptr(),
+                c("(I)Z").as_ptr(),
+                [5].as_ptr(),
+                1,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_OK);
+            assert_eq!(out, 1);
+
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("divide").as_ptr(),
+                c("(II)I").as_ptr(),
+                [1, 0].as_ptr(),
+                2,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_EXCEPTION);
+            assert_eq!(
+                error_message(vm),
+                "java.lang.IllegalArgumentException: divisor is zero\n\tFfiTest.divide(FfiTest.java:9)"
+            );
+
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("square").as_ptr(),
+                c("(J)J").as_ptr(),
+                [1].as_ptr(),
+                1,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_INVALID_ARGUMENT);
+            assert_eq!(
+                error_message(vm),
+                "(J)J does not take and return int values"
+            );
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("add").as_ptr(),
+                c("(II)I").as_ptr(),
+                [1].as_ptr(),
+                1,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_INVALID_ARGUMENT);
+            assert_eq!(error_message(vm), "(II)I expects 2 arguments, got 1");
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("missing").as_ptr(),
+                c("()I").as_ptr(),
+                std::ptr::null(),
+                0,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_ERROR);
+            assert!(error_message(vm).starts_with("internal error: "));
+            assert_eq!(
+                invoke_static_int(
+                    vm,
+                    std::ptr::null(),
+                    c("add").as_ptr(),
+                    c("(II)I").as_ptr(),
+                    args.as_ptr(),
+                    2,
+                    &mut out,
+                ),
+                LITEJVM_INVALID_ARGUMENT
+            );
+            assert_eq!(error_message(vm), "class is null");
+
+            //出错后虚拟机可以继续使用，成功的调用清除错误信息
+            let status = invoke_static_int(
+                vm,
+                class.as_ptr(),
+                c("add").as_ptr(),
+                c("(II)I").as_ptr(),
+                [-1, -2].as_ptr(),
+                2,
+                &mut out,
+            );
+            assert_eq!(status, LITEJVM_OK);
+            assert_eq!(out, -3);
+            assert!(last_error_message(vm).is_null());
+            destroy(vm);
+
+            assert_eq!(
+                add_classpath(std::ptr::null_mut(), c(".").as_ptr()),
+                LITEJVM_INVALID_ARGUMENT
+            );
+            assert!(last_error_message(std::ptr::null()).is_null());
+            destroy(std::ptr::null_mut());
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use crate::jvm_values::{ArrayReference, ObjectReference, ReferenceValue, Value};
+use crate::virtual_machine::VirtualMachine;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::{Display, Formatter};
+
+/// 从根出发可达的堆快照，用于和golden文件比较。
+///
+/// 对象按照广度优先遍历的顺序编号，同样的对象图每次得到的编号相同，引用都替换为编号，因此可以处理循环引用。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HeapDump {
+    pub roots: Vec<DumpValue>,
+    pub objects: BTreeMap<usize, DumpEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DumpValue {
+    Uninitialized,
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    ReturnAddress(u32),
+    Reference(usize),
+    Null,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DumpEntry {
+    Object {
+        class_name: String,
+        //父类字段被遮蔽时使用`类名.字段名`作为key
+        fields: BTreeMap<String, DumpValue>,
+    },
+    Array {
+        array_class_name: String,
+        elements: Vec<DumpValue>,
+    },
+    //java.lang.String直接输出文本
+    String(String),
+}
+
+/// 两份快照之间的一处差异，path指出差异的位置，例如`objects[2].fields.a`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+impl Display for Difference {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let missing = "<missing>".to_string();
+        write!(
+            f,
+            "{}: {} != {}",
+            self.path,
+            self.left.as_ref().unwrap_or(&missing),
+            self.right.as_ref().unwrap_or(&missing)
+        )
+    }
+}
+
+impl HeapDump {
+    pub fn diff(&self, other: &HeapDump) -> Vec<Difference> {
+        let mut differences = Vec::new();
+        diff_list(
+            "roots",
+            &self.roots,
+            &other.roots,
+            &mut differences,
+            diff_value,
+        );
+        let ids: BTreeSet<&usize> = self.objects.keys().chain(other.objects.keys()).collect();
+        for id in ids {
+            let path = format!("objects[{}]", id);
+            match (self.objects.get(id), other.objects.get(id)) {
+                (Some(left), Some(right)) => diff_entry(&path, left, right, &mut differences),
+                (left, right) => differences.push(Difference {
+                    path,
+                    left: left.map(|e| format!("{:?}", e)),
+                    right: right.map(|e| format!("{:?}", e)),
+                }),
+            }
+        }
+        differences
+    }
+}
+
+fn diff_value(path: &str, left: &DumpValue, right: &DumpValue, differences: &mut Vec<Difference>) {
+    if left != right {
+        differences.push(Difference {
+            path: path.to_string(),
+            left: Some(format!("{:?}", left)),
+            right: Some(format!("{:?}", right)),
+        });
+    }
+}
+
+fn diff_list<T, F>(path: &str, left: &[T], right: &[T], differences: &mut Vec<Difference>, diff: F)
+where
+    T: std::fmt::Debug,
+    F: Fn(&str, &T, &T, &mut Vec<Difference>),
+{
+    for index in 0..left.len().max(right.len()) {
+        let item_path = format!("{}[{}]", path, index);
+        match (left.get(index), right.get(index)) {
+            (Some(l), Some(r)) => diff(&item_path, l, r, differences),
+            (l, r) => differences.push(Difference {
+                path: item_path,
+                left: l.map(|v| format!("{:?}", v)),
+                right: r.map(|v| format!("{:?}", v)),
+            }),
+        }
+    }
+}
+
+fn diff_entry(path: &str, left: &DumpEntry, right: &DumpEntry, differences: &mut Vec<Difference>) {
+    match (left, right) {
+        (
+            DumpEntry::Object {
+                class_name: left_class,
+                fields: left_fields,
+            },
+            DumpEntry::Object {
+                class_name: right_class,
+                fields: right_fields,
+            },
+        ) if left_class == right_class => {
+            let names: BTreeSet<&String> = left_fields.keys().chain(right_fields.keys()).collect();
+            for name in names {
+                let field_path = format!("{}.fields.{}", path, name);
+                match (left_fields.get(name), right_fields.get(name)) {
+                    (Some(l), Some(r)) => diff_value(&field_path, l, r, differences),
+                    (l, r) => differences.push(Difference {
+                        path: field_path,
+                        left: l.map(|v| format!("{:?}", v)),
+                        right: r.map(|v| format!("{:?}", v)),
+                    }),
+                }
+            }
+        }
+        (
+            DumpEntry::Array {
+                array_class_name: left_class,
+                elements: left_elements,
+            },
+            DumpEntry::Array {
+                array_class_name: right_class,
+                elements: right_elements,
+            },
+        ) if left_class == right_class => diff_list(
+            &format!("{}.elements", path),
+            left_elements,
+            right_elements,
+            differences,
+            diff_value,
+        ),
+        _ => {
+            if left != right {
+                differences.push(Difference {
+                    path: path.to_string(),
+                    left: Some(format!("{:?}", left)),
+                    right: Some(format!("{:?}", right)),
+                })
+            }
+        }
+    }
+}
+
+struct DumpBuilder<'a> {
+    ids: HashMap<*mut u8, usize>,
+    pending: VecDeque<Value<'a>>,
+    objects: BTreeMap<usize, DumpEntry>,
+}
+
+impl<'a> DumpBuilder<'a> {
+    //第一次遇到的引用分配新编号并加入待处理队列
+    fn dump_value(&mut self, value: &Value<'a>) -> DumpValue {
+        let ptr = match value {
+            Value::Uninitialized => return DumpValue::Uninitialized,
+            Value::Int(v) => return DumpValue::Int(*v),
+            Value::Long(v) => return DumpValue::Long(*v),
+            Value::Float(v) => return DumpValue::Float(*v),
+            Value::Double(v) => return DumpValue::Double(*v),
+            Value::ReturnAddress(v) => return DumpValue::ReturnAddress(*v),
+            Value::Null => return DumpValue::Null,
+            Value::ObjectRef(object_ref) => object_ref.ptr(),
+            Value::ArrayRef(array_ref) => array_ref.ptr(),
+        };
+        let next_id = self.ids.len();
+        let id = *self.ids.entry(ptr).or_insert_with(|| {
+            self.pending.push_back(value.clone());
+            next_id
+        });
+        DumpValue::Reference(id)
+    }
+
+    fn dump_object(&mut self, object_ref: ObjectReference<'a>) -> DumpEntry {
+        let class_ref = object_ref.get_class();
+        if class_ref.name == "java/lang/String" {
+            if let Ok(text) = Value::ObjectRef(object_ref).get_string() {
+                return DumpEntry::String(text);
+            }
+        }
+        let mut fields = BTreeMap::new();
+        let mut current = Some(class_ref);
+        while let Some(class) = current {
+            for field in class.fields.values().filter(|f| !f.is_static()) {
+                let key = if fields.contains_key(&field.name) {
+                    format!("{}.{}", class.name, field.name)
+                } else {
+                    field.name.clone()
+                };
+                let value = object_ref
+                    .get_field_value(field)
+                    .unwrap_or(Value::Uninitialized);
+                let dump_value = self.dump_value(&value);
+                fields.insert(key, dump_value);
+            }
+            current = class.super_class;
+        }
+        DumpEntry::Object {
+            class_name: class_ref.name.clone(),
+            fields,
+        }
+    }
+
+    fn dump_array(&mut self, array_ref: ArrayReference<'a>) -> DumpEntry {
+        let elements = array_ref
+            .read_all()
+            .iter()
+            .map(|value| self.dump_value(value))
+            .collect();
+        DumpEntry::Array {
+            array_class_name: array_ref.get_array_type().array_class_name(),
+            elements,
+        }
+    }
+}
+
+impl<'a> VirtualMachine<'a> {
+    /// 导出从roots可达的所有对象和数组
+    pub fn dump_reachable(&self, roots: &[Value<'a>]) -> HeapDump {
+        let mut builder = DumpBuilder {
+            ids: HashMap::new(),
+            pending: VecDeque::new(),
+            objects: BTreeMap::new(),
+        };
+        let roots = roots.iter().map(|v| builder.dump_value(v)).collect();
+        while let Some(value) = builder.pending.pop_front() {
+            let (ptr, entry) = match value {
+                Value::ObjectRef(object_ref) => (object_ref.ptr(), builder.dump_object(object_ref)),
+                Value::ArrayRef(array_ref) => (array_ref.ptr(), builder.dump_array(array_ref)),
+                _ => continue,
+            };
+            let id = builder.ids[&ptr];
+            builder.objects.insert(id, entry);
+        }
+        HeapDump {
+            roots,
+            objects: builder.objects,
+        }
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_dump_reachable() {
+        use crate::heap_dump::{DumpEntry, DumpValue, HeapDump};
+        use crate::jvm_values::{ArrayElement, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        use std::collections::BTreeMap;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "FieldTest")
+            .unwrap();
+        let object_ref = vm.new_object(call_stack, class_ref).unwrap();
+        let init_method = class_ref.get_method("<init>", "()V").unwrap();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            init_method,
+            Some(object_ref),
+            Vec::new(),
+        )
+        .unwrap();
+
+        let dump = vm.dump_reachable(&[Value::ObjectRef(object_ref)]);
+        let fields = BTreeMap::from([
+            ("fieldString".to_string(), DumpValue::Reference(1)),
+            ("a".to_string(), DumpValue::Int(0)),
+            ("b".to_string(), DumpValue::Null),
+            ("c".to_string(), DumpValue::Reference(2)),
+            ("fieldDouble".to_string(), DumpValue::Double(100f64)),
+            ("fieldFloat".to_string(), DumpValue::Float(50f32)),
+        ]);
+        let expected = HeapDump {
+            roots: vec![DumpValue::Reference(0)],
+            objects: BTreeMap::from([
+                (
+                    0,
+                    DumpEntry::Object {
+                        class_name: "FieldTest".to_string(),
+                        fields,
+                    },
+                ),
+                (1, DumpEntry::String("default".to_string())),
+                (
+                    2,
+                    DumpEntry::Object {
+                        class_name: "java/lang/Long".to_string(),
+                        fields: BTreeMap::from([("value".to_string(), DumpValue::Long(1))]),
+                    },
+                ),
+            ]),
+        };
+        assert_eq!(dump.diff(&expected), vec![]);
+        assert_eq!(dump, expected);
+
+        let json = serde_json::to_string(&dump).unwrap();
+        let parsed: HeapDump = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, dump);
+
+        //修改字段后可以定位到具体的差异
+        object_ref.set_field_by_name("a", &Value::Int(7)).unwrap();
+        let differences = vm
+            .dump_reachable(&[Value::ObjectRef(object_ref)])
+            .diff(&expected);
+        assert_eq!(differences.len(), 1);
+        assert_eq!(
+            differences[0].to_string(),
+            "objects[0].fields.a: Int(7) != Int(0)"
+        );
+
+        //数组引用自身形成环
+        let object_class = vm
+            .lookup_class_and_initialize(call_stack, "java/lang/Object")
+            .unwrap();
+        let array_ref = vm
+            .new_array(call_stack, ArrayElement::ClassReference(object_class), 2)
+            .unwrap();
+        array_ref
+            .set_field_by_offset(0, &Value::ObjectRef(object_ref))
+            .unwrap();
+        let dump = vm.dump_reachable(&[Value::ArrayRef(array_ref), Value::ObjectRef(object_ref)]);
+        assert_eq!(
+            dump.roots,
+            vec![DumpValue::Reference(0), DumpValue::Reference(1)]
+        );
+        assert_eq!(
+            dump.objects[&0],
+            DumpEntry::Array {
+                array_class_name: "[Ljava/lang/Object;".to_string(),
+                elements: vec![DumpValue::Reference(1), DumpValue::Null],
+            }
+        );
+        assert_eq!(dump.objects.len(), 4);
+    }
+}
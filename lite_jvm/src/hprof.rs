@@ -0,0 +1,855 @@
+use crate::jvm_values::{ArrayElement, ArrayReference, ObjectReference, ReferenceValue, Value};
+use crate::loaded_class::{Class, ClassRef};
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Error, ErrorKind, Result, Write};
+
+/// HPROF格式的堆转储，可以用VisualVM、Eclipse MAT等工具打开。
+///
+/// 只输出堆相关的记录，不包含线程和分配调用栈。对象和数组的标识使用分配地址，
+/// 类的标识使用Class结构的地址，数组类没有对应的Class，使用奇数编号与地址区分。
+/// https://hg.openjdk.org/jdk8/jdk8/jdk/file/tip/src/share/demo/jvmti/hprof/manual.html
+pub const HPROF_HEADER: &[u8] = b"JAVA PROFILE 1.0.2\0";
+
+const ID_SIZE: u32 = 8;
+//所有记录都引用同一个空的调用栈
+const STACK_TRACE_SERIAL: u32 = 1;
+
+const TAG_UTF8: u8 = 0x01;
+const TAG_LOAD_CLASS: u8 = 0x02;
+const TAG_STACK_TRACE: u8 = 0x05;
+const TAG_HEAP_DUMP: u8 = 0x0c;
+const TAG_HEAP_DUMP_SEGMENT: u8 = 0x1c;
+
+const ROOT_UNKNOWN: u8 = 0xff;
+const ROOT_JNI_GLOBAL: u8 = 0x01;
+const ROOT_JNI_LOCAL: u8 = 0x02;
+const ROOT_JAVA_FRAME: u8 = 0x03;
+const ROOT_NATIVE_STACK: u8 = 0x04;
+const ROOT_STICKY_CLASS: u8 = 0x05;
+const ROOT_THREAD_BLOCK: u8 = 0x06;
+const ROOT_MONITOR_USED: u8 = 0x07;
+const ROOT_THREAD_OBJECT: u8 = 0x08;
+const CLASS_DUMP: u8 = 0x20;
+const INSTANCE_DUMP: u8 = 0x21;
+const OBJECT_ARRAY_DUMP: u8 = 0x22;
+const PRIMITIVE_ARRAY_DUMP: u8 = 0x23;
+
+pub const TYPE_OBJECT: u8 = 2;
+pub const TYPE_BOOLEAN: u8 = 4;
+pub const TYPE_CHAR: u8 = 5;
+pub const TYPE_FLOAT: u8 = 6;
+pub const TYPE_DOUBLE: u8 = 7;
+pub const TYPE_BYTE: u8 = 8;
+pub const TYPE_SHORT: u8 = 9;
+pub const TYPE_INT: u8 = 10;
+pub const TYPE_LONG: u8 = 11;
+
+//字段描述符对应的基本类型
+fn descriptor_type(descriptor: &str) -> u8 {
+    match descriptor.as_bytes().first() {
+        Some(b'Z') => TYPE_BOOLEAN,
+        Some(b'C') => TYPE_CHAR,
+        Some(b'F') => TYPE_FLOAT,
+        Some(b'D') => TYPE_DOUBLE,
+        Some(b'B') => TYPE_BYTE,
+        Some(b'S') => TYPE_SHORT,
+        Some(b'I') => TYPE_INT,
+        Some(b'J') => TYPE_LONG,
+        _ => TYPE_OBJECT,
+    }
+}
+
+fn type_size(basic_type: u8, id_size: u32) -> usize {
+    match basic_type {
+        TYPE_BOOLEAN | TYPE_BYTE => 1,
+        TYPE_CHAR | TYPE_SHORT => 2,
+        TYPE_FLOAT | TYPE_INT => 4,
+        TYPE_DOUBLE | TYPE_LONG => 8,
+        _ => id_size as usize,
+    }
+}
+
+fn element_type(element: &ArrayElement) -> u8 {
+    match element {
+        ArrayElement::PrimaryValue(_) => descriptor_type(&element.array_class_name()[1..]),
+        _ => TYPE_OBJECT,
+    }
+}
+
+fn reference_id(value: &Value) -> u64 {
+    match value {
+        Value::ObjectRef(object_ref) => object_ref.ptr() as u64,
+        Value::ArrayRef(array_ref) => array_ref.ptr() as u64,
+        _ => 0,
+    }
+}
+
+fn class_id(class_ref: ClassRef) -> u64 {
+    class_ref as *const Class as u64
+}
+
+struct HprofWriter<'a> {
+    strings: HashMap<String, u64>,
+    string_records: Vec<u8>,
+    class_records: Vec<u8>,
+    heap: Vec<u8>,
+    next_class_serial: u32,
+    dumped_classes: HashSet<u64>,
+    array_classes: HashMap<String, u64>,
+    object_class_id: u64,
+    visited: HashSet<u64>,
+    pending: VecDeque<Value<'a>>,
+}
+
+impl<'a> HprofWriter<'a> {
+    fn string_id(&mut self, value: &str) -> u64 {
+        if let Some(id) = self.strings.get(value) {
+            return *id;
+        }
+        let id = self.strings.len() as u64 + 1;
+        self.strings.insert(value.to_string(), id);
+        write_record_header(
+            &mut self.string_records,
+            TAG_UTF8,
+            ID_SIZE as usize + value.len(),
+        );
+        self.string_records.extend_from_slice(&id.to_be_bytes());
+        self.string_records.extend_from_slice(value.as_bytes());
+        id
+    }
+
+    fn load_class(&mut self, id: u64, class_name: &str) {
+        let name_id = self.string_id(&class_name.replace('/', "."));
+        self.next_class_serial += 1;
+        write_record_header(
+            &mut self.class_records,
+            TAG_LOAD_CLASS,
+            8 + 2 * ID_SIZE as usize,
+        );
+        self.class_records
+            .extend_from_slice(&self.next_class_serial.to_be_bytes());
+        self.class_records.extend_from_slice(&id.to_be_bytes());
+        self.class_records
+            .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+        self.class_records.extend_from_slice(&name_id.to_be_bytes());
+    }
+
+    fn put_id(&mut self, id: u64) {
+        self.heap.extend_from_slice(&id.to_be_bytes());
+    }
+
+    //引用类型的值写入标识，并加入待处理队列
+    fn put_value(&mut self, basic_type: u8, value: &Value<'a>) {
+        match (basic_type, value) {
+            (TYPE_OBJECT, _) => {
+                let id = reference_id(value);
+                if id != 0 {
+                    self.pending.push_back(value.clone());
+                }
+                self.put_id(id)
+            }
+            (TYPE_BOOLEAN | TYPE_BYTE, Value::Int(v)) => self.heap.push(*v as u8),
+            (TYPE_CHAR | TYPE_SHORT, Value::Int(v)) => {
+                self.heap.extend_from_slice(&(*v as u16).to_be_bytes())
+            }
+            (TYPE_INT, Value::Int(v)) => self.heap.extend_from_slice(&v.to_be_bytes()),
+            (TYPE_LONG, Value::Long(v)) => self.heap.extend_from_slice(&v.to_be_bytes()),
+            (TYPE_FLOAT, Value::Float(v)) => {
+                self.heap.extend_from_slice(&v.to_bits().to_be_bytes())
+            }
+            (TYPE_DOUBLE, Value::Double(v)) => {
+                self.heap.extend_from_slice(&v.to_bits().to_be_bytes())
+            }
+            //未初始化的字段写入0
+            _ => {
+                let length = self.heap.len() + type_size(basic_type, ID_SIZE);
+                self.heap.resize(length, 0)
+            }
+        }
+    }
+
+    fn dump_class(&mut self, vm: &VirtualMachine<'a>, class_ref: ClassRef<'a>) {
+        let id = class_id(class_ref);
+        if !self.dumped_classes.insert(id) {
+            return;
+        }
+        if class_ref.name == "java/lang/Object" {
+            self.object_class_id = id;
+        }
+        if let Some(super_class) = class_ref.super_class {
+            self.dump_class(vm, super_class);
+        }
+        self.load_class(id, &class_ref.name);
+        self.heap.push(ROOT_STICKY_CLASS);
+        self.put_id(id);
+
+        let mut instance_size = 0;
+        let mut current = Some(class_ref);
+        while let Some(class) = current {
+            instance_size += class
+                .fields
+                .values()
+                .filter(|f| !f.is_static())
+                .map(|f| type_size(descriptor_type(&f.descriptor), ID_SIZE))
+                .sum::<usize>();
+            current = class.super_class;
+        }
+        let static_fields: Vec<(&str, u8, Value<'a>)> = vm
+            .static_area()
+            .get_static_fields(class_ref)
+            .into_iter()
+            .flatten()
+            .filter_map(|(name, value)| {
+                let field = class_ref.fields.get(name.as_str())?;
+                let basic_type = descriptor_type(&field.descriptor);
+                Some((field.name.as_str(), basic_type, value.clone()))
+            })
+            .collect();
+        let class_loader = class_ref.class_loader.map(Value::ObjectRef);
+
+        self.heap.push(CLASS_DUMP);
+        self.put_id(id);
+        self.heap
+            .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+        self.put_id(class_ref.super_class.map_or(0, class_id));
+        self.put_value(TYPE_OBJECT, &class_loader.unwrap_or(Value::Null));
+        //signers, protection domain和两个保留字段
+        for _ in 0..4 {
+            self.put_id(0);
+        }
+        self.heap
+            .extend_from_slice(&(instance_size as u32).to_be_bytes());
+        //常量池
+        self.heap.extend_from_slice(&0u16.to_be_bytes());
+        self.heap
+            .extend_from_slice(&(static_fields.len() as u16).to_be_bytes());
+        for (name, basic_type, value) in static_fields {
+            let name_id = self.string_id(name);
+            self.put_id(name_id);
+            self.heap.push(basic_type);
+            self.put_value(basic_type, &value);
+        }
+        let instance_fields: Vec<_> = class_ref
+            .fields
+            .values()
+            .filter(|f| !f.is_static())
+            .collect();
+        self.heap
+            .extend_from_slice(&(instance_fields.len() as u16).to_be_bytes());
+        for field in instance_fields {
+            let name_id = self.string_id(&field.name);
+            self.put_id(name_id);
+            self.heap.push(descriptor_type(&field.descriptor));
+        }
+    }
+
+    //数组类在所有对象处理完之后输出，此时已经知道java/lang/Object的标识
+    fn array_class_id(&mut self, array_class_name: String) -> u64 {
+        let next_id = self.array_classes.len() as u64 * 2 + 1;
+        *self
+            .array_classes
+            .entry(array_class_name)
+            .or_insert(next_id)
+    }
+
+    fn dump_array_classes(&mut self) {
+        let mut array_classes: Vec<(String, u64)> = self.array_classes.drain().collect();
+        array_classes.sort_by_key(|(_, id)| *id);
+        for (name, id) in array_classes {
+            self.load_class(id, &name);
+            self.heap.push(ROOT_STICKY_CLASS);
+            self.put_id(id);
+            self.heap.push(CLASS_DUMP);
+            self.put_id(id);
+            self.heap
+                .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+            self.put_id(self.object_class_id);
+            for _ in 0..5 {
+                self.put_id(0);
+            }
+            //instance size, 常量池, 静态字段, 实例字段
+            self.heap.extend_from_slice(&0u32.to_be_bytes());
+            self.heap.extend_from_slice(&[0; 6]);
+        }
+    }
+
+    fn dump_object(&mut self, vm: &VirtualMachine<'a>, object_ref: ObjectReference<'a>) {
+        let class_ref = object_ref.get_class();
+        self.dump_class(vm, class_ref);
+        let mut values = vec![];
+        let mut current = Some(class_ref);
+        while let Some(class) = current {
+            for field in class.fields.values().filter(|f| !f.is_static()) {
+                let value = object_ref
+                    .get_field_value(field)
+                    .unwrap_or(Value::Uninitialized);
+                values.push((descriptor_type(&field.descriptor), value));
+            }
+            current = class.super_class;
+        }
+        self.heap.push(INSTANCE_DUMP);
+        self.put_id(object_ref.ptr() as u64);
+        self.heap
+            .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+        self.put_id(class_id(class_ref));
+        let length: usize = values
+            .iter()
+            .map(|(basic_type, _)| type_size(*basic_type, ID_SIZE))
+            .sum();
+        self.heap.extend_from_slice(&(length as u32).to_be_bytes());
+        for (basic_type, value) in values {
+            self.put_value(basic_type, &value);
+        }
+    }
+
+    fn dump_array(&mut self, array_ref: ArrayReference<'a>) {
+        let element = array_ref.get_array_type();
+        let elements = array_ref.read_all();
+        let basic_type = element_type(&element);
+        let array_class_id = self.array_class_id(element.array_class_name());
+        if basic_type == TYPE_OBJECT {
+            self.heap.push(OBJECT_ARRAY_DUMP);
+            self.put_id(array_ref.ptr() as u64);
+            self.heap
+                .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+            self.heap
+                .extend_from_slice(&(elements.len() as u32).to_be_bytes());
+            self.put_id(array_class_id);
+        } else {
+            self.heap.push(PRIMITIVE_ARRAY_DUMP);
+            self.put_id(array_ref.ptr() as u64);
+            self.heap
+                .extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+            self.heap
+                .extend_from_slice(&(elements.len() as u32).to_be_bytes());
+            self.heap.push(basic_type);
+        }
+        for value in elements {
+            self.put_value(basic_type, &value);
+        }
+    }
+
+    fn add_root(&mut self, tag: u8, value: Value<'a>, extra: &[u32]) {
+        let id = reference_id(&value);
+        if id == 0 {
+            return;
+        }
+        self.heap.push(tag);
+        self.put_id(id);
+        for v in extra {
+            self.heap.extend_from_slice(&v.to_be_bytes());
+        }
+        self.pending.push_back(value);
+    }
+}
+
+fn write_record_header(buffer: &mut Vec<u8>, tag: u8, length: usize) {
+    buffer.push(tag);
+    //相对于文件头时间戳的微秒数
+    buffer.extend_from_slice(&0u32.to_be_bytes());
+    buffer.extend_from_slice(&(length as u32).to_be_bytes());
+}
+
+impl<'a> VirtualMachine<'a> {
+    /// 以HPROF格式写出从静态字段、字符串和Class对象、类加载器以及传入的调用栈可达的对象
+    pub fn write_heap_dump(&self, call_stacks: &[&CallStack<'a>], w: &mut dyn Write) -> Result<()> {
+        let mut writer = HprofWriter {
+            strings: HashMap::new(),
+            string_records: vec![],
+            class_records: vec![],
+            heap: vec![],
+            next_class_serial: 0,
+            dumped_classes: HashSet::new(),
+            array_classes: HashMap::new(),
+            object_class_id: 0,
+            visited: HashSet::new(),
+            pending: VecDeque::new(),
+        };
+        //栈帧从栈顶开始编号
+        for (thread_serial, call_stack) in call_stacks.iter().enumerate() {
+            for (frame_number, frame) in call_stack.frames().rev().enumerate() {
+                let locals = frame
+                    .local_var_table
+                    .iter()
+                    .filter_map(|local| match local {
+                        crate::stack_frame::LocalValue::Entry(value) => Some(value.clone()),
+                        _ => None,
+                    });
+                let values: Vec<Value<'a>> = locals
+                    .chain(frame.op_stack.values().iter().cloned())
+                    .collect();
+                for value in values {
+                    let extra = [thread_serial as u32 + 1, frame_number as u32];
+                    writer.add_root(ROOT_JAVA_FRAME, value, &extra);
+                }
+            }
+        }
+        let static_area = self.static_area();
+        for class_ref in static_area.static_classes() {
+            writer.dump_class(self, class_ref);
+        }
+        //和collect_garbage使用相同的根：静态区中的引用值以及类加载器
+        let roots: Vec<Value<'a>> = static_area
+            .iter_reference_values()
+            .chain(self.class_loaders().into_iter().map(Value::ObjectRef))
+            .collect();
+        for value in roots {
+            writer.add_root(ROOT_UNKNOWN, value, &[]);
+        }
+
+        while let Some(value) = writer.pending.pop_front() {
+            if !writer.visited.insert(reference_id(&value)) {
+                continue;
+            }
+            match value {
+                Value::ObjectRef(object_ref) => writer.dump_object(self, object_ref),
+                Value::ArrayRef(array_ref) => writer.dump_array(array_ref),
+                _ => {}
+            }
+        }
+        writer.dump_array_classes();
+
+        w.write_all(HPROF_HEADER)?;
+        w.write_all(&ID_SIZE.to_be_bytes())?;
+        w.write_all(&(self.current_time_millis() as u64).to_be_bytes())?;
+        w.write_all(&writer.string_records)?;
+        w.write_all(&writer.class_records)?;
+        let mut stack_trace = vec![];
+        write_record_header(&mut stack_trace, TAG_STACK_TRACE, 12);
+        stack_trace.extend_from_slice(&STACK_TRACE_SERIAL.to_be_bytes());
+        stack_trace.extend_from_slice(&[0; 8]);
+        w.write_all(&stack_trace)?;
+        let mut heap_dump = vec![];
+        write_record_header(&mut heap_dump, TAG_HEAP_DUMP, writer.heap.len());
+        w.write_all(&heap_dump)?;
+        w.write_all(&writer.heap)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HprofValue {
+    Object(u64),
+    Boolean(bool),
+    Char(u16),
+    Float(f32),
+    Double(f64),
+    Byte(i8),
+    Short(i16),
+    Int(i32),
+    Long(i64),
+}
+
+#[derive(Debug, Clone)]
+pub struct HprofClass {
+    pub name: String,
+    pub super_class_id: u64,
+    pub static_fields: Vec<(String, HprofValue)>,
+    pub instance_fields: Vec<(String, u8)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct HprofInstance {
+    pub class_id: u64,
+    pub data: Vec<u8>,
+}
+
+#[derive(Debug, Clone)]
+pub enum HprofArray {
+    Object {
+        array_class_id: u64,
+        elements: Vec<u64>,
+    },
+    Primitive {
+        element_type: u8,
+        elements: Vec<HprofValue>,
+    },
+}
+
+/// 读取HPROF文件中的堆记录，用于校验write_heap_dump的输出，或者转换成其他格式
+#[derive(Debug, Default)]
+pub struct HprofDump {
+    pub id_size: u32,
+    pub timestamp: u64,
+    pub classes: HashMap<u64, HprofClass>,
+    pub instances: HashMap<u64, HprofInstance>,
+    pub arrays: HashMap<u64, HprofArray>,
+    pub roots: Vec<u64>,
+}
+
+struct HprofReader<'b> {
+    bytes: &'b [u8],
+    position: usize,
+    id_size: u32,
+}
+
+impl<'b> HprofReader<'b> {
+    fn read_bytes(&mut self, length: usize) -> Result<&'b [u8]> {
+        let end = self.position + length;
+        if end > self.bytes.len() {
+            return Err(Error::new(ErrorKind::UnexpectedEof, "truncated hprof"));
+        }
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+    fn read_u1(&mut self) -> Result<u8> {
+        Ok(self.read_bytes(1)?[0])
+    }
+    fn read_u2(&mut self) -> Result<u16> {
+        Ok(u16::from_be_bytes(self.read_bytes(2)?.try_into().unwrap()))
+    }
+    fn read_u4(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.read_bytes(4)?.try_into().unwrap()))
+    }
+    fn read_u8(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.read_bytes(8)?.try_into().unwrap()))
+    }
+    fn read_id(&mut self) -> Result<u64> {
+        match self.id_size {
+            4 => Ok(self.read_u4()? as u64),
+            _ => self.read_u8(),
+        }
+    }
+    fn read_value(&mut self, basic_type: u8) -> Result<HprofValue> {
+        Ok(match basic_type {
+            TYPE_OBJECT => HprofValue::Object(self.read_id()?),
+            TYPE_BOOLEAN => HprofValue::Boolean(self.read_u1()? != 0),
+            TYPE_CHAR => HprofValue::Char(self.read_u2()?),
+            TYPE_FLOAT => HprofValue::Float(f32::from_bits(self.read_u4()?)),
+            TYPE_DOUBLE => HprofValue::Double(f64::from_bits(self.read_u8()?)),
+            TYPE_BYTE => HprofValue::Byte(self.read_u1()? as i8),
+            TYPE_SHORT => HprofValue::Short(self.read_u2()? as i16),
+            TYPE_INT => HprofValue::Int(self.read_u4()? as i32),
+            TYPE_LONG => HprofValue::Long(self.read_u8()? as i64),
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown basic type {}", basic_type),
+                ))
+            }
+        })
+    }
+}
+
+impl HprofDump {
+    pub fn parse(bytes: &[u8]) -> Result<HprofDump> {
+        let header_end = bytes
+            .iter()
+            .position(|b| *b == 0)
+            .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing hprof header"))?;
+        if !bytes[..header_end].starts_with(b"JAVA PROFILE") {
+            return Err(Error::new(ErrorKind::InvalidData, "not a hprof file"));
+        }
+        let mut reader = HprofReader {
+            bytes,
+            position: header_end + 1,
+            id_size: 4,
+        };
+        let mut dump = HprofDump {
+            id_size: reader.read_u4()?,
+            timestamp: reader.read_u8()?,
+            ..Default::default()
+        };
+        reader.id_size = dump.id_size;
+        let mut strings: HashMap<u64, String> = HashMap::new();
+        let mut class_names: HashMap<u64, u64> = HashMap::new();
+        while reader.position < bytes.len() {
+            let tag = reader.read_u1()?;
+            reader.read_u4()?;
+            let length = reader.read_u4()? as usize;
+            let end = reader.position + length;
+            match tag {
+                TAG_UTF8 => {
+                    let id = reader.read_id()?;
+                    let text = reader.read_bytes(end - reader.position)?;
+                    strings.insert(id, String::from_utf8_lossy(text).to_string());
+                }
+                TAG_LOAD_CLASS => {
+                    reader.read_u4()?;
+                    let class_id = reader.read_id()?;
+                    reader.read_u4()?;
+                    class_names.insert(class_id, reader.read_id()?);
+                }
+                TAG_HEAP_DUMP | TAG_HEAP_DUMP_SEGMENT => {
+                    while reader.position < end {
+                        dump.read_sub_record(&mut reader, &strings)?;
+                    }
+                }
+                _ => {}
+            }
+            reader.position = end;
+        }
+        for (class_id, class) in dump.classes.iter_mut() {
+            if let Some(name) = class_names.get(class_id).and_then(|id| strings.get(id)) {
+                class.name = name.clone();
+            }
+        }
+        Ok(dump)
+    }
+
+    fn read_sub_record(
+        &mut self,
+        reader: &mut HprofReader,
+        strings: &HashMap<u64, String>,
+    ) -> Result<()> {
+        let tag = reader.read_u1()?;
+        match tag {
+            ROOT_UNKNOWN | ROOT_STICKY_CLASS | ROOT_MONITOR_USED => {
+                self.roots.push(reader.read_id()?);
+            }
+            ROOT_JNI_GLOBAL => {
+                self.roots.push(reader.read_id()?);
+                reader.read_id()?;
+            }
+            ROOT_NATIVE_STACK | ROOT_THREAD_BLOCK => {
+                self.roots.push(reader.read_id()?);
+                reader.read_u4()?;
+            }
+            ROOT_JNI_LOCAL | ROOT_JAVA_FRAME | ROOT_THREAD_OBJECT => {
+                self.roots.push(reader.read_id()?);
+                reader.read_u4()?;
+                reader.read_u4()?;
+            }
+            CLASS_DUMP => {
+                let class_id = reader.read_id()?;
+                reader.read_u4()?;
+                let super_class_id = reader.read_id()?;
+                for _ in 0..5 {
+                    reader.read_id()?;
+                }
+                reader.read_u4()?;
+                for _ in 0..reader.read_u2()? {
+                    reader.read_u2()?;
+                    let basic_type = reader.read_u1()?;
+                    reader.read_value(basic_type)?;
+                }
+                let mut static_fields = vec![];
+                for _ in 0..reader.read_u2()? {
+                    let name = strings.get(&reader.read_id()?).cloned().unwrap_or_default();
+                    let basic_type = reader.read_u1()?;
+                    static_fields.push((name, reader.read_value(basic_type)?));
+                }
+                let mut instance_fields = vec![];
+                for _ in 0..reader.read_u2()? {
+                    let name = strings.get(&reader.read_id()?).cloned().unwrap_or_default();
+                    instance_fields.push((name, reader.read_u1()?));
+                }
+                let class = HprofClass {
+                    name: String::new(),
+                    super_class_id,
+                    static_fields,
+                    instance_fields,
+                };
+                self.classes.insert(class_id, class);
+            }
+            INSTANCE_DUMP => {
+                let id = reader.read_id()?;
+                reader.read_u4()?;
+                let class_id = reader.read_id()?;
+                let length = reader.read_u4()? as usize;
+                let data = reader.read_bytes(length)?.to_vec();
+                self.instances.insert(id, HprofInstance { class_id, data });
+            }
+            OBJECT_ARRAY_DUMP => {
+                let id = reader.read_id()?;
+                reader.read_u4()?;
+                let length = reader.read_u4()?;
+                let array_class_id = reader.read_id()?;
+                let elements = (0..length)
+                    .map(|_| reader.read_id())
+                    .collect::<Result<Vec<u64>>>()?;
+                let array = HprofArray::Object {
+                    array_class_id,
+                    elements,
+                };
+                self.arrays.insert(id, array);
+            }
+            PRIMITIVE_ARRAY_DUMP => {
+                let id = reader.read_id()?;
+                reader.read_u4()?;
+                let length = reader.read_u4()?;
+                let element_type = reader.read_u1()?;
+                let elements = (0..length)
+                    .map(|_| reader.read_value(element_type))
+                    .collect::<Result<Vec<HprofValue>>>()?;
+                let array = HprofArray::Primitive {
+                    element_type,
+                    elements,
+                };
+                self.arrays.insert(id, array);
+            }
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("unknown heap dump sub record {:#x}", tag),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// 类名使用`.`分隔，例如java.lang.String
+    pub fn class_id(&self, class_name: &str) -> Option<u64> {
+        self.classes
+            .iter()
+            .find(|(_, class)| class.name == class_name)
+            .map(|(id, _)| *id)
+    }
+
+    pub fn instances_of(&self, class_name: &str) -> Vec<u64> {
+        let Some(class_id) = self.class_id(class_name) else {
+            return vec![];
+        };
+        let mut ids: Vec<u64> = self
+            .instances
+            .iter()
+            .filter(|(_, instance)| instance.class_id == class_id)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// 按照子类到父类的顺序解析实例数据，返回第一个同名字段的值
+    pub fn field_value(&self, object_id: u64, field_name: &str) -> Option<HprofValue> {
+        let instance = self.instances.get(&object_id)?;
+        let mut reader = HprofReader {
+            bytes: &instance.data,
+            position: 0,
+            id_size: self.id_size,
+        };
+        let mut class_id = instance.class_id;
+        while let Some(class) = self.classes.get(&class_id) {
+            for (name, basic_type) in &class.instance_fields {
+                let value = reader.read_value(*basic_type).ok()?;
+                if name == field_name {
+                    return Some(value);
+                }
+            }
+            class_id = class.super_class_id;
+        }
+        None
+    }
+
+    pub fn static_value(&self, class_name: &str, field_name: &str) -> Option<HprofValue> {
+        let class = self.classes.get(&self.class_id(class_name)?)?;
+        class
+            .static_fields
+            .iter()
+            .find(|(name, _)| name == field_name)
+            .map(|(_, value)| value.clone())
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_write_heap_dump() {
+        use crate::hprof::{HprofArray, HprofDump, HprofValue, TYPE_INT, TYPE_OBJECT};
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        vm.call(
+            call_stack,
+            "HeapDumpTest",
+            "build",
+            "(I)V",
+            None,
+            vec![Value::Int(3)],
+        )
+        .unwrap();
+        //基本类型的Class对象只被静态区引用，同样作为根输出
+        let int_class = vm
+            .get_primitive_class_object(call_stack, "int")
+            .unwrap()
+            .unwrap();
+
+        let mut bytes = vec![];
+        vm.write_heap_dump(&[call_stack], &mut bytes).unwrap();
+        let dump = HprofDump::parse(&bytes).unwrap();
+        assert_eq!(dump.id_size, 8);
+        assert_eq!(dump.instances_of("HeapDumpTest").len(), 3);
+        assert!(dump
+            .instances
+            .contains_key(&super::reference_id(&Value::ObjectRef(int_class))));
+
+        //沿着静态字段head遍历链表
+        let mut node = dump.static_value("HeapDumpTest", "head");
+        let mut values = vec![];
+        while let Some(HprofValue::Object(id)) = node {
+            if id == 0 {
+                break;
+            }
+            values.push(dump.field_value(id, "value").unwrap());
+            match dump.field_value(id, "data") {
+                Some(HprofValue::Object(data_id)) => match &dump.arrays[&data_id] {
+                    HprofArray::Primitive {
+                        element_type,
+                        elements,
+                    } => {
+                        assert_eq!(*element_type, TYPE_INT);
+                        assert_eq!(elements[1], HprofValue::Int(values.len() as i32 * 2));
+                    }
+                    array => panic!("unexpected {:?}", array),
+                },
+                value => panic!("unexpected {:?}", value),
+            }
+            node = dump.field_value(id, "next");
+        }
+        assert_eq!(
+            values,
+            vec![HprofValue::Int(1), HprofValue::Int(2), HprofValue::Int(3)]
+        );
+
+        let Some(HprofValue::Object(head)) = dump.static_value("HeapDumpTest", "head") else {
+            panic!("head not dumped");
+        };
+        let Some(HprofValue::Object(refs)) = dump.field_value(head, "refs") else {
+            panic!("refs not dumped");
+        };
+        match &dump.arrays[&refs] {
+            HprofArray::Object {
+                array_class_id,
+                elements,
+            } => {
+                assert_eq!(dump.classes[array_class_id].name, "[Ljava.lang.Object;");
+                assert_eq!(
+                    Some(HprofValue::Object(elements[0])),
+                    dump.field_value(head, "next")
+                );
+                let string_class = dump.instances[&elements[1]].class_id;
+                assert_eq!(dump.classes[&string_class].name, "java.lang.String");
+            }
+            array => panic!("unexpected {:?}", array),
+        }
+
+        //所有引用都指向转储中的对象、数组或类
+        for instance in dump.instances.values() {
+            let mut class_id = instance.class_id;
+            let mut offset = 0;
+            while let Some(class) = dump.classes.get(&class_id) {
+                for (_, basic_type) in &class.instance_fields {
+                    if *basic_type == TYPE_OBJECT {
+                        let id = u64::from_be_bytes(
+                            instance.data[offset..offset + 8].try_into().unwrap(),
+                        );
+                        assert!(
+                            id == 0
+                                || dump.instances.contains_key(&id)
+                                || dump.arrays.contains_key(&id)
+                                || dump.classes.contains_key(&id),
+                            "dangling reference {:#x}",
+                            id
+                        );
+                    }
+                    offset += super::type_size(*basic_type, dump.id_size);
+                }
+                class_id = class.super_class_id;
+            }
+            assert_eq!(offset, instance.data.len());
+        }
+    }
+}
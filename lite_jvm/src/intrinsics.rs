@@ -0,0 +1,752 @@
+use crate::java_exception::{InvokeMethodResult, MethodCallError};
+use crate::jvm_error::VmError;
+use crate::jvm_values::Value;
+use crate::native_method_area::{NativeMethod, NativeMethodArea};
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+use std::collections::{HashMap, HashSet};
+
+//生成参数和返回值都是基本类型的intrinsic，参数依次用对应的get方法取出
+macro_rules! generate_intrinsic {
+    ($name:ident, |$($arg:ident: $getter:ident),+| $ret:ident($body:expr)) => {
+        fn $name<'a>(
+            _vm: &mut VirtualMachine<'a>,
+            _call_stack: &mut CallStack<'a>,
+            _receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            let mut args = args.iter();
+            $(let $arg = args.next().ok_or(VmError::ValueTypeMissMatch)?.$getter()?;)+
+            Ok(Some(Value::$ret($body)))
+        }
+    };
+}
+
+generate_intrinsic!(integer_number_of_leading_zeros, |i: get_int| Int(
+    i.leading_zeros() as i32
+));
+generate_intrinsic!(integer_number_of_trailing_zeros, |i: get_int| Int(
+    i.trailing_zeros() as i32
+));
+generate_intrinsic!(integer_bit_count, |i: get_int| Int(i.count_ones() as i32));
+//i & (MIN_VALUE >>> numberOfLeadingZeros(i))，i为0时移位32位按0x1f取模
+generate_intrinsic!(integer_highest_one_bit, |i: get_int| Int(
+    i & ((i32::MIN as u32) >> (i.leading_zeros() & 0x1f)) as i32
+));
+generate_intrinsic!(integer_lowest_one_bit, |i: get_int| Int(
+    i & i.wrapping_neg()
+));
+generate_intrinsic!(integer_rotate_left, |i: get_int, distance: get_int| Int(
+    i.rotate_left((distance & 0x1f) as u32)
+));
+generate_intrinsic!(integer_rotate_right, |i: get_int, distance: get_int| Int(
+    i.rotate_right((distance & 0x1f) as u32)
+));
+generate_intrinsic!(integer_reverse, |i: get_int| Int(i.reverse_bits()));
+generate_intrinsic!(integer_reverse_bytes, |i: get_int| Int(i.swap_bytes()));
+generate_intrinsic!(integer_signum, |i: get_int| Int(i.signum()));
+
+generate_intrinsic!(long_number_of_leading_zeros, |l: get_long| Int(
+    l.leading_zeros() as i32
+));
+generate_intrinsic!(long_number_of_trailing_zeros, |l: get_long| Int(
+    l.trailing_zeros() as i32
+));
+generate_intrinsic!(long_bit_count, |l: get_long| Int(l.count_ones() as i32));
+generate_intrinsic!(long_highest_one_bit, |l: get_long| Long(
+    l & ((i64::MIN as u64) >> (l.leading_zeros() & 0x3f)) as i64
+));
+generate_intrinsic!(long_lowest_one_bit, |l: get_long| Long(
+    l & l.wrapping_neg()
+));
+generate_intrinsic!(long_rotate_left, |l: get_long, distance: get_int| Long(
+    l.rotate_left((distance & 0x3f) as u32)
+));
+generate_intrinsic!(long_rotate_right, |l: get_long, distance: get_int| Long(
+    l.rotate_right((distance & 0x3f) as u32)
+));
+generate_intrinsic!(long_reverse, |l: get_long| Long(l.reverse_bits()));
+generate_intrinsic!(long_reverse_bytes, |l: get_long| Long(l.swap_bytes()));
+generate_intrinsic!(long_signum, |l: get_long| Int(l.signum() as i32));
+
+generate_intrinsic!(math_max_int, |a: get_int, b: get_int| Int(a.max(b)));
+generate_intrinsic!(math_min_int, |a: get_int, b: get_int| Int(a.min(b)));
+generate_intrinsic!(math_max_long, |a: get_long, b: get_long| Long(a.max(b)));
+generate_intrinsic!(math_min_long, |a: get_long, b: get_long| Long(a.min(b)));
+generate_intrinsic!(math_max_float, |a: get_float, b: get_float| Float(
+    java_max_float(a, b)
+));
+generate_intrinsic!(math_min_float, |a: get_float, b: get_float| Float(
+    java_min_float(a, b)
+));
+generate_intrinsic!(math_max_double, |a: get_double, b: get_double| Double(
+    java_max_double(a, b)
+));
+generate_intrinsic!(math_min_double, |a: get_double, b: get_double| Double(
+    java_min_double(a, b)
+));
+
+//和Math.max/min的实现保持一致：a为NaN时返回a，b为NaN时比较失败返回b，-0.0小于0.0
+//https://docs.oracle.com/javase/8/docs/api/java/lang/Math.html#max-double-double-
+macro_rules! generate_java_max_min {
+    ($max:ident, $min:ident, $t:ty) => {
+        fn $max(a: $t, b: $t) -> $t {
+            if a.is_nan() {
+                return a;
+            }
+            if a == 0.0 && b == 0.0 && a.to_bits() == (-0.0 as $t).to_bits() {
+                return b;
+            }
+            if a >= b {
+                a
+            } else {
+                b
+            }
+        }
+
+        fn $min(a: $t, b: $t) -> $t {
+            if a.is_nan() {
+                return a;
+            }
+            if a == 0.0 && b == 0.0 && b.to_bits() == (-0.0 as $t).to_bits() {
+                return b;
+            }
+            if a <= b {
+                a
+            } else {
+                b
+            }
+        }
+    };
+}
+
+generate_java_max_min!(java_max_float, java_min_float, f32);
+generate_java_max_min!(java_max_double, java_min_double, f64);
+
+//和Integer.parseInt(String, int)、Long.parseLong(String, int)逐位计算的方式一致：按负数累加，
+//这样MIN_VALUE不会溢出。直接读取String的value数组，ASCII以外的字符才调用Character.digit，
+//因此空白字符、单独的正负号和空字符串都抛出NumberFormatException
+fn parse_integer<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    text: &Value<'a>,
+    radix: i32,
+    min_value: i64,
+    max_value: i64,
+) -> Result<i64, MethodCallError<'a>> {
+    let string = match text {
+        Value::Null => return Err(number_format_exception(vm, call_stack, "null")),
+        value => value.get_object()?,
+    };
+    if radix < 2 {
+        let message = format!("radix {} less than Character.MIN_RADIX", radix);
+        return Err(number_format_exception(vm, call_stack, &message));
+    }
+    if radix > 36 {
+        let message = format!("radix {} greater than Character.MAX_RADIX", radix);
+        return Err(number_format_exception(vm, call_stack, &message));
+    }
+    let radix = radix as i64;
+    let chars = vm.get_string_chars(string)?;
+    let (negative, digits) = match chars.first() {
+        Some(0x2d) => (true, &chars[1..]),
+        Some(0x2b) => (false, &chars[1..]),
+        _ => (false, &chars[..]),
+    };
+    let limit = if negative { min_value } else { -max_value };
+    let multiply_min = limit / radix;
+    let mut result = 0i64;
+    let mut valid = !digits.is_empty();
+    for c in digits {
+        let digit = match char::from_u32(*c as u32).filter(char::is_ascii) {
+            Some(c) => c.to_digit(radix as u32).map_or(-1, |d| d as i64),
+            None => {
+                let args = vec![Value::Int(*c as i32), Value::Int(radix as i32)];
+                let digit = vm.call(
+                    call_stack,
+                    "java/lang/Character",
+                    "digit",
+                    "(CI)I",
+                    None,
+                    args,
+                )?;
+                digit.ok_or(VmError::ValueTypeMissMatch)?.get_int()? as i64
+            }
+        };
+        if digit < 0 || result < multiply_min || result * radix < limit + digit {
+            valid = false;
+            break;
+        }
+        result = result * radix - digit;
+    }
+    if valid {
+        Ok(if negative { result } else { -result })
+    } else {
+        let message = format!("For input string: \"{}\"", String::from_utf16_lossy(&chars));
+        Err(number_format_exception(vm, call_stack, &message))
+    }
+}
+
+fn number_format_exception<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    message: &str,
+) -> MethodCallError<'a> {
+    match vm.new_exception(call_stack, "java/lang/NumberFormatException", message) {
+        Ok(exception) => MethodCallError::ExceptionThrown(exception),
+        Err(e) => e,
+    }
+}
+
+macro_rules! generate_parse_intrinsic {
+    ($name:ident, $variant:ident, $t:ty, $default_radix:expr) => {
+        fn $name<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            _receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            let radix = match args.get(1) {
+                Some(radix) => radix.get_int()?,
+                None => $default_radix,
+            };
+            let value = parse_integer(
+                vm,
+                call_stack,
+                &args[0],
+                radix,
+                <$t>::MIN as i64,
+                <$t>::MAX as i64,
+            )?;
+            Ok(Some(Value::$variant(value as $t)))
+        }
+    };
+}
+
+generate_parse_intrinsic!(integer_parse_int, Int, i32, 10);
+generate_parse_intrinsic!(long_parse_long, Long, i64, 10);
+
+/// 热点JDK方法的Rust实现。invoke_method在创建栈帧之前查找，命中时直接计算结果，
+/// 因此每个intrinsic的结果都必须和执行对应的字节码完全一致
+pub struct Intrinsics<'a> {
+    methods: HashMap<(&'static str, &'static str, &'static str), NativeMethod<'a>>,
+    //存在intrinsic的类，避免每次调用方法都计算三个字符串的hash
+    classes: HashSet<&'static str>,
+}
+
+impl<'a> Intrinsics<'a> {
+    pub fn new_with_default() -> Intrinsics<'a> {
+        let mut intrinsics = Intrinsics {
+            methods: HashMap::new(),
+            classes: HashSet::new(),
+        };
+        let methods: [(&str, &str, &str, NativeMethod<'a>); 35] = [
+            (
+                "java/lang/Integer",
+                "numberOfLeadingZeros",
+                "(I)I",
+                integer_number_of_leading_zeros,
+            ),
+            (
+                "java/lang/Integer",
+                "numberOfTrailingZeros",
+                "(I)I",
+                integer_number_of_trailing_zeros,
+            ),
+            ("java/lang/Integer", "bitCount", "(I)I", integer_bit_count),
+            (
+                "java/lang/Integer",
+                "highestOneBit",
+                "(I)I",
+                integer_highest_one_bit,
+            ),
+            (
+                "java/lang/Integer",
+                "lowestOneBit",
+                "(I)I",
+                integer_lowest_one_bit,
+            ),
+            (
+                "java/lang/Integer",
+                "rotateLeft",
+                "(II)I",
+                integer_rotate_left,
+            ),
+            (
+                "java/lang/Integer",
+                "rotateRight",
+                "(II)I",
+                integer_rotate_right,
+            ),
+            ("java/lang/Integer", "reverse", "(I)I", integer_reverse),
+            (
+                "java/lang/Integer",
+                "reverseBytes",
+                "(I)I",
+                integer_reverse_bytes,
+            ),
+            ("java/lang/Integer", "signum", "(I)I", integer_signum),
+            (
+                "java/lang/Long",
+                "numberOfLeadingZeros",
+                "(J)I",
+                long_number_of_leading_zeros,
+            ),
+            (
+                "java/lang/Long",
+                "numberOfTrailingZeros",
+                "(J)I",
+                long_number_of_trailing_zeros,
+            ),
+            ("java/lang/Long", "bitCount", "(J)I", long_bit_count),
+            (
+                "java/lang/Long",
+                "highestOneBit",
+                "(J)J",
+                long_highest_one_bit,
+            ),
+            (
+                "java/lang/Long",
+                "lowestOneBit",
+                "(J)J",
+                long_lowest_one_bit,
+            ),
+            ("java/lang/Long", "rotateLeft", "(JI)J", long_rotate_left),
+            ("java/lang/Long", "rotateRight", "(JI)J", long_rotate_right),
+            ("java/lang/Long", "reverse", "(J)J", long_reverse),
+            ("java/lang/Long", "reverseBytes", "(J)J", long_reverse_bytes),
+            ("java/lang/Long", "signum", "(J)I", long_signum),
+            (
+                "java/lang/Integer",
+                "parseInt",
+                "(Ljava/lang/String;)I",
+                integer_parse_int,
+            ),
+            (
+                "java/lang/Integer",
+                "parseInt",
+                "(Ljava/lang/String;I)I",
+                integer_parse_int,
+            ),
+            (
+                "java/lang/Long",
+                "parseLong",
+                "(Ljava/lang/String;)J",
+                long_parse_long,
+            ),
+            (
+                "java/lang/Long",
+                "parseLong",
+                "(Ljava/lang/String;I)J",
+                long_parse_long,
+            ),
+            ("java/lang/Math", "max", "(II)I", math_max_int),
+            ("java/lang/Math", "min", "(II)I", math_min_int),
+            ("java/lang/Math", "max", "(JJ)J", math_max_long),
+            ("java/lang/Math", "min", "(JJ)J", math_min_long),
+            ("java/lang/Math", "max", "(FF)F", math_max_float),
+            ("java/lang/Math", "min", "(FF)F", math_min_float),
+            ("java/lang/Math", "max", "(DD)D", math_max_double),
+            ("java/lang/Math", "min", "(DD)D", math_min_double),
+            (
+                "java/lang/System",
+                "nanoTime",
+                "()J",
+                NativeMethodArea::java_lang_system_nano_time,
+            ),
+            (
+                "java/lang/System",
+                "currentTimeMillis",
+                "()J",
+                NativeMethodArea::java_lang_system_current_time_millis,
+            ),
+            (
+                "java/lang/Object",
+                "getClass",
+                "()Ljava/lang/Class;",
+                NativeMethodArea::java_lang_object_get_class,
+            ),
+        ];
+        for (class_name, method_name, descriptor, method) in methods {
+            intrinsics.registry(class_name, method_name, descriptor, method);
+        }
+        intrinsics
+    }
+
+    pub fn registry(
+        &mut self,
+        class_name: &'static str,
+        method_name: &'static str,
+        method_descriptor: &'static str,
+        method: NativeMethod<'a>,
+    ) {
+        self.classes.insert(class_name);
+        self.methods
+            .insert((class_name, method_name, method_descriptor), method);
+    }
+
+    pub fn get(
+        &self,
+        class_name: &str,
+        method_name: &str,
+        method_descriptor: &str,
+    ) -> Option<NativeMethod<'a>> {
+        if !self.classes.contains(class_name) {
+            return None;
+        }
+        self.methods
+            .get(&(class_name, method_name, method_descriptor))
+            .copied()
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_intrinsics_match_bytecode() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let methods = [
+            ("java/lang/Integer", "numberOfLeadingZeros", "(I)I"),
+            ("java/lang/Integer", "numberOfTrailingZeros", "(I)I"),
+            ("java/lang/Integer", "bitCount", "(I)I"),
+            ("java/lang/Integer", "highestOneBit", "(I)I"),
+            ("java/lang/Integer", "lowestOneBit", "(I)I"),
+            ("java/lang/Integer", "rotateLeft", "(II)I"),
+            ("java/lang/Integer", "rotateRight", "(II)I"),
+            ("java/lang/Integer", "reverse", "(I)I"),
+            ("java/lang/Integer", "reverseBytes", "(I)I"),
+            ("java/lang/Integer", "signum", "(I)I"),
+            ("java/lang/Long", "numberOfLeadingZeros", "(J)I"),
+            ("java/lang/Long", "numberOfTrailingZeros", "(J)I"),
+            ("java/lang/Long", "bitCount", "(J)I"),
+            ("java/lang/Long", "highestOneBit", "(J)J"),
+            ("java/lang/Long", "lowestOneBit", "(J)J"),
+            ("java/lang/Long", "rotateLeft", "(JI)J"),
+            ("java/lang/Long", "rotateRight", "(JI)J"),
+            ("java/lang/Long", "reverse", "(J)J"),
+            ("java/lang/Long", "reverseBytes", "(J)J"),
+            ("java/lang/Long", "signum", "(J)I"),
+            ("java/lang/Math", "max", "(II)I"),
+            ("java/lang/Math", "min", "(II)I"),
+            ("java/lang/Math", "max", "(JJ)J"),
+            ("java/lang/Math", "min", "(JJ)J"),
+            ("java/lang/Math", "max", "(FF)F"),
+            ("java/lang/Math", "min", "(FF)F"),
+            ("java/lang/Math", "max", "(DD)D"),
+            ("java/lang/Math", "min", "(DD)D"),
+        ];
+        //xorshift，每次运行生成相同的输入。前几轮使用边界值
+        let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed
+        };
+        let mut random_arg = |descriptor: char, round: usize| -> Value {
+            let bits = random();
+            match descriptor {
+                'I' => Value::Int(
+                    *[0, 1, -1, i32::MIN, i32::MAX, 32, -33]
+                        .get(round)
+                        .unwrap_or(&(bits as i32)),
+                ),
+                'J' => Value::Long(
+                    *[0, 1, -1, i64::MIN, i64::MAX, 64, -65]
+                        .get(round)
+                        .unwrap_or(&(bits as i64)),
+                ),
+                'F' => Value::Float(
+                    *[
+                        0f32,
+                        -0f32,
+                        f32::NAN,
+                        f32::INFINITY,
+                        f32::NEG_INFINITY,
+                        1.5,
+                        -1.5,
+                    ]
+                    .get(round)
+                    .unwrap_or(&f32::from_bits(bits as u32)),
+                ),
+                'D' => Value::Double(
+                    *[
+                        0f64,
+                        -0f64,
+                        f64::NAN,
+                        f64::INFINITY,
+                        f64::NEG_INFINITY,
+                        1.5,
+                        -1.5,
+                    ]
+                    .get(round)
+                    .unwrap_or(&f64::from_bits(bits)),
+                ),
+                _ => unreachable!(),
+            }
+        };
+        //浮点数按位比较，NaN也必须完全一致
+        let bits = |value: &Value| match value {
+            Value::Int(v) => *v as u64,
+            Value::Long(v) => *v as u64,
+            Value::Float(v) => v.to_bits() as u64,
+            Value::Double(v) => v.to_bits(),
+            _ => panic!("unexpected value {:?}", value),
+        };
+
+        let mut interpreted = create_vm().with_intrinsics(false);
+        let mut intrinsic = create_vm();
+        let interpreted_stack = &mut interpreted.create_call_stack();
+        let intrinsic_stack = &mut intrinsic.create_call_stack();
+        for (class_name, method_name, descriptor) in methods {
+            let interpreted_class = interpreted
+                .lookup_class_and_initialize(interpreted_stack, class_name)
+                .unwrap();
+            let intrinsic_class = intrinsic
+                .lookup_class_and_initialize(intrinsic_stack, class_name)
+                .unwrap();
+            let interpreted_method = interpreted_class
+                .get_method(method_name, descriptor)
+                .unwrap();
+            let intrinsic_method = intrinsic_class.get_method(method_name, descriptor).unwrap();
+            let arg_types: Vec<char> = descriptor[1..descriptor.find(')').unwrap()]
+                .chars()
+                .collect();
+            for round in 0..200 {
+                let args: Vec<Value> = arg_types
+                    .iter()
+                    .map(|arg_type| random_arg(*arg_type, round))
+                    .collect();
+                let expected = interpreted
+                    .invoke_method(
+                        interpreted_stack,
+                        interpreted_class,
+                        interpreted_method,
+                        None::<ObjectReference>,
+                        args.clone(),
+                    )
+                    .unwrap()
+                    .unwrap();
+                let actual = intrinsic
+                    .invoke_method(
+                        intrinsic_stack,
+                        intrinsic_class,
+                        intrinsic_method,
+                        None::<ObjectReference>,
+                        args.clone(),
+                    )
+                    .unwrap()
+                    .unwrap();
+                assert_eq!(
+                    bits(&expected),
+                    bits(&actual),
+                    "{}.{}{} {:?}: {:?} != {:?}",
+                    class_name,
+                    method_name,
+                    descriptor,
+                    args,
+                    expected,
+                    actual
+                );
+            }
+        }
+        //intrinsic不创建栈帧
+        assert_eq!(intrinsic_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_time_intrinsics() {
+        use crate::jvm_values::ObjectReference;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "java/lang/System")
+            .unwrap();
+        let mut invoke = |method_name: &str| {
+            let method_ref = class_ref.get_method(method_name, "()J").unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            )
+            .unwrap()
+            .unwrap()
+            .get_long()
+            .unwrap()
+        };
+        let first = invoke("nanoTime");
+        let second = invoke("nanoTime");
+        assert!(first <= second);
+        //2020-01-01之后
+        assert!(invoke("currentTimeMillis") > 1_577_836_800_000);
+    }
+
+    //cargo test --release -p lite_jvm bench_intrinsics -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_intrinsics() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+        use std::time::Instant;
+        for enabled in [false, true] {
+            let mut vm = create_vm().with_intrinsics(enabled);
+            let call_stack = &mut vm.create_call_stack();
+            for (class_name, method_name, descriptor, args) in [
+                (
+                    "java/lang/Integer",
+                    "bitCount",
+                    "(I)I",
+                    vec![Value::Int(0x1234_5678)],
+                ),
+                (
+                    "java/lang/Long",
+                    "numberOfTrailingZeros",
+                    "(J)I",
+                    vec![Value::Long(1 << 40)],
+                ),
+                (
+                    "java/lang/Math",
+                    "max",
+                    "(DD)D",
+                    vec![Value::Double(-0f64), Value::Double(0f64)],
+                ),
+            ] {
+                let class_ref = vm
+                    .lookup_class_and_initialize(call_stack, class_name)
+                    .unwrap();
+                let method_ref = class_ref.get_method(method_name, descriptor).unwrap();
+                let iterations = 100_000;
+                let start = Instant::now();
+                for _ in 0..iterations {
+                    vm.invoke_method(
+                        call_stack,
+                        class_ref,
+                        method_ref,
+                        None::<ObjectReference>,
+                        args.clone(),
+                    )
+                    .unwrap();
+                }
+                tracing::info!(
+                    enabled,
+                    class = class_name,
+                    method = method_name,
+                    descriptor,
+                    per_call = ?(start.elapsed() / iterations),
+                    "intrinsics benchmark"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_intrinsics_match_bytecode() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::stack::CallStack;
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        use crate::virtual_machine::VirtualMachine;
+        let texts = [
+            Some("0"),
+            Some("-0"),
+            Some("+42"),
+            Some("-2147483648"),
+            Some("2147483647"),
+            Some("2147483648"),
+            Some("-2147483649"),
+            Some("-9223372036854775808"),
+            Some("9223372036854775807"),
+            Some("9223372036854775808"),
+            Some("00000000000000000000000000000000001"),
+            Some(""),
+            Some("-"),
+            Some("+"),
+            Some(" 1"),
+            Some("1 "),
+            Some("1_000"),
+            Some("7fffffff"),
+            Some("-80000000"),
+            Some("ZZ"),
+            Some("1010"),
+            //Character.digit支持ASCII以外的数字
+            Some("\u{661}\u{662}"),
+            Some("\u{ff11}\u{ff41}"),
+            None,
+        ];
+        let methods = [
+            ("java/lang/Integer", "parseInt", "(Ljava/lang/String;I)I"),
+            ("java/lang/Long", "parseLong", "(Ljava/lang/String;I)J"),
+            ("java/lang/Integer", "parseInt", "(Ljava/lang/String;)I"),
+            ("java/lang/Long", "parseLong", "(Ljava/lang/String;)J"),
+        ];
+        let mut interpreted = create_vm_with(1024000, RESOURCES_DIR, true).with_intrinsics(false);
+        let mut intrinsic = create_vm_with(1024000, RESOURCES_DIR, true);
+        let interpreted_stack = &mut interpreted.create_call_stack();
+        let intrinsic_stack = &mut intrinsic.create_call_stack();
+        //异常只比较第一行，解释执行时的调用栈多了JDK内部的栈帧
+        fn parse<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            method: (&str, &str, &str),
+            text: Option<&str>,
+            radix: i32,
+        ) -> String {
+            let mut args = vec![match text {
+                Some(text) => {
+                    Value::ObjectRef(vm.new_java_lang_string_object(call_stack, text).unwrap())
+                }
+                None => Value::Null,
+            }];
+            if method.2.contains(";I)") {
+                args.push(Value::Int(radix));
+            }
+            match vm.call(call_stack, method.0, method.1, method.2, None, args) {
+                Ok(value) => format!("{:?}", value.unwrap()),
+                Err(MethodCallError::ExceptionThrown(exception)) => {
+                    let formatted = vm.format_exception(exception);
+                    formatted.lines().next().unwrap().to_string()
+                }
+                Err(e) => panic!("{:?} {:?} failed with {:?}", method, text, e),
+            }
+        }
+        for method in methods {
+            for radix in [10, 2, 16, 36, 1, 37] {
+                for text in texts {
+                    let expected = parse(&mut interpreted, interpreted_stack, method, text, radix);
+                    let actual = parse(&mut intrinsic, intrinsic_stack, method, text, radix);
+                    assert_eq!(expected, actual, "{:?} {:?} radix {}", method, text, radix);
+                }
+            }
+        }
+        assert_eq!(
+            parse(
+                &mut intrinsic,
+                intrinsic_stack,
+                methods[0],
+                Some("-2147483648"),
+                10
+            ),
+            format!("{:?}", Value::Int(i32::MIN))
+        );
+        assert_eq!(
+            parse(
+                &mut intrinsic,
+                intrinsic_stack,
+                methods[3],
+                Some("2147483648"),
+                10
+            ),
+            format!("{:?}", Value::Long(2147483648))
+        );
+        assert_eq!(
+            parse(&mut intrinsic, intrinsic_stack, methods[2], Some(""), 10),
+            "java.lang.NumberFormatException: For input string: \"\""
+        );
+        assert_eq!(intrinsic_stack.depth(), 0);
+    }
+}
@@ -5,6 +5,12 @@ use crate::jvm_values::{ObjectReference, Value};
 pub enum MethodCallError<'a> {
     InternalError(VmError),
     ExceptionThrown(ObjectReference<'a>),
+    //调用栈的指令预算耗尽。不是Java异常，不经过异常表，Java代码无法捕获
+    BudgetExceeded,
+    //外部设置了调用栈的取消标志，和BudgetExceeded一样直接返回到宿主
+    Cancelled,
+    //Java代码调用了System.exit或者Runtime.halt，展开所有栈帧，携带退出状态返回到宿主
+    VmExit(i32),
 }
 
 impl<'a> From<VmError> for MethodCallError<'a> {
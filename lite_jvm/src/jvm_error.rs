@@ -3,8 +3,8 @@ use thiserror::Error;
 
 #[derive(Error, Debug, Clone, PartialEq)]
 pub enum VmError {
-    #[error("ClassFormatError")]
-    ClassFormatError,
+    #[error("ClassFormatError {0}")]
+    ClassFormatError(String),
     #[error("ClassNotFoundException {0}")]
     ClassNotFoundException(String),
     #[error("MethodNotFoundException {0} {1}")]
@@ -41,11 +41,31 @@ pub enum VmError {
     ArithmeticException,
     #[error("NotImplemented error")]
     NotImplemented,
+    #[error("LinkageError {0}")]
+    LinkageError(String),
+    #[error("VerifyError {0}")]
+    VerifyError(String),
+    #[error("UnsatisfiedLinkError {0}")]
+    UnsatisfiedLinkError(String),
+    #[error("InvalidDescriptor {0}")]
+    InvalidDescriptor(String),
+    #[error("FieldTypeMismatch {field} expected {expected} but was {actual}")]
+    FieldTypeMismatch {
+        field: String,
+        expected: String,
+        actual: String,
+    },
+    #[error("OutOfMemory requested {0} bytes, {1} bytes free")]
+    OutOfMemory(usize, usize),
+    #[error("AmbiguousMethodCall {0} {1:?}")]
+    AmbiguousMethodCall(String, Vec<String>),
+    #[error("ClassCircularityError {}", .0.join(" -> "))]
+    ClassCircularity(Vec<String>),
 }
 
 pub type VmExecResult<T> = Result<T, VmError>;
 
-impl<'a> From<ClassFileError> for VmError {
+impl From<ClassFileError> for VmError {
     fn from(value: ClassFileError) -> Self {
         VmError::ReadClassBytesError(value.to_string())
     }
@@ -1,7 +1,11 @@
+use crate::java_exception::MethodCallError;
 use crate::jvm_error::{VmError, VmExecResult};
 use crate::loaded_class::{ClassRef, FieldRef};
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
 
 use bitfield_struct::bitfield;
+use std::fmt::{Debug, Display, Formatter};
 use std::marker::PhantomData;
 use std::mem::size_of;
 
@@ -22,7 +26,7 @@ pub enum PrimaryType {
     Boolean,
 }
 
-#[derive(Debug, Default, Clone, PartialEq)]
+#[derive(Default, Clone, PartialEq)]
 pub enum Value<'a> {
     #[default]
     Uninitialized,
@@ -36,6 +40,9 @@ pub enum Value<'a> {
     Null,
 }
 
+const OBJECT_DESCRIPTOR: &str = "Ljava/lang/Object;";
+const STRING_DESCRIPTOR: &str = "Ljava/lang/String;";
+
 macro_rules! generate_get_value {
     ($name:ident, $variant:ident, $type:ty) => {
         pub fn $name(&self) -> VmExecResult<$type> {
@@ -55,6 +62,29 @@ impl<'a> Value<'a> {
     generate_get_value!(get_double, Double, f64);
     generate_get_value!(get_object, ObjectRef, ObjectReference<'a>);
     generate_get_value!(get_array, ArrayRef, ArrayReference<'a>);
+    //long和double是第二类计算类型，占两个字，在局部变量表中占两个槽位，pop2/dup2时单独作为一个整体
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.1
+    pub fn category(&self) -> u8 {
+        match self {
+            Value::Long(_) | Value::Double(_) => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn is_category_two(&self) -> bool {
+        self.category() == 2
+    }
+
+    //派生的PartialEq按IEEE 754比较浮点数，NaN不等于自身而0.0等于-0.0，和fcmp/dcmp一致。
+    //需要按值的身份比较（例如作为缓存的键、比较执行结果）时使用bitwise_eq，浮点数比较位模式
+    pub fn bitwise_eq(&self, other: &Value<'_>) -> bool {
+        match (self, other) {
+            (Value::Float(a), Value::Float(b)) => a.to_bits() == b.to_bits(),
+            (Value::Double(a), Value::Double(b)) => a.to_bits() == b.to_bits(),
+            _ => self == other,
+        }
+    }
+
     pub fn get_string(&self) -> VmExecResult<String> {
         let string_object = self.get_object()?;
         assert_eq!(string_object.get_class().name, "java/lang/String");
@@ -67,6 +97,20 @@ impl<'a> Value<'a> {
             .collect();
         Ok(String::from_utf16_lossy(&bytes))
     }
+
+    //值的类型是否和字段描述符一致，引用类型只校验是对象还是数组
+    pub fn matches_descriptor(&self, descriptor: &str) -> bool {
+        match descriptor.as_bytes().first() {
+            Some(b'B' | b'C' | b'I' | b'S' | b'Z') => matches!(self, Value::Int(_)),
+            Some(b'J') => matches!(self, Value::Long(_)),
+            Some(b'F') => matches!(self, Value::Float(_)),
+            Some(b'D') => matches!(self, Value::Double(_)),
+            Some(b'[') => matches!(self, Value::ArrayRef(_) | Value::Null),
+            //数组也可以赋值给Object、Cloneable和Serializable
+            Some(b'L') => matches!(self, Value::ObjectRef(_) | Value::ArrayRef(_) | Value::Null),
+            _ => false,
+        }
+    }
 }
 #[derive(Debug, Clone, PartialEq)]
 pub enum ValueType {
@@ -78,6 +122,63 @@ pub enum ValueType {
     Void,
 }
 
+impl ValueType {
+    /// 解析字段描述符，例如I、Ljava/lang/String;、[[J
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.3.2
+    pub fn from_field_descriptor(descriptor: &str) -> VmExecResult<ValueType> {
+        let invalid = || VmError::InvalidDescriptor(descriptor.to_string());
+        let dimension = descriptor.bytes().take_while(|b| *b == b'[').count();
+        //数组最多255维
+        if dimension > 255 {
+            return Err(invalid());
+        }
+        let element = &descriptor[dimension..];
+        let primary_type = match element {
+            "B" => PrimaryType::Byte,
+            "C" => PrimaryType::Char,
+            "D" => PrimaryType::Double,
+            "F" => PrimaryType::Float,
+            "I" => PrimaryType::Int,
+            "J" => PrimaryType::Long,
+            "S" => PrimaryType::Short,
+            "Z" => PrimaryType::Boolean,
+            _ => {
+                let class_name = element
+                    .strip_prefix('L')
+                    .and_then(|name| name.strip_suffix(';'))
+                    .filter(|name| !name.is_empty() && !name.contains([';', '[', '.']))
+                    .ok_or_else(invalid)?
+                    .to_string();
+                return Ok(match dimension {
+                    0 => ValueType::Object(class_name),
+                    _ => ValueType::ObjectArray(class_name, dimension),
+                });
+            }
+        };
+        Ok(match dimension {
+            0 => ValueType::Primary(primary_type),
+            _ => ValueType::PrimaryArray(primary_type, dimension),
+        })
+    }
+
+    /// 数组类型的元素类型，不是数组时返回None
+    pub fn component_type(&self) -> Option<ValueType> {
+        match self {
+            ValueType::PrimaryArray(primary_type, 1) => {
+                Some(ValueType::Primary(primary_type.clone()))
+            }
+            ValueType::PrimaryArray(primary_type, dimension) => {
+                Some(ValueType::PrimaryArray(primary_type.clone(), dimension - 1))
+            }
+            ValueType::ObjectArray(class_name, 1) => Some(ValueType::Object(class_name.clone())),
+            ValueType::ObjectArray(class_name, dimension) => {
+                Some(ValueType::ObjectArray(class_name.clone(), dimension - 1))
+            }
+            _ => None,
+        }
+    }
+}
+
 pub trait ReferenceValue<'a> {
     fn ptr(&self) -> *mut u8;
     fn get_data_length(&self) -> usize;
@@ -101,14 +202,14 @@ pub trait ReferenceValue<'a> {
 }
 
 //数组引用分配
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Clone, Copy)]
 pub struct ArrayReference<'a> {
     data: *mut u8,
     _marker: PhantomData<&'a [u8]>,
 }
 
 /// 对象引用分配
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct ObjectReference<'a> {
     data: *mut u8,
     _marker: PhantomData<&'a [u8]>,
@@ -131,6 +232,22 @@ unsafe fn read_allocate_header(ptr: *const u8) -> AllocateHeader {
     unsafe { std::ptr::read(ptr as *const AllocateHeader) }
 }
 
+//根据分配头还原出ptr处分配的引用，同时返回该分配占用的大小
+pub(crate) unsafe fn read_reference_at<'a>(ptr: *mut u8) -> (Value<'a>, usize) {
+    let header = read_allocate_header(ptr);
+    let value = match header.kind() {
+        ReferenceValueType::Object => Value::ObjectRef(ObjectReference {
+            data: ptr,
+            _marker: PhantomData,
+        }),
+        ReferenceValueType::Array => Value::ArrayRef(ArrayReference {
+            data: ptr,
+            _marker: PhantomData,
+        }),
+    };
+    (value, header.size())
+}
+
 const fn align_to_8_bytes(required_size: usize) -> usize {
     match required_size % 8 {
         0 => required_size,
@@ -217,6 +334,47 @@ macro_rules! write_nullable_value_at {
     };
 }
 
+//引用字段和引用数组的元素既可以存放对象也可以存放数组，槽位里只保存指针，
+//读取时根据被引用分配的头区分是对象还是数组
+macro_rules! read_reference_value_at {
+    ($name:ident) => {
+        pub(crate) unsafe fn $name(&self, index: usize) -> VmExecResult<Value<'a>> {
+            let total_fields = self.get_data_length();
+            if index >= total_fields {
+                return Err(VmError::IndexOutOfBounds);
+            }
+            let offset = self.data_offset() + 8 * index;
+            let pointer = self.data.add(offset);
+            let data = std::ptr::read(pointer as *mut *mut u8);
+            if data.is_null() {
+                Ok(Value::Null)
+            } else {
+                Ok(read_reference_at(data).0)
+            }
+        }
+    };
+}
+
+macro_rules! write_reference_value_at {
+    ($name:ident) => {
+        pub(crate) unsafe fn $name(&self, index: usize, value: &Value<'a>) -> VmExecResult<()> {
+            let total_fields = self.get_data_length();
+            if index >= total_fields {
+                return Err(VmError::IndexOutOfBounds);
+            }
+            let offset = self.data_offset() + 8 * index;
+            let pointer = self.data.add(offset);
+            match value {
+                Value::ObjectRef(v) => std::ptr::write(pointer as *mut ObjectReference, *v),
+                Value::ArrayRef(v) => std::ptr::write(pointer as *mut ArrayReference, *v),
+                Value::Null => std::ptr::write(pointer as *mut u64, 0),
+                _ => return Err(VmError::ValueTypeMissMatch),
+            }
+            Ok(())
+        }
+    };
+}
+
 #[bitfield(u64)]
 #[derive(PartialEq, Eq)]
 pub struct AllocateHeader {
@@ -248,6 +406,7 @@ impl ReferenceValueType {
     }
 }
 
+#[derive(Clone)]
 pub enum ArrayElement<'a> {
     PrimaryValue(PrimaryType),
     ClassReference(ClassRef<'a>),
@@ -255,6 +414,73 @@ pub enum ArrayElement<'a> {
 }
 
 impl<'a> ArrayElement<'a> {
+    //Java源码中的类型名称，例如int, java.lang.String[]
+    pub fn java_type_name(&self) -> String {
+        match self {
+            ArrayElement::PrimaryValue(primary_type) => match primary_type {
+                PrimaryType::Byte => "byte".to_string(),
+                PrimaryType::Char => "char".to_string(),
+                PrimaryType::Double => "double".to_string(),
+                PrimaryType::Float => "float".to_string(),
+                PrimaryType::Int => "int".to_string(),
+                PrimaryType::Long => "long".to_string(),
+                PrimaryType::Short => "short".to_string(),
+                PrimaryType::Boolean => "boolean".to_string(),
+            },
+            ArrayElement::ClassReference(class_ref) => class_ref.name.replace('/', "."),
+            ArrayElement::Array(inner) => format!("{}[]", inner.java_type_name()),
+        }
+    }
+
+    //数组类的名称，例如[I, [Ljava/lang/String;
+    pub fn array_class_name(&self) -> String {
+        let element_descriptor = match self {
+            ArrayElement::PrimaryValue(primary_type) => match primary_type {
+                PrimaryType::Byte => "B".to_string(),
+                PrimaryType::Char => "C".to_string(),
+                PrimaryType::Double => "D".to_string(),
+                PrimaryType::Float => "F".to_string(),
+                PrimaryType::Int => "I".to_string(),
+                PrimaryType::Long => "J".to_string(),
+                PrimaryType::Short => "S".to_string(),
+                PrimaryType::Boolean => "Z".to_string(),
+            },
+            ArrayElement::ClassReference(class_ref) => format!("L{};", class_ref.name),
+            ArrayElement::Array(inner) => inner.array_class_name(),
+        };
+        format!("[{element_descriptor}")
+    }
+
+    //数组元素能否存放该值
+    pub(crate) fn accepts(&self, value: &Value<'_>) -> bool {
+        match self {
+            ArrayElement::PrimaryValue(primary_type) => match primary_type {
+                PrimaryType::Double => matches!(value, Value::Double(_)),
+                PrimaryType::Float => matches!(value, Value::Float(_)),
+                PrimaryType::Long => matches!(value, Value::Long(_)),
+                PrimaryType::Int
+                | PrimaryType::Byte
+                | PrimaryType::Char
+                | PrimaryType::Short
+                | PrimaryType::Boolean => matches!(value, Value::Int(_)),
+            },
+            //对象的类需要是元素类型的子类型，数组只能存放到元素类型为Object、Cloneable或Serializable的引用数组中
+            ArrayElement::ClassReference(class_ref) => match value {
+                Value::ObjectRef(object_ref) => object_ref.is_instance_of(class_ref),
+                Value::ArrayRef(array_ref) => {
+                    ArrayElement::Array(Box::new(array_ref.get_array_type())).is_subclass_of(self)
+                }
+                Value::Null => true,
+                _ => false,
+            },
+            ArrayElement::Array(inner) => match value {
+                Value::ArrayRef(array_ref) => array_ref.is_instance_of(inner),
+                Value::Null => true,
+                _ => false,
+            },
+        }
+    }
+
     fn is_subclass_of(&self, target_element_type: &ArrayElement<'a>) -> bool {
         match self {
             ArrayElement::PrimaryValue(my_type) => {
@@ -271,13 +497,15 @@ impl<'a> ArrayElement<'a> {
                     false
                 }
             }
-            ArrayElement::Array(inner) => {
-                if let ArrayElement::Array(target) = target_element_type {
-                    inner.is_subclass_of(target)
-                } else {
-                    false
-                }
-            }
+            //数组可以赋值给Object、Cloneable和Serializable，例如String[][]是Object[]
+            ArrayElement::Array(inner) => match target_element_type {
+                ArrayElement::Array(target) => inner.is_subclass_of(target),
+                ArrayElement::ClassReference(target) => matches!(
+                    target.name.as_str(),
+                    "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+                ),
+                ArrayElement::PrimaryValue(_) => false,
+            },
         }
     }
 }
@@ -291,10 +519,15 @@ pub struct ObjectHeader<'a> {
 }
 
 impl<'a> ArrayReference<'a> {
-    pub fn get_array_header(&self) -> ArrayHeader {
-        unsafe {
+    //多维数组的元素类型包含Box，堆上的ArrayHeader始终持有它，这里返回克隆的副本
+    pub fn get_array_header(&self) -> ArrayHeader<'a> {
+        let header = unsafe {
             let class_ref_ptr = self.data.add(ALLOC_HEADER_SIZE);
-            std::ptr::read(class_ref_ptr as *const ArrayHeader)
+            &*(class_ref_ptr as *const ArrayHeader<'a>)
+        };
+        ArrayHeader {
+            element: header.element.clone(),
+            array_size: header.array_size,
         }
     }
 
@@ -305,7 +538,7 @@ impl<'a> ArrayReference<'a> {
             .collect()
     }
 
-    pub fn get_array_type(&self) -> ArrayElement {
+    pub fn get_array_type(&self) -> ArrayElement<'a> {
         self.get_array_header().element
     }
 
@@ -313,20 +546,107 @@ impl<'a> ArrayReference<'a> {
         self.get_array_type().is_subclass_of(target_type)
     }
 
+    //元素类型和期望的描述符不一致时返回FieldTypeMismatch，field为数组下标
+    fn check_element_type(&self, index: usize, expected: &str) -> VmExecResult<()> {
+        let actual = self.get_array_type().array_class_name()[1..].to_string();
+        if actual != expected {
+            return Err(VmError::FieldTypeMismatch {
+                field: format!("[{}]", index),
+                expected: expected.to_string(),
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    /// 读取int[]的元素，下标越界时返回IndexOutOfBounds
+    pub fn get_int(&self, index: usize) -> VmExecResult<i32> {
+        self.check_element_type(index, "I")?;
+        self.get_field_by_offset(index)?.get_int()
+    }
+
+    pub fn as_i32_vec(&self) -> VmExecResult<Vec<i32>> {
+        self.check_element_type(0, "I")?;
+        self.read_all().iter().map(Value::get_int).collect()
+    }
+
+    /// String[]的内容，null元素为None
+    pub fn as_string_vec(&self, vm: &VirtualMachine<'a>) -> VmExecResult<Vec<Option<String>>> {
+        self.check_element_type(0, STRING_DESCRIPTOR)?;
+        self.read_all()
+            .into_iter()
+            .map(|value| match value {
+                Value::ObjectRef(string) => vm.get_string_content(string).map(Some),
+                _ => Ok(None),
+            })
+            .collect()
+    }
+
     read_value_at!(read_int, Int, i32);
     read_value_at!(read_long, Long, i64);
     read_value_at!(read_float, Float, f32);
     read_value_at!(read_double, Double, f64);
-    read_nullable_value_at!(read_object, ObjectRef, ObjectReference<'a>);
+    read_reference_value_at!(read_reference);
     read_nullable_value_at!(read_array, ArrayRef, ArrayReference<'a>);
 
     write_value_at!(write_int, Int, i32);
     write_value_at!(write_long, Long, i64);
     write_value_at!(write_float, Float, f32);
     write_value_at!(write_double, Double, f64);
-    write_nullable_value_at!(write_object, ObjectRef, ObjectReference<'a>);
+    write_reference_value_at!(write_reference);
     write_nullable_value_at!(write_array, ArrayRef, ArrayReference<'a>);
 
+    //从下标0开始批量写入char数组，和write_int一样每个元素占一个槽位，不需要逐个构造Value
+    pub(crate) fn write_chars(&self, chars: &[u16]) -> VmExecResult<()> {
+        if !matches!(
+            self.get_array_type(),
+            ArrayElement::PrimaryValue(PrimaryType::Char)
+        ) {
+            return Err(VmError::ValueTypeMissMatch);
+        }
+        if chars.len() > self.get_data_length() {
+            return Err(VmError::IndexOutOfBounds);
+        }
+        unsafe {
+            let start = self.data.add(self.data_offset());
+            for (index, c) in chars.iter().enumerate() {
+                std::ptr::write(start.add(8 * index) as *mut i32, *c as i32);
+            }
+        }
+        Ok(())
+    }
+
+    //byte数组的内容，元素按有符号的byte存储
+    pub(crate) fn read_bytes(&self) -> VmExecResult<Vec<u8>> {
+        if !matches!(
+            self.get_array_type(),
+            ArrayElement::PrimaryValue(PrimaryType::Byte)
+        ) {
+            return Err(VmError::ValueTypeMissMatch);
+        }
+        self.read_all()
+            .iter()
+            .map(|value| value.get_int().map(|v| v as u8))
+            .collect()
+    }
+
+    //从下标0开始批量写入byte数组，和bastore一样按有符号的byte存储
+    pub(crate) fn write_bytes(&self, bytes: &[u8]) -> VmExecResult<()> {
+        if !matches!(
+            self.get_array_type(),
+            ArrayElement::PrimaryValue(PrimaryType::Byte)
+        ) {
+            return Err(VmError::ValueTypeMissMatch);
+        }
+        if bytes.len() > self.get_data_length() {
+            return Err(VmError::IndexOutOfBounds);
+        }
+        for (index, byte) in bytes.iter().enumerate() {
+            self.set_field_by_offset(index, &Value::Int(*byte as i8 as i32))?;
+        }
+        Ok(())
+    }
+
     pub(crate) fn new_array(
         element: ArrayElement,
         array_size: usize,
@@ -388,7 +708,7 @@ impl<'a> ReferenceValue<'a> for ArrayReference<'a> {
                     PrimaryType::Float => self.write_float(offset, value),
                     PrimaryType::Long => self.write_long(offset, value),
                 },
-                ArrayElement::ClassReference(_) => self.write_object(offset, value),
+                ArrayElement::ClassReference(_) => self.write_reference(offset, value),
                 ArrayElement::Array(_) => self.write_array(offset, value),
             }
         }
@@ -412,7 +732,7 @@ impl<'a> ReferenceValue<'a> for ArrayReference<'a> {
                     | PrimaryType::Short
                     | PrimaryType::Boolean => self.read_int(offset),
                 },
-                ArrayElement::ClassReference(_) => self.read_object(offset),
+                ArrayElement::ClassReference(_) => self.read_reference(offset),
                 ArrayElement::Array(_) => self.read_array(offset),
             }
         }
@@ -431,6 +751,22 @@ impl<'a> ObjectReference<'a> {
     pub(crate) fn hash_code(&self) -> i32 {
         self.data as i32
     }
+
+    //按(声明类, 字段名)读写，父类中被子类同名字段遮蔽的字段也能访问到
+    pub fn get_field_in_class(&self, class_name: &str, name: &str) -> VmExecResult<Value<'a>> {
+        let field = self.get_class().get_field_in_class(class_name, name)?;
+        unsafe { self.read_value_at_offset(field) }
+    }
+
+    pub fn set_field_in_class(
+        &self,
+        class_name: &str,
+        name: &str,
+        value: &Value<'_>,
+    ) -> VmExecResult<()> {
+        let field = self.get_class().get_field_in_class(class_name, name)?;
+        unsafe { self.write_value_at_offset(field, value) }
+    }
     pub(crate) fn new_object(
         class_ref: ClassRef,
         start_ptr: *const u8,
@@ -451,6 +787,11 @@ impl<'a> ObjectReference<'a> {
         }
     }
 
+    //直接按字段读取，父类中被子类同名字段遮蔽的字段也能读到
+    pub(crate) fn get_field_value(&self, field: FieldRef<'a>) -> VmExecResult<Value<'a>> {
+        unsafe { self.read_value_at_offset(field) }
+    }
+
     pub(crate) fn get_class(&self) -> ClassRef<'a> {
         unsafe {
             let class_ref_ptr = self.data.add(ALLOC_HEADER_SIZE);
@@ -462,34 +803,7 @@ impl<'a> ObjectReference<'a> {
     write_value_at!(write_long, Long, i64);
     write_value_at!(write_float, Float, f32);
     write_value_at!(write_double, Double, f64);
-    pub(crate) unsafe fn write_reference(
-        &self,
-        index: usize,
-        value: &Value<'a>,
-    ) -> VmExecResult<()> {
-        let total_fields = self.get_data_length();
-        if index >= total_fields {
-            return Err(VmError::IndexOutOfBounds);
-        }
-        let offset = self.data_offset() + 8 * index;
-        let pointer = self.data.add(offset);
-        match value {
-            Value::ObjectRef(v) => {
-                std::ptr::write(pointer as *mut ObjectReference, *v);
-                Ok(())
-            }
-            Value::ArrayRef(v) => {
-                std::ptr::write(pointer as *mut ArrayReference, *v);
-                Ok(())
-            }
-            Value::Null => {
-                std::ptr::write(pointer as *mut u64, 0);
-                Ok(())
-            }
-            _ => Err(VmError::ValueTypeMissMatch),
-        }
-    }
-    write_nullable_value_at!(write_object, ObjectRef, ObjectReference);
+    write_reference_value_at!(write_reference);
     write_nullable_value_at!(write_array, ArrayRef, ArrayReference);
 
     //TODO 校验Value与RuntimeFieldInfo是否一致
@@ -508,13 +822,12 @@ impl<'a> ObjectReference<'a> {
             "J" => self.write_long(offset, value),
             "S" => self.write_int(offset, value),
             "Z" => self.write_int(offset, value),
-            //Object是所有的父类
-            "Ljava/lang/Object;" => self.write_reference(offset, value),
             other => {
                 if other.starts_with('[') {
                     self.write_array(offset, value)
                 } else {
-                    self.write_object(offset, value)
+                    //数组也可以赋值给Object、Cloneable和Serializable类型的字段
+                    self.write_reference(offset, value)
                 }
             }
         }
@@ -533,13 +846,12 @@ impl<'a> ObjectReference<'a> {
             "J" => self.read_long(offset),
             "S" => self.read_int(offset),
             "Z" => self.read_int(offset),
-            //Object是所有的父类
-            "Ljava/lang/Object;" => self.read_reference(offset),
             other => {
                 if other.starts_with('[') {
                     self.read_array(offset)
                 } else {
-                    self.read_object(offset)
+                    //数组也可以赋值给Object、Cloneable和Serializable类型的字段
+                    self.read_reference(offset)
                 }
             }
         }
@@ -549,34 +861,120 @@ impl<'a> ObjectReference<'a> {
     read_value_at!(read_long, Long, i64);
     read_value_at!(read_float, Float, f32);
     read_value_at!(read_double, Double, f64);
-    pub(crate) unsafe fn read_reference(&self, index: usize) -> VmExecResult<Value<'a>> {
-        let total_fields = self.get_data_length();
-        if index >= total_fields {
-            return Err(VmError::IndexOutOfBounds);
+    read_reference_value_at!(read_reference);
+    read_nullable_value_at!(read_array, ArrayRef, ArrayReference);
+
+    pub fn is_instance_of(&self, class_ref: ClassRef<'a>) -> bool {
+        self.get_class().is_subclass_of(&class_ref.name)
+    }
+
+    //按名称查找字段并校验描述符。期望Ljava/lang/Object;时接受任意对象类型的字段，数组除外，
+    //写入时还需要检查值的类型
+    fn typed_field(&self, name: &str, expected: &str) -> VmExecResult<FieldRef<'a>> {
+        let class_ref = self.get_class();
+        let field = class_ref.get_field_by_name(name)?;
+        let matches = field.descriptor == expected
+            || (expected == OBJECT_DESCRIPTOR && field.descriptor.starts_with('L'));
+        if !matches {
+            return Err(VmError::FieldTypeMismatch {
+                field: format!("{}.{}", class_ref.name, name),
+                expected: expected.to_string(),
+                actual: field.descriptor.clone(),
+            });
+        }
+        Ok(field)
+    }
+
+    fn get_typed_field(&self, name: &str, expected: &str) -> VmExecResult<Value<'a>> {
+        let field = self.typed_field(name, expected)?;
+        unsafe { self.read_value_at_offset(field) }
+    }
+
+    fn set_typed_field(&self, name: &str, expected: &str, value: &Value<'a>) -> VmExecResult<()> {
+        let field = self.typed_field(name, expected)?;
+        unsafe { self.write_value_at_offset(field, value) }
+    }
+
+    pub fn get_int_field(&self, name: &str) -> VmExecResult<i32> {
+        self.get_typed_field(name, "I")?.get_int()
+    }
+
+    pub fn get_long_field(&self, name: &str) -> VmExecResult<i64> {
+        self.get_typed_field(name, "J")?.get_long()
+    }
+
+    pub fn get_double_field(&self, name: &str) -> VmExecResult<f64> {
+        self.get_typed_field(name, "D")?.get_double()
+    }
+
+    /// 字段值为null时返回None
+    pub fn get_object_field(&self, name: &str) -> VmExecResult<Option<ObjectReference<'a>>> {
+        match self.get_typed_field(name, OBJECT_DESCRIPTOR)? {
+            Value::ObjectRef(object_ref) => Ok(Some(object_ref)),
+            _ => Ok(None),
         }
-        let value_type = self.get_header().kind();
-
-        let offset = self.data_offset() + 8 * index;
-        let pointer = self.data.add(offset);
-        let data = std::ptr::read(pointer as *mut u64);
-        if data == 0 {
-            Ok(Value::Null)
-        } else {
-            match value_type {
-                ReferenceValueType::Object => Ok(Value::ObjectRef(std::ptr::read(
-                    pointer as *mut ObjectReference,
-                ))),
-                ReferenceValueType::Array => Ok(Value::ArrayRef(std::ptr::read(
-                    pointer as *mut ArrayReference,
-                ))),
+    }
+
+    /// String字段的内容，字段值为null时返回None
+    pub fn get_string_field(
+        &self,
+        vm: &VirtualMachine<'a>,
+        name: &str,
+    ) -> VmExecResult<Option<String>> {
+        match self.get_typed_field(name, STRING_DESCRIPTOR)? {
+            Value::ObjectRef(string) => vm.get_string_content(string).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    pub fn set_int_field(&self, name: &str, value: i32) -> VmExecResult<()> {
+        self.set_typed_field(name, "I", &Value::Int(value))
+    }
+
+    pub fn set_long_field(&self, name: &str, value: i64) -> VmExecResult<()> {
+        self.set_typed_field(name, "J", &Value::Long(value))
+    }
+
+    pub fn set_double_field(&self, name: &str, value: f64) -> VmExecResult<()> {
+        self.set_typed_field(name, "D", &Value::Double(value))
+    }
+
+    /// 对象的类必须是字段声明的类型或者它的子类，None写入null
+    pub fn set_object_field(
+        &self,
+        name: &str,
+        value: Option<ObjectReference<'a>>,
+    ) -> VmExecResult<()> {
+        let field = self.typed_field(name, OBJECT_DESCRIPTOR)?;
+        if let Some(object) = value {
+            let declared_class = &field.descriptor[1..field.descriptor.len() - 1];
+            if !object.get_class().is_subclass_of(declared_class) {
+                return Err(VmError::FieldTypeMismatch {
+                    field: format!("{}.{}", self.get_class().name, name),
+                    expected: field.descriptor.clone(),
+                    actual: format!("L{};", object.get_class().name),
+                });
             }
         }
+        let value = value.map_or(Value::Null, Value::ObjectRef);
+        unsafe { self.write_value_at_offset(field, &value) }
     }
-    read_nullable_value_at!(read_object, ObjectRef, ObjectReference);
-    read_nullable_value_at!(read_array, ArrayRef, ArrayReference);
 
-    pub fn is_instance_of(&self, class_ref: ClassRef<'a>) -> bool {
-        self.get_class().is_subclass_of(&class_ref.name)
+    /// 字符串对象通过虚拟机的字符串常量池创建，None写入null
+    pub fn set_string_field(
+        &self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        name: &str,
+        value: Option<&str>,
+    ) -> Result<(), MethodCallError<'a>> {
+        //先校验字段类型，类型不符时不创建字符串对象
+        self.typed_field(name, STRING_DESCRIPTOR)?;
+        let value = match value {
+            Some(value) => Value::ObjectRef(vm.new_java_lang_string_object(call_stack, value)?),
+            None => Value::Null,
+        };
+        Ok(self.set_typed_field(name, STRING_DESCRIPTOR, &value)?)
     }
 }
 
@@ -632,6 +1030,85 @@ impl<'a> ReferenceValue<'a> for ObjectReference<'a> {
     }
 }
 
+//格式和Object.toString()一致，类名@identityHashCode
+impl Display for ObjectReference<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.data.is_null() {
+            return write!(f, "null");
+        }
+        write!(
+            f,
+            "{}@{:x}",
+            self.get_class().name.replace('/', "."),
+            self.hash_code() as u32
+        )
+    }
+}
+
+impl Debug for ObjectReference<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+//元素类型[长度]@identityHashCode，例如int[10]@3c4d
+impl Display for ArrayReference<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.data.is_null() {
+            return write!(f, "null");
+        }
+        let header = self.get_array_header();
+        let element_name = header.element.java_type_name();
+        let array_size = header.array_size;
+        write!(
+            f,
+            "{}[{}]@{:x}",
+            element_name,
+            array_size,
+            self.hash_code() as u32
+        )
+    }
+}
+
+impl Debug for ArrayReference<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(self, f)
+    }
+}
+
+//基本类型按照Java字面量输出
+impl Display for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Uninitialized => write!(f, "<uninitialized>"),
+            Value::Int(v) => write!(f, "{}", v),
+            Value::Long(v) => write!(f, "{}L", v),
+            Value::Float(v) => write!(f, "{:?}f", v),
+            Value::Double(v) => write!(f, "{:?}", v),
+            Value::ReturnAddress(v) => write!(f, "<returnAddress {}>", v),
+            Value::ObjectRef(object_ref) => write!(f, "{}", object_ref),
+            Value::ArrayRef(array_ref) => write!(f, "{}", array_ref),
+            Value::Null => write!(f, "null"),
+        }
+    }
+}
+
+impl Debug for Value<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Uninitialized => write!(f, "Uninitialized"),
+            Value::Int(v) => write!(f, "Int({})", v),
+            Value::Long(v) => write!(f, "Long({})", v),
+            Value::Float(v) => write!(f, "Float({:?})", v),
+            Value::Double(v) => write!(f, "Double({:?})", v),
+            Value::ReturnAddress(v) => write!(f, "ReturnAddress({})", v),
+            Value::ObjectRef(object_ref) => write!(f, "ObjectRef({})", object_ref),
+            Value::ArrayRef(array_ref) => write!(f, "ArrayRef({})", array_ref),
+            Value::Null => write!(f, "Null"),
+        }
+    }
+}
+
 mod tests {
 
     #[test]
@@ -642,4 +1119,293 @@ mod tests {
         assert_ne!(Value::Int(1), Value::Double(1f64));
         assert_ne!(Value::Int(1), Value::Null);
     }
+
+    #[test]
+    fn test_bitwise_eq() {
+        use crate::jvm_values::Value;
+
+        //IEEE比较：NaN不等于自身，0.0等于-0.0
+        assert_ne!(Value::Float(f32::NAN), Value::Float(f32::NAN));
+        assert_ne!(Value::Double(f64::NAN), Value::Double(f64::NAN));
+        assert_eq!(Value::Float(0.0), Value::Float(-0.0));
+        assert_eq!(Value::Double(0.0), Value::Double(-0.0));
+
+        //位模式比较：正好相反
+        assert!(Value::Float(f32::NAN).bitwise_eq(&Value::Float(f32::NAN)));
+        assert!(Value::Double(f64::NAN).bitwise_eq(&Value::Double(f64::NAN)));
+        assert!(!Value::Float(0.0).bitwise_eq(&Value::Float(-0.0)));
+        assert!(!Value::Double(0.0).bitwise_eq(&Value::Double(-0.0)));
+
+        assert!(Value::Int(1).bitwise_eq(&Value::Int(1)));
+        assert!(!Value::Int(0).bitwise_eq(&Value::Float(0.0)));
+        assert!(Value::Null.bitwise_eq(&Value::Null));
+    }
+
+    #[test]
+    fn test_is_category_two() {
+        use crate::jvm_values::Value;
+
+        assert!(Value::Long(0).is_category_two());
+        assert!(Value::Double(f64::NAN).is_category_two());
+        for value in [
+            Value::Int(0),
+            Value::Float(0.0),
+            Value::ReturnAddress(3),
+            Value::Null,
+            Value::Uninitialized,
+        ] {
+            assert!(!value.is_category_two(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn test_from_field_descriptor() {
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{PrimaryType, ValueType};
+
+        for (descriptor, expected) in [
+            ("I", ValueType::Primary(PrimaryType::Int)),
+            ("[I", ValueType::PrimaryArray(PrimaryType::Int, 1)),
+            ("[J", ValueType::PrimaryArray(PrimaryType::Long, 1)),
+            ("[[[Z", ValueType::PrimaryArray(PrimaryType::Boolean, 3)),
+            (
+                "Ljava/lang/String;",
+                ValueType::Object("java/lang/String".to_string()),
+            ),
+            (
+                "[[Ljava/lang/String;",
+                ValueType::ObjectArray("java/lang/String".to_string(), 2),
+            ),
+        ] {
+            assert_eq!(
+                ValueType::from_field_descriptor(descriptor).unwrap(),
+                expected,
+                "{}",
+                descriptor
+            );
+        }
+        let too_deep = format!("{}I", "[".repeat(256));
+        for descriptor in [
+            "",
+            "[",
+            "V",
+            "[V",
+            "II",
+            "L;",
+            "Ljava/lang/String",
+            "java/lang/String",
+            "[Ljava.lang.String;",
+            "[Ljava/lang/String;I",
+            too_deep.as_str(),
+        ] {
+            assert_eq!(
+                ValueType::from_field_descriptor(descriptor),
+                Err(VmError::InvalidDescriptor(descriptor.to_string())),
+                "{}",
+                descriptor
+            );
+        }
+    }
+
+    #[test]
+    fn test_value_display() {
+        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::method_area::MethodArea;
+        use crate::object_heap::ObjectHeap;
+
+        let primitives = [
+            (Value::Uninitialized, "<uninitialized>", "Uninitialized"),
+            (Value::Int(-3), "-3", "Int(-3)"),
+            (Value::Long(7), "7L", "Long(7)"),
+            (Value::Float(1.5), "1.5f", "Float(1.5)"),
+            (Value::Double(2.0), "2.0", "Double(2.0)"),
+            (
+                Value::ReturnAddress(12),
+                "<returnAddress 12>",
+                "ReturnAddress(12)",
+            ),
+            (Value::Null, "null", "Null"),
+        ];
+        for (value, display, debug) in primitives {
+            assert_eq!(value.to_string(), display);
+            assert_eq!(format!("{:?}", value), debug);
+        }
+
+        let area = MethodArea::default();
+        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
+        area.add_class_path(Box::new(file_system_path));
+        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
+        area.add_class_path(Box::new(rt_jar_path));
+        let class_ref = area.load_class("FieldTest").unwrap();
+        let string_class = area.load_class("java/lang/String").unwrap();
+
+        let mut heap = ObjectHeap::new(1024);
+        let object_ref = heap.allocate_object(class_ref).unwrap();
+        let hash = object_ref.hash_code() as u32;
+        assert_eq!(
+            Value::ObjectRef(object_ref).to_string(),
+            format!("FieldTest@{:x}", hash)
+        );
+        assert_eq!(
+            format!("{:?}", Value::ObjectRef(object_ref)),
+            format!("ObjectRef(FieldTest@{:x})", hash)
+        );
+
+        let int_array = heap
+            .allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), 10)
+            .unwrap();
+        let hash = int_array.hash_code() as u32;
+        assert_eq!(
+            Value::ArrayRef(int_array).to_string(),
+            format!("int[10]@{:x}", hash)
+        );
+        let string_array = heap
+            .allocate_array(ArrayElement::ClassReference(string_class), 2)
+            .unwrap();
+        let hash = string_array.hash_code() as u32;
+        assert_eq!(
+            format!("{:?}", Value::ArrayRef(string_array)),
+            format!("ArrayRef(java.lang.String[2]@{:x})", hash)
+        );
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let object = vm
+            .new_object_by_class_name(call_stack, "FieldTest")
+            .unwrap();
+        vm.call(
+            call_stack,
+            "FieldTest",
+            "<init>",
+            "()V",
+            Some(Value::ObjectRef(object)),
+            vec![],
+        )
+        .unwrap();
+
+        assert_eq!(object.get_int_field("a").unwrap(), 0);
+        object.set_int_field("a", 42).unwrap();
+        assert_eq!(object.get_int_field("a").unwrap(), 42);
+        assert_eq!(object.get_double_field("fieldDouble").unwrap(), 100.0);
+        object.set_double_field("fieldDouble", 2.5).unwrap();
+        assert_eq!(object.get_double_field("fieldDouble").unwrap(), 2.5);
+        assert_eq!(
+            object.get_string_field(&vm, "fieldString").unwrap(),
+            Some("default".to_string())
+        );
+        object
+            .set_string_field(&mut vm, call_stack, "fieldString", Some("changed"))
+            .unwrap();
+        assert_eq!(
+            object.get_string_field(&vm, "fieldString").unwrap(),
+            Some("changed".to_string())
+        );
+        object
+            .set_string_field(&mut vm, call_stack, "fieldString", None)
+            .unwrap();
+        assert_eq!(object.get_string_field(&vm, "fieldString").unwrap(), None);
+        assert_eq!(object.get_object_field("b").unwrap(), None);
+
+        //Long c = 1L，long字段使用包装对象的value
+        let boxed = object.get_object_field("c").unwrap().unwrap();
+        assert_eq!(boxed.get_long_field("value").unwrap(), 1);
+        boxed.set_long_field("value", 7).unwrap();
+        assert_eq!(boxed.get_long_field("value").unwrap(), 7);
+        object.set_object_field("b", None).unwrap();
+
+        //描述符不一致时不按其他类型解释
+        let mismatch = |expected: &str, actual: &str, field: &str| VmError::FieldTypeMismatch {
+            field: format!("FieldTest.{}", field),
+            expected: expected.to_string(),
+            actual: actual.to_string(),
+        };
+        assert_eq!(
+            object.get_int_field("fieldFloat").unwrap_err(),
+            mismatch("I", "F", "fieldFloat")
+        );
+        assert_eq!(
+            object.get_long_field("c").unwrap_err(),
+            mismatch("J", "Ljava/lang/Long;", "c")
+        );
+        assert_eq!(
+            object.get_double_field("a").unwrap_err(),
+            mismatch("D", "I", "a")
+        );
+        assert_eq!(
+            object.get_string_field(&vm, "b").unwrap_err(),
+            mismatch("Ljava/lang/String;", "Ljava/lang/Integer;", "b")
+        );
+        assert_eq!(
+            object.get_object_field("a").unwrap_err(),
+            mismatch("Ljava/lang/Object;", "I", "a")
+        );
+        assert_eq!(
+            object.set_int_field("fieldDouble", 1).unwrap_err(),
+            mismatch("I", "D", "fieldDouble")
+        );
+        assert!(matches!(
+            object.set_string_field(&mut vm, call_stack, "a", Some("x")),
+            Err(MethodCallError::InternalError(
+                VmError::FieldTypeMismatch { .. }
+            ))
+        ));
+        assert_eq!(object.get_int_field("a").unwrap(), 42);
+        //写入的对象必须和字段声明的类型兼容
+        let string = vm.new_java_lang_string_object(call_stack, "x").unwrap();
+        assert_eq!(
+            object.set_object_field("b", Some(string)).unwrap_err(),
+            mismatch("Ljava/lang/Integer;", "Ljava/lang/String;", "b")
+        );
+        assert_eq!(object.get_object_field("b").unwrap(), None);
+        let integer = vm
+            .new_object_by_class_name(call_stack, "java/lang/Integer")
+            .unwrap();
+        object.set_object_field("b", Some(integer)).unwrap();
+        assert_eq!(object.get_object_field("b").unwrap(), Some(integer));
+
+        let ints = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 3)
+            .unwrap();
+        ints.set_field_by_offset(1, &Value::Int(5)).unwrap();
+        assert_eq!(ints.get_int(1).unwrap(), 5);
+        assert_eq!(ints.get_int(3).unwrap_err(), VmError::IndexOutOfBounds);
+        assert_eq!(ints.as_i32_vec().unwrap(), vec![0, 5, 0]);
+        assert_eq!(
+            ints.as_string_vec(&vm).unwrap_err(),
+            VmError::FieldTypeMismatch {
+                field: "[0]".to_string(),
+                expected: "Ljava/lang/String;".to_string(),
+                actual: "I".to_string(),
+            }
+        );
+
+        let string_class = vm.java_lang_string_class(call_stack).unwrap();
+        let strings = vm
+            .new_array(call_stack, ArrayElement::ClassReference(string_class), 2)
+            .unwrap();
+        let hello = vm.new_java_lang_string_object(call_stack, "hello").unwrap();
+        strings
+            .set_field_by_offset(0, &Value::ObjectRef(hello))
+            .unwrap();
+        assert_eq!(
+            strings.as_string_vec(&vm).unwrap(),
+            vec![Some("hello".to_string()), None]
+        );
+        assert_eq!(
+            strings.get_int(0).unwrap_err(),
+            VmError::FieldTypeMismatch {
+                field: "[0]".to_string(),
+                expected: "I".to_string(),
+                actual: "Ljava/lang/String;".to_string(),
+            }
+        );
+    }
 }
@@ -1,14 +1,25 @@
 pub mod bootstrap_class_loader;
 pub mod class_finder;
+pub mod conversion;
+pub mod dynamic_constant;
+pub mod embedded_core;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "serde")]
+pub mod heap_dump;
+pub mod hprof;
+pub mod intrinsics;
 pub mod java_exception;
 pub mod jvm_error;
 pub mod jvm_values;
 pub mod loaded_class;
 pub mod memory_trunk;
 pub mod method_area;
+pub mod method_handle;
 pub mod native_method_area;
 pub mod object_heap;
 pub mod operand_stack;
+pub mod replay;
 pub mod runtime_attribute_info;
 pub mod runtime_constant_pool;
 pub mod runtime_field_info;
@@ -17,4 +28,16 @@ pub mod stack;
 pub mod stack_frame;
 pub mod stack_trace_element;
 pub mod static_field_area;
+pub mod string_coding;
+pub mod string_concat;
+pub mod test_runner;
+pub mod trace_filter;
+pub mod trivial_method;
 pub mod virtual_machine;
+
+//测试辅助函数使用lite_jvm::开头的路径，和集成测试共用
+#[cfg(test)]
+extern crate self as lite_jvm;
+#[cfg(test)]
+#[path = "../tests/common/mod.rs"]
+mod test_common;
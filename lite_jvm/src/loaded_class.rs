@@ -1,15 +1,18 @@
 use crate::jvm_error::{VmError, VmExecResult};
-use crate::runtime_attribute_info::BootstrapMethod;
+use crate::jvm_values::ObjectReference;
+use crate::runtime_attribute_info::{BootstrapMethod, EnclosingMethod, InnerClass};
 use crate::runtime_constant_pool::RuntimeConstantPool;
 use crate::runtime_field_info::RuntimeFieldInfo;
-use crate::runtime_method_info::{MethodKey, RuntimeMethodInfo};
+use crate::runtime_method_info::{MethodKey, MethodKeyRef, RuntimeMethodInfo};
 use class_file_reader::class_file::ClassAccessFlags;
 use class_file_reader::class_file_version::ClassFileVersion;
+use class_file_reader::instruction::read_instructions;
 use indexmap::IndexMap;
+use std::cell::Cell;
 use std::fmt::{Display, Formatter};
 use std::hash::{Hash, Hasher};
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum ClassStatus {
     Loading,
     Loaded,
@@ -17,13 +20,23 @@ pub enum ClassStatus {
     Linked,
     Initializing,
     Initialized,
+    //<clinit>执行失败，之后使用该类都会抛出NoClassDefFoundError
+    Erroneous,
+}
+
+//类名中最后一个/之前的部分，默认包为空
+fn package_name(class_name: &str) -> &str {
+    class_name
+        .rfind('/')
+        .map_or("", |index| &class_name[..index])
 }
 
 /// 表示加载的类，加载后该类会经过->链接->初始化过程最终加载完成。
 ///
 pub struct Class<'a> {
     pub version: ClassFileVersion,
-    pub status: ClassStatus,
+    //类加载后通过共享引用访问，状态推进使用Cell修改
+    pub status: Cell<ClassStatus>,
     pub name: String,
     //常量池解析
     pub constant_pool: RuntimeConstantPool,
@@ -31,11 +44,11 @@ pub struct Class<'a> {
     //超类解析
     pub super_class: Option<ClassRef<'a>>,
     //接口解析
-    pub interfaces: IndexMap<&'a str, ClassRef<'a>>,
+    pub interfaces: IndexMap<String, ClassRef<'a>>,
     //字段解析
-    pub fields: IndexMap<&'a str, RuntimeFieldInfo>,
+    pub fields: IndexMap<String, RuntimeFieldInfo>,
     //方法解析
-    pub methods: IndexMap<MethodKey<'a>, RuntimeMethodInfo>,
+    pub methods: IndexMap<MethodKey, RuntimeMethodInfo>,
 
     pub super_class_name: Option<String>,
     pub interface_names: Vec<String>,
@@ -45,6 +58,13 @@ pub struct Class<'a> {
     pub total_num_of_fields: usize,
 
     pub bootstrap_method: Vec<BootstrapMethod>,
+
+    //InnerClasses属性，包含该类自身以及引用到的所有嵌套类
+    pub inner_classes: Vec<InnerClass>,
+    //局部类和匿名类才有EnclosingMethod属性
+    pub enclosing_method: Option<EnclosingMethod>,
+    //定义该类的类加载器对象，None表示由引导类加载器定义
+    pub class_loader: Option<ObjectReference<'a>>,
 }
 
 impl<'a> Class<'a> {
@@ -57,11 +77,30 @@ impl<'a> Class<'a> {
         }
         Err(VmError::FieldNotFoundException(name.to_string()))
     }
-    pub(crate) fn get_field(&self, offset: usize) -> VmExecResult<FieldRef<'a>> {
+    //getfield/putfield从常量池中的类开始解析，子类同名字段不能遮蔽父类的私有字段
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.2
+    pub fn get_field_in_class(
+        &'a self,
+        class_name: &str,
+        name: &str,
+    ) -> VmExecResult<FieldRef<'a>> {
+        let mut current = Some(self);
+        while let Some(class_ref) = current {
+            if class_ref.name == class_name {
+                return class_ref.get_field_by_name(name);
+            }
+            current = class_ref.super_class;
+        }
+        Err(VmError::FieldNotFoundException(format!(
+            "{}.{}",
+            class_name, name
+        )))
+    }
+    pub(crate) fn get_field(&'a self, offset: usize) -> VmExecResult<FieldRef<'a>> {
         assert!(offset < self.total_num_of_fields);
         let super_class_offset = if let Some(class_ref) = self.super_class {
             if offset < class_ref.total_num_of_fields {
-                return Ok(class_ref.fields.get_index(offset).unwrap().1);
+                return class_ref.get_field(offset);
             }
             class_ref.total_num_of_fields
         } else {
@@ -72,12 +111,15 @@ impl<'a> Class<'a> {
             .get_index(offset - super_class_offset)
             .expect("")
             .1;
-        //self的声明周期要大于classRef<'a>,实用unsafe 使得编译器能够编译
-        let method_ref = unsafe {
-            let const_ptr: *const RuntimeFieldInfo = field;
-            &*const_ptr
-        };
-        Ok(method_ref)
+        Ok(field)
+    }
+
+    /// 字段在get_field中对应的索引，父类的字段排在前面
+    pub(crate) fn field_index(&self, name: &str) -> Option<usize> {
+        if let Some(index) = self.fields.get_index_of(name) {
+            return Some(self.super_class.map_or(0, |c| c.total_num_of_fields) + index);
+        }
+        self.super_class?.field_index(name)
     }
 
     pub fn is_interface(&self) -> bool {
@@ -88,11 +130,133 @@ impl<'a> Class<'a> {
         self.access_flags.contains(ClassAccessFlags::ABSTRACT)
     }
 
+    /// 链接时的继承检查：父类不能是final类，实例方法不能覆盖父类中的final方法
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.5
+    pub(crate) fn check_inheritance(&self) -> VmExecResult<()> {
+        let Some(super_class) = self.super_class else {
+            return Ok(());
+        };
+        if super_class.access_flags.contains(ClassAccessFlags::FINAL) {
+            return Err(VmError::VerifyError(format!(
+                "Cannot inherit from final class {}",
+                super_class.name
+            )));
+        }
+        for method in self.methods.values() {
+            //静态方法、私有方法和构造方法不参与覆盖
+            if method.is_static() || method.is_private() || method.name.starts_with('<') {
+                continue;
+            }
+            let mut ancestor = Some(super_class);
+            while let Some(class) = ancestor {
+                if let Ok(super_method) = class.get_method(&method.name, &method.descriptor) {
+                    let overrides = !super_method.is_static()
+                        && !super_method.is_private()
+                        && (!super_method.is_package_private()
+                            || package_name(&class.name) == package_name(&self.name));
+                    if overrides && super_method.is_final() {
+                        return Err(VmError::VerifyError(format!(
+                            "class {} overrides final method {}.{}{}",
+                            self.name, class.name, method.name, method.descriptor
+                        )));
+                    }
+                }
+                ancestor = class.super_class;
+            }
+        }
+        Ok(())
+    }
+
+    /// 不执行代码的静态检查：父类和接口的类型，方法是否有Code属性以及字节码能否完整解码。
+    /// 返回发现的所有错误
+    pub(crate) fn verify(&self) -> Vec<VmError> {
+        let mut errors = vec![];
+        if let Some(super_class) = self.super_class {
+            if super_class.is_interface() {
+                errors.push(VmError::LinkageError(format!(
+                    "class {} has interface {} as super class",
+                    self.name, super_class.name
+                )));
+            }
+        }
+        if let Err(e) = self.check_inheritance() {
+            errors.push(e);
+        }
+        for interface in self.interfaces.values() {
+            if !interface.is_interface() {
+                errors.push(VmError::LinkageError(format!(
+                    "class {} can not implement {}, because it is not an interface",
+                    self.name, interface.name
+                )));
+            }
+        }
+        for method in self.methods.values() {
+            let name = format!("{}.{}{}", self.name, method.name, method.descriptor);
+            let code = match (&method.code, method.is_abstract() || method.is_native()) {
+                (Some(code), false) => code,
+                (None, true) => continue,
+                (Some(_), true) => {
+                    errors.push(VmError::VerifyError(format!(
+                        "abstract or native method {} has Code attribute",
+                        name
+                    )));
+                    continue;
+                }
+                (None, false) => {
+                    errors.push(VmError::VerifyError(format!(
+                        "method {} has no Code attribute",
+                        name
+                    )));
+                    continue;
+                }
+            };
+            if let Err(e) = read_instructions(&code.code) {
+                errors.push(VmError::VerifyError(format!("{}: {}", name, e)));
+            }
+        }
+        errors
+    }
+
+    fn inner_class_entry(&self) -> Option<&InnerClass> {
+        self.inner_classes
+            .iter()
+            .find(|inner| inner.inner_class_name == self.name)
+    }
+
+    /// 成员类所在的类，对应Class.getDeclaringClass()。局部类和匿名类返回None
+    pub fn declaring_class_name(&self) -> Option<&str> {
+        self.inner_class_entry()?.outer_class_name.as_deref()
+    }
+
+    /// 对应Class.getEnclosingMethod()/getEnclosingConstructor()
+    pub fn enclosing_method(&self) -> Option<&EnclosingMethod> {
+        self.enclosing_method.as_ref()
+    }
+
+    /// 直接声明在该类中的成员类，对应Class.getDeclaredClasses()
+    pub fn declared_class_names(&self) -> Vec<&str> {
+        self.inner_classes
+            .iter()
+            .filter(|inner| inner.outer_class_name.as_deref() == Some(self.name.as_str()))
+            .map(|inner| inner.inner_class_name.as_str())
+            .collect()
+    }
+
+    pub fn is_anonymous_class(&self) -> bool {
+        self.inner_class_entry()
+            .is_some_and(|inner| inner.inner_name.is_none())
+    }
+
     pub(crate) fn is_subclass_of(&self, class_name: &str) -> bool {
         if self.name == class_name {
             return true;
         }
-        if self.interfaces.get(class_name).is_some() {
+        //接口可能继承自其他接口，需要递归查找
+        if self
+            .interfaces
+            .values()
+            .any(|interface| interface.is_subclass_of(class_name))
+        {
             return true;
         }
         if let Some(super_class) = self.super_class {
@@ -108,13 +272,8 @@ impl<'a> Class<'a> {
         method_name: &str,
         descriptor: &str,
     ) -> VmExecResult<MethodRef<'a>> {
-        if let Some(method) = self.methods.get(&MethodKey::new(method_name, descriptor)) {
-            //self的声明周期要大于classRef<'a>,实用unsafe 使得编译器能够编译
-            let method_ref = unsafe {
-                let const_ptr: *const RuntimeMethodInfo = method;
-                &*const_ptr
-            };
-            Ok(method_ref)
+        if let Some(method) = self.methods.get(&MethodKeyRef(method_name, descriptor)) {
+            Ok(method)
         } else {
             Err(VmError::MethodNotFoundException(
                 method_name.to_string(),
@@ -127,34 +286,58 @@ impl<'a> Class<'a> {
         method_name: &str,
         descriptor: &str,
     ) -> VmExecResult<(ClassRef<'a>, MethodRef<'a>)> {
-        if let Some(method) = self.methods.get(&MethodKey::new(method_name, descriptor)) {
-            //self的声明周期要大于classRef<'a>,实用unsafe 使得编译器能够编译
-            let method_ref = unsafe {
-                let const_ptr: *const RuntimeMethodInfo = method;
-                &*const_ptr
-            };
-            return Ok((self, method_ref));
-        }
-
-        //查找父类
-        if let Some(supper_class) = &self.super_class {
-            let by_super_class = supper_class.get_method_by_checking_super(method_name, descriptor);
-            if by_super_class.is_ok() {
-                return by_super_class;
+        //先沿着父类链查找
+        let mut current = Some(self);
+        while let Some(class_ref) = current {
+            if let Ok(method_ref) = class_ref.get_method(method_name, descriptor) {
+                return Ok((class_ref, method_ref));
             }
+            current = class_ref.super_class;
         }
-        //查找接口
-        for (_, interface) in &self.interfaces {
-            let by_interface = interface.get_method_by_checking_super(method_name, descriptor);
-            if by_interface.is_ok() {
-                return by_interface;
+        //再查找所有超接口，静态和私有方法不参与继承
+        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.3
+        let mut candidates: Vec<(ClassRef<'a>, MethodRef<'a>)> = vec![];
+        for interface in self.all_super_interfaces() {
+            if let Ok(method_ref) = interface.get_method(method_name, descriptor) {
+                if !method_ref.is_static() && !method_ref.is_private() {
+                    candidates.push((interface, method_ref));
+                }
             }
         }
+        //优先选择最具体的默认方法：没有其他候选接口是它的子接口
+        let maximally_specific = candidates.iter().find(|(interface, method_ref)| {
+            !method_ref.is_abstract()
+                && !candidates.iter().any(|(other, _)| {
+                    !std::ptr::eq(*other, *interface) && other.is_subclass_of(&interface.name)
+                })
+        });
+        if let Some(found) = maximally_specific.or(candidates.first()) {
+            return Ok(*found);
+        }
         Err(VmError::MethodNotFoundException(
             method_name.to_string(),
             descriptor.to_string(),
         ))
     }
+
+    //自身及所有父类实现的接口，包含接口继承的接口，按声明顺序去重
+    fn all_super_interfaces(&'a self) -> Vec<ClassRef<'a>> {
+        fn collect<'a>(class_ref: ClassRef<'a>, result: &mut Vec<ClassRef<'a>>) {
+            for interface in class_ref.interfaces.values() {
+                if !result.iter().any(|c| std::ptr::eq(*c, *interface)) {
+                    result.push(interface);
+                    collect(interface, result);
+                }
+            }
+        }
+        let mut result = vec![];
+        let mut current = Some(self);
+        while let Some(class_ref) = current {
+            collect(class_ref, &mut result);
+            current = class_ref.super_class;
+        }
+        result
+    }
 }
 
 pub type ClassRef<'a> = &'a Class<'a>;
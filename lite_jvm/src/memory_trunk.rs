@@ -31,6 +31,19 @@ impl MemoryChunk {
         Some((ptr, required_size))
     }
 
+    //已经分配出去的内存起始地址和大小
+    pub(crate) fn allocated(&self) -> (*mut u8, usize) {
+        (self.memory, self.used)
+    }
+
+    pub(crate) fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub(crate) fn used(&self) -> usize {
+        self.used
+    }
+
     // unsafe fn contains(&self, ptr: *const u8) -> bool {
     //     ptr >= self.memory && ptr <= self.memory.add(self.used)
     // }
@@ -1,31 +1,52 @@
-use crate::bootstrap_class_loader::{BootstrapClassLoader, ClassLoader, LoadClassResult};
+use crate::bootstrap_class_loader::{
+    BootstrapClassLoader, ClassLoader, ClassTransformer, LoadClassResult,
+};
 use crate::class_finder::ClassPath;
-use crate::jvm_error::VmExecResult;
+use crate::jvm_error::{VmError, VmExecResult};
+use crate::jvm_values::ObjectReference;
 use crate::loaded_class::{Class, ClassRef, ClassStatus};
-use crate::runtime_attribute_info::BootstrapMethod;
+use crate::runtime_attribute_info::{BootstrapMethod, EnclosingMethod, InnerClass};
 use crate::runtime_constant_pool::RuntimeConstantPool;
 use crate::runtime_field_info::RuntimeFieldInfo;
 use crate::runtime_method_info::{MethodKey, RuntimeMethodInfo};
 use class_file_reader::attribute_info::AttributeType;
-use class_file_reader::class_file::ClassFile;
+use class_file_reader::class_file::{ClassAccessFlags, ClassFile};
 use indexmap::IndexMap;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use typed_arena::Arena;
 
+/// 单个类加载、链接、初始化各阶段的耗时。
+/// load只包含读取、解析class文件和创建类的时间，不包含父类和接口的加载；
+/// initialize包含<clinit>中触发的其他类的初始化
+#[derive(Debug, Clone, Default)]
+pub struct ClassLoadTiming {
+    pub class_name: String,
+    pub load: Duration,
+    pub link: Duration,
+    pub initialize: Duration,
+}
+
 /// 方法区的功能抽象，用来管理类的加载->链接->初始化。
 /// 需要一个classloader以外的管理者进行对类统一管理。
 pub struct MethodArea<'a> {
     bootstrap_class_loader: RefCell<BootstrapClassLoader<'a>>,
     custom_class_loader: HashMap<&'a str, ClassRef<'a>>,
+    //类由类名+类加载器共同标识。记录自定义类加载器定义的类，以及委托其他加载器加载后返回的类
+    loader_classes: RefCell<HashMap<(ObjectReference<'a>, String), ClassRef<'a>>>,
     classes: Arena<Class<'a>>,
+    //为None时不记录耗时
+    timings: RefCell<Option<IndexMap<String, ClassLoadTiming>>>,
 }
 impl<'a> Default for MethodArea<'a> {
     fn default() -> Self {
         MethodArea {
             bootstrap_class_loader: RefCell::new(BootstrapClassLoader::default()),
             custom_class_loader: HashMap::new(),
+            loader_classes: RefCell::new(HashMap::new()),
             classes: Arena::new(),
+            timings: RefCell::new(None),
         }
     }
 }
@@ -34,32 +55,38 @@ impl<'a> MethodArea<'a> {
         self.classes.len()
     }
 
-    pub fn get_mut(&mut self, class_ref: ClassRef<'a>) -> Option<&'a mut Class<'a>> {
-        for mut_ref in self.classes.iter_mut() {
-            let v1 = mut_ref as *const Class;
-            let v2 = class_ref as *const Class;
-            if v1 == v2 {
-                let ptr = unsafe {
-                    let str_ptr: *mut Class = mut_ref;
-                    &mut *str_ptr
-                };
-                return Some(ptr);
-            }
-        }
-        None
-    }
-
     pub fn is_class_loaded(&self, class_name: &str) -> bool {
         self.bootstrap_class_loader.borrow().exist(class_name)
     }
+    /// 引导类加载器已经加载的类，不会触发加载
+    pub fn find_loaded_class(&self, class_name: &str) -> Option<ClassRef<'a>> {
+        self.bootstrap_class_loader
+            .borrow_mut()
+            .find_loaded_class(class_name)
+            .copied()
+    }
     pub fn load_class(&self, class_name: &str) -> VmExecResult<ClassRef<'a>> {
+        if class_name.starts_with('[') {
+            return self.load_array_class(class_name);
+        }
+        let start = Instant::now();
         let load_class_result = self
             .bootstrap_class_loader
             .borrow()
             .load_class(class_name)?;
         match load_class_result {
             LoadClassResult::NewLoaded(class) => {
-                let class = self.do_class_loading(class)?;
+                let read_duration = start.elapsed();
+                //父类和接口加载完成之前不会注册该类，再次遇到同名的类说明继承关系中有循环
+                self.bootstrap_class_loader
+                    .borrow_mut()
+                    .begin_loading(class_name)?;
+                let result = self.do_class_loading(class);
+                self.bootstrap_class_loader
+                    .borrow_mut()
+                    .end_loading(class_name);
+                let class = result?;
+                self.record_timing(&class.name, |timing| timing.load += read_duration);
                 self.bootstrap_class_loader
                     .borrow_mut()
                     .registry_class(class);
@@ -69,63 +96,225 @@ impl<'a> MethodArea<'a> {
         }
     }
 
+    /// 数组类没有类文件，第一次使用时由虚拟机创建，父类是Object并实现Cloneable和Serializable。
+    /// 元素类型都由引导类加载器加载，数组类也只记录在引导类加载器中
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.3.3
+    fn load_array_class(&self, class_name: &str) -> VmExecResult<ClassRef<'a>> {
+        if let Some(class_ref) = self
+            .bootstrap_class_loader
+            .borrow_mut()
+            .find_loaded_class(class_name)
+        {
+            return Ok(*class_ref);
+        }
+        let super_class = self.load_class("java/lang/Object")?;
+        let mut interfaces = IndexMap::new();
+        for interface_name in ["java/lang/Cloneable", "java/io/Serializable"] {
+            interfaces.insert(interface_name.to_string(), self.load_class(interface_name)?);
+        }
+        let class_ref = self.alloc_class(Class {
+            version: Default::default(),
+            total_num_of_fields: 0,
+            //没有<clinit>，创建后就是初始化完成的状态
+            status: Cell::new(ClassStatus::Initialized),
+            name: class_name.to_string(),
+            constant_pool: RuntimeConstantPool::new(),
+            access_flags: ClassAccessFlags::PUBLIC
+                | ClassAccessFlags::FINAL
+                | ClassAccessFlags::ABSTRACT,
+            super_class: Some(super_class),
+            interface_names: interfaces.keys().cloned().collect(),
+            interfaces,
+            fields: IndexMap::new(),
+            methods: IndexMap::new(),
+            super_class_name: Some(super_class.name.clone()),
+            source_file: None,
+            bootstrap_method: Vec::new(),
+            inner_classes: Vec::new(),
+            enclosing_method: None,
+            class_loader: None,
+        });
+        self.bootstrap_class_loader
+            .borrow_mut()
+            .registry_class(class_ref);
+        Ok(class_ref)
+    }
+
     fn do_class_loading(&self, class_file: ClassFile) -> VmExecResult<ClassRef<'a>> {
-        let mut super_num_of_fields: usize = 0;
         //解析super_class
         let super_class = if let Some(super_class_name) = &class_file.super_class_name {
-            let result = self.load_class(super_class_name)?;
-            super_num_of_fields = result.total_num_of_fields;
-            Some(result)
+            Some(self.load_class(super_class_name)?)
         } else {
             None
         };
-
-        let mut interfaces = IndexMap::new();
         //解析加载接口
+        let mut interfaces = Vec::new();
         for interface_name in &class_file.interface_names {
-            let result = self.load_class(interface_name)?;
-            //我会确保map的key与Value中的name保持一致
-            let key = unsafe {
-                let str_ptr: *const str = result.name.as_str();
-                &*str_ptr
-            };
-            interfaces.insert(key, result);
+            interfaces.push(self.load_class(interface_name)?);
+        }
+        self.create_class(class_file, super_class, interfaces, None)
+    }
+
+    /// 查找类加载器已经定义或者作为初始加载器加载过的类
+    pub fn find_class_by_loader(
+        &self,
+        class_loader: ObjectReference<'a>,
+        class_name: &str,
+    ) -> Option<ClassRef<'a>> {
+        self.loader_classes
+            .borrow()
+            .get(&(class_loader, class_name.to_string()))
+            .copied()
+    }
+
+    /// 所有定义过类或者作为初始加载器加载过类的类加载器对象
+    pub fn class_loaders(&self) -> Vec<ObjectReference<'a>> {
+        self.loader_classes
+            .borrow()
+            .keys()
+            .map(|(class_loader, _)| *class_loader)
+            .collect()
+    }
+
+    /// 记录class_loader作为初始加载器加载了该类
+    pub fn record_initiating_loader(
+        &self,
+        class_loader: ObjectReference<'a>,
+        class_name: &str,
+        class_ref: ClassRef<'a>,
+    ) {
+        self.loader_classes
+            .borrow_mut()
+            .insert((class_loader, class_name.to_string()), class_ref);
+    }
+
+    /// 由自定义类加载器定义类，父类和接口需要调用方按照该加载器解析好
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.3.5
+    pub fn define_class(
+        &self,
+        class_loader: ObjectReference<'a>,
+        class_file: ClassFile,
+        super_class: Option<ClassRef<'a>>,
+        interfaces: Vec<ClassRef<'a>>,
+    ) -> VmExecResult<ClassRef<'a>> {
+        let class_name = class_file.this_class_name.clone();
+        if self
+            .find_class_by_loader(class_loader, &class_name)
+            .is_some()
+        {
+            return Err(VmError::LinkageError(format!(
+                "loader attempted duplicate class definition for {}",
+                class_name.replace('/', ".")
+            )));
+        }
+        let class_ref =
+            self.create_class(class_file, super_class, interfaces, Some(class_loader))?;
+        self.record_initiating_loader(class_loader, &class_name, class_ref);
+        Ok(class_ref)
+    }
+
+    /// 虚拟机重置时调用。类的元数据保留，引导类加载器加载的类回到Loaded状态，
+    /// 之后使用时重新链接和初始化。自定义类加载器对象在堆上，它们加载的类不再可见
+    pub(crate) fn reset_class_status(&self) {
+        for class_ref in self.bootstrap_class_loader.borrow().loaded_classes() {
+            //数组类没有静态状态，创建时就是Initialized
+            if !class_ref.name.starts_with('[') {
+                class_ref.status.set(ClassStatus::Loaded);
+            }
+        }
+        self.loader_classes.borrow_mut().clear();
+        self.take_timings();
+    }
+
+    /// 打开后记录每个类的加载、链接、初始化耗时，关闭时丢弃已有的记录
+    pub fn set_timing_enabled(&self, enabled: bool) {
+        *self.timings.borrow_mut() = enabled.then(IndexMap::new);
+    }
+
+    /// 按照类第一次出现的顺序取出已有的耗时记录
+    pub fn take_timings(&self) -> Vec<ClassLoadTiming> {
+        match self.timings.borrow_mut().as_mut() {
+            Some(timings) => std::mem::take(timings).into_values().collect(),
+            None => vec![],
+        }
+    }
+
+    pub(crate) fn record_timing(
+        &self,
+        class_name: &str,
+        update: impl FnOnce(&mut ClassLoadTiming),
+    ) {
+        if let Some(timings) = self.timings.borrow_mut().as_mut() {
+            let timing = timings
+                .entry(class_name.to_string())
+                .or_insert_with(|| ClassLoadTiming {
+                    class_name: class_name.to_string(),
+                    ..Default::default()
+                });
+            update(timing);
+        }
+    }
+
+    fn create_class(
+        &self,
+        class_file: ClassFile,
+        super_class: Option<ClassRef<'a>>,
+        interface_refs: Vec<ClassRef<'a>>,
+        class_loader: Option<ObjectReference<'a>>,
+    ) -> VmExecResult<ClassRef<'a>> {
+        let start = Instant::now();
+        let class_ref =
+            self.do_create_class(class_file, super_class, interface_refs, class_loader)?;
+        let duration = start.elapsed();
+        self.record_timing(&class_ref.name, |timing| timing.load += duration);
+        Ok(class_ref)
+    }
+
+    fn do_create_class(
+        &self,
+        class_file: ClassFile,
+        super_class: Option<ClassRef<'a>>,
+        interface_refs: Vec<ClassRef<'a>>,
+        class_loader: Option<ObjectReference<'a>>,
+    ) -> VmExecResult<ClassRef<'a>> {
+        let super_num_of_fields = super_class.map_or(0, |c| c.total_num_of_fields);
+        let mut interfaces = IndexMap::new();
+        for result in interface_refs {
+            interfaces.insert(result.name.clone(), result);
         }
         let constant_pool = RuntimeConstantPool::from(&class_file.constant_pool)?;
+        let source_file = class_file.source_file()?;
         let mut fields = IndexMap::new();
         let mut field_offset = 0;
         for field_info in class_file.field_info {
             let mut field = RuntimeFieldInfo::from(field_info, &constant_pool)?;
-            //我会确保map的key与Value中的name保持一致
-            let key = unsafe {
-                let str_ptr: *const str = field.name.as_str();
-                &*str_ptr
-            };
             if !field.is_static() {
                 field_offset += 1;
                 field.offset = super_num_of_fields + field_offset;
             }
-            fields.insert(key, field);
+            fields.insert(field.name.clone(), field);
         }
         let mut methods = IndexMap::new();
         for method_info in class_file.method_info {
             let method = RuntimeMethodInfo::from(method_info, &constant_pool)?;
             methods.insert(MethodKey::by_method(&method), method);
         }
-        let mut source_file = None;
         let mut bootstrap_method = Vec::new();
+        let mut inner_classes = Vec::new();
+        let mut enclosing_method = None;
         for x in &class_file.attribute_info {
-            if x.name == AttributeType::SourceFile {
-                let index = u16::from_be_bytes(x.info.as_slice().try_into().unwrap());
-                source_file = Some(constant_pool.get_utf8_string(index)?)
-            } else if x.name == AttributeType::BootstrapMethods {
+            if x.name == AttributeType::BootstrapMethods {
                 bootstrap_method = BootstrapMethod::from(&x.info, &constant_pool)?;
+            } else if x.name == AttributeType::InnerClasses {
+                inner_classes = InnerClass::from(&x.info, &constant_pool)?;
+            } else if x.name == AttributeType::EnclosingMethod {
+                enclosing_method = Some(EnclosingMethod::from(&x.info, &constant_pool)?);
             }
         }
-        let class_ref = self.classes.alloc(Class {
+        Ok(self.alloc_class(Class {
             version: class_file.version,
             total_num_of_fields: super_num_of_fields + fields.len(),
-            status: ClassStatus::Loaded,
+            status: Cell::new(ClassStatus::Loaded),
             name: class_file.this_class_name,
             constant_pool,
             access_flags: class_file.access_flags,
@@ -137,13 +326,20 @@ impl<'a> MethodArea<'a> {
             interface_names: class_file.interface_names,
             source_file,
             bootstrap_method,
-        });
-        //self的声明周期要大于classRef<'a>,实用unsafe 使得编译器能够编译
-        let class_ref = unsafe {
+            inner_classes,
+            enclosing_method,
+            class_loader,
+        }))
+    }
+
+    fn alloc_class(&self, class: Class<'a>) -> ClassRef<'a> {
+        let class_ref = self.classes.alloc(class);
+        //arena中的类在MethodArea销毁前不会移动或释放，而虚拟机持有的ClassRef<'a>都不会比MethodArea活得更久。
+        //借用检查器无法表达这种自引用关系，这里把&self的生命周期延长为'a
+        unsafe {
             let class_ptr: *const Class<'_> = class_ref;
             &*class_ptr
-        };
-        Ok(class_ref)
+        }
     }
 
     ///
@@ -161,6 +357,12 @@ impl<'a> MethodArea<'a> {
             .borrow_mut()
             .add_class_path(class_path);
     }
+
+    pub fn set_class_transformer(&self, transformer: Option<ClassTransformer>) {
+        self.bootstrap_class_loader
+            .borrow_mut()
+            .set_class_transformer(transformer);
+    }
 }
 
 mod tests {
@@ -171,7 +373,7 @@ mod tests {
         use crate::loaded_class::ClassStatus;
         use crate::method_area::MethodArea;
 
-        let mut area = MethodArea::default();
+        let area = MethodArea::default();
 
         let file_system_path = FileSystemClassPath::new("./resources").unwrap();
         area.add_class_path(Box::new(file_system_path));
@@ -180,7 +382,7 @@ mod tests {
         area.add_class_path(Box::new(rt_jar_path));
         let result = area.load_class("HelloWorld").unwrap();
 
-        assert!(matches!(result.status, ClassStatus::Loaded));
+        assert_eq!(result.status.get(), ClassStatus::Loaded);
         assert_eq!(2, area.num_of_classes());
 
         let (_, main_method) = result
@@ -189,10 +391,65 @@ mod tests {
 
         assert_eq!(main_method.name, "main");
 
-        let option = area.get_mut(result);
-        assert!(option.is_some());
+        //状态通过共享引用推进，不需要可变借用方法区
+        result.status.set(ClassStatus::Linked);
+        let reloaded = area.load_class("HelloWorld").unwrap();
+        assert_eq!(reloaded.status.get(), ClassStatus::Linked);
 
         let system_class = area.load_class("java/lang/System").unwrap();
-        println!("{}", system_class)
+        assert!(system_class.to_string().contains("class java/lang/System"));
+    }
+
+    #[test]
+    fn test_inner_classes() {
+        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
+        use crate::method_area::MethodArea;
+        use class_file_reader::class_file::InnerClassAccessFlags;
+
+        let area = MethodArea::default();
+        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
+        area.add_class_path(Box::new(file_system_path));
+        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
+        area.add_class_path(Box::new(rt_jar_path));
+
+        let outer = area.load_class("NestedTest").unwrap();
+        assert_eq!(outer.declaring_class_name(), None);
+        let mut declared = outer.declared_class_names();
+        declared.sort();
+        assert_eq!(
+            declared,
+            vec!["NestedTest$Inner", "NestedTest$StaticNested"]
+        );
+
+        let nested = area.load_class("NestedTest$StaticNested").unwrap();
+        assert_eq!(nested.declaring_class_name(), Some("NestedTest"));
+        assert!(nested.enclosing_method().is_none());
+        let entry = nested
+            .inner_classes
+            .iter()
+            .find(|inner| inner.inner_class_name == "NestedTest$StaticNested")
+            .unwrap();
+        assert_eq!(entry.inner_name.as_deref(), Some("StaticNested"));
+        assert!(entry.access_flags.contains(InnerClassAccessFlags::STATIC));
+
+        //匿名类和局部类没有声明类，但是有EnclosingMethod
+        let anonymous = area.load_class("NestedTest$1").unwrap();
+        assert!(anonymous.is_anonymous_class());
+        assert_eq!(anonymous.declaring_class_name(), None);
+        let enclosing = anonymous.enclosing_method().unwrap();
+        assert_eq!(enclosing.class_name, "NestedTest");
+        assert_eq!(enclosing.method_name.as_deref(), Some("anonymous"));
+        assert_eq!(
+            enclosing.method_descriptor.as_deref(),
+            Some("()Ljava/lang/Runnable;")
+        );
+
+        let local = area.load_class("NestedTest$1Local").unwrap();
+        assert!(!local.is_anonymous_class());
+        assert_eq!(local.declaring_class_name(), None);
+        assert_eq!(
+            local.enclosing_method().unwrap().method_name.as_deref(),
+            Some("local")
+        );
     }
 }
@@ -0,0 +1,226 @@
+//! ldc加载的MethodHandle常量，以及MethodHandle.invokeExact/invoke的调用。
+//! 句柄对象只作为标识，引用的(kind, 类, 名称, 描述符)保存在静态区，调用时直接使用已有的方法调用和字段访问。
+//! https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.5
+//! https://docs.oracle.com/javase/8/docs/api/java/lang/invoke/MethodHandle.html
+use crate::java_exception::{InvokeMethodResult, MethodCallError};
+use crate::jvm_error::VmError;
+use crate::jvm_values::{ObjectReference, Value, ValueType};
+use crate::runtime_constant_pool::MethodHandlerKind;
+use crate::runtime_method_info::MethodDescriptor;
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+
+pub(crate) const METHOD_HANDLE: &str = "java/lang/invoke/MethodHandle";
+pub(crate) const METHOD_HANDLES_LOOKUP: &str = "java/lang/invoke/MethodHandles$Lookup";
+
+//签名多态的方法，调用点的描述符就是调用的类型，不需要在MethodHandle中查找
+pub(crate) fn is_signature_polymorphic(class_name: &str, method_name: &str) -> bool {
+    class_name == METHOD_HANDLE && matches!(method_name, "invokeExact" | "invoke")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodHandleInfo {
+    pub kind: MethodHandlerKind,
+    pub class_name: String,
+    pub name: String,
+    pub descriptor: String,
+}
+
+impl MethodHandleInfo {
+    /// 句柄的类型。实例方法和实例字段的第一个参数是接收者，构造方法返回新建的对象
+    pub fn method_type(&self) -> String {
+        let owner = if self.class_name.starts_with('[') {
+            self.class_name.clone()
+        } else {
+            format!("L{};", self.class_name)
+        };
+        match self.kind {
+            MethodHandlerKind::InvokeStatic => self.descriptor.clone(),
+            MethodHandlerKind::InvokeVirtual
+            | MethodHandlerKind::InvokeInterface
+            | MethodHandlerKind::InvokeSpecial => format!("({}{}", owner, &self.descriptor[1..]),
+            MethodHandlerKind::NewInvokeSpecial => {
+                let args_end = self.descriptor.rfind(')').unwrap_or(0);
+                format!("{}){}", &self.descriptor[..args_end], owner)
+            }
+            MethodHandlerKind::GetField => format!("({}){}", owner, self.descriptor),
+            MethodHandlerKind::GetStatic => format!("(){}", self.descriptor),
+            MethodHandlerKind::PutField => format!("({}{})V", owner, self.descriptor),
+            MethodHandlerKind::PutStatic => format!("({})V", self.descriptor),
+        }
+    }
+
+    /// 检查调用点的描述符。invokeExact要求完全一致；
+    /// invoke还允许引用类型的宽化：参数是句柄参数类型的子类，句柄的返回值是调用点返回类型的子类
+    pub(crate) fn accepts<'a>(
+        &self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        site_descriptor: &str,
+        exact: bool,
+    ) -> Result<bool, MethodCallError<'a>> {
+        let method_type = self.method_type();
+        if method_type == site_descriptor {
+            return Ok(true);
+        }
+        if exact {
+            return Ok(false);
+        }
+        let expected = MethodDescriptor::new(&method_type)?;
+        let site = MethodDescriptor::new(site_descriptor)?;
+        if expected.args.len() != site.args.len() {
+            return Ok(false);
+        }
+        for (from, to) in site.args.iter().zip(&expected.args) {
+            if !Self::is_assignable(vm, call_stack, from, to)? {
+                return Ok(false);
+            }
+        }
+        //调用点不需要返回值时丢弃
+        if site.ret == ValueType::Void {
+            return Ok(true);
+        }
+        Self::is_assignable(vm, call_stack, &expected.ret, &site.ret)
+    }
+
+    fn is_assignable<'a>(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        from: &ValueType,
+        to: &ValueType,
+    ) -> Result<bool, MethodCallError<'a>> {
+        match (from, to) {
+            _ if from == to => Ok(true),
+            (
+                ValueType::Object(_) | ValueType::ObjectArray(..) | ValueType::PrimaryArray(..),
+                ValueType::Object(to),
+            ) if to == "java/lang/Object" => Ok(true),
+            (ValueType::Object(from), ValueType::Object(to)) => {
+                let class_ref = vm.load_class_by_loader(call_stack, None, from)?;
+                Ok(class_ref.is_subclass_of(to))
+            }
+            _ => Ok(false),
+        }
+    }
+
+    /// 按句柄的类型执行对应的字节码行为，args已经按调用点的描述符从操作数栈弹出
+    pub(crate) fn invoke<'a>(
+        &self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        mut args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let (class_name, name, descriptor) = (&self.class_name, &self.name, &self.descriptor);
+        match self.kind {
+            MethodHandlerKind::InvokeStatic => {
+                vm.call(call_stack, class_name, name, descriptor, None, args)
+            }
+            MethodHandlerKind::InvokeVirtual | MethodHandlerKind::InvokeInterface => {
+                let receiver = args.remove(0);
+                vm.call(
+                    call_stack,
+                    class_name,
+                    name,
+                    descriptor,
+                    Some(receiver),
+                    args,
+                )
+            }
+            //invokespecial不按接收者的实际类型分派
+            MethodHandlerKind::InvokeSpecial => {
+                let receiver = self.receiver(vm, call_stack, args.remove(0))?;
+                let (class_ref, method_ref) =
+                    vm.lookup_method(call_stack, class_name, name, descriptor)?;
+                vm.invoke_method(call_stack, class_ref, method_ref, Some(receiver), args)
+            }
+            MethodHandlerKind::NewInvokeSpecial => {
+                let object = vm.new_object_by_class_name(call_stack, class_name)?;
+                let (class_ref, method_ref) =
+                    vm.lookup_method(call_stack, class_name, name, descriptor)?;
+                vm.invoke_method(call_stack, class_ref, method_ref, Some(object), args)?;
+                Ok(Some(Value::ObjectRef(object)))
+            }
+            MethodHandlerKind::GetField => {
+                let receiver = self.receiver(vm, call_stack, args.remove(0))?;
+                Ok(Some(receiver.get_field_in_class(class_name, name)?))
+            }
+            MethodHandlerKind::PutField => {
+                let receiver = self.receiver(vm, call_stack, args.remove(0))?;
+                receiver.set_field_in_class(class_name, name, &args[0])?;
+                Ok(None)
+            }
+            MethodHandlerKind::GetStatic => {
+                let value = vm.get_static_field_by_class_name(call_stack, class_name, name)?;
+                Ok(Some(value.cloned().ok_or_else(|| {
+                    VmError::FieldNotFoundException(format!("{}.{}", class_name, name))
+                })?))
+            }
+            MethodHandlerKind::PutStatic => {
+                let value = args.remove(0);
+                vm.set_static_field_by_class_name(call_stack, class_name, name, value)?;
+                Ok(None)
+            }
+        }
+    }
+
+    //接收者为null时抛出NullPointerException，信息和VirtualMachine::call一致，是句柄指向的成员
+    fn receiver<'a>(
+        &self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        value: Value<'a>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        match value {
+            Value::ObjectRef(object_ref) => Ok(object_ref),
+            Value::Null => {
+                let error = vm.new_exception(
+                    call_stack,
+                    "java/lang/NullPointerException",
+                    &format!("{}.{}{}", self.class_name, self.name, self.descriptor),
+                )?;
+                Err(MethodCallError::ExceptionThrown(error))
+            }
+            _ => Err(VmError::ValueTypeMissMatch.into()),
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_method_type() {
+        use crate::method_handle::MethodHandleInfo;
+        use crate::runtime_constant_pool::MethodHandlerKind;
+        let info = |kind, name: &str, descriptor: &str| MethodHandleInfo {
+            kind,
+            class_name: "Point".to_string(),
+            name: name.to_string(),
+            descriptor: descriptor.to_string(),
+        };
+        for (handle, expected) in [
+            (
+                info(MethodHandlerKind::InvokeStatic, "of", "(II)LPoint;"),
+                "(II)LPoint;",
+            ),
+            (
+                info(MethodHandlerKind::InvokeVirtual, "getX", "()I"),
+                "(LPoint;)I",
+            ),
+            (
+                info(MethodHandlerKind::NewInvokeSpecial, "<init>", "(II)V"),
+                "(II)LPoint;",
+            ),
+            (info(MethodHandlerKind::GetField, "x", "I"), "(LPoint;)I"),
+            (info(MethodHandlerKind::PutField, "x", "I"), "(LPoint;I)V"),
+            (
+                info(MethodHandlerKind::GetStatic, "ORIGIN", "LPoint;"),
+                "()LPoint;",
+            ),
+            (
+                info(MethodHandlerKind::PutStatic, "ORIGIN", "LPoint;"),
+                "(LPoint;)V",
+            ),
+        ] {
+            assert_eq!(handle.method_type(), expected, "{:?}", handle);
+        }
+    }
+}
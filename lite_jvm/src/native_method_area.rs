@@ -1,11 +1,173 @@
 use crate::java_exception::{InvokeMethodResult, MethodCallError};
-use crate::jvm_error::VmError;
+use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+use crate::loaded_class::ClassRef;
 use crate::stack::CallStack;
+use crate::stack_trace_element::StackTraceElement;
+use crate::string_coding;
 use crate::virtual_machine::VirtualMachine;
-use class_file_reader::class_file_version::ClassFileVersion;
-use std::collections::HashMap;
+use class_file_reader::class_file::{ClassAccessFlags, InnerClassAccessFlags};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
 
+//生成StrictMath中参数和返回值都是double的native方法
+macro_rules! generate_strict_math_native {
+    ($name:ident, |$($arg:ident),+| $body:expr) => {
+        pub fn $name(
+            _vm: &mut VirtualMachine<'a>,
+            _call_stack: &mut CallStack<'a>,
+            _receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            let mut args = args.iter();
+            $(let $arg = args.next().ok_or(VmError::ValueTypeMissMatch)?.get_double()?;)+
+            Ok(Some(Value::Double($body)))
+        }
+    };
+}
+
+//和C的pow不同，Java中底数绝对值为1且指数为无穷大、以及指数为NaN时结果都是NaN
+//https://docs.oracle.com/javase/8/docs/api/java/lang/Math.html#pow-double-double-
+fn java_pow(a: f64, b: f64) -> f64 {
+    if b == 0f64 {
+        1f64
+    } else if b.is_nan() || (a.abs() == 1f64 && b.is_infinite()) {
+        f64::NAN
+    } else {
+        a.powf(b)
+    }
+}
+
+//IEEE 754的取余，商取最接近的整数，两个整数距离相同时取偶数
+fn ieee_remainder(a: f64, b: f64) -> f64 {
+    if a.is_nan() || b.is_nan() || a.is_infinite() || b == 0f64 {
+        return f64::NAN;
+    }
+    if b.is_infinite() {
+        return a;
+    }
+    let remainder = a % b;
+    let divisor = b.abs();
+    let rest = divisor - remainder.abs();
+    let quotient_is_odd = ((a - remainder) / b).abs() % 2f64 == 1f64;
+    if remainder.abs() > rest || (remainder.abs() == rest && quotient_is_odd) {
+        remainder - divisor.copysign(remainder)
+    } else {
+        remainder
+    }
+}
+
+//生成Integer/Long/Float/Double.toString的native方法，和Java实现一样每次返回新的String对象
+macro_rules! generate_to_string_native {
+    ($name:ident, $getter:ident, $format:expr) => {
+        pub fn $name(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            _receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            let text: String = $format(args[0].$getter()?);
+            let chars: Vec<u16> = text.encode_utf16().collect();
+            let string_object = vm.new_java_lang_string_from_chars(call_stack, &chars)?;
+            Ok(Some(Value::ObjectRef(string_object)))
+        }
+    };
+}
+
+//Double.toString/Float.toString的格式：10^-3 <= |d| < 10^7时使用小数形式，否则使用科学计数法，
+//两种形式的小数点后都至少有一位。有效数字取能唯一还原该值的最短表示(与JDK 19之后一致)，
+//由Rust的{:e}格式化得到
+//https://docs.oracle.com/javase/8/docs/api/java/lang/Double.html#toString-double-
+fn java_floating_to_string(scientific: String, negative: bool, plain: bool) -> String {
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    let exponent: i32 = exponent.parse().unwrap();
+    let digits = mantissa.replace('.', "");
+    let mut text = String::from(if negative { "-" } else { "" });
+    if !plain {
+        text.push_str(&digits[..1]);
+        text.push('.');
+        text.push_str(if digits.len() > 1 { &digits[1..] } else { "0" });
+        text.push_str(&format!("E{}", exponent));
+    } else if exponent < 0 {
+        text.push_str("0.");
+        text.push_str(&"0".repeat((-exponent - 1) as usize));
+        text.push_str(&digits);
+    } else {
+        let point = exponent as usize + 1;
+        if digits.len() > point {
+            text.push_str(&digits[..point]);
+            text.push('.');
+            text.push_str(&digits[point..]);
+        } else {
+            text.push_str(&digits);
+            text.push_str(&"0".repeat(point - digits.len()));
+            text.push_str(".0");
+        }
+    }
+    text
+}
+
+macro_rules! generate_java_floating_to_string {
+    ($name:ident, $t:ty) => {
+        fn $name(value: $t) -> String {
+            if value.is_nan() {
+                return "NaN".to_string();
+            }
+            if value.is_infinite() {
+                return if value > 0.0 { "Infinity" } else { "-Infinity" }.to_string();
+            }
+            if value == 0.0 {
+                return if value.is_sign_negative() {
+                    "-0.0"
+                } else {
+                    "0.0"
+                }
+                .to_string();
+            }
+            let abs = value.abs();
+            java_floating_to_string(
+                format!("{:e}", abs),
+                value.is_sign_negative(),
+                (1e-3..1e7).contains(&abs),
+            )
+        }
+    };
+}
+
+generate_java_floating_to_string!(java_double_to_string, f64);
+generate_java_floating_to_string!(java_float_to_string, f32);
+
+//Unsafe使用的偏移量就是get_field_by_offset的索引，对象是字段索引，数组是元素下标
+fn unsafe_get<'a>(target: &Value<'a>, offset: i64) -> VmExecResult<Value<'a>> {
+    match target {
+        Value::ObjectRef(object_ref) => object_ref.get_field_by_offset(offset as usize),
+        Value::ArrayRef(array_ref) => array_ref.get_field_by_offset(offset as usize),
+        _ => Err(VmError::ValueTypeMissMatch),
+    }
+}
+
+fn unsafe_put(target: &Value, offset: i64, value: &Value) -> VmExecResult<()> {
+    match target {
+        Value::ObjectRef(object_ref) => object_ref.set_field_by_offset(offset as usize, value),
+        Value::ArrayRef(array_ref) => array_ref.set_field_by_offset(offset as usize, value),
+        _ => Err(VmError::ValueTypeMissMatch),
+    }
+}
+
+//单线程执行，读取比较之后直接写入即可
+fn unsafe_compare_and_swap<'a>(args: &[Value<'a>]) -> InvokeMethodResult<'a> {
+    let offset = args[1].get_long()?;
+    let swapped = unsafe_get(&args[0], offset)? == args[2];
+    if swapped {
+        unsafe_put(&args[0], offset, &args[3])?;
+    }
+    Ok(Some(Value::Int(swapped as i32)))
+}
+
+/// native方法的实现，参数依次是虚拟机、调用栈、接收者(静态方法为None)和参数。
+/// 参数按描述符一个参数一个元素，long和double也只占一个元素，和局部变量表的槽位不同
 pub type NativeMethod<'a> = fn(
     &mut VirtualMachine<'a>,
     &mut CallStack<'a>,
@@ -15,18 +177,23 @@ pub type NativeMethod<'a> = fn(
 
 pub struct NativeMethodArea<'a> {
     native_methods: HashMap<String, NativeMethod<'a>>,
+    //存在被替换实现的非native方法的类，避免每次调用方法都拼接key查找
+    overridden_classes: HashSet<String>,
 }
 
 impl<'a> NativeMethodArea<'a> {
     pub fn new_with_default_native() -> NativeMethodArea<'a> {
         let mut area = NativeMethodArea {
             native_methods: HashMap::new(),
+            overridden_classes: HashSet::new(),
         };
-        area.registry_native_method(
+        //registerNatives只负责注册native方法，System的<clinit>完成后虚拟机再调用initializeSystemClass
+        area.registry_native_method("java/lang/System", "registerNatives", "()V", Self::nop);
+        area.registry_override_method(
             "java/lang/System",
-            "registerNatives",
+            "initializeSystemClass",
             "()V",
-            Self::java_lang_system_register_native,
+            Self::java_lang_system_initialize_system_class,
         );
         area.registry_native_method(
             "java/lang/Class",
@@ -52,17 +219,443 @@ impl<'a> NativeMethodArea<'a> {
         area.registry_native_method("java/lang/Thread", "registerNatives", "()V", Self::nop);
         area.registry_native_method("java/lang/Class", "registerNatives", "()V", Self::nop);
         area.registry_native_method("sun/misc/Unsafe", "registerNatives", "()V", Self::nop);
+        area.registry_native_method("java/lang/ClassLoader", "registerNatives", "()V", Self::nop);
+        area.registry_native_method("sun/misc/VM", "initialize", "()V", Self::nop);
+        area.registry_native_method(
+            "java/lang/Object",
+            "hashCode",
+            "()I",
+            Self::java_lang_class_hash_code,
+        );
+        area.registry_native_method(
+            "java/lang/Object",
+            "getClass",
+            "()Ljava/lang/Class;",
+            Self::java_lang_object_get_class,
+        );
+        area.registry_native_method(
+            "java/lang/System",
+            "nanoTime",
+            "()J",
+            Self::java_lang_system_nano_time,
+        );
+        area.registry_native_method(
+            "java/lang/System",
+            "currentTimeMillis",
+            "()J",
+            Self::java_lang_system_current_time_millis,
+        );
+        area.registry_native_method(
+            "java/lang/Thread",
+            "sleep",
+            "(J)V",
+            Self::java_lang_thread_sleep,
+        );
         area.registry_native_method(
             "java/lang/Object",
             "clone",
             "()Ljava/lang/Object;",
             Self::java_lang_object_clone,
         );
-        area.registry_native_method(
+        //AtomicInteger、AtomicLong、ConcurrentHashMap等依赖的Unsafe子集
+        let unsafe_methods: [(&str, &str, NativeMethod<'a>); 28] = [
+            (
+                "arrayBaseOffset",
+                "(Ljava/lang/Class;)I",
+                Self::sun_misc_unsafe_array_base_offset,
+            ),
+            (
+                "arrayIndexScale",
+                "(Ljava/lang/Class;)I",
+                Self::sun_misc_unsafe_array_index_scale,
+            ),
+            ("addressSize", "()I", Self::sun_misc_unsafe_address_size),
+            (
+                "objectFieldOffset",
+                "(Ljava/lang/reflect/Field;)J",
+                Self::sun_misc_unsafe_object_field_offset,
+            ),
+            (
+                "getInt",
+                "(Ljava/lang/Object;J)I",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "getIntVolatile",
+                "(Ljava/lang/Object;J)I",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "getLong",
+                "(Ljava/lang/Object;J)J",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "getLongVolatile",
+                "(Ljava/lang/Object;J)J",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "getObject",
+                "(Ljava/lang/Object;J)Ljava/lang/Object;",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "getObjectVolatile",
+                "(Ljava/lang/Object;J)Ljava/lang/Object;",
+                Self::sun_misc_unsafe_get,
+            ),
+            (
+                "putInt",
+                "(Ljava/lang/Object;JI)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putIntVolatile",
+                "(Ljava/lang/Object;JI)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putOrderedInt",
+                "(Ljava/lang/Object;JI)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putLong",
+                "(Ljava/lang/Object;JJ)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putLongVolatile",
+                "(Ljava/lang/Object;JJ)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putOrderedLong",
+                "(Ljava/lang/Object;JJ)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putObject",
+                "(Ljava/lang/Object;JLjava/lang/Object;)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putObjectVolatile",
+                "(Ljava/lang/Object;JLjava/lang/Object;)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "putOrderedObject",
+                "(Ljava/lang/Object;JLjava/lang/Object;)V",
+                Self::sun_misc_unsafe_put,
+            ),
+            (
+                "compareAndSwapInt",
+                "(Ljava/lang/Object;JII)Z",
+                Self::sun_misc_unsafe_compare_and_swap,
+            ),
+            (
+                "compareAndSwapLong",
+                "(Ljava/lang/Object;JJJ)Z",
+                Self::sun_misc_unsafe_compare_and_swap,
+            ),
+            (
+                "compareAndSwapObject",
+                "(Ljava/lang/Object;JLjava/lang/Object;Ljava/lang/Object;)Z",
+                Self::sun_misc_unsafe_compare_and_swap,
+            ),
+            (
+                "allocateInstance",
+                "(Ljava/lang/Class;)Ljava/lang/Object;",
+                Self::sun_misc_unsafe_allocate_instance,
+            ),
+            ("park", "(ZJ)V", Self::nop),
+            ("unpark", "(Ljava/lang/Object;)V", Self::nop),
+            ("loadFence", "()V", Self::nop),
+            ("storeFence", "()V", Self::nop),
+            ("fullFence", "()V", Self::nop),
+        ];
+        for (method_name, descriptor, method) in unsafe_methods {
+            area.registry_native_method("sun/misc/Unsafe", method_name, descriptor, method);
+        }
+        //JDK8中getAndAddInt是CAS循环，单线程下直接读取相加
+        area.registry_override_method(
             "sun/misc/Unsafe",
-            "arrayBaseOffset",
-            "(Ljava/lang/Class;)I",
-            Self::sun_misc_unsafe_array_base_offset,
+            "getAndAddInt",
+            "(Ljava/lang/Object;JI)I",
+            Self::sun_misc_unsafe_get_and_add_int,
+        );
+        //initializeSystemClass没有调用VM.saveAndRemoveProperties，savedProps为空时getSavedProperty会抛出IllegalStateException，
+        //这里当作没有保存任何属性，IntegerCache等使用默认值
+        area.registry_override_method(
+            "sun/misc/VM",
+            "getSavedProperty",
+            "(Ljava/lang/String;)Ljava/lang/String;",
+            Self::sun_misc_vm_get_saved_property,
+        );
+        //没有安全管理器，特权操作直接执行
+        for descriptor in [
+            "(Ljava/security/PrivilegedAction;)Ljava/lang/Object;",
+            "(Ljava/security/PrivilegedAction;Ljava/security/AccessControlContext;)Ljava/lang/Object;",
+        ] {
+            area.registry_native_method(
+                "java/security/AccessController",
+                "doPrivileged",
+                descriptor,
+                Self::java_security_access_controller_do_privileged,
+            );
+        }
+        //System.out/err的print/println直接写入VirtualMachine的writer，其他PrintStream仍然执行字节码
+        let print_methods: [(&str, NativeMethod<'a>, NativeMethod<'a>); 9] = [
+            (
+                "Z",
+                Self::java_io_print_stream_print::<'Z', false>,
+                Self::java_io_print_stream_print::<'Z', true>,
+            ),
+            (
+                "C",
+                Self::java_io_print_stream_print::<'C', false>,
+                Self::java_io_print_stream_print::<'C', true>,
+            ),
+            (
+                "I",
+                Self::java_io_print_stream_print::<'I', false>,
+                Self::java_io_print_stream_print::<'I', true>,
+            ),
+            (
+                "J",
+                Self::java_io_print_stream_print::<'J', false>,
+                Self::java_io_print_stream_print::<'J', true>,
+            ),
+            (
+                "F",
+                Self::java_io_print_stream_print::<'F', false>,
+                Self::java_io_print_stream_print::<'F', true>,
+            ),
+            (
+                "D",
+                Self::java_io_print_stream_print::<'D', false>,
+                Self::java_io_print_stream_print::<'D', true>,
+            ),
+            (
+                "[C",
+                Self::java_io_print_stream_print::<'[', false>,
+                Self::java_io_print_stream_print::<'[', true>,
+            ),
+            (
+                "Ljava/lang/String;",
+                Self::java_io_print_stream_print::<'S', false>,
+                Self::java_io_print_stream_print::<'S', true>,
+            ),
+            (
+                "Ljava/lang/Object;",
+                Self::java_io_print_stream_print::<'L', false>,
+                Self::java_io_print_stream_print::<'L', true>,
+            ),
+        ];
+        for (arg, print, println) in print_methods {
+            let descriptor = format!("({})V", arg);
+            area.registry_override_method("java/io/PrintStream", "print", &descriptor, print);
+            area.registry_override_method("java/io/PrintStream", "println", &descriptor, println);
+        }
+        area.registry_override_method(
+            "java/io/PrintStream",
+            "println",
+            "()V",
+            Self::java_io_print_stream_print::<'V', true>,
+        );
+        area.registry_override_method(
+            "java/io/PrintStream",
+            "flush",
+            "()V",
+            Self::java_io_print_stream_flush,
+        );
+        area.registry_native_method(
+            "java/util/concurrent/atomic/AtomicLong",
+            "VMSupportsCS8",
+            "()Z",
+            Self::java_util_concurrent_atomic_atomic_long_vm_supports_cs8,
+        );
+        area.registry_native_method(
+            "sun/reflect/Reflection",
+            "getCallerClass",
+            "()Ljava/lang/Class;",
+            Self::sun_reflect_reflection_get_caller_class,
+        );
+        //Class.getDeclaredField依赖ReflectionData、SoftReference以及ReflectionFactory，
+        //这里直接根据类中的字段构造Field对象
+        area.registry_override_method(
+            "java/lang/Class",
+            "getDeclaredField",
+            "(Ljava/lang/String;)Ljava/lang/reflect/Field;",
+            Self::java_lang_class_get_declared_field,
+        );
+        //Class.getInterfaces优先从ReflectionData中读取缓存，同样依赖SoftReference，直接返回getInterfaces0的结果
+        area.registry_override_method(
+            "java/lang/Class",
+            "getInterfaces",
+            "()[Ljava/lang/Class;",
+            Self::java_lang_class_get_interfaces,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "getInterfaces0",
+            "()[Ljava/lang/Class;",
+            Self::java_lang_class_get_interfaces,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "getSuperclass",
+            "()Ljava/lang/Class;",
+            Self::java_lang_class_get_superclass,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "isInterface",
+            "()Z",
+            Self::java_lang_class_is_interface,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "isArray",
+            "()Z",
+            Self::java_lang_class_is_array,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "isPrimitive",
+            "()Z",
+            Self::java_lang_class_is_primitive,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "getModifiers",
+            "()I",
+            Self::java_lang_class_get_modifiers,
+        );
+        //Enum.valueOf通过Class.enumConstantDirectory反射调用values()，找不到常量时还依赖getCanonicalName，
+        //这里直接调用枚举类的values()按名称查找
+        area.registry_override_method(
+            "java/lang/Enum",
+            "valueOf",
+            "(Ljava/lang/Class;Ljava/lang/String;)Ljava/lang/Enum;",
+            Self::java_lang_enum_value_of,
+        );
+
+        area.registry_native_method(
+            "java/lang/Float",
+            "floatToRawIntBits",
+            "(F)I",
+            Self::java_lang_float_float_to_raw_int_bits,
+        );
+        area.registry_native_method(
+            "java/lang/Double",
+            "doubleToRawLongBits",
+            "(D)J",
+            Self::java_lang_double_double_to_raw_long_bits,
+        );
+        //非Raw的版本把所有NaN都转换为规范的NaN，JDK中是Java实现，这里直接替换
+        area.registry_override_method(
+            "java/lang/Float",
+            "floatToIntBits",
+            "(F)I",
+            Self::java_lang_float_float_to_int_bits,
+        );
+        area.registry_override_method(
+            "java/lang/Double",
+            "doubleToLongBits",
+            "(D)J",
+            Self::java_lang_double_double_to_long_bits,
+        );
+        area.registry_native_method(
+            "java/lang/Float",
+            "intBitsToFloat",
+            "(I)F",
+            Self::java_lang_float_int_bits_to_float,
+        );
+        area.registry_native_method(
+            "java/lang/Double",
+            "longBitsToDouble",
+            "(J)D",
+            Self::java_lang_double_long_bits_to_double,
+        );
+
+        //Double/Float.toString依赖FloatingDecimal，Integer/Long.toString依赖getChars，都直接用Rust格式化
+        area.registry_override_method(
+            "java/lang/Integer",
+            "toString",
+            "(I)Ljava/lang/String;",
+            Self::java_lang_integer_to_string,
+        );
+        area.registry_override_method(
+            "java/lang/Long",
+            "toString",
+            "(J)Ljava/lang/String;",
+            Self::java_lang_long_to_string,
+        );
+        area.registry_override_method(
+            "java/lang/Double",
+            "toString",
+            "(D)Ljava/lang/String;",
+            Self::java_lang_double_to_string,
+        );
+        area.registry_override_method(
+            "java/lang/Float",
+            "toString",
+            "(F)Ljava/lang/String;",
+            Self::java_lang_float_to_string,
+        );
+
+        //Double.parseDouble依赖FloatingDecimal，直接用Rust解析。Integer.parseInt和Long.parseLong见intrinsics
+        area.registry_override_method(
+            "java/lang/Double",
+            "parseDouble",
+            "(Ljava/lang/String;)D",
+            Self::java_lang_double_parse_double,
+        );
+
+        //StringCoding依赖sun.nio.cs的编码器，byte[]和String之间的转换直接在Rust中完成
+        for descriptor in [
+            "([B)V",
+            "([BLjava/lang/String;)V",
+            "([BII)V",
+            "([BIILjava/lang/String;)V",
+        ] {
+            area.registry_override_method(
+                "java/lang/String",
+                "<init>",
+                descriptor,
+                string_coding::java_lang_string_init_bytes,
+            );
+        }
+        for descriptor in ["()[B", "(Ljava/lang/String;)[B"] {
+            area.registry_override_method(
+                "java/lang/String",
+                "getBytes",
+                descriptor,
+                string_coding::java_lang_string_get_bytes,
+            );
+        }
+
+        //不支持关闭钩子，exit和halt一样直接结束执行
+        area.registry_override_method(
+            "java/lang/Runtime",
+            "exit",
+            "(I)V",
+            Self::java_lang_shutdown_halt0,
+        );
+        area.registry_native_method(
+            "java/lang/Shutdown",
+            "halt0",
+            "(I)V",
+            Self::java_lang_shutdown_halt0,
+        );
+        area.registry_native_method(
+            "java/lang/System",
+            "exit",
+            "(I)V",
+            Self::java_lang_shutdown_halt0,
         );
 
         area.registry_native_method(
@@ -71,6 +664,135 @@ impl<'a> NativeMethodArea<'a> {
             "(Ljava/lang/Object;ILjava/lang/Object;II)V",
             Self::java_lang_system_arraycopy,
         );
+        //Math的大部分方法最终都调用StrictMath的native方法
+        let strict_math_natives: [(&str, &str, NativeMethod<'a>); 20] = [
+            ("sin", "(D)D", Self::java_lang_strict_math_sin),
+            ("cos", "(D)D", Self::java_lang_strict_math_cos),
+            ("tan", "(D)D", Self::java_lang_strict_math_tan),
+            ("asin", "(D)D", Self::java_lang_strict_math_asin),
+            ("acos", "(D)D", Self::java_lang_strict_math_acos),
+            ("atan", "(D)D", Self::java_lang_strict_math_atan),
+            ("exp", "(D)D", Self::java_lang_strict_math_exp),
+            ("log", "(D)D", Self::java_lang_strict_math_log),
+            ("log10", "(D)D", Self::java_lang_strict_math_log10),
+            ("sqrt", "(D)D", Self::java_lang_strict_math_sqrt),
+            ("cbrt", "(D)D", Self::java_lang_strict_math_cbrt),
+            ("sinh", "(D)D", Self::java_lang_strict_math_sinh),
+            ("cosh", "(D)D", Self::java_lang_strict_math_cosh),
+            ("tanh", "(D)D", Self::java_lang_strict_math_tanh),
+            ("expm1", "(D)D", Self::java_lang_strict_math_expm1),
+            ("log1p", "(D)D", Self::java_lang_strict_math_log1p),
+            ("atan2", "(DD)D", Self::java_lang_strict_math_atan2),
+            ("pow", "(DD)D", Self::java_lang_strict_math_pow),
+            ("hypot", "(DD)D", Self::java_lang_strict_math_hypot),
+            (
+                "IEEEremainder",
+                "(DD)D",
+                Self::java_lang_strict_math_ieee_remainder,
+            ),
+        ];
+        for (method_name, descriptor, method) in strict_math_natives {
+            area.registry_native_method("java/lang/StrictMath", method_name, descriptor, method);
+        }
+        area.registry_native_method(
+            "java/lang/Throwable",
+            "fillInStackTrace",
+            "(I)Ljava/lang/Throwable;",
+            Self::java_lang_throwable_fill_in_stack_trace,
+        );
+        area.registry_native_method(
+            "java/lang/Throwable",
+            "getStackTraceDepth",
+            "()I",
+            Self::java_lang_throwable_get_stack_trace_depth,
+        );
+        area.registry_native_method(
+            "java/lang/Throwable",
+            "getStackTraceElement",
+            "(I)Ljava/lang/StackTraceElement;",
+            Self::java_lang_throwable_get_stack_trace_element,
+        );
+        area.registry_native_method(
+            "java/lang/Class",
+            "forName0",
+            "(Ljava/lang/String;ZLjava/lang/ClassLoader;Ljava/lang/Class;)Ljava/lang/Class;",
+            Self::java_lang_class_for_name0,
+        );
+        //ClassLoader的构造函数和加载流程依赖SecurityManager、ProtectionDomain、PerfCounter等，
+        //这里只保留parent委派、findClass以及defineClass
+        area.registry_override_method(
+            "java/lang/ClassLoader",
+            "<init>",
+            "(Ljava/lang/Void;Ljava/lang/ClassLoader;)V",
+            Self::java_lang_class_loader_init,
+        );
+        area.registry_override_method(
+            "java/lang/ClassLoader",
+            "loadClass",
+            "(Ljava/lang/String;Z)Ljava/lang/Class;",
+            Self::java_lang_class_loader_load_class,
+        );
+        area.registry_override_method(
+            "java/lang/ClassLoader",
+            "findLoadedClass",
+            "(Ljava/lang/String;)Ljava/lang/Class;",
+            Self::java_lang_class_loader_find_loaded_class,
+        );
+        area.registry_override_method(
+            "java/lang/ClassLoader",
+            "defineClass",
+            "(Ljava/lang/String;[BIILjava/security/ProtectionDomain;)Ljava/lang/Class;",
+            Self::java_lang_class_loader_define_class,
+        );
+        //run_test_class运行的测试代码使用的断言，内置在核心类库中
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertEquals",
+            "(II)V",
+            Self::lite_jvm_assert_equals_int,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertEquals",
+            "(JJ)V",
+            Self::lite_jvm_assert_equals_long,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertTrue",
+            "(Z)V",
+            Self::lite_jvm_assert_true,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertFalse",
+            "(Z)V",
+            Self::lite_jvm_assert_false,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertNull",
+            "(Ljava/lang/Object;)V",
+            Self::lite_jvm_assert_null,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "assertNotNull",
+            "(Ljava/lang/Object;)V",
+            Self::lite_jvm_assert_not_null,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "fail",
+            "(Ljava/lang/String;)V",
+            Self::lite_jvm_assert_fail,
+        );
+        area.registry_native_method(
+            "lite/jvm/Assert",
+            "failNotEquals",
+            "(Ljava/lang/String;Ljava/lang/String;)V",
+            Self::lite_jvm_assert_fail_not_equals,
+        );
         area
     }
     pub fn nop(
@@ -82,13 +804,78 @@ impl<'a> NativeMethodArea<'a> {
         Ok(None)
     }
 
-    pub fn java_lang_object_clone(
+    pub fn sun_misc_vm_get_saved_property(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(Value::Null))
+    }
+
+    pub fn java_lang_system_nano_time(
+        vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(Value::Long(vm.nano_time())))
+    }
+
+    pub fn java_lang_system_current_time_millis(
         vm: &mut VirtualMachine<'a>,
         _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(Value::Long(vm.current_time_millis())))
+    }
+
+    //暂不支持线程中断，sleep不会抛出InterruptedException
+    pub fn java_lang_thread_sleep(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let millis = args[0].get_long()?;
+        if millis < 0 {
+            let exception = vm.new_exception(
+                call_stack,
+                "java/lang/IllegalArgumentException",
+                "timeout value is negative",
+            )?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        std::thread::sleep(Duration::from_millis(millis as u64));
+        Ok(None)
+    }
+
+    pub fn java_lang_object_clone(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(vm.clone_value(call_stack, &receiver.unwrap())?))
+    }
+    pub fn java_lang_object_get_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
         receiver: Option<Value<'a>>,
         _args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        Ok(Some(vm.clone_value(&receiver.unwrap())))
+        let class_object = match receiver {
+            Some(Value::ObjectRef(object_ref)) => {
+                vm.get_java_lang_class_object(call_stack, object_ref.get_class())?
+            }
+            Some(Value::ArrayRef(array_ref)) => {
+                let array_class = vm.array_class(array_ref)?;
+                vm.get_java_lang_class_object(call_stack, array_class)?
+            }
+            _ => return Err(MethodCallError::InternalError(VmError::ValueTypeMissMatch)),
+        };
+        Ok(Some(Value::ObjectRef(class_object)))
     }
     pub fn sun_misc_unsafe_array_base_offset(
         _vm: &mut VirtualMachine<'a>,
@@ -96,98 +883,992 @@ impl<'a> NativeMethodArea<'a> {
         _receiver: Option<Value<'a>>,
         _args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        todo!("实现Unsafe相关的工作是非常繁琐的")
+        Ok(Some(Value::Int(0)))
     }
 
-    pub fn java_lang_class_hash_code(
+    //数组元素按下标访问，每个元素占一个偏移量
+    pub fn sun_misc_unsafe_array_index_scale(
         _vm: &mut VirtualMachine<'a>,
         _call_stack: &mut CallStack<'a>,
         _receiver: Option<Value<'a>>,
         _args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        if let Some(obj) = _receiver {
-            match obj {
-                Value::ObjectRef(obj) => Ok(Some(Value::Int(obj.hash_code()))),
-                Value::ArrayRef(obj) => Ok(Some(Value::Int(obj.hash_code()))),
-                _ => Ok(Some(Value::Int(-1))),
-            }
-        } else {
-            Ok(Some(Value::Int(-1)))
-        }
+        Ok(Some(Value::Int(1)))
     }
-    pub fn java_lang_class_desired_assertion_status0(
+
+    pub fn sun_misc_unsafe_address_size(
         _vm: &mut VirtualMachine<'a>,
         _call_stack: &mut CallStack<'a>,
         _receiver: Option<Value<'a>>,
         _args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        Ok(Some(Value::Int(1)))
+        Ok(Some(Value::Int(std::mem::size_of::<usize>() as i32)))
     }
-    pub fn java_lang_system_arraycopy(
+
+    pub fn sun_misc_unsafe_object_field_offset(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let field = args[0].get_object()?;
+        let class_object = field.get_field_by_name("clazz")?.get_object()?;
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        let name = field.get_field_by_name("name")?.get_string()?;
+        let index = class_ref
+            .field_index(&name)
+            .ok_or(VmError::FieldNotFoundException(name))?;
+        Ok(Some(Value::Long(index as i64)))
+    }
+
+    pub fn sun_misc_unsafe_get(
         _vm: &mut VirtualMachine<'a>,
         _call_stack: &mut CallStack<'a>,
         _receiver: Option<Value<'a>>,
         args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        assert_eq!(args.len(), 5);
-        let from_array = args[0].get_array()?;
-        let from_start = args[1].get_int()?;
+        Ok(Some(unsafe_get(&args[0], args[1].get_long()?)?))
+    }
 
-        let to_array = args[2].get_array()?;
-        let to_start = args[3].get_int()?;
-        let length = args[4].get_int()?;
-        for offset in 0..length {
-            let value = from_array.get_field_by_offset((offset + from_start) as usize)?;
-            to_array.set_field_by_offset((offset + to_start) as usize, &value)?;
-        }
+    pub fn sun_misc_unsafe_put(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        unsafe_put(&args[0], args[1].get_long()?, &args[2])?;
         Ok(None)
     }
-    pub fn java_lang_class_get_primitive_class(
+
+    pub fn sun_misc_unsafe_compare_and_swap(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        unsafe_compare_and_swap(&args)
+    }
+
+    pub fn sun_misc_unsafe_get_and_add_int(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let offset = args[1].get_long()?;
+        let old_value = unsafe_get(&args[0], offset)?.get_int()?;
+        let new_value = Value::Int(old_value.wrapping_add(args[2].get_int()?));
+        unsafe_put(&args[0], offset, &new_value)?;
+        Ok(Some(Value::Int(old_value)))
+    }
+
+    //分配对象但不执行构造函数
+    pub fn sun_misc_unsafe_allocate_instance(
         vm: &mut VirtualMachine<'a>,
         call_stack: &mut CallStack<'a>,
         _receiver: Option<Value<'a>>,
         args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        let class_name = &args[0].get_string()?;
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, args[0].get_object()?)?;
+        vm.link_and_initialize(call_stack, class_ref)?;
+        Ok(Some(Value::ObjectRef(
+            vm.new_object(call_stack, class_ref)?,
+        )))
+    }
 
-        let wrapped_class_name = match class_name.as_str() {
-            "long" => "java/lang/Long",
-            "boolean" => "java/lang/Boolean",
-            "int" => "java/lang/Integer",
-            "short" => "java/lang/Short",
-            "byte" => "java/lang/Byte",
-            "double" => "java/lang/Double",
-            "float" => "java/lang/Float",
-            _ => class_name,
-        };
-        let object_ref = vm.new_java_lang_class_object(call_stack, wrapped_class_name)?;
-        Ok(Some(Value::ObjectRef(object_ref)))
+    pub fn java_util_concurrent_atomic_atomic_long_vm_supports_cs8(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(Value::Int(1)))
     }
-    pub fn java_lang_system_register_native(
+
+    //native方法不创建栈帧，栈顶是调用getCallerClass的方法，再往下一层就是它的调用者
+    pub fn sun_reflect_reflection_get_caller_class(
         vm: &mut VirtualMachine<'a>,
         call_stack: &mut CallStack<'a>,
         _receiver: Option<Value<'a>>,
         _args: Vec<Value<'a>>,
     ) -> InvokeMethodResult<'a> {
-        let class_ref = vm.get_class_by_name(call_stack, "java/lang/System")?;
-        let method_ref = match class_ref.version {
-            ClassFileVersion::Jdk8 => class_ref.get_method("initializeSystemClass", "()V")?,
-            ClassFileVersion::Jdk17 => class_ref.get_method("initPhase1", "()V")?,
-            _ => {
-                return Err(MethodCallError::InternalError(
-                    VmError::ClassVersionNotSupport,
-                ))
+        let caller = call_stack
+            .frames()
+            .rev()
+            .nth(1)
+            .map(|frame| frame.class_ref);
+        match caller {
+            Some(class_ref) => Ok(Some(Value::ObjectRef(
+                vm.get_java_lang_class_object(call_stack, class_ref)?,
+            ))),
+            None => Ok(Some(Value::Null)),
+        }
+    }
+
+    pub fn java_security_access_controller_do_privileged(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        vm.call(
+            call_stack,
+            "java/security/PrivilegedAction",
+            "run",
+            "()Ljava/lang/Object;",
+            Some(args[0].clone()),
+            vec![],
+        )
+    }
+
+    //TYPE是参数描述符的首字符，String用'S'、无参数用'V'表示。
+    //String以外的参数通过String.valueOf转换，格式和Java保持一致
+    pub fn java_io_print_stream_print<const TYPE: char, const NEWLINE: bool>(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let stream = receiver.unwrap().get_object()?;
+        if !vm.is_standard_stream(stream) {
+            let descriptor = match TYPE {
+                'V' => "()V".to_string(),
+                'S' => "(Ljava/lang/String;)V".to_string(),
+                'L' => "(Ljava/lang/Object;)V".to_string(),
+                '[' => "([C)V".to_string(),
+                primary => format!("({})V", primary),
+            };
+            let method_name = if NEWLINE { "println" } else { "print" };
+            return Self::invoke_print_stream_bytecode(
+                vm,
+                call_stack,
+                stream,
+                method_name,
+                &descriptor,
+                args,
+            );
+        }
+        let mut text = match (TYPE, args.into_iter().next()) {
+            (_, None) => String::new(),
+            ('S', Some(Value::Null)) => "null".to_string(),
+            ('S', Some(value)) => value.get_string()?,
+            (_, Some(value)) => {
+                let arg = match TYPE {
+                    'L' => "Ljava/lang/Object;".to_string(),
+                    '[' => "[C".to_string(),
+                    primary => primary.to_string(),
+                };
+                let string = vm.call(
+                    call_stack,
+                    "java/lang/String",
+                    "valueOf",
+                    &format!("({})Ljava/lang/String;", arg),
+                    None,
+                    vec![value],
+                )?;
+                string.ok_or(VmError::ValueTypeMissMatch)?.get_string()?
+            }
+        };
+        if NEWLINE {
+            text.push('\n');
+        }
+        vm.write_to_stream(stream, &text)?;
+        Ok(None)
+    }
+
+    pub fn java_io_print_stream_flush(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let stream = receiver.unwrap().get_object()?;
+        if vm.is_standard_stream(stream) {
+            //write_to_stream每次写入后都已经flush
+            return Ok(None);
+        }
+        Self::invoke_print_stream_bytecode(vm, call_stack, stream, "flush", "()V", args)
+    }
+
+    //System.out/err以外的PrintStream，例如包装了ByteArrayOutputStream的，执行原来的字节码写入底层的OutputStream
+    fn invoke_print_stream_bytecode(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        stream: ObjectReference<'a>,
+        method_name: &str,
+        descriptor: &str,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_ref = vm.get_class_by_name(call_stack, "java/io/PrintStream")?;
+        let method_ref = class_ref.get_method(method_name, descriptor)?;
+        vm.invoke_bytecode(call_stack, class_ref, method_ref, Some(stream), args)
+    }
+
+    pub fn java_lang_class_get_declared_field(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        let name = args[0].get_string()?;
+        //基本类型没有字段
+        if vm.is_primitive_class_object(class_object) {
+            let exception =
+                vm.new_exception(call_stack, "java/lang/NoSuchFieldException", &name)?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        let Some((slot, _, field)) = class_ref.fields.get_full(name.as_str()) else {
+            let exception =
+                vm.new_exception(call_stack, "java/lang/NoSuchFieldException", &name)?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        };
+        let type_name = match field.descriptor.as_str() {
+            "B" => "byte",
+            "C" => "char",
+            "D" => "double",
+            "F" => "float",
+            "I" => "int",
+            "J" => "long",
+            "S" => "short",
+            "Z" => "boolean",
+            descriptor if descriptor.starts_with('L') => &descriptor[1..descriptor.len() - 1],
+            descriptor => descriptor,
+        };
+        //基本类型使用和int.class、Integer.TYPE相同的Class对象
+        let type_object = match vm.get_primitive_class_object(call_stack, type_name)? {
+            Some(class_object) => class_object,
+            None => vm.new_java_lang_class_object(call_stack, type_name)?,
+        };
+        let field_object = vm.new_object_by_class_name(call_stack, "java/lang/reflect/Field")?;
+        let name_object = vm.new_java_lang_string_object(call_stack, &name)?;
+        field_object.set_field_by_name("clazz", &Value::ObjectRef(class_object))?;
+        field_object.set_field_by_name("slot", &Value::Int(slot as i32))?;
+        field_object.set_field_by_name("name", &Value::ObjectRef(name_object))?;
+        field_object.set_field_by_name("type", &Value::ObjectRef(type_object))?;
+        field_object
+            .set_field_by_name("modifiers", &Value::Int(field.access_flags.bits() as i32))?;
+        Ok(Some(Value::ObjectRef(field_object)))
+    }
+
+    //直接实现的接口，按类文件中声明的顺序，每次调用返回新数组
+    pub fn java_lang_class_get_interfaces(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        if vm.is_primitive_class_object(class_object) {
+            let interfaces = vm.new_array_by_descriptor(call_stack, "[Ljava/lang/Class;", 0)?;
+            return Ok(Some(Value::ArrayRef(interfaces)));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        let interfaces = vm.new_array_by_descriptor(
+            call_stack,
+            "[Ljava/lang/Class;",
+            class_ref.interface_names.len(),
+        )?;
+        for (index, interface_name) in class_ref.interface_names.iter().enumerate() {
+            let interface = vm.resolve_class_object(call_stack, class_ref, interface_name)?;
+            interfaces.set_field_by_offset(index, &Value::ObjectRef(interface))?;
+        }
+        Ok(Some(Value::ArrayRef(interfaces)))
+    }
+
+    //接口、Object以及基本类型没有父类，数组的父类是Object
+    pub fn java_lang_class_get_superclass(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        if vm.is_primitive_class_object(class_object) {
+            return Ok(Some(Value::Null));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        match class_ref.super_class {
+            Some(super_class) if !class_ref.is_interface() => {
+                let super_object = vm.get_java_lang_class_object(call_stack, super_class)?;
+                Ok(Some(Value::ObjectRef(super_object)))
+            }
+            _ => Ok(Some(Value::Null)),
+        }
+    }
+
+    pub fn java_lang_class_is_interface(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        if vm.is_primitive_class_object(class_object) {
+            return Ok(Some(Value::Int(0)));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        Ok(Some(Value::Int(class_ref.is_interface() as i32)))
+    }
+
+    pub fn java_lang_class_is_array(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        if vm.is_primitive_class_object(class_object) {
+            return Ok(Some(Value::Int(0)));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        Ok(Some(Value::Int(class_ref.name.starts_with('[') as i32)))
+    }
+
+    //取值和java.lang.reflect.Modifier一致。嵌套类使用InnerClasses属性中的访问标记，这样才有private、
+    //protected和static；去掉和SYNCHRONIZED同值的ACC_SUPER。数组的可见性取决于元素类型，并且总是final和abstract，
+    //基本类型是public final abstract
+    pub fn java_lang_class_get_modifiers(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        if vm.is_primitive_class_object(class_object) {
+            let modifiers =
+                ClassAccessFlags::PUBLIC | ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT;
+            return Ok(Some(Value::Int(modifiers.bits() as i32)));
+        }
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        let modifiers = Self::class_modifiers(vm, call_stack, class_ref)?;
+        Ok(Some(Value::Int(modifiers as i32)))
+    }
+
+    fn class_modifiers(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<u16, MethodCallError<'a>> {
+        if class_ref.name.starts_with('[') {
+            let visibility = InnerClassAccessFlags::PUBLIC
+                | InnerClassAccessFlags::PRIVATE
+                | InnerClassAccessFlags::PROTECTED;
+            let visibility = match vm.array_element_class(call_stack, class_ref)? {
+                Some(element_class) => {
+                    Self::class_modifiers(vm, call_stack, element_class)? & visibility.bits()
+                }
+                None => ClassAccessFlags::PUBLIC.bits(),
+            };
+            return Ok(visibility | (ClassAccessFlags::FINAL | ClassAccessFlags::ABSTRACT).bits());
+        }
+        let flags = class_ref
+            .inner_classes
+            .iter()
+            .find(|inner| inner.inner_class_name == class_ref.name)
+            .map_or(class_ref.access_flags.bits(), |inner| {
+                inner.access_flags.bits()
+            });
+        Ok(flags & !(ClassAccessFlags::SUPER | ClassAccessFlags::MODULE).bits())
+    }
+
+    fn assertion_failed(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        message: &str,
+    ) -> InvokeMethodResult<'a> {
+        let error = vm.new_exception(call_stack, "java/lang/AssertionError", message)?;
+        Err(MethodCallError::ExceptionThrown(error))
+    }
+
+    //失败信息和JUnit一致，例如expected:<1> but was:<2>
+    fn assert_equals<T: Display + PartialEq>(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        expected: T,
+        actual: T,
+    ) -> InvokeMethodResult<'a> {
+        if expected == actual {
+            return Ok(None);
+        }
+        let message = format!("expected:<{}> but was:<{}>", expected, actual);
+        Self::assertion_failed(vm, call_stack, &message)
+    }
+
+    pub fn lite_jvm_assert_equals_int(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Self::assert_equals(vm, call_stack, args[0].get_int()?, args[1].get_int()?)
+    }
+
+    pub fn lite_jvm_assert_equals_long(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Self::assert_equals(vm, call_stack, args[0].get_long()?, args[1].get_long()?)
+    }
+
+    pub fn lite_jvm_assert_true(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if args[0].get_int()? != 0 {
+            return Ok(None);
+        }
+        Self::assertion_failed(vm, call_stack, "expected true")
+    }
+
+    pub fn lite_jvm_assert_false(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if args[0].get_int()? == 0 {
+            return Ok(None);
+        }
+        Self::assertion_failed(vm, call_stack, "expected false")
+    }
+
+    pub fn lite_jvm_assert_null(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if let Value::Null = args[0] {
+            return Ok(None);
+        }
+        let message = format!("expected null, but was:<{}>", vm.format_value(&args[0]));
+        Self::assertion_failed(vm, call_stack, &message)
+    }
+
+    pub fn lite_jvm_assert_not_null(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if let Value::Null = args[0] {
+            return Self::assertion_failed(vm, call_stack, "expected not null");
+        }
+        Ok(None)
+    }
+
+    pub fn lite_jvm_assert_fail(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let message = args[0].get_string().unwrap_or_default();
+        Self::assertion_failed(vm, call_stack, &message)
+    }
+
+    pub fn lite_jvm_assert_fail_not_equals(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Self::assert_equals(vm, call_stack, args[0].get_string()?, args[1].get_string()?)
+    }
+
+    pub fn java_lang_enum_value_of(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        //检查顺序和JDK一致：先是枚举类型，再是名称
+        let Value::ObjectRef(class_object) = args[0] else {
+            let exception =
+                vm.new_object_by_class_name(call_stack, "java/lang/NullPointerException")?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        };
+        let class_ref = vm.get_class_ref_of_class_object(call_stack, class_object)?;
+        let class_name = class_ref.name.replace('/', ".");
+        let is_enum = class_ref.access_flags.contains(ClassAccessFlags::ENUM)
+            && class_ref.super_class_name.as_deref() == Some("java/lang/Enum");
+        if !is_enum {
+            let exception = vm.new_exception(
+                call_stack,
+                "java/lang/IllegalArgumentException",
+                &format!("{} is not an enum type", class_name),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        if args[1] == Value::Null {
+            let exception =
+                vm.new_exception(call_stack, "java/lang/NullPointerException", "Name is null")?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        let name = args[1].get_string()?;
+        vm.link_and_initialize(call_stack, class_ref)?;
+        let values_method = class_ref.get_method("values", &format!("()[L{};", class_ref.name))?;
+        let constants = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                values_method,
+                None::<ObjectReference>,
+                vec![],
+            )?
+            .unwrap_or(Value::Null)
+            .get_array()?;
+        for constant in constants.read_all() {
+            if constant
+                .get_object()?
+                .get_field_by_name("name")?
+                .get_string()?
+                == name
+            {
+                return Ok(Some(constant));
             }
+        }
+        //和getCanonicalName一致，成员类使用外部类名.类名
+        let canonical_name = match class_ref.declaring_class_name() {
+            Some(outer) => format!(
+                "{}.{}",
+                outer.replace('/', "."),
+                &class_ref.name[outer.len() + 1..]
+            ),
+            None => class_name,
         };
+        let exception = vm.new_exception(
+            call_stack,
+            "java/lang/IllegalArgumentException",
+            &format!("No enum constant {}.{}", canonical_name, name),
+        )?;
+        Err(MethodCallError::ExceptionThrown(exception))
+    }
+
+    pub fn java_lang_class_hash_code(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if let Some(obj) = _receiver {
+            match obj {
+                Value::ObjectRef(obj) => Ok(Some(Value::Int(obj.hash_code()))),
+                Value::ArrayRef(obj) => Ok(Some(Value::Int(obj.hash_code()))),
+                _ => Ok(Some(Value::Int(-1))),
+            }
+        } else {
+            Ok(Some(Value::Int(-1)))
+        }
+    }
+    pub fn java_lang_class_desired_assertion_status0(
+        vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Ok(Some(Value::Int(vm.is_assertions_enabled() as i32)))
+    }
+    pub fn java_lang_system_arraycopy(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        assert_eq!(args.len(), 5);
+        let from_array = args[0].get_array()?;
+        let from_start = args[1].get_int()?;
+
+        let to_array = args[2].get_array()?;
+        let to_start = args[3].get_int()?;
+        let length = args[4].get_int()?;
+        for offset in 0..length {
+            let value = from_array.get_field_by_offset((offset + from_start) as usize)?;
+            to_array.set_field_by_offset((offset + to_start) as usize, &value)?;
+        }
+        Ok(None)
+    }
+    pub fn java_lang_float_float_to_raw_int_bits(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let value = args[0].get_float()?;
+        Ok(Some(Value::Int(value.to_bits() as i32)))
+    }
+    pub fn java_lang_double_double_to_raw_long_bits(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let value = args[0].get_double()?;
+        Ok(Some(Value::Long(value.to_bits() as i64)))
+    }
+    pub fn java_lang_float_float_to_int_bits(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let value = args[0].get_float()?;
+        let bits = if value.is_nan() {
+            0x7fc00000
+        } else {
+            value.to_bits()
+        };
+        Ok(Some(Value::Int(bits as i32)))
+    }
+    pub fn java_lang_double_double_to_long_bits(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let value = args[0].get_double()?;
+        let bits = if value.is_nan() {
+            0x7ff8000000000000
+        } else {
+            value.to_bits()
+        };
+        Ok(Some(Value::Long(bits as i64)))
+    }
+    pub fn java_lang_float_int_bits_to_float(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let bits = args[0].get_int()?;
+        Ok(Some(Value::Float(f32::from_bits(bits as u32))))
+    }
+    pub fn java_lang_double_long_bits_to_double(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let bits = args[0].get_long()?;
+        Ok(Some(Value::Double(f64::from_bits(bits as u64))))
+    }
+    generate_to_string_native!(java_lang_integer_to_string, get_int, |i: i32| i.to_string());
+    generate_to_string_native!(java_lang_long_to_string, get_long, |l: i64| l.to_string());
+    generate_to_string_native!(
+        java_lang_double_to_string,
+        get_double,
+        java_double_to_string
+    );
+    generate_to_string_native!(java_lang_float_to_string, get_float, java_float_to_string);
+    fn number_format_exception(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        message: &str,
+    ) -> InvokeMethodResult<'a> {
+        let exception = vm.new_exception(call_stack, "java/lang/NumberFormatException", message)?;
+        Err(MethodCallError::ExceptionThrown(exception))
+    }
+
+    //Java会去掉首尾的空白(<= \u0020)，允许d、f后缀，无穷大和NaN只接受Infinity和NaN两种写法。
+    //Rust还接受inf、nan等写法，这里先排除掉。十六进制浮点数暂不支持
+    pub fn java_lang_double_parse_double(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if let Value::Null = args[0] {
+            let exception =
+                vm.new_object_by_class_name(call_stack, "java/lang/NullPointerException")?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        let text = args[0].get_string()?;
+        let trimmed = text.trim_matches(|c: char| c <= '\u{20}');
+        if trimmed.is_empty() {
+            return Self::number_format_exception(vm, call_stack, "empty String");
+        }
+        let (number, has_suffix) = match trimmed.strip_suffix(['d', 'D', 'f', 'F']) {
+            Some(number) => (number, true),
+            None => (trimmed, false),
+        };
+        let unsigned = number.strip_prefix(['+', '-']).unwrap_or(number);
+        let value = match unsigned {
+            "Infinity" | "NaN" if !has_suffix => {
+                f64::from_str(&number.replace("Infinity", "inf")).ok()
+            }
+            _ if unsigned
+                .chars()
+                .all(|c| c.is_ascii_digit() || "eE.+-".contains(c)) =>
+            {
+                f64::from_str(number).ok()
+            }
+            _ => None,
+        };
+        match value {
+            Some(value) => Ok(Some(Value::Double(value))),
+            None => Self::number_format_exception(
+                vm,
+                call_stack,
+                &format!("For input string: \"{}\"", text),
+            ),
+        }
+    }
+
+    //VmExit不是Java异常，沿途的finally块和catch都不会执行，和JVM中halt的行为一致
+    pub fn java_lang_shutdown_halt0(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        Err(MethodCallError::VmExit(args[0].get_int()?))
+    }
+
+    //基本类型的Class对象不对应任何加载的类，名称不是基本类型时返回null
+    pub fn java_lang_class_get_primitive_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let name = args[0].get_string()?;
+        match vm.get_primitive_class_object(call_stack, &name)? {
+            Some(object_ref) => Ok(Some(Value::ObjectRef(object_ref))),
+            None => Ok(Some(Value::Null)),
+        }
+    }
+
+    pub fn java_lang_class_is_primitive(
+        vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_object = receiver.unwrap().get_object()?;
+        Ok(Some(Value::Int(
+            vm.is_primitive_class_object(class_object) as i32
+        )))
+    }
+    generate_strict_math_native!(java_lang_strict_math_sin, |a| a.sin());
+    generate_strict_math_native!(java_lang_strict_math_cos, |a| a.cos());
+    generate_strict_math_native!(java_lang_strict_math_tan, |a| a.tan());
+    generate_strict_math_native!(java_lang_strict_math_asin, |a| a.asin());
+    generate_strict_math_native!(java_lang_strict_math_acos, |a| a.acos());
+    generate_strict_math_native!(java_lang_strict_math_atan, |a| a.atan());
+    generate_strict_math_native!(java_lang_strict_math_exp, |a| a.exp());
+    generate_strict_math_native!(java_lang_strict_math_log, |a| a.ln());
+    generate_strict_math_native!(java_lang_strict_math_log10, |a| a.log10());
+    generate_strict_math_native!(java_lang_strict_math_sqrt, |a| a.sqrt());
+    generate_strict_math_native!(java_lang_strict_math_cbrt, |a| a.cbrt());
+    generate_strict_math_native!(java_lang_strict_math_sinh, |a| a.sinh());
+    generate_strict_math_native!(java_lang_strict_math_cosh, |a| a.cosh());
+    generate_strict_math_native!(java_lang_strict_math_tanh, |a| a.tanh());
+    generate_strict_math_native!(java_lang_strict_math_expm1, |a| a.exp_m1());
+    generate_strict_math_native!(java_lang_strict_math_log1p, |a| a.ln_1p());
+    generate_strict_math_native!(java_lang_strict_math_atan2, |a, b| a.atan2(b));
+    generate_strict_math_native!(java_lang_strict_math_pow, |a, b| java_pow(a, b));
+    generate_strict_math_native!(java_lang_strict_math_hypot, |a, b| a.hypot(b));
+    generate_strict_math_native!(java_lang_strict_math_ieee_remainder, |a, b| {
+        ieee_remainder(a, b)
+    });
+
+    //构造异常时记录完整的调用栈，跳过fillInStackTrace和异常自身的构造函数。
+    //Throwable随后会把stackTrace置为UNASSIGNED_STACK，因此记录在backtrace中，
+    //由getStackTraceDepth/getStackTraceElement按需读取
+    pub fn java_lang_throwable_fill_in_stack_trace(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if let Some(Value::ObjectRef(exception)) = receiver {
+            let stack_trace: Vec<StackTraceElement> = call_stack
+                .frames()
+                .rev()
+                .skip_while(|frame| {
+                    frame.method_ref.name == "fillInStackTrace"
+                        || (frame.method_ref.name == "<init>"
+                            && frame.class_ref.is_subclass_of("java/lang/Throwable"))
+                })
+                .map(|frame| frame.to_stack_trace())
+                .collect();
+            let backtrace = vm.new_exception_stack_trace_element(call_stack, &stack_trace)?;
+            exception.set_field_by_name("backtrace", &Value::ArrayRef(backtrace))?;
+        }
+        Ok(receiver)
+    }
+
+    pub fn java_lang_throwable_get_stack_trace_depth(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let exception = receiver.unwrap().get_object()?;
+        let depth = match exception.get_field_by_name("backtrace")? {
+            Value::ArrayRef(backtrace) => backtrace.get_data_length() as i32,
+            _ => 0,
+        };
+        Ok(Some(Value::Int(depth)))
+    }
+
+    pub fn java_lang_throwable_get_stack_trace_element(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let exception = receiver.unwrap().get_object()?;
+        let index = args[0].get_int()?;
+        match exception.get_field_by_name("backtrace")? {
+            Value::ArrayRef(backtrace) => Ok(Some(backtrace.get_field_by_offset(index as usize)?)),
+            _ => Ok(Some(Value::Null)),
+        }
+    }
+
+    pub fn java_lang_class_for_name0(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_name = args[0].get_string()?.replace('.', "/");
+        let initialize = args[1].get_int()? != 0;
+        let class_loader = args[2].get_object().ok();
+        let class_ref = vm.load_class_by_loader(call_stack, class_loader, &class_name)?;
+        if initialize {
+            vm.link_and_initialize(call_stack, class_ref)?;
+        }
+        let class_object = vm.get_java_lang_class_object(call_stack, class_ref)?;
+        Ok(Some(Value::ObjectRef(class_object)))
+    }
+
+    pub fn java_lang_class_loader_init(
+        _vm: &mut VirtualMachine<'a>,
+        _call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_loader = receiver.unwrap().get_object()?;
+        class_loader.set_field_by_name("parent", &args[1])?;
+        Ok(None)
+    }
+
+    //先从已加载的类中查找，然后委派给parent(为null时委派给引导类加载器)，最后调用findClass
+    pub fn java_lang_class_loader_load_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_loader = receiver.unwrap().get_object()?;
+        let class_name = args[0].get_string()?.replace('.', "/");
+        if let Some(class_ref) = vm.find_loaded_class(class_loader, &class_name) {
+            let class_object = vm.get_java_lang_class_object(call_stack, class_ref)?;
+            return Ok(Some(Value::ObjectRef(class_object)));
+        }
+        let parent = class_loader.get_field_by_name("parent")?.get_object().ok();
+        match vm.load_class_by_loader(call_stack, parent, &class_name) {
+            Ok(class_ref) => {
+                let class_object = vm.get_java_lang_class_object(call_stack, class_ref)?;
+                return Ok(Some(Value::ObjectRef(class_object)));
+            }
+            Err(MethodCallError::InternalError(VmError::ClassNotFoundException(_))) => {}
+            Err(MethodCallError::ExceptionThrown(e))
+                if e.get_class()
+                    .is_subclass_of("java/lang/ClassNotFoundException") => {}
+            Err(e) => return Err(e),
+        }
+        let (class_ref, method_ref) = class_loader
+            .get_class()
+            .get_method_by_checking_super("findClass", "(Ljava/lang/String;)Ljava/lang/Class;")?;
         vm.invoke_method(
             call_stack,
             class_ref,
             method_ref,
-            None::<ObjectReference>,
-            Vec::new(),
+            Some(class_loader),
+            vec![args[0].clone()],
         )
     }
 
+    pub fn java_lang_class_loader_find_loaded_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_loader = receiver.unwrap().get_object()?;
+        let class_name = args[0].get_string()?.replace('.', "/");
+        match vm.find_loaded_class(class_loader, &class_name) {
+            Some(class_ref) => {
+                let class_object = vm.get_java_lang_class_object(call_stack, class_ref)?;
+                Ok(Some(Value::ObjectRef(class_object)))
+            }
+            None => Ok(Some(Value::Null)),
+        }
+    }
+
+    pub fn java_lang_class_loader_define_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_loader = receiver.unwrap().get_object()?;
+        let array = args[1].get_array()?;
+        let offset = args[2].get_int()?;
+        let length = args[3].get_int()?;
+        let array_length = array.get_data_length() as i64;
+        if offset < 0 || length < 0 || offset as i64 + length as i64 > array_length {
+            let exception = vm.new_exception(
+                call_stack,
+                "java/lang/IndexOutOfBoundsException",
+                &format!(
+                    "offset {}, length {}, array length {}",
+                    offset, length, array_length
+                ),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        let bytes = array
+            .read_all()
+            .iter()
+            .skip(offset as usize)
+            .take(length as usize)
+            .map(|v| v.get_int().map(|byte| byte as u8))
+            .collect::<VmExecResult<Vec<u8>>>()?;
+        //name为null时使用字节码中的类名
+        let name = match &args[0] {
+            Value::Null => None,
+            name => Some(name.get_string()?),
+        };
+        let class_ref = vm.define_class(call_stack, class_loader, name.as_deref(), &bytes)?;
+        let class_object = vm.get_java_lang_class_object(call_stack, class_ref)?;
+        Ok(Some(Value::ObjectRef(class_object)))
+    }
+
+    //initializeSystemClass依赖的Properties、FileDescriptor和本地库加载都没有实现，
+    //这里只设置行分隔符，System.out/err由虚拟机在System初始化完成后安装
+    pub fn java_lang_system_initialize_system_class(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        _receiver: Option<Value<'a>>,
+        _args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let class_ref = vm.get_class_by_name(call_stack, "java/lang/System")?;
+        if class_ref.get_field_by_name("lineSeparator").is_ok() {
+            let separator = vm.new_java_lang_string_object(call_stack, "\n")?;
+            vm.set_static(class_ref, "lineSeparator", Value::ObjectRef(separator));
+        }
+        Ok(None)
+    }
+
     pub fn registry_native_method(
         &mut self,
         class_name: &str,
@@ -198,6 +1879,31 @@ impl<'a> NativeMethodArea<'a> {
         let key = format!("{}:{}{}", class_name, method_name, method_descriptor);
         self.native_methods.insert(key, method);
     }
+    /// 替换非native方法的实现，调用该方法时不再执行字节码
+    pub fn registry_override_method(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        method_descriptor: &str,
+        method: NativeMethod<'a>,
+    ) {
+        self.overridden_classes.insert(class_name.to_string());
+        self.registry_native_method(class_name, method_name, method_descriptor, method);
+    }
+
+    pub fn get_override_method(
+        &mut self,
+        class_name: &str,
+        method_name: &str,
+        method_descriptor: &str,
+    ) -> Option<NativeMethod<'a>> {
+        if !self.overridden_classes.contains(class_name) {
+            return None;
+        }
+        self.get_method(class_name, method_name, method_descriptor)
+            .copied()
+    }
+
     pub fn get_method(
         &mut self,
         class_name: &str,
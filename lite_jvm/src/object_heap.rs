@@ -1,26 +1,45 @@
+use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::{
-    size_of_array, size_of_object, ArrayElement, ArrayReference, ObjectReference,
+    read_reference_at, size_of_array, size_of_object, ArrayElement, ArrayReference,
+    ObjectReference, Value,
 };
 use crate::loaded_class::ClassRef;
 use crate::memory_trunk::MemoryChunk;
 use std::marker::PhantomData;
 
+//小对象共用的trunk大小，超过LARGE_ALLOCATION_SIZE的分配使用单独的trunk
+const SMALL_TRUNK_SIZE: usize = 16 * 1024;
+const LARGE_ALLOCATION_SIZE: usize = SMALL_TRUNK_SIZE / 4;
+//堆满之后创建OutOfMemoryError还要初始化异常类、创建消息字符串，这部分分配使用max_size之外的预留空间
+const OUT_OF_MEMORY_RESERVE: usize = SMALL_TRUNK_SIZE;
+
+/// 由多个trunk组成的堆，trunk按需创建，总大小不超过max_size。
+/// 小对象在当前trunk中顺序分配，大数组使用单独的trunk，不会因为当前trunk剩余空间不足而失败
 pub struct ObjectHeap<'a> {
-    memory: MemoryChunk,
+    max_size: usize,
+    trunks: Vec<MemoryChunk>,
+    //小对象正在使用的trunk
+    current: Option<usize>,
+    //预留空间，只在reserve_open时使用，第一次用到时创建
+    reserve: Option<MemoryChunk>,
+    reserve_open: bool,
     _marker: PhantomData<&'a ObjectReference<'a>>,
 }
 impl<'a> ObjectHeap<'a> {
-    pub(crate) fn new(size: usize) -> ObjectHeap<'a> {
+    pub(crate) fn new(max_size: usize) -> ObjectHeap<'a> {
         ObjectHeap {
-            memory: MemoryChunk::new(size),
+            max_size,
+            trunks: Vec::new(),
+            current: None,
+            reserve: None,
+            reserve_open: false,
             _marker: Default::default(),
         }
     }
 
-    pub fn allocate_object(&mut self, class: ClassRef) -> Option<ObjectReference<'a>> {
+    pub fn allocate_object(&mut self, class: ClassRef) -> VmExecResult<ObjectReference<'a>> {
         let size = size_of_object(class);
-        self.memory
-            .alloc(size)
+        self.alloc(size)
             .map(|(ptr, size)| ObjectReference::new_object(class, ptr, size))
     }
 
@@ -28,12 +47,129 @@ impl<'a> ObjectHeap<'a> {
         &mut self,
         array_element: ArrayElement,
         length: usize,
-    ) -> Option<ArrayReference<'a>> {
+    ) -> VmExecResult<ArrayReference<'a>> {
         let size = size_of_array(length);
-        self.memory
-            .alloc(size)
+        self.alloc(size)
             .map(|(ptr, size)| ArrayReference::new_array(array_element, length, ptr, size))
     }
+
+    //先使用已有trunk的剩余空间，都放不下时在max_size的范围内创建新的trunk
+    fn alloc(&mut self, size: usize) -> VmExecResult<(*mut u8, usize)> {
+        let large = size > LARGE_ALLOCATION_SIZE;
+        if !large {
+            if let Some(allocated) = self
+                .current
+                .and_then(|index| self.trunks[index].alloc(size))
+            {
+                return Ok(allocated);
+            }
+        }
+        let found = self
+            .trunks
+            .iter_mut()
+            .enumerate()
+            .find_map(|(index, trunk)| trunk.alloc(size).map(|allocated| (index, allocated)));
+        if let Some((index, allocated)) = found {
+            if !large {
+                self.current = Some(index);
+            }
+            return Ok(allocated);
+        }
+        //分配大小都是8的倍数，trunk的大小也按8对齐
+        let unreserved = (self.max_size - self.reserved_bytes()) & !7;
+        let capacity = if large {
+            size
+        } else {
+            SMALL_TRUNK_SIZE.min(unreserved)
+        };
+        if capacity < size || capacity > unreserved {
+            return self.alloc_reserve(size);
+        }
+        let mut trunk = MemoryChunk::new(capacity);
+        let allocated = trunk
+            .alloc(size)
+            .expect("new trunk should fit the allocation");
+        self.trunks.push(trunk);
+        if !large {
+            self.current = Some(self.trunks.len() - 1);
+        }
+        Ok(allocated)
+    }
+
+    fn alloc_reserve(&mut self, size: usize) -> VmExecResult<(*mut u8, usize)> {
+        if self.reserve_open {
+            let reserve = self
+                .reserve
+                .get_or_insert_with(|| MemoryChunk::new(OUT_OF_MEMORY_RESERVE));
+            if let Some(allocated) = reserve.alloc(size) {
+                return Ok(allocated);
+            }
+        }
+        Err(VmError::OutOfMemory(size, self.free_bytes()))
+    }
+
+    /// 打开后max_size之内放不下的分配使用预留空间，用于在堆满时创建OutOfMemoryError
+    pub(crate) fn set_reserve_open(&mut self, open: bool) {
+        self.reserve_open = open;
+    }
+
+    pub(crate) fn is_reserve_open(&self) -> bool {
+        self.reserve_open
+    }
+
+    //已经创建的trunk的总大小
+    fn reserved_bytes(&self) -> usize {
+        self.trunks.iter().map(MemoryChunk::capacity).sum()
+    }
+
+    /// 已经分配给对象和数组的字节数
+    pub fn used_bytes(&self) -> usize {
+        self.chunks().map(MemoryChunk::used).sum()
+    }
+
+    //trunk和已经创建的预留空间
+    fn chunks(&self) -> impl Iterator<Item = &MemoryChunk> + '_ {
+        self.trunks.iter().chain(self.reserve.iter())
+    }
+
+    /// 还没有分配出去的字节数，包括尚未创建的trunk，不包括预留空间。
+    /// 各trunk末尾的剩余空间不连续，单次分配能得到的最大空间可能小于该值
+    pub fn free_bytes(&self) -> usize {
+        self.max_size - self.trunks.iter().map(MemoryChunk::used).sum::<usize>()
+    }
+
+    //丢弃所有已分配的对象，之前的引用都不再有效。trunk保留下来供之后的分配复用
+    pub(crate) fn reset(&mut self) {
+        for trunk in self.trunks.iter_mut().chain(self.reserve.iter_mut()) {
+            trunk.reset();
+        }
+        self.current = None;
+    }
+
+    /// 遍历堆上分配的所有对象和数组，通过每个分配头中记录的大小找到下一个分配。
+    /// 按trunk的创建顺序遍历，不同trunk之间不保证分配的先后顺序
+    pub fn iter_references(&self) -> impl Iterator<Item = Value<'a>> + '_ {
+        self.chunks().flat_map(|trunk| {
+            let (start, used) = trunk.allocated();
+            let mut offset = 0;
+            std::iter::from_fn(move || {
+                if offset >= used {
+                    return None;
+                }
+                let (value, size) = unsafe { read_reference_at(start.add(offset)) };
+                //大小为0说明内存被破坏，停止遍历避免死循环
+                offset = if size == 0 { used } else { offset + size };
+                Some(value)
+            })
+        })
+    }
+
+    pub fn iter_objects(&self) -> impl Iterator<Item = ObjectReference<'a>> + '_ {
+        self.iter_references().filter_map(|value| match value {
+            Value::ObjectRef(object_ref) => Some(object_ref),
+            _ => None,
+        })
+    }
 }
 
 mod tests {
@@ -65,4 +201,97 @@ mod tests {
         let value = allocated_obj.get_field_by_name("a").unwrap();
         assert!(matches!(value, Value::Int(2)));
     }
+
+    #[test]
+    fn test_iter_objects() {
+        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
+        use crate::jvm_values::{ArrayElement, PrimaryType, Value};
+        use crate::method_area::MethodArea;
+        use crate::object_heap::ObjectHeap;
+        let area = MethodArea::default();
+
+        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
+        area.add_class_path(Box::new(file_system_path));
+        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
+
+        area.add_class_path(Box::new(rt_jar_path));
+        let result = area.load_class("FieldTest").unwrap();
+
+        let mut heap = ObjectHeap::new(4096);
+        assert_eq!(heap.iter_references().count(), 0);
+        let first = heap.allocate_object(result).unwrap();
+        let array = heap
+            .allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), 3)
+            .unwrap();
+        let second = heap.allocate_object(result).unwrap();
+
+        let references: Vec<Value> = heap.iter_references().collect();
+        assert_eq!(
+            references,
+            vec![
+                Value::ObjectRef(first),
+                Value::ArrayRef(array),
+                Value::ObjectRef(second)
+            ]
+        );
+        let objects: Vec<_> = heap.iter_objects().collect();
+        assert_eq!(objects, vec![first, second]);
+    }
+
+    #[test]
+    fn test_allocate_across_trunks() {
+        use super::SMALL_TRUNK_SIZE;
+        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::ReferenceValue;
+        use crate::jvm_values::{size_of_array, size_of_object, ArrayElement, PrimaryType};
+        use crate::method_area::MethodArea;
+        use crate::object_heap::ObjectHeap;
+        let area = MethodArea::default();
+        area.add_class_path(Box::new(FileSystemClassPath::new("./resources").unwrap()));
+        area.add_class_path(Box::new(
+            JarFileClassPath::new("./resources/rt.jar").unwrap(),
+        ));
+        let class_ref = area.load_class("FieldTest").unwrap();
+        let object_size = size_of_object(class_ref);
+
+        let max_size = 256 * 1024;
+        let mut heap = ObjectHeap::new(max_size);
+        for _ in 0..100 {
+            heap.allocate_object(class_ref).unwrap();
+        }
+        assert_eq!(heap.used_bytes(), 100 * object_size);
+        assert_eq!(heap.free_bytes(), max_size - 100 * object_size);
+
+        //小对象只占用一个trunk，剩下的空间可以整块分配给大数组
+        let length = (max_size - SMALL_TRUNK_SIZE - size_of_array(0)) / 8;
+        let array = heap
+            .allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), length)
+            .unwrap();
+        assert_eq!(array.get_data_length(), length);
+        //小对象继续使用原来trunk的剩余空间
+        heap.allocate_object(class_ref).unwrap();
+        let free_bytes = SMALL_TRUNK_SIZE - 101 * object_size;
+        assert_eq!(heap.free_bytes(), free_bytes);
+        assert_eq!(heap.iter_references().count(), 102);
+
+        //剩余空间不够时返回OutOfMemory
+        let length = free_bytes / 8;
+        let result = heap.allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), length);
+        assert_eq!(
+            result.unwrap_err(),
+            VmError::OutOfMemory(size_of_array(length), free_bytes)
+        );
+
+        //打开预留空间后可以在max_size之外分配，关闭后仍然返回OutOfMemory
+        heap.set_reserve_open(true);
+        heap.allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), length)
+            .unwrap();
+        heap.set_reserve_open(false);
+        assert_eq!(heap.free_bytes(), free_bytes);
+        assert_eq!(heap.iter_references().count(), 103);
+        assert!(heap
+            .allocate_array(ArrayElement::PrimaryValue(PrimaryType::Int), length)
+            .is_err());
+    }
 }
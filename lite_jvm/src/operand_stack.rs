@@ -1,17 +1,43 @@
 use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::Value;
-use log::trace;
+use crate::runtime_method_info::MethodDescriptor;
+use tracing::trace;
 
+/// 栈中每个值只占一个元素，long和double也是一个Value，所以按值的个数出栈。
+/// 而Code属性中的max_stack、max_locals和invokeinterface的count都以字为单位，long和double占两个字，
+/// 因此容量按字计算，字数见Value::category和MethodDescriptor::arg_slots
 #[derive(Debug)]
 pub struct OperandStack<'a> {
     stack: Vec<Value<'a>>,
+    //栈中所有值占用的字数
+    words: usize,
+    //方法Code属性中的max_stack，单位是字
+    max_size: usize,
+    check_overflow: bool,
 }
 impl<'a> OperandStack<'a> {
     pub(crate) fn new(max_size: usize) -> OperandStack<'a> {
         OperandStack {
             stack: Vec::with_capacity(max_size),
+            words: 0,
+            max_size,
+            check_overflow: true,
         }
     }
+
+    //关闭检查后超过max_stack也继续压栈
+    pub(crate) fn set_overflow_check(&mut self, enabled: bool) {
+        self.check_overflow = enabled;
+    }
+
+    pub(crate) fn max_size(&self) -> usize {
+        self.max_size
+    }
+
+    pub(crate) fn words(&self) -> usize {
+        self.words
+    }
+
     pub(crate) fn pop_n(&mut self, n: usize) -> VmExecResult<Vec<Value<'a>>> {
         let mut vec = Vec::with_capacity(n);
         (0..n).for_each(|_| vec.push(Value::Null));
@@ -20,16 +46,48 @@ impl<'a> OperandStack<'a> {
         }
         Ok(vec)
     }
+    //按描述符弹出调用参数，参数个数是值的个数，占用的字数也必须和描述符一致
+    pub(crate) fn pop_args(
+        &mut self,
+        descriptor: &MethodDescriptor,
+    ) -> VmExecResult<Vec<Value<'a>>> {
+        let args = self.pop_n(descriptor.args.len())?;
+        let words: usize = args.iter().map(|v| v.category() as usize).sum();
+        if words != descriptor.arg_slots() {
+            return Err(VmError::ValueTypeMissMatch);
+        }
+        Ok(args)
+    }
+
     pub(crate) fn pop(&mut self) -> VmExecResult<Value<'a>> {
         let result = self.stack.pop().ok_or(VmError::PopFromEmptyStack);
-        trace!("--- value stack --- {:?}", self.stack);
+        if let Ok(value) = &result {
+            self.words -= value.category() as usize;
+        }
+        trace!(stack = ?self.stack, "operand stack");
         result
     }
 
+    //异常传播出栈帧时丢弃剩余的操作数
+    pub(crate) fn clear(&mut self) {
+        self.stack.clear();
+        self.words = 0;
+    }
+
+    pub(crate) fn values(&self) -> &[Value<'a>] {
+        &self.stack
+    }
+
+    pub(crate) fn peek(&self) -> Option<&Value<'a>> {
+        self.stack.last()
+    }
+
     pub(crate) fn push(&mut self, value: Value<'a>) -> VmExecResult<()> {
-        if self.stack.len() < self.stack.capacity() {
+        let words = self.words + value.category() as usize;
+        if !self.check_overflow || words <= self.max_size {
+            self.words = words;
             self.stack.push(value);
-            trace!("--- value stack --- {:?}", self.stack);
+            trace!(stack = ?self.stack, "operand stack");
             Ok(())
         } else {
             Err(VmError::StackOverFlow)
@@ -51,19 +109,31 @@ impl<'a> OperandStack<'a> {
         self.push(value1)
     }
 
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup_x2
     pub fn dup_x2(&mut self) -> VmExecResult<()> {
-        let value1 = self.pop()?;
+        let value1 = self.pop_category_one()?;
         let value2 = self.pop()?;
-        let value3 = self.pop()?;
+        //形式2：value2是第二类
+        if value2.is_category_two() {
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+        let value3 = self.pop_category_one()?;
         self.push(value1.clone())?;
         self.push(value3)?;
         self.push(value2)?;
         self.push(value1)
     }
 
+    //栈中的long和double只占一个元素，dup2对第二类的值只复制它本身
     pub fn dup2(&mut self) -> VmExecResult<()> {
         let value1 = self.pop()?;
-        let value2 = self.pop()?;
+        if value1.is_category_two() {
+            self.push(value1.clone())?;
+            return self.push(value1);
+        }
+        let value2 = self.pop_category_one()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
         self.push(value2)?;
@@ -72,8 +142,14 @@ impl<'a> OperandStack<'a> {
 
     pub fn dup2_x1(&mut self) -> VmExecResult<()> {
         let value1 = self.pop()?;
-        let value2 = self.pop()?;
-        let value3 = self.pop()?;
+        if value1.is_category_two() {
+            let value2 = self.pop_category_one()?;
+            self.push(value1.clone())?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+        let value2 = self.pop_category_one()?;
+        let value3 = self.pop_category_one()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
         self.push(value3)?;
@@ -81,11 +157,36 @@ impl<'a> OperandStack<'a> {
         self.push(value1)
     }
 
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup2_x2
     pub fn dup2_x2(&mut self) -> VmExecResult<()> {
         let value1 = self.pop()?;
-        let value2 = self.pop()?;
+        if value1.is_category_two() {
+            let value2 = self.pop()?;
+            //形式4：value1和value2都是第二类
+            if value2.is_category_two() {
+                self.push(value1.clone())?;
+                self.push(value2)?;
+                return self.push(value1);
+            }
+            //形式2：value1是第二类，value2和value3是第一类
+            let value3 = self.pop_category_one()?;
+            self.push(value1.clone())?;
+            self.push(value3)?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+        let value2 = self.pop_category_one()?;
         let value3 = self.pop()?;
-        let value4 = self.pop()?;
+        //形式3：value1和value2是第一类，value3是第二类
+        if value3.is_category_two() {
+            self.push(value2.clone())?;
+            self.push(value1.clone())?;
+            self.push(value3)?;
+            self.push(value2)?;
+            return self.push(value1);
+        }
+        //形式1：四个值都是第一类
+        let value4 = self.pop_category_one()?;
         self.push(value2.clone())?;
         self.push(value1.clone())?;
         self.push(value4)?;
@@ -94,6 +195,15 @@ impl<'a> OperandStack<'a> {
         self.push(value1)
     }
 
+    fn pop_category_one(&mut self) -> VmExecResult<Value<'a>> {
+        let value = self.pop()?;
+        if value.is_category_two() {
+            Err(VmError::ValueTypeMissMatch)
+        } else {
+            Ok(value)
+        }
+    }
+
     pub fn swap(&mut self) -> VmExecResult<()> {
         let value1 = self.pop()?;
         let value2 = self.pop()?;
@@ -101,3 +211,193 @@ impl<'a> OperandStack<'a> {
         self.push(value2)
     }
 }
+
+mod tests {
+
+    #[test]
+    fn test_dup2_category_two() {
+        use crate::jvm_values::Value;
+        use crate::operand_stack::OperandStack;
+
+        let mut stack = OperandStack::new(8);
+        stack.push(Value::Int(1)).unwrap();
+        stack.push(Value::Long(2)).unwrap();
+        stack.dup2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[Value::Int(1), Value::Long(2), Value::Long(2)]
+        );
+
+        let mut stack = OperandStack::new(8);
+        stack.push(Value::Int(1)).unwrap();
+        stack.push(Value::Float(2.0)).unwrap();
+        stack.dup2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[
+                Value::Int(1),
+                Value::Float(2.0),
+                Value::Int(1),
+                Value::Float(2.0)
+            ]
+        );
+
+        //value1是第二类，value2是第一类
+        let mut stack = OperandStack::new(8);
+        stack.push(Value::Int(1)).unwrap();
+        stack.push(Value::Double(2.0)).unwrap();
+        stack.dup2_x1().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[Value::Double(2.0), Value::Int(1), Value::Double(2.0)]
+        );
+
+        let mut stack = OperandStack::new(8);
+        stack.push(Value::Int(3)).unwrap();
+        stack.push(Value::Int(2)).unwrap();
+        stack.push(Value::Int(1)).unwrap();
+        stack.dup2_x1().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[
+                Value::Int(2),
+                Value::Int(1),
+                Value::Int(3),
+                Value::Int(2),
+                Value::Int(1)
+            ]
+        );
+
+        //第二类的值不能和第一类的值拆开复制
+        let mut stack = OperandStack::new(8);
+        stack.push(Value::Long(2)).unwrap();
+        stack.push(Value::Int(1)).unwrap();
+        assert!(stack.dup2().is_err());
+    }
+
+    #[test]
+    fn test_dup_x2_forms() {
+        use crate::jvm_values::Value;
+        use crate::operand_stack::OperandStack;
+
+        let stack_of = |values: &[Value<'static>]| {
+            let mut stack = OperandStack::new(8);
+            for value in values {
+                stack.push(value.clone()).unwrap();
+            }
+            stack
+        };
+        let (i1, i2, i3, i4) = (Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4));
+        let (l, d) = (Value::Long(5), Value::Double(6.0));
+
+        let mut stack = stack_of(&[i3.clone(), i2.clone(), i1.clone()]);
+        stack.dup_x2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[i1.clone(), i3.clone(), i2.clone(), i1.clone()]
+        );
+        let mut stack = stack_of(&[l.clone(), i1.clone()]);
+        stack.dup_x2().unwrap();
+        assert_eq!(stack.values(), &[i1.clone(), l.clone(), i1.clone()]);
+        //value1不能是第二类，value2是第一类时value3也必须是第一类
+        assert!(stack_of(&[i2.clone(), l.clone()]).dup_x2().is_err());
+        assert!(stack_of(&[l.clone(), i2.clone(), i1.clone()])
+            .dup_x2()
+            .is_err());
+
+        //形式1
+        let mut stack = stack_of(&[i4.clone(), i3.clone(), i2.clone(), i1.clone()]);
+        stack.dup2_x2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[
+                i2.clone(),
+                i1.clone(),
+                i4.clone(),
+                i3.clone(),
+                i2.clone(),
+                i1.clone()
+            ]
+        );
+        //形式2：(int, int, long)
+        let mut stack = stack_of(&[i3.clone(), i2.clone(), l.clone()]);
+        stack.dup2_x2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[l.clone(), i3.clone(), i2.clone(), l.clone()]
+        );
+        //形式3
+        let mut stack = stack_of(&[d.clone(), i2.clone(), i1.clone()]);
+        stack.dup2_x2().unwrap();
+        assert_eq!(
+            stack.values(),
+            &[i2.clone(), i1.clone(), d.clone(), i2.clone(), i1.clone()]
+        );
+        //形式4：(double, long)
+        let mut stack = stack_of(&[d.clone(), l.clone()]);
+        stack.dup2_x2().unwrap();
+        assert_eq!(stack.values(), &[l.clone(), d.clone(), l.clone()]);
+
+        //第二类的值不能和第一类的值拆开
+        assert!(stack_of(&[l.clone(), i2.clone(), d.clone()])
+            .dup2_x2()
+            .is_err());
+        assert!(stack_of(&[d.clone(), i1.clone()]).dup2_x2().is_err());
+        assert!(stack_of(&[l.clone(), i3.clone(), i2.clone(), i1.clone()])
+            .dup2_x2()
+            .is_err());
+    }
+
+    #[test]
+    fn test_word_accounting() {
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::Value;
+        use crate::operand_stack::OperandStack;
+        use crate::runtime_method_info::MethodDescriptor;
+
+        let descriptor = MethodDescriptor::new("(JDJD)V").unwrap();
+        assert_eq!(descriptor.arg_slots(), 8);
+        assert_eq!(descriptor.ret_slots(), 0);
+        let descriptor = MethodDescriptor::new("(I[JLjava/lang/String;)J").unwrap();
+        assert_eq!(descriptor.arg_slots(), 3);
+        assert_eq!(descriptor.ret_slots(), 2);
+        assert_eq!(Value::Double(0.0).category(), 2);
+        assert_eq!(Value::Null.category(), 1);
+
+        //(JDJD)V的参数是4个值，占8个字，正好填满max_stack为8的操作数栈
+        let mut stack = OperandStack::new(8);
+        for value in [
+            Value::Long(1),
+            Value::Double(2.0),
+            Value::Long(3),
+            Value::Double(4.0),
+        ] {
+            stack.push(value).unwrap();
+        }
+        assert_eq!(stack.values().len(), 4);
+        assert_eq!(stack.words(), 8);
+        assert_eq!(stack.push(Value::Int(5)), Err(VmError::StackOverFlow));
+        let descriptor = MethodDescriptor::new("(JDJD)V").unwrap();
+        let args = stack.pop_args(&descriptor).unwrap();
+        assert_eq!(args[0], Value::Long(1));
+        assert_eq!(args[3], Value::Double(4.0));
+        assert_eq!(stack.words(), 0);
+
+        //值的个数相同但字数不一致
+        let mut stack = OperandStack::new(8);
+        for value in [Value::Int(1), Value::Int(2), Value::Int(3), Value::Int(4)] {
+            stack.push(value).unwrap();
+        }
+        assert!(stack.pop_args(&descriptor).is_err());
+
+        //dup2复制一个long，占两个字
+        let mut stack = OperandStack::new(4);
+        stack.push(Value::Long(7)).unwrap();
+        stack.dup2().unwrap();
+        assert_eq!(stack.values(), &[Value::Long(7), Value::Long(7)]);
+        assert_eq!(stack.words(), 4);
+        assert_eq!(stack.dup2(), Err(VmError::StackOverFlow));
+        stack.clear();
+        assert_eq!(stack.words(), 0);
+    }
+}
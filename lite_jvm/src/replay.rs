@@ -0,0 +1,152 @@
+use crate::jvm_values::Value;
+use std::fmt::{Display, Formatter};
+
+/// 执行记录中的一条，对应一条已执行的指令。
+///
+/// 记录的是指令执行之后的栈顶值，引用类型只记录类名，这样同一段代码多次执行得到的记录可以直接比较。
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ExecutionRecord {
+    pub class_name: String,
+    pub method_name: String,
+    pub pc: u16,
+    pub opcode: u8,
+    pub top_of_stack: Option<RecordedValue>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RecordedValue {
+    Uninitialized,
+    Int(i32),
+    Long(i64),
+    //浮点数按位记录，NaN也能比较
+    Float(u32),
+    Double(u64),
+    ReturnAddress(u32),
+    Object(String),
+    Array(String),
+    Null,
+}
+
+impl From<&Value<'_>> for RecordedValue {
+    fn from(value: &Value<'_>) -> Self {
+        match value {
+            Value::Uninitialized => RecordedValue::Uninitialized,
+            Value::Int(v) => RecordedValue::Int(*v),
+            Value::Long(v) => RecordedValue::Long(*v),
+            Value::Float(v) => RecordedValue::Float(v.to_bits()),
+            Value::Double(v) => RecordedValue::Double(v.to_bits()),
+            Value::ReturnAddress(v) => RecordedValue::ReturnAddress(*v),
+            Value::ObjectRef(object_ref) => {
+                RecordedValue::Object(object_ref.get_class().name.clone())
+            }
+            Value::ArrayRef(array_ref) => {
+                RecordedValue::Array(array_ref.get_array_type().array_class_name())
+            }
+            Value::Null => RecordedValue::Null,
+        }
+    }
+}
+
+impl Display for RecordedValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecordedValue::Uninitialized => write!(f, "uninitialized"),
+            RecordedValue::Int(v) => write!(f, "int {}", v),
+            RecordedValue::Long(v) => write!(f, "long {}", v),
+            RecordedValue::Float(v) => write!(f, "float {}", f32::from_bits(*v)),
+            RecordedValue::Double(v) => write!(f, "double {}", f64::from_bits(*v)),
+            RecordedValue::ReturnAddress(v) => write!(f, "returnAddress {}", v),
+            RecordedValue::Object(class_name) => write!(f, "object {}", class_name),
+            RecordedValue::Array(class_name) => write!(f, "array {}", class_name),
+            RecordedValue::Null => write!(f, "null"),
+        }
+    }
+}
+
+//每条记录一行，便于写入外部文件后用文本工具比较
+impl Display for ExecutionRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}.{}@{} op={:#04x} top=",
+            self.class_name, self.method_name, self.pc, self.opcode
+        )?;
+        match &self.top_of_stack {
+            Some(value) => write!(f, "{}", value),
+            None => write!(f, "-"),
+        }
+    }
+}
+
+/// 两份执行记录第一次出现差异的位置，一侧已经结束时对应的记录为None
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergence {
+    pub index: usize,
+    pub left: Option<ExecutionRecord>,
+    pub right: Option<ExecutionRecord>,
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "executions diverge at instruction #{}", self.index)?;
+        for (side, record) in [("left", &self.left), ("right", &self.right)] {
+            match record {
+                Some(record) => writeln!(f, "  {}: {}", side, record)?,
+                None => writeln!(f, "  {}: <end of recording>", side)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 比较两份执行记录，返回第一个不同的位置，完全相同时返回None
+pub fn diff(
+    recording_a: &[ExecutionRecord],
+    recording_b: &[ExecutionRecord],
+) -> Option<Divergence> {
+    let length = recording_a.len().max(recording_b.len());
+    (0..length).find_map(|index| {
+        let left = recording_a.get(index);
+        let right = recording_b.get(index);
+        if left == right {
+            None
+        } else {
+            Some(Divergence {
+                index,
+                left: left.cloned(),
+                right: right.cloned(),
+            })
+        }
+    })
+}
+
+mod tests {
+
+    #[test]
+    fn test_diff() {
+        use crate::replay::{diff, ExecutionRecord, RecordedValue};
+        let record = |pc: u16, top: i32| ExecutionRecord {
+            class_name: "ReplayTest".to_string(),
+            method_name: "fibonacci".to_string(),
+            pc,
+            opcode: 0x1a,
+            top_of_stack: Some(RecordedValue::Int(top)),
+        };
+        let a = vec![record(0, 1), record(1, 2), record(2, 3)];
+        assert_eq!(diff(&a, &a.clone()), None);
+
+        let mut b = a.clone();
+        b[1] = record(1, 5);
+        let divergence = diff(&a, &b).unwrap();
+        assert_eq!(divergence.index, 1);
+        assert_eq!(divergence.left, Some(record(1, 2)));
+        assert_eq!(divergence.right, Some(record(1, 5)));
+
+        let divergence = diff(&a, &a[..2]).unwrap();
+        assert_eq!(divergence.index, 2);
+        assert_eq!(divergence.right, None);
+        assert_eq!(a[0].to_string(), "ReplayTest.fibonacci@0 op=0x1a top=int 1");
+    }
+}
@@ -3,8 +3,11 @@ use crate::runtime_constant_pool::{
     MethodHandlerKind, RuntimeConstantPool, RuntimeConstantPoolEntry,
 };
 use class_file_reader::cesu8_byte_buffer::ByteBuffer;
+use class_file_reader::class_file::InnerClassAccessFlags;
 use class_file_reader::class_file_error;
+use class_file_reader::instruction::{read_instructions, Instruction};
 use indexmap::IndexMap;
+use std::cell::OnceCell;
 use std::fmt::{Display, Formatter};
 
 ///https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.2
@@ -17,12 +20,11 @@ pub enum ConstantValueAttribute {
 }
 
 pub(crate) fn get_attr_as_constant_value(
-    value: &Vec<u8>,
+    value: &[u8],
     cp: &RuntimeConstantPool,
 ) -> VmExecResult<ConstantValueAttribute> {
     assert_eq!(2, value.len());
-    let bytes = &value[..];
-    let const_pool_index = u16::from_be_bytes(bytes.try_into().unwrap());
+    let const_pool_index = u16::from_be_bytes(value.try_into().unwrap());
     match cp.get(const_pool_index)? {
         RuntimeConstantPoolEntry::Integer(v) => Ok(ConstantValueAttribute::Int(*v)),
         RuntimeConstantPoolEntry::Float(v) => Ok(ConstantValueAttribute::Float(*v)),
@@ -82,6 +84,29 @@ pub struct CodeAttribute {
     pub line_number_table: IndexMap<u16, u16>,
     pub local_variable_table: IndexMap<u16, LocalVariableTable>,
     pub local_variable_type_table: IndexMap<u16, LocalVariableTypeTable>,
+    //预解码的指令和它们的字节码地址，第一次以预解码方式执行时生成
+    pub(crate) instructions: OnceCell<Vec<(usize, Instruction)>>,
+}
+
+impl CodeAttribute {
+    /// 字节码地址pc所在的源代码行号，即start_pc不大于pc的表项中start_pc最大的一项，没有时返回0。
+    /// 只依赖字节码地址，和指令是否预解码无关
+    pub fn line_number_at(&self, pc: usize) -> u16 {
+        self.line_number_table
+            .iter()
+            .filter(|(start_pc, _)| **start_pc as usize <= pc)
+            .max_by_key(|(start_pc, _)| **start_pc)
+            .map_or(0, |(_, line_number)| *line_number)
+    }
+
+    pub(crate) fn decoded_instructions(&self) -> VmExecResult<&[(usize, Instruction)]> {
+        if let Some(instructions) = self.instructions.get() {
+            return Ok(instructions);
+        }
+        let instructions =
+            read_instructions(&self.code).map_err(|e| VmError::ClassFormatError(e.to_string()))?;
+        Ok(self.instructions.get_or_init(|| instructions))
+    }
 }
 
 pub struct ExceptionTable {
@@ -92,6 +117,8 @@ pub struct ExceptionTable {
 }
 
 impl ExceptionTable {
+    //处理范围是[start_pc, end_pc)，end_pc处的指令不在范围内
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.7.3
     pub fn catch_line(&self, line_number: u16) -> bool {
         line_number >= self.start_pc && line_number < self.end_pc
     }
@@ -209,6 +236,7 @@ fn read_code_bytes(
         line_number_table,
         local_variable_table,
         local_variable_type_table,
+        instructions: OnceCell::new(),
     })
 }
 pub(crate) fn get_attr_as_code(
@@ -293,3 +321,72 @@ impl BootstrapMethod {
             .collect()
     }
 }
+
+///https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.6
+pub struct InnerClass {
+    pub inner_class_name: String,
+    //不是类的成员时(局部类、匿名类)为None
+    pub outer_class_name: Option<String>,
+    //匿名类为None
+    pub inner_name: Option<String>,
+    pub access_flags: InnerClassAccessFlags,
+}
+
+impl InnerClass {
+    pub fn from(bytes: &[u8], cp: &RuntimeConstantPool) -> VmExecResult<Vec<InnerClass>> {
+        let mut buffer = ByteBuffer::new(bytes);
+        let number_of_classes = buffer.read_u16()?;
+        (0..number_of_classes)
+            .map(|_| {
+                let inner_class_index = buffer.read_u16()?;
+                let outer_class_index = buffer.read_u16()?;
+                let inner_name_index = buffer.read_u16()?;
+                let access_flags = buffer.read_u16()?;
+                Ok(InnerClass {
+                    inner_class_name: cp.get_class_name(inner_class_index)?.to_string(),
+                    outer_class_name: match outer_class_index {
+                        0 => None,
+                        index => Some(cp.get_class_name(index)?.to_string()),
+                    },
+                    inner_name: match inner_name_index {
+                        0 => None,
+                        index => Some(cp.get_utf8_string(index)?),
+                    },
+                    access_flags: InnerClassAccessFlags::from_bits_truncate(access_flags),
+                })
+            })
+            .collect()
+    }
+}
+
+///https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-4.html#jvms-4.7.7
+pub struct EnclosingMethod {
+    pub class_name: String,
+    //类不是直接定义在方法或构造器中时(例如字段初始化中的匿名类)为None
+    pub method_name: Option<String>,
+    pub method_descriptor: Option<String>,
+}
+
+impl EnclosingMethod {
+    pub fn from(bytes: &[u8], cp: &RuntimeConstantPool) -> VmExecResult<EnclosingMethod> {
+        let mut buffer = ByteBuffer::new(bytes);
+        let class_index = buffer.read_u16()?;
+        let method_index = buffer.read_u16()?;
+        let (method_name, method_descriptor) = if method_index == 0 {
+            (None, None)
+        } else if let RuntimeConstantPoolEntry::NameAndTypeDescriptor(name, descriptor) =
+            cp.get(method_index)?
+        {
+            (Some(name.clone()), Some(descriptor.clone()))
+        } else {
+            return Err(VmError::ReadClassBytesError(
+                "Should Be NameAndType".to_string(),
+            ));
+        };
+        Ok(EnclosingMethod {
+            class_name: cp.get_class_name(class_index)?.to_string(),
+            method_name,
+            method_descriptor,
+        })
+    }
+}
@@ -5,7 +5,7 @@ use class_file_reader::constant_pool::{
 use std::fmt::{Display, Formatter};
 
 //https://docs.oracle.com/javase/specs/jvms/se21/html/jvms-5.html#jvms-5.4.3.5
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum MethodHandlerKind {
     GetField,
     GetStatic,
@@ -253,7 +253,7 @@ impl Display for RuntimeConstantPoolEntry {
                 write!(f, "MethodType\t{descriptor}")
             }
             RuntimeConstantPoolEntry::Dynamic(idx, name, descriptor) => {
-                write!(f, "InvokeDynamic\t#{idx},{name}:{descriptor}")
+                write!(f, "Dynamic\t#{idx},{name}:{descriptor}")
             }
             RuntimeConstantPoolEntry::InvokeDynamic(idx, name, descriptor) => {
                 write!(f, "InvokeDynamic\t#{idx},{name}:{descriptor}")
@@ -293,7 +293,7 @@ impl Display for RuntimeConstantPool {
 }
 
 impl RuntimeConstantPool {
-    fn new() -> RuntimeConstantPool {
+    pub(crate) fn new() -> RuntimeConstantPool {
         RuntimeConstantPool {
             entries: Vec::new(),
         }
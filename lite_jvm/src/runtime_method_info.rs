@@ -2,8 +2,10 @@ use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::{PrimaryType, ValueType};
 use crate::runtime_attribute_info::{get_attr_as_code, get_attr_as_exception, CodeAttribute};
 use crate::runtime_constant_pool::RuntimeConstantPool;
+use crate::trivial_method::TrivialMethod;
 use class_file_reader::attribute_info::AttributeType;
 use class_file_reader::method_info::{MethodAccessFlags, MethodInfo};
+use indexmap::Equivalent;
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct MethodDescriptor {
@@ -23,7 +25,8 @@ impl MethodDescriptor {
     }
 
     fn construct_object_type(array_count: &mut usize, value_type: Vec<char>) -> ValueType {
-        let class_name = value_type.iter().collect();
+        //跳过开头的L，只保留类名
+        let class_name = value_type.iter().skip(1).collect();
         if *array_count > 0 {
             let primary_array = ValueType::ObjectArray(class_name, *array_count);
             *array_count = 0;
@@ -32,7 +35,7 @@ impl MethodDescriptor {
             ValueType::Object(class_name)
         }
     }
-    fn new(descriptor: &str) -> VmExecResult<MethodDescriptor> {
+    pub(crate) fn new(descriptor: &str) -> VmExecResult<MethodDescriptor> {
         assert!(descriptor.starts_with('('));
         let mut args = Vec::new();
         let mut reader = Vec::new();
@@ -95,6 +98,24 @@ impl MethodDescriptor {
         let ret = args.pop().unwrap();
         Ok(MethodDescriptor { args, ret })
     }
+
+    fn slots(value_type: &ValueType) -> usize {
+        match value_type {
+            ValueType::Void => 0,
+            ValueType::Primary(PrimaryType::Long | PrimaryType::Double) => 2,
+            _ => 1,
+        }
+    }
+
+    /// 参数占用的字数，不包含接收者
+    pub fn arg_slots(&self) -> usize {
+        self.args.iter().map(Self::slots).sum()
+    }
+
+    /// 返回值占用的字数，void为0
+    pub fn ret_slots(&self) -> usize {
+        Self::slots(&self.ret)
+    }
 }
 pub struct RuntimeMethodInfo {
     pub access_flags: MethodAccessFlags,
@@ -104,6 +125,8 @@ pub struct RuntimeMethodInfo {
     //除了native方法应该都有code属性
     pub code: Option<CodeAttribute>,
     pub exception: Vec<String>,
+    //加载时识别出的简单方法，调用时不创建栈帧
+    pub(crate) trivial_method: Option<TrivialMethod>,
 }
 
 //Code	method_info	45.3
@@ -118,6 +141,28 @@ impl RuntimeMethodInfo {
     pub fn is_static(&self) -> bool {
         self.access_flags.contains(MethodAccessFlags::STATIC)
     }
+    pub fn is_abstract(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::ABSTRACT)
+    }
+    //最后一个参数是可变参数
+    pub fn is_varargs(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::VARARGS)
+    }
+    pub fn is_private(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::PRIVATE)
+    }
+    pub fn is_final(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::FINAL)
+    }
+    pub fn is_synchronized(&self) -> bool {
+        self.access_flags.contains(MethodAccessFlags::SYNCHRONIZED)
+    }
+    //包内可见的方法只能被同一个包中的类覆盖
+    pub fn is_package_private(&self) -> bool {
+        !self.access_flags.intersects(
+            MethodAccessFlags::PUBLIC | MethodAccessFlags::PROTECTED | MethodAccessFlags::PRIVATE,
+        )
+    }
 
     pub fn is_class_init_method(&self) -> bool {
         self.access_flags.contains(MethodAccessFlags::STATIC) && self.name.as_str() == "<clinit>"
@@ -143,6 +188,14 @@ impl RuntimeMethodInfo {
             }
         }
         let descriptor_args_ret = MethodDescriptor::new(&method_info.descriptor)?;
+        let trivial_method = code.as_ref().and_then(|code| {
+            TrivialMethod::classify(
+                &method_info.access_flags,
+                &descriptor_args_ret.args,
+                code,
+                cp,
+            )
+        });
         Ok(RuntimeMethodInfo {
             access_flags: method_info.access_flags,
             name: method_info.name,
@@ -150,27 +203,34 @@ impl RuntimeMethodInfo {
             descriptor_args_ret,
             code,
             exception,
+            trivial_method,
         })
     }
 }
 
-#[derive(PartialEq, Eq, Hash, Clone, Copy)]
-pub struct MethodKey<'a>(&'a str, &'a str);
+//方法表的key，名称和描述符共同标识一个方法
+#[derive(Debug, PartialEq, Eq, Hash, Clone)]
+pub struct MethodKey {
+    pub name: String,
+    pub descriptor: String,
+}
 
-impl<'a> MethodKey<'a> {
-    pub fn new(name: &'a str, descriptor: &'a str) -> MethodKey<'a> {
-        MethodKey(name, descriptor)
+impl MethodKey {
+    pub fn by_method(method: &RuntimeMethodInfo) -> MethodKey {
+        MethodKey {
+            name: method.name.clone(),
+            descriptor: method.descriptor.clone(),
+        }
     }
+}
+
+//查找方法时使用的借用形式，避免每次查找都分配String。
+//派生的Hash与MethodKey依次对两个字符串求hash，结果一致
+#[derive(Hash)]
+pub(crate) struct MethodKeyRef<'k>(pub &'k str, pub &'k str);
 
-    pub fn by_method(method: &RuntimeMethodInfo) -> MethodKey<'a> {
-        let name = unsafe {
-            let str_ptr: *const str = method.name.as_str();
-            &*str_ptr
-        };
-        let descriptor = unsafe {
-            let str_ptr: *const str = method.descriptor.as_str();
-            &*str_ptr
-        };
-        MethodKey(name, descriptor)
+impl Equivalent<MethodKey> for MethodKeyRef<'_> {
+    fn equivalent(&self, key: &MethodKey) -> bool {
+        self.0 == key.name && self.1 == key.descriptor
     }
 }
@@ -1,7 +1,12 @@
+use crate::java_exception::MethodCallError;
 use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::{ReferenceValue, Value};
 use crate::loaded_class::{ClassRef, MethodRef};
 use crate::stack_frame::StackFrame;
+use std::collections::HashSet;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use typed_arena::Arena;
 
 //需要包装一个裸指针，用来保持mutable的引用
@@ -22,6 +27,9 @@ impl<'a> AsMut<StackFrame<'a>> for StackFrameRef<'a> {
 pub struct CallStack<'a> {
     frames: Vec<StackFrameRef<'a>>,
     arena: Arena<StackFrame<'a>>,
+    //剩余可以执行的字节码指令数，None表示不限制。嵌套调用的栈帧共享同一个预算
+    instruction_budget: Option<u64>,
+    cancel_flag: Option<Arc<AtomicBool>>,
 }
 
 impl<'a> CallStack<'a> {
@@ -29,11 +37,43 @@ impl<'a> CallStack<'a> {
         CallStack {
             frames: Vec::new(),
             arena: Arena::new(),
+            instruction_budget: None,
+            cancel_flag: None,
         }
     }
     pub fn depth(&self) -> usize {
         self.frames.len()
     }
+
+    /// 之后最多还能执行的字节码指令数，耗尽时调用返回MethodCallError::BudgetExceeded
+    pub fn set_instruction_budget(&mut self, budget: Option<u64>) {
+        self.instruction_budget = budget;
+    }
+
+    pub fn instruction_budget(&self) -> Option<u64> {
+        self.instruction_budget
+    }
+
+    /// 宿主在其他线程把标志设为true后，正在执行的方法在下一条指令前返回MethodCallError::Cancelled
+    pub fn set_cancel_flag(&mut self, cancel_flag: Option<Arc<AtomicBool>>) {
+        self.cancel_flag = cancel_flag;
+    }
+
+    //每条指令执行前检查取消标志并扣减预算
+    pub(crate) fn charge_instruction(&mut self) -> Result<(), MethodCallError<'a>> {
+        if let Some(cancel_flag) = &self.cancel_flag {
+            if cancel_flag.load(Ordering::Relaxed) {
+                return Err(MethodCallError::Cancelled);
+            }
+        }
+        if let Some(remaining) = &mut self.instruction_budget {
+            if *remaining == 0 {
+                return Err(MethodCallError::BudgetExceeded);
+            }
+            *remaining -= 1;
+        }
+        Ok(())
+    }
     pub(crate) fn new_frame(
         &mut self,
         class_ref: ClassRef<'a>,
@@ -41,27 +81,81 @@ impl<'a> CallStack<'a> {
         object: Option<impl ReferenceValue<'a>>,
         args: Vec<Value<'a>>,
     ) -> VmExecResult<StackFrameRef<'a>> {
-        if method_ref.is_native() {
-            return Err(VmError::NotImplemented);
-        };
+        //在压入栈帧之前检查，避免构造到一半失败
+        let code = StackFrame::code_attribute(class_ref, method_ref)?;
         let locals: Vec<Value<'a>> = object
             .into_iter()
             .map(|e| e.as_value())
             .chain(args)
             .collect();
+        //max_locals以字为单位，long和double占两个槽位
+        let slots: usize = locals.iter().map(|v| v.category() as usize).sum();
+        if slots > code.max_locals as usize {
+            return Err(VmError::ExecuteCodeError(format!(
+                "{}.{}{} called with {} argument slots but max_locals is {}",
+                class_ref.name, method_ref.name, method_ref.descriptor, slots, code.max_locals
+            )));
+        }
         let new_frame = self
             .arena
-            .alloc(StackFrame::new(class_ref, method_ref, locals));
+            .alloc(StackFrame::new(class_ref, method_ref, locals)?);
         let frame = StackFrameRef(new_frame);
         self.frames.push(frame.clone());
         Ok(frame)
     }
 
-    pub(crate) fn pop_frame(&mut self) -> Option<StackFrameRef<'a>> {
-        if !self.frames.is_empty() {
-            Some(self.frames.pop().unwrap())
-        } else {
-            None
+    //从栈底到栈顶遍历当前的栈帧
+    pub(crate) fn frames(&self) -> impl DoubleEndedIterator<Item = &StackFrame<'a>> {
+        self.frames.iter().map(|frame| frame.as_ref())
+    }
+
+    /// 记录当前深度，返回的guard在drop时弹出之后压入的所有栈帧
+    pub(crate) fn guard(&mut self) -> CallStackGuard<'_, 'a> {
+        let depth = self.depth();
+        CallStackGuard {
+            call_stack: self,
+            depth,
         }
     }
+
+    /// 检查栈帧没有重复，操作数栈、局部变量表和pc都在方法Code属性声明的范围内
+    pub fn is_consistent(&self) -> bool {
+        let mut seen = HashSet::new();
+        self.frames.iter().all(|frame_ref| {
+            let frame = frame_ref.as_ref();
+            let Some(code) = frame.method_ref.code.as_ref() else {
+                return false;
+            };
+            seen.insert(frame_ref.0)
+                && frame.op_stack.words() <= code.max_stack as usize
+                && frame.local_var_table.len() == code.max_locals as usize
+                && frame.current_instruction_pc <= code.code.len()
+        })
+    }
+}
+
+/// 调用返回或者出错时都把调用栈恢复到调用前的深度
+pub(crate) struct CallStackGuard<'s, 'a> {
+    call_stack: &'s mut CallStack<'a>,
+    depth: usize,
+}
+
+impl<'a> Deref for CallStackGuard<'_, 'a> {
+    type Target = CallStack<'a>;
+
+    fn deref(&self) -> &Self::Target {
+        self.call_stack
+    }
+}
+
+impl<'a> DerefMut for CallStackGuard<'_, 'a> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.call_stack
+    }
+}
+
+impl Drop for CallStackGuard<'_, '_> {
+    fn drop(&mut self) {
+        self.call_stack.frames.truncate(self.depth);
+    }
 }
@@ -1,3 +1,4 @@
+use crate::conversion;
 use crate::java_exception::{InvokeMethodResult, MethodCallError};
 use crate::jvm_error::VmError::ValueTypeMissMatch;
 use crate::jvm_error::{VmError, VmExecResult};
@@ -5,21 +6,24 @@ use crate::jvm_values::Value::{
     ArrayRef, Double, Float, Int, Long, Null, ObjectRef, ReturnAddress, Uninitialized,
 };
 use crate::jvm_values::{
-    ArrayElement, ArrayReference, ObjectReference, PrimaryType, ReferenceValue, Value,
+    ArrayElement, ArrayReference, ObjectReference, PrimaryType, ReferenceValue, Value, ValueType,
 };
 use crate::loaded_class::{ClassRef, MethodRef};
+use crate::method_handle::is_signature_polymorphic;
 use crate::operand_stack::OperandStack;
-use crate::runtime_attribute_info::ExceptionTable;
+use crate::replay::{ExecutionRecord, RecordedValue};
+use crate::runtime_attribute_info::{BootstrapMethod, CodeAttribute, ExceptionTable};
 use crate::runtime_constant_pool::RuntimeConstantPoolEntry;
+use crate::runtime_method_info::MethodDescriptor;
 use crate::stack::CallStack;
 use crate::stack_frame::InstructionResult::{ContinueMethodExecution, ReturnFromMethod};
 use crate::stack_trace_element::StackTraceElement;
+use crate::string_concat::{StringConcatCallSite, STRING_CONCAT_FACTORY};
 use crate::virtual_machine::VirtualMachine;
 use class_file_reader::cesu8_byte_buffer::ByteBuffer;
 use class_file_reader::instruction::{read_one_instruction, Instruction};
-use indexmap::IndexMap;
-use log::{debug, log_enabled, trace, Level};
-use std::ops::{BitAnd, BitOr, BitXor, Div, Mul, Rem, Shl, Shr, Sub};
+use std::ops::{BitAnd, BitOr, BitXor};
+use tracing::{trace, trace_span};
 
 #[derive(Debug)]
 pub(crate) enum InstructionResult<'a> {
@@ -36,13 +40,23 @@ pub enum LocalValue<'a> {
 pub struct StackFrame<'a> {
     pub(crate) class_ref: ClassRef<'a>,
     pub(crate) method_ref: MethodRef<'a>,
-    pub(crate) pc: usize,
-    //复用bytebuffer。包含了pc和code
+    //当前指令opcode的地址。跳转偏移、异常表查找和行号都以它为基准
+    pub(crate) current_instruction_pc: usize,
+    //下一条要执行的指令地址。wide和switch的对齐填充使指令长度不固定，跳转指令改为目标地址
+    pub(crate) next_instruction_pc: usize,
+    //复用bytebuffer，只用于解码指令
     pub(crate) byte_buffer: ByteBuffer<'a>,
     pub(crate) local_var_table: Vec<LocalValue<'a>>,
     pub(crate) op_stack: OperandStack<'a>,
     pub(crate) exception_tables: &'a Vec<ExceptionTable>,
-    pub(crate) line_number_table: &'a IndexMap<u16, u16>,
+    pub(crate) code_attr: &'a CodeAttribute,
+    //开启预解码时为方法的全部指令，按字节码地址查找，不再每次从字节码解码
+    pub(crate) instructions: Option<&'a [(usize, Instruction)]>,
+    //进入方法时根据TraceFilter计算一次，避免每条指令都去匹配
+    pub(crate) trace_enabled: bool,
+    pub(crate) trace_snapshots: bool,
+    //monitorenter进入且还没有monitorexit的监视器，帧异常返回时释放
+    pub(crate) entered_monitors: Vec<Value<'a>>,
 }
 
 type InvokeResult<'a, T> = Result<T, MethodCallError<'a>>;
@@ -54,9 +68,29 @@ fn is_double_division_returning_nan(a: f64, b: f64) -> bool {
         || ((a == 0f64 || a == -0f64) && (b == 0f64 || b == -0f64))
 }
 
+//drem/frem的结果符号与被除数相同，对应C的fmod而不是IEEE 754的remainder
+//被除数为无穷大或除数为0时结果为NaN，被除数有限而除数为无穷大时结果等于被除数
+//https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.drem
+macro_rules! generate_remainder {
+    ($name:ident, $type:ty) => {
+        fn $name(dividend: $type, divisor: $type) -> $type {
+            if dividend.is_nan() || divisor.is_nan() || dividend.is_infinite() || divisor == 0.0 {
+                <$type>::NAN
+            } else if divisor.is_infinite() || dividend == 0.0 {
+                dividend
+            } else {
+                dividend % divisor
+            }
+        }
+    };
+}
+
+generate_remainder!(double_remainder, f64);
+generate_remainder!(float_remainder, f32);
+
 macro_rules! generate_get_local {
     ($name:ident, $variant:ident, $type:ty) => {
-        fn $name(&mut self, index: u8) -> InvokeResult<'a, $type> {
+        fn $name(&mut self, index: u16) -> InvokeResult<'a, $type> {
             let value = self.get_local(index as usize)?;
             match value {
                 Value::$variant(value) => Ok(value),
@@ -115,6 +149,7 @@ macro_rules! generate_array_store {
             let array = self.pop_array()?;
              match value {
                 $(Value::$variant(_) => {
+                  Self::check_array_store(&array, &value)?;
                   array.set_field_by_offset(index, &value).map_err(MethodCallError::from)
                 })+
                 _=>  Err(MethodCallError::InternalError(VmError::ValueTypeMissMatch))
@@ -137,7 +172,7 @@ macro_rules! generate_return {
 
 macro_rules! generate_load {
      ($name:ident, $($variant:ident),+) => {
-        fn $name(&mut self, index: u8) -> InvokeResult<'a,()> {
+        fn $name(&mut self, index: u16) -> InvokeResult<'a,()> {
             let local = self.get_local(index as usize)?;
             match local {
                 $($variant(..) => {
@@ -151,7 +186,7 @@ macro_rules! generate_load {
 
 macro_rules! generate_store {
     ($name:ident, $variant:ident) => {
-        fn $name(&mut self, index: u8) -> InvokeResult<'a, ()> {
+        fn $name(&mut self, index: u16) -> InvokeResult<'a, ()> {
             let value = self.pop()?;
             match value {
                 $variant(..) => {
@@ -165,11 +200,11 @@ macro_rules! generate_store {
 }
 
 macro_rules! generate_convert {
-    ($name:ident, $variant:ident, $target:ident, $type:ty) => {
+    ($name:ident, $variant:ident, $target:ident, $convert:path) => {
         fn $name(&mut self) -> InvokeResult<'a, ()> {
             let value = self.pop()?;
             if let $variant(v) = value {
-                self.push($target(v as $type))
+                self.push($target($convert(v)))
             } else {
                 Err(MethodCallError::InternalError(VmError::ExecuteCodeError(
                     "convert Error".to_string(),
@@ -212,7 +247,7 @@ macro_rules! generate_if_cmp {
             };
             let result = evaluator(val1, val2);
             if result {
-                self.goto_offset(branch as i32)
+                self.goto_offset(branch as i32)?
             }
             Ok(())
         }
@@ -243,7 +278,9 @@ macro_rules! generate_math {
 
 macro_rules! generate_cmp {
     ($name:ident, $variant:ident,$type:ty) => {
-        fn $name(&mut self, greater_result: i32) -> InvokeResult<'a, ()> {
+        //nan_result为任一操作数是NaN时的结果，xcmpg为1，xcmpl为-1
+        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dcmp_op
+        fn $name(&mut self, nan_result: i32) -> InvokeResult<'a, ()> {
             let val2 = if let $variant(v) = self.pop()? {
                 v
             } else {
@@ -254,13 +291,14 @@ macro_rules! generate_cmp {
             } else {
                 return Err(MethodCallError::InternalError(VmError::ValueTypeMissMatch));
             };
-            let result = val1 - val2;
-            let value = if result > 0 as $type {
-                greater_result
-            } else if result < 0 as $type {
-                0 - greater_result
-            } else {
+            let value = if val1 > val2 {
+                1
+            } else if val1 < val2 {
+                -1
+            } else if val1 == val2 {
                 0
+            } else {
+                nan_result
             };
             self.push(Int(value))
         }
@@ -268,29 +306,57 @@ macro_rules! generate_cmp {
 }
 
 impl<'a> StackFrame<'a> {
+    /// 方法的Code属性。抽象方法在调用时已经抛出AbstractMethodError，不会执行到这里；
+    /// native方法没有找到实现时返回UnsatisfiedLinkError，其他方法缺少Code说明类文件有误
+    pub(crate) fn code_attribute(
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+    ) -> VmExecResult<&'a CodeAttribute> {
+        if let Some(code) = method_ref.code.as_ref() {
+            return Ok(code);
+        }
+        let method = format!(
+            "{}.{}{}",
+            class_ref.name, method_ref.name, method_ref.descriptor
+        );
+        if method_ref.is_native() {
+            Err(VmError::UnsatisfiedLinkError(method))
+        } else {
+            Err(VmError::ClassFormatError(format!(
+                "{} has no Code attribute",
+                method
+            )))
+        }
+    }
+
     pub fn new(
         class_ref: ClassRef<'a>,
         method_ref: MethodRef<'a>,
         local_variables: Vec<Value<'a>>,
-    ) -> StackFrame<'a> {
-        let code_attr = method_ref.code.as_ref().expect("Should Has Code");
+    ) -> VmExecResult<StackFrame<'a>> {
+        let code_attr = Self::code_attribute(class_ref, method_ref)?;
 
         let mut frame = StackFrame {
             class_ref,
             method_ref,
             byte_buffer: ByteBuffer::new(&code_attr.code),
-            pc: 0,
+            current_instruction_pc: 0,
+            next_instruction_pc: 0,
             local_var_table: Vec::new(),
             op_stack: OperandStack::new(code_attr.max_stack as usize),
             exception_tables: &code_attr.exception_table,
-            line_number_table: &code_attr.line_number_table,
+            code_attr,
+            instructions: None,
+            trace_enabled: false,
+            trace_snapshots: false,
+            entered_monitors: Vec::new(),
         };
         for value in local_variables {
             frame.push_local(value);
         }
         let n = code_attr.max_locals as usize - frame.local_var_table.len();
         (0..n).for_each(|_| frame.push_local(Uninitialized));
-        frame
+        Ok(frame)
     }
 
     fn get_local(&self, offset: usize) -> VmExecResult<Value<'a>> {
@@ -304,13 +370,12 @@ impl<'a> StackFrame<'a> {
     }
 
     fn push_local(&mut self, value: Value<'a>) {
-        if let Long(_) | Double(_) = &value {
-            self.local_var_table.push(LocalValue::Entry(value));
+        let category_two = value.is_category_two();
+        self.local_var_table.push(LocalValue::Entry(value));
+        if category_two {
             self.local_var_table.push(LocalValue::PlaceHolder);
-        } else {
-            self.local_var_table.push(LocalValue::Entry(value));
         }
-        trace!("--- local variables --- {:?}", self.local_var_table);
+        trace!(locals = ?self.local_var_table, "local variables");
     }
 
     fn set_local(&mut self, offset: usize, value: Value<'a>) -> VmExecResult<()> {
@@ -336,34 +401,110 @@ impl<'a> StackFrame<'a> {
             Err(MethodCallError::InternalError(ValueTypeMissMatch))
         }
     }
-    generate_int_array_load!(exec_caload, i16);
+    generate_int_array_load!(exec_caload, u16);
     generate_int_array_load!(exec_saload, i16);
     generate_array_load!(exec_iaload, Int);
     generate_array_load!(exec_laload, Long);
     generate_array_load!(exec_faload, Float);
     generate_array_load!(exec_daload, Double);
-    generate_int_array_load!(exec_baload, i8);
-    fn exec_aastore(&mut self) -> InvokeResult<'a, ()> {
+    //baload同时用于byte[]和boolean[]，boolean数组存储时已经只保留最低位
+    fn exec_baload(&mut self) -> InvokeResult<'a, ()> {
+        let index = self.pop_int()? as usize;
+        let array = self.pop_array()?;
+        Self::check_narrow_array("baload", &array, &[PrimaryType::Byte, PrimaryType::Boolean])?;
+        let value = array.get_field_by_offset(index)?.get_int()?;
+        self.push(Int(value as i8 as i32))
+    }
+    fn exec_aastore(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+    ) -> InvokeResult<'a, ()> {
         let value = self.pop()?;
         let index = self.pop_int()? as usize;
         let array = self.pop_array()?;
-        if let ObjectRef(_) | ArrayRef(_) | Null = value {
-            array
-                .set_field_by_offset(index, &value)
-                .map_err(MethodCallError::from)
+        //引用的类型和元素类型不兼容时抛出ArrayStoreException，基本类型的值仍然是验证错误
+        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aastore
+        let element = array.get_array_type();
+        let stored_class = match &value {
+            Value::ObjectRef(object_ref) => Some(object_ref.get_class().name.clone()),
+            Value::ArrayRef(array_ref) => Some(array_ref.get_array_type().array_class_name()),
+            _ => None,
+        };
+        if let Some(stored_class) = stored_class {
+            if !matches!(element, ArrayElement::PrimaryValue(_)) && !element.accepts(&value) {
+                let exception = vm.new_exception(
+                    call_stack,
+                    "java/lang/ArrayStoreException",
+                    &stored_class.replace('/', "."),
+                )?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+        }
+        Self::check_array_store(&array, &value)?;
+        array
+            .set_field_by_offset(index, &value)
+            .map_err(MethodCallError::from)
+    }
+
+    fn check_array_store(array: &ArrayReference<'a>, value: &Value<'a>) -> InvokeResult<'a, ()> {
+        let element = array.get_array_type();
+        if element.accepts(value) {
+            Ok(())
         } else {
-            Err(MethodCallError::InternalError(ValueTypeMissMatch))
+            Err(MethodCallError::InternalError(VmError::VerifyError(
+                format!(
+                    "can't store {:?} into {}",
+                    value,
+                    element.array_class_name()
+                ),
+            )))
+        }
+    }
+    //byte、char、short、boolean数组存储前先截断为元素类型，boolean数组只保留最低位
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.bastore
+    fn exec_narrow_array_store(
+        &mut self,
+        instruction: &str,
+        element_types: &[PrimaryType],
+    ) -> InvokeResult<'a, ()> {
+        let value = self.pop_int()?;
+        let index = self.pop_int()? as usize;
+        let array = self.pop_array()?;
+        let value = match Self::check_narrow_array(instruction, &array, element_types)? {
+            PrimaryType::Boolean => value & 1,
+            PrimaryType::Byte => value as i8 as i32,
+            PrimaryType::Char => value as u16 as i32,
+            PrimaryType::Short => value as i16 as i32,
+            _ => value,
+        };
+        array
+            .set_field_by_offset(index, &Int(value))
+            .map_err(MethodCallError::from)
+    }
+
+    //返回数组的元素类型，不是指令能操作的数组类型时是验证错误
+    fn check_narrow_array(
+        instruction: &str,
+        array: &ArrayReference<'a>,
+        element_types: &[PrimaryType],
+    ) -> InvokeResult<'a, PrimaryType> {
+        let element = array.get_array_type();
+        match element {
+            ArrayElement::PrimaryValue(primary_type) if element_types.contains(&primary_type) => {
+                Ok(primary_type)
+            }
+            _ => Err(MethodCallError::InternalError(VmError::VerifyError(
+                format!("{} on {}", instruction, element.array_class_name()),
+            ))),
         }
     }
-    generate_array_store!(exec_castore, Int);
-    generate_array_store!(exec_sastore, Int);
     generate_array_store!(exec_iastore, Int);
     generate_array_store!(exec_lastore, Long);
     generate_array_store!(exec_fastore, Float);
     generate_array_store!(exec_dastore, Double);
-    generate_array_store!(exec_bastore, Int);
 
-    fn exec_aload(&mut self, index: u8) -> InvokeResult<'a, ()> {
+    fn exec_aload(&mut self, index: u16) -> InvokeResult<'a, ()> {
         let local = self.get_local(index as usize)?;
         match local {
             ObjectRef(_) | ArrayRef(_) | Null => self.push(local.clone()),
@@ -376,34 +517,47 @@ impl<'a> StackFrame<'a> {
     generate_load!(exec_iload, Int);
     generate_load!(exec_lload, Long);
 
-    fn exec_astore(&mut self, index: u8) -> InvokeResult<'a, ()> {
-        let object_ref = self.pop_reference_or_null()?;
-        self.set_local(index as usize, object_ref.clone())
+    //astore是唯一可以处理returnAddress的存储指令，jsr跳转到的子程序用它保存返回地址
+    fn exec_astore(&mut self, index: u16) -> InvokeResult<'a, ()> {
+        let value = self.pop()?;
+        if !matches!(value, ReturnAddress(_)) {
+            self.check_reference_or_null("astore", &value)?;
+        }
+        self.set_local(index as usize, value)
             .map_err(MethodCallError::from)
     }
 
+    //ret跳转到astore保存的返回地址
+    fn exec_ret(&mut self, index: u16) -> InvokeResult<'a, ()> {
+        if let ReturnAddress(address) = self.get_local(index as usize)? {
+            self.goto(address as usize)
+        } else {
+            Err(MethodCallError::InternalError(ValueTypeMissMatch))
+        }
+    }
+
     generate_store!(exec_dstore, Double);
     generate_store!(exec_fstore, Float);
     generate_store!(exec_istore, Int);
     generate_store!(exec_lstore, Long);
 
-    generate_convert!(exec_d2f, Double, Float, f32);
-    generate_convert!(exec_d2l, Double, Long, i64);
-    generate_convert!(exec_d2i, Double, Int, i32);
-    generate_convert!(exec_f2d, Float, Double, f64);
-    generate_convert!(exec_f2i, Float, Int, i32);
-    generate_convert!(exec_f2l, Float, Long, i64);
+    generate_convert!(exec_d2f, Double, Float, conversion::d2f);
+    generate_convert!(exec_d2l, Double, Long, conversion::d2l);
+    generate_convert!(exec_d2i, Double, Int, conversion::d2i);
+    generate_convert!(exec_f2d, Float, Double, conversion::f2d);
+    generate_convert!(exec_f2i, Float, Int, conversion::f2i);
+    generate_convert!(exec_f2l, Float, Long, conversion::f2l);
 
     generate_int_convert!(exec_i2b, i8);
     generate_int_convert!(exec_i2c, u16);
-    generate_convert!(exec_i2d, Int, Double, f64);
-    generate_convert!(exec_i2f, Int, Float, f32);
-    generate_convert!(exec_i2l, Int, Long, i64);
+    generate_convert!(exec_i2d, Int, Double, conversion::i2d);
+    generate_convert!(exec_i2f, Int, Float, conversion::i2f);
+    generate_convert!(exec_i2l, Int, Long, conversion::i2l);
     generate_int_convert!(exec_i2s, i16);
 
-    generate_convert!(exec_l2d, Long, Double, f64);
-    generate_convert!(exec_l2f, Long, Float, f32);
-    generate_convert!(exec_l2i, Long, Int, i32);
+    generate_convert!(exec_l2d, Long, Double, conversion::l2d);
+    generate_convert!(exec_l2f, Long, Float, conversion::l2f);
+    generate_convert!(exec_l2i, Long, Int, conversion::l2i);
 
     generate_math!(exec_double_math, Double, f64);
     generate_math!(exec_float_math, Float, f32);
@@ -423,11 +577,11 @@ impl<'a> StackFrame<'a> {
     where
         T: FnOnce(Value<'a>, Value<'a>) -> bool,
     {
-        let val2 = self.pop_reference_or_null()?;
-        let val1 = self.pop_reference_or_null()?;
+        let val2 = self.pop_reference_or_null("if_acmp")?;
+        let val1 = self.pop_reference_or_null("if_acmp")?;
         let result = evaluator(val1, val2);
         if result {
-            self.goto_offset(branch as i32)
+            self.goto_offset(branch as i32)?
         }
         Ok(())
     }
@@ -452,20 +606,49 @@ impl<'a> StackFrame<'a> {
             )))
         }
     }
-    fn pop_reference_or_null(&mut self) -> InvokeResult<'a, Value<'a>> {
+    fn pop_reference_or_null(&mut self, instruction: &str) -> InvokeResult<'a, Value<'a>> {
         let value = self.pop()?;
-        if let ObjectRef(_) | ArrayRef(_) | Null = value {
-            Ok(value)
-        } else {
-            Err(MethodCallError::InternalError(VmError::ExecuteCodeError(
-                "ShouldBeObjectOrNull".to_string(),
-            )))
+        self.check_reference_or_null(instruction, &value)?;
+        Ok(value)
+    }
+
+    //ifnull、ifnonnull、if_acmp只接受引用，和验证器一样拒绝returnAddress，不把它当作非空值
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.10.1.9.ifnull
+    fn check_reference_or_null(
+        &self,
+        instruction: &str,
+        value: &Value<'a>,
+    ) -> InvokeResult<'a, ()> {
+        match value {
+            ObjectRef(_) | ArrayRef(_) | Null => Ok(()),
+            ReturnAddress(_) => Err(MethodCallError::InternalError(VmError::VerifyError(
+                format!(
+                    "{} at {}: expected reference, found {:?}",
+                    instruction,
+                    self.location(),
+                    value
+                ),
+            ))),
+            _ => Err(MethodCallError::InternalError(VmError::ExecuteCodeError(
+                format!(
+                    "ShouldBeObjectOrNull {} at {}: found {:?}",
+                    instruction,
+                    self.location(),
+                    value
+                ),
+            ))),
         }
     }
-    fn pop_n(&mut self, n: usize) -> InvokeResult<'a, Vec<Value<'a>>> {
-        self.op_stack
-            .pop_n(n)
-            .map_err(MethodCallError::InternalError)
+
+    //错误信息中使用的当前位置，例如Foo.bar(I)V@12
+    fn location(&self) -> String {
+        format!(
+            "{}.{}{}@{}",
+            self.class_ref.name,
+            self.method_ref.name,
+            self.method_ref.descriptor,
+            self.current_instruction_pc
+        )
     }
     fn pop_object(&mut self) -> InvokeResult<'a, ObjectReference<'a>> {
         if let ObjectRef(v) = self.pop()? {
@@ -483,16 +666,14 @@ impl<'a> StackFrame<'a> {
 
     fn exec_pop2(&mut self) -> InvokeResult<'a, ()> {
         let value_1 = self.op_stack.pop()?;
-        match value_1 {
-            Long(_) | Double(_) => Ok(()),
-            Int(_) | Float(_) | ReturnAddress(_) => {
-                if let Int(_) | Float(_) | ReturnAddress(_) = self.pop()? {
-                    Ok(())
-                } else {
-                    Err(MethodCallError::InternalError(ValueTypeMissMatch))
-                }
-            }
-            _ => Err(MethodCallError::InternalError(ValueTypeMissMatch)),
+        if value_1.is_category_two() {
+            return Ok(());
+        }
+        //两个第一类的值，引用也是第一类
+        if self.pop()?.is_category_two() {
+            Err(MethodCallError::InternalError(ValueTypeMissMatch))
+        } else {
+            Ok(())
         }
     }
 
@@ -530,13 +711,102 @@ impl<'a> StackFrame<'a> {
         call_stack: &mut CallStack<'a>,
         constant_index: u16,
     ) -> InvokeResult<'a, ()> {
-        let length = self.pop_int()? as usize;
+        let length = self.pop_array_length(vm, call_stack)?;
         let class_name = self.get_class_name_in_constant_pool(constant_index)?;
-        let class = vm.lookup_class_and_initialize(call_stack, class_name)?;
-        let array = vm.new_array(ArrayElement::ClassReference(class), length);
+        //常量池中的类也可以是数组类，此时创建的是多维数组
+        let descriptor = if class_name.starts_with('[') {
+            format!("[{}", class_name)
+        } else {
+            format!("[L{};", class_name)
+        };
+        let element =
+            vm.array_element_by_descriptor(call_stack, self.class_ref.class_loader, &descriptor)?;
+        let array = vm.new_array(call_stack, element, length)?;
         self.push(ArrayRef(array))
     }
 
+    //从栈顶依次弹出dimensions个长度，外层数组的长度在最下面
+    fn exec_multianewarray(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        constant_index: u16,
+        dimensions: u8,
+    ) -> InvokeResult<'a, ()> {
+        let descriptor = self.get_class_name_in_constant_pool(constant_index)?;
+        //dimensions至少是1，并且不能超过数组类型的维数，否则类文件有误
+        let array_dimensions = descriptor.chars().take_while(|c| *c == '[').count();
+        if dimensions == 0 || dimensions as usize > array_dimensions {
+            return Err(MethodCallError::InternalError(VmError::VerifyError(
+                format!(
+                    "{}.{}{}: multianewarray of {} with {} dimensions at pc {}",
+                    self.class_ref.name,
+                    self.method_ref.name,
+                    self.method_ref.descriptor,
+                    descriptor,
+                    dimensions,
+                    self.current_instruction_pc
+                ),
+            )));
+        }
+        let mut lengths = Vec::with_capacity(dimensions as usize);
+        for _ in 0..dimensions {
+            lengths.push(self.pop_array_length(vm, call_stack)?);
+        }
+        lengths.reverse();
+        let descriptor = self.get_class_name_in_constant_pool(constant_index)?;
+        let array = Self::new_multi_array(
+            vm,
+            call_stack,
+            self.class_ref.class_loader,
+            descriptor,
+            &lengths,
+        )?;
+        self.push(ArrayRef(array))
+    }
+
+    //按lengths逐层创建数组，未指定长度的内层保持为null
+    fn new_multi_array(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        class_loader: Option<ObjectReference<'a>>,
+        descriptor: &str,
+        lengths: &[usize],
+    ) -> InvokeResult<'a, ArrayReference<'a>> {
+        let element = vm.array_element_by_descriptor(call_stack, class_loader, descriptor)?;
+        let array = vm.new_array(call_stack, element, lengths[0])?;
+        if lengths.len() > 1 {
+            for index in 0..lengths[0] {
+                let inner = Self::new_multi_array(
+                    vm,
+                    call_stack,
+                    class_loader,
+                    &descriptor[1..],
+                    &lengths[1..],
+                )?;
+                array.set_field_by_offset(index, &ArrayRef(inner))?;
+            }
+        }
+        Ok(array)
+    }
+
+    fn pop_array_length(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+    ) -> InvokeResult<'a, usize> {
+        let length = self.pop_int()?;
+        if length < 0 {
+            let exception = vm.new_exception(
+                call_stack,
+                "java/lang/NegativeArraySizeException",
+                &length.to_string(),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        Ok(length as usize)
+    }
+
     fn exec_arraylength(&mut self) -> InvokeResult<'a, ()> {
         let array = self.pop_array()?;
         let length = array.get_data_length();
@@ -564,17 +834,16 @@ impl<'a> StackFrame<'a> {
                 (
                     true,
                     None,
-                    Some(ArrayElement::ClassReference(
-                        vm.lookup_class_and_initialize(
-                            call_stack,
-                            &class_name[2..class_name.len() - 1],
-                        )?,
-                    )),
+                    Some(ArrayElement::ClassReference(vm.resolve_class(
+                        call_stack,
+                        self.class_ref,
+                        &class_name[2..class_name.len() - 1],
+                    )?)),
                 )
             } else {
                 (
                     false,
-                    Some(vm.lookup_class_and_initialize(call_stack, class_name)?),
+                    Some(vm.resolve_class(call_stack, self.class_ref, class_name)?),
                     None,
                 )
             };
@@ -587,13 +856,11 @@ impl<'a> StackFrame<'a> {
                     class_ref.is_instance_of(target_class_ref.unwrap())
                 }
             }
-            ArrayRef(array_ref) => {
-                if is_array {
-                    array_ref.is_instance_of(&array_class.unwrap())
-                } else {
-                    false
-                }
-            }
+            ArrayRef(array_ref) if is_array => array_ref.is_instance_of(&array_class.unwrap()),
+            //目标是Object、Cloneable、Serializable或者基本类型数组类
+            ArrayRef(array_ref) => vm
+                .array_class(*array_ref)?
+                .is_subclass_of(&target_class_ref.unwrap().name),
             _ => false,
         };
         Ok(result)
@@ -605,15 +872,12 @@ impl<'a> StackFrame<'a> {
         call_stack: &mut CallStack<'a>,
         instruction: Instruction,
     ) -> InvokeResult<'a, InstructionResult<'a>> {
-        if log_enabled!(Level::Trace) {
-            let depth = "\t".repeat(call_stack.depth());
-            trace!("{}exec {:?}", depth, instruction);
-        }
         match instruction {
             Instruction::Aaload => self.exec_aaload()?,
-            Instruction::Aastore => self.exec_aastore()?,
+            Instruction::Aastore => self.exec_aastore(vm, call_stack)?,
             Instruction::Aconst_null => self.op_stack.push(Null)?,
-            Instruction::Aload(local_index) => self.exec_aload(local_index)?,
+            Instruction::Aload(local_index) => self.exec_aload(local_index.into())?,
+            Instruction::AloadWide(local_index) => self.exec_aload(local_index)?,
             Instruction::Aload_0 => self.exec_aload(0)?,
             Instruction::Aload_1 => self.exec_aload(1)?,
             Instruction::Aload_2 => self.exec_aload(2)?,
@@ -625,7 +889,8 @@ impl<'a> StackFrame<'a> {
                 return self.exec_areturn();
             }
             Instruction::Arraylength => self.exec_arraylength()?,
-            Instruction::Astore(local_index) => self.exec_astore(local_index)?,
+            Instruction::Astore(local_index) => self.exec_astore(local_index.into())?,
+            Instruction::AstoreWide(local_index) => self.exec_astore(local_index)?,
             Instruction::Astore_0 => self.exec_astore(0)?,
             Instruction::Astore_1 => self.exec_astore(1)?,
             Instruction::Astore_2 => self.exec_astore(2)?,
@@ -634,10 +899,14 @@ impl<'a> StackFrame<'a> {
                 return self.exec_athrow();
             }
             Instruction::Baload => self.exec_baload()?,
-            Instruction::Bastore => self.exec_bastore()?,
-            Instruction::Bipush(byte_value) => self.push(Int(byte_value as i32))?,
+            Instruction::Bastore => {
+                self.exec_narrow_array_store("bastore", &[PrimaryType::Byte, PrimaryType::Boolean])?
+            }
+            Instruction::Bipush(byte_value) => self.push(Int(byte_value as i8 as i32))?,
             Instruction::Caload => self.exec_caload()?,
-            Instruction::Castore => self.exec_castore()?,
+            Instruction::Castore => {
+                self.exec_narrow_array_store("castore", &[PrimaryType::Char])?
+            }
             Instruction::Checkcast(constant_pool_index) => {
                 let value = self.pop()?;
                 let is_instance_of =
@@ -654,8 +923,8 @@ impl<'a> StackFrame<'a> {
             Instruction::Dadd => self.exec_double_math(|v1, v2| Ok(v1 + v2))?,
             Instruction::Daload => self.exec_daload()?,
             Instruction::Dastore => self.exec_dastore()?,
-            Instruction::Dcmpg => self.exec_dcmp(-1)?,
-            Instruction::Dcmpl => self.exec_dcmp(1)?,
+            Instruction::Dcmpg => self.exec_dcmp(1)?,
+            Instruction::Dcmpl => self.exec_dcmp(-1)?,
             Instruction::Dconst_0 => self.push(Double(0f64))?,
             Instruction::Dconst_1 => self.push(Double(1f64))?,
             Instruction::Ddiv => self.exec_double_math(|v1, v2| {
@@ -667,7 +936,8 @@ impl<'a> StackFrame<'a> {
                     }
                 })
             })?,
-            Instruction::Dload(local_index) => self.exec_dload(local_index)?,
+            Instruction::Dload(local_index) => self.exec_dload(local_index.into())?,
+            Instruction::DloadWide(local_index) => self.exec_dload(local_index)?,
             Instruction::Dload_0 => self.exec_dload(0)?,
             Instruction::Dload_1 => self.exec_dload(1)?,
             Instruction::Dload_2 => self.exec_dload(2)?,
@@ -677,15 +947,10 @@ impl<'a> StackFrame<'a> {
                 let value = self.pop_double()?;
                 self.push(Double(-value))?;
             }
-            Instruction::Drem => self.exec_double_math(|v1, v2| {
-                Ok(if is_double_division_returning_nan(v1, v2) {
-                    f64::NAN
-                } else {
-                    v1 % v2
-                })
-            })?,
+            Instruction::Drem => self.exec_double_math(|v1, v2| Ok(double_remainder(v1, v2)))?,
             Instruction::Dreturn => return self.exec_dreturn(),
-            Instruction::Dstore(local_index) => self.exec_dstore(local_index)?,
+            Instruction::Dstore(local_index) => self.exec_dstore(local_index.into())?,
+            Instruction::DstoreWide(local_index) => self.exec_dstore(local_index)?,
             Instruction::Dstore_0 => self.exec_dstore(0)?,
             Instruction::Dstore_1 => self.exec_dstore(1)?,
             Instruction::Dstore_2 => self.exec_dstore(2)?,
@@ -703,8 +968,8 @@ impl<'a> StackFrame<'a> {
             Instruction::Fadd => self.exec_float_math(|v1, v2| Ok(v1 + v2))?,
             Instruction::Faload => self.exec_faload()?,
             Instruction::Fastore => self.exec_fastore()?,
-            Instruction::Fcmpl => self.exec_fcmp(1)?,
-            Instruction::Fcmpg => self.exec_fcmp(-1)?,
+            Instruction::Fcmpl => self.exec_fcmp(-1)?,
+            Instruction::Fcmpg => self.exec_fcmp(1)?,
             Instruction::Fconst_0 => self.push(Float(0f32))?,
             Instruction::Fconst_1 => self.push(Float(1f32))?,
             Instruction::Fconst_2 => self.push(Float(2f32))?,
@@ -717,7 +982,8 @@ impl<'a> StackFrame<'a> {
                     }
                 })
             })?,
-            Instruction::Fload(local_index) => self.exec_fload(local_index)?,
+            Instruction::Fload(local_index) => self.exec_fload(local_index.into())?,
+            Instruction::FloadWide(local_index) => self.exec_fload(local_index)?,
             Instruction::Fload_0 => self.exec_fload(0)?,
             Instruction::Fload_1 => self.exec_fload(1)?,
             Instruction::Fload_2 => self.exec_fload(2)?,
@@ -727,15 +993,10 @@ impl<'a> StackFrame<'a> {
                 let v = self.pop_float()?;
                 self.push(Float(-v))?;
             }
-            Instruction::Frem => self.exec_float_math(|v1, v2| {
-                Ok(if is_double_division_returning_nan(v1 as f64, v2 as f64) {
-                    f32::NAN
-                } else {
-                    v1 % v2
-                })
-            })?,
+            Instruction::Frem => self.exec_float_math(|v1, v2| Ok(float_remainder(v1, v2)))?,
             Instruction::Freturn => return self.exec_freturn(),
-            Instruction::Fstore(local_index) => self.exec_fstore(local_index)?,
+            Instruction::Fstore(local_index) => self.exec_fstore(local_index.into())?,
+            Instruction::FstoreWide(local_index) => self.exec_fstore(local_index)?,
             Instruction::Fstore_0 => self.exec_fstore(0)?,
             Instruction::Fstore_1 => self.exec_fstore(1)?,
             Instruction::Fstore_2 => self.exec_fstore(2)?,
@@ -745,15 +1006,15 @@ impl<'a> StackFrame<'a> {
             Instruction::Getstatic(const_pool_index) => {
                 self.exec_get_static(vm, call_stack, const_pool_index)?
             }
-            Instruction::Goto(code_position) => self.goto_offset(code_position as i32),
-            Instruction::Goto_w(code_position) => self.goto_offset(code_position),
+            Instruction::Goto(code_position) => self.goto_offset(code_position as i32)?,
+            Instruction::Goto_w(code_position) => self.goto_offset(code_position)?,
             Instruction::I2b => self.exec_i2b()?,
             Instruction::I2c => self.exec_i2c()?,
             Instruction::I2d => self.exec_i2d()?,
             Instruction::I2f => self.exec_i2f()?,
             Instruction::I2l => self.exec_i2l()?,
             Instruction::I2s => self.exec_i2s()?,
-            Instruction::Iadd => self.exec_int_math(|i1, i2| Ok(i1 + i2))?,
+            Instruction::Iadd => self.exec_int_math(|i1, i2| Ok(i1.wrapping_add(i2)))?,
             Instruction::Iaload => self.exec_iaload()?,
             Instruction::Iand => self.exec_int_math(|i1, i2| Ok(i1 & i2))?,
             Instruction::Iastore => self.exec_iastore()?,
@@ -767,7 +1028,7 @@ impl<'a> StackFrame<'a> {
             //TODO 除以0异常，
             Instruction::Idiv => self.exec_int_math(|i1, i2| match i2 {
                 0 => Err(MethodCallError::InternalError(VmError::ArithmeticException)),
-                _ => Ok(i1 / i2),
+                _ => Ok(i1.wrapping_div(i2)),
             })?,
             Instruction::If_acmpeq(branch) => self.exec_if_acmp(branch, |a1, a2| a1 == a2)?,
             Instruction::If_acmpne(branch) => self.exec_if_acmp(branch, |a1, a2| a1 != a2)?,
@@ -784,31 +1045,32 @@ impl<'a> StackFrame<'a> {
             Instruction::Ifgt(branch) => self.exec_if(branch, |i1| i1 > 0)?,
             Instruction::Ifle(branch) => self.exec_if(branch, |i1| i1 <= 0)?,
             Instruction::Ifnonnull(branch) => {
-                let v = self.pop_reference_or_null()?;
+                let v = self.pop_reference_or_null("ifnonnull")?;
                 if let Null = v {
                 } else {
-                    self.goto_offset(branch as i32);
+                    self.goto_offset(branch as i32)?;
                 }
             }
             Instruction::Ifnull(branch) => {
-                let v = self.pop_reference_or_null()?;
+                let v = self.pop_reference_or_null("ifnull")?;
                 if let Null = v {
-                    self.goto_offset(branch as i32);
+                    self.goto_offset(branch as i32)?;
                 }
             }
             Instruction::Iinc(index, to_add) => {
                 let local = self.get_local_int(index)?;
-                self.set_local(index as usize, Int(local + to_add as i32))?;
+                self.set_local(index as usize, Int(local.wrapping_add(to_add as i32)))?;
             }
-            Instruction::Iload(n) => self.exec_iload(n)?,
+            Instruction::Iload(n) => self.exec_iload(n.into())?,
+            Instruction::IloadWide(n) => self.exec_iload(n)?,
             Instruction::Iload_0 => self.exec_iload(0)?,
             Instruction::Iload_1 => self.exec_iload(1)?,
             Instruction::Iload_2 => self.exec_iload(2)?,
             Instruction::Iload_3 => self.exec_iload(3)?,
-            Instruction::Imul => self.exec_int_math(|i1, i2| Ok(i1 * i2))?,
+            Instruction::Imul => self.exec_int_math(|i1, i2| Ok(i1.wrapping_mul(i2)))?,
             Instruction::Ineg => {
                 let value = self.pop_int()?;
-                self.push(Int(-value))?;
+                self.push(Int(value.wrapping_neg()))?;
             }
             Instruction::Instanceof(cp_index) => {
                 let value = self.pop()?;
@@ -835,39 +1097,41 @@ impl<'a> StackFrame<'a> {
             Instruction::Ior => self.exec_int_math(|i1, i2| Ok(i1.bitor(i2)))?,
             Instruction::Irem => self.exec_int_math(|i1, i2| match i2 {
                 0 => Err(MethodCallError::InternalError(VmError::ArithmeticException)),
-                _ => Ok(i1.rem(i2)),
+                _ => Ok(i1.wrapping_rem(i2)),
             })?,
             Instruction::Ireturn => {
                 return self.exec_ireturn();
             }
             Instruction::Ishl => self.exec_int_math(|i1, i2| Ok(i1 << (i2 & 0x1f)))?,
             Instruction::Ishr => self.exec_int_math(|i1, i2| Ok(i1 >> (i2 & 0x1f)))?,
-            Instruction::Istore(local_index) => self.exec_istore(local_index)?,
+            Instruction::Istore(local_index) => self.exec_istore(local_index.into())?,
+            Instruction::IstoreWide(local_index) => self.exec_istore(local_index)?,
             Instruction::Istore_0 => self.exec_istore(0)?,
             Instruction::Istore_1 => self.exec_istore(1)?,
             Instruction::Istore_2 => self.exec_istore(2)?,
             Instruction::Istore_3 => self.exec_istore(3)?,
-            Instruction::Isub => self.exec_int_math(|i1, i2| Ok(i1 - i2))?,
-            Instruction::Iushr => self.exec_int_math(|i1, i2| {
-                Ok({
-                    if i1 > 0 {
-                        i1 >> (i2 & 0x1f)
-                    } else {
-                        ((i1 as u32) >> (i2 & 0x1f)) as i32
-                    }
-                })
-            })?,
+            Instruction::Isub => self.exec_int_math(|i1, i2| Ok(i1.wrapping_sub(i2)))?,
+            Instruction::Iushr => {
+                self.exec_int_math(|i1, i2| Ok(((i1 as u32) >> (i2 & 0x1f)) as i32))?
+            }
             Instruction::Ixor => self.exec_int_math(|i1, i2| Ok(i1.bitxor(i2)))?,
-            Instruction::Jsr(address) => self.push(ReturnAddress(address as u32))?,
-            Instruction::Jsr_w(address) => self.push(ReturnAddress(address))?,
+            //返回地址是jsr的下一条指令，偏移量是有符号数
+            Instruction::Jsr(offset) => {
+                self.push(ReturnAddress(self.next_instruction_pc as u32))?;
+                self.goto_offset(offset as i16 as i32)?;
+            }
+            Instruction::Jsr_w(offset) => {
+                self.push(ReturnAddress(self.next_instruction_pc as u32))?;
+                self.goto_offset(offset as i32)?;
+            }
             Instruction::L2d => self.exec_l2d()?,
             Instruction::L2f => self.exec_l2f()?,
             Instruction::L2i => self.exec_l2i()?,
-            Instruction::Ladd => self.exec_long_math(|l1, l2| Ok(l1 + l2))?,
+            Instruction::Ladd => self.exec_long_math(|l1, l2| Ok(l1.wrapping_add(l2)))?,
             Instruction::Laload => self.exec_laload()?,
             Instruction::Land => self.exec_long_math(|l1, l2| Ok(l1.bitand(l2)))?,
             Instruction::Lastore => self.exec_lastore()?,
-            Instruction::Lcmp => self.exec_lcmp(1)?,
+            Instruction::Lcmp => self.exec_lcmp(0)?,
             Instruction::Lconst_0 => self.push(Long(0))?,
             Instruction::Lconst_1 => self.push(Long(1))?,
             Instruction::Ldc(constant_pool_index) => {
@@ -876,53 +1140,66 @@ impl<'a> StackFrame<'a> {
             Instruction::Ldc_w(constant_pool_index) => {
                 self.exec_ldc(vm, call_stack, constant_pool_index)?
             }
-            Instruction::Ldc2_w(constant_pool_index) => self.exec_ldc2(constant_pool_index)?,
+            Instruction::Ldc2_w(constant_pool_index) => {
+                self.exec_ldc2(vm, call_stack, constant_pool_index)?
+            }
             Instruction::Ldiv => self.exec_long_math(|l1, l2| match l2 {
                 0 => Err(MethodCallError::InternalError(VmError::ArithmeticException)),
-                _ => Ok(l1.div(l2)),
+                _ => Ok(l1.wrapping_div(l2)),
             })?,
-            Instruction::Lload(n) => self.exec_lload(n)?,
+            Instruction::Lload(n) => self.exec_lload(n.into())?,
+            Instruction::LloadWide(n) => self.exec_lload(n)?,
             Instruction::Lload_0 => self.exec_lload(0)?,
             Instruction::Lload_1 => self.exec_lload(1)?,
             Instruction::Lload_2 => self.exec_lload(2)?,
             Instruction::Lload_3 => self.exec_lload(3)?,
-            Instruction::Lmut => self.exec_long_math(|l1, l2| Ok(l1.mul(l2)))?,
+            Instruction::Lmul => self.exec_long_math(|l1, l2| Ok(l1.wrapping_mul(l2)))?,
             Instruction::Lneg => {
                 let value = self.pop_long()?;
-                self.push(Long(-value))?
+                self.push(Long(value.wrapping_neg()))?
+            }
+            Instruction::Lookupswitch(lookup_switch) => {
+                let key = self.pop_int()?;
+                self.goto_offset(lookup_switch.branch_offset(key))?;
             }
-            Instruction::Lookupswitch => {}
-            Instruction::Lor => self.exec_long_math(|l1, l2| Ok(l1.bitxor(l2)))?,
+            Instruction::Lor => self.exec_long_math(|l1, l2| Ok(l1.bitor(l2)))?,
             Instruction::Lrem => self.exec_long_math(|l1, l2| match l2 {
                 0 => Err(MethodCallError::InternalError(VmError::ArithmeticException)),
-                _ => Ok(l1.rem(l2)),
+                _ => Ok(l1.wrapping_rem(l2)),
             })?,
             Instruction::Lreturn => return self.exec_lreturn(),
-            Instruction::Lshl => self.exec_long_shift(|l1, l2| Ok(l1.shl(l2)))?,
-            Instruction::Lshr => self.exec_long_shift(|l1, l2| Ok(l1.shr(l2)))?,
-            Instruction::Lstore(n) => self.exec_lstore(n)?,
+            Instruction::Lshl => self.exec_long_shift(|l1, l2| Ok(l1 << (l2 & 0x3f)))?,
+            Instruction::Lshr => self.exec_long_shift(|l1, l2| Ok(l1 >> (l2 & 0x3f)))?,
+            Instruction::Lstore(n) => self.exec_lstore(n.into())?,
+            Instruction::LstoreWide(n) => self.exec_lstore(n)?,
             Instruction::Lstore_0 => self.exec_lstore(0)?,
             Instruction::Lstore_1 => self.exec_lstore(1)?,
             Instruction::Lstore_2 => self.exec_lstore(2)?,
             Instruction::Lstore_3 => self.exec_lstore(3)?,
-            Instruction::Lsub => self.exec_long_math(|l1, l2| Ok(l1.sub(l2)))?,
-            Instruction::Lushr => self.exec_long_shift(|l1, l2| {
-                Ok({
-                    if l1 > 0 {
-                        l1 >> (l2 & 0x1f)
-                    } else {
-                        ((l1 as u64) >> (l2 & 0x1f)) as i64
-                    }
-                })
-            })?,
+            Instruction::Lsub => self.exec_long_math(|l1, l2| Ok(l1.wrapping_sub(l2)))?,
+            Instruction::Lushr => {
+                self.exec_long_shift(|l1, l2| Ok(((l1 as u64) >> (l2 & 0x3f)) as i64))?
+            }
             Instruction::Lxor => self.exec_long_math(|l1, l2| Ok(l1.bitxor(l2)))?,
-            Instruction::Monitorenter => {}
-            Instruction::Monitorexit => {}
-            Instruction::Multianewarray(_, _) => {}
+            Instruction::Monitorenter => {
+                let object = self.pop_reference_or_null("monitorenter")?;
+                vm.monitor_enter(call_stack, &object)?;
+                self.entered_monitors.push(object);
+            }
+            Instruction::Monitorexit => {
+                let object = self.pop_reference_or_null("monitorexit")?;
+                vm.monitor_exit(call_stack, &object)?;
+                if let Some(index) = self.entered_monitors.iter().rposition(|v| *v == object) {
+                    self.entered_monitors.remove(index);
+                }
+            }
+            Instruction::Multianewarray(constant_pool_index, dimensions) => {
+                self.exec_multianewarray(vm, call_stack, constant_pool_index, dimensions)?
+            }
             Instruction::New(constant_pool_index) => {
                 self.exec_new_object(vm, call_stack, constant_pool_index)?
             }
-            Instruction::NewArray(a_type) => self.exec_new_array(vm, a_type)?,
+            Instruction::NewArray(a_type) => self.exec_new_array(vm, call_stack, a_type)?,
             Instruction::Nop => {}
             Instruction::Pop => {
                 self.pop()?;
@@ -934,20 +1211,19 @@ impl<'a> StackFrame<'a> {
             Instruction::Putstatic(constant_pool_index) => {
                 self.exec_put_static(vm, call_stack, constant_pool_index)?
             }
-            Instruction::Ret(local_var_index) => {
-                if let ReturnAddress(address) = self.get_local(local_var_index as usize)? {
-                    self.goto(address as usize);
-                } else {
-                    return Err(MethodCallError::InternalError(ValueTypeMissMatch));
-                }
-            }
+            Instruction::Ret(local_var_index) => self.exec_ret(local_var_index.into())?,
+            Instruction::RetWide(local_var_index) => self.exec_ret(local_var_index)?,
             Instruction::Return => return Ok(ReturnFromMethod(None)),
             Instruction::Saload => self.exec_saload()?,
-            Instruction::Sastore => self.exec_sastore()?,
+            Instruction::Sastore => {
+                self.exec_narrow_array_store("sastore", &[PrimaryType::Short])?
+            }
             Instruction::Sipush(value) => self.push(Int(value as i32))?,
             Instruction::Swap => self.op_stack.swap()?,
-            Instruction::Tableswitch => {}
-            Instruction::Wide => {}
+            Instruction::Tableswitch(table_switch) => {
+                let index = self.pop_int()?;
+                self.goto_offset(table_switch.branch_offset(index))?;
+            }
         }
         Ok(ContinueMethodExecution)
     }
@@ -959,13 +1235,18 @@ impl<'a> StackFrame<'a> {
         pool_index: u16,
     ) -> InvokeResult<'a, ()> {
         let class_name = self.get_class_name_in_constant_pool(pool_index)?;
-        let class_ref = vm.lookup_class_and_initialize(call_stack, class_name)?;
-        let object_reference = vm.new_object(class_ref);
+        let class_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
+        let object_reference = vm.new_object(call_stack, class_ref)?;
         self.push(ObjectRef(object_reference))
     }
 
-    fn exec_new_array(&mut self, vm: &mut VirtualMachine<'a>, a_type: u8) -> InvokeResult<'a, ()> {
-        let count = self.pop_int()?;
+    fn exec_new_array(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        a_type: u8,
+    ) -> InvokeResult<'a, ()> {
+        let count = self.pop_array_length(vm, call_stack)?;
         let primary_type = match a_type {
             4 => PrimaryType::Boolean,
             5 => PrimaryType::Char,
@@ -975,9 +1256,14 @@ impl<'a> StackFrame<'a> {
             9 => PrimaryType::Short,
             10 => PrimaryType::Int,
             11 => PrimaryType::Long,
-            _ => return Err(MethodCallError::InternalError(ValueTypeMissMatch)),
+            _ => {
+                return Err(MethodCallError::InternalError(VmError::VerifyError(
+                    format!("newarray with invalid atype {}", a_type),
+                )))
+            }
         };
-        let array_ref = vm.new_array(ArrayElement::PrimaryValue(primary_type), count as usize);
+        let array_ref =
+            vm.new_array(call_stack, ArrayElement::PrimaryValue(primary_type), count)?;
         self.push(ArrayRef(array_ref))
     }
 
@@ -997,50 +1283,59 @@ impl<'a> StackFrame<'a> {
             RuntimeConstantPoolEntry::Float(f) => self.push(Float(*f)),
 
             RuntimeConstantPoolEntry::ClassReference(class_name) => self.push(ObjectRef(
-                vm.new_java_lang_class_object(call_stack, class_name)
-                    .unwrap(),
-            )),
-            RuntimeConstantPoolEntry::StringReference(str) => self.push(ObjectRef(
-                vm.new_java_lang_string_object(call_stack, str).unwrap(),
+                vm.resolve_class_object(call_stack, self.class_ref, class_name)?,
             )),
+            RuntimeConstantPoolEntry::StringReference(str) => {
+                self.push(ObjectRef(vm.new_java_lang_string_object(call_stack, str)?))
+            }
 
             RuntimeConstantPoolEntry::MethodReference(
                 class_name,
                 method_name,
                 method_descriptor,
-            ) => self.push(ObjectRef(
-                vm.new_java_lang_invoke_method_type(
-                    call_stack,
-                    class_name,
-                    method_name,
-                    method_descriptor,
-                )
-                .unwrap(),
-            )),
+            ) => self.push(ObjectRef(vm.new_java_lang_invoke_method_type(
+                call_stack,
+                class_name,
+                method_name,
+                method_descriptor,
+            )?)),
             RuntimeConstantPoolEntry::MethodHandler(
                 kind,
                 class_name,
                 method_name,
                 method_descriptor,
-            ) => self.push(ObjectRef(
-                vm.new_java_lang_invoke_method_handler(
-                    call_stack,
-                    kind,
-                    class_name,
-                    method_name,
-                    method_descriptor,
-                )
-                .unwrap(),
-            )),
+            ) => self.push(ObjectRef(vm.new_java_lang_invoke_method_handler(
+                call_stack,
+                kind,
+                class_name,
+                method_name,
+                method_descriptor,
+            )?)),
+            RuntimeConstantPoolEntry::Dynamic(_, _, descriptor)
+                if !matches!(descriptor.as_str(), "J" | "D") =>
+            {
+                self.push(vm.resolve_dynamic_constant(call_stack, self.class_ref, index)?)
+            }
             _ => Err(MethodCallError::InternalError(ValueTypeMissMatch)),
         }
     }
 
-    fn exec_ldc2(&mut self, index: u16) -> InvokeResult<'a, ()> {
+    fn exec_ldc2(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        index: u16,
+    ) -> InvokeResult<'a, ()> {
         let value = self.get_constant_pool(index)?;
         match value {
             RuntimeConstantPoolEntry::Long(i) => self.push(Long(*i)),
             RuntimeConstantPoolEntry::Double(f) => self.push(Double(*f)),
+            //long和double类型的动态计算常量使用ldc2_w加载
+            RuntimeConstantPoolEntry::Dynamic(_, _, descriptor)
+                if matches!(descriptor.as_str(), "J" | "D") =>
+            {
+                self.push(vm.resolve_dynamic_constant(call_stack, self.class_ref, index)?)
+            }
             _ => Err(MethodCallError::InternalError(ValueTypeMissMatch)),
         }
     }
@@ -1051,29 +1346,78 @@ impl<'a> StackFrame<'a> {
     {
         let value = self.pop_int()?;
         if evaluator(value) {
-            self.goto_offset(offset as i32);
+            self.goto_offset(offset as i32)?;
         }
         Ok(())
     }
-    fn goto(&mut self, new_pc: usize) {
-        self.pc = new_pc;
-        self.byte_buffer.jump_to(new_pc);
+    //跳转目标必须在code数组之内，否则下一次取指会越过code的末尾
+    fn goto(&mut self, new_pc: usize) -> InvokeResult<'a, ()> {
+        if new_pc >= self.byte_buffer.len() {
+            return Err(self.invalid_jump_target(new_pc as i64));
+        }
+        self.next_instruction_pc = new_pc;
+        Ok(())
+    }
+
+    //偏移量相对于跳转指令本身的地址，与指令长度无关
+    fn goto_offset(&mut self, offset: i32) -> InvokeResult<'a, ()> {
+        let target = self.current_instruction_pc as i64 + offset as i64;
+        if target < 0 {
+            return Err(self.invalid_jump_target(target));
+        }
+        self.goto(target as usize)
+    }
+
+    fn invalid_jump_target(&self, target: i64) -> MethodCallError<'a> {
+        MethodCallError::InternalError(VmError::VerifyError(format!(
+            "{}.{}{}: jump from pc {} to {} outside code length {}",
+            self.class_ref.name,
+            self.method_ref.name,
+            self.method_ref.descriptor,
+            self.current_instruction_pc,
+            target,
+            self.byte_buffer.len()
+        )))
+    }
+
+    fn invalid_instruction(&self) -> MethodCallError<'a> {
+        MethodCallError::InternalError(VmError::ClassFormatError(format!(
+            "{}.{}{}: invalid instruction at pc {}",
+            self.class_ref.name,
+            self.method_ref.name,
+            self.method_ref.descriptor,
+            self.current_instruction_pc
+        )))
     }
 
-    fn goto_offset(&mut self, offset: i32) {
-        self.pc = (self.pc as i32 + offset) as usize;
-        self.byte_buffer.jump_to(self.pc);
+    //字节码压栈超过了max_stack，说明类文件有误，不是Java的StackOverflowError
+    fn operand_stack_overflow(&self) -> MethodCallError<'a> {
+        MethodCallError::InternalError(VmError::VerifyError(format!(
+            "{}.{}{}: operand stack overflow at pc {}, max_stack {}",
+            self.class_ref.name,
+            self.method_ref.name,
+            self.method_ref.descriptor,
+            self.current_instruction_pc,
+            self.op_stack.max_size()
+        )))
     }
 
     fn exec_get_field(&mut self, field_index: u16) -> InvokeResult<'a, ()> {
         let object = self.pop()?;
         if let ObjectRef(object_ref) = object {
-            let (class_name, field_name, _descriptor) =
+            let (class_name, field_name, descriptor) =
                 self.get_field_in_constant_pool(field_index)?;
-            let class_ref = object_ref.get_class();
-            //TODO 校验描述符类型
-            assert!(class_ref.is_subclass_of(class_name));
-            let field_value = object_ref.get_field_by_name(field_name)?;
+            let field_value = object_ref.get_field_in_class(class_name, field_name)?;
+            //写入时已经校验过，debug构建下再校验读取结果，尽早发现堆上的数据被破坏
+            if cfg!(debug_assertions) {
+                Self::check_field_value(
+                    "getfield",
+                    class_name,
+                    field_name,
+                    descriptor,
+                    &field_value,
+                )?;
+            }
             return self.push(field_value);
         }
         Err(MethodCallError::InternalError(ValueTypeMissMatch))
@@ -1083,19 +1427,33 @@ impl<'a> StackFrame<'a> {
         let value = self.pop()?;
         let object = self.pop()?;
         if let ObjectRef(object_ref) = object {
-            let (class_name, field_name, _descriptor) =
+            let (class_name, field_name, descriptor) =
                 self.get_field_in_constant_pool(field_index)?;
-            let class_ref = object_ref.get_class();
-            //TODO 校验描述符类型
-            assert!(class_ref.is_subclass_of(class_name));
-            //TODO 校验值类型
+            Self::check_field_value("putfield", class_name, field_name, descriptor, &value)?;
             return object_ref
-                .set_field_by_name(field_name, &value)
+                .set_field_in_class(class_name, field_name, &value)
                 .map_err(MethodCallError::from);
         }
         Err(MethodCallError::InternalError(ValueTypeMissMatch))
     }
 
+    //值的类型和字段描述符不一致时直接写入会导致之后把整数当作指针读取
+    fn check_field_value(
+        instruction: &str,
+        class_name: &str,
+        field_name: &str,
+        descriptor: &str,
+        value: &Value<'a>,
+    ) -> InvokeResult<'a, ()> {
+        if value.matches_descriptor(descriptor) {
+            Ok(())
+        } else {
+            Err(MethodCallError::InternalError(VmError::VerifyError(
+                format!("{instruction} {class_name}.{field_name}:{descriptor} with {value:?}"),
+            )))
+        }
+    }
+
     fn exec_get_static(
         &mut self,
         vm: &mut VirtualMachine<'a>,
@@ -1103,16 +1461,10 @@ impl<'a> StackFrame<'a> {
         field_index: u16,
     ) -> InvokeResult<'a, ()> {
         let (class_name, field_name, _descriptor) = self.get_field_in_constant_pool(field_index)?;
-        let value = vm.get_static_field_by_class_name(call_stack, class_name, field_name)?;
-        if let Some(value) = value {
-            self.push(value.clone())
-        } else {
-            vm.lookup_class_and_initialize(call_stack, class_name)?;
-            self.push(
-                vm.get_static_field_by_class_name(call_stack, class_name, field_name)?
-                    .unwrap()
-                    .clone(),
-            )
+        let class_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
+        match vm.get_static(class_ref, field_name) {
+            Some(value) => self.push(value.clone()),
+            None => Err(VmError::FieldNotFoundException(field_name.to_string()).into()),
         }
     }
 
@@ -1123,8 +1475,17 @@ impl<'a> StackFrame<'a> {
         field_index: u16,
     ) -> InvokeResult<'a, ()> {
         let static_value = self.pop()?;
-        let (class_name, field_name, _descriptor) = self.get_field_in_constant_pool(field_index)?;
-        vm.set_static_field_by_class_name(call_stack, class_name, field_name, static_value)
+        let (class_name, field_name, descriptor) = self.get_field_in_constant_pool(field_index)?;
+        Self::check_field_value(
+            "putstatic",
+            class_name,
+            field_name,
+            descriptor,
+            &static_value,
+        )?;
+        let class_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
+        vm.set_static(class_ref, field_name, static_value);
+        Ok(())
     }
 
     fn exec_invoke_interface(
@@ -1132,7 +1493,7 @@ impl<'a> StackFrame<'a> {
         vm: &mut VirtualMachine<'a>,
         call_stack: &mut CallStack<'a>,
         offset: u16,
-        _arg_count: u8,
+        arg_count: u8,
     ) -> InvokeResult<'a, ()> {
         if let RuntimeConstantPoolEntry::InterfaceMethodReference(
             class_name,
@@ -1140,8 +1501,24 @@ impl<'a> StackFrame<'a> {
             descriptor,
         ) = self.get_constant_pool(offset)?
         {
-            let interface_ref = vm.lookup_class_and_initialize(call_stack, class_name)?;
-            assert!(interface_ref.is_interface());
+            let interface_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
+            Self::check_method_ref_kind(vm, call_stack, interface_ref, true)?;
+            //count以字为单位，包含接收者，long和double占两个字
+            let (declaring_ref, method_ref) =
+                interface_ref.get_method_by_checking_super(method_name, descriptor)?;
+            let expected_count = 1 + method_ref.descriptor_args_ret.arg_slots();
+            if arg_count as usize != expected_count {
+                return Err(MethodCallError::InternalError(VmError::ExecuteCodeError(
+                    format!(
+                        "invokeinterface count {arg_count} mismatch {class_name}:{method_name}{descriptor}, expected {expected_count}"
+                    ),
+                )));
+            }
+            //JDK 11之后私有接口方法通过invokeinterface调用，私有方法不参与分派，直接调用
+            //https://docs.oracle.com/javase/specs/jvms/se11/html/jvms-6.html#jvms-6.5.invokeinterface
+            if method_ref.is_private() {
+                return self.invoke_on_receiver(vm, call_stack, declaring_ref, method_ref);
+            }
             self.invoke_virtual_on_receiver(vm, call_stack, interface_ref, method_name, descriptor)
         } else {
             Err(MethodCallError::InternalError(ValueTypeMissMatch))
@@ -1159,9 +1536,9 @@ impl<'a> StackFrame<'a> {
         let method_ref =
             class_or_interface_ref.get_method_by_checking_super(method_name, descriptor)?;
         assert!(!method_ref.1.is_init_method() && !method_ref.1.is_class_init_method());
-        let method_args = &method_ref.1.descriptor_args_ret.args;
+        let method_args = &method_ref.1.descriptor_args_ret;
         //TODO validate method_args and poped args type
-        let args = self.op_stack.pop_n(method_args.len())?;
+        let args = self.op_stack.pop_args(method_args)?;
         let pop_value = self.pop()?;
         match pop_value {
             ObjectRef(object_ref) => {
@@ -1179,9 +1556,10 @@ impl<'a> StackFrame<'a> {
             }
 
             ArrayRef(object_ref) => {
-                //多态方法，方法要从当前对象去查方法实例
-                let (class_ref, method_ref) =
-                    class_or_interface_ref.get_method_by_checking_super(method_name, descriptor)?;
+                //数组类没有声明方法，从数组类开始查找会得到Object中的实现
+                let (class_ref, method_ref) = vm
+                    .array_class(object_ref)?
+                    .get_method_by_checking_super(method_name, descriptor)?;
                 if let Some(v) =
                     vm.invoke_method(call_stack, class_ref, method_ref, Some(object_ref), args)?
                 {
@@ -1194,7 +1572,7 @@ impl<'a> StackFrame<'a> {
                     vm.new_object_by_class_name(call_stack, "java/lang/NullPointerException")?;
                 Err(MethodCallError::ExceptionThrown(null_pointer_exception))
             }
-            _ => return Err(MethodCallError::InternalError(ValueTypeMissMatch)),
+            _ => Err(MethodCallError::InternalError(ValueTypeMissMatch)),
         }
     }
 
@@ -1204,27 +1582,78 @@ impl<'a> StackFrame<'a> {
         call_stack: &mut CallStack<'a>,
         offset: u16,
     ) -> InvokeResult<'a, ()> {
-        if let RuntimeConstantPoolEntry::MethodReference(class_name, method_name, descriptor) =
-            self.get_constant_pool(offset)?
-        {
-            let class_ref = vm.lookup_class_and_initialize(call_stack, class_name)?;
-            let method_ref = class_ref.get_method(method_name, descriptor)?;
-            let method_args = &method_ref.descriptor_args_ret.args;
-            //TODO validate method_args and poped args type
-            let args = self.pop_n(method_args.len())?;
-            let object_ref = self.pop_object()?;
-            //必须是子类调用父类的方法，自身的私有方法，以及实例初始化化方法
-            assert!(object_ref.is_instance_of(class_ref));
-
-            if let Some(v) =
-                vm.invoke_method(call_stack, class_ref, method_ref, Some(object_ref), args)?
-            {
-                self.push(v)?;
-            }
-            Ok(())
+        let (class_name, method_name, descriptor, is_interface_method) =
+            match self.get_constant_pool(offset)? {
+                RuntimeConstantPoolEntry::MethodReference(class_name, method_name, descriptor) => {
+                    (class_name, method_name, descriptor, false)
+                }
+                RuntimeConstantPoolEntry::InterfaceMethodReference(
+                    class_name,
+                    method_name,
+                    descriptor,
+                ) => (class_name, method_name, descriptor, true),
+                _ => return Err(MethodCallError::InternalError(ValueTypeMissMatch)),
+            };
+        let class_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
+        Self::check_method_ref_kind(vm, call_stack, class_ref, is_interface_method)?;
+        //接口上的invokespecial是私有方法或者X.super.m()，只在该接口及其父接口中查找，不按接收者分派
+        let (class_ref, method_ref) = if is_interface_method {
+            class_ref.get_method_by_checking_super(method_name, descriptor)?
         } else {
-            Err(MethodCallError::InternalError(ValueTypeMissMatch))
+            (class_ref, class_ref.get_method(method_name, descriptor)?)
+        };
+        self.invoke_on_receiver(vm, call_stack, class_ref, method_ref)
+    }
+
+    //不做动态分派，直接在接收者上调用解析到的方法
+    fn invoke_on_receiver(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+    ) -> InvokeResult<'a, ()> {
+        let method_args = &method_ref.descriptor_args_ret;
+        //TODO validate method_args and poped args type
+        let args = self.op_stack.pop_args(method_args)?;
+        let object_ref = self.pop_object()?;
+        //必须是子类调用父类的方法，自身的私有方法，以及实例初始化化方法
+        assert!(object_ref.is_instance_of(class_ref));
+
+        if let Some(v) =
+            vm.invoke_method(call_stack, class_ref, method_ref, Some(object_ref), args)?
+        {
+            self.push(v)?;
+        }
+        Ok(())
+    }
+
+    //Methodref必须指向类，InterfaceMethodref必须指向接口
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.4.3.3
+    fn check_method_ref_kind(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        is_interface_method: bool,
+    ) -> InvokeResult<'a, ()> {
+        if class_ref.is_interface() == is_interface_method {
+            return Ok(());
         }
+        let expected = if is_interface_method {
+            "interface"
+        } else {
+            "class"
+        };
+        let error = vm.new_exception(
+            call_stack,
+            "java/lang/IncompatibleClassChangeError",
+            &format!(
+                "Found {}, but {} was expected",
+                class_ref.name.replace('/', "."),
+                expected
+            ),
+        )?;
+        Err(MethodCallError::ExceptionThrown(error))
     }
     fn exec_invoke_virtual(
         &mut self,
@@ -1239,7 +1668,15 @@ impl<'a> StackFrame<'a> {
             descriptor,
         ) = self.get_constant_pool(offset)?
         {
-            let class_ref = vm.lookup_class_and_initialize(call_stack, class_name)?;
+            if is_signature_polymorphic(class_name, method_name) {
+                return self.exec_invoke_method_handle(
+                    vm,
+                    call_stack,
+                    method_name == "invokeExact",
+                    descriptor,
+                );
+            }
+            let class_ref = vm.resolve_class(call_stack, self.class_ref, class_name)?;
             assert!(!class_ref.is_interface());
             self.invoke_virtual_on_receiver(vm, call_stack, class_ref, method_name, descriptor)
         } else {
@@ -1247,42 +1684,109 @@ impl<'a> StackFrame<'a> {
         }
     }
 
+    //invokeExact/invoke的调用点描述符和句柄类型不匹配时抛出WrongMethodTypeException
+    fn exec_invoke_method_handle(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        exact: bool,
+        descriptor: &str,
+    ) -> InvokeResult<'a, ()> {
+        let site = MethodDescriptor::new(descriptor)?;
+        let args = self.op_stack.pop_args(&site)?;
+        let handle = match self.pop()? {
+            ObjectRef(handle) => handle,
+            Null => {
+                let method_name = if exact { "invokeExact" } else { "invoke" };
+                let error = vm.new_exception(
+                    call_stack,
+                    "java/lang/NullPointerException",
+                    &format!(
+                        "java/lang/invoke/MethodHandle.{}{}",
+                        method_name, descriptor
+                    ),
+                )?;
+                return Err(MethodCallError::ExceptionThrown(error));
+            }
+            _ => return Err(MethodCallError::InternalError(ValueTypeMissMatch)),
+        };
+        //只支持ldc加载的MethodHandle常量
+        let info = vm.method_handle_info(handle).cloned().ok_or_else(|| {
+            VmError::LinkageError(format!("unsupported method handle {}", handle))
+        })?;
+        if !info.accepts(vm, call_stack, descriptor, exact)? {
+            let error = vm.new_exception(
+                call_stack,
+                "java/lang/invoke/WrongMethodTypeException",
+                &format!("expected {} but found {}", info.method_type(), descriptor),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(error));
+        }
+        let result = info.invoke(vm, call_stack, args)?;
+        match (result, site.ret) {
+            (_, ValueType::Void) | (None, _) => Ok(()),
+            (Some(value), _) => self.push(value),
+        }
+    }
+
     fn exec_invoke_static(
         &mut self,
         vm: &mut VirtualMachine<'a>,
         call_stack: &mut CallStack<'a>,
         offset: u16,
     ) -> InvokeResult<'a, ()> {
-        if let RuntimeConstantPoolEntry::MethodReference(class_name, method_name, descriptor)
-        | RuntimeConstantPoolEntry::InterfaceMethodReference(
-            class_name,
-            method_name,
-            descriptor,
-        ) = self.get_constant_pool(offset)?
-        {
-            let class_ref = if &self.class_ref.name != class_name {
-                vm.get_class_by_name(call_stack, class_name)?
-            } else {
-                self.class_ref
+        let (class_name, method_name, descriptor, is_interface_method) =
+            match self.get_constant_pool(offset)? {
+                RuntimeConstantPoolEntry::MethodReference(class_name, method_name, descriptor) => {
+                    (class_name, method_name, descriptor, false)
+                }
+                RuntimeConstantPoolEntry::InterfaceMethodReference(
+                    class_name,
+                    method_name,
+                    descriptor,
+                ) => (class_name, method_name, descriptor, true),
+                _ => return Err(MethodCallError::InternalError(ValueTypeMissMatch)),
             };
-            let method_ref = class_ref.get_method(method_name, descriptor)?;
-            assert!(method_ref.is_static());
-            let method_args = &method_ref.descriptor_args_ret.args;
-            //TODO validate method_args and poped args type
-            let args = self.op_stack.pop_n(method_args.len())?;
-            if let Some(v) = vm.invoke_method(
-                call_stack,
-                class_ref,
-                method_ref,
-                None::<ObjectReference>,
-                args,
-            )? {
-                self.push(v)?;
-            }
-            Ok(())
+        let class_ref = if &self.class_ref.name != class_name {
+            vm.resolve_class_without_initialize(call_stack, self.class_ref, class_name)?
         } else {
-            Err(MethodCallError::InternalError(ValueTypeMissMatch))
+            self.class_ref
+        };
+        Self::check_method_ref_kind(vm, call_stack, class_ref, is_interface_method)?;
+        //接口的静态方法不会被继承，只在接口自身查找；类的静态方法可能声明在父类中，
+        //这时只初始化声明该方法的父类，不初始化引用中的子类
+        let (class_ref, method_ref) = if is_interface_method {
+            (class_ref, class_ref.get_method(method_name, descriptor)?)
+        } else {
+            class_ref.get_method_by_checking_super(method_name, descriptor)?
+        };
+        if !method_ref.is_static() {
+            let error = vm.new_exception(
+                call_stack,
+                "java/lang/IncompatibleClassChangeError",
+                &format!(
+                    "Expected static method {}.{}{}",
+                    class_ref.name.replace('/', "."),
+                    method_name,
+                    descriptor
+                ),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(error));
         }
+        vm.link_and_initialize(call_stack, class_ref)?;
+        let method_args = &method_ref.descriptor_args_ret;
+        //TODO validate method_args and poped args type
+        let args = self.op_stack.pop_args(method_args)?;
+        if let Some(v) = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            args,
+        )? {
+            self.push(v)?;
+        }
+        Ok(())
     }
     pub fn to_stack_trace(&self) -> StackTraceElement {
         StackTraceElement {
@@ -1298,40 +1802,136 @@ impl<'a> StackFrame<'a> {
         vm: &mut VirtualMachine<'a>,
         call_stack: &mut CallStack<'a>,
     ) -> InvokeMethodResult<'a> {
-        if log_enabled!(Level::Trace) {
+        //每次调用一个span，嵌套调用的span是调用者span的子span
+        let span = trace_span!(
+            "invoke_method",
+            class = %self.class_ref.name,
+            method = %self.method_ref.name,
+            descriptor = %self.method_ref.descriptor,
+            depth = call_stack.depth(),
+        );
+        let _entered = span.enter();
+        (self.trace_enabled, self.trace_snapshots) =
+            vm.frame_trace_options(self.class_ref, self.method_ref);
+        if vm.is_predecode_enabled() {
+            self.instructions = Some(self.code_attr.decoded_instructions()?);
+        }
+        self.op_stack
+            .set_overflow_check(vm.is_operand_stack_check_enabled());
+        if self.trace_enabled {
             let depth = "\t".repeat(call_stack.depth() - 1);
-            debug!(
-                "{}=> invoke_method {}:{}{}--{:?}",
+            let locals: Vec<String> = self
+                .local_var_table
+                .iter()
+                .map(|local| match local {
+                    LocalValue::Entry(value) => vm.format_value(value),
+                    LocalValue::PlaceHolder => "_".to_string(),
+                })
+                .collect();
+            vm.trace_line(&format!(
+                "{}=> invoke_method {}:{}{}--[{}]",
                 depth,
                 self.class_ref.name,
                 self.method_ref.name,
                 self.method_ref.descriptor,
-                self.local_var_table
-            );
+                locals.join(", ")
+            ));
+        }
+        let result = self.execute_instructions(vm, call_stack);
+        if result.is_err() {
+            self.release_monitors(vm, call_stack);
+        }
+        result
+    }
+
+    //预算耗尽、取消和System.exit不经过异常表，同步块里的monitorexit不会执行，
+    //异常没有被处理就离开方法时也一样，剩下的监视器由帧自己释放
+    fn release_monitors(&mut self, vm: &mut VirtualMachine<'a>, call_stack: &mut CallStack<'a>) {
+        while let Some(object) = self.entered_monitors.pop() {
+            //记录的监视器一定被持有，monitor_exit不会失败
+            let _ = vm.monitor_exit(call_stack, &object);
         }
+    }
 
+    fn execute_instructions(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+    ) -> InvokeMethodResult<'a> {
         loop {
-            //记录当前指令的地址，用于实现偏移
-            self.pc = self.byte_buffer.position;
-            let instruction = read_one_instruction(&mut self.byte_buffer)
-                .map_err(|_| MethodCallError::InternalError(VmError::ClassFormatError))?;
+            //预算耗尽或被取消时不经过异常表，直接返回到宿主
+            call_stack.charge_instruction()?;
+            self.current_instruction_pc = self.next_instruction_pc;
+            self.byte_buffer.jump_to(self.current_instruction_pc);
+            let opcode = self.byte_buffer.peek_u8().unwrap_or_default();
+            let instruction = match self.instructions {
+                Some(instructions) => {
+                    let index = instructions
+                        .binary_search_by_key(&self.current_instruction_pc, |(pc, _)| *pc)
+                        .map_err(|_| self.invalid_instruction())?;
+                    self.next_instruction_pc = instructions
+                        .get(index + 1)
+                        .map_or(self.byte_buffer.len(), |(pc, _)| *pc);
+                    instructions[index].1.clone()
+                }
+                None => {
+                    let instruction = read_one_instruction(&mut self.byte_buffer)
+                        .map_err(|_| self.invalid_instruction())?;
+                    self.next_instruction_pc = self.byte_buffer.position;
+                    instruction
+                }
+            };
+            trace!(pc = self.current_instruction_pc, ?instruction, "exec");
+            if self.trace_enabled {
+                let depth = "\t".repeat(call_stack.depth());
+                vm.trace_line(&format!("{}exec {:?}", depth, instruction));
+            }
             let result = self.execute_instruction(vm, call_stack, instruction);
+            if self.trace_snapshots {
+                let depth = "\t".repeat(call_stack.depth());
+                vm.trace_line(&format!(
+                    "{}   stack: {:?} locals: {:?}",
+                    depth, self.op_stack, self.local_var_table
+                ));
+            }
+            if vm.is_recording_execution() {
+                vm.record(ExecutionRecord {
+                    class_name: self.class_ref.name.clone(),
+                    method_name: self.method_ref.name.clone(),
+                    pc: self.current_instruction_pc as u16,
+                    opcode,
+                    top_of_stack: self.op_stack.peek().map(RecordedValue::from),
+                });
+            }
             match result {
                 Ok(ReturnFromMethod(return_value)) => {
                     return Ok(return_value);
                 }
                 Err(MethodCallError::ExceptionThrown(exp_ref)) => {
-                    let catch_exception = self
-                        .exception_tables
-                        .iter()
-                        .find(|t| t.catch_line(self.pc as u16));
+                    //按异常表顺序找第一个范围包含该指令且类型匹配的处理器
+                    let exception_class = exp_ref.get_class();
+                    let catch_exception = self.exception_tables.iter().find(|t| {
+                        t.catch_line(self.current_instruction_pc as u16)
+                            && t.catch_type
+                                .as_ref()
+                                .is_none_or(|name| exception_class.is_subclass_of(name))
+                    });
                     if let Some(table) = catch_exception {
+                        //进入处理器前清空操作数栈，只留下异常对象
+                        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.athrow
+                        self.op_stack.clear();
                         self.push(ObjectRef(exp_ref))?;
-                        self.goto(table.handler_pc as usize);
+                        self.goto(table.handler_pc as usize)?;
                     } else {
+                        //没有匹配的处理器，丢弃操作数栈后传播给调用方
+                        self.op_stack.clear();
+                        vm.capture_stack_trace(call_stack, exp_ref)?;
                         return Err(MethodCallError::ExceptionThrown(exp_ref));
                     }
                 }
+                Err(MethodCallError::InternalError(VmError::StackOverFlow)) => {
+                    return Err(self.operand_stack_overflow());
+                }
                 Err(e) => {
                     return Err(e);
                 }
@@ -1348,18 +1948,21 @@ impl<'a> StackFrame<'a> {
     ) -> InvokeResult<'a, ()> {
         if let RuntimeConstantPoolEntry::InvokeDynamic(
             bootstrap_method_attr_index,
-            method_name,
+            _method_name,
             method_descriptor,
         ) = self.get_constant_pool(cp_index)?
         {
             let method = &self.class_ref.bootstrap_method[*bootstrap_method_attr_index as usize];
+            if method.class_name == STRING_CONCAT_FACTORY {
+                return self.exec_string_concat(vm, call_stack, method, method_descriptor);
+            }
             let bootstrap_class_ref =
-                vm.lookup_class_and_initialize(call_stack, &method.class_name)?;
+                vm.resolve_class(call_stack, self.class_ref, &method.class_name)?;
             let bootstrap_method_ref =
                 bootstrap_class_ref.get_method(&method.method_name, &method.method_descriptor)?;
             let args = Vec::new();
             //调用一个方法去获得实际的方法
-            if let Some(ObjectRef(callsite)) = vm.invoke_method(
+            if let Some(ObjectRef(_callsite)) = vm.invoke_method(
                 call_stack,
                 bootstrap_class_ref,
                 bootstrap_method_ref,
@@ -1373,16 +1976,51 @@ impl<'a> StackFrame<'a> {
         }
     }
 
-    pub fn get_line_number(&self) -> u16 {
-        let code_index = self.pc as u16;
-        let mut current_line_number: u16 = 0;
-        for (start, line_number) in self.line_number_table.iter() {
-            if *start < code_index {
-                current_line_number = *line_number
-            } else {
-                return current_line_number;
-            }
+    //JDK 9+的字符串拼接，不调用引导方法，直接根据recipe生成调用点
+    fn exec_string_concat(
+        &mut self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        bootstrap_method: &BootstrapMethod,
+        descriptor: &str,
+    ) -> InvokeResult<'a, ()> {
+        let mut static_args = Vec::with_capacity(bootstrap_method.args.len());
+        for index in &bootstrap_method.args {
+            static_args.push(match self.get_constant_pool(*index)? {
+                RuntimeConstantPoolEntry::StringReference(value) => value.clone(),
+                RuntimeConstantPoolEntry::Integer(value) => value.to_string(),
+                RuntimeConstantPoolEntry::Long(value) => value.to_string(),
+                other => {
+                    return Err(VmError::LinkageError(format!(
+                        "unsupported string concat constant {}",
+                        other
+                    ))
+                    .into())
+                }
+            });
         }
-        current_line_number
+        let call_site = match (
+            bootstrap_method.method_name.as_str(),
+            static_args.split_first(),
+        ) {
+            ("makeConcatWithConstants", Some((recipe, constants))) => {
+                StringConcatCallSite::new(Some(recipe), constants, descriptor)?
+            }
+            ("makeConcat", _) => StringConcatCallSite::new(None, &[], descriptor)?,
+            (name, _) => {
+                return Err(VmError::LinkageError(format!(
+                    "unsupported bootstrap method {}.{}",
+                    STRING_CONCAT_FACTORY, name
+                ))
+                .into())
+            }
+        };
+        let args = self.op_stack.pop_n(call_site.arg_count())?;
+        let string = call_site.invoke(vm, call_stack, args)?;
+        self.push(ObjectRef(string))
+    }
+
+    pub fn get_line_number(&self) -> u16 {
+        self.code_attr.line_number_at(self.current_instruction_pc)
     }
 }
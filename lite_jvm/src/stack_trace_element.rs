@@ -7,17 +7,13 @@ pub struct StackTraceElement {
     pub file_name: Option<String>,
     pub line_number: u16,
 }
+//和Java中StackTraceElement.toString一致，没有SourceFile属性时输出(Unknown Source)
 impl Display for StackTraceElement {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "\t{}.{}({}:{})",
-            self.declaring_class,
-            self.method_name,
-            self.file_name
-                .as_ref()
-                .unwrap_or(&"<unknown source>".to_string()),
-            self.line_number
-        )
+        write!(f, "\t{}.{}", self.declaring_class, self.method_name)?;
+        match &self.file_name {
+            Some(file_name) => write!(f, "({}:{})", file_name, self.line_number),
+            None => write!(f, "(Unknown Source)"),
+        }
     }
 }
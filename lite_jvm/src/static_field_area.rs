@@ -1,16 +1,25 @@
 use crate::jvm_values::{ObjectReference, Value};
 use crate::loaded_class::ClassRef;
+use crate::method_handle::MethodHandleInfo;
 use crate::object_heap::ObjectHeap;
 use indexmap::IndexMap;
 use std::collections::HashMap;
 
 /// 静态区。用来存储静态属性和字符串
-
 pub(crate) struct StaticArea<'a> {
     fields: HashMap<ClassRef<'a>, IndexMap<String, Value<'a>>>,
     static_object_heap: ObjectHeap<'a>,
     pub(crate) string_constant_pool: HashMap<String, ObjectReference<'a>>,
     pub(crate) class_constant_pool: HashMap<String, ObjectReference<'a>>,
+    //自定义类加载器定义的类可能重名，Class对象按类区分
+    pub(crate) loader_class_objects: HashMap<ClassRef<'a>, ObjectReference<'a>>,
+    //int.class、void.class等基本类型的Class对象，按类型名称缓存
+    pub(crate) primitive_class_objects: HashMap<String, ObjectReference<'a>>,
+    //ldc加载的MethodHandle常量，相同的引用返回同一个对象
+    pub(crate) method_handle_pool: HashMap<MethodHandleInfo, ObjectReference<'a>>,
+    pub(crate) method_handle_infos: HashMap<ObjectReference<'a>, MethodHandleInfo>,
+    //已解析的动态计算常量，按(类, 常量池索引)缓存
+    pub(crate) dynamic_constants: HashMap<(ClassRef<'a>, u16), Value<'a>>,
 }
 impl<'a> StaticArea<'a> {
     pub(crate) fn new(static_heap_size: usize) -> StaticArea<'a> {
@@ -19,9 +28,27 @@ impl<'a> StaticArea<'a> {
             static_object_heap: ObjectHeap::new(static_heap_size),
             string_constant_pool: Default::default(),
             class_constant_pool: Default::default(),
+            loader_class_objects: Default::default(),
+            primitive_class_objects: Default::default(),
+            method_handle_pool: Default::default(),
+            method_handle_infos: Default::default(),
+            dynamic_constants: Default::default(),
         }
     }
 
+    //清空静态字段以及字符串、Class对象等常量池，分配在静态区的对象一起丢弃
+    pub(crate) fn reset(&mut self) {
+        self.fields.clear();
+        self.static_object_heap.reset();
+        self.string_constant_pool.clear();
+        self.class_constant_pool.clear();
+        self.loader_class_objects.clear();
+        self.primitive_class_objects.clear();
+        self.method_handle_pool.clear();
+        self.method_handle_infos.clear();
+        self.dynamic_constants.clear();
+    }
+
     pub fn new_object(&mut self, class_ref: ClassRef) -> ObjectReference<'a> {
         self.static_object_heap.allocate_object(class_ref).unwrap()
     }
@@ -35,6 +62,36 @@ impl<'a> StaticArea<'a> {
         map.get(field_name)
     }
 
+    /// 静态区中的引用值，作为垃圾回收的根扫描。
+    /// 静态字段可以引用分配在对象堆上的对象和数组(例如<clinit>中创建的数组)，
+    /// 字符串常量、Class对象和动态计算常量则可能分配在静态区自己的堆上
+    pub(crate) fn iter_reference_values(&self) -> impl Iterator<Item = Value<'a>> + '_ {
+        let fields = self.fields.values().flat_map(|map| map.values().cloned());
+        let objects = self
+            .string_constant_pool
+            .values()
+            .chain(self.class_constant_pool.values())
+            .chain(self.loader_class_objects.values())
+            .chain(self.primitive_class_objects.values())
+            .chain(self.method_handle_pool.values())
+            .map(|object_ref| Value::ObjectRef(*object_ref));
+        fields
+            .chain(objects)
+            .chain(self.dynamic_constants.values().cloned())
+            .filter(|value| matches!(value, Value::ObjectRef(_) | Value::ArrayRef(_)))
+    }
+
+    pub(crate) fn get_static_fields(
+        &self,
+        class_ref: ClassRef<'a>,
+    ) -> Option<&IndexMap<String, Value<'a>>> {
+        self.fields.get(class_ref)
+    }
+
+    pub(crate) fn static_classes(&self) -> impl Iterator<Item = ClassRef<'a>> + '_ {
+        self.fields.keys().copied()
+    }
+
     pub(crate) fn set_static_field(
         &mut self,
         class_ref: ClassRef<'a>,
@@ -0,0 +1,183 @@
+//! String和byte[]之间的转换。JDK通过StringCoding按名称查找Charset，依赖sun.nio.cs中的编码器实现，
+//! 这里只支持UTF-8、ISO-8859-1和US-ASCII，直接按UTF-16内容转换，其他编码抛出UnsupportedEncodingException。
+//! 替换规则和JDK一致：解码时无法映射的字节替换为U+FFFD，编码时无法映射的字符替换为'?'
+use crate::java_exception::{InvokeMethodResult, MethodCallError};
+use crate::jvm_values::{ArrayElement, ArrayReference, PrimaryType, ReferenceValue, Value};
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Charset {
+    Utf8,
+    Latin1,
+    Ascii,
+}
+
+impl Charset {
+    /// 按名称查找，不区分大小写，支持java.nio.charset中的常用别名
+    pub fn for_name(name: &str) -> Option<Charset> {
+        match name.to_ascii_uppercase().as_str() {
+            "UTF-8" | "UTF8" => Some(Charset::Utf8),
+            "ISO-8859-1" | "ISO8859-1" | "ISO8859_1" | "ISO_8859_1" | "LATIN1" | "8859_1" => {
+                Some(Charset::Latin1)
+            }
+            "US-ASCII" | "ASCII" | "US_ASCII" | "ASCII7" => Some(Charset::Ascii),
+            _ => None,
+        }
+    }
+
+    pub fn decode(self, bytes: &[u8]) -> Vec<u16> {
+        match self {
+            Charset::Utf8 => String::from_utf8_lossy(bytes).encode_utf16().collect(),
+            Charset::Latin1 => bytes.iter().map(|b| *b as u16).collect(),
+            Charset::Ascii => bytes
+                .iter()
+                .map(|b| if b.is_ascii() { *b as u16 } else { 0xfffd })
+                .collect(),
+        }
+    }
+
+    //不成对的代理也按无法映射的字符处理，补充平面字符在单字节编码中只替换为一个'?'
+    pub fn encode(self, chars: &[u16]) -> Vec<u8> {
+        let chars = char::decode_utf16(chars.iter().copied()).map(|c| c.unwrap_or('?'));
+        match self {
+            Charset::Utf8 => chars.collect::<String>().into_bytes(),
+            Charset::Latin1 => chars
+                .map(|c| u8::try_from(c as u32).unwrap_or(b'?'))
+                .collect(),
+            Charset::Ascii => chars
+                .map(|c| if c.is_ascii() { c as u8 } else { b'?' })
+                .collect(),
+        }
+    }
+}
+
+//没有指定编码时使用的默认编码，相当于file.encoding=UTF-8
+fn charset_argument<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    charset_name: Option<&Value<'a>>,
+) -> Result<Charset, MethodCallError<'a>> {
+    let name = match charset_name {
+        None => return Ok(Charset::Utf8),
+        Some(Value::Null) => {
+            let exception =
+                vm.new_exception(call_stack, "java/lang/NullPointerException", "charsetName")?;
+            return Err(MethodCallError::ExceptionThrown(exception));
+        }
+        Some(value) => value.get_string()?,
+    };
+    match Charset::for_name(&name) {
+        Some(charset) => Ok(charset),
+        None => {
+            let exception =
+                vm.new_exception(call_stack, "java/io/UnsupportedEncodingException", &name)?;
+            Err(MethodCallError::ExceptionThrown(exception))
+        }
+    }
+}
+
+fn byte_array_argument<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    bytes: &Value<'a>,
+) -> Result<ArrayReference<'a>, MethodCallError<'a>> {
+    match bytes {
+        Value::ArrayRef(array_ref) => Ok(*array_ref),
+        _ => {
+            let exception =
+                vm.new_object_by_class_name(call_stack, "java/lang/NullPointerException")?;
+            Err(MethodCallError::ExceptionThrown(exception))
+        }
+    }
+}
+
+/// String(byte[])、String(byte[], String)、String(byte[], int, int)和String(byte[], int, int, String)，
+/// 参数个数区分是否有offset/length和编码名称
+pub(crate) fn java_lang_string_init_bytes<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: Option<Value<'a>>,
+    args: Vec<Value<'a>>,
+) -> InvokeMethodResult<'a> {
+    let string = receiver.unwrap().get_object()?;
+    let array_ref = byte_array_argument(vm, call_stack, &args[0])?;
+    let (range, charset_name) = match args.len() {
+        1 | 2 => (None, args.get(1)),
+        _ => (Some((args[1].get_int()?, args[2].get_int()?)), args.get(3)),
+    };
+    let charset = charset_argument(vm, call_stack, charset_name)?;
+    let bytes = array_ref.read_bytes()?;
+    let bytes = match range {
+        None => &bytes[..],
+        Some((offset, length)) => {
+            //和String.checkBounds一致
+            if offset < 0 || length < 0 || offset as i64 + length as i64 > bytes.len() as i64 {
+                let exception = vm.new_exception(
+                    call_stack,
+                    "java/lang/StringIndexOutOfBoundsException",
+                    &format!(
+                        "offset {}, count {}, length {}",
+                        offset,
+                        length,
+                        bytes.len()
+                    ),
+                )?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+            &bytes[offset as usize..(offset + length) as usize]
+        }
+    };
+    let chars = charset.decode(bytes);
+    let value = vm.new_array(
+        call_stack,
+        ArrayElement::PrimaryValue(PrimaryType::Char),
+        chars.len(),
+    )?;
+    value.write_chars(&chars)?;
+    string.set_field_by_name("value", &Value::ArrayRef(value))?;
+    string.set_field_by_name("hash", &Value::Int(0))?;
+    Ok(None)
+}
+
+/// String.getBytes()和String.getBytes(String)
+pub(crate) fn java_lang_string_get_bytes<'a>(
+    vm: &mut VirtualMachine<'a>,
+    call_stack: &mut CallStack<'a>,
+    receiver: Option<Value<'a>>,
+    args: Vec<Value<'a>>,
+) -> InvokeMethodResult<'a> {
+    let string = receiver.unwrap().get_object()?;
+    let charset = charset_argument(vm, call_stack, args.first())?;
+    let bytes = charset.encode(&vm.get_string_chars(string)?);
+    let array_ref = vm.new_array(
+        call_stack,
+        ArrayElement::PrimaryValue(PrimaryType::Byte),
+        bytes.len(),
+    )?;
+    array_ref.write_bytes(&bytes)?;
+    Ok(Some(Value::ArrayRef(array_ref)))
+}
+
+mod tests {
+    #[test]
+    fn test_charset_conversion() {
+        use crate::string_coding::Charset;
+        assert_eq!(Charset::for_name("utf-8"), Some(Charset::Utf8));
+        assert_eq!(Charset::for_name("ISO8859_1"), Some(Charset::Latin1));
+        assert_eq!(Charset::for_name("US-ASCII"), Some(Charset::Ascii));
+        assert_eq!(Charset::for_name("EBCDIC"), None);
+
+        let text: Vec<u16> = "héllo 😀".encode_utf16().collect();
+        let utf8 = Charset::Utf8.encode(&text);
+        assert_eq!(utf8, "héllo 😀".as_bytes());
+        assert_eq!(Charset::Utf8.decode(&utf8), text);
+        assert_eq!(Charset::Latin1.encode(&text), b"h\xe9llo ?");
+        assert_eq!(Charset::Ascii.encode(&text), b"h?llo ?");
+        //不成对的代理编码为'?'，非法的UTF-8字节解码为U+FFFD
+        assert_eq!(Charset::Utf8.encode(&[0x61, 0xd83d]), b"a?");
+        assert_eq!(Charset::Utf8.decode(b"a\xffb"), vec![0x61, 0xfffd, 0x62]);
+        assert_eq!(Charset::Ascii.decode(b"a\xe9"), vec![0x61, 0xfffd]);
+        assert_eq!(Charset::Latin1.decode(b"a\xe9"), vec![0x61, 0xe9]);
+    }
+}
@@ -0,0 +1,179 @@
+//! JDK 9之后javac把字符串拼接编译为invokedynamic，引导方法是StringConcatFactory。
+//! 这里不构造MethodHandle组合，而是解析recipe直接生成调用点，调用时按String.valueOf的规则转换参数后拼接。
+//! https://docs.oracle.com/javase/9/docs/api/java/lang/invoke/StringConcatFactory.html
+use crate::java_exception::MethodCallError;
+use crate::jvm_error::{VmError, VmExecResult};
+use crate::jvm_values::{ObjectReference, PrimaryType, Value, ValueType};
+use crate::runtime_method_info::MethodDescriptor;
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+
+pub(crate) const STRING_CONCAT_FACTORY: &str = "java/lang/invoke/StringConcatFactory";
+
+//recipe中的参数占位符
+const TAG_ARG: char = '\u{1}';
+//recipe中的常量占位符，依次使用引导方法的其余静态参数
+const TAG_CONST: char = '\u{2}';
+
+#[derive(Debug, PartialEq)]
+pub(crate) enum RecipeElement {
+    Constant(String),
+    //调用点参数的下标
+    Argument(usize),
+}
+
+pub(crate) struct StringConcatCallSite {
+    elements: Vec<RecipeElement>,
+    arg_types: Vec<ValueType>,
+}
+
+impl StringConcatCallSite {
+    /// makeConcatWithConstants的recipe来自第一个静态参数，其余静态参数是常量；
+    /// makeConcat没有recipe，所有参数依次拼接
+    pub(crate) fn new(
+        recipe: Option<&str>,
+        constants: &[String],
+        descriptor: &str,
+    ) -> VmExecResult<StringConcatCallSite> {
+        let arg_types = MethodDescriptor::new(descriptor)?.args;
+        let elements = match recipe {
+            Some(recipe) => Self::parse_recipe(recipe, constants, arg_types.len())?,
+            None => (0..arg_types.len()).map(RecipeElement::Argument).collect(),
+        };
+        Ok(StringConcatCallSite {
+            elements,
+            arg_types,
+        })
+    }
+
+    pub(crate) fn arg_count(&self) -> usize {
+        self.arg_types.len()
+    }
+
+    //相邻的字面量和常量合并为一个Constant
+    pub(crate) fn parse_recipe(
+        recipe: &str,
+        constants: &[String],
+        arg_count: usize,
+    ) -> VmExecResult<Vec<RecipeElement>> {
+        let mut elements = Vec::new();
+        let mut literal = String::new();
+        let mut next_arg = 0;
+        let mut constants = constants.iter();
+        for c in recipe.chars() {
+            match c {
+                TAG_ARG => {
+                    if !literal.is_empty() {
+                        elements.push(RecipeElement::Constant(std::mem::take(&mut literal)));
+                    }
+                    elements.push(RecipeElement::Argument(next_arg));
+                    next_arg += 1;
+                }
+                TAG_CONST => {
+                    let constant = constants.next().ok_or_else(|| {
+                        VmError::LinkageError(format!(
+                            "string concat recipe {:?} uses more constants than provided",
+                            recipe
+                        ))
+                    })?;
+                    literal.push_str(constant);
+                }
+                _ => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            elements.push(RecipeElement::Constant(literal));
+        }
+        if next_arg != arg_count || constants.next().is_some() {
+            return Err(VmError::LinkageError(format!(
+                "string concat recipe {:?} does not match {} arguments",
+                recipe, arg_count
+            )));
+        }
+        Ok(elements)
+    }
+
+    //结果和javac生成的StringBuilder拼接一样是新建的String，不放入常量池。
+    //按UTF-16代码单元拼接，分别传入的高低代理可以组成一个补充平面字符
+    pub(crate) fn invoke<'a>(
+        &self,
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        args: Vec<Value<'a>>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let mut result: Vec<u16> = Vec::new();
+        for element in &self.elements {
+            match element {
+                RecipeElement::Constant(constant) => result.extend(constant.encode_utf16()),
+                RecipeElement::Argument(index) => {
+                    let value = args[*index].clone();
+                    let chars = Self::to_chars(vm, call_stack, &self.arg_types[*index], value)?;
+                    result.extend(chars);
+                }
+            }
+        }
+        vm.new_java_lang_string_from_chars(call_stack, &result)
+    }
+
+    //和String.valueOf一致，浮点数和对象交给Java代码处理，格式与Double.toString、toString()相同
+    fn to_chars<'a>(
+        vm: &mut VirtualMachine<'a>,
+        call_stack: &mut CallStack<'a>,
+        arg_type: &ValueType,
+        value: Value<'a>,
+    ) -> Result<Vec<u16>, MethodCallError<'a>> {
+        let value_of = match (arg_type, &value) {
+            (_, Value::Null) => return Ok("null".encode_utf16().collect()),
+            (ValueType::Primary(PrimaryType::Boolean), _) => {
+                return Ok((value.get_int()? != 0).to_string().encode_utf16().collect())
+            }
+            (ValueType::Primary(PrimaryType::Char), _) => return Ok(vec![value.get_int()? as u16]),
+            (ValueType::Primary(PrimaryType::Byte | PrimaryType::Short | PrimaryType::Int), _) => {
+                return Ok(value.get_int()?.to_string().encode_utf16().collect())
+            }
+            (ValueType::Primary(PrimaryType::Long), _) => {
+                return Ok(value.get_long()?.to_string().encode_utf16().collect())
+            }
+            (ValueType::Object(class_name), _) if class_name == "java/lang/String" => {
+                return Ok(vm.get_string_chars(value.get_object()?)?)
+            }
+            (ValueType::Primary(PrimaryType::Float), _) => "(F)Ljava/lang/String;",
+            (ValueType::Primary(PrimaryType::Double), _) => "(D)Ljava/lang/String;",
+            _ => "(Ljava/lang/Object;)Ljava/lang/String;",
+        };
+        let string = vm.call(
+            call_stack,
+            "java/lang/String",
+            "valueOf",
+            value_of,
+            None,
+            vec![value],
+        )?;
+        let string = string.ok_or(VmError::ValueTypeMissMatch)?.get_object()?;
+        Ok(vm.get_string_chars(string)?)
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_parse_recipe() {
+        use crate::string_concat::{RecipeElement, StringConcatCallSite};
+        let elements =
+            StringConcatCallSite::parse_recipe("a\u{1}\u{1}-\u{2}\u{1}", &["c".to_string()], 3)
+                .unwrap();
+        assert_eq!(
+            elements,
+            vec![
+                RecipeElement::Constant("a".to_string()),
+                RecipeElement::Argument(0),
+                RecipeElement::Argument(1),
+                RecipeElement::Constant("-c".to_string()),
+                RecipeElement::Argument(2),
+            ]
+        );
+        //参数个数和描述符不一致
+        assert!(StringConcatCallSite::parse_recipe("\u{1}", &[], 2).is_err());
+        //常量不够
+        assert!(StringConcatCallSite::parse_recipe("\u{2}", &[], 0).is_err());
+    }
+}
@@ -0,0 +1,165 @@
+//! 不依赖JUnit的测试运行器：执行类中所有名称以test开头的静态无参方法。
+//! 测试代码可以调用内置的lite.jvm.Assert，断言失败时抛出java.lang.AssertionError
+use crate::java_exception::MethodCallError;
+use crate::jvm_values::ObjectReference;
+use crate::stack::CallStack;
+use crate::virtual_machine::VirtualMachine;
+use std::fmt::{Display, Formatter};
+use std::time::{Duration, Instant};
+
+/// 单个测试方法的执行结果，failure是失败原因，抛出的异常包含调用栈
+#[derive(Debug, Clone)]
+pub struct TestResult {
+    pub method_name: String,
+    pub failure: Option<String>,
+    pub elapsed: Duration,
+}
+
+impl TestResult {
+    pub fn is_passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// 测试类的执行结果，按方法在类文件中声明的顺序排列
+#[derive(Debug, Clone)]
+pub struct TestReport {
+    pub class_name: String,
+    pub results: Vec<TestResult>,
+}
+
+impl TestReport {
+    pub fn passed(&self) -> usize {
+        self.results.iter().filter(|r| r.is_passed()).count()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.results.len() - self.passed()
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed() == 0
+    }
+
+    pub fn get(&self, method_name: &str) -> Option<&TestResult> {
+        self.results.iter().find(|r| r.method_name == method_name)
+    }
+}
+
+//输出格式：
+//AssertTest: 3 tests, 2 passed, 1 failed
+//  PASS testAdd (12µs)
+//  FAIL testBroken (30µs)
+//    java.lang.AssertionError: expected:<3> but was:<4>
+//    	AssertTest.testBroken(AssertTest.java:14)
+impl Display for TestReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: {} tests, {} passed, {} failed",
+            self.class_name.replace('/', "."),
+            self.results.len(),
+            self.passed(),
+            self.failed()
+        )?;
+        for result in &self.results {
+            let status = if result.is_passed() { "PASS" } else { "FAIL" };
+            write!(
+                f,
+                "\n  {} {} ({:?})",
+                status, result.method_name, result.elapsed
+            )?;
+            if let Some(failure) = &result.failure {
+                for line in failure.lines() {
+                    write!(f, "\n    {}", line)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> VirtualMachine<'a> {
+    /// 初始化类，依次执行其中名称以test开头、描述符为()V的静态方法。
+    /// 测试方法抛出异常或者执行出错都记为失败，不影响后面的测试方法。类加载或初始化失败时返回错误
+    pub fn run_test_class(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+    ) -> Result<TestReport, MethodCallError<'a>> {
+        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
+        let test_methods = class_ref
+            .methods
+            .values()
+            .filter(|m| m.is_static() && m.descriptor == "()V" && m.name.starts_with("test"));
+        let mut results = Vec::new();
+        for method_ref in test_methods {
+            let started_at = Instant::now();
+            let result = self.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            );
+            let failure = match result {
+                Ok(_) => None,
+                Err(e) => Some(self.format_error(e)),
+            };
+            results.push(TestResult {
+                method_name: method_ref.name.clone(),
+                failure,
+                elapsed: started_at.elapsed(),
+            });
+        }
+        Ok(TestReport {
+            class_name: class_ref.name.clone(),
+            results,
+        })
+    }
+
+    pub(crate) fn format_error(&self, error: MethodCallError<'a>) -> String {
+        match error {
+            MethodCallError::ExceptionThrown(exception) => self.format_exception(exception),
+            MethodCallError::InternalError(error) => format!("internal error: {}", error),
+            MethodCallError::BudgetExceeded => "exceeded instruction budget".to_string(),
+            MethodCallError::Cancelled => "cancelled".to_string(),
+            MethodCallError::VmExit(status) => format!("exited with status {}", status),
+        }
+    }
+}
+
+mod tests {
+    #[test]
+    fn test_run_test_class() {
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let report = vm.run_test_class(call_stack, "SelfTest").unwrap();
+        //helper的描述符不是()V，testInstance不是静态方法
+        let names: Vec<&str> = report
+            .results
+            .iter()
+            .map(|r| r.method_name.as_str())
+            .collect();
+        assert_eq!(names, vec!["testAdd", "testStrings", "testBroken"]);
+        assert_eq!(report.passed(), 2);
+        assert_eq!(report.failed(), 1);
+        assert!(!report.is_success());
+        assert!(report.get("testAdd").unwrap().is_passed());
+        assert!(report.get("testStrings").unwrap().is_passed());
+        let failure = report.get("testBroken").unwrap().failure.as_deref();
+        assert_eq!(
+            failure,
+            Some(
+                "java.lang.AssertionError: expected:<3> but was:<4>\n\
+                 \tSelfTest.testBroken(SelfTest.java:17)"
+            )
+        );
+        let summary = report.to_string();
+        assert!(summary.starts_with("SelfTest: 3 tests, 2 passed, 1 failed\n  PASS testAdd ("));
+        assert!(summary.contains("\n  FAIL testBroken ("));
+        assert!(summary.contains("\n    java.lang.AssertionError: expected:<3> but was:<4>"));
+        assert_eq!(call_stack.depth(), 0);
+    }
+}
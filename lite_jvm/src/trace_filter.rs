@@ -0,0 +1,162 @@
+use log::{log, Level};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// 执行追踪的过滤器。
+///
+/// 模式格式为`类名::方法名`，两部分都支持`*`通配，例如`FieldTest::*`、`java/lang/String::hashCode`。
+/// 只写类名时匹配该类的所有方法。类名中的`.`会被视为`/`。
+#[derive(Debug, Clone, Default)]
+pub struct TraceFilter {
+    patterns: Vec<MethodPattern>,
+    include_snapshots: bool,
+}
+
+impl TraceFilter {
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> TraceFilter {
+        TraceFilter {
+            patterns: patterns
+                .iter()
+                .map(|p| MethodPattern::parse(p.as_ref()))
+                .collect(),
+            include_snapshots: false,
+        }
+    }
+
+    //匹配的方法每条指令执行后输出操作数栈和局部变量表
+    pub fn with_snapshots(mut self, include_snapshots: bool) -> TraceFilter {
+        self.include_snapshots = include_snapshots;
+        self
+    }
+
+    pub fn include_snapshots(&self) -> bool {
+        self.include_snapshots
+    }
+
+    pub fn matches(&self, class_name: &str, method_name: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| pattern.matches(class_name, method_name))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MethodPattern {
+    class_pattern: String,
+    method_pattern: String,
+}
+
+impl MethodPattern {
+    fn parse(pattern: &str) -> MethodPattern {
+        let (class_pattern, method_pattern) = match pattern.split_once("::") {
+            Some((class_pattern, method_pattern)) => (class_pattern, method_pattern),
+            None => (pattern, "*"),
+        };
+        MethodPattern {
+            class_pattern: class_pattern.replace('.', "/"),
+            method_pattern: method_pattern.to_string(),
+        }
+    }
+
+    fn matches(&self, class_name: &str, method_name: &str) -> bool {
+        glob_matches(&self.class_pattern, class_name)
+            && glob_matches(&self.method_pattern, method_name)
+    }
+}
+
+//只支持`*`通配任意长度字符
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+    let parts: Vec<&str> = parts.collect();
+    let Some((last, middle)) = parts.split_last() else {
+        return rest.is_empty();
+    };
+    for part in middle {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+/// 追踪输出的去向，默认通过`log`的trace级别输出
+pub trait TraceSink {
+    fn write_line(&mut self, line: &str);
+
+    //VirtualMachine::set_trace_level调用，不按log级别输出的实现忽略
+    fn set_level(&mut self, _level: Level) {}
+}
+
+#[derive(Debug)]
+pub struct LogTraceSink {
+    level: Level,
+}
+
+impl LogTraceSink {
+    pub fn new(level: Level) -> LogTraceSink {
+        LogTraceSink { level }
+    }
+}
+
+impl Default for LogTraceSink {
+    fn default() -> Self {
+        LogTraceSink::new(Level::Trace)
+    }
+}
+
+impl TraceSink for LogTraceSink {
+    fn write_line(&mut self, line: &str) {
+        log!(self.level, "{}", line);
+    }
+
+    fn set_level(&mut self, level: Level) {
+        self.level = level;
+    }
+}
+
+/// 收集追踪输出，用于测试或者嵌入方自行处理
+#[derive(Debug, Default, Clone)]
+pub struct CollectingTraceSink {
+    lines: Rc<RefCell<Vec<String>>>,
+}
+
+impl CollectingTraceSink {
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+}
+
+impl TraceSink for CollectingTraceSink {
+    fn write_line(&mut self, line: &str) {
+        self.lines.borrow_mut().push(line.to_string());
+    }
+}
+
+mod tests {
+
+    #[test]
+    fn test_trace_filter_matches() {
+        use crate::trace_filter::{glob_matches, TraceFilter};
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("hash*", "hashCode"));
+        assert!(glob_matches("*Code", "hashCode"));
+        assert!(glob_matches("h*C*e", "hashCode"));
+        assert!(!glob_matches("hash", "hashCode"));
+        assert!(!glob_matches("*Codes", "hashCode"));
+
+        let filter = TraceFilter::new(&["FieldTest::*", "java.lang.String::hashCode"]);
+        assert!(filter.matches("FieldTest", "<clinit>"));
+        assert!(filter.matches("java/lang/String", "hashCode"));
+        assert!(!filter.matches("java/lang/String", "equals"));
+        assert!(!filter.matches("FieldTest2", "increaseInt"));
+
+        let filter = TraceFilter::new(&["java/util/*"]);
+        assert!(filter.matches("java/util/ArrayList", "add"));
+        assert!(!filter.matches("java/lang/Object", "<init>"));
+    }
+}
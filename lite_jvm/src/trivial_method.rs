@@ -0,0 +1,494 @@
+//! 识别只有几条指令的简单方法：返回常量、返回参数、读取或写入this的字段。
+//! 调用这些方法时不创建栈帧，直接对接收者和参数执行。
+//! 识别是保守的，出现其他指令、异常表或者synchronized时都按正常流程解释执行；
+//! 执行时值的类型和预期不一致也回到正常流程，由解释器给出同样的错误。
+use crate::jvm_values::{ObjectReference, PrimaryType, Value, ValueType};
+use crate::runtime_attribute_info::CodeAttribute;
+use crate::runtime_constant_pool::RuntimeConstantPool;
+use class_file_reader::cesu8_byte_buffer::ByteBuffer;
+use class_file_reader::instruction::{read_one_instruction, Instruction};
+use class_file_reader::method_info::MethodAccessFlags;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum ValueKind {
+    Int,
+    Long,
+    Float,
+    Double,
+    Reference,
+}
+
+impl ValueKind {
+    fn matches(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (ValueKind::Int, Value::Int(_))
+                | (ValueKind::Long, Value::Long(_))
+                | (ValueKind::Float, Value::Float(_))
+                | (ValueKind::Double, Value::Double(_))
+                | (
+                    ValueKind::Reference,
+                    Value::ObjectRef(_) | Value::ArrayRef(_) | Value::Null
+                )
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum TrivialConstant {
+    Int(i32),
+    Long(i64),
+    Float(f32),
+    Double(f64),
+    Null,
+}
+
+impl TrivialConstant {
+    fn to_value<'a>(self) -> Value<'a> {
+        match self {
+            TrivialConstant::Int(i) => Value::Int(i),
+            TrivialConstant::Long(l) => Value::Long(l),
+            TrivialConstant::Float(f) => Value::Float(f),
+            TrivialConstant::Double(d) => Value::Double(d),
+            TrivialConstant::Null => Value::Null,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TrivialMethod {
+    //return
+    ReturnVoid,
+    //xconst_n/bipush/sipush/aconst_null; xreturn
+    ReturnConstant(TrivialConstant),
+    //xload_n; xreturn，index是参数在[接收者, 参数...]中的位置
+    ReturnLocal {
+        index: usize,
+        kind: ValueKind,
+    },
+    //aload_0; getfield; xreturn
+    GetField {
+        class_name: String,
+        field_name: String,
+        descriptor: String,
+        kind: ValueKind,
+    },
+    //aload_0; xload_1; putfield; return
+    SetField {
+        class_name: String,
+        field_name: String,
+        descriptor: String,
+    },
+}
+
+impl TrivialMethod {
+    pub(crate) fn classify(
+        access_flags: &MethodAccessFlags,
+        args: &[ValueType],
+        code: &CodeAttribute,
+        cp: &RuntimeConstantPool,
+    ) -> Option<TrivialMethod> {
+        if access_flags.contains(MethodAccessFlags::SYNCHRONIZED)
+            || !code.exception_table.is_empty()
+            || code.code.len() > 8
+        {
+            return None;
+        }
+        let is_static = access_flags.contains(MethodAccessFlags::STATIC);
+        let mut buffer = ByteBuffer::new(&code.code);
+        let mut instructions = Vec::with_capacity(4);
+        while buffer.has_more_data() {
+            if instructions.len() == 4 {
+                return None;
+            }
+            instructions.push(read_one_instruction(&mut buffer).ok()?);
+        }
+        match instructions.as_slice() {
+            [Instruction::Return] => Some(TrivialMethod::ReturnVoid),
+            [load, ret] => {
+                let ret_kind = return_kind(ret)?;
+                if let Some((constant, kind)) = constant(load) {
+                    return (kind == ret_kind).then_some(TrivialMethod::ReturnConstant(constant));
+                }
+                let (slot, kind) = load_slot(load)?;
+                if kind != ret_kind {
+                    return None;
+                }
+                let index = local_index(is_static, args, slot)?;
+                Some(TrivialMethod::ReturnLocal { index, kind })
+            }
+            //this只会出现在实例方法中，不会是null
+            [Instruction::Aload_0, Instruction::Getfield(field_index), ret] if !is_static => {
+                let (class_name, field_name, descriptor) = cp.get_field_name(*field_index).ok()?;
+                Some(TrivialMethod::GetField {
+                    class_name: class_name.to_string(),
+                    field_name: field_name.to_string(),
+                    descriptor: descriptor.to_string(),
+                    kind: return_kind(ret)?,
+                })
+            }
+            [Instruction::Aload_0, load, Instruction::Putfield(field_index), Instruction::Return]
+                if !is_static && load_slot(load)?.0 == 1 =>
+            {
+                let (class_name, field_name, descriptor) = cp.get_field_name(*field_index).ok()?;
+                Some(TrivialMethod::SetField {
+                    class_name: class_name.to_string(),
+                    field_name: field_name.to_string(),
+                    descriptor: descriptor.to_string(),
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 返回None表示需要按正常流程执行，Some(None)表示void方法
+    pub(crate) fn invoke<'a>(
+        &self,
+        receiver: Option<Value<'a>>,
+        args: &[Value<'a>],
+    ) -> Option<Option<Value<'a>>> {
+        match self {
+            TrivialMethod::ReturnVoid => Some(None),
+            TrivialMethod::ReturnConstant(constant) => Some(Some(constant.to_value())),
+            TrivialMethod::ReturnLocal { index, kind } => {
+                let value = match receiver {
+                    Some(receiver) if *index == 0 => receiver,
+                    Some(_) => args.get(index - 1)?.clone(),
+                    None => args.get(*index)?.clone(),
+                };
+                kind.matches(&value).then_some(Some(value))
+            }
+            TrivialMethod::GetField {
+                class_name,
+                field_name,
+                descriptor,
+                kind,
+            } => {
+                let object_ref = this_object(receiver, class_name)?;
+                let value = object_ref.get_field_in_class(class_name, field_name).ok()?;
+                (value.matches_descriptor(descriptor) && kind.matches(&value))
+                    .then_some(Some(value))
+            }
+            TrivialMethod::SetField {
+                class_name,
+                field_name,
+                descriptor,
+            } => {
+                let object_ref = this_object(receiver, class_name)?;
+                let value = args.first()?;
+                if !value.matches_descriptor(descriptor) {
+                    return None;
+                }
+                object_ref
+                    .set_field_in_class(class_name, field_name, value)
+                    .ok()?;
+                Some(None)
+            }
+        }
+    }
+}
+
+fn this_object<'a>(receiver: Option<Value<'a>>, class_name: &str) -> Option<ObjectReference<'a>> {
+    match receiver {
+        Some(Value::ObjectRef(object_ref)) if object_ref.get_class().is_subclass_of(class_name) => {
+            Some(object_ref)
+        }
+        _ => None,
+    }
+}
+
+fn return_kind(instruction: &Instruction) -> Option<ValueKind> {
+    match instruction {
+        Instruction::Ireturn => Some(ValueKind::Int),
+        Instruction::Lreturn => Some(ValueKind::Long),
+        Instruction::Freturn => Some(ValueKind::Float),
+        Instruction::Dreturn => Some(ValueKind::Double),
+        Instruction::Areturn => Some(ValueKind::Reference),
+        _ => None,
+    }
+}
+
+fn constant(instruction: &Instruction) -> Option<(TrivialConstant, ValueKind)> {
+    let constant = match instruction {
+        Instruction::Iconst_m1 => TrivialConstant::Int(-1),
+        Instruction::Iconst_0 => TrivialConstant::Int(0),
+        Instruction::Iconst_1 => TrivialConstant::Int(1),
+        Instruction::Iconst_2 => TrivialConstant::Int(2),
+        Instruction::Iconst_3 => TrivialConstant::Int(3),
+        Instruction::Iconst_4 => TrivialConstant::Int(4),
+        Instruction::Iconst_5 => TrivialConstant::Int(5),
+        Instruction::Bipush(byte_value) => TrivialConstant::Int(*byte_value as i8 as i32),
+        Instruction::Sipush(value) => TrivialConstant::Int(*value as i32),
+        Instruction::Lconst_0 => TrivialConstant::Long(0),
+        Instruction::Lconst_1 => TrivialConstant::Long(1),
+        Instruction::Fconst_0 => TrivialConstant::Float(0.0),
+        Instruction::Fconst_1 => TrivialConstant::Float(1.0),
+        Instruction::Fconst_2 => TrivialConstant::Float(2.0),
+        Instruction::Dconst_0 => TrivialConstant::Double(0.0),
+        Instruction::Dconst_1 => TrivialConstant::Double(1.0),
+        Instruction::Aconst_null => TrivialConstant::Null,
+        _ => return None,
+    };
+    let kind = match constant {
+        TrivialConstant::Int(_) => ValueKind::Int,
+        TrivialConstant::Long(_) => ValueKind::Long,
+        TrivialConstant::Float(_) => ValueKind::Float,
+        TrivialConstant::Double(_) => ValueKind::Double,
+        TrivialConstant::Null => ValueKind::Reference,
+    };
+    Some((constant, kind))
+}
+
+//局部变量表的槽位以及读取的类型
+fn load_slot(instruction: &Instruction) -> Option<(usize, ValueKind)> {
+    let (slot, kind) = match instruction {
+        Instruction::Iload(n) => (u16::from(*n), ValueKind::Int),
+        Instruction::IloadWide(n) => (*n, ValueKind::Int),
+        Instruction::Iload_0 => (0, ValueKind::Int),
+        Instruction::Iload_1 => (1, ValueKind::Int),
+        Instruction::Iload_2 => (2, ValueKind::Int),
+        Instruction::Iload_3 => (3, ValueKind::Int),
+        Instruction::Lload(n) => (u16::from(*n), ValueKind::Long),
+        Instruction::LloadWide(n) => (*n, ValueKind::Long),
+        Instruction::Lload_0 => (0, ValueKind::Long),
+        Instruction::Lload_1 => (1, ValueKind::Long),
+        Instruction::Lload_2 => (2, ValueKind::Long),
+        Instruction::Lload_3 => (3, ValueKind::Long),
+        Instruction::Fload(n) => (u16::from(*n), ValueKind::Float),
+        Instruction::FloadWide(n) => (*n, ValueKind::Float),
+        Instruction::Fload_0 => (0, ValueKind::Float),
+        Instruction::Fload_1 => (1, ValueKind::Float),
+        Instruction::Fload_2 => (2, ValueKind::Float),
+        Instruction::Fload_3 => (3, ValueKind::Float),
+        Instruction::Dload(n) => (u16::from(*n), ValueKind::Double),
+        Instruction::DloadWide(n) => (*n, ValueKind::Double),
+        Instruction::Dload_0 => (0, ValueKind::Double),
+        Instruction::Dload_1 => (1, ValueKind::Double),
+        Instruction::Dload_2 => (2, ValueKind::Double),
+        Instruction::Dload_3 => (3, ValueKind::Double),
+        Instruction::Aload(n) => (u16::from(*n), ValueKind::Reference),
+        Instruction::AloadWide(n) => (*n, ValueKind::Reference),
+        Instruction::Aload_0 => (0, ValueKind::Reference),
+        Instruction::Aload_1 => (1, ValueKind::Reference),
+        Instruction::Aload_2 => (2, ValueKind::Reference),
+        Instruction::Aload_3 => (3, ValueKind::Reference),
+        _ => return None,
+    };
+    Some((slot as usize, kind))
+}
+
+//槽位以字为单位，long和double占两个槽位；参数只占一个元素
+fn local_index(is_static: bool, args: &[ValueType], slot: usize) -> Option<usize> {
+    let mut current_slot = 0;
+    let mut index = 0;
+    if !is_static {
+        if slot == 0 {
+            return Some(0);
+        }
+        current_slot = 1;
+        index = 1;
+    }
+    for arg in args {
+        if current_slot == slot {
+            return Some(index);
+        }
+        current_slot += match arg {
+            ValueType::Primary(PrimaryType::Long | PrimaryType::Double) => 2,
+            _ => 1,
+        };
+        if current_slot > slot {
+            return None;
+        }
+        index += 1;
+    }
+    None
+}
+
+mod tests {
+
+    #[test]
+    fn test_classify_accessors() {
+        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
+        use crate::method_area::MethodArea;
+        use crate::trivial_method::{TrivialConstant, TrivialMethod, ValueKind};
+
+        let area = MethodArea::default();
+        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
+        area.add_class_path(Box::new(file_system_path));
+        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
+        area.add_class_path(Box::new(rt_jar_path));
+        let class_ref = area.load_class("AccessorTest").unwrap();
+        let trivial = |name: &str, descriptor: &str| {
+            class_ref
+                .get_method(name, descriptor)
+                .unwrap()
+                .trivial_method
+                .clone()
+        };
+        assert!(matches!(
+            trivial("getTotal", "()J"),
+            Some(TrivialMethod::GetField { field_name, kind: ValueKind::Long, .. }) if field_name == "total"
+        ));
+        assert!(matches!(
+            trivial("setName", "(Ljava/lang/String;)V"),
+            Some(TrivialMethod::SetField { field_name, .. }) if field_name == "name"
+        ));
+        assert_eq!(
+            trivial("minus100", "()I"),
+            Some(TrivialMethod::ReturnConstant(TrivialConstant::Int(-100)))
+        );
+        assert_eq!(
+            trivial("self", "()LAccessorTest;"),
+            Some(TrivialMethod::ReturnLocal {
+                index: 0,
+                kind: ValueKind::Reference
+            })
+        );
+        //long参数占两个槽位，dload_2读取的是第二个参数
+        assert_eq!(
+            trivial("second", "(JD)D"),
+            Some(TrivialMethod::ReturnLocal {
+                index: 1,
+                kind: ValueKind::Double
+            })
+        );
+        assert_eq!(trivial("<init>", "()V"), None);
+        assert_eq!(trivial("countOf", "(LAccessorTest;)I"), None);
+        assert_eq!(trivial("run", "(I)J"), None);
+        let object_class = area.load_class("java/lang/Object").unwrap();
+        assert_eq!(
+            object_class
+                .get_method("<init>", "()V")
+                .unwrap()
+                .trivial_method,
+            Some(TrivialMethod::ReturnVoid)
+        );
+    }
+
+    #[test]
+    fn test_trivial_methods_match_interpreter() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+
+        let mut results = vec![];
+        for enabled in [true, false] {
+            let mut vm = create_vm();
+            vm.set_trivial_methods_enabled(enabled);
+            let call_stack = &mut vm.create_call_stack();
+
+            let accessor = vm
+                .call(
+                    call_stack,
+                    "AccessorTest",
+                    "create",
+                    "()LAccessorTest;",
+                    None,
+                    vec![],
+                )
+                .unwrap()
+                .unwrap();
+            let name = Value::ObjectRef(vm.new_java_lang_string_object(call_stack, "vm").unwrap());
+            let this = Some(accessor.clone());
+            let calls = vec![
+                ("setCount", "(I)V", this.clone(), vec![Value::Int(7)]),
+                ("getCount", "()I", this.clone(), vec![]),
+                ("setTotal", "(J)V", this.clone(), vec![Value::Long(-1)]),
+                ("getTotal", "()J", this.clone(), vec![]),
+                ("setRatio", "(F)V", this.clone(), vec![Value::Float(0.5)]),
+                ("getRatio", "()F", this.clone(), vec![]),
+                ("setWeight", "(D)V", this.clone(), vec![Value::Double(2.5)]),
+                ("getWeight", "()D", this.clone(), vec![]),
+                ("getName", "()Ljava/lang/String;", this.clone(), vec![]),
+                ("setName", "(Ljava/lang/String;)V", this.clone(), vec![name]),
+                ("getName", "()Ljava/lang/String;", this.clone(), vec![]),
+                ("setFlag", "(Z)V", this.clone(), vec![Value::Int(1)]),
+                ("isFlag", "()Z", this.clone(), vec![]),
+                ("self", "()LAccessorTest;", this.clone(), vec![]),
+                ("minus100", "()I", this.clone(), vec![]),
+                ("one", "()J", this.clone(), vec![]),
+                ("nothing", "()Ljava/lang/Object;", this.clone(), vec![]),
+                (
+                    "second",
+                    "(JD)D",
+                    None,
+                    vec![Value::Long(3), Value::Double(-0.0)],
+                ),
+                ("countOf", "(LAccessorTest;)I", None, vec![Value::Null]),
+                ("getCount", "()I", Some(Value::Null), vec![]),
+                //写入类型不一致的值时和解释执行报同样的错误
+                ("setCount", "(I)V", this.clone(), vec![Value::Long(1)]),
+                ("nullReceiver", "()I", None, vec![]),
+                ("run", "(I)J", None, vec![Value::Int(1000)]),
+            ];
+            let mut observed = vec![];
+            for (method_name, descriptor, receiver, args) in calls {
+                let result = vm
+                    .call(
+                        call_stack,
+                        "AccessorTest",
+                        method_name,
+                        descriptor,
+                        receiver,
+                        args,
+                    )
+                    .map(|v| match v {
+                        Some(v @ Value::ObjectRef(object_ref)) => {
+                            if object_ref.get_class().name == "java/lang/String" {
+                                v.get_string().unwrap()
+                            } else {
+                                object_ref.get_class().name.clone()
+                            }
+                        }
+                        v => format!("{:?}", v),
+                    })
+                    //只比较错误的种类
+                    .map_err(|e| format!("{:?}", e).split('(').next().unwrap().to_string());
+                observed.push((method_name, result));
+            }
+            results.push(observed);
+        }
+        assert_eq!(results[0], results[1]);
+        let find = |name: &str| {
+            results[0]
+                .iter()
+                .find(|(method_name, _)| *method_name == name)
+                .unwrap()
+                .1
+                .clone()
+        };
+        assert_eq!(find("minus100"), Ok("Some(Int(-100))".to_string()));
+        assert_eq!(find("nullReceiver"), Ok("Some(Int(-1))".to_string()));
+    }
+
+    //cargo test --release -p lite_jvm bench_trivial_methods -- --ignored --nocapture
+    #[test]
+    #[ignore]
+    fn bench_trivial_methods() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let _ = tracing_subscriber::fmt().with_test_writer().try_init();
+        use std::time::Instant;
+        for enabled in [false, true] {
+            let mut vm = create_vm();
+            vm.set_trivial_methods_enabled(enabled);
+            let call_stack = &mut vm.create_call_stack();
+            let iterations = 100_000;
+            let start = Instant::now();
+            vm.call(
+                call_stack,
+                "AccessorTest",
+                "run",
+                "(I)J",
+                None,
+                vec![Value::Int(iterations)],
+            )
+            .unwrap();
+            tracing::info!(
+                enabled,
+                iterations,
+                elapsed = ?start.elapsed(),
+                "trivial methods benchmark AccessorTest.run"
+            );
+        }
+    }
+}
@@ -1,19 +1,78 @@
-use crate::class_finder::ClassPath;
+use crate::bootstrap_class_loader::ClassTransformer;
+use crate::class_finder::{ClassPath, FileSystemClassPath, JarFileClassPath, JmodClassPath};
+use crate::dynamic_constant;
+use crate::intrinsics::Intrinsics;
 use crate::java_exception::{InvokeMethodResult, MethodCallError};
+use crate::jvm_error::{VmError, VmExecResult};
 use crate::jvm_values::{
-    ArrayElement, ArrayReference, ObjectReference, PrimaryType, ReferenceValue, Value,
+    ArrayElement, ArrayReference, ObjectReference, PrimaryType, ReferenceValue, Value, ValueType,
 };
 use crate::loaded_class::{ClassRef, ClassStatus, MethodRef};
-use crate::method_area::MethodArea;
+use crate::method_area::{ClassLoadTiming, MethodArea};
+use crate::method_handle::{MethodHandleInfo, METHOD_HANDLE, METHOD_HANDLES_LOOKUP};
 use crate::native_method_area::NativeMethodArea;
 use crate::object_heap::ObjectHeap;
+use crate::replay::ExecutionRecord;
 use crate::runtime_attribute_info::ConstantValueAttribute;
-use crate::runtime_constant_pool::MethodHandlerKind;
+use crate::runtime_constant_pool::{MethodHandlerKind, RuntimeConstantPoolEntry};
 use crate::stack::CallStack;
+use crate::stack_frame::LocalValue;
 use crate::stack_trace_element::StackTraceElement;
 use crate::static_field_area::StaticArea;
-use log::{debug, error, log_enabled, Level};
-use typed_arena::Arena;
+use crate::trace_filter::{LogTraceSink, TraceFilter, TraceSink};
+use class_file_reader::class_file_reader::read_buffer;
+use class_file_reader::class_file_version::ClassFileVersion;
+use log::{log_enabled, warn, Level};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use tracing::{debug, debug_span};
+
+/// 执行用户代码前通常都会用到的核心类，可以传给VirtualMachine::preload预热
+pub const BOOTSTRAP_CLASSES: &[&str] = &[
+    "java/lang/Object",
+    "java/lang/String",
+    "java/lang/Class",
+    "java/lang/System",
+    "java/lang/Character",
+    "java/lang/Integer",
+    "java/lang/Integer$IntegerCache",
+    "java/lang/StringBuilder",
+    "java/lang/Math",
+];
+
+/// preload_all中某个类加载、链接或校验失败的原因
+#[derive(Debug)]
+pub struct PreloadError<'a> {
+    pub class_name: String,
+    pub error: MethodCallError<'a>,
+}
+
+impl Display for PreloadError<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:?}", self.class_name, self.error)
+    }
+}
+
+/// call_static/call_instance的参数，字符串会转换成驻留的String对象
+#[derive(Debug, Clone)]
+pub enum CallArg<'a, 's> {
+    Value(Value<'a>),
+    Str(&'s str),
+}
+
+impl<'a> From<Value<'a>> for CallArg<'a, '_> {
+    fn from(value: Value<'a>) -> Self {
+        CallArg::Value(value)
+    }
+}
+
+impl<'s> From<&'s str> for CallArg<'_, 's> {
+    fn from(value: &'s str) -> Self {
+        CallArg::Str(value)
+    }
+}
 
 /// 虚拟机实现。 虚拟机应该是总入口
 ///
@@ -45,14 +104,49 @@ use typed_arena::Arena;
 ///
 /// 类加载后。类是由类名+类加载器共同标识的。
 /// 每个这样的类或接口都属于单个运行时包。类或接口的运行时包由包名和类或接口的定义加载器决定。   
-///
-
 pub struct VirtualMachine<'a> {
     method_area: MethodArea<'a>,
     object_heap: ObjectHeap<'a>,
-    vm_stacks: Arena<CallStack<'a>>,
     static_area: StaticArea<'a>,
     native_method_area: NativeMethodArea<'a>,
+    trace_filter: Option<TraceFilter>,
+    //匹配的方法在调用入口和出口输出参数、返回值和耗时
+    call_log_filter: Option<TraceFilter>,
+    trace_sink: Box<dyn TraceSink>,
+    trace_level: Level,
+    record_execution: bool,
+    recording: Vec<ExecutionRecord>,
+    record_writer: Option<Box<dyn Write>>,
+    intrinsics: Intrinsics<'a>,
+    intrinsics_enabled: bool,
+    trivial_methods_enabled: bool,
+    predecode_enabled: bool,
+    operand_stack_check_enabled: bool,
+    assertions_enabled: bool,
+    //System.nanoTime的起点
+    started_at: Instant,
+    //测试时替换系统时钟，返回(currentTimeMillis, nanoTime)
+    clock: Option<Box<dyn Fn() -> (i64, i64)>>,
+    //已经执行过finalize的对象，每个对象只执行一次
+    finalized_objects: HashSet<*mut u8>,
+    //对象监视器的重入次数，只有一个线程执行，不需要等待其他线程释放
+    monitors: HashMap<*mut u8, usize>,
+    core_classes: CoreClasses<'a>,
+    //System.out和System.err的输出位置，默认是进程的标准输出和标准错误
+    stdout: Box<dyn Write>,
+    stderr: Box<dyn Write>,
+    //安装为System.out和System.err的PrintStream，只有这两个对象的print/println由虚拟机直接输出
+    standard_streams: Option<(ObjectReference<'a>, ObjectReference<'a>)>,
+    //堆满时抛出的OutOfMemoryError，分配在静态区
+    out_of_memory_error: Option<ObjectReference<'a>>,
+}
+
+//创建字符串、Class对象时都要用到的类，初始化完成后缓存下来，避免每次按名称查找
+#[derive(Default)]
+struct CoreClasses<'a> {
+    object: Option<ClassRef<'a>>,
+    class: Option<ClassRef<'a>>,
+    string: Option<ClassRef<'a>>,
 }
 
 impl<'a> VirtualMachine<'a> {
@@ -60,16 +154,285 @@ impl<'a> VirtualMachine<'a> {
         VirtualMachine {
             method_area: MethodArea::default(),
             object_heap: ObjectHeap::new(heap_size),
-            vm_stacks: Arena::new(),
             static_area: StaticArea::new(1024 * 1024),
             native_method_area: NativeMethodArea::new_with_default_native(),
+            trace_filter: None,
+            call_log_filter: None,
+            trace_sink: Box::new(LogTraceSink::default()),
+            trace_level: Level::Trace,
+            record_execution: false,
+            recording: Vec::new(),
+            record_writer: None,
+            intrinsics: Intrinsics::new_with_default(),
+            intrinsics_enabled: true,
+            trivial_methods_enabled: true,
+            predecode_enabled: false,
+            operand_stack_check_enabled: true,
+            assertions_enabled: false,
+            started_at: Instant::now(),
+            clock: None,
+            finalized_objects: HashSet::new(),
+            monitors: HashMap::new(),
+            core_classes: CoreClasses::default(),
+            stdout: Box::new(std::io::stdout()),
+            stderr: Box::new(std::io::stderr()),
+            standard_streams: None,
+            out_of_memory_error: None,
+        }
+    }
+
+    /// 清空堆、静态区以及字符串和Class对象常量池，保留已加载类的元数据，用来重复执行程序而不重新分配内存。
+    /// 已加载的类回到Loaded状态，之后使用时重新链接和初始化，已有的ClassRef仍然有效。
+    /// 之前得到的对象引用和调用栈都不能继续使用
+    pub fn reset(&mut self) {
+        self.object_heap.reset();
+        self.static_area.reset();
+        self.method_area.reset_class_status();
+        //缓存的核心类需要重新初始化
+        self.core_classes = CoreClasses::default();
+        self.finalized_objects.clear();
+        self.monitors.clear();
+        self.recording.clear();
+        self.standard_streams = None;
+        self.out_of_memory_error = None;
+    }
+
+    /// 默认开启，关闭后所有方法都按照字节码或者native执行，用于对比intrinsic的结果
+    pub fn with_intrinsics(mut self, enabled: bool) -> VirtualMachine<'a> {
+        self.intrinsics_enabled = enabled;
+        self
+    }
+
+    pub fn set_intrinsics_enabled(&mut self, enabled: bool) {
+        self.intrinsics_enabled = enabled;
+    }
+
+    /// 默认开启，关闭后getter、setter等简单方法也创建栈帧解释执行
+    pub fn set_trivial_methods_enabled(&mut self, enabled: bool) {
+        self.trivial_methods_enabled = enabled;
+    }
+
+    /// 默认关闭，开启后方法第一次执行时解码全部指令并缓存，之后按字节码地址取出指令执行
+    pub fn set_predecode_enabled(&mut self, enabled: bool) {
+        self.predecode_enabled = enabled;
+    }
+
+    pub(crate) fn is_predecode_enabled(&self) -> bool {
+        self.predecode_enabled
+    }
+
+    /// 默认开启，压栈超过方法的max_stack时以VerifyError结束执行。
+    /// 关闭后操作数栈按需增长，用于运行max_stack计算有误的生成代码
+    pub fn set_operand_stack_check_enabled(&mut self, enabled: bool) {
+        self.operand_stack_check_enabled = enabled;
+    }
+
+    pub(crate) fn is_operand_stack_check_enabled(&self) -> bool {
+        self.operand_stack_check_enabled
+    }
+
+    /// 默认关闭，和java不带-ea时一样。Class.desiredAssertionStatus返回这个值，
+    /// 类在初始化时据此设置$assertionsDisabled，已经初始化的类不受影响
+    pub fn set_assertions_enabled(&mut self, enabled: bool) {
+        self.assertions_enabled = enabled;
+    }
+
+    pub(crate) fn is_assertions_enabled(&self) -> bool {
+        self.assertions_enabled
+    }
+
+    /// 替换System.currentTimeMillis和System.nanoTime使用的时钟，
+    /// clock返回(毫秒, 纳秒)，用于在测试中得到确定的时间
+    pub fn set_clock(&mut self, clock: Box<dyn Fn() -> (i64, i64)>) {
+        self.clock = Some(clock);
+    }
+
+    //单调递增，从虚拟机创建时开始计时
+    pub(crate) fn nano_time(&self) -> i64 {
+        match &self.clock {
+            Some(clock) => clock().1,
+            None => self.started_at.elapsed().as_nanos() as i64,
+        }
+    }
+
+    pub(crate) fn current_time_millis(&self) -> i64 {
+        match &self.clock {
+            Some(clock) => clock().0,
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_millis() as i64),
+        }
+    }
+
+    pub fn with_trace_filter(mut self, trace_filter: TraceFilter) -> VirtualMachine<'a> {
+        self.trace_filter = Some(trace_filter);
+        self
+    }
+
+    /// 记录匹配方法的调用：入口输出参数，出口输出返回值或抛出的异常类，以及嵌套深度和耗时。
+    /// 模式格式和TraceFilter相同，本地方法同样适用
+    pub fn log_calls(mut self, patterns: Vec<String>) -> VirtualMachine<'a> {
+        self.call_log_filter = Some(TraceFilter::new(&patterns));
+        self
+    }
+
+    /// 设置为None时恢复为按照log的trace级别追踪所有方法
+    pub fn set_trace_filter(&mut self, trace_filter: Option<TraceFilter>) {
+        self.trace_filter = trace_filter;
+    }
+
+    /// 打开或关闭所有方法的执行追踪，不再依赖log的级别判断
+    pub fn set_trace(&mut self, enabled: bool) {
+        let patterns: &[&str] = if enabled { &["*"] } else { &[] };
+        self.trace_filter = Some(TraceFilter::new(patterns));
+    }
+
+    /// 设置追踪输出使用的log级别，已经设置的TraceSink保持不变，只更新它的级别
+    pub fn set_trace_level(&mut self, level: Level) {
+        self.trace_level = level;
+        self.trace_sink.set_level(level);
+    }
+
+    /// 新的TraceSink使用set_trace_level已经设置的级别
+    pub fn set_trace_sink(&mut self, mut trace_sink: Box<dyn TraceSink>) {
+        trace_sink.set_level(self.trace_level);
+        self.trace_sink = trace_sink;
+    }
+
+    //返回(是否追踪指令, 是否输出栈快照)
+    pub(crate) fn frame_trace_options(
+        &self,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+    ) -> (bool, bool) {
+        match &self.trace_filter {
+            Some(filter) if filter.matches(&class_ref.name, &method_ref.name) => {
+                (true, filter.include_snapshots())
+            }
+            Some(_) => (false, false),
+            None => (log_enabled!(self.trace_level), false),
         }
     }
 
+    pub(crate) fn trace_line(&mut self, line: &str) {
+        self.trace_sink.write_line(line);
+    }
+
+    /// 用于追踪日志和测试失败信息的可读格式：字符串输出内容，数组输出[len=N 类型]，
+    /// 其他对象输出类名@hash，基本类型和Display一致
+    pub fn format_value(&self, value: &Value<'a>) -> String {
+        match value {
+            Value::ObjectRef(object_ref) if object_ref.get_class().name == "java/lang/String" => {
+                match value.get_string() {
+                    Ok(string) => format!("{:?}", string),
+                    Err(_) => object_ref.to_string(),
+                }
+            }
+            Value::ArrayRef(array_ref) => {
+                let header = array_ref.get_array_header();
+                format!(
+                    "[len={} {}]",
+                    header.array_size,
+                    header.element.java_type_name()
+                )
+            }
+            _ => value.to_string(),
+        }
+    }
+
+    /// 打开后每执行一条指令都会记录一条ExecutionRecord，用于和其他执行路径做比较
+    pub fn record_execution(&mut self, enabled: bool) {
+        self.record_execution = enabled;
+    }
+
+    pub(crate) fn is_recording_execution(&self) -> bool {
+        self.record_execution
+    }
+
+    //设置后记录按行写入writer，不再保存在内存中
+    pub fn set_record_writer(&mut self, writer: Option<Box<dyn Write>>) {
+        self.record_writer = writer;
+    }
+
+    /// 取出内存中已有的执行记录
+    pub fn take_recording(&mut self) -> Vec<ExecutionRecord> {
+        std::mem::take(&mut self.recording)
+    }
+
+    pub(crate) fn record(&mut self, record: ExecutionRecord) {
+        match &mut self.record_writer {
+            Some(writer) => {
+                if let Err(e) = writeln!(writer, "{}", record) {
+                    warn!("failed to write execution record: {}", e);
+                }
+            }
+            None => self.recording.push(record),
+        }
+    }
+
+    /// 替换System.out的输出位置，用于捕获Java程序的输出
+    pub fn set_stdout(&mut self, writer: Box<dyn Write>) {
+        self.stdout = writer;
+    }
+
+    pub fn set_stderr(&mut self, writer: Box<dyn Write>) {
+        self.stderr = writer;
+    }
+
+    //System.out和System.err由虚拟机输出，其他PrintStream执行原来的字节码
+    pub(crate) fn is_standard_stream(&self, stream: ObjectReference<'a>) -> bool {
+        matches!(self.standard_streams, Some((out, err)) if out == stream || err == stream)
+    }
+
+    //stream是安装的System.err时写入stderr，否则写入stdout
+    pub(crate) fn write_to_stream(
+        &mut self,
+        stream: ObjectReference<'a>,
+        text: &str,
+    ) -> Result<(), MethodCallError<'a>> {
+        let is_err = matches!(self.standard_streams, Some((_, err)) if err == stream);
+        let writer = if is_err {
+            &mut self.stderr
+        } else {
+            &mut self.stdout
+        };
+        writer
+            .write_all(text.as_bytes())
+            .and_then(|_| writer.flush())
+            .map_err(|e| VmError::ExecuteCodeError(format!("write to stream failed: {}", e)))?;
+        Ok(())
+    }
+
     pub fn add_class_path(&mut self, class_path: Box<dyn ClassPath>) {
         self.method_area.add_class_path(class_path);
     }
 
+    /// 引导类加载器读取到类文件后、解析之前调用transformer，可以用来改写字节码。
+    /// 只影响之后加载的类，自定义类加载器通过defineClass定义的类不经过transformer
+    pub fn set_class_transformer(&mut self, transformer: ClassTransformer) {
+        self.method_area.set_class_transformer(Some(transformer));
+    }
+
+    /// 和java -cp一样，按平台的分隔符（Unix是:，Windows是;）拆分后依次添加。
+    /// 以.jar结尾的是jar包，.jmod结尾的是jmod文件，其他的是目录
+    pub fn add_class_path_str(&mut self, class_path: &str) -> VmExecResult<()> {
+        let separator = if cfg!(windows) { ';' } else { ':' };
+        for entry in class_path
+            .split(separator)
+            .filter(|entry| !entry.is_empty())
+        {
+            let class_path: Box<dyn ClassPath> = if entry.ends_with(".jar") {
+                Box::new(JarFileClassPath::new(entry)?)
+            } else if entry.ends_with(".jmod") {
+                Box::new(JmodClassPath::new(entry)?)
+            } else {
+                Box::new(FileSystemClassPath::new(entry)?)
+            };
+            self.add_class_path(class_path);
+        }
+        Ok(())
+    }
+
     pub fn new_java_lang_class_object(
         &mut self,
         call_stack: &mut CallStack<'a>,
@@ -79,24 +442,88 @@ impl<'a> VirtualMachine<'a> {
             Ok(*v)
         } else {
             // self.get_class_by_name(call_stack, class_name)?;
-            let class_ref = self.get_class_by_name(call_stack, "java/lang/Class")?;
+            let class_ref = self.java_lang_class_class(call_stack)?;
             let class_object = self.static_area.new_object(class_ref);
-            let string_object = self.new_java_lang_string_object(call_stack, class_name)?;
+            //name字段缓存Class.getName的结果，使用点分隔的类名
+            let string_object =
+                self.new_java_lang_string_object(call_stack, &class_name.replace('/', "."))?;
             class_object.set_field_by_name("name", &Value::ObjectRef(string_object))?;
+            self.static_area
+                .class_constant_pool
+                .insert(class_name.to_string(), class_object);
             Ok(class_object)
         }
     }
 
-    pub fn new_java_lang_invoke_method_type(
+    /// 基本类型对应的Class对象，例如int.class、void.class。name字段是类型名称，和同名的类区分开。
+    /// name不是基本类型名称时返回None
+    pub fn get_primitive_class_object(
         &mut self,
         call_stack: &mut CallStack<'a>,
-        class_name: &str,
-        method_name: &str,
-        method_descriptor: &str,
+        name: &str,
+    ) -> Result<Option<ObjectReference<'a>>, MethodCallError<'a>> {
+        if !matches!(
+            name,
+            "boolean" | "byte" | "char" | "short" | "int" | "long" | "float" | "double" | "void"
+        ) {
+            return Ok(None);
+        }
+        if let Some(v) = self.static_area.primitive_class_objects.get(name) {
+            return Ok(Some(*v));
+        }
+        let class_ref = self.java_lang_class_class(call_stack)?;
+        let class_object = self.static_area.new_object(class_ref);
+        let string_object = self.new_java_lang_string_object(call_stack, name)?;
+        class_object.set_field_by_name("name", &Value::ObjectRef(string_object))?;
+        self.static_area
+            .primitive_class_objects
+            .insert(name.to_string(), class_object);
+        Ok(Some(class_object))
+    }
+
+    pub fn is_primitive_class_object(&self, class_object: ObjectReference<'a>) -> bool {
+        self.static_area
+            .primitive_class_objects
+            .values()
+            .any(|v| *v == class_object)
+    }
+
+    /// 类对应的java.lang.Class对象，自定义类加载器定义的类会设置classLoader字段
+    pub fn get_java_lang_class_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let Some(class_loader) = class_ref.class_loader else {
+            return self.new_java_lang_class_object(call_stack, &class_ref.name);
+        };
+        if let Some(v) = self.static_area.loader_class_objects.get(class_ref) {
+            return Ok(*v);
+        }
+        let java_lang_class = self.java_lang_class_class(call_stack)?;
+        let class_object = self.static_area.new_object(java_lang_class);
+        let string_object =
+            self.new_java_lang_string_object(call_stack, &class_ref.name.replace('/', "."))?;
+        class_object.set_field_by_name("name", &Value::ObjectRef(string_object))?;
+        class_object.set_field_by_name("classLoader", &Value::ObjectRef(class_loader))?;
+        self.static_area
+            .loader_class_objects
+            .insert(class_ref, class_object);
+        Ok(class_object)
+    }
+
+    pub fn new_java_lang_invoke_method_type(
+        &mut self,
+        _call_stack: &mut CallStack<'a>,
+        _class_name: &str,
+        _method_name: &str,
+        _method_descriptor: &str,
     ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
         todo!()
     }
 
+    /// 创建MethodHandle常量对象。对象不初始化MethodHandle的字段，
+    /// 引用的信息保存在静态区，invokeExact/invoke时通过method_handle_info取回
     pub fn new_java_lang_invoke_method_handler(
         &mut self,
         call_stack: &mut CallStack<'a>,
@@ -105,7 +532,43 @@ impl<'a> VirtualMachine<'a> {
         method_name: &str,
         method_descriptor: &str,
     ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
-        todo!()
+        let info = MethodHandleInfo {
+            kind: *kind,
+            class_name: class_name.to_string(),
+            name: method_name.to_string(),
+            descriptor: method_descriptor.to_string(),
+        };
+        if let Some(handle) = self.static_area.method_handle_pool.get(&info) {
+            return Ok(*handle);
+        }
+        //MethodHandle的类初始化依赖大量java.lang.invoke的内部实现，这里只加载不初始化
+        let class_ref = self.load_class_by_loader(call_stack, None, METHOD_HANDLE)?;
+        let handle = self.static_area.new_object(class_ref);
+        self.static_area
+            .method_handle_infos
+            .insert(handle, info.clone());
+        self.static_area.method_handle_pool.insert(info, handle);
+        Ok(handle)
+    }
+
+    /// 创建调用引导方法时传入的MethodHandles.Lookup，lookupClass是调用者，拥有全部访问权限。
+    /// 和MethodHandle一样只加载不初始化，字段直接写入
+    pub fn new_java_lang_invoke_lookup(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        lookup_class: ClassRef<'a>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let class_ref = self.load_class_by_loader(call_stack, None, METHOD_HANDLES_LOOKUP)?;
+        let lookup = self.new_object(call_stack, class_ref)?;
+        let class_object = self.get_java_lang_class_object(call_stack, lookup_class)?;
+        lookup.set_field_by_name("lookupClass", &Value::ObjectRef(class_object))?;
+        //PUBLIC | PRIVATE | PROTECTED | PACKAGE
+        lookup.set_field_by_name("allowedModes", &Value::Int(15))?;
+        Ok(lookup)
+    }
+
+    pub fn method_handle_info(&self, handle: ObjectReference<'a>) -> Option<&MethodHandleInfo> {
+        self.static_area.method_handle_infos.get(&handle)
     }
 
     pub fn new_java_lang_string_object(
@@ -116,18 +579,14 @@ impl<'a> VirtualMachine<'a> {
         if let Some(v) = self.static_area.string_constant_pool.get(value) {
             Ok(*v)
         } else {
-            let char_array: Vec<Value<'a>> =
-                value.encode_utf16().map(|c| Value::Int(c as i32)).collect();
+            let chars: Vec<u16> = value.encode_utf16().collect();
             let array_ref = self.new_array(
+                call_stack,
                 ArrayElement::PrimaryValue(PrimaryType::Char),
-                char_array.len(),
-            );
-            char_array
-                .into_iter()
-                .enumerate()
-                .for_each(|(index, value)| array_ref.set_field_by_offset(index, &value).unwrap());
-            let string_class_ref =
-                self.lookup_class_and_initialize(call_stack, "java/lang/String")?;
+                chars.len(),
+            )?;
+            array_ref.write_chars(&chars)?;
+            let string_class_ref = self.java_lang_string_class(call_stack)?;
             let object = self.static_area.new_object(string_class_ref);
             object.set_field_by_name("value", &Value::ArrayRef(array_ref))?;
             object.set_field_by_name("hash", &Value::Int(0))?;
@@ -138,6 +597,26 @@ impl<'a> VirtualMachine<'a> {
         }
     }
 
+    /// 在堆上创建不放入字符串常量池的String，和new String(char[])一样每次都是新对象。
+    /// 内容是UTF-16代码单元，不成对的代理原样保留
+    pub(crate) fn new_java_lang_string_from_chars(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        chars: &[u16],
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let array_ref = self.new_array(
+            call_stack,
+            ArrayElement::PrimaryValue(PrimaryType::Char),
+            chars.len(),
+        )?;
+        array_ref.write_chars(chars)?;
+        let string_class_ref = self.java_lang_string_class(call_stack)?;
+        let object = self.new_object(call_stack, string_class_ref)?;
+        object.set_field_by_name("value", &Value::ArrayRef(array_ref))?;
+        object.set_field_by_name("hash", &Value::Int(0))?;
+        Ok(object)
+    }
+
     fn init_static_fields(
         &mut self,
         call_stack: &mut CallStack<'a>,
@@ -151,9 +630,9 @@ impl<'a> VirtualMachine<'a> {
                         ConstantValueAttribute::Float(f) => Value::Float(*f),
                         ConstantValueAttribute::Long(l) => Value::Long(*l),
                         ConstantValueAttribute::Double(d) => Value::Double(*d),
-                        ConstantValueAttribute::String(str) => Value::ObjectRef(
-                            self.new_java_lang_string_object(call_stack, str).unwrap(),
-                        ),
+                        ConstantValueAttribute::String(str) => {
+                            Value::ObjectRef(self.new_java_lang_string_object(call_stack, str)?)
+                        }
                     }
                 } else {
                     match field.descriptor.as_str() {
@@ -178,419 +657,7205 @@ impl<'a> VirtualMachine<'a> {
         call_stack: &mut CallStack<'a>,
         class_ref: ClassRef<'a>,
     ) -> Result<(), MethodCallError<'a>> {
-        if class_ref.status == ClassStatus::Loaded {
-            self.set_class_stage(class_ref, ClassStatus::Linking);
-            self.init_static_fields(call_stack, class_ref)?;
-            self.set_class_stage(class_ref, ClassStatus::Linked);
+        if class_ref.status.get() == ClassStatus::Loaded {
+            let start = Instant::now();
+            class_ref.status.set(ClassStatus::Linking);
+            let result = match class_ref.check_inheritance() {
+                Err(VmError::VerifyError(message)) => self
+                    .new_exception(call_stack, "java/lang/VerifyError", &message)
+                    .and_then(|error| Err(MethodCallError::ExceptionThrown(error))),
+                Err(e) => Err(e.into()),
+                Ok(()) => self.init_static_fields(call_stack, class_ref),
+            };
+            if let Err(e) = result {
+                //链接失败时回到Loaded，之后使用该类会重新链接
+                class_ref.status.set(ClassStatus::Loaded);
+                return Err(e);
+            }
+            class_ref.status.set(ClassStatus::Linked);
+            let duration = start.elapsed();
+            self.method_area
+                .record_timing(&class_ref.name, |timing| timing.link += duration);
         }
         Ok(())
     }
-    fn set_class_stage(&mut self, class_ref: ClassRef<'a>, class_status: ClassStatus) {
-        if let Some(mut_class_ref) = self.method_area.get_mut(class_ref) {
-            mut_class_ref.status = class_status;
-        }
-    }
     //类的初始化。需要执行<clinit>方法。初始化一些变量。
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.5
     fn initialize_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
         class_ref: ClassRef<'a>,
     ) -> Result<(), MethodCallError<'a>> {
-        if let ClassStatus::Linked = class_ref.status {
-            self.set_class_stage(class_ref, ClassStatus::Initializing);
-
-            if let Ok(method_ref) = class_ref.get_method("<clinit>", "()V") {
-                self.invoke_method(
+        match class_ref.status.get() {
+            ClassStatus::Linked => {
+                let start = Instant::now();
+                let result = self.run_class_initializer(call_stack, class_ref);
+                let duration = start.elapsed();
+                self.method_area
+                    .record_timing(&class_ref.name, |timing| timing.initialize += duration);
+                result?;
+                if class_ref.name == "java/lang/System" {
+                    self.initialize_system_class(call_stack, class_ref)?;
+                }
+            }
+            ClassStatus::Erroneous => {
+                let error = self.new_exception(
                     call_stack,
-                    class_ref,
-                    method_ref,
-                    None::<ObjectReference>,
-                    Vec::new(),
+                    "java/lang/NoClassDefFoundError",
+                    &format!(
+                        "Could not initialize class {}",
+                        class_ref.name.replace('/', ".")
+                    ),
                 )?;
+                return Err(MethodCallError::ExceptionThrown(error));
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    //和HotSpot一样，System的<clinit>执行完之后再调用initializeSystemClass(JDK8)或initPhase1(JDK17)，
+    //而不是在registerNatives中调用。内置的核心类没有这两个方法，out/err在<clinit>中创建。
+    //之后记录当前的System.out/err
+    fn initialize_system_class(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        let method_name = match class_ref.version {
+            ClassFileVersion::Jdk8 => "initializeSystemClass",
+            _ => "initPhase1",
+        };
+        if let Ok(method_ref) = class_ref.get_method(method_name, "()V") {
+            self.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                Vec::new(),
+            )?;
+        }
+        self.install_standard_streams(call_stack)
+    }
+
+    fn run_class_initializer(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        class_ref.status.set(ClassStatus::Initializing);
+        if let Err(e) = self.initialize_super_types(call_stack, class_ref) {
+            //父类或接口初始化失败时直接抛出同一个异常，不再包装
+            class_ref.status.set(ClassStatus::Erroneous);
+            return Err(e);
+        }
+        if let Ok(method_ref) = class_ref.get_method("<clinit>", "()V") {
+            let result = self.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                Vec::new(),
+            );
+            if let Err(
+                e @ (MethodCallError::BudgetExceeded
+                | MethodCallError::Cancelled
+                | MethodCallError::VmExit(_)),
+            ) = result
+            {
+                //被宿主中断或者调用了System.exit时<clinit>没有执行完，回到Linked状态，下次使用时重新初始化
+                class_ref.status.set(ClassStatus::Linked);
+                return Err(e);
+            }
+            if let Err(e) = result {
+                //<clinit>只会执行一次，失败后类被标记为错误状态
+                class_ref.status.set(ClassStatus::Erroneous);
+                return Err(self.wrap_initializer_error(call_stack, e));
+            }
+        }
+        class_ref.status.set(ClassStatus::Initialized);
+        Ok(())
+    }
+
+    //类初始化前先初始化父类，以及声明了默认方法的超接口。
+    //超接口按照interfaces的顺序递归枚举，先枚举接口的父接口再返回接口本身。
+    //接口初始化时不会初始化它的父接口
+    //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.5
+    fn initialize_super_types(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        if class_ref.is_interface() {
+            return Ok(());
+        }
+        if let Some(super_class) = class_ref.super_class {
+            self.link_and_initialize(call_stack, super_class)?;
+        }
+        fn collect<'a>(interface: ClassRef<'a>, result: &mut Vec<ClassRef<'a>>) {
+            for super_interface in interface.interfaces.values() {
+                collect(super_interface, result);
+            }
+            if !result.iter().any(|c| std::ptr::eq(*c, interface)) {
+                result.push(interface);
+            }
+        }
+        let mut interfaces = vec![];
+        for interface in class_ref.interfaces.values() {
+            collect(interface, &mut interfaces);
+        }
+        for interface in interfaces {
+            let declares_default_method = interface
+                .methods
+                .values()
+                .any(|method_ref| !method_ref.is_abstract() && !method_ref.is_static());
+            if declares_default_method {
+                self.link_and_initialize(call_stack, interface)?;
             }
-            self.set_class_stage(class_ref, ClassStatus::Initialized);
         }
         Ok(())
     }
+
+    //<clinit>抛出的异常如果不是Error，需要包装为ExceptionInInitializerError，原异常作为cause
+    fn wrap_initializer_error(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        error: MethodCallError<'a>,
+    ) -> MethodCallError<'a> {
+        let MethodCallError::ExceptionThrown(exception) = error else {
+            return error;
+        };
+        if exception.get_class().is_subclass_of("java/lang/Error") {
+            return MethodCallError::ExceptionThrown(exception);
+        }
+        let wrapper = match self
+            .new_object_by_class_name(call_stack, "java/lang/ExceptionInInitializerError")
+        {
+            Ok(wrapper) => wrapper,
+            Err(e) => return e,
+        };
+        let cause = Value::ObjectRef(exception);
+        //jdk8中getCause()返回的是exception字段
+        if let Err(e) = wrapper
+            .set_field_by_name("exception", &cause)
+            .and_then(|_| wrapper.set_field_by_name("cause", &cause))
+        {
+            return MethodCallError::InternalError(e);
+        }
+        MethodCallError::ExceptionThrown(wrapper)
+    }
     pub fn lookup_class_and_initialize(
         &mut self,
         call_stack: &mut CallStack<'a>,
         class_name: &str,
     ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
-        let class_name = if class_name.starts_with('[') {
-            "java/lang/Object"
-        } else {
-            class_name
-        };
-        let class = self.method_area.load_class(class_name)?;
-        self.link_class(call_stack, class)?;
-        self.initialize_class(call_stack, class)?;
+        let class = self.load_bootstrap_class(call_stack, class_name)?;
+        self.link_and_initialize(call_stack, class)?;
         Ok(class)
     }
-    pub fn lookup_method(
+
+    //引导类加载器加载类，父类或接口形成循环时抛出ClassCircularityError
+    fn load_bootstrap_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
         class_name: &str,
-        method_name: &str,
-        descriptor: &str,
-    ) -> Result<(ClassRef<'a>, MethodRef<'a>), MethodCallError<'a>> {
-        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
-        let method_ref = class_ref.get_method_by_checking_super(method_name, descriptor)?;
-        Ok(method_ref)
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        match self.method_area.load_class(class_name) {
+            Err(VmError::ClassCircularity(chain)) => {
+                let message = chain.join(" -> ").replace('/', ".");
+                let error =
+                    self.new_exception(call_stack, "java/lang/ClassCircularityError", &message)?;
+                Err(MethodCallError::ExceptionThrown(error))
+            }
+            result => Ok(result?),
+        }
     }
 
-    pub fn new_object(&mut self, class_ref: ClassRef) -> ObjectReference<'a> {
-        self.object_heap.allocate_object(class_ref).unwrap()
+    /// 数组对象对应的数组类，例如[I
+    pub fn array_class(&self, array_ref: ArrayReference<'a>) -> VmExecResult<ClassRef<'a>> {
+        self.method_area
+            .load_class(&array_ref.get_array_type().array_class_name())
     }
 
-    pub fn new_object_by_class_name(
+    //多维数组取最内层的元素类型，基本类型的数组返回None。元素类型由引导类加载器加载
+    pub(crate) fn array_element_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
-        class_name: &str,
-    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
-        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
-        Ok(self.new_object(class_ref))
+        array_class: ClassRef<'a>,
+    ) -> Result<Option<ClassRef<'a>>, MethodCallError<'a>> {
+        let element = array_class.name.trim_start_matches('[');
+        match element.strip_prefix('L').and_then(|e| e.strip_suffix(';')) {
+            Some(class_name) => Ok(Some(self.load_bootstrap_class(call_stack, class_name)?)),
+            None => Ok(None),
+        }
     }
 
-    pub fn new_array(&mut self, array_element: ArrayElement, length: usize) -> ArrayReference<'a> {
-        self.object_heap
-            .allocate_array(array_element, length)
-            .unwrap()
+    pub fn java_lang_object_class(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        self.core_class(call_stack, "java/lang/Object", |c| &mut c.object)
     }
 
-    pub fn get_static(&self, class_ref: ClassRef<'a>, field_name: &str) -> Option<&Value<'a>> {
-        self.static_area.get_static_field(class_ref, field_name)
-    }
-    pub fn get_class_by_name(
+    pub fn java_lang_class_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
-        class_name: &str,
     ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
-        //防止重复加载
-        let class_ref = if !self.method_area.is_class_loaded(class_name) {
-            self.lookup_class_and_initialize(call_stack, class_name)?
-        } else {
-            self.method_area.load_class(class_name)?
-        };
-        Ok(class_ref)
+        self.core_class(call_stack, "java/lang/Class", |c| &mut c.class)
     }
-    pub fn get_static_field_by_class_name(
+
+    pub fn java_lang_string_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
-        class_name: &str,
-        field_name: &str,
-    ) -> Result<Option<&Value<'a>>, MethodCallError<'a>> {
-        //防止重复加载
-        let class_ref = self.get_class_by_name(call_stack, class_name)?;
-        let value = self.static_area.get_static_field(class_ref, field_name);
-        Ok(value)
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        self.core_class(call_stack, "java/lang/String", |c| &mut c.string)
     }
 
-    pub fn set_static_field_by_class_name(
+    fn core_class(
         &mut self,
         call_stack: &mut CallStack<'a>,
         class_name: &str,
-        field_name: &str,
-        value: Value<'a>,
+        slot: for<'c> fn(&'c mut CoreClasses<'a>) -> &'c mut Option<ClassRef<'a>>,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        if let Some(class_ref) = *slot(&mut self.core_classes) {
+            return Ok(class_ref);
+        }
+        let class_ref = self.load_bootstrap_class(call_stack, class_name)?;
+        self.link_and_initialize(call_stack, class_ref)?;
+        //<clinit>执行过程中还会再进入这里，只缓存初始化完成的类
+        if class_ref.status.get() == ClassStatus::Initialized {
+            *slot(&mut self.core_classes) = Some(class_ref);
+        }
+        Ok(class_ref)
+    }
+
+    /// 提前加载、链接并初始化这些类，嵌入方可以在执行用户代码之前预热一次。
+    /// 遇到第一个失败的类时返回错误
+    pub fn preload(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_names: &[&str],
     ) -> Result<(), MethodCallError<'a>> {
-        let class_ref = self.get_class_by_name(call_stack, class_name)?;
+        for class_name in class_names {
+            self.lookup_class_and_initialize(call_stack, class_name)?;
+        }
+        Ok(())
+    }
+
+    /// 打开后记录每个类加载、链接、初始化的耗时，用于找出启动时较慢的类
+    pub fn record_class_load_timing(&mut self, enabled: bool) {
+        self.method_area.set_timing_enabled(enabled);
+    }
+
+    /// 取出已有的类加载耗时记录
+    pub fn take_class_load_timings(&mut self) -> Vec<ClassLoadTiming> {
+        self.method_area.take_timings()
+    }
+
+    /// 预先加载、链接并校验这些类以及它们的父类和接口，不执行<clinit>。
+    /// 某个类出错时继续处理其余的类，最后返回所有错误
+    pub fn preload_all(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_names: &[&str],
+    ) -> Result<Vec<ClassRef<'a>>, Vec<PreloadError<'a>>> {
+        let mut loaded = vec![];
+        let mut errors = vec![];
+        let mut visited = HashSet::new();
+        for class_name in class_names {
+            let class_ref = match self.load_bootstrap_class(call_stack, class_name) {
+                Ok(class_ref) => class_ref,
+                Err(e) => {
+                    errors.push(PreloadError {
+                        class_name: class_name.to_string(),
+                        error: e,
+                    });
+                    continue;
+                }
+            };
+            loaded.push(class_ref);
+            let mut pending = vec![class_ref];
+            while let Some(class_ref) = pending.pop() {
+                if !visited.insert(class_ref.name.as_str()) {
+                    continue;
+                }
+                pending.extend(class_ref.super_class);
+                pending.extend(class_ref.interfaces.values());
+                if let Err(e) = self.link_class(call_stack, class_ref) {
+                    errors.push(PreloadError {
+                        class_name: class_ref.name.clone(),
+                        error: e,
+                    });
+                }
+                errors.extend(class_ref.verify().into_iter().map(|e| PreloadError {
+                    class_name: class_ref.name.clone(),
+                    error: e.into(),
+                }));
+            }
+        }
+        if errors.is_empty() {
+            Ok(loaded)
+        } else {
+            Err(errors)
+        }
+    }
+
+    pub(crate) fn link_and_initialize(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        self.link_class(call_stack, class_ref)?;
+        self.initialize_class(call_stack, class_ref)
+    }
+
+    /// 解析referrer中引用的类并初始化。由自定义类加载器定义的类，引用的类也通过该加载器加载
+    /// https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.3
+    pub(crate) fn resolve_class(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        referrer: ClassRef<'a>,
+        class_name: &str,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        let class_ref = self.resolve_class_without_initialize(call_stack, referrer, class_name)?;
+        self.link_and_initialize(call_stack, class_ref)?;
+        Ok(class_ref)
+    }
+
+    //只加载referrer中引用的类，由调用方决定初始化哪个类，例如invokestatic初始化声明方法的类
+    pub(crate) fn resolve_class_without_initialize(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        referrer: ClassRef<'a>,
+        class_name: &str,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        match referrer.class_loader {
+            None => self.load_bootstrap_class(call_stack, class_name),
+            Some(class_loader) => {
+                self.load_class_by_loader(call_stack, Some(class_loader), class_name)
+            }
+        }
+    }
+
+    //ldc加载类常量，不需要初始化该类
+    pub(crate) fn resolve_class_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        referrer: ClassRef<'a>,
+        class_name: &str,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        match referrer.class_loader {
+            None => self.new_java_lang_class_object(call_stack, class_name),
+            Some(class_loader) => {
+                let class_ref =
+                    self.load_class_by_loader(call_stack, Some(class_loader), class_name)?;
+                self.get_java_lang_class_object(call_stack, class_ref)
+            }
+        }
+    }
+
+    /// ldc加载动态计算常量，第一次解析时调用引导方法，之后直接返回缓存的结果
+    pub(crate) fn resolve_dynamic_constant(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        index: u16,
+    ) -> Result<Value<'a>, MethodCallError<'a>> {
+        if let Some(value) = self.static_area.dynamic_constants.get(&(class_ref, index)) {
+            return Ok(value.clone());
+        }
+        let RuntimeConstantPoolEntry::Dynamic(bootstrap_method_attr_index, name, descriptor) =
+            class_ref.constant_pool.get(index)?
+        else {
+            return Err(VmError::ValueTypeMissMatch.into());
+        };
+        let bootstrap_method = class_ref
+            .bootstrap_method
+            .get(*bootstrap_method_attr_index as usize)
+            .ok_or_else(|| {
+                VmError::LinkageError(format!(
+                    "bootstrap method #{} not found in {}",
+                    bootstrap_method_attr_index, class_ref.name
+                ))
+            })?;
+        let value = dynamic_constant::bootstrap(
+            self,
+            call_stack,
+            class_ref,
+            bootstrap_method,
+            name,
+            descriptor,
+        )?;
+        if !value.matches_descriptor(descriptor) {
+            return Err(VmError::LinkageError(format!(
+                "dynamic constant {}:{} resolved to {}",
+                name, descriptor, value
+            ))
+            .into());
+        }
         self.static_area
-            .set_static_field(class_ref, field_name, value);
+            .dynamic_constants
+            .insert((class_ref, index), value.clone());
+        Ok(value)
+    }
+
+    /// 使用指定的类加载器加载类，None表示引导类加载器。
+    /// 自定义类加载器会调用该对象的loadClass(String)方法，返回的类记录在该加载器下，之后不再重复调用
+    pub fn load_class_by_loader(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_loader: Option<ObjectReference<'a>>,
+        class_name: &str,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        //数组类只由引导类加载器创建
+        let Some(class_loader) = class_loader.filter(|_| !class_name.starts_with('[')) else {
+            return self.load_bootstrap_class(call_stack, class_name);
+        };
+        if let Some(class_ref) = self
+            .method_area
+            .find_class_by_loader(class_loader, class_name)
+        {
+            return Ok(class_ref);
+        }
+        let (loader_class, method_ref) = class_loader
+            .get_class()
+            .get_method_by_checking_super("loadClass", "(Ljava/lang/String;)Ljava/lang/Class;")?;
+        let binary_name =
+            self.new_java_lang_string_object(call_stack, &class_name.replace('/', "."))?;
+        let result = self.invoke_method(
+            call_stack,
+            loader_class,
+            method_ref,
+            Some(class_loader),
+            vec![Value::ObjectRef(binary_name)],
+        )?;
+        let Some(Value::ObjectRef(class_object)) = result else {
+            return Err(VmError::ClassNotFoundException(class_name.to_string()).into());
+        };
+        let class_ref = self.get_class_ref_of_class_object(call_stack, class_object)?;
+        self.method_area
+            .record_initiating_loader(class_loader, class_name, class_ref);
+        Ok(class_ref)
+    }
+
+    /// 对应ClassLoader.defineClass，使用内存中的字节码定义类，父类和接口通过该加载器解析。
+    /// 指定了name时需要和字节码中的类名一致，否则抛出NoClassDefFoundError
+    pub fn define_class(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_loader: ObjectReference<'a>,
+        name: Option<&str>,
+        bytes: &[u8],
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        let class_file = read_buffer(bytes).map_err(VmError::from)?;
+        if let Some(name) = name {
+            if name.replace('.', "/") != class_file.this_class_name {
+                let exception = self.new_exception(
+                    call_stack,
+                    "java/lang/NoClassDefFoundError",
+                    &format!(
+                        "{} (wrong name: {})",
+                        class_file.this_class_name,
+                        name.replace('.', "/")
+                    ),
+                )?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+        }
+        let super_class = match &class_file.super_class_name {
+            Some(super_class_name) => {
+                Some(self.load_class_by_loader(call_stack, Some(class_loader), super_class_name)?)
+            }
+            None => None,
+        };
+        let mut interfaces = Vec::new();
+        for interface_name in &class_file.interface_names {
+            interfaces.push(self.load_class_by_loader(
+                call_stack,
+                Some(class_loader),
+                interface_name,
+            )?);
+        }
+        Ok(self
+            .method_area
+            .define_class(class_loader, class_file, super_class, interfaces)?)
+    }
+
+    /// 查找类加载器已经加载过的类，对应ClassLoader.findLoadedClass
+    pub fn find_loaded_class(
+        &self,
+        class_loader: ObjectReference<'a>,
+        class_name: &str,
+    ) -> Option<ClassRef<'a>> {
+        self.method_area
+            .find_class_by_loader(class_loader, class_name)
+    }
+
+    /// 引导类加载器加载的类当前所处的状态，没有加载过时返回None。
+    /// 只读取状态，不会触发类的加载、链接或初始化
+    pub fn class_status(&self, class_name: &str) -> Option<ClassStatus> {
+        self.method_area
+            .find_loaded_class(class_name)
+            .map(|class_ref| class_ref.status.get())
+    }
+
+    /// 根据java.lang.Class对象找到对应的类
+    pub fn get_class_ref_of_class_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_object: ObjectReference<'a>,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        let class_name = class_object
+            .get_field_by_name("name")?
+            .get_string()?
+            .replace('.', "/");
+        match class_object.get_field_by_name("classLoader")? {
+            Value::ObjectRef(class_loader) => self
+                .method_area
+                .find_class_by_loader(class_loader, &class_name)
+                .ok_or_else(|| VmError::NoClassDefFoundError(class_name).into()),
+            _ => self.load_bootstrap_class(call_stack, &class_name),
+        }
+    }
+    pub fn lookup_method(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+    ) -> Result<(ClassRef<'a>, MethodRef<'a>), MethodCallError<'a>> {
+        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
+        let method_ref = class_ref.get_method_by_checking_super(method_name, descriptor)?;
+        Ok(method_ref)
+    }
+
+    /// 宿主程序调用Java方法的入口，加载并初始化类后解析方法并调用。
+    /// receiver为None时调用静态方法，否则按receiver的实际类型分派实例方法
+    pub fn call(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        method_name: &str,
+        descriptor: &str,
+        receiver: Option<Value<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let (class_ref, method_ref) =
+            self.lookup_method(call_stack, class_name, method_name, descriptor)?;
+        if method_ref.is_static() != receiver.is_none() {
+            let expected = if receiver.is_none() {
+                "static"
+            } else {
+                "instance"
+            };
+            let error = self.new_exception(
+                call_stack,
+                "java/lang/IncompatibleClassChangeError",
+                &format!(
+                    "Expected {} method {}.{}{}",
+                    expected,
+                    class_ref.name.replace('/', "."),
+                    method_name,
+                    descriptor
+                ),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(error));
+        }
+        match receiver {
+            None => self.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                args,
+            ),
+            Some(Value::ObjectRef(object_ref)) => {
+                if !object_ref.is_instance_of(class_ref) {
+                    return Err(VmError::ValueTypeMissMatch.into());
+                }
+                //构造方法和私有方法不参与分派
+                let (class_ref, method_ref) =
+                    if method_ref.name == "<init>" || method_ref.is_private() {
+                        (class_ref, method_ref)
+                    } else {
+                        object_ref
+                            .get_class()
+                            .get_method_by_checking_super(method_name, descriptor)?
+                    };
+                self.invoke_method(call_stack, class_ref, method_ref, Some(object_ref), args)
+            }
+            Some(Value::ArrayRef(array_ref)) => {
+                //数组只能调用Object的方法，或者通过Cloneable、Serializable调用
+                let array_class = self.array_class(array_ref)?;
+                if !array_class.is_subclass_of(&class_ref.name) {
+                    let error = self.new_exception(
+                        call_stack,
+                        "java/lang/IncompatibleClassChangeError",
+                        &format!(
+                            "{} is not an instance of {}",
+                            array_class.name.replace('/', "."),
+                            class_ref.name.replace('/', ".")
+                        ),
+                    )?;
+                    return Err(MethodCallError::ExceptionThrown(error));
+                }
+                self.invoke_method(call_stack, class_ref, method_ref, Some(array_ref), args)
+            }
+            Some(Value::Null) => {
+                let error = self.new_exception(
+                    call_stack,
+                    "java/lang/NullPointerException",
+                    &format!(
+                        "{}.{}{}",
+                        class_ref.name.replace('/', "."),
+                        method_name,
+                        descriptor
+                    ),
+                )?;
+                Err(MethodCallError::ExceptionThrown(error))
+            }
+            Some(_) => Err(VmError::ValueTypeMissMatch.into()),
+        }
+    }
+
+    /// 按方法名调用静态方法，不需要给出描述符。有多个重载时根据参数的类型选择，
+    /// 无法确定唯一的方法时返回AmbiguousMethodCall。
+    /// 没有参数个数一致的重载时，可变参数方法多出的参数打包成数组传入
+    pub fn call_static(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        method_name: &str,
+        args: &[CallArg<'a, '_>],
+    ) -> InvokeMethodResult<'a> {
+        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
+        let args = self.convert_call_args(call_stack, args)?;
+        let (declaring_class, method_ref, varargs) =
+            Self::select_overload(class_ref, method_name, true, &args)?;
+        let args = self.pack_varargs(call_stack, method_ref, args, varargs)?;
+        //直接调用选中的方法，不再按类名查找，声明方法的类可能由用户定义的ClassLoader加载
+        self.link_and_initialize(call_stack, declaring_class)?;
+        self.invoke_method(
+            call_stack,
+            declaring_class,
+            method_ref,
+            None::<ObjectReference>,
+            args,
+        )
+    }
+
+    /// 按方法名调用receiver的实例方法，从receiver的实际类型开始查找重载
+    pub fn call_instance(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        receiver: ObjectReference<'a>,
+        method_name: &str,
+        args: &[CallArg<'a, '_>],
+    ) -> InvokeMethodResult<'a> {
+        let args = self.convert_call_args(call_stack, args)?;
+        let (declaring_class, method_ref, varargs) =
+            Self::select_overload(receiver.get_class(), method_name, false, &args)?;
+        let args = self.pack_varargs(call_stack, method_ref, args, varargs)?;
+        self.invoke_method(
+            call_stack,
+            declaring_class,
+            method_ref,
+            Some(receiver),
+            args,
+        )
+    }
+
+    /// 执行main(String[])，System.out/System.err的输出写入set_stdout/set_stderr设置的writer。
+    /// 返回进程的退出状态：main正常返回时是0，调用System.exit时是传入的状态。未捕获的异常作为错误返回
+    pub fn run_main(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        args: &[&str],
+    ) -> Result<i32, MethodCallError<'a>> {
+        self.install_standard_streams(call_stack)?;
+        let string_class = self.java_lang_string_class(call_stack)?;
+        let array = self.new_array(
+            call_stack,
+            ArrayElement::ClassReference(string_class),
+            args.len(),
+        )?;
+        for (index, arg) in args.iter().enumerate() {
+            let string = self.new_java_lang_string_object(call_stack, arg)?;
+            array.set_field_by_offset(index, &Value::ObjectRef(string))?;
+        }
+        let result = self.call(
+            call_stack,
+            class_name,
+            "main",
+            "([Ljava/lang/String;)V",
+            None,
+            vec![Value::ArrayRef(array)],
+        );
+        match result {
+            Ok(_) => Ok(0),
+            Err(MethodCallError::VmExit(status)) => Ok(status),
+            Err(e) => Err(e),
+        }
+    }
+
+    //记录System初始化完成时的System.out/err，之后通过System.setOut替换的PrintStream执行字节码。
+    //System.out/err为null时分配不执行构造函数的PrintStream，print/println由native实现
+    pub(crate) fn install_standard_streams(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        let system_class = self.lookup_class_and_initialize(call_stack, "java/lang/System")?;
+        if self.standard_streams.is_some() {
+            return Ok(());
+        }
+        let mut streams = Vec::with_capacity(2);
+        for name in ["out", "err"] {
+            let stream = match self.get_static(system_class, name) {
+                Some(Value::ObjectRef(stream)) => *stream,
+                _ => {
+                    let stream =
+                        self.new_object_by_class_name(call_stack, "java/io/PrintStream")?;
+                    self.set_static(system_class, name, Value::ObjectRef(stream));
+                    stream
+                }
+            };
+            streams.push(stream);
+        }
+        self.standard_streams = Some((streams[0], streams[1]));
         Ok(())
     }
 
-    pub fn invoke_native_method(
-        &mut self,
-        call_stack: &mut CallStack<'a>,
-        class_ref: ClassRef<'a>,
-        method_ref: MethodRef<'a>,
-        object: Option<impl ReferenceValue<'a>>,
-        args: Vec<Value<'a>>,
-    ) -> InvokeMethodResult<'a> {
-        if log_enabled!(Level::Debug) {
-            let depth = "\t".repeat(call_stack.depth() - 1);
-            debug!(
-                "{}=> invoke_native_method {}:{}{}",
-                depth, class_ref.name, method_ref.name, method_ref.descriptor
-            );
+    fn convert_call_args(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        args: &[CallArg<'a, '_>],
+    ) -> Result<Vec<Value<'a>>, MethodCallError<'a>> {
+        args.iter()
+            .map(|arg| match arg {
+                CallArg::Value(value) => Ok(value.clone()),
+                CallArg::Str(s) => Ok(Value::ObjectRef(
+                    self.new_java_lang_string_object(call_stack, s)?,
+                )),
+            })
+            .collect()
+    }
+
+    //沿着父类链收集同名方法，子类中的方法覆盖父类中描述符相同的方法。
+    //参数都能匹配的候选只有一个时直接选择，否则选择所有参数类型都完全一致的那个。
+    //和javac一样，固定参数个数的候选都不匹配时才考虑可变参数方法，返回值的最后一项表示需要打包参数
+    fn select_overload(
+        class_ref: ClassRef<'a>,
+        method_name: &str,
+        is_static: bool,
+        args: &[Value<'a>],
+    ) -> VmExecResult<(ClassRef<'a>, MethodRef<'a>, bool)> {
+        let mut candidates: Vec<(ClassRef<'a>, MethodRef<'a>)> = vec![];
+        let mut current = Some(class_ref);
+        while let Some(class_ref) = current {
+            for method_ref in class_ref.methods.values() {
+                if method_ref.name == method_name
+                    && method_ref.is_static() == is_static
+                    && !candidates
+                        .iter()
+                        .any(|(_, m)| m.descriptor == method_ref.descriptor)
+                {
+                    candidates.push((class_ref, method_ref));
+                }
+            }
+            current = class_ref.super_class;
+        }
+        //(候选, 是否所有参数都完全一致)
+        let mut varargs = false;
+        let mut matched = Self::match_overloads(&candidates, args, false);
+        if matched.is_empty() {
+            varargs = true;
+            matched = Self::match_overloads(&candidates, args, true);
+        }
+        let exact: Vec<_> = matched.iter().filter(|(_, exact)| *exact).collect();
+        match (matched.as_slice(), exact.as_slice()) {
+            ([((class_ref, method_ref), _)], _) | (_, [((class_ref, method_ref), _)]) => {
+                Ok((*class_ref, *method_ref, varargs))
+            }
+            ([], _) => Err(VmError::MethodNotFoundException(
+                format!("{}.{}", class_ref.name, method_name),
+                format!("no overload accepts {:?}", args),
+            )),
+            _ => Err(VmError::AmbiguousMethodCall(
+                format!("{}.{}", class_ref.name, method_name),
+                matched
+                    .iter()
+                    .map(|((_, method_ref), _)| method_ref.descriptor.clone())
+                    .collect(),
+            )),
+        }
+    }
+
+    //varargs为true时只匹配可变参数方法，最后一个参数之后的实参逐个和数组的元素类型比较
+    fn match_overloads(
+        candidates: &[(ClassRef<'a>, MethodRef<'a>)],
+        args: &[Value<'a>],
+        varargs: bool,
+    ) -> Vec<((ClassRef<'a>, MethodRef<'a>), bool)> {
+        candidates
+            .iter()
+            .filter_map(|candidate| {
+                let params = &candidate.1.descriptor_args_ret.args;
+                let param_types: Vec<_> = if varargs {
+                    let (last, fixed) = params.split_last()?;
+                    if !candidate.1.is_varargs() || args.len() < fixed.len() {
+                        return None;
+                    }
+                    let component_type = last.component_type()?;
+                    fixed
+                        .iter()
+                        .cloned()
+                        .chain(std::iter::repeat(component_type))
+                        .take(args.len())
+                        .collect()
+                } else if params.len() == args.len() {
+                    params.clone()
+                } else {
+                    return None;
+                };
+                let mut exact = true;
+                for (arg, value_type) in args.iter().zip(&param_types) {
+                    exact &= Self::match_call_arg(arg, value_type)?;
+                }
+                Some((*candidate, exact))
+            })
+            .collect()
+    }
+
+    //把可变参数方法多出的参数放进最后一个参数类型的数组
+    fn pack_varargs(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        method_ref: MethodRef<'a>,
+        mut args: Vec<Value<'a>>,
+        varargs: bool,
+    ) -> Result<Vec<Value<'a>>, MethodCallError<'a>> {
+        if !varargs {
+            return Ok(args);
+        }
+        let fixed = method_ref.descriptor_args_ret.args.len() - 1;
+        let rest = args.split_off(fixed);
+        let array_descriptor = Self::last_param_descriptor(&method_ref.descriptor);
+        let array = self.new_array_by_descriptor(call_stack, array_descriptor, rest.len())?;
+        for (index, value) in rest.iter().enumerate() {
+            array.set_field_by_offset(index, value)?;
+        }
+        args.push(Value::ArrayRef(array));
+        Ok(args)
+    }
+
+    //方法描述符中最后一个参数的描述符，例如(I[Ljava/lang/String;)V中的[Ljava/lang/String;
+    fn last_param_descriptor(descriptor: &str) -> &str {
+        let params = &descriptor[1..descriptor.find(')').unwrap_or(1)];
+        let bytes = params.as_bytes();
+        let (mut start, mut index) = (0, 0);
+        while index < bytes.len() {
+            start = index;
+            while bytes[index] == b'[' {
+                index += 1;
+            }
+            index = match bytes[index] {
+                b'L' => index + params[index..].find(';').map_or(bytes.len(), |i| i + 1),
+                _ => index + 1,
+            };
+        }
+        &params[start..]
+    }
+
+    //None表示不能作为该类型的参数，Some(true)表示类型完全一致
+    fn match_call_arg(value: &Value<'a>, value_type: &ValueType) -> Option<bool> {
+        match (value, value_type) {
+            (Value::Int(_), ValueType::Primary(PrimaryType::Int)) => Some(true),
+            (
+                Value::Int(_),
+                ValueType::Primary(
+                    PrimaryType::Byte
+                    | PrimaryType::Char
+                    | PrimaryType::Short
+                    | PrimaryType::Boolean,
+                ),
+            ) => Some(false),
+            (Value::Long(_), ValueType::Primary(PrimaryType::Long))
+            | (Value::Float(_), ValueType::Primary(PrimaryType::Float))
+            | (Value::Double(_), ValueType::Primary(PrimaryType::Double)) => Some(true),
+            (Value::ObjectRef(object_ref), ValueType::Object(class_name)) => {
+                let class_ref = object_ref.get_class();
+                if class_ref.name == *class_name {
+                    Some(true)
+                } else {
+                    class_ref.is_subclass_of(class_name).then_some(false)
+                }
+            }
+            (
+                Value::ArrayRef(array_ref),
+                ValueType::PrimaryArray(_, _) | ValueType::ObjectArray(_, _),
+            ) => Self::match_array_arg(&array_ref.get_array_type(), value_type),
+            (Value::ArrayRef(_), ValueType::Object(class_name))
+                if matches!(
+                    class_name.as_str(),
+                    "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+                ) =>
+            {
+                Some(false)
+            }
+            (
+                Value::Null,
+                ValueType::Object(_) | ValueType::PrimaryArray(_, _) | ValueType::ObjectArray(_, _),
+            ) => Some(false),
+            _ => None,
+        }
+    }
+
+    //基本类型数组的类型需要完全一致；引用类型数组维数相同时比较元素类型能否赋值，
+    //维数更多时只能作为Object[]、Cloneable[]或Serializable[]类型的参数
+    fn match_array_arg(element: &ArrayElement<'a>, value_type: &ValueType) -> Option<bool> {
+        let mut dimension = 1;
+        let mut base = element;
+        while let ArrayElement::Array(inner) = base {
+            dimension += 1;
+            base = inner;
+        }
+        match (base, value_type) {
+            (ArrayElement::PrimaryValue(primary_type), ValueType::PrimaryArray(expected, d)) => {
+                (primary_type == expected && dimension == *d).then_some(true)
+            }
+            (ArrayElement::ClassReference(class_ref), ValueType::ObjectArray(class_name, d))
+                if dimension == *d =>
+            {
+                if class_ref.name == *class_name {
+                    Some(true)
+                } else {
+                    class_ref.is_subclass_of(class_name).then_some(false)
+                }
+            }
+            (_, ValueType::ObjectArray(class_name, d)) if dimension > *d => matches!(
+                class_name.as_str(),
+                "java/lang/Object" | "java/lang/Cloneable" | "java/io/Serializable"
+            )
+            .then_some(false),
+            _ => None,
+        }
+    }
+
+    /// 堆空间不足时抛出OutOfMemoryError
+    pub fn new_object(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        match self.object_heap.allocate_object(class_ref) {
+            Ok(object) => Ok(object),
+            Err(VmError::OutOfMemory(..)) => Err(self.out_of_memory_error(call_stack)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    //堆已经满了，OutOfMemoryError分配在静态区，并且只创建一次，之后重复抛出同一个对象
+    fn out_of_memory_error(&mut self, call_stack: &mut CallStack<'a>) -> MethodCallError<'a> {
+        if let Some(error) = self.out_of_memory_error {
+            return MethodCallError::ExceptionThrown(error);
+        }
+        //预留空间也用完了，不能再递归创建
+        if self.object_heap.is_reserve_open() {
+            return VmError::OutOfMemory(0, self.object_heap.free_bytes()).into();
+        }
+        //初始化OutOfMemoryError及其父类、创建消息字符串都要在堆上分配，这期间使用预留空间
+        self.object_heap.set_reserve_open(true);
+        let result = self.new_out_of_memory_error(call_stack);
+        self.object_heap.set_reserve_open(false);
+        match result {
+            Ok(error) => {
+                self.out_of_memory_error = Some(error);
+                MethodCallError::ExceptionThrown(error)
+            }
+            Err(e) => e,
+        }
+    }
+
+    fn new_out_of_memory_error(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let class_ref =
+            self.lookup_class_and_initialize(call_stack, "java/lang/OutOfMemoryError")?;
+        let error = self.static_area.new_object(class_ref);
+        let message = self.new_java_lang_string_object(call_stack, "Java heap space")?;
+        error.set_field_by_name("detailMessage", &Value::ObjectRef(message))?;
+        Ok(error)
+    }
+
+    pub fn new_object_by_class_name(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let class_ref = self.lookup_class_and_initialize(call_stack, class_name)?;
+        self.new_object(call_stack, class_ref)
+    }
+
+    /// 创建带有detailMessage的异常对象，不执行构造函数
+    pub(crate) fn new_exception(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        message: &str,
+    ) -> Result<ObjectReference<'a>, MethodCallError<'a>> {
+        let exception = self.new_object_by_class_name(call_stack, class_name)?;
+        let message = self.new_java_lang_string_object(call_stack, message)?;
+        exception.set_field_by_name("detailMessage", &Value::ObjectRef(message))?;
+        Ok(exception)
+    }
+
+    /// 堆空间不足时抛出OutOfMemoryError
+    pub fn new_array(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        array_element: ArrayElement<'a>,
+        length: usize,
+    ) -> Result<ArrayReference<'a>, MethodCallError<'a>> {
+        match self.object_heap.allocate_array(array_element, length) {
+            Ok(array) => Ok(array),
+            Err(VmError::OutOfMemory(..)) => Err(self.out_of_memory_error(call_stack)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 按数组描述符创建数组，例如[I、[[Ljava/lang/String;。元素引用的类只加载不初始化
+    pub fn new_array_by_descriptor(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        descriptor: &str,
+        length: usize,
+    ) -> Result<ArrayReference<'a>, MethodCallError<'a>> {
+        let element = self.array_element_by_descriptor(call_stack, None, descriptor)?;
+        self.new_array(call_stack, element, length)
+    }
+
+    /// 数组描述符的元素类型，多维数组的元素是低一维的数组。引用类型使用指定的类加载器加载
+    pub(crate) fn array_element_by_descriptor(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_loader: Option<ObjectReference<'a>>,
+        descriptor: &str,
+    ) -> Result<ArrayElement<'a>, MethodCallError<'a>> {
+        let (mut element, dimension) = match ValueType::from_field_descriptor(descriptor)? {
+            ValueType::PrimaryArray(primary_type, dimension) => {
+                (ArrayElement::PrimaryValue(primary_type), dimension)
+            }
+            ValueType::ObjectArray(class_name, dimension) => {
+                let class_ref = self.load_class_by_loader(call_stack, class_loader, &class_name)?;
+                (ArrayElement::ClassReference(class_ref), dimension)
+            }
+            _ => {
+                return Err(VmError::InvalidDescriptor(format!(
+                    "{} is not an array descriptor",
+                    descriptor
+                ))
+                .into())
+            }
+        };
+        for _ in 1..dimension {
+            element = ArrayElement::Array(Box::new(element));
+        }
+        Ok(element)
+    }
+
+    /// 读取java.lang.String对象的内容。JDK 8的value是char[]，
+    /// JDK 9之后是byte[]，并由coder区分LATIN1(0)和按本机字节序存储的UTF16(1)
+    pub fn get_string_content(&self, string: ObjectReference<'a>) -> VmExecResult<String> {
+        Ok(String::from_utf16_lossy(&self.get_string_chars(string)?))
+    }
+
+    //字符串的UTF-16内容，不成对的代理也原样保留
+    pub(crate) fn get_string_chars(&self, string: ObjectReference<'a>) -> VmExecResult<Vec<u16>> {
+        if string.get_class().name != "java/lang/String" {
+            return Err(VmError::ValueTypeMissMatch);
+        }
+        let value = string.get_field_by_name("value")?.get_array()?;
+        let units: Vec<i32> = value
+            .read_all()
+            .iter()
+            .map(Value::get_int)
+            .collect::<Result<_, _>>()?;
+        let chars: Vec<u16> = match value.get_array_type() {
+            ArrayElement::PrimaryValue(PrimaryType::Char) => {
+                units.iter().map(|unit| *unit as u16).collect()
+            }
+            ArrayElement::PrimaryValue(PrimaryType::Byte) => {
+                let bytes: Vec<u8> = units.iter().map(|unit| *unit as u8).collect();
+                match string.get_field_by_name("coder")?.get_int()? {
+                    0 => bytes.iter().map(|byte| *byte as u16).collect(),
+                    _ => bytes
+                        .chunks_exact(2)
+                        .map(|pair| u16::from_ne_bytes([pair[0], pair[1]]))
+                        .collect(),
+                }
+            }
+            _ => return Err(VmError::ValueTypeMissMatch),
+        };
+        Ok(chars)
+    }
+
+    /// 和format_value的输出相同，保留给已有的调用者
+    pub fn describe_value(&self, value: &Value<'a>) -> String {
+        self.format_value(value)
+    }
+
+    /// 异常的可读格式，第一行和Throwable.toString一致，之后每行一个调用栈元素，例如
+    /// java.lang.AssertionError: expected:<1> but was:<2>
+    ///     AssertTest.testBroken(AssertTest.java:12)
+    pub fn format_exception(&self, exception: ObjectReference<'a>) -> String {
+        let mut result = exception.get_class().name.replace('/', ".");
+        if let Ok(message) = exception
+            .get_field_by_name("detailMessage")
+            .and_then(|message| message.get_string())
+        {
+            result.push_str(": ");
+            result.push_str(&message);
+        }
+        //从栈帧传播出去时记录在stackTrace中，构造时由fillInStackTrace记录在backtrace中
+        let stack_trace = ["stackTrace", "backtrace"]
+            .iter()
+            .filter_map(|name| exception.get_field_by_name(name).ok()?.get_array().ok())
+            .find(|array_ref| array_ref.get_data_length() > 0);
+        for element in stack_trace.map(|v| v.read_all()).unwrap_or_default() {
+            let Ok(element) = element.get_object() else {
+                continue;
+            };
+            let string_field = |name: &str| {
+                element
+                    .get_field_by_name(name)
+                    .and_then(|value| value.get_string())
+                    .ok()
+            };
+            let element = StackTraceElement {
+                declaring_class: string_field("declaringClass").unwrap_or_default(),
+                method_name: string_field("methodName").unwrap_or_default(),
+                file_name: string_field("fileName"),
+                line_number: element
+                    .get_field_by_name("lineNumber")
+                    .and_then(|value| value.get_int())
+                    .unwrap_or_default() as u16,
+            };
+            result.push('\n');
+            result.push_str(&element.to_string());
+        }
+        result
+    }
+
+    /// 按类名统计堆上的对象数量，数组按数组类名统计，例如[I
+    pub fn heap_report(&self) -> HashMap<String, usize> {
+        let mut report = HashMap::new();
+        for value in self.object_heap.iter_references() {
+            let class_name = match value {
+                Value::ObjectRef(object_ref) => object_ref.get_class().name.clone(),
+                Value::ArrayRef(array_ref) => array_ref.get_array_type().array_class_name(),
+                _ => continue,
+            };
+            *report.entry(class_name).or_insert(0) += 1;
+        }
+        report
+    }
+
+    pub(crate) fn static_area(&self) -> &StaticArea<'a> {
+        &self.static_area
+    }
+
+    pub(crate) fn class_loaders(&self) -> Vec<ObjectReference<'a>> {
+        self.method_area.class_loaders()
+    }
+
+    pub fn get_static(&self, class_ref: ClassRef<'a>, field_name: &str) -> Option<&Value<'a>> {
+        self.static_area.get_static_field(class_ref, field_name)
+    }
+
+    pub fn set_static(&mut self, class_ref: ClassRef<'a>, field_name: &str, value: Value<'a>) {
+        self.static_area
+            .set_static_field(class_ref, field_name, value);
+    }
+    pub fn get_class_by_name(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+    ) -> Result<ClassRef<'a>, MethodCallError<'a>> {
+        //防止重复加载
+        let class_ref = if !self.method_area.is_class_loaded(class_name) {
+            self.lookup_class_and_initialize(call_stack, class_name)?
+        } else {
+            let class_ref = self.load_bootstrap_class(call_stack, class_name)?;
+            //初始化失败的类每次使用都要抛出NoClassDefFoundError
+            if class_ref.status.get() == ClassStatus::Erroneous {
+                self.initialize_class(call_stack, class_ref)?;
+            }
+            class_ref
+        };
+        Ok(class_ref)
+    }
+    pub fn get_static_field_by_class_name(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        field_name: &str,
+    ) -> Result<Option<&Value<'a>>, MethodCallError<'a>> {
+        //防止重复加载
+        let class_ref = self.get_class_by_name(call_stack, class_name)?;
+        let value = self.static_area.get_static_field(class_ref, field_name);
+        Ok(value)
+    }
+
+    pub fn set_static_field_by_class_name(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_name: &str,
+        field_name: &str,
+        value: Value<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        let class_ref = self.get_class_by_name(call_stack, class_name)?;
+        self.static_area
+            .set_static_field(class_ref, field_name, value);
+        Ok(())
+    }
+
+    pub fn invoke_native_method(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let span = debug_span!(
+            "invoke_native_method",
+            class = %class_ref.name,
+            method = %method_ref.name,
+            descriptor = %method_ref.descriptor,
+            depth = call_stack.depth(),
+        );
+        let _entered = span.enter();
+        debug!("native call");
+
+        let native_method = self.native_method_area.get_method(
+            &class_ref.name,
+            &method_ref.name,
+            &method_ref.descriptor,
+        );
+        let Some(native_method) = native_method else {
+            return Err(VmError::UnsatisfiedLinkError(format!(
+                "{}.{}{}",
+                class_ref.name, method_ref.name, method_ref.descriptor
+            ))
+            .into());
+        };
+        debug_assert_eq!(
+            args.len(),
+            method_ref.descriptor_args_ret.args.len(),
+            "native {}.{}{} args",
+            class_ref.name,
+            method_ref.name,
+            method_ref.descriptor
+        );
+        native_method(self, call_stack, object.map(|e| e.as_value()), args)
+    }
+
+    pub fn new_exception_stack_trace_element(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        stack_trace_element: &[StackTraceElement],
+    ) -> Result<ArrayReference<'a>, MethodCallError<'a>> {
+        let stack_trace_class =
+            self.lookup_class_and_initialize(call_stack, "java/lang/StackTraceElement")?;
+        let reference = self.new_array(
+            call_stack,
+            ArrayElement::ClassReference(stack_trace_class),
+            stack_trace_element.len(),
+        )?;
+        for (index, trace) in stack_trace_element.iter().enumerate() {
+            let trace_ele = self.new_object(call_stack, stack_trace_class)?;
+            let declaring_class = self.new_java_lang_string_object(
+                call_stack,
+                &trace.declaring_class.replace('/', "."),
+            )?;
+            let method_name = self.new_java_lang_string_object(call_stack, &trace.method_name)?;
+            let file_name = match &trace.file_name {
+                Some(file_name) => {
+                    Value::ObjectRef(self.new_java_lang_string_object(call_stack, file_name)?)
+                }
+                None => Value::Null,
+            };
+            trace_ele.set_field_by_name("declaringClass", &Value::ObjectRef(declaring_class))?;
+            trace_ele.set_field_by_name("methodName", &Value::ObjectRef(method_name))?;
+            trace_ele.set_field_by_name("fileName", &file_name)?;
+            trace_ele.set_field_by_name("lineNumber", &Value::Int(trace.line_number as i32))?;
+            reference.set_field_by_offset(index, &Value::ObjectRef(trace_ele))?;
+        }
+
+        Ok(reference)
+    }
+
+    /// 异常从栈帧传播出去时调用。异常还没有记录调用栈时，
+    /// 把当前栈帧以及所有调用者记录进stackTrace，已经记录过的不再覆盖，
+    /// 这样多层传播和catch之后重新抛出都保留最初抛出位置的完整调用链
+    pub(crate) fn capture_stack_trace(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        exception: ObjectReference<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        //预先分配的OutOfMemoryError不记录调用栈，堆已经满了
+        if self.out_of_memory_error == Some(exception) {
+            return Ok(());
+        }
+        //构造时由fillInStackTrace记录在backtrace中的也视为已经记录
+        if !matches!(exception.get_field_by_name("backtrace")?, Value::Null) {
+            return Ok(());
+        }
+        if let Value::ArrayRef(stack_trace) = exception.get_field_by_name("stackTrace")? {
+            if stack_trace.get_data_length() > 0 {
+                return Ok(());
+            }
+        }
+        let stack_trace: Vec<StackTraceElement> = call_stack
+            .frames()
+            .rev()
+            .map(|frame| frame.to_stack_trace())
+            .collect();
+        self.set_stack_trace(call_stack, exception, &stack_trace)
+    }
+
+    fn set_stack_trace(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        exception: ObjectReference<'a>,
+        stack_trace: &[StackTraceElement],
+    ) -> Result<(), MethodCallError<'a>> {
+        for element in stack_trace {
+            debug!(exception = %exception.get_class().name, %element, "stack trace");
+        }
+        let stack_trace_array_ref =
+            self.new_exception_stack_trace_element(call_stack, stack_trace)?;
+        exception.set_field_by_name("stackTrace", &Value::ArrayRef(stack_trace_array_ref))?;
+        Ok(())
+    }
+
+    pub fn invoke_method(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        //同步方法在调用前后进入和退出监视器，静态方法锁定声明类的Class对象，实例方法锁定接收者
+        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-2.html#jvms-2.11.10
+        let monitor = if method_ref.is_synchronized() {
+            let monitor = match &object {
+                Some(object) if !method_ref.is_static() => object.as_value(),
+                _ => Value::ObjectRef(self.get_java_lang_class_object(call_stack, class_ref)?),
+            };
+            self.monitor_enter(call_stack, &monitor)?;
+            Some(monitor)
+        } else {
+            None
+        };
+        let result = match &self.call_log_filter {
+            Some(filter) if filter.matches(&class_ref.name, &method_ref.name) => {
+                self.invoke_method_with_log(call_stack, class_ref, method_ref, object, args)
+            }
+            _ => self.invoke_method_without_log(call_stack, class_ref, method_ref, object, args),
+        };
+        //方法异常返回、预算耗尽或退出时同样释放监视器，并保留原来的错误
+        match monitor {
+            Some(monitor) => {
+                let exit = self.monitor_exit(call_stack, &monitor);
+                result.and_then(|value| exit.map(|_| value))
+            }
+            None => result,
+        }
+    }
+
+    /// 和invoke_method一样调用方法，但包括嵌套调用和类初始化在内最多执行max_instructions条字节码指令，
+    /// 超出时返回MethodCallError::BudgetExceeded。调用栈上已有的预算更小时使用原来的预算，
+    /// 返回后调用栈的预算扣除本次执行的指令数
+    pub fn invoke_with_limit(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+        max_instructions: u64,
+    ) -> InvokeMethodResult<'a> {
+        let previous = call_stack.instruction_budget();
+        let budget = previous.map_or(max_instructions, |v| v.min(max_instructions));
+        call_stack.set_instruction_budget(Some(budget));
+        let result = self.invoke_method(call_stack, class_ref, method_ref, object, args);
+        let executed = budget - call_stack.instruction_budget().unwrap_or(0);
+        call_stack.set_instruction_budget(previous.map(|v| v - executed));
+        result
+    }
+
+    /// 对象监视器当前的进入次数，0表示没有被锁定
+    pub fn monitor_count(&self, object: impl ReferenceValue<'a>) -> usize {
+        self.monitors.get(&object.ptr()).copied().unwrap_or(0)
+    }
+
+    //monitorenter和同步方法调用。null抛出NullPointerException
+    pub(crate) fn monitor_enter(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        object: &Value<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        let ptr = self.monitor_ptr(call_stack, object)?;
+        *self.monitors.entry(ptr).or_insert(0) += 1;
+        Ok(())
+    }
+
+    //monitorexit和同步方法返回。没有持有监视器时抛出IllegalMonitorStateException
+    pub(crate) fn monitor_exit(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        object: &Value<'a>,
+    ) -> Result<(), MethodCallError<'a>> {
+        let ptr = self.monitor_ptr(call_stack, object)?;
+        match self.monitors.get_mut(&ptr) {
+            Some(count) if *count > 1 => *count -= 1,
+            Some(_) => {
+                self.monitors.remove(&ptr);
+            }
+            None => {
+                let exception = self.new_object_by_class_name(
+                    call_stack,
+                    "java/lang/IllegalMonitorStateException",
+                )?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+        }
+        Ok(())
+    }
+
+    fn monitor_ptr(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        object: &Value<'a>,
+    ) -> Result<*mut u8, MethodCallError<'a>> {
+        match object {
+            Value::ObjectRef(object_ref) => Ok(object_ref.ptr()),
+            Value::ArrayRef(array_ref) => Ok(array_ref.ptr()),
+            Value::Null => {
+                let exception =
+                    self.new_object_by_class_name(call_stack, "java/lang/NullPointerException")?;
+                Err(MethodCallError::ExceptionThrown(exception))
+            }
+            _ => Err(MethodCallError::InternalError(VmError::ValueTypeMissMatch)),
+        }
+    }
+
+    //输出格式：
+    //  -> [1] SwitchTest.select(Ljava/lang/String;)I args: (java.lang.String@1a2b "BB")
+    //  <- [1] SwitchTest.select(Ljava/lang/String;)I returned 2 in 15.2µs
+    fn invoke_method_with_log(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        let depth = call_stack.depth() + 1;
+        let indent = "  ".repeat(depth - 1);
+        let method = format!(
+            "{}.{}{}",
+            class_ref.name, method_ref.name, method_ref.descriptor
+        );
+        let mut rendered: Vec<String> = args.iter().map(|v| self.format_value(v)).collect();
+        if let Some(object) = &object {
+            rendered.insert(0, format!("this={}", self.format_value(&object.as_value())));
+        }
+        self.trace_line(&format!(
+            "{}-> [{}] {} args: ({})",
+            indent,
+            depth,
+            method,
+            rendered.join(", ")
+        ));
+        let started_at = Instant::now();
+        let result =
+            self.invoke_method_without_log(call_stack, class_ref, method_ref, object, args);
+        let elapsed = started_at.elapsed();
+        let outcome = match &result {
+            Ok(Some(value)) => format!("returned {}", self.format_value(value)),
+            Ok(None) => "returned void".to_string(),
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                format!("threw {}", exception.get_class().name.replace('/', "."))
+            }
+            Err(MethodCallError::InternalError(error)) => format!("failed {}", error),
+            Err(MethodCallError::BudgetExceeded) => "exceeded instruction budget".to_string(),
+            Err(MethodCallError::Cancelled) => "cancelled".to_string(),
+            Err(MethodCallError::VmExit(status)) => format!("exited with status {}", status),
+        };
+        self.trace_line(&format!(
+            "{}<- [{}] {} {} in {:?}",
+            indent, depth, method, outcome, elapsed
+        ));
+        result
+    }
+
+    fn invoke_method_without_log(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        if self.intrinsics_enabled {
+            if let Some(intrinsic) =
+                self.intrinsics
+                    .get(&class_ref.name, &method_ref.name, &method_ref.descriptor)
+            {
+                return intrinsic(self, call_stack, object.map(|e| e.as_value()), args);
+            }
+        }
+        if method_ref.is_native() {
+            return self.invoke_native_method(call_stack, class_ref, method_ref, object, args);
+        }
+        //部分JDK方法依赖虚拟机还不支持的安全检查、性能统计等设施，由虚拟机直接实现
+        if let Some(method) = self.native_method_area.get_override_method(
+            &class_ref.name,
+            &method_ref.name,
+            &method_ref.descriptor,
+        ) {
+            return method(self, call_stack, object.map(|e| e.as_value()), args);
+        }
+        //虚方法分派到抽象方法，说明类文件之间不一致。其他缺少Code的方法在创建栈帧时返回ClassFormatError
+        if method_ref.is_abstract() {
+            let error = self.new_exception(
+                call_stack,
+                "java/lang/AbstractMethodError",
+                &format!(
+                    "{}.{}{}",
+                    class_ref.name.replace('/', "."),
+                    method_ref.name,
+                    method_ref.descriptor
+                ),
+            )?;
+            return Err(MethodCallError::ExceptionThrown(error));
+        }
+        self.invoke_bytecode(call_stack, class_ref, method_ref, object, args)
+    }
+
+    //不经过intrinsic和替换实现，执行方法本身的字节码
+    pub(crate) fn invoke_bytecode(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        class_ref: ClassRef<'a>,
+        method_ref: MethodRef<'a>,
+        object: Option<impl ReferenceValue<'a>>,
+        args: Vec<Value<'a>>,
+    ) -> InvokeMethodResult<'a> {
+        //跟踪和记录需要逐条指令执行，这时不使用快速路径
+        if let Some(trivial_method) = &method_ref.trivial_method {
+            if self.trivial_methods_enabled
+                && !self.record_execution
+                && !self.frame_trace_options(class_ref, method_ref).0
+            {
+                let receiver = object.as_ref().map(|e| e.as_value());
+                if let Some(result) = trivial_method.invoke(receiver, &args) {
+                    return Ok(result);
+                }
+            }
+        }
+        //无论正常返回、抛出异常还是内部错误，都只弹出本次调用压入的栈帧，外层栈帧由各自的调用者弹出
+        let mut call_stack = call_stack.guard();
+        let mut frame = call_stack.new_frame(class_ref, method_ref, object, args)?;
+        let result = frame.as_mut().execute(self, &mut call_stack);
+        debug_assert!(call_stack.is_consistent());
+        result
+    }
+
+    pub fn clone_value(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        value: &Value<'a>,
+    ) -> Result<Value<'a>, MethodCallError<'a>> {
+        match value {
+            Value::ObjectRef(obj) => {
+                let class_ref = obj.get_class();
+                let new_ref = self.new_object(call_stack, class_ref)?;
+                obj.copy_to(&new_ref);
+                Ok(Value::ObjectRef(new_ref))
+            }
+            Value::ArrayRef(arr) => {
+                let header = arr.get_array_header();
+                let new_ref = self.new_array(call_stack, header.element, header.array_size)?;
+                arr.copy_to(&new_ref);
+                Ok(Value::ArrayRef(new_ref))
+            }
+
+            _ => Ok(value.clone()),
+        }
+    }
+
+    /// 标记从根可达的对象，对不可达且重写了finalize()V的对象执行一次finalize。
+    /// 根包括静态字段、常量池中的字符串和Class对象、类加载器、call_stack中所有栈帧的
+    /// 局部变量和操作数栈，以及调用方持有的roots。
+    /// 堆是顺序分配的，目前还不能回收单个对象，finalize之后对象仍然留在堆上。
+    /// 返回本次执行finalize的对象数量
+    pub fn collect_garbage(
+        &mut self,
+        call_stack: &mut CallStack<'a>,
+        roots: &[Value<'a>],
+    ) -> Result<usize, MethodCallError<'a>> {
+        let mut pending: VecDeque<Value<'a>> = roots.iter().cloned().collect();
+        pending.extend(self.static_area.iter_reference_values());
+        pending.extend(
+            self.method_area
+                .class_loaders()
+                .into_iter()
+                .map(Value::ObjectRef),
+        );
+        for frame in call_stack.frames() {
+            for local in &frame.local_var_table {
+                if let LocalValue::Entry(value) = local {
+                    pending.push_back(value.clone());
+                }
+            }
+            pending.extend(frame.op_stack.values().iter().cloned());
+        }
+        let mut marked = HashSet::new();
+        while let Some(value) = pending.pop_front() {
+            match value {
+                Value::ObjectRef(object_ref) => {
+                    if !marked.insert(object_ref.ptr()) {
+                        continue;
+                    }
+                    let mut current = Some(object_ref.get_class());
+                    while let Some(class_ref) = current {
+                        for field in class_ref.fields.values().filter(|f| !f.is_static()) {
+                            pending.push_back(object_ref.get_field_value(field)?);
+                        }
+                        current = class_ref.super_class;
+                    }
+                }
+                Value::ArrayRef(array_ref) if marked.insert(array_ref.ptr()) => {
+                    pending.extend(array_ref.read_all());
+                }
+                _ => {}
+            }
+        }
+
+        let unreachable: Vec<ObjectReference<'a>> = self
+            .object_heap
+            .iter_objects()
+            .filter(|object_ref| {
+                !marked.contains(&object_ref.ptr())
+                    && !self.finalized_objects.contains(&object_ref.ptr())
+            })
+            .collect();
+        let mut finalized = 0;
+        for object_ref in unreachable {
+            self.finalized_objects.insert(object_ref.ptr());
+            //Object.finalize是空方法，只有重写了的类才需要执行
+            let (class_ref, method_ref) = object_ref
+                .get_class()
+                .get_method_by_checking_super("finalize", "()V")?;
+            if class_ref.name == "java/lang/Object" {
+                continue;
+            }
+            //finalize抛出的异常会被忽略
+            //https://docs.oracle.com/javase/specs/jls/se8/html/jls-12.html#jls-12.6
+            match self.invoke_method(call_stack, class_ref, method_ref, Some(object_ref), vec![]) {
+                Ok(_) | Err(MethodCallError::ExceptionThrown(_)) => finalized += 1,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(finalized)
+    }
+
+    /// 创建一个新的调用栈，由调用者持有，调用方法时传入
+    pub fn create_call_stack(&self) -> CallStack<'a> {
+        CallStack::new()
+    }
+}
+
+#[allow(unused_imports)]
+mod tests {
+    #[cfg(test)]
+    use crate::class_finder::MemoryClassPath;
+    use crate::jvm_values::Value;
+    use crate::virtual_machine::VirtualMachine;
+    #[cfg(test)]
+    use class_file_reader::class_file_builder::ClassFileBuilder;
+
+    #[test]
+    fn test_thread() {
+        let _ = env_logger::try_init();
+        use crate::jvm_values::ObjectReference;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "ThreadTest")
+            .unwrap();
+        assert_eq!(class_ref.status.get(), ClassStatus::Initialized);
+
+        let method_ref = class_ref
+            .get_method("main", "([Ljava/lang/String;)V")
+            .unwrap();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_field_value() {
+        use crate::jvm_values::ObjectReference;
+        use crate::jvm_values::ReferenceValue;
+        use crate::jvm_values::Value;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "FieldTest")
+            .unwrap();
+        assert_eq!(class_ref.status.get(), ClassStatus::Initialized);
+        //测试初始化数据
+        //由ConstantValue设置的初始值
+        let name = vm.get_static(class_ref, "NAME").unwrap();
+        assert_eq!(name.get_string().unwrap(), "static");
+        //由<clinit>的初始值
+        let an_int = vm
+            .get_static(class_ref, "anInt")
+            .unwrap()
+            .get_int()
+            .unwrap();
+        assert_eq!(2, an_int);
+        //初始化对象
+        let object_ref = vm.new_object(call_stack, class_ref).unwrap();
+        let a = object_ref.get_field_by_name("a").unwrap();
+        assert_eq!(a.get_int().unwrap(), 0i32);
+        let b = object_ref.get_field_by_name("b").unwrap();
+        assert_eq!(b, Value::Null);
+
+        let field_double = object_ref.get_field_by_name("fieldDouble").unwrap();
+        assert_eq!(field_double.get_double().unwrap(), 0f64);
+
+        //调用初始化方法
+        let init_method = class_ref.get_method("<init>", "()V").unwrap();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            init_method,
+            Some(object_ref),
+            Vec::new(),
+        )
+        .unwrap();
+        let field_double = object_ref.get_field_by_name("fieldDouble").unwrap();
+        //初始化后fieldDouble应该是100
+        assert_eq!(field_double.get_double().unwrap(), 100f64);
+        let field_float = object_ref.get_field_by_name("fieldFloat").unwrap();
+        //初始化后fieldFloat应该是50
+        assert_eq!(field_float.get_float().unwrap(), 50f32);
+
+        //测试方法调用
+        let main_method = class_ref.get_method("increaseInt", "()V").unwrap();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            main_method,
+            None::<ObjectReference>,
+            Vec::new(),
+        )
+        .unwrap();
+        let an_int = vm.get_static(class_ref, "anInt");
+        assert!(matches!(an_int, Some(Value::Int(3))));
+    }
+
+    #[test]
+    fn test_exception() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::ReferenceValue;
+        use crate::jvm_values::Value;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let _ = env_logger::try_init();
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "ExceptionTest")
+            .unwrap();
+        assert_eq!(class_ref.status.get(), ClassStatus::Initialized);
+        let obj_ref = vm.new_object(call_stack, class_ref).unwrap();
+
+        //测试异常try-catch
+        let method_recovery = class_ref.get_method("methodRecovery", "()I").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_recovery,
+                Some(obj_ref),
+                Vec::new(),
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(result, Value::Int(2));
+
+        //测试抛出异常
+        let throw_null_pointer_exception = class_ref
+            .get_method("throwNullPointException", "()I")
+            .unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            throw_null_pointer_exception,
+            Some(obj_ref),
+            Vec::new(),
+        );
+        if let Err(MethodCallError::ExceptionThrown(exp)) = result {
+            let x = exp.get_class();
+            assert_eq!(x.name, "java/lang/NullPointerException")
+        }
+
+        //测试异常堆栈信息
+        let throw_null_pointer_exception = class_ref
+            .get_method("methodStackTrace", "()[Ljava/lang/StackTraceElement;")
+            .unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                throw_null_pointer_exception,
+                Some(obj_ref),
+                Vec::new(),
+            )
+            .unwrap();
+        if let Some(Value::ArrayRef(array_ref)) = result {
+            assert_eq!(array_ref.get_data_length(), 2);
+        } else {
+            panic!("should has stack trace element");
+        }
+    }
+
+    #[test]
+    fn test_string_switch() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "SwitchTest")
+            .unwrap();
+
+        //String.hashCode依赖caload的无符号扩展和乘法溢出回绕
+        let hash_method = class_ref
+            .get_method("hash", "(Ljava/lang/String;)I")
+            .unwrap();
+        for input in [
+            "Aa",
+            "BB",
+            "banana",
+            "\u{8000}\u{ffff}",
+            "lite-jvm-rust:hash",
+        ] {
+            let string_ref = vm.new_java_lang_string_object(call_stack, input).unwrap();
+            let result = vm
+                .invoke_method(
+                    call_stack,
+                    class_ref,
+                    hash_method,
+                    None::<ObjectReference>,
+                    vec![Value::ObjectRef(string_ref)],
+                )
+                .unwrap();
+            let expected = input
+                .encode_utf16()
+                .fold(0i32, |h, c| h.wrapping_mul(31).wrapping_add(c as i32));
+            assert_eq!(result, Some(Value::Int(expected)), "hash of {input}");
+        }
+
+        let select_method = class_ref
+            .get_method("select", "(Ljava/lang/String;)I")
+            .unwrap();
+        //"Aa"和"BB"的hashCode相同，需要通过equals区分
+        for (input, expected) in [
+            ("Aa", 1),
+            ("BB", 2),
+            ("apple", 3),
+            ("banana", 4),
+            ("cherry", 5),
+            ("C#", -1),
+            ("durian", -1),
+        ] {
+            let string_ref = vm.new_java_lang_string_object(call_stack, input).unwrap();
+            let result = vm
+                .invoke_method(
+                    call_stack,
+                    class_ref,
+                    select_method,
+                    None::<ObjectReference>,
+                    vec![Value::ObjectRef(string_ref)],
+                )
+                .unwrap();
+            assert_eq!(result, Some(Value::Int(expected)), "select {input}");
+        }
+    }
+
+    #[test]
+    fn test_interface_iterator() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "InterfaceTest")
+            .unwrap();
+
+        //通过Iterator接口遍历ArrayList求和
+        let sum_method = class_ref.get_method("sumByIterator", "(I)I").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                sum_method,
+                None::<ObjectReference>,
+                vec![Value::Int(10)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(55)));
+
+        //for-each语法同样编译为Iterator的invokeinterface
+        let sum_method = class_ref.get_method("sumByForEach", "(I)J").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                sum_method,
+                None::<ObjectReference>,
+                vec![Value::Int(5)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Long(15_000_000_000)));
+
+        //通过子接口调用父接口中的default方法
+        let default_method = class_ref.get_method("callInheritedDefault", "()I").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                default_method,
+                None::<ObjectReference>,
+                Vec::new(),
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(42)));
+
+        //(JID)J的count为6，long和double各占两个字
+        let wide_method = class_ref.get_method("callWideArguments", "()J").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                wide_method,
+                None::<ObjectReference>,
+                Vec::new(),
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Long((1i64 << 40) + 7)));
+    }
+
+    #[test]
+    fn test_enum() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::loaded_class::ClassRef;
+        use crate::stack::CallStack;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "EnumTest")
+            .unwrap();
+        fn invoke<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            class_ref: ClassRef<'a>,
+            name: &str,
+            descriptor: &str,
+            args: Vec<Value<'a>>,
+        ) -> Value<'a> {
+            let method_ref = class_ref.get_method(name, descriptor).unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                args,
+            )
+            .unwrap()
+            .unwrap()
+        }
+
+        //枚举常量在<clinit>中创建，通过getstatic访问
+        let green = invoke(
+            &mut vm,
+            call_stack,
+            class_ref,
+            "green",
+            "()LEnumTest$Color;",
+            Vec::new(),
+        );
+        let color_ref = vm
+            .lookup_class_and_initialize(call_stack, "EnumTest$Color")
+            .unwrap();
+        assert_eq!(vm.get_static(color_ref, "GREEN"), Some(&green));
+
+        //values()返回$VALUES的克隆，修改返回的数组不影响$VALUES
+        let length = invoke(
+            &mut vm,
+            call_stack,
+            class_ref,
+            "valuesLength",
+            "()I",
+            Vec::new(),
+        );
+        assert_eq!(length, Value::Int(3));
+        let is_copy = invoke(
+            &mut vm,
+            call_stack,
+            class_ref,
+            "valuesIsCopy",
+            "()Z",
+            Vec::new(),
+        );
+        assert_eq!(is_copy, Value::Int(1));
+
+        for (index, name) in ["RED", "GREEN", "BLUE"].iter().enumerate() {
+            let args = vec![Value::Int(index as i32)];
+            let ordinal = invoke(
+                &mut vm,
+                call_stack,
+                class_ref,
+                "ordinalOf",
+                "(I)I",
+                args.clone(),
+            );
+            assert_eq!(ordinal, Value::Int(index as i32));
+            let value = invoke(
+                &mut vm,
+                call_stack,
+                class_ref,
+                "nameOf",
+                "(I)Ljava/lang/String;",
+                args,
+            );
+            assert_eq!(value.get_string().unwrap(), *name);
+
+            //Enum.valueOf被替换为直接调用values()按名称查找，不经过Class.enumConstantDirectory
+            let name_ref = vm.new_java_lang_string_object(call_stack, name).unwrap();
+            let ordinal = invoke(
+                &mut vm,
+                call_stack,
+                class_ref,
+                "valueOfOrdinal",
+                "(Ljava/lang/String;)I",
+                vec![Value::ObjectRef(name_ref)],
+            );
+            assert_eq!(ordinal, Value::Int(index as i32));
+        }
+        //不存在的常量名抛出IllegalArgumentException
+        let unknown = vm
+            .new_java_lang_string_object(call_stack, "PURPLE")
+            .unwrap();
+        let rejected = invoke(
+            &mut vm,
+            call_stack,
+            class_ref,
+            "valueOfRejects",
+            "(Ljava/lang/String;)Z",
+            vec![Value::ObjectRef(unknown)],
+        );
+        assert_eq!(rejected, Value::Int(1));
+
+        //switch通过合成类EnumTest$1的$SwitchMap数组和tableswitch实现，该类在第一次switch时才初始化
+        assert!(!vm.method_area.is_class_loaded("EnumTest$1"));
+        for (name, expected) in [("RED", 10), ("GREEN", 20), ("BLUE", 30)] {
+            let color = vm.get_static(color_ref, name).unwrap().clone();
+            let result = invoke(
+                &mut vm,
+                call_stack,
+                class_ref,
+                "describe",
+                "(LEnumTest$Color;)I",
+                vec![color],
+            );
+            assert_eq!(result, Value::Int(expected));
+        }
+        let switch_map_ref = vm
+            .lookup_class_and_initialize(call_stack, "EnumTest$1")
+            .unwrap();
+        let switch_map = vm
+            .get_static(switch_map_ref, "$SwitchMap$EnumTest$Color")
+            .unwrap()
+            .get_array()
+            .unwrap()
+            .read_all();
+        assert_eq!(
+            switch_map,
+            vec![Value::Int(1), Value::Int(2), Value::Int(3)]
+        );
+    }
+
+    #[test]
+    fn test_default_method() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "DefaultMethodTest")
+            .unwrap();
+        //Plain没有重写greet，继承Greeter的默认方法
+        //Loud同时实现Greeter和LoudGreeter，选择更具体的LoudGreeter.greet
+        //idPlusGreet是默认方法，内部调用Loud实现的抽象方法id
+        for (name, expected) in [
+            ("plainGreet", 1),
+            ("loudGreet", 2),
+            ("loudGreetViaInterface", 2),
+            ("inheritedDefaultCallingAbstract", 105),
+        ] {
+            let method_ref = class_ref.get_method(name, "()I").unwrap();
+            let result = vm
+                .invoke_method(
+                    call_stack,
+                    class_ref,
+                    method_ref,
+                    None::<ObjectReference>,
+                    Vec::new(),
+                )
+                .unwrap();
+            assert_eq!(result, Some(Value::Int(expected)), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_abstract_method_error() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "AbstractMethodTest")
+            .unwrap();
+        //Square编译时Shape.area还是默认方法，之后Shape重新编译为抽象方法
+        let method_ref = class_ref.get_method("callArea", "()I").unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        );
+        let Err(MethodCallError::ExceptionThrown(error)) = result else {
+            panic!("expect AbstractMethodError, got {:?}", result);
+        };
+        assert_eq!(error.get_class().name, "java/lang/AbstractMethodError");
+        let message = error.get_field_by_name("detailMessage").unwrap();
+        assert_eq!(
+            message.get_string().unwrap(),
+            "AbstractMethodTest$Shape.area()I"
+        );
+
+        //通过接口引用调用时同样抛出AbstractMethodError，可以被Java代码捕获
+        let result = vm
+            .call(
+                call_stack,
+                "AbstractMethodTest",
+                "callAreaThroughInterface",
+                "()I",
+                None,
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(-1)));
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_missing_code_attribute() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        //value既不是abstract也不是native，但Code属性被删除
+        let result = vm.call(
+            call_stack,
+            "StrippedCodeTest",
+            "callValue",
+            "()I",
+            None,
+            vec![],
+        );
+        match result {
+            Err(MethodCallError::InternalError(VmError::ClassFormatError(message))) => {
+                assert_eq!(message, "StrippedCodeTest.value()I has no Code attribute")
+            }
+            _ => panic!("expected ClassFormatError, got {:?}", result),
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_class_initializer_error() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "InitErrorTest")
+            .unwrap();
+        let method_ref = class_ref.get_method("readBroken", "()I").unwrap();
+
+        //第一次访问时<clinit>抛出NullPointerException，被包装为ExceptionInInitializerError
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        );
+        let Err(MethodCallError::ExceptionThrown(error)) = result else {
+            panic!("expect ExceptionInInitializerError, got {:?}", result);
+        };
+        assert_eq!(
+            error.get_class().name,
+            "java/lang/ExceptionInInitializerError"
+        );
+        let Value::ObjectRef(cause) = error.get_field_by_name("exception").unwrap() else {
+            panic!("cause should be an object");
+        };
+        assert_eq!(cause.get_class().name, "java/lang/NullPointerException");
+        assert_eq!(
+            error.get_field_by_name("cause").unwrap(),
+            Value::ObjectRef(cause)
+        );
+
+        //之后的访问直接抛出NoClassDefFoundError，不会再执行<clinit>
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        );
+        let Err(MethodCallError::ExceptionThrown(error)) = result else {
+            panic!("expect NoClassDefFoundError, got {:?}", result);
+        };
+        assert_eq!(error.get_class().name, "java/lang/NoClassDefFoundError");
+        assert_eq!(
+            error
+                .get_field_by_name("detailMessage")
+                .unwrap()
+                .get_string()
+                .unwrap(),
+            "Could not initialize class InitErrorTest$Broken"
+        );
+        assert_eq!(vm.get_static(class_ref, "initCount"), Some(&Value::Int(1)));
+        let broken_ref = vm.method_area.load_class("InitErrorTest$Broken").unwrap();
+        assert_eq!(broken_ref.status.get(), ClassStatus::Erroneous);
+    }
+
+    #[test]
+    fn test_heap_report() {
+        use crate::jvm_values::{ArrayElement, PrimaryType};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "FieldTest")
+            .unwrap();
+        let before = vm.heap_report();
+        assert_eq!(before.get("FieldTest"), None);
+
+        for _ in 0..10 {
+            vm.new_object(call_stack, class_ref).unwrap();
+        }
+        vm.new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 4)
+            .unwrap();
+        let report = vm.heap_report();
+        assert_eq!(report.get("FieldTest"), Some(&10));
+        assert_eq!(
+            report.get("[I").copied().unwrap_or_default(),
+            before.get("[I").copied().unwrap_or_default() + 1
+        );
+    }
+
+    #[test]
+    fn test_array_of_arrays_store() {
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "ArrayOfArrays")
+            .unwrap();
+        for (name, expected) in [("store", 1), ("load", 3 + 7), ("covariantStore", 2)] {
+            let method_ref = class_ref.get_method(name, "()I").unwrap();
+            let result = vm
+                .invoke_method(
+                    call_stack,
+                    class_ref,
+                    method_ref,
+                    None::<ObjectReference>,
+                    vec![],
+                )
+                .unwrap();
+            assert_eq!(result, Some(Value::Int(expected)), "{name}");
+        }
+        //Object[]的元素读回来仍然是数组
+        let method_ref = class_ref
+            .get_method("nested", "()[Ljava/lang/Object;")
+            .unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            )
+            .unwrap();
+        let array = result.unwrap().get_array().unwrap();
+        assert!(matches!(
+            array.get_field_by_offset(0),
+            Ok(Value::ArrayRef(_))
+        ));
+    }
+
+    #[test]
+    fn test_field_type_verification() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ArrayElement, ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //VerifyTest.class被修改过，aconst_null替换为iconst_0
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "VerifyTest")
+            .unwrap();
+        let object_ref = vm.new_object(call_stack, class_ref).unwrap();
+        let object_class = vm
+            .lookup_class_and_initialize(call_stack, "java/lang/Object")
+            .unwrap();
+        let array_ref = vm
+            .new_array(call_stack, ArrayElement::ClassReference(object_class), 1)
+            .unwrap();
+        for (name, descriptor, args) in [
+            (
+                "putFieldInt",
+                "(LVerifyTest;)V",
+                vec![Value::ObjectRef(object_ref)],
+            ),
+            ("putStaticInt", "()V", vec![]),
+            (
+                "storeIntIntoObjectArray",
+                "([Ljava/lang/Object;)V",
+                vec![Value::ArrayRef(array_ref)],
+            ),
+        ] {
+            let method_ref = class_ref.get_method(name, descriptor).unwrap();
+            let result = vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                args,
+            );
+            assert!(
+                matches!(
+                    result,
+                    Err(MethodCallError::InternalError(VmError::VerifyError(_)))
+                ),
+                "{name}: {result:?}"
+            );
+        }
+        //写入失败时字段和数组保持原值
+        assert_eq!(object_ref.get_field_by_name("value"), Ok(Value::Null));
+        assert_eq!(vm.get_static(class_ref, "staticValue"), Some(&Value::Null));
+        assert_eq!(array_ref.get_field_by_offset(0), Ok(Value::Null));
+
+        //Object类型的字段可以存放数组，读取时按照被引用的分配还原为数组
+        object_ref
+            .set_field_by_name("value", &Value::ArrayRef(array_ref))
+            .unwrap();
+        let read_value = class_ref
+            .get_method("readValue", "(LVerifyTest;)Ljava/lang/Object;")
+            .unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                read_value,
+                None::<ObjectReference>,
+                vec![Value::ObjectRef(object_ref)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::ArrayRef(array_ref)));
+    }
+
+    #[test]
+    fn test_describe_value() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let string_ref = vm
+            .new_java_lang_string_object(call_stack, "hi \"vm\"")
+            .unwrap();
+        assert_eq!(
+            vm.describe_value(&Value::ObjectRef(string_ref)),
+            "\"hi \\\"vm\\\"\""
+        );
+        assert_eq!(vm.describe_value(&Value::Long(3)), "3L");
+        assert_eq!(vm.describe_value(&Value::Null), "null");
+    }
+
+    #[test]
+    fn test_static_long_double() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "StaticWideTest")
+            .unwrap();
+        //未赋值的long/double静态字段默认为0
+        assert_eq!(vm.get_static(class_ref, "longValue"), Some(&Value::Long(0)));
+        assert_eq!(
+            vm.get_static(class_ref, "doubleValue"),
+            Some(&Value::Double(0.0))
+        );
+
+        let mut invoke = |name: &str, descriptor: &str| {
+            let method_ref = class_ref.get_method(name, descriptor).unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                Vec::new(),
+            )
+            .unwrap()
+        };
+        //putstatic/getstatic需要保留完整的64位
+        assert_eq!(
+            invoke("storeLong", "()J"),
+            Some(Value::Long(0x1122334455667788))
+        );
+        let result = invoke("storeDouble", "()D").unwrap().get_double().unwrap();
+        assert_eq!(result.to_bits(), std::f64::consts::PI.to_bits());
+        //相邻的int静态字段不受影响
+        assert_eq!(invoke("neighbours", "()I"), Some(Value::Int(12)));
+
+        assert_eq!(
+            vm.get_static(class_ref, "longValue"),
+            Some(&Value::Long(0x1122334455667788))
+        );
+        vm.set_static_field_by_class_name(
+            call_stack,
+            "StaticWideTest",
+            "doubleValue",
+            Value::Double(-1.0e-300),
+        )
+        .unwrap();
+        let value = vm
+            .get_static_field_by_class_name(call_stack, "StaticWideTest", "doubleValue")
+            .unwrap()
+            .unwrap()
+            .get_double()
+            .unwrap();
+        assert_eq!(value.to_bits(), (-1.0e-300f64).to_bits());
+    }
+
+    #[test]
+    fn test_user_class_loader() {
+        use crate::jvm_values::{
+            ArrayElement, ObjectReference, PrimaryType, ReferenceValue, Value,
+        };
+        use crate::loaded_class::ClassRef;
+        use crate::stack::CallStack;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //patched目录不在类路径中，其中的Versioned只能由自定义类加载器定义
+        let bytes = std::fs::read("./resources/patched/Versioned.class").unwrap();
+        let byte_array = vm
+            .new_array(
+                call_stack,
+                ArrayElement::PrimaryValue(PrimaryType::Byte),
+                bytes.len(),
+            )
+            .unwrap();
+        for (index, byte) in bytes.iter().enumerate() {
+            byte_array
+                .set_field_by_offset(index, &Value::Int(*byte as i8 as i32))
+                .unwrap();
+        }
+        let loader_class = vm
+            .lookup_class_and_initialize(call_stack, "PatchingClassLoader")
+            .unwrap();
+        let init_method = loader_class
+            .get_method("<init>", "(Ljava/lang/String;[B)V")
+            .unwrap();
+        let target = vm
+            .new_java_lang_string_object(call_stack, "Versioned")
+            .unwrap();
+        let mut loaders = Vec::new();
+        for _ in 0..2 {
+            let loader = vm.new_object(call_stack, loader_class).unwrap();
+            vm.invoke_method(
+                call_stack,
+                loader_class,
+                init_method,
+                Some(loader),
+                vec![Value::ObjectRef(target), Value::ArrayRef(byte_array)],
+            )
+            .unwrap();
+            loaders.push(loader);
+        }
+        let (loader, other_loader) = (loaders[0], loaders[1]);
+
+        fn for_name<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            class_name: ObjectReference<'a>,
+            loader: ObjectReference<'a>,
+        ) -> ObjectReference<'a> {
+            let (class_ref, method_ref) = vm
+                .lookup_method(
+                    call_stack,
+                    "ClassLoaderTest",
+                    "load",
+                    "(Ljava/lang/String;Ljava/lang/ClassLoader;)Ljava/lang/Class;",
+                )
+                .unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![Value::ObjectRef(class_name), Value::ObjectRef(loader)],
+            )
+            .unwrap()
+            .unwrap()
+            .get_object()
+            .unwrap()
+        }
+        let class_object = for_name(&mut vm, call_stack, target, loader);
+        let patched = vm
+            .get_class_ref_of_class_object(call_stack, class_object)
+            .unwrap();
+        assert_eq!(patched.class_loader, Some(loader));
+        assert_eq!(
+            class_object.get_field_by_name("classLoader").unwrap(),
+            Value::ObjectRef(loader)
+        );
+        //再次加载返回同一个Class对象
+        assert_eq!(for_name(&mut vm, call_stack, target, loader), class_object);
+
+        fn version<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            class_ref: ClassRef<'a>,
+        ) -> Option<Value<'a>> {
+            let method_ref = class_ref.get_method("version", "()I").unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                Vec::new(),
+            )
+            .unwrap()
+        }
+        //同名的两个版本共存，静态字段相互独立
+        let original = vm
+            .lookup_class_and_initialize(call_stack, "Versioned")
+            .unwrap();
+        assert!(!std::ptr::eq(original, patched));
+        assert_eq!(original.class_loader, None);
+        assert_eq!(
+            version(&mut vm, call_stack, original),
+            Some(Value::Int(101))
+        );
+        assert_eq!(version(&mut vm, call_stack, patched), Some(Value::Int(102)));
+        assert_eq!(version(&mut vm, call_stack, patched), Some(Value::Int(102)));
+        assert_eq!(vm.get_static(original, "calls"), Some(&Value::Int(1)));
+        assert_eq!(vm.get_static(patched, "calls"), Some(&Value::Int(2)));
+
+        //父类和方法中引用的类都通过该加载器的loadClass解析，最终委派给引导类加载器
+        let helper = vm
+            .lookup_class_and_initialize(call_stack, "VersionedHelper")
+            .unwrap();
+        assert!(std::ptr::eq(
+            vm.find_loaded_class(loader, "VersionedHelper").unwrap(),
+            helper
+        ));
+        assert!(vm.find_loaded_class(loader, "java/lang/Object").is_some());
+        assert_eq!(
+            loader.get_field_by_name("loadRequests").unwrap(),
+            Value::Int(3)
+        );
+
+        let other_class_object = for_name(&mut vm, call_stack, target, other_loader);
+        let other = vm
+            .get_class_ref_of_class_object(call_stack, other_class_object)
+            .unwrap();
+        assert!(!std::ptr::eq(other, patched));
+        assert_eq!(other.class_loader, Some(other_loader));
+        assert_eq!(vm.get_static(other, "calls"), Some(&Value::Int(0)));
+
+        //defineClass的范围或者类名不正确时抛出Java异常，不会定义类
+        let range_loader = vm.new_object(call_stack, loader_class).unwrap();
+        vm.invoke_method(
+            call_stack,
+            loader_class,
+            init_method,
+            Some(range_loader),
+            vec![Value::ObjectRef(target), Value::ArrayRef(byte_array)],
+        )
+        .unwrap();
+        let define_range = loader_class
+            .get_method("defineRange", "(Ljava/lang/String;II)Ljava/lang/Class;")
+            .unwrap();
+        let length = bytes.len() as i32;
+        let out_of_bounds = "java/lang/IndexOutOfBoundsException";
+        for (name, offset, length, expected) in [
+            ("Versioned", -1, length, out_of_bounds),
+            ("Versioned", 0, -1, out_of_bounds),
+            ("Versioned", 1, length, out_of_bounds),
+            ("Other", 0, length, "java/lang/NoClassDefFoundError"),
+            ("Versioned", 0, length, "java/lang/Class"),
+        ] {
+            let name = vm.new_java_lang_string_object(call_stack, name).unwrap();
+            let result = vm.invoke_method(
+                call_stack,
+                loader_class,
+                define_range,
+                Some(range_loader),
+                vec![
+                    Value::ObjectRef(name),
+                    Value::Int(offset),
+                    Value::Int(length),
+                ],
+            );
+            let object = match result {
+                Ok(Some(value)) => value.get_object().unwrap(),
+                Err(crate::java_exception::MethodCallError::ExceptionThrown(exception)) => {
+                    exception
+                }
+                other => panic!("unexpected result {:?}", other),
+            };
+            assert_eq!(object.get_class().name, expected);
+        }
+        assert_eq!(
+            vm.find_loaded_class(range_loader, "Versioned")
+                .unwrap()
+                .class_loader,
+            Some(range_loader)
+        );
+    }
+
+    #[test]
+    fn test_exception_handler_range() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "ExceptionRangeTest")
+            .unwrap();
+
+        let method_ref = class_ref.get_method("thrownInsideRange", "()I").unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(-1)));
+
+        //抛出异常的指令恰好位于end_pc，不能被该处理器捕获
+        let method_ref = class_ref.get_method("thrownAtEndPc", "()I").unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            Vec::new(),
+        );
+        match result {
+            Err(MethodCallError::ExceptionThrown(exception)) => assert_eq!(
+                exception.get_class().name,
+                "java/lang/IllegalStateException"
+            ),
+            _ => panic!("exception at end_pc should not be caught"),
+        }
+        assert_eq!(vm.get_static(class_ref, "marker"), Some(&Value::Int(2)));
+    }
+
+    #[test]
+    fn test_math_natives() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use std::f64::consts::{E, FRAC_PI_4, PI};
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "MathTest")
+            .unwrap();
+        let unary_method = class_ref.get_method("unary", "(ID)D").unwrap();
+        let binary_method = class_ref.get_method("binary", "(IDD)D").unwrap();
+        let mut call = |method_ref, args| {
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                args,
+            )
+            .unwrap()
+            .unwrap()
+            .get_double()
+            .unwrap()
+        };
+
+        //正负零按位比较，NaN只要求都是NaN
+        let same = |a: f64, b: f64| (a.is_nan() && b.is_nan()) || a.to_bits() == b.to_bits();
+        let unary_cases = [
+            (0, 0f64, 0f64),
+            (0, f64::INFINITY, f64::NAN),
+            (1, 0f64, 1f64),
+            (2, FRAC_PI_4, FRAC_PI_4.tan()),
+            (3, 2f64, f64::NAN),
+            (4, 1f64, 0f64),
+            (5, 1f64, FRAC_PI_4),
+            (6, 1f64, E),
+            (6, f64::NEG_INFINITY, 0f64),
+            (7, 0f64, f64::NEG_INFINITY),
+            (7, -1f64, f64::NAN),
+            (8, 1000f64, 3f64),
+            (9, 4f64, 2f64),
+            (9, -0f64, -0f64),
+            (9, -1f64, f64::NAN),
+            (10, -27f64, -3f64),
+            (11, -1.5f64, -2f64),
+            (12, -0.5f64, -0f64),
+            (13, 2.5f64, 2f64),
+            (13, 3.5f64, 4f64),
+            (14, -0f64, 0f64),
+            (14, f64::NEG_INFINITY, f64::INFINITY),
+            (15, 1f64, 1f64.sinh()),
+            (16, 0f64, 1f64),
+            (17, f64::INFINITY, 1f64),
+            (18, -0f64, -0f64),
+            (19, -1f64, f64::NEG_INFINITY),
+        ];
+        for (op, a, expected) in unary_cases {
+            let result = call(unary_method, vec![Value::Int(op), Value::Double(a)]);
+            assert!(
+                same(result, expected),
+                "unary op {} of {}: {} != {}",
+                op,
+                a,
+                result,
+                expected
+            );
+        }
+
+        let binary_cases = [
+            (0, 1f64, 1f64, FRAC_PI_4),
+            (0, 0f64, -0f64, PI),
+            (1, 2f64, 10f64, 1024f64),
+            (1, f64::NAN, 0f64, 1f64),
+            (1, f64::NAN, -0f64, 1f64),
+            (1, 1f64, f64::NAN, f64::NAN),
+            (1, -1f64, f64::INFINITY, f64::NAN),
+            (1, -8f64, 1f64 / 3f64, f64::NAN),
+            (1, -2f64, 3f64, -8f64),
+            (1, 0f64, -1f64, f64::INFINITY),
+            (1, -0f64, -1f64, f64::NEG_INFINITY),
+            (2, 3f64, 4f64, 5f64),
+            (2, f64::INFINITY, f64::NAN, f64::INFINITY),
+            (2, 1f64, f64::NAN, f64::NAN),
+            (3, 5f64, 3f64, -1f64),
+            (3, 5f64, 2f64, 1f64),
+            (3, 7f64, 2f64, -1f64),
+            (3, 4f64, 2f64, 0f64),
+            (3, -4f64, 2f64, -0f64),
+            (3, 1f64, 0f64, f64::NAN),
+            (3, f64::INFINITY, 1f64, f64::NAN),
+            (3, 1f64, f64::INFINITY, 1f64),
+        ];
+        for (op, a, b, expected) in binary_cases {
+            let result = call(
+                binary_method,
+                vec![Value::Int(op), Value::Double(a), Value::Double(b)],
+            );
+            assert!(
+                same(result, expected),
+                "binary op {} of ({}, {}): {} != {}",
+                op,
+                a,
+                b,
+                result,
+                expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_rethrow_stack_trace() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "RethrowTest")
+            .unwrap();
+        let method_names = |array: Value| -> Vec<String> {
+            let array = array.get_array().unwrap();
+            (0..array.get_data_length())
+                .map(|i| {
+                    let element = array.get_field_by_offset(i).unwrap();
+                    element
+                        .get_object()
+                        .unwrap()
+                        .get_field_by_name("methodName")
+                        .unwrap()
+                        .get_string()
+                        .unwrap()
+                })
+                .collect()
+        };
+
+        //虚拟机抛出的空指针异常穿过middle、outer两个栈帧，outer中catch后重新抛出
+        let obj_ref = vm.new_object(call_stack, class_ref).unwrap();
+        let method_ref = class_ref.get_method("outer", "()I").unwrap();
+        let result = vm.invoke_method(call_stack, class_ref, method_ref, Some(obj_ref), vec![]);
+        let exception = match result {
+            Err(MethodCallError::ExceptionThrown(exception)) => exception,
+            _ => panic!("NullPointerException should propagate out of outer"),
+        };
+        assert_eq!(exception.get_class().name, "java/lang/NullPointerException");
+        assert_eq!(call_stack.depth(), 0);
+        let stack_trace = exception.get_field_by_name("stackTrace").unwrap();
+        assert_eq!(method_names(stack_trace), vec!["inner", "middle", "outer"]);
+
+        //Java代码构造的异常，调用栈在构造时记录，不包含构造函数
+        let method_ref = class_ref
+            .get_method("constructedTrace", "()[Ljava/lang/String;")
+            .unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            )
+            .unwrap()
+            .unwrap();
+        let names: Vec<String> = {
+            let array = result.get_array().unwrap();
+            (0..array.get_data_length())
+                .map(|i| array.get_field_by_offset(i).unwrap().get_string().unwrap())
+                .collect()
+        };
+        assert_eq!(names, vec!["fail", "propagate", "constructedTrace"]);
+    }
+
+    #[test]
+    fn test_finalize_on_gc() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "FinalizeTest")
+            .unwrap();
+        for method_name in ["allocateKept", "allocateGarbage"] {
+            let method_ref = class_ref.get_method(method_name, "()V").unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            vm.get_static(class_ref, "finalizedCount"),
+            Some(&Value::Int(0))
+        );
+
+        //静态字段引用的对象可达，只有allocateGarbage中创建的对象会被finalize
+        assert_eq!(vm.collect_garbage(call_stack, &[]).unwrap(), 1);
+        assert_eq!(
+            vm.get_static(class_ref, "finalizedCount"),
+            Some(&Value::Int(1))
+        );
+        //每个对象只执行一次finalize
+        assert_eq!(vm.collect_garbage(call_stack, &[]).unwrap(), 0);
+
+        //调用方持有的引用也是根
+        let held = vm.new_object(call_stack, class_ref).unwrap();
+        assert_eq!(
+            vm.collect_garbage(call_stack, &[Value::ObjectRef(held)])
+                .unwrap(),
+            0
+        );
+        vm.set_static(class_ref, "kept", Value::Null);
+        assert_eq!(vm.collect_garbage(call_stack, &[]).unwrap(), 2);
+        assert_eq!(
+            vm.get_static(class_ref, "finalizedCount"),
+            Some(&Value::Int(3))
+        );
+    }
+
+    #[test]
+    fn test_clock_natives() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::loaded_class::ClassRef;
+        use crate::stack::CallStack;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        use std::cell::Cell;
+        use std::rc::Rc;
+        fn invoke<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            class_ref: ClassRef<'a>,
+            name: &str,
+            descriptor: &str,
+            args: Vec<Value<'a>>,
+        ) -> i64 {
+            let method_ref = class_ref.get_method(name, descriptor).unwrap();
+            vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                args,
+            )
+            .unwrap()
+            .unwrap()
+            .get_long()
+            .unwrap()
+        }
+        for intrinsics in [true, false] {
+            let mut vm = create_vm().with_intrinsics(intrinsics);
+            let call_stack = &mut vm.create_call_stack();
+            let class_ref = vm
+                .lookup_class_and_initialize(call_stack, "ClockTest")
+                .unwrap();
+            let elapsed = invoke(
+                &mut vm,
+                call_stack,
+                class_ref,
+                "elapsedAroundSleep",
+                "(J)J",
+                vec![Value::Long(10)],
+            );
+            assert!(
+                (10_000_000..5_000_000_000).contains(&elapsed),
+                "elapsed {}",
+                elapsed
+            );
+            let first = invoke(&mut vm, call_stack, class_ref, "nanos", "()J", vec![]);
+            assert!(invoke(&mut vm, call_stack, class_ref, "nanos", "()J", vec![]) >= first);
+
+            //每读取一次时钟前进1微秒
+            let ticks = Rc::new(Cell::new(0i64));
+            let clock_ticks = ticks.clone();
+            vm.set_clock(Box::new(move || {
+                let tick = clock_ticks.get();
+                clock_ticks.set(tick + 1);
+                (1_700_000_000_000 + tick, tick * 1_000)
+            }));
+            assert_eq!(
+                invoke(&mut vm, call_stack, class_ref, "millis", "()J", vec![]),
+                1_700_000_000_000
+            );
+            assert_eq!(
+                invoke(&mut vm, call_stack, class_ref, "nanos", "()J", vec![]),
+                1_000
+            );
+            assert_eq!(
+                invoke(
+                    &mut vm,
+                    call_stack,
+                    class_ref,
+                    "elapsedAroundSleep",
+                    "(J)J",
+                    vec![Value::Long(1)]
+                ),
+                1_000
+            );
+            assert_eq!(ticks.get(), 4);
+        }
+    }
+
+    #[test]
+    fn test_invoke_static_interface_method() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "StaticInterfaceTest")
+            .unwrap();
+        for (method_name, expected) in [("callFoo", 42), ("callTwice", 126), ("callInherited", 7)] {
+            let method_ref = class_ref.get_method(method_name, "()I").unwrap();
+            let result = vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            );
+            assert_eq!(
+                result.unwrap(),
+                Some(Value::Int(expected)),
+                "{}",
+                method_name
+            );
+        }
+        //通过子类调用父类的静态方法只初始化父类
+        assert_eq!(
+            vm.class_status("StaticParent"),
+            Some(ClassStatus::Initialized)
+        );
+        assert_ne!(
+            vm.class_status("StaticChild"),
+            Some(ClassStatus::Initialized)
+        );
+    }
+
+    #[test]
+    fn test_unsafe_natives() {
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "UnsafeTest")
+            .unwrap();
+        for (method_name, descriptor, expected) in [
+            ("atomicIncrement", "()I", Value::Int(17)),
+            ("atomicLong", "()J", Value::Long((1 << 40) + 2)),
+            ("compareAndSet", "()Z", Value::Int(1)),
+        ] {
+            let method_ref = class_ref.get_method(method_name, descriptor).unwrap();
+            let result = vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            );
+            assert_eq!(result.unwrap(), Some(expected), "{}", method_name);
+        }
+        let method_ref = class_ref
+            .get_method("atomicReference", "()Ljava/lang/String;")
+            .unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get_string().unwrap(), "b");
+
+        //直接调用compareAndSwapInt，第一次成功，第二次期望值不匹配
+        let unsafe_class = vm
+            .lookup_class_and_initialize(call_stack, "sun/misc/Unsafe")
+            .unwrap();
+        let the_unsafe = vm
+            .get_static(unsafe_class, "theUnsafe")
+            .unwrap()
+            .get_object()
+            .unwrap();
+        let atomic_class = vm
+            .lookup_class_and_initialize(call_stack, "java/util/concurrent/atomic/AtomicInteger")
+            .unwrap();
+        let value_offset = vm.get_static(atomic_class, "valueOffset").unwrap().clone();
+        let atomic = vm.new_object(call_stack, atomic_class).unwrap();
+        atomic.set_field_by_name("value", &Value::Int(1)).unwrap();
+        let cas = unsafe_class
+            .get_method("compareAndSwapInt", "(Ljava/lang/Object;JII)Z")
+            .unwrap();
+        for (expected, update, swapped) in [(1, 2, 1), (1, 3, 0)] {
+            let result = vm.invoke_method(
+                call_stack,
+                unsafe_class,
+                cas,
+                Some(the_unsafe),
+                vec![
+                    Value::ObjectRef(atomic),
+                    value_offset.clone(),
+                    Value::Int(expected),
+                    Value::Int(update),
+                ],
+            );
+            assert_eq!(result.unwrap(), Some(Value::Int(swapped)));
+        }
+        assert_eq!(atomic.get_field_by_name("value").unwrap(), Value::Int(2));
+    }
+
+    #[test]
+    fn test_call() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let result = vm.call(
+            call_stack,
+            "CallTest",
+            "add",
+            "(II)I",
+            None,
+            vec![Value::Int(3), Value::Int(4)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(7)));
+
+        for (class_name, expected) in [("CallTest", 15), ("ScaledCallTest", 50)] {
+            let object = vm.new_object_by_class_name(call_stack, class_name).unwrap();
+            let receiver = Some(Value::ObjectRef(object));
+            vm.call(
+                call_stack,
+                class_name,
+                "<init>",
+                "(I)V",
+                receiver.clone(),
+                vec![Value::Int(10)],
+            )
+            .unwrap();
+            //通过父类解析的方法按receiver的实际类型分派
+            let result = vm.call(
+                call_stack,
+                "CallTest",
+                "offset",
+                "(I)I",
+                receiver,
+                vec![Value::Int(5)],
+            );
+            assert_eq!(
+                result.unwrap(),
+                Some(Value::Int(expected)),
+                "{}",
+                class_name
+            );
+        }
+
+        let result = vm.call(
+            call_stack,
+            "CallTest",
+            "offset",
+            "(I)I",
+            None,
+            vec![Value::Int(5)],
+        );
+        match result {
+            Err(MethodCallError::ExceptionThrown(e)) => {
+                assert_eq!(e.get_class().name, "java/lang/IncompatibleClassChangeError")
+            }
+            _ => panic!("expected IncompatibleClassChangeError"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_float_remainder() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //(被除数, 除数, 期望结果)，结果符号与被除数相同
+        let cases = [
+            (5.0, f64::INFINITY, 5.0),
+            (-5.0, f64::NEG_INFINITY, -5.0),
+            (f64::INFINITY, 5.0, f64::NAN),
+            (5.0, 0.0, f64::NAN),
+            (5.0, -0.0, f64::NAN),
+            (f64::INFINITY, f64::INFINITY, f64::NAN),
+            (f64::NAN, 1.0, f64::NAN),
+            (-0.0, 3.0, -0.0),
+            (0.0, f64::INFINITY, 0.0),
+            (5.5, -2.0, 1.5),
+            (-5.5, 2.0, -1.5),
+            (-4.0, 2.0, -0.0),
+        ];
+        for (dividend, divisor, expected) in cases {
+            let result = vm
+                .call(
+                    call_stack,
+                    "RemainderTest",
+                    "drem",
+                    "(DD)D",
+                    None,
+                    vec![Value::Double(dividend), Value::Double(divisor)],
+                )
+                .unwrap()
+                .unwrap()
+                .get_double()
+                .unwrap();
+            let message = format!("{} % {} = {}", dividend, divisor, result);
+            if expected.is_nan() {
+                assert!(result.is_nan(), "{}", message);
+            } else {
+                assert_eq!(result.to_bits(), expected.to_bits(), "{}", message);
+            }
+
+            let (dividend, divisor, expected) = (dividend as f32, divisor as f32, expected as f32);
+            let result = vm
+                .call(
+                    call_stack,
+                    "RemainderTest",
+                    "frem",
+                    "(FF)F",
+                    None,
+                    vec![Value::Float(dividend), Value::Float(divisor)],
+                )
+                .unwrap()
+                .unwrap()
+                .get_float()
+                .unwrap();
+            let message = format!("{} % {} = {}", dividend, divisor, result);
+            if expected.is_nan() {
+                assert!(result.is_nan(), "{}", message);
+            } else {
+                assert_eq!(result.to_bits(), expected.to_bits(), "{}", message);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsigned_shift() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //移位距离int只取低5位，long只取低6位
+        let int_cases = [
+            (-8, 1, 2147483644),
+            (-8, 33, 2147483644),
+            (-1, 0, -1),
+            (-1, 31, 1),
+            (0, 5, 0),
+            (64, 3, 8),
+            (i32::MIN, 31, 1),
+        ];
+        for (value, distance, expected) in int_cases {
+            let result = vm.call(
+                call_stack,
+                "ShiftTest",
+                "iushr",
+                "(II)I",
+                None,
+                vec![Value::Int(value), Value::Int(distance)],
+            );
+            assert_eq!(
+                result.unwrap(),
+                Some(Value::Int(expected)),
+                "{} >>> {}",
+                value,
+                distance
+            );
+        }
+        let long_cases = [
+            (-8, 1, 9223372036854775804),
+            (-8, 65, 9223372036854775804),
+            (-8, 33, 0x7fff_ffff),
+            (-1, 0, -1),
+            (-1, 63, 1),
+            (1 << 40, 8, 1 << 32),
+            (i64::MIN, 63, 1),
+        ];
+        for (value, distance, expected) in long_cases {
+            let result = vm.call(
+                call_stack,
+                "ShiftTest",
+                "lushr",
+                "(JI)J",
+                None,
+                vec![Value::Long(value), Value::Int(distance)],
+            );
+            assert_eq!(
+                result.unwrap(),
+                Some(Value::Long(expected)),
+                "{} >>> {}",
+                value,
+                distance
+            );
+        }
+    }
+
+    #[test]
+    fn test_vm_usable_after_internal_error() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::Value;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //缺少native实现是内部错误，不是Java异常，调用栈要回到调用前的深度
+        let result = vm.call(
+            call_stack,
+            "MissingNativeTest",
+            "callMissing",
+            "(I)I",
+            None,
+            vec![Value::Int(3)],
+        );
+        match result {
+            Err(MethodCallError::InternalError(VmError::UnsatisfiedLinkError(name))) => {
+                assert_eq!(name, "MissingNativeTest.missing()I")
+            }
+            _ => panic!("expected UnsatisfiedLinkError"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+        assert!(call_stack.is_consistent());
+
+        let result = vm.call(call_stack, "MissingNativeTest", "ok", "()I", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(42)));
+
+        //<clinit>中出现内部错误后类进入Erroneous状态，再次使用抛出NoClassDefFoundError
+        let result = vm.lookup_class_and_initialize(call_stack, "MissingNativeInit");
+        assert!(matches!(result, Err(MethodCallError::InternalError(_))));
+        let class_ref = vm.method_area.load_class("MissingNativeInit").unwrap();
+        assert_eq!(class_ref.status.get(), ClassStatus::Erroneous);
+        match vm.lookup_class_and_initialize(call_stack, "MissingNativeInit") {
+            Err(MethodCallError::ExceptionThrown(e)) => {
+                assert_eq!(e.get_class().name, "java/lang/NoClassDefFoundError")
+            }
+            _ => panic!("expected NoClassDefFoundError"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+
+        let result = vm.call(call_stack, "MissingNativeTest", "ok", "()I", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(42)));
+    }
+
+    #[test]
+    fn test_preload_all() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        let classes = vm
+            .preload_all(call_stack, &["PreloadTest", "java/util/ArrayList"])
+            .unwrap();
+        assert_eq!(classes.len(), 2);
+        //只链接不初始化
+        assert_eq!(classes[0].status.get(), ClassStatus::Linked);
+        assert_eq!(
+            classes[0].super_class.unwrap().status.get(),
+            ClassStatus::Linked
+        );
+        assert_eq!(
+            vm.get_static(classes[0], "GREETING").unwrap().get_string(),
+            Ok("hello".to_string())
+        );
+
+        //出错后继续处理剩余的类
+        let Err(errors) = vm.preload_all(
+            call_stack,
+            &["PreloadMissingSuper", "PreloadTest", "DoesNotExist"],
+        ) else {
+            panic!("expected preload errors");
+        };
+        let errors: Vec<(&str, &MethodCallError)> = errors
+            .iter()
+            .map(|e| (e.class_name.as_str(), &e.error))
+            .collect();
+        assert_eq!(errors.len(), 2, "{:?}", errors);
+        assert!(matches!(
+            errors[0],
+            ("PreloadMissingSuper", MethodCallError::InternalError(VmError::ClassNotFoundException(name)))
+                if name == "PreloadMissing"
+        ));
+        assert!(matches!(
+            errors[1],
+            ("DoesNotExist", MethodCallError::InternalError(VmError::ClassNotFoundException(name)))
+                if name == "DoesNotExist"
+        ));
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_core_classes_cached() {
+        use crate::jvm_values::Value;
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+
+        assert!(vm.core_classes.string.is_none());
+        let first = vm.new_java_lang_string_object(call_stack, "s0").unwrap();
+        let string_class = vm.core_classes.string.unwrap();
+        assert!(std::ptr::eq(first.get_class(), string_class));
+        assert!(std::ptr::eq(
+            string_class,
+            vm.method_area.load_class("java/lang/String").unwrap()
+        ));
+
+        for i in 0..2000 {
+            let text = format!("s{}", i);
+            let string_ref = vm.new_java_lang_string_object(call_stack, &text).unwrap();
+            assert!(std::ptr::eq(string_ref.get_class(), string_class));
+            assert_eq!(Value::ObjectRef(string_ref).get_string().unwrap(), text);
+        }
+        //同一个字面量仍然返回同一个对象
+        let again = vm.new_java_lang_string_object(call_stack, "s0").unwrap();
+        assert!(again == first);
+        assert!(std::ptr::eq(vm.core_classes.string.unwrap(), string_class));
+
+        let class_object = vm
+            .new_java_lang_class_object(call_stack, "CallTest")
+            .unwrap();
+        assert!(std::ptr::eq(
+            class_object.get_class(),
+            vm.core_classes.class.unwrap()
+        ));
+        vm.java_lang_object_class(call_stack).unwrap();
+        let array_class = vm.lookup_class_and_initialize(call_stack, "[I").unwrap();
+        assert_eq!(array_class.name, "[I");
+        assert!(std::ptr::eq(
+            array_class.super_class.unwrap(),
+            vm.core_classes.object.unwrap()
+        ));
+    }
+
+    #[test]
+    fn test_number_to_string() {
+        use crate::jvm_values::Value;
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+
+        let cases = [
+            ("intToString", "(I)Ljava/lang/String;", Value::Int(1), "1"),
+            ("intToString", "(I)Ljava/lang/String;", Value::Int(-1), "-1"),
+            (
+                "intToString",
+                "(I)Ljava/lang/String;",
+                Value::Int(i32::MIN),
+                "-2147483648",
+            ),
+            (
+                "longToString",
+                "(J)Ljava/lang/String;",
+                Value::Long(i64::MIN),
+                "-9223372036854775808",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1.0),
+                "1.0",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(-1.0),
+                "-1.0",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(0.0),
+                "0.0",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(-0.0),
+                "-0.0",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1.5),
+                "1.5",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(0.1),
+                "0.1",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1e20),
+                "1.0E20",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1e10),
+                "1.0E10",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1e7),
+                "1.0E7",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1234567.5),
+                "1234567.5",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(0.001),
+                "0.001",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(1.25e-4),
+                "1.25E-4",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(100.0),
+                "100.0",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(f64::NAN),
+                "NaN",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(f64::NEG_INFINITY),
+                "-Infinity",
+            ),
+            (
+                "doubleToString",
+                "(D)Ljava/lang/String;",
+                Value::Double(f64::MAX),
+                "1.7976931348623157E308",
+            ),
+            (
+                "floatToString",
+                "(F)Ljava/lang/String;",
+                Value::Float(0.1),
+                "0.1",
+            ),
+            (
+                "floatToString",
+                "(F)Ljava/lang/String;",
+                Value::Float(1.5),
+                "1.5",
+            ),
+            (
+                "floatToString",
+                "(F)Ljava/lang/String;",
+                Value::Float(1e10),
+                "1.0E10",
+            ),
+            (
+                "floatToString",
+                "(F)Ljava/lang/String;",
+                Value::Float(f32::INFINITY),
+                "Infinity",
+            ),
+        ];
+        for (method_name, descriptor, arg, expected) in cases {
+            let result = vm
+                .call(
+                    call_stack,
+                    "NumberToStringTest",
+                    method_name,
+                    descriptor,
+                    None,
+                    vec![arg.clone()],
+                )
+                .unwrap()
+                .unwrap();
+            assert_eq!(result.get_string().unwrap(), expected, "{:?}", arg);
+        }
+        let result = vm
+            .call(
+                call_stack,
+                "NumberToStringTest",
+                "boxed",
+                "(ID)Ljava/lang/String;",
+                None,
+                vec![Value::Int(-42), Value::Double(2.5)],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get_string().unwrap(), "-42,2.5");
+        let result = vm
+            .call(
+                call_stack,
+                "NumberToStringTest",
+                "sameInstance",
+                "(I)Z",
+                None,
+                vec![Value::Int(7)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(0)));
+    }
+
+    #[test]
+    fn test_interface_static_and_private_methods() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //InterfaceMethodTest使用--release 17编译
+        let cases = [
+            ("staticHelper", Value::Int(42)),
+            //base() + twice(size()) + offset() = 10 + 8 + 1
+            ("defaultArea", Value::Int(19)),
+            ("superArea", Value::Int(1700)),
+        ];
+        for (method_name, expected) in cases {
+            let result = vm
+                .call(
+                    call_stack,
+                    "InterfaceMethodTest",
+                    method_name,
+                    "()I",
+                    None,
+                    vec![],
+                )
+                .unwrap();
+            assert_eq!(result, Some(expected), "{}", method_name);
+        }
+        //接口的静态方法不会被实现类继承
+        let not_inherited = vm.call(
+            call_stack,
+            "InterfaceMethodTest$Square",
+            "twice",
+            "(I)I",
+            None,
+            vec![Value::Int(1)],
+        );
+        assert!(not_inherited.is_err());
+
+        let result = vm
+            .call(
+                call_stack,
+                "InterfacePrivateLegacyTest",
+                "next",
+                "(I)I",
+                None,
+                vec![Value::Int(1)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(6)));
+    }
+
+    #[test]
+    fn test_call_by_method_name() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, Value};
+        use crate::test_common::create_vm;
+
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        let result = vm
+            .call_static(call_stack, "OverloadTest", "foo", &[Value::Int(1).into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(2)));
+        let result = vm
+            .call_static(call_stack, "OverloadTest", "foo", &[Value::Long(1).into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Long(3)));
+        let result = vm
+            .call_static(call_stack, "OverloadTest", "foo", &["lite".into()])
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get_string().unwrap(), "lite!");
+        //describe(Object)和describe(CharSequence)都可以接收String
+        let error = vm
+            .call_static(call_stack, "OverloadTest", "describe", &["lite".into()])
+            .unwrap_err();
+        match error {
+            MethodCallError::InternalError(VmError::AmbiguousMethodCall(name, mut candidates)) => {
+                assert_eq!(name, "OverloadTest.describe");
+                candidates.sort();
+                assert_eq!(
+                    candidates,
+                    vec!["(Ljava/lang/CharSequence;)I", "(Ljava/lang/Object;)I"]
+                );
+            }
+            e => panic!("unexpected error {:?}", e),
+        }
+        //数组参数按元素类型选择重载
+        let ints = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 3)
+            .unwrap();
+        let result = vm
+            .call_static(
+                call_stack,
+                "OverloadTest",
+                "count",
+                &[Value::ArrayRef(ints).into()],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(3)));
+        let strings = vm
+            .new_array_by_descriptor(call_stack, "[Ljava/lang/String;", 2)
+            .unwrap();
+        let result = vm
+            .call_static(
+                call_stack,
+                "OverloadTest",
+                "count",
+                &[Value::ArrayRef(strings).into()],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(-2)));
+        let longs = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Long), 1)
+            .unwrap();
+        assert!(matches!(
+            vm.call_static(
+                call_stack,
+                "OverloadTest",
+                "count",
+                &[Value::ArrayRef(longs).into()]
+            ),
+            Err(MethodCallError::InternalError(
+                VmError::MethodNotFoundException(_, _)
+            ))
+        ));
+        let result = vm.call_static(
+            call_stack,
+            "OverloadTest",
+            "foo",
+            &[Value::Float(1.0).into()],
+        );
+        assert!(matches!(
+            result,
+            Err(MethodCallError::InternalError(
+                VmError::MethodNotFoundException(_, _)
+            ))
+        ));
+
+        let receiver = vm
+            .new_object_by_class_name(call_stack, "OverloadTest")
+            .unwrap();
+        vm.call(
+            call_stack,
+            "OverloadTest",
+            "<init>",
+            "(I)V",
+            Some(Value::ObjectRef(receiver)),
+            vec![Value::Int(10)],
+        )
+        .unwrap();
+        let result = vm
+            .call_instance(call_stack, receiver, "add", &[Value::Int(5).into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(15)));
+        let result = vm
+            .call_instance(call_stack, receiver, "add", &[Value::Long(5).into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Long(15)));
+        let result = vm
+            .call_instance(call_stack, receiver, "length", &["lite".into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(14)));
+        //静态方法不能通过call_instance调用
+        assert!(vm
+            .call_instance(call_stack, receiver, "foo", &[Value::Int(1).into()])
+            .is_err());
+    }
+
+    #[test]
+    fn test_call_instance_on_user_defined_class() {
+        use crate::jvm_values::{ArrayElement, PrimaryType, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let bytes = std::fs::read("./resources/patched/Versioned.class").unwrap();
+        let byte_array = vm
+            .new_array(
+                call_stack,
+                ArrayElement::PrimaryValue(PrimaryType::Byte),
+                bytes.len(),
+            )
+            .unwrap();
+        let target = vm
+            .new_java_lang_string_object(call_stack, "Versioned")
+            .unwrap();
+        let loader = vm
+            .new_object_by_class_name(call_stack, "PatchingClassLoader")
+            .unwrap();
+        vm.call(
+            call_stack,
+            "PatchingClassLoader",
+            "<init>",
+            "(Ljava/lang/String;[B)V",
+            Some(Value::ObjectRef(loader)),
+            vec![Value::ObjectRef(target), Value::ArrayRef(byte_array)],
+        )
+        .unwrap();
+        let patched = vm.define_class(call_stack, loader, None, &bytes).unwrap();
+        let receiver = vm.new_object(call_stack, patched).unwrap();
+        //调用的是receiver所属的类中的方法，而不是引导类加载器加载的同名类
+        let result = vm
+            .call_instance(call_stack, receiver, "instanceVersion", &[])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(2)));
+        assert_eq!(
+            vm.class_status("Versioned"),
+            None,
+            "bootstrap Versioned should not be loaded"
+        );
+    }
+
+    #[test]
+    fn test_source_file_in_stack_trace() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::stack_trace_element::StackTraceElement;
+        use crate::test_common::create_vm;
+
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //NoSourceFileTest使用javac -g:none编译，没有SourceFile属性
+        for (class_name, expected) in [
+            ("SourceFileTest", Some("SourceFileTest.java")),
+            ("NoSourceFileTest", None),
+        ] {
+            let class_ref = vm
+                .lookup_class_and_initialize(call_stack, class_name)
+                .unwrap();
+            assert_eq!(class_ref.source_file.as_deref(), expected);
+            let result = vm.call(
+                call_stack,
+                class_name,
+                "fail",
+                "(Ljava/lang/String;)I",
+                None,
+                vec![Value::Null],
+            );
+            let Err(MethodCallError::ExceptionThrown(exception)) = result else {
+                panic!("{} should throw NullPointerException", class_name);
+            };
+            let stack_trace = exception
+                .get_field_by_name("stackTrace")
+                .unwrap()
+                .get_array()
+                .unwrap();
+            let element = stack_trace
+                .get_field_by_offset(0)
+                .unwrap()
+                .get_object()
+                .unwrap();
+            let file_name = match element.get_field_by_name("fileName").unwrap() {
+                Value::Null => None,
+                file_name => Some(file_name.get_string().unwrap()),
+            };
+            assert_eq!(file_name.as_deref(), expected);
+        }
+
+        let element = StackTraceElement {
+            declaring_class: "NoSourceFileTest".to_string(),
+            method_name: "fail".to_string(),
+            file_name: None,
+            line_number: 0,
+        };
+        assert_eq!(
+            element.to_string(),
+            "\tNoSourceFileTest.fail(Unknown Source)"
+        );
+        let element = StackTraceElement {
+            file_name: Some("SourceFileTest.java".to_string()),
+            line_number: 3,
+            ..element
+        };
+        assert_eq!(
+            element.to_string(),
+            "\tNoSourceFileTest.fail(SourceFileTest.java:3)"
+        );
+    }
+
+    #[test]
+    fn test_null_checks() {
+        use crate::class_finder::MemoryClassPath;
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        use class_file_reader::class_file_builder::ClassFileBuilder;
+        use class_file_reader::instruction::Instruction::*;
+
+        let mut vm = create_vm();
+        //javac不会生成jsr/ret，直接构造字节码
+        let subroutine_class = ClassFileBuilder::new("SubroutineTest")
+            .method(
+                "subroutine",
+                "(Ljava/lang/Object;)I",
+                &[Jsr(7), Iconst_3, Ireturn, Nop, Nop, Astore_0, Ret(0)],
+            )
+            .method(
+                "returnAddressNullCheck",
+                "(Ljava/lang/Object;)I",
+                &[Jsr(5), Iconst_0, Ireturn, Ifnull(4), Iconst_0, Ireturn],
+            )
+            .build();
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class("SubroutineTest", subroutine_class);
+        vm.add_class_path(Box::new(memory_path));
+        let call_stack = &mut vm.create_call_stack();
+
+        let object_class = vm.java_lang_object_class(call_stack).unwrap();
+        let object = Value::ObjectRef(vm.new_object(call_stack, object_class).unwrap());
+        let array = Value::ArrayRef(
+            vm.new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 1)
+                .unwrap(),
+        );
+        let mut call = |method_name: &str, descriptor: &str, arg| {
+            vm.call(
+                call_stack,
+                "NullCheckTest",
+                method_name,
+                descriptor,
+                None,
+                vec![arg],
+            )
+        };
+        for (value, is_null) in [(object.clone(), 0), (array.clone(), 0), (Value::Null, 1)] {
+            let result = call("isNull", "(Ljava/lang/Object;)I", value.clone()).unwrap();
+            assert_eq!(result, Some(Value::Int(is_null)));
+            let result = call("isNonNull", "(Ljava/lang/Object;)I", value).unwrap();
+            assert_eq!(result, Some(Value::Int(1 - is_null)));
+        }
+
+        //向后跳转：依次取出数组元素，直到遇到null或者非null
+        let mut object_array = |values: &[&Value]| {
+            let array = vm
+                .new_array(
+                    call_stack,
+                    ArrayElement::ClassReference(object_class),
+                    values.len(),
+                )
+                .unwrap();
+            for (index, value) in values.iter().enumerate() {
+                array.set_field_by_offset(index, value).unwrap();
+            }
+            Value::ArrayRef(array)
+        };
+        let until_null = object_array(&[&object, &object, &Value::Null, &object]);
+        let skip_nulls = object_array(&[&Value::Null, &Value::Null, &object, &Value::Null]);
+        let mut call = |method_name: &str, arg| {
+            vm.call(
+                call_stack,
+                "NullCheckTest",
+                method_name,
+                "([Ljava/lang/Object;)I",
+                None,
+                vec![arg],
+            )
+            .unwrap()
+        };
+        assert_eq!(call("countUntilNull", until_null), Some(Value::Int(3)));
+        assert_eq!(call("skipNulls", skip_nulls), Some(Value::Int(3)));
+
+        //astore可以保存jsr压入的返回地址，ifnull不接受返回地址
+        let result = vm.call(
+            call_stack,
+            "SubroutineTest",
+            "subroutine",
+            "(Ljava/lang/Object;)I",
+            None,
+            vec![Value::Null],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(3)));
+        let result = vm.call(
+            call_stack,
+            "SubroutineTest",
+            "returnAddressNullCheck",
+            "(Ljava/lang/Object;)I",
+            None,
+            vec![Value::Null],
+        );
+        match result {
+            Err(MethodCallError::InternalError(VmError::VerifyError(message))) => assert_eq!(
+                message,
+                "ifnull at SubroutineTest.returnAddressNullCheck(Ljava/lang/Object;)I@5: \
+                 expected reference, found ReturnAddress(3)"
+            ),
+            e => panic!("unexpected result {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_long_string_round_trip() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+
+        let mut vm = create_vm_with(10 * 1024 * 1024, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+
+        //包含代理对、\0以及超过0x7fff的code unit
+        let text: String = (0..20000)
+            .map(|i| match i % 5 {
+                0 => "a",
+                1 => "中",
+                2 => "😀",
+                3 => "\0",
+                _ => "\u{fffe}",
+            })
+            .collect();
+        let string = vm.new_java_lang_string_object(call_stack, &text).unwrap();
+        let value = Value::ObjectRef(string);
+        assert_eq!(value.get_string().unwrap(), text);
+        let chars = string
+            .get_field_by_name("value")
+            .unwrap()
+            .get_array()
+            .unwrap();
+        assert_eq!(chars.get_data_length(), text.encode_utf16().count());
+        assert_eq!(chars.get_field_by_offset(5).unwrap(), Value::Int(0xfffe));
+        //Java代码读取到的内容一致
+        let length = vm
+            .call(
+                call_stack,
+                "java/lang/String",
+                "length",
+                "()I",
+                Some(value.clone()),
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(length, Some(Value::Int(text.encode_utf16().count() as i32)));
+        let char_at = vm
+            .call(
+                call_stack,
+                "java/lang/String",
+                "charAt",
+                "(I)C",
+                Some(value),
+                vec![Value::Int(2)],
+            )
+            .unwrap();
+        assert_eq!(char_at, Some(Value::Int(0xd83d)));
+    }
+
+    #[test]
+    fn test_interface_initialization_order() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        let result = vm
+            .call(call_stack, "InterfaceInitTest", "run", "()[I", None, vec![])
+            .unwrap()
+            .unwrap();
+        let orders: Vec<Value> = result.get_array().unwrap().read_all();
+        //和HotSpot的输出一致
+        let expected: Vec<Value> = [1, 2, 3, 3, 4, 4, 5, 6].map(Value::Int).to_vec();
+        assert_eq!(orders, expected);
+    }
+
+    #[test]
+    fn test_preload_and_class_load_timing() {
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::BOOTSTRAP_CLASSES;
+        use std::time::Duration;
+
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        //没有打开时不记录
+        vm.preload(call_stack, &["java/lang/Object"]).unwrap();
+        assert!(vm.take_class_load_timings().is_empty());
+
+        vm.record_class_load_timing(true);
+        vm.preload(call_stack, BOOTSTRAP_CLASSES).unwrap();
+        let timings = vm.take_class_load_timings();
+        for class_name in &BOOTSTRAP_CLASSES[1..] {
+            let class_ref = vm.get_class_by_name(call_stack, class_name).unwrap();
+            assert_eq!(class_ref.status.get(), ClassStatus::Initialized);
+            let timing = timings
+                .iter()
+                .find(|timing| timing.class_name == *class_name)
+                .unwrap_or_else(|| panic!("no timing for {}", class_name));
+            assert!(timing.load > Duration::ZERO, "{:?}", timing);
+            assert!(timing.link > Duration::ZERO, "{:?}", timing);
+            assert!(timing.initialize > Duration::ZERO, "{:?}", timing);
+        }
+        //Object在打开之前已经初始化
+        assert!(timings
+            .iter()
+            .all(|timing| timing.class_name != "java/lang/Object"));
+        //已经初始化的类再次预热不会产生记录
+        vm.preload(call_stack, BOOTSTRAP_CLASSES).unwrap();
+        assert!(vm.take_class_load_timings().is_empty());
+        vm.preload(call_stack, &["NoSourceFileTest"]).unwrap();
+        let timings = vm.take_class_load_timings();
+        assert_eq!(timings.len(), 1);
+        assert_eq!(timings[0].class_name, "NoSourceFileTest");
+        //初始化耗时包含先初始化的父类
+        vm.preload(call_stack, &["InterfaceInitTest$Impl"]).unwrap();
+        let timings = vm.take_class_load_timings();
+        let initialize = |class_name: &str| {
+            timings
+                .iter()
+                .find(|timing| timing.class_name == class_name)
+                .unwrap()
+                .initialize
+        };
+        assert!(initialize("InterfaceInitTest$Impl") >= initialize("InterfaceInitTest$Base"));
+
+        vm.record_class_load_timing(false);
+        vm.preload(call_stack, &["CallTest"]).unwrap();
+        assert!(vm.take_class_load_timings().is_empty());
+        assert!(vm.preload(call_stack, &["NotExist"]).is_err());
+    }
+
+    #[test]
+    fn test_exception_table_boundaries() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "ExceptionBoundaryTest")
+            .unwrap();
+        //fail()每次调用计数加一并抛出NullPointerException
+        let calls = |vm: &VirtualMachine| match vm.get_static(class_ref, "calls") {
+            Some(Value::Int(calls)) => *calls,
+            other => panic!("calls: {:?}", other),
+        };
+        for (method, caught) in [
+            ("atStart", true),
+            ("atEndMinusOne", true),
+            ("atEnd", false),
+            ("beforeStart", false),
+        ] {
+            let method_ref = class_ref.get_method(method, "()I").unwrap();
+            let before = calls(&vm);
+            let result = vm.invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![],
+            );
+            match result {
+                Ok(value) => {
+                    assert!(caught, "{} should not catch", method);
+                    assert_eq!(value, Some(Value::Int(-1)));
+                }
+                Err(MethodCallError::ExceptionThrown(exception)) => {
+                    assert!(!caught, "{} should catch", method);
+                    assert_eq!(exception.get_class().name, "java/lang/NullPointerException");
+                }
+                Err(e) => panic!("{}: {:?}", method, e),
+            }
+            //atEnd如果错误地捕获了end_pc处的异常，finally处理器会再调用一次fail()
+            assert_eq!(calls(&vm), before + 1, "{}", method);
+        }
+        //类型不匹配的处理器即使范围相同也要跳过
+        let method_ref = class_ref.get_method("catchTypeOrder", "()I").unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(-2)));
+    }
+
+    #[test]
+    fn test_shadowed_private_field() {
+        use crate::jvm_values::{ObjectReference, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "FieldShadowTest")
+            .unwrap();
+        let method_ref = class_ref
+            .get_method("create", "()LFieldShadowTest$Sub;")
+            .unwrap();
+        let Some(Value::ObjectRef(sub)) = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                Vec::new(),
+            )
+            .unwrap()
+        else {
+            panic!("expected FieldShadowTest$Sub")
+        };
+        //两个同名字段各自占用一个槽位
+        assert_eq!(
+            sub.get_field_in_class("FieldShadowTest$Base", "value"),
+            Ok(Value::Int(1))
+        );
+        assert_eq!(
+            sub.get_field_in_class("FieldShadowTest$Sub", "value"),
+            Ok(Value::Int(2))
+        );
+        //按名字查找时子类的字段优先
+        assert_eq!(sub.get_field_by_name("value"), Ok(Value::Int(2)));
+
+        sub.set_field_in_class("FieldShadowTest$Base", "value", &Value::Int(7))
+            .unwrap();
+        let method_ref = class_ref
+            .get_method("combined", "(LFieldShadowTest$Sub;)I")
+            .unwrap();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            vec![Value::ObjectRef(sub)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(72)));
+    }
+
+    #[test]
+    fn test_string_concat_factory() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //JDK 17编译，字符串拼接使用invokedynamic
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "mixed",
+                "(I)Ljava/lang/String;",
+                None,
+                vec![Value::Int(7)],
+            )
+            .unwrap();
+        assert_eq!(result.unwrap().get_string().unwrap(), "a71.5(7,-7)");
+
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "primitives",
+                "(JCZFLjava/lang/Object;)Ljava/lang/String;",
+                None,
+                vec![
+                    Value::Long(-9000000000),
+                    Value::Int('z' as i32),
+                    Value::Int(1),
+                    Value::Float(0.1),
+                    Value::Null,
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            result.unwrap().get_string().unwrap(),
+            "-9000000000|z|true|0.1|null"
+        );
+
+        let arg = vm.new_java_lang_string_object(call_stack, "x").unwrap();
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "withMarker",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                None,
+                vec![Value::ObjectRef(arg)],
+            )
+            .unwrap();
+        assert_eq!(result.unwrap().get_string().unwrap(), "tag\u{1}x");
+
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "surrogates",
+                "(CC)Ljava/lang/String;",
+                None,
+                vec![Value::Int(0xd83d), Value::Int(0xde00)],
+            )
+            .unwrap();
+        assert_eq!(result.unwrap().get_string().unwrap(), "😀");
+
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "sameInstance",
+                "(I)Z",
+                None,
+                vec![Value::Int(3)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(0)));
+
+        //拼接结果不进入字符串常量池
+        let pool_size = vm.static_area.string_constant_pool.len();
+        let result = vm
+            .call(
+                call_stack,
+                "StringConcatTest",
+                "repeat",
+                "(I)I",
+                None,
+                vec![Value::Int(100)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(100 + 190)));
+        assert_eq!(vm.static_area.string_constant_pool.len(), pool_size);
+    }
+
+    #[test]
+    fn test_wide_iinc() {
+        use class_file_reader::instruction::Instruction::*;
+        let wide_class = ClassFileBuilder::new("WideIincTest")
+            .method(
+                "incrementLargeDelta",
+                "(I)I",
+                &[Iinc(0, 1000), Iinc(0, -3000), Iload_0, Ireturn],
+            )
+            .method(
+                "incrementHighLocal",
+                "()I",
+                &[
+                    Sipush(299),
+                    IstoreWide(300),
+                    Iinc(300, 1000),
+                    Iinc(300, -3),
+                    IloadWide(300),
+                    Ireturn,
+                ],
+            )
+            .method(
+                "copyHighLocal",
+                "(I)I",
+                &[
+                    Iinc(0, -1),
+                    Iload_0,
+                    IstoreWide(299),
+                    Iinc(299, 1000),
+                    Iinc(0, -1),
+                    IloadWide(299),
+                    Iload_0,
+                    Iadd,
+                    Ireturn,
+                ],
+            )
+            .build();
+        let mut vm = VirtualMachine::new(102400);
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class("WideIincTest", wide_class);
+        vm.add_class_path(Box::new(memory_path));
+        let call_stack = &mut vm.create_call_stack();
+        //增量超出i8范围的iinc
+        let result = vm
+            .call(
+                call_stack,
+                "WideIincTest",
+                "incrementLargeDelta",
+                "(I)I",
+                None,
+                vec![Value::Int(7)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(7 + 1000 - 3000)));
+        //局部变量#300使用wide iload/istore/iinc读写
+        let result = vm
+            .call(
+                call_stack,
+                "WideIincTest",
+                "incrementHighLocal",
+                "()I",
+                None,
+                vec![],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(299 + 1000 - 3)));
+        //wide指令之后的iinc仍然按普通形式解码
+        let result = vm
+            .call(
+                call_stack,
+                "WideIincTest",
+                "copyHighLocal",
+                "(I)I",
+                None,
+                vec![Value::Int(10)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int((9 + 1000) + 8)));
+    }
+
+    #[test]
+    fn test_method_handle_invoke() {
+        use crate::class_finder::MemoryClassPath;
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        use class_file_reader::class_file_builder::ClassFileBuilder;
+        use class_file_reader::instruction::Instruction::*;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str, args: &[i32]| {
+            vm.call(
+                call_stack,
+                "MethodHandleTest",
+                method,
+                descriptor,
+                None,
+                args.iter().map(|i| Value::Int(*i)).collect(),
+            )
+            .map_err(|e| match e {
+                MethodCallError::ExceptionThrown(exception) => exception.get_class().name.clone(),
+                e => panic!("{:?}", e),
+            })
+        };
+        //静态方法句柄
+        assert_eq!(
+            call("invokeExactAdd", "(II)I", &[3, 4]),
+            Ok(Some(Value::Int(7)))
+        );
+        assert_eq!(
+            call("invokeExactAddAsLong", "(II)J", &[3, 4]),
+            Err("java/lang/invoke/WrongMethodTypeException".to_string())
+        );
+        //实例方法句柄，第一个参数是接收者
+        assert_eq!(
+            call("invokeExactGetValue", "(I)I", &[42]),
+            Ok(Some(Value::Int(42)))
+        );
+        assert_eq!(
+            call("invokeExactGetValueOnSub", "(I)I", &[42]),
+            Err("java/lang/invoke/WrongMethodTypeException".to_string())
+        );
+        //invoke允许子类作为接收者，但Object不行
+        assert_eq!(
+            call("invokeGetValueOnSub", "(I)I", &[5]),
+            Ok(Some(Value::Int(5)))
+        );
+        assert_eq!(
+            call("invokeGetValueOnObject", "(I)I", &[5]),
+            Err("java/lang/invoke/WrongMethodTypeException".to_string())
+        );
+
+        //句柄为null时抛出NullPointerException，信息中是调用的方法
+        let mut builder = ClassFileBuilder::new("NullMethodHandleTest");
+        let invoke_exact =
+            builder.method_ref("java/lang/invoke/MethodHandle", "invokeExact", "(II)I");
+        builder.method(
+            "invokeNull",
+            "()I",
+            &[
+                Aconst_null,
+                Iconst_1,
+                Iconst_2,
+                Invokevirtual(invoke_exact),
+                Ireturn,
+            ],
+        );
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class("NullMethodHandleTest", builder.build());
+        vm.add_class_path(Box::new(memory_path));
+        match vm.call(
+            call_stack,
+            "NullMethodHandleTest",
+            "invokeNull",
+            "()I",
+            None,
+            vec![],
+        ) {
+            Err(MethodCallError::ExceptionThrown(exception)) => assert_eq!(
+                vm.format_exception(exception).lines().next(),
+                Some(
+                    "java.lang.NullPointerException: \
+                     java/lang/invoke/MethodHandle.invokeExact(II)I"
+                )
+            ),
+            other => panic!("expected NullPointerException, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_add_class_path_str() {
+        use crate::jvm_error::VmError;
+        use crate::virtual_machine::VirtualMachine;
+        let separator = if cfg!(windows) { ";" } else { ":" };
+        let mut vm = VirtualMachine::new(102400);
+        vm.add_class_path_str(&["./resources", "./resources/rt.jar"].join(separator))
+            .unwrap();
+        let call_stack = &mut vm.create_call_stack();
+        //HelloWorld来自目录，java/util/ArrayList来自jar包
+        let hello_world = vm
+            .lookup_class_and_initialize(call_stack, "HelloWorld")
+            .unwrap();
+        assert_eq!(hello_world.name, "HelloWorld");
+        let array_list = vm
+            .lookup_class_and_initialize(call_stack, "java/util/ArrayList")
+            .unwrap();
+        assert_eq!(array_list.name, "java/util/ArrayList");
+
+        let mut vm = VirtualMachine::new(102400);
+        assert!(matches!(
+            vm.add_class_path_str("./resources/missing.jar"),
+            Err(VmError::JarFileNotExist(_))
+        ));
+    }
+
+    #[test]
+    fn test_get_static_constant_initializes_class() {
+        use crate::jvm_values::Value;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+
+        //ConstantReader编译时VALUE/NAME还不是常量，所以保留了getstatic
+        let result = vm.call(
+            call_stack,
+            "ConstantReader",
+            "readValue",
+            "()I",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(42)));
+        //值来自ConstantValue属性，但getstatic仍然会初始化ConstantHolder
+        //https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-5.html#jvms-5.5
+        let class_ref = vm.method_area.load_class("ConstantHolder").unwrap();
+        assert_eq!(class_ref.status.get(), ClassStatus::Initialized);
+        assert_eq!(
+            vm.get_static(class_ref, "initialized"),
+            Some(&Value::Int(1))
+        );
+        let result = vm.call(
+            call_stack,
+            "ConstantReader",
+            "readName",
+            "()Ljava/lang/String;",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap().unwrap().get_string().unwrap(), "constant");
+        let result = vm.call(
+            call_stack,
+            "ConstantReader",
+            "readCounter",
+            "()I",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_branch_after_padded_switch() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let cases: &[(&str, &[(i32, i32)])] = &[
+            //tableswitch前的填充为0到3个字节，之后的if/goto仍按opcode地址计算目标
+            ("pad0", &[(0, 0), (1, 1), (2, 2), (5, 3), (-2000, -1)]),
+            ("pad1", &[(0, 10), (1, 22), (2, 24), (3, -1)]),
+            ("pad2", &[(0, 10), (1, 22), (2, 24), (7, -1)]),
+            ("pad3", &[(0, 10), (1, 22), (2, 24), (9, 7)]),
+            //循环的回跳在switch之后
+            ("loop", &[(10, 2233)]),
+            ("lookup", &[(-100, 1), (5, 2), (100000, 3), (7, -1)]),
+            //switch分支中抛出的异常按athrow的地址查找异常表
+            ("throwing", &[(0, -1), (1, 1), (2, 2), (3, -2)]),
+        ];
+        for (method, inputs) in cases {
+            for (arg, expected) in inputs.iter() {
+                let result = vm.call(
+                    call_stack,
+                    "SwitchBranchTest",
+                    method,
+                    "(I)I",
+                    None,
+                    vec![Value::Int(*arg)],
+                );
+                assert_eq!(
+                    result.unwrap(),
+                    Some(Value::Int(*expected)),
+                    "{}({})",
+                    method,
+                    arg
+                );
+            }
+        }
+        assert_eq!(call_stack.depth(), 0);
+        assert!(call_stack.is_consistent());
+    }
+
+    #[test]
+    fn test_format_value() {
+        use crate::jvm_values::{ArrayElement, PrimaryType, Value};
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = VirtualMachine::new(102400);
+        let call_stack = &mut vm.create_call_stack();
+        let string = vm.new_java_lang_string_object(call_stack, "hello").unwrap();
+        assert_eq!(vm.format_value(&Value::ObjectRef(string)), "\"hello\"");
+
+        let array = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 3)
+            .unwrap();
+        assert_eq!(vm.format_value(&Value::ArrayRef(array)), "[len=3 int]");
+
+        let object = vm
+            .new_object_by_class_name(call_stack, "java/lang/Object")
+            .unwrap();
+        assert_eq!(
+            vm.format_value(&Value::ObjectRef(object)),
+            format!("java.lang.Object@{:x}", object.hash_code() as u32)
+        );
+        assert_eq!(vm.format_value(&Value::Long(3)), "3L");
+        assert_eq!(vm.format_value(&Value::Null), "null");
+    }
+
+    #[test]
+    fn test_array_receiver() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str| {
+            vm.call(
+                call_stack,
+                "ArrayClassTest",
+                method,
+                descriptor,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let string = |value: Value| value.get_string().unwrap();
+        assert_eq!(string(call("intArrayName", "()Ljava/lang/String;")), "[I");
+        assert_eq!(
+            string(call("stringArrayName", "()Ljava/lang/String;")),
+            "[Ljava.lang.String;"
+        );
+        assert_eq!(call("sameClass", "()Z"), Value::Int(1));
+        assert_eq!(call("cloneSum", "()I"), Value::Int(14));
+        assert_eq!(call("equalsSelf", "()Z"), Value::Int(1));
+        assert_eq!(call("hashCodeStable", "()Z"), Value::Int(1));
+        assert!(string(call("arrayToString", "()Ljava/lang/String;")).starts_with("[I@"));
+        assert_eq!(call("instanceOfChecks", "()Z"), Value::Int(1));
+
+        //数组类在第一次使用时创建，父类是Object，实现Cloneable和Serializable
+        let array_class = vm.method_area.load_class("[I").unwrap();
+        assert_eq!(array_class.status.get(), ClassStatus::Initialized);
+        assert_eq!(array_class.super_class.unwrap().name, "java/lang/Object");
+        assert!(array_class.is_subclass_of("java/lang/Cloneable"));
+        assert!(array_class.is_subclass_of("java/io/Serializable"));
+        assert!(std::ptr::eq(
+            array_class,
+            vm.method_area.load_class("[I").unwrap()
+        ));
+
+        //数组作为receiver只能调用Object的方法
+        let array = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 2)
+            .unwrap();
+        let result = vm
+            .call(
+                call_stack,
+                "java/lang/Object",
+                "getClass",
+                "()Ljava/lang/Class;",
+                Some(Value::ArrayRef(array)),
+                vec![],
+            )
+            .unwrap();
+        assert!(result.is_some());
+        let mut expect_exception = |receiver, class_name: &str, message: &str| match vm.call(
+            call_stack,
+            "java/lang/String",
+            "length",
+            "()I",
+            Some(receiver),
+            vec![],
+        ) {
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                assert_eq!(exception.get_class().name, class_name);
+                assert_eq!(
+                    exception
+                        .get_field_by_name("detailMessage")
+                        .unwrap()
+                        .get_string()
+                        .unwrap(),
+                    message
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        };
+        expect_exception(
+            Value::ArrayRef(array),
+            "java/lang/IncompatibleClassChangeError",
+            "[I is not an instance of java.lang.String",
+        );
+        expect_exception(
+            Value::Null,
+            "java/lang/NullPointerException",
+            "java.lang.String.length()I",
+        );
+    }
+
+    #[test]
+    fn test_final_override_rejected_at_link() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::ReferenceValue;
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        for (class_name, expected) in [
+            (
+                "FinalMethodOverride",
+                "class FinalMethodOverride overrides final method FinalMethodBase.value()I",
+            ),
+            (
+                "FinalClassSub",
+                "Cannot inherit from final class FinalClassBase",
+            ),
+        ] {
+            match vm.lookup_class_and_initialize(call_stack, class_name) {
+                Err(MethodCallError::ExceptionThrown(e)) => {
+                    assert_eq!(e.get_class().name, "java/lang/VerifyError");
+                    let message = e.get_field_by_name("detailMessage").unwrap();
+                    assert_eq!(message.get_string().unwrap(), expected);
+                }
+                _ => panic!("{}: expected VerifyError", class_name),
+            }
+            //链接失败的类回到Loaded
+            let class_ref = vm.method_area.load_class(class_name).unwrap();
+            assert_eq!(class_ref.status.get(), ClassStatus::Loaded);
+        }
+        //父类中的private final方法不会被覆盖
+        vm.lookup_class_and_initialize(call_stack, "FinalMethodHidden")
+            .unwrap();
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_tracing_spans() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        use std::sync::{Arc, Mutex};
+        use tracing::field::{Field, Visit};
+        use tracing::span::{Attributes, Id};
+        use tracing::{Event, Level, Subscriber};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use tracing_subscriber::registry::LookupSpan;
+        use tracing_subscriber::Layer;
+
+        #[derive(Default)]
+        struct MethodField(String);
+        impl Visit for MethodField {
+            fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+                if field.name() == "method" {
+                    self.0 = format!("{:?}", value);
+                }
+            }
+        }
+
+        //(span名称, 方法名, 父span的方法名)
+        type SpanRecord = (String, String, Option<String>);
+        //记录创建的span，以及DEBUG事件所在span的方法名
+        #[derive(Clone, Default)]
+        struct Collector {
+            spans: Arc<Mutex<Vec<SpanRecord>>>,
+            debug_events: Arc<Mutex<Vec<Option<String>>>>,
+        }
+        impl<S: Subscriber + for<'l> LookupSpan<'l>> Layer<S> for Collector {
+            fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+                let mut method = MethodField::default();
+                attrs.record(&mut method);
+                let span = ctx.span(id).unwrap();
+                let parent = span
+                    .parent()
+                    .and_then(|parent| parent.extensions().get::<String>().cloned());
+                span.extensions_mut().insert(method.0.clone());
+                self.spans.lock().unwrap().push((
+                    attrs.metadata().name().to_string(),
+                    method.0,
+                    parent,
+                ));
+            }
+
+            fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+                if *event.metadata().level() == Level::DEBUG {
+                    let current = ctx
+                        .lookup_current()
+                        .and_then(|span| span.extensions().get::<String>().cloned());
+                    self.debug_events.lock().unwrap().push(current);
+                }
+            }
+        }
+
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        //提前完成类的初始化，只观察a -> b -> hashCode的调用
+        let object = vm
+            .new_object_by_class_name(call_stack, "java/lang/Object")
+            .unwrap();
+        vm.lookup_class_and_initialize(call_stack, "TracingTest")
+            .unwrap();
+
+        let collector = Collector::default();
+        let subscriber = tracing_subscriber::registry().with(collector.clone());
+        let result = tracing::subscriber::with_default(subscriber, || {
+            vm.call(
+                call_stack,
+                "TracingTest",
+                "a",
+                "(Ljava/lang/Object;)I",
+                None,
+                vec![Value::ObjectRef(object)],
+            )
+        });
+        assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        let method = |name: &str| Some(name.to_string());
+        assert_eq!(
+            *collector.spans.lock().unwrap(),
+            vec![
+                ("invoke_method".to_string(), "a".to_string(), None),
+                ("invoke_method".to_string(), "b".to_string(), method("a")),
+                (
+                    "invoke_native_method".to_string(),
+                    "hashCode".to_string(),
+                    method("b")
+                ),
+            ]
+        );
+        assert_eq!(
+            *collector.debug_events.lock().unwrap(),
+            vec![method("hashCode")]
+        );
+    }
+
+    #[test]
+    fn test_new_array_atypes() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str, args: &[i32]| {
+            let args = args.iter().map(|arg| Value::Int(*arg)).collect();
+            vm.call(call_stack, "NewArrayTest", method, descriptor, None, args)
+        };
+        assert_eq!(call("lengths", "()I", &[]).unwrap(), Some(Value::Int(36)));
+        match call("badAtype", "()I", &[]) {
+            Err(MethodCallError::InternalError(VmError::VerifyError(message))) => {
+                assert_eq!(message, "newarray with invalid atype 3")
+            }
+            _ => panic!("expected VerifyError"),
+        }
+
+        //存储时截断为元素类型，boolean数组只保留最低位
+        for (method, descriptor, value, expected) in [
+            ("storeBoolean", "(Z)[Z", 2, 0),
+            ("storeBoolean", "(Z)[Z", 3, 1),
+            ("storeByte", "(B)[B", 200, -56),
+            ("storeByte", "(B)[B", -129, 127),
+            ("storeChar", "(C)[C", 70000, 4464),
+            ("storeChar", "(C)[C", -1, 0xffff),
+            ("storeShort", "(S)[S", 40000, -25536),
+        ] {
+            let array = call(method, descriptor, &[value])
+                .unwrap()
+                .unwrap()
+                .get_array()
+                .unwrap();
+            assert_eq!(
+                array.get_field_by_offset(0).unwrap(),
+                Value::Int(expected),
+                "{}({})",
+                method,
+                value
+            );
+        }
+        assert_eq!(
+            call("loadByte", "(B)I", &[255]).unwrap(),
+            Some(Value::Int(-1))
+        );
+        assert_eq!(
+            call("loadBoolean", "(Z)Z", &[4]).unwrap(),
+            Some(Value::Int(0))
+        );
+        assert_eq!(
+            call("byteBoundaries", "()I", &[]).unwrap(),
+            Some(Value::Int(-128 + 127 * 1000 - 1000000))
+        );
+
+        //boolean[]和byte[]是不同的数组类
+        for (method, expected) in [("booleanKind", 1), ("byteKind", 2), ("charKind", 0)] {
+            assert_eq!(
+                call(method, "()I", &[]).unwrap(),
+                Some(Value::Int(expected)),
+                "{}",
+                method
+            );
+        }
+    }
+
+    #[test]
+    fn test_ldc_dynamic_constant() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str| {
+            vm.call(call_stack, "CondyTest", method, descriptor, None, vec![])
+                .unwrap()
+                .unwrap()
+        };
+        assert!(call("nullObject", "()Ljava/lang/Object;") == Value::Null);
+        assert!(call("nullString", "()Ljava/lang/String;") == Value::Null);
+        let int_class = call("intClass", "()Ljava/lang/Class;")
+            .get_object()
+            .unwrap();
+        let class_name = int_class.get_field_by_name("name").unwrap();
+        assert_eq!(class_name.get_string().unwrap(), "int");
+        assert_eq!(call("answer", "()I").get_int().unwrap(), 42);
+        //引导方法只在第一次ldc时调用
+        assert_eq!(call("answerTwice", "()I").get_int().unwrap(), 85);
+        let greeting = call("greeting", "()Ljava/lang/String;");
+        assert_eq!(greeting.get_string().unwrap(), "hello condy");
+        //引导方法收到的Lookup以调用者为lookupClass
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "CondyTest")
+            .unwrap();
+        let class_object = vm
+            .get_java_lang_class_object(call_stack, class_ref)
+            .unwrap();
+        let lookup_class = vm
+            .get_static_field_by_class_name(call_stack, "CondyTest", "lookupClass")
+            .unwrap()
+            .cloned();
+        assert!(lookup_class == Some(Value::ObjectRef(class_object)));
+    }
+
+    #[test]
+    fn test_new_array_by_descriptor() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+
+        let table = vm
+            .new_array_by_descriptor(call_stack, "[[Ljava/lang/String;", 2)
+            .unwrap();
+        for (index, words) in [["a", "b"], ["c", "d"]].iter().enumerate() {
+            let row = vm
+                .new_array_by_descriptor(call_stack, "[Ljava/lang/String;", words.len())
+                .unwrap();
+            for (column, word) in words.iter().enumerate() {
+                let string = vm.new_java_lang_string_object(call_stack, word).unwrap();
+                row.set_field_by_offset(column, &Value::ObjectRef(string))
+                    .unwrap();
+            }
+            table
+                .set_field_by_offset(index, &Value::ArrayRef(row))
+                .unwrap();
+        }
+        assert_eq!(vm.array_class(table).unwrap().name, "[[Ljava/lang/String;");
+        let row = table.get_field_by_offset(1).unwrap().get_array().unwrap();
+        assert_eq!(
+            row.get_field_by_offset(0).unwrap().get_string().unwrap(),
+            "c"
+        );
+        assert_eq!(
+            vm.format_value(&Value::ArrayRef(table)),
+            "[len=2 java.lang.String[]]"
+        );
+        //一维数组只能存放元素类型的值
+        assert!(table
+            .set_field_by_offset(
+                0,
+                &Value::ObjectRef(vm.new_java_lang_string_object(call_stack, "x").unwrap())
+            )
+            .is_err());
+
+        for descriptor in ["I", "Ljava/lang/String;", "[Q", "[Ljava/lang/String"] {
+            match vm.new_array_by_descriptor(call_stack, descriptor, 1) {
+                Err(MethodCallError::InternalError(VmError::InvalidDescriptor(_))) => {}
+                _ => panic!("expected InvalidDescriptor for {}", descriptor),
+            }
+        }
+
+        //multianewarray和元素类型是数组的anewarray
+        let mut call = |method: &str, descriptor: &str, args: &[i32]| {
+            let args = args.iter().map(|arg| Value::Int(*arg)).collect();
+            vm.call(call_stack, "MultiArrayTest", method, descriptor, None, args)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(call("grid", "(II)I", &[3, 4]), Value::Int(3000 + 400 + 138));
+        let partial = call("partial", "(II)[[[Ljava/lang/String;", &[2, 3])
+            .get_array()
+            .unwrap();
+        let middle = partial.get_field_by_offset(1).unwrap().get_array().unwrap();
+        assert_eq!(middle.get_data_length(), 3);
+        assert_eq!(middle.get_field_by_offset(2).unwrap(), Value::Null);
+        let jagged = call("jagged", "()[[Ljava/lang/String;", &[]);
+        let second = jagged.get_array().unwrap().get_field_by_offset(1).unwrap();
+        assert_eq!(second.get_array().unwrap().get_data_length(), 2);
+        assert_eq!(call("negative", "(II)I", &[2, -1]), Value::Int(-1));
+        assert_eq!(call("negative", "(II)I", &[2, 5]), Value::Int(2));
+        let is_object_array = vm
+            .call(
+                call_stack,
+                "MultiArrayTest",
+                "isObjectArray",
+                "(Ljava/lang/Object;)Z",
+                None,
+                vec![jagged],
+            )
+            .unwrap();
+        assert_eq!(is_object_array, Some(Value::Int(1)));
+    }
+
+    #[test]
+    fn test_multianewarray_dimensions() {
+        use crate::class_finder::MemoryClassPath;
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::test_common::create_vm_without_rt_jar;
+        use class_file_reader::class_file_builder::ClassFileBuilder;
+        use class_file_reader::instruction::Instruction::*;
+
+        let mut vm = create_vm_without_rt_jar();
+        //javac不会生成这样的multianewarray，直接构造字节码
+        let mut builder = ClassFileBuilder::new("MultiArrayDimensionTest");
+        let array_class = builder.class("[[I");
+        builder
+            .method(
+                "zero",
+                "()Ljava/lang/Object;",
+                &[Multianewarray(array_class, 0), Areturn],
+            )
+            .method(
+                "tooMany",
+                "()Ljava/lang/Object;",
+                &[
+                    Iconst_1,
+                    Iconst_1,
+                    Iconst_1,
+                    Multianewarray(array_class, 3),
+                    Areturn,
+                ],
+            );
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class("MultiArrayDimensionTest", builder.build());
+        vm.add_class_path(Box::new(memory_path));
+        let call_stack = &mut vm.create_call_stack();
+        for method in ["zero", "tooMany"] {
+            let result = vm.call(
+                call_stack,
+                "MultiArrayDimensionTest",
+                method,
+                "()Ljava/lang/Object;",
+                None,
+                vec![],
+            );
+            match result {
+                Err(MethodCallError::InternalError(VmError::VerifyError(message))) => {
+                    assert!(message.contains("multianewarray of [[I"), "{}", message)
+                }
+                _ => panic!("expected VerifyError for {}", method),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invoke_interface_on_class() {
+        use crate::class_finder::MemoryClassPath;
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm_without_rt_jar;
+        use class_file_reader::class_file_builder::ClassFileBuilder;
+        use class_file_reader::instruction::Instruction::*;
+
+        let mut vm = create_vm_without_rt_jar();
+        //InterfaceMethodref指向的是类，解析时抛出IncompatibleClassChangeError
+        let mut builder = ClassFileBuilder::new("InterfaceOnClassTest");
+        let method_ref = builder.interface_method_ref("java/lang/Object", "hashCode", "()I");
+        builder.method(
+            "hash",
+            "(Ljava/lang/Object;)I",
+            &[Aload_0, Invokeinterface(method_ref, 1), Ireturn],
+        );
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class("InterfaceOnClassTest", builder.build());
+        vm.add_class_path(Box::new(memory_path));
+        let call_stack = &mut vm.create_call_stack();
+        let receiver = vm.new_java_lang_string_object(call_stack, "a").unwrap();
+        let result = vm.call(
+            call_stack,
+            "InterfaceOnClassTest",
+            "hash",
+            "(Ljava/lang/Object;)I",
+            None,
+            vec![Value::ObjectRef(receiver)],
+        );
+        match result {
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                assert_eq!(
+                    exception.get_class().name,
+                    "java/lang/IncompatibleClassChangeError"
+                );
+                assert_eq!(
+                    exception
+                        .get_field_by_name("detailMessage")
+                        .unwrap()
+                        .get_string()
+                        .unwrap(),
+                    "Found java.lang.Object, but interface was expected"
+                );
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_modified_utf8_names() {
+        use crate::class_finder::MemoryClassPath;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        use class_file_reader::class_file_builder::ClassFileBuilder;
+        use class_file_reader::instruction::Instruction::*;
+
+        //类名、方法名和字符串常量由encode_modified_utf8写入常量池，加载时再解码，
+        //读回的名称经过new_java_lang_string_object和原来的字符串一致
+        let class_name = "Utf8名字😀";
+        let method_name = "emoji😀\0nul";
+        let mut builder = ClassFileBuilder::new(class_name);
+        let string_index = builder.string(method_name);
+        builder.method(
+            method_name,
+            "()Ljava/lang/String;",
+            &[Ldc_w(string_index), Areturn],
+        );
+        let mut memory_path = MemoryClassPath::new();
+        memory_path.add_class(class_name, builder.build());
+        let mut vm = create_vm();
+        vm.add_class_path(Box::new(memory_path));
+        let call_stack = &mut vm.create_call_stack();
+        let result = vm
+            .call(
+                call_stack,
+                class_name,
+                method_name,
+                "()Ljava/lang/String;",
+                None,
+                vec![],
+            )
+            .unwrap()
+            .unwrap();
+        let expected = vm
+            .new_java_lang_string_object(call_stack, method_name)
+            .unwrap();
+        assert_eq!(result, Value::ObjectRef(expected));
+        assert_eq!(result.get_string().unwrap(), method_name);
+
+        let class_ref = vm.get_class_by_name(call_stack, class_name).unwrap();
+        let class_object = vm
+            .get_java_lang_class_object(call_stack, class_ref)
+            .unwrap();
+        let name = vm
+            .call(
+                call_stack,
+                "java/lang/Class",
+                "getName",
+                "()Ljava/lang/String;",
+                Some(Value::ObjectRef(class_object)),
+                vec![],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(name.get_string().unwrap(), class_name);
+    }
+
+    #[test]
+    fn test_jump_target_out_of_code() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str| {
+            vm.call(
+                call_stack,
+                "ReturnAddressTest",
+                method,
+                descriptor,
+                None,
+                vec![],
+            )
+        };
+        assert_eq!(
+            call("validSubroutine", "()I").unwrap(),
+            Some(Value::Int(15))
+        );
+        for (method, descriptor, expected) in [
+            (
+                "retPastEnd",
+                "()I",
+                "ReturnAddressTest.retPastEnd()I: jump from pc 4 to 9 outside code length 9",
+            ),
+            (
+                "gotoOutside",
+                "()V",
+                "ReturnAddressTest.gotoOutside()V: jump from pc 0 to 100 outside code length 3",
+            ),
+            (
+                "gotoNegative",
+                "()V",
+                "ReturnAddressTest.gotoNegative()V: jump from pc 1 to -4 outside code length 4",
+            ),
+        ] {
+            match call(method, descriptor) {
+                Err(MethodCallError::InternalError(VmError::VerifyError(message))) => {
+                    assert_eq!(message, expected)
+                }
+                _ => panic!("expected VerifyError from {}", method),
+            }
+        }
+    }
+
+    #[test]
+    fn test_native_category_two_args() {
+        use crate::java_exception::InvokeMethodResult;
+        use crate::jvm_values::Value;
+        use crate::stack::CallStack;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm();
+        fn shift<'a>(
+            _vm: &mut VirtualMachine<'a>,
+            _call_stack: &mut CallStack<'a>,
+            receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            assert!(receiver.is_none());
+            assert_eq!(args.len(), 2);
+            Ok(Some(Value::Long(args[0].get_long()? << args[1].get_int()?)))
+        }
+        fn scale<'a>(
+            _vm: &mut VirtualMachine<'a>,
+            _call_stack: &mut CallStack<'a>,
+            receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            assert!(matches!(receiver, Some(Value::ObjectRef(_))));
+            assert_eq!(args.len(), 3);
+            let value = args[0].get_int()? as f64 * args[1].get_double()?;
+            Ok(Some(Value::Double(value + args[2].get_long()? as f64)))
+        }
+        vm.native_method_area
+            .registry_native_method("NativeArgsTest", "shift", "(JI)J", shift);
+        vm.native_method_area
+            .registry_native_method("NativeArgsTest", "scale", "(IDJ)D", scale);
+        let call_stack = &mut vm.create_call_stack();
+
+        let result = vm.call(
+            call_stack,
+            "NativeArgsTest",
+            "callShift",
+            "(JI)J",
+            None,
+            vec![Value::Long(3 << 40), Value::Int(4)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Long((3 << 44) + 1)));
+        let result = vm.call(
+            call_stack,
+            "NativeArgsTest",
+            "callScale",
+            "(IDJ)D",
+            None,
+            vec![Value::Int(3), Value::Double(1.5), Value::Long(10)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Double(14.5)));
+        //System.arraycopy的5个参数
+        let result = vm.call(
+            call_stack,
+            "NativeArgsTest",
+            "copyLongs",
+            "()J",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Long(3210)));
+    }
+
+    #[test]
+    fn test_log_calls() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use crate::trace_filter::CollectingTraceSink;
+        let sink = CollectingTraceSink::default();
+        let mut vm = create_vm().log_calls(vec![
+            "SwitchTest::select".to_string(),
+            "java/lang/String::equals".to_string(),
+        ]);
+        vm.set_trace_sink(Box::new(sink.clone()));
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "SwitchTest")
+            .unwrap();
+        let select_method = class_ref
+            .get_method("select", "(Ljava/lang/String;)I")
+            .unwrap();
+        let string_ref = vm.new_java_lang_string_object(call_stack, "BB").unwrap();
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                select_method,
+                None::<ObjectReference>,
+                vec![Value::ObjectRef(string_ref)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(2)));
+
+        //耗时不固定，去掉" in "之后的部分再比较
+        let strip_elapsed = |line: &String| match line.rsplit_once(" in ") {
+            Some((head, _)) if line.contains("<- ") => head.to_string(),
+            _ => line.clone(),
+        };
+        let lines: Vec<String> = sink.lines().iter().map(strip_elapsed).collect();
+        let argument = "\"BB\"";
+        assert_eq!(
+            lines.first().unwrap(),
+            &format!(
+                "-> [1] SwitchTest.select(Ljava/lang/String;)I args: ({})",
+                argument
+            )
+        );
+        assert_eq!(
+            lines.last().unwrap(),
+            "<- [1] SwitchTest.select(Ljava/lang/String;)I returned 2"
+        );
+        //嵌套调用按深度缩进，接收者作为this输出
+        assert!(lines.iter().any(|l| l.starts_with(&format!(
+            "  -> [2] java/lang/String.equals(Ljava/lang/Object;)Z args: (this={}, ",
+            argument
+        ))));
+        assert!(lines
+            .iter()
+            .any(|l| l == "  <- [2] java/lang/String.equals(Ljava/lang/Object;)Z returned 1"));
+        assert!(sink.lines().last().unwrap().contains(" in "));
+
+        //抛出异常时输出异常类
+        let previous_len = sink.lines().len();
+        let result = vm.invoke_method(
+            call_stack,
+            class_ref,
+            select_method,
+            None::<ObjectReference>,
+            vec![Value::Null],
+        );
+        assert!(matches!(result, Err(MethodCallError::ExceptionThrown(_))));
+        let lines: Vec<String> = sink.lines()[previous_len..]
+            .iter()
+            .map(strip_elapsed)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "-> [1] SwitchTest.select(Ljava/lang/String;)I args: (null)".to_string(),
+                "<- [1] SwitchTest.select(Ljava/lang/String;)I threw java.lang.NullPointerException"
+                    .to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_number_after_predecode() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "RethrowTest")
+            .unwrap();
+        let method_ref = class_ref.get_method("outer", "()I").unwrap();
+        let expected = vec![
+            ("inner".to_string(), 5),
+            ("middle".to_string(), 9),
+            ("outer".to_string(), 14),
+        ];
+        //预解码前后抛出异常时记录的行号相同
+        for predecode in [false, true] {
+            vm.set_predecode_enabled(predecode);
+            let obj_ref = vm.new_object(call_stack, class_ref).unwrap();
+            let exception =
+                match vm.invoke_method(call_stack, class_ref, method_ref, Some(obj_ref), vec![]) {
+                    Err(MethodCallError::ExceptionThrown(exception)) => exception,
+                    _ => panic!("NullPointerException should propagate out of outer"),
+                };
+            let stack_trace = exception.get_field_by_name("stackTrace").unwrap();
+            let array = stack_trace.get_array().unwrap();
+            let lines: Vec<(String, i32)> = (0..array.get_data_length())
+                .map(|i| {
+                    let element = array.get_field_by_offset(i).unwrap().get_object().unwrap();
+                    let method_name = element
+                        .get_field_by_name("methodName")
+                        .unwrap()
+                        .get_string()
+                        .unwrap();
+                    match element.get_field_by_name("lineNumber").unwrap() {
+                        Value::Int(line_number) => (method_name, line_number),
+                        other => panic!("unexpected lineNumber {}", other),
+                    }
+                })
+                .collect();
+            assert_eq!(lines, expected);
+        }
+        let inner = class_ref.get_method("inner", "()I").unwrap();
+        let code = inner.code.as_ref().unwrap();
+        assert!(code.instructions.get().is_some());
+        //行号表项的start_pc本身也属于该行
+        assert_eq!(code.line_number_at(0), 5);
+    }
+
+    #[test]
+    fn test_operand_stack_overflow() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let args = vec![Value::Int(1), Value::Int(2)];
+        let result = vm
+            .call(
+                call_stack,
+                "OperandStackTest",
+                "add",
+                "(II)I",
+                None,
+                args.clone(),
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(3)));
+
+        //overPush的max_stack被改为1，第二次压栈时失败
+        let result = vm.call(
+            call_stack,
+            "OperandStackTest",
+            "overPush",
+            "(II)I",
+            None,
+            args.clone(),
+        );
+        match result {
+            Err(MethodCallError::InternalError(VmError::VerifyError(message))) => assert_eq!(
+                message,
+                "OperandStackTest.overPush(II)I: operand stack overflow at pc 1, max_stack 1"
+            ),
+            _ => panic!("expected VerifyError"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+
+        //关闭检查后按需增长
+        vm.set_operand_stack_check_enabled(false);
+        let result = vm
+            .call(
+                call_stack,
+                "OperandStackTest",
+                "overPush",
+                "(II)I",
+                None,
+                args,
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(3)));
+    }
+
+    #[test]
+    fn test_synchronized_static_method() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "MonitorTest")
+            .unwrap();
+        let class_object = vm
+            .get_java_lang_class_object(call_stack, class_ref)
+            .unwrap();
+        let result = vm.call(call_stack, "MonitorTest", "next", "()I", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        assert_eq!(vm.monitor_count(class_object), 0);
+        //同步块和同步方法重入同一个Class对象的监视器
+        let result = vm.call(
+            call_stack,
+            "MonitorTest",
+            "nested",
+            "(I)I",
+            None,
+            vec![Value::Int(3)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(9)));
+        assert_eq!(vm.monitor_count(class_object), 0);
+        //抛出异常时也释放监视器
+        match vm.call(call_stack, "MonitorTest", "fail", "()I", None, vec![]) {
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                assert_eq!(
+                    exception.get_class().name,
+                    "java/lang/IllegalStateException"
+                )
+            }
+            result => panic!("expected IllegalStateException, got {:?}", result),
+        }
+        assert_eq!(vm.monitor_count(class_object), 0);
+        //实例同步方法锁定接收者
+        let object: ObjectReference = vm.new_object(call_stack, class_ref).unwrap();
+        let result = vm.call(
+            call_stack,
+            "MonitorTest",
+            "add",
+            "(I)I",
+            Some(Value::ObjectRef(object)),
+            vec![Value::Int(5)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(5)));
+        assert_eq!(vm.monitor_count(object), 0);
+        //预算耗尽和System.exit不经过异常表，同步块进入的监视器由栈帧释放
+        let (class_ref, spin) = vm
+            .lookup_method(call_stack, "MonitorTest", "spin", "()V")
+            .unwrap();
+        let result = vm.invoke_with_limit(
+            call_stack,
+            class_ref,
+            spin,
+            None::<ObjectReference>,
+            vec![],
+            1_000,
+        );
+        assert!(matches!(result, Err(MethodCallError::BudgetExceeded)));
+        assert_eq!(vm.monitor_count(class_object), 0);
+        let result = vm.call(
+            call_stack,
+            "MonitorTest",
+            "exitInside",
+            "(I)V",
+            None,
+            vec![Value::Int(3)],
+        );
+        assert!(matches!(result, Err(MethodCallError::VmExit(3))));
+        assert_eq!(vm.monitor_count(class_object), 0);
+        assert!(vm.monitors.is_empty());
+    }
+
+    #[test]
+    fn test_static_reference_roots() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "StaticReferenceTest")
+            .unwrap();
+        //<clinit>创建的数组和对象分配在对象堆上，由静态区的字段引用
+        let Some(Value::ArrayRef(names)) = vm.get_static(class_ref, "NAMES").cloned() else {
+            panic!("NAMES should be an array");
+        };
+        let Some(Value::ObjectRef(holder)) = vm.get_static(class_ref, "holder").cloned() else {
+            panic!("holder should be an object");
+        };
+        let roots: Vec<Value> = vm.static_area.iter_reference_values().collect();
+        assert!(roots.contains(&Value::ArrayRef(names)));
+        assert!(roots.contains(&Value::ObjectRef(holder)));
+        assert!(roots
+            .iter()
+            .all(|v| matches!(v, Value::ObjectRef(_) | Value::ArrayRef(_))));
+
+        let result = vm.call(
+            call_stack,
+            "StaticReferenceTest",
+            "churn",
+            "(I)I",
+            None,
+            vec![Value::Int(500)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(2000)));
+        //只有静态字段引用的对象，回收时可达，不会被finalize
+        assert_eq!(vm.collect_garbage(call_stack, &[]).unwrap(), 0);
+        let result = vm.call(
+            call_stack,
+            "StaticReferenceTest",
+            "joinNames",
+            "()Ljava/lang/String;",
+            None,
+            vec![],
+        );
+        let Some(Value::ObjectRef(joined)) = result.unwrap() else {
+            panic!("joinNames should return a string");
+        };
+        assert_eq!(vm.get_string_content(joined).unwrap(), "ab");
+        let result = vm.call(
+            call_stack,
+            "StaticReferenceTest",
+            "holderValue",
+            "()I",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(42)));
+        assert_eq!(
+            vm.get_static(class_ref, "NAMES").cloned(),
+            Some(Value::ArrayRef(names))
+        );
+        assert_eq!(names.get_data_length(), 2);
+        assert_eq!(
+            vm.get_static(class_ref, "finalizedCount"),
+            Some(&Value::Int(0))
+        );
+    }
+
+    #[test]
+    fn test_class_get_interfaces() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let mut interface_names = |method_name: &str| {
+            let result = vm.call(
+                call_stack,
+                "GetInterfacesTest",
+                method_name,
+                "()[Ljava/lang/Class;",
+                None,
+                vec![],
+            );
+            let Some(Value::ArrayRef(interfaces)) = result.unwrap() else {
+                panic!("{} should return an array", method_name);
+            };
+            (0..interfaces.get_data_length())
+                .map(|index| {
+                    let class_object = interfaces
+                        .get_field_by_offset(index)
+                        .unwrap()
+                        .get_object()
+                        .unwrap();
+                    vm.get_class_ref_of_class_object(call_stack, class_object)
+                        .unwrap()
+                        .name
+                        .clone()
+                })
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            interface_names("interfacesOfBoth"),
+            vec!["GetInterfacesTest$First", "GetInterfacesTest$Second"]
+        );
+        //父类实现的接口不在结果中
+        assert_eq!(
+            interface_names("interfacesOfChild"),
+            vec!["GetInterfacesTest$Third"]
+        );
+        let result = vm.call(
+            call_stack,
+            "GetInterfacesTest",
+            "countOfObject",
+            "()I",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(0)));
+    }
+
+    #[test]
+    fn test_instruction_budget() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Arc;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //先初始化，Object等父类的<clinit>不计入预算
+        let (class_ref, spin) = vm
+            .lookup_method(call_stack, "BudgetTest", "spin", "()V")
+            .unwrap();
+        let result = vm.invoke_with_limit(
+            call_stack,
+            class_ref,
+            spin,
+            None::<ObjectReference>,
+            vec![],
+            10_000,
+        );
+        assert!(matches!(result, Err(MethodCallError::BudgetExceeded)));
+        assert_eq!(call_stack.depth(), 0);
+        assert_eq!(call_stack.instruction_budget(), None);
+        assert!(call_stack.is_consistent());
+        assert_eq!(vm.get_static(class_ref, "caught"), Some(&Value::Int(0)));
+        let Some(Value::Int(counter)) = vm.get_static(class_ref, "counter").cloned() else {
+            panic!("counter should be an int");
+        };
+        assert!(counter > 0);
+
+        //chain执行6条指令，两次调用leaf各执行6条，嵌套栈帧共享预算
+        let (class_ref, chain) = vm
+            .lookup_method(call_stack, "BudgetTest", "chain", "()I")
+            .unwrap();
+        let result = vm.invoke_with_limit(
+            call_stack,
+            class_ref,
+            chain,
+            None::<ObjectReference>,
+            vec![],
+            17,
+        );
+        assert!(matches!(result, Err(MethodCallError::BudgetExceeded)));
+        call_stack.set_instruction_budget(Some(100));
+        let result = vm.invoke_with_limit(
+            call_stack,
+            class_ref,
+            chain,
+            None::<ObjectReference>,
+            vec![],
+            18,
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(8)));
+        assert_eq!(call_stack.instruction_budget(), Some(82));
+        call_stack.set_instruction_budget(None);
+
+        //设置取消标志后在下一条指令前返回
+        let cancel_flag = Arc::new(AtomicBool::new(true));
+        call_stack.set_cancel_flag(Some(cancel_flag.clone()));
+        let result = vm.call(call_stack, "BudgetTest", "spin", "()V", None, vec![]);
+        assert!(matches!(result, Err(MethodCallError::Cancelled)));
+        assert_eq!(call_stack.depth(), 0);
+        cancel_flag.store(false, Ordering::Relaxed);
+
+        //中断之后虚拟机仍然可以正常调用
+        let result = vm.call(call_stack, "BudgetTest", "chain", "()I", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(8)));
+    }
+
+    #[test]
+    fn test_number_parsing() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+        let mut parse = |class_name: &str, method_name: &str, descriptor: &str, text: &str| {
+            let string = vm.new_java_lang_string_object(call_stack, text).unwrap();
+            let result = vm.call(
+                call_stack,
+                class_name,
+                method_name,
+                descriptor,
+                None,
+                vec![Value::ObjectRef(string)],
+            );
+            match result {
+                Ok(value) => Ok(value.unwrap()),
+                Err(MethodCallError::ExceptionThrown(exception)) => {
+                    Err(vm.format_exception(exception))
+                }
+                Err(e) => panic!("parse {:?} failed with {:?}", text, e),
+            }
+        };
+        let mut parse_int = |text: &str| {
+            parse(
+                "java/lang/Integer",
+                "parseInt",
+                "(Ljava/lang/String;)I",
+                text,
+            )
+        };
+        assert_eq!(parse_int("42"), Ok(Value::Int(42)));
+        assert_eq!(parse_int("-2147483648"), Ok(Value::Int(i32::MIN)));
+        assert_eq!(parse_int("+7"), Ok(Value::Int(7)));
+        for text in ["bad", "", " 42", "2147483648", "4.0"] {
+            let message = parse_int(text).unwrap_err();
+            assert!(
+                message.starts_with(&format!(
+                    "java.lang.NumberFormatException: For input string: \"{}\"",
+                    text
+                )),
+                "{}",
+                message
+            );
+        }
+        let mut parse_double = |text: &str| {
+            parse(
+                "java/lang/Double",
+                "parseDouble",
+                "(Ljava/lang/String;)D",
+                text,
+            )
+        };
+        assert_eq!(parse_double("3.14"), Ok(Value::Double(314.0 / 100.0)));
+        assert_eq!(parse_double(" -1e3 "), Ok(Value::Double(-1000.0)));
+        assert_eq!(parse_double("2.5f"), Ok(Value::Double(2.5)));
+        assert_eq!(parse_double(".5"), Ok(Value::Double(0.5)));
+        assert_eq!(
+            parse_double("-Infinity"),
+            Ok(Value::Double(f64::NEG_INFINITY))
+        );
+        assert!(matches!(parse_double("NaN"), Ok(Value::Double(v)) if v.is_nan()));
+        for text in ["bad", "inf", "nan", "1.0dd", "Infinityd", "1e"] {
+            let message = parse_double(text).unwrap_err();
+            assert!(
+                message.starts_with("java.lang.NumberFormatException: For input string"),
+                "{}: {}",
+                text,
+                message
+            );
+        }
+        assert!(parse_double("  ")
+            .unwrap_err()
+            .starts_with("java.lang.NumberFormatException: empty String"));
+    }
+
+    #[test]
+    fn test_system_exit() {
+        use crate::class_finder::JarFileClassPath;
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ArrayElement, Value};
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        //使用rt.jar时经过Runtime.exit，只有内置核心类时是System.exit的native
+        for with_rt_jar in [true, false] {
+            let mut vm = create_vm_with(1024000, RESOURCES_DIR, false);
+            if with_rt_jar {
+                vm.add_class_path(Box::new(
+                    JarFileClassPath::new("./resources/rt.jar").unwrap(),
+                ));
+            }
+            let call_stack = &mut vm.create_call_stack();
+            assert_eq!(vm.run_main(call_stack, "ExitTest", &[]).unwrap(), 3);
+            assert_eq!(call_stack.depth(), 0);
+            let class_ref = vm
+                .lookup_class_and_initialize(call_stack, "ExitTest")
+                .unwrap();
+            assert_eq!(vm.get_static(class_ref, "before"), Some(&Value::Int(1)));
+            assert_eq!(vm.get_static(class_ref, "after"), Some(&Value::Int(0)));
+            assert_eq!(vm.get_static(class_ref, "finallyRan"), Some(&Value::Int(0)));
+
+            //直接调用时以VmExit返回
+            let string_class = vm.java_lang_string_class(call_stack).unwrap();
+            let args = vm
+                .new_array(call_stack, ArrayElement::ClassReference(string_class), 0)
+                .unwrap();
+            let result = vm.call(
+                call_stack,
+                "ExitTest",
+                "main",
+                "([Ljava/lang/String;)V",
+                None,
+                vec![Value::ArrayRef(args)],
+            );
+            assert!(matches!(result, Err(MethodCallError::VmExit(3))));
+            assert_eq!(call_stack.depth(), 0);
+
+            let result = vm.call(call_stack, "ExitTest", "answer", "()I", None, vec![]);
+            assert_eq!(result.unwrap(), Some(Value::Int(42)));
+        }
+    }
+
+    #[test]
+    fn test_supplementary_string_constant() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::create_vm_without_rt_jar;
+        //类文件中😀是两个3字节编码的代理(ED A0 BD ED B8 80)，不是标准UTF-8的4字节形式
+        let bytes = std::fs::read("./resources/Utf8ConstantTest.class").unwrap();
+        let cesu8 = [0xed, 0xa0, 0xbd, 0xed, 0xb8, 0x80];
+        assert!(bytes.windows(cesu8.len()).any(|w| w == cesu8));
+
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method_name: &str, descriptor: &str| {
+            vm.call(
+                call_stack,
+                "Utf8ConstantTest",
+                method_name,
+                descriptor,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .unwrap()
+        };
+        let emoji = call("emoji", "()Ljava/lang/String;");
+        assert_eq!(emoji.get_string().unwrap(), "emoji 😀!");
+        let chars = emoji
+            .get_object()
+            .unwrap()
+            .get_field_by_name("value")
+            .unwrap()
+            .get_array()
+            .unwrap();
+        let units: Vec<u16> = "emoji 😀!".encode_utf16().collect();
+        assert_eq!(chars.get_data_length(), units.len());
+        for (index, unit) in units.iter().enumerate() {
+            assert_eq!(
+                chars.get_field_by_offset(index).unwrap(),
+                Value::Int(*unit as i32)
+            );
+        }
+        assert_eq!(
+            call("withNul", "()Ljava/lang/String;")
+                .get_string()
+                .unwrap(),
+            "a\0b"
+        );
+        //Java代码看到的是UTF-16的代理对
+        assert_eq!(call("emojiLength", "()I"), Value::Int(9));
+        assert_eq!(call("highSurrogate", "()I"), Value::Int(0xd83d));
+        assert_eq!(call("lowSurrogate", "()I"), Value::Int(0xde00));
+    }
+
+    #[test]
+    fn test_class_transformer() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        use class_file_reader::class_file::ClassAccessFlags;
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        //access_flags紧跟在常量池之后，跳过常量池得到它的位置
+        fn access_flags_offset(bytes: &[u8]) -> usize {
+            let u16_at = |i: usize| u16::from_be_bytes([bytes[i], bytes[i + 1]]) as usize;
+            let count = u16_at(8);
+            let mut offset = 10;
+            let mut index = 1;
+            while index < count {
+                let tag = bytes[offset];
+                offset += 1 + match tag {
+                    1 => 2 + u16_at(offset + 1),
+                    7 | 8 | 16 | 19 | 20 => 2,
+                    15 => 3,
+                    3 | 4 | 9 | 10 | 11 | 12 | 17 | 18 => 4,
+                    5 | 6 => 8,
+                    _ => panic!("unknown constant tag {}", tag),
+                };
+                index += if tag == 5 || tag == 6 { 2 } else { 1 };
+            }
+            offset
+        }
+
+        for clear_final in [false, true] {
+            let mut vm = create_vm_without_rt_jar();
+            let transformed = Rc::new(RefCell::new(Vec::new()));
+            let seen = transformed.clone();
+            vm.set_class_transformer(Box::new(move |name: &str, mut bytes: Vec<u8>| {
+                seen.borrow_mut().push(name.to_string());
+                if clear_final && name == "TransformTarget" {
+                    let offset = access_flags_offset(&bytes);
+                    bytes[offset + 1] &= !(ClassAccessFlags::FINAL.bits() as u8);
+                }
+                bytes
+            }));
+            let call_stack = &mut vm.create_call_stack();
+            let class_ref = vm
+                .lookup_class_and_initialize(call_stack, "TransformTarget")
+                .unwrap();
+            assert_eq!(
+                class_ref.access_flags.contains(ClassAccessFlags::FINAL),
+                !clear_final
+            );
+            assert!(class_ref.access_flags.contains(ClassAccessFlags::PUBLIC));
+            //父类也经过转换函数
+            assert!(transformed
+                .borrow()
+                .contains(&"TransformTarget".to_string()));
+            assert!(transformed
+                .borrow()
+                .contains(&"java/lang/Object".to_string()));
+            let result = vm.call(call_stack, "TransformTarget", "value", "()I", None, vec![]);
+            assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        }
+    }
+
+    #[test]
+    fn test_string_bytes_conversion() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, Value};
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+        let mut round_trip = |text: &str, charset_name: &str| {
+            let text = vm.new_java_lang_string_object(call_stack, text).unwrap();
+            let charset_name = vm
+                .new_java_lang_string_object(call_stack, charset_name)
+                .unwrap();
+            let result = vm.call(
+                call_stack,
+                "StringBytesTest",
+                "roundTrip",
+                "(Ljava/lang/String;Ljava/lang/String;)Ljava/lang/String;",
+                None,
+                vec![Value::ObjectRef(text), Value::ObjectRef(charset_name)],
+            );
+            match result {
+                Ok(value) => Ok(value.unwrap().get_string().unwrap()),
+                Err(MethodCallError::ExceptionThrown(exception)) => {
+                    Err(vm.format_exception(exception))
+                }
+                Err(e) => panic!("round trip failed with {:?}", e),
+            }
+        };
+        let text = "héllo 中文 😀";
+        assert_eq!(round_trip(text, "UTF-8"), Ok(text.to_string()));
+        assert_eq!(round_trip(text, "utf8"), Ok(text.to_string()));
+        assert_eq!(round_trip(text, "ISO-8859-1"), Ok("héllo ?? ?".to_string()));
+        assert_eq!(round_trip(text, "US-ASCII"), Ok("h?llo ?? ?".to_string()));
+        let error = round_trip(text, "EBCDIC").unwrap_err();
+        assert!(
+            error.starts_with("java.io.UnsupportedEncodingException: EBCDIC"),
+            "{}",
+            error
+        );
+
+        let text = vm.new_java_lang_string_object(call_stack, "中文").unwrap();
+        let length = vm
+            .call(
+                call_stack,
+                "StringBytesTest",
+                "utf8Length",
+                "(Ljava/lang/String;)I",
+                None,
+                vec![Value::ObjectRef(text)],
+            )
+            .unwrap();
+        assert_eq!(length, Some(Value::Int(6)));
+        //默认编码是UTF-8，offset和length按字节计算
+        let text = vm.new_java_lang_string_object(call_stack, "[中]").unwrap();
+        let result = vm
+            .call(
+                call_stack,
+                "StringBytesTest",
+                "defaultCharset",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                None,
+                vec![Value::ObjectRef(text)],
+            )
+            .unwrap();
+        assert_eq!(result.unwrap().get_string().unwrap(), "中");
+        //非法的UTF-8字节解码为U+FFFD
+        let bytes = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Byte), 3)
+            .unwrap();
+        bytes.write_bytes(&[b'a', 0xff, b'b']).unwrap();
+        let charset_name = vm.new_java_lang_string_object(call_stack, "UTF-8").unwrap();
+        let result = vm
+            .call(
+                call_stack,
+                "StringBytesTest",
+                "fromBytes",
+                "([BLjava/lang/String;)Ljava/lang/String;",
+                None,
+                vec![Value::ArrayRef(bytes), Value::ObjectRef(charset_name)],
+            )
+            .unwrap();
+        assert_eq!(result.unwrap().get_string().unwrap(), "a\u{fffd}b");
+        //length超过数组长度以及offset + length溢出int时都抛出StringIndexOutOfBoundsException
+        for (offset, length) in [(0, 5), (i32::MAX, 1), (-1, 1)] {
+            let result = vm.call(
+                call_stack,
+                "StringBytesTest",
+                "slice",
+                "([BII)Ljava/lang/String;",
+                None,
+                vec![
+                    Value::ArrayRef(bytes),
+                    Value::Int(offset),
+                    Value::Int(length),
+                ],
+            );
+            match result {
+                Err(MethodCallError::ExceptionThrown(exception)) => assert_eq!(
+                    exception.get_class().name,
+                    "java/lang/StringIndexOutOfBoundsException"
+                ),
+                other => panic!("unexpected result {:?}", other),
+            }
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_class_circularity() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::test_common::create_vm_without_rt_jar;
+        use crate::virtual_machine::PreloadError;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        //CircularA extends CircularB，CircularB extends CircularA；两个接口互相继承
+        for (class_name, message) in [
+            ("CircularA", "CircularA -> CircularB -> CircularA"),
+            ("CircularJ", "CircularJ -> CircularI -> CircularJ"),
+        ] {
+            match vm.lookup_class_and_initialize(call_stack, class_name) {
+                Err(MethodCallError::ExceptionThrown(error)) => {
+                    assert_eq!(error.get_class().name, "java/lang/ClassCircularityError");
+                    assert_eq!(
+                        vm.format_exception(error),
+                        format!("java.lang.ClassCircularityError: {}", message)
+                    );
+                }
+                other => panic!("expect ClassCircularityError, got {:?}", other.err()),
+            }
+            assert!(!vm.method_area.is_class_loaded(class_name));
+        }
+        assert_eq!(
+            vm.method_area.load_class("CircularB").err(),
+            Some(VmError::ClassCircularity(vec![
+                "CircularB".to_string(),
+                "CircularA".to_string(),
+                "CircularB".to_string(),
+            ]))
+        );
+        //preload_all同样报告为ClassCircularityError
+        let Err(errors) = vm.preload_all(call_stack, &["CircularJ"]) else {
+            panic!("expect preload_all to fail");
+        };
+        match &errors[..] {
+            [PreloadError {
+                error: MethodCallError::ExceptionThrown(error),
+                ..
+            }] => assert_eq!(error.get_class().name, "java/lang/ClassCircularityError"),
+            other => panic!("expect ClassCircularityError, got {:?}", other),
+        }
+        //出错后正在加载的记录已经清空，正常的类仍然可以加载
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "HelloWorld")
+            .unwrap();
+        assert_eq!(class_ref.name, "HelloWorld");
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_class_status() {
+        use crate::loaded_class::ClassStatus;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        assert_eq!(vm.class_status("HelloWorld"), None);
+        vm.method_area.load_class("HelloWorld").unwrap();
+        assert_eq!(vm.class_status("HelloWorld"), Some(ClassStatus::Loaded));
+        //查询状态不会触发链接和初始化
+        assert_eq!(vm.class_status("HelloWorld"), Some(ClassStatus::Loaded));
+        vm.lookup_class_and_initialize(call_stack, "HelloWorld")
+            .unwrap();
+        assert_eq!(
+            vm.class_status("HelloWorld"),
+            Some(ClassStatus::Initialized)
+        );
+        assert_eq!(
+            vm.class_status("java/lang/Object"),
+            Some(ClassStatus::Initialized)
+        );
+        assert_eq!(vm.class_status("DoesNotExist"), None);
+    }
+
+    #[test]
+    fn test_array_in_reference_field() {
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let values = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 3)
+            .unwrap();
+        values.set_field_by_offset(0, &Value::Int(5)).unwrap();
+        values.set_field_by_offset(1, &Value::Int(7)).unwrap();
+        let class_name = "ReferenceFieldTest";
+        let descriptor = "([I)LReferenceFieldTest;";
+        let args = vec![Value::ArrayRef(values)];
+        let test = vm
+            .call(call_stack, class_name, "create", descriptor, None, args)
+            .unwrap()
+            .unwrap()
+            .get_object()
+            .unwrap();
+        //Object和接口类型的字段读回来仍然是数组
+        for name in ["obj", "cloneable"] {
+            match test.get_field_by_name(name).unwrap() {
+                Value::ArrayRef(array) => assert_eq!(array.ptr(), values.ptr()),
+                other => panic!("{} should be an array, got {:?}", name, other),
+            }
+        }
+        let value = vm
+            .get_static_field_by_class_name(call_stack, class_name, "serializable")
+            .unwrap();
+        assert!(matches!(value, Some(Value::ArrayRef(_))));
+        let args = vec![Value::ObjectRef(test)];
+        let descriptor = "(LReferenceFieldTest;)I";
+        let total = vm.call(call_stack, class_name, "total", descriptor, None, args);
+        assert_eq!(total.unwrap(), Some(Value::Int(5 + 3 + 7)));
+
+        //从Rust侧直接给接口类型的字段赋值数组
+        let other = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Long), 1)
+            .unwrap();
+        test.set_field_by_name("cloneable", &Value::ArrayRef(other))
+            .unwrap();
+        assert!(matches!(
+            test.get_field_by_name("cloneable").unwrap(),
+            Value::ArrayRef(array) if array.ptr() == other.ptr()
+        ));
+        let args = vec![Value::ObjectRef(test)];
+        let descriptor = "(LReferenceFieldTest;)V";
+        vm.call(call_stack, class_name, "clear", descriptor, None, args)
+            .unwrap();
+        assert_eq!(test.get_field_by_name("obj").unwrap(), Value::Null);
+        assert_eq!(test.get_field_by_name("cloneable").unwrap(), Value::Null);
+
+        //Object[]的元素可以是数组，也可以是对象
+        let args = vec![Value::ArrayRef(values)];
+        let wrapped = vm
+            .call(
+                call_stack,
+                class_name,
+                "wrap",
+                "([I)[Ljava/lang/Object;",
+                None,
+                args,
+            )
+            .unwrap()
+            .unwrap()
+            .get_array()
+            .unwrap();
+        assert!(matches!(
+            wrapped.get_field_by_offset(0).unwrap(),
+            Value::ArrayRef(array) if array.ptr() == values.ptr()
+        ));
+        let text = wrapped.get_field_by_offset(1).unwrap();
+        assert_eq!(text.get_string().unwrap(), "text");
+        //String[]不能存放数组
+        let string_class = vm
+            .get_class_by_name(call_stack, "java/lang/String")
+            .unwrap();
+        let strings = vm
+            .new_array(call_stack, ArrayElement::ClassReference(string_class), 1)
+            .unwrap();
+        assert!(!strings.get_array_type().accepts(&Value::ArrayRef(values)));
+    }
+
+    #[test]
+    fn test_catch_native_exception() {
+        use crate::java_exception::{InvokeMethodResult, MethodCallError};
+        use crate::jvm_values::Value;
+        use crate::stack::CallStack;
+        use crate::test_common::create_vm;
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm();
+        fn check<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            _receiver: Option<Value<'a>>,
+            args: Vec<Value<'a>>,
+        ) -> InvokeMethodResult<'a> {
+            let value = args[0].get_int()?;
+            if value < 0 {
+                let message = format!("negative: {}", value);
+                let exception =
+                    vm.new_exception(call_stack, "java/lang/IllegalStateException", &message)?;
+                return Err(MethodCallError::ExceptionThrown(exception));
+            }
+            Ok(Some(Value::Int(value * 2)))
+        }
+        vm.native_method_area
+            .registry_native_method("NativeThrowTest", "check", "(I)I", check);
+        let call_stack = &mut vm.create_call_stack();
+        let mut call = |method: &str, descriptor: &str, value: i32| {
+            vm.call(
+                call_stack,
+                "NativeThrowTest",
+                method,
+                descriptor,
+                None,
+                vec![Value::Int(value)],
+            )
+        };
+        assert_eq!(call("recover", "(I)I", 21).unwrap(), Some(Value::Int(42)));
+        assert_eq!(call("recover", "(I)I", -1).unwrap(), Some(Value::Int(-1)));
+        let message = call("message", "(I)Ljava/lang/String;", -7)
+            .unwrap()
+            .unwrap();
+        assert_eq!(message.get_string().unwrap(), "negative: -7");
+        //捕获后继续循环，第三次调用成功
+        assert_eq!(call("retry", "(I)I", -3).unwrap(), Some(Value::Int(3)));
+        //调用方的异常表不匹配时继续向外抛出
+        match call("uncaught", "(I)I", -1) {
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                let formatted = vm.format_exception(exception);
+                assert!(formatted.starts_with("java.lang.IllegalStateException: negative: -1"));
+            }
+            _ => panic!("expected IllegalStateException"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_floating_point_bits() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let mut call =
+            |class_name: &str, method_name: &str, descriptor: &str, arg: Value<'static>| {
+                vm.call(
+                    call_stack,
+                    class_name,
+                    method_name,
+                    descriptor,
+                    None,
+                    vec![arg],
+                )
+                .unwrap()
+                .unwrap()
+            };
+        let float_bits =
+            |call: &mut dyn FnMut(&str, &str, &str, Value<'static>) -> Value<'static>,
+             method: &str,
+             value: f32| {
+                call("java/lang/Float", method, "(F)I", Value::Float(value))
+                    .get_int()
+                    .unwrap() as u32
+            };
+        //带payload的NaN，Raw版本保留原始位，非Raw版本转换为规范的NaN
+        let nan = f32::from_bits(0x7fc0_1234);
+        assert_eq!(float_bits(&mut call, "floatToRawIntBits", nan), 0x7fc0_1234);
+        assert_eq!(float_bits(&mut call, "floatToIntBits", nan), 0x7fc0_0000);
+        assert_eq!(float_bits(&mut call, "floatToIntBits", -0.0), 0x8000_0000);
+        assert_eq!(float_bits(&mut call, "floatToIntBits", 0.0), 0);
+        assert_eq!(
+            float_bits(&mut call, "floatToIntBits", 1.5),
+            1.5f32.to_bits()
+        );
+        let value = call(
+            "java/lang/Float",
+            "intBitsToFloat",
+            "(I)F",
+            Value::Int(0xffc0_0001u32 as i32),
+        );
+        assert!(matches!(value, Value::Float(v) if v.to_bits() == 0xffc0_0001));
+
+        let double_bits =
+            |call: &mut dyn FnMut(&str, &str, &str, Value<'static>) -> Value<'static>,
+             method: &str,
+             value: f64| {
+                call("java/lang/Double", method, "(D)J", Value::Double(value))
+                    .get_long()
+                    .unwrap() as u64
+            };
+        let nan = f64::from_bits(0xfff8_0000_0000_0042);
+        assert_eq!(
+            double_bits(&mut call, "doubleToRawLongBits", nan),
+            0xfff8_0000_0000_0042
+        );
+        assert_eq!(
+            double_bits(&mut call, "doubleToLongBits", nan),
+            0x7ff8_0000_0000_0000
+        );
+        assert_eq!(
+            double_bits(&mut call, "doubleToLongBits", -0.0),
+            0x8000_0000_0000_0000
+        );
+        assert_eq!(double_bits(&mut call, "doubleToLongBits", 0.0), 0);
+        let value = call(
+            "java/lang/Double",
+            "longBitsToDouble",
+            "(J)D",
+            Value::Long(f64::NEG_INFINITY.to_bits() as i64),
+        );
+        assert_eq!(value, Value::Double(f64::NEG_INFINITY));
+        //hashCode依赖doubleToLongBits，所有NaN的hashCode相同
+        let hash = call("java/lang/Double", "hashCode", "(D)I", Value::Double(nan));
+        assert_eq!(hash, Value::Int(0x7ff8_0000));
+    }
+
+    #[test]
+    fn test_operand_stack_words() {
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        //javac按字计算的max_stack恰好够用：调用(JDJD)V需要8个字，invokeinterface还要加上接收者
+        let result = vm.call(
+            call_stack,
+            "WordCountTest",
+            "callStatic",
+            "()D",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Double(24.0)));
+        let result = vm.call(
+            call_stack,
+            "WordCountTest",
+            "callInterface",
+            "()D",
+            None,
+            vec![],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Double(10.0)));
+
+        //复合赋值编译为dup2和dup2_x2
+        let values = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Long), 2)
+            .unwrap();
+        values.set_field_by_offset(0, &Value::Long(10)).unwrap();
+        values.set_field_by_offset(1, &Value::Long(20)).unwrap();
+        let result = vm
+            .call(
+                call_stack,
+                "WordCountTest",
+                "duplicate",
+                "([J)[J",
+                None,
+                vec![Value::ArrayRef(values)],
+            )
+            .unwrap()
+            .unwrap()
+            .get_array()
+            .unwrap();
+        assert_eq!(
+            result.read_all(),
+            vec![Value::Long(15), Value::Long(21), Value::Long(20)]
+        );
+        assert!(call_stack.is_consistent());
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_assertion_status() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let class_name = "AssertionStatusTest";
+        //默认关闭，assert语句不执行
+        let call_stack = &mut vm.create_call_stack();
+        let result = vm.call(
+            call_stack,
+            class_name,
+            "check",
+            "(I)I",
+            None,
+            vec![Value::Int(-1)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(-1)));
+        let result = vm.call(call_stack, class_name, "enabled", "()Z", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(0)));
+        let disabled = vm
+            .get_static_field_by_class_name(call_stack, class_name, "$assertionsDisabled")
+            .unwrap()
+            .cloned();
+        assert_eq!(disabled, Some(Value::Int(1)));
+
+        //重新初始化之后按新的状态设置$assertionsDisabled
+        vm.reset();
+        vm.set_assertions_enabled(true);
+        let call_stack = &mut vm.create_call_stack();
+        let result = vm.call(call_stack, class_name, "enabled", "()Z", None, vec![]);
+        assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        let disabled = vm
+            .get_static_field_by_class_name(call_stack, class_name, "$assertionsDisabled")
+            .unwrap()
+            .cloned();
+        assert_eq!(disabled, Some(Value::Int(0)));
+        let result = vm.call(
+            call_stack,
+            class_name,
+            "check",
+            "(I)I",
+            None,
+            vec![Value::Int(3)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(3)));
+        match vm.call(
+            call_stack,
+            class_name,
+            "check",
+            "(I)I",
+            None,
+            vec![Value::Int(-1)],
+        ) {
+            Err(MethodCallError::ExceptionThrown(exception)) => {
+                let formatted = vm.format_exception(exception);
+                assert!(
+                    formatted.starts_with("java.lang.AssertionError: value must be positive"),
+                    "{}",
+                    formatted
+                );
+            }
+            _ => panic!("expected AssertionError"),
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_class_hierarchy_reflection() {
+        use crate::jvm_values::{ReferenceValue, Value};
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+        let class_name = "ReflectHierarchyTest";
+        let descriptor = "()Ljava/lang/String;";
+        let result = vm.call(
+            call_stack,
+            class_name,
+            "hierarchy",
+            descriptor,
+            None,
+            vec![],
+        );
+        let hierarchy = result.unwrap().unwrap().get_string().unwrap();
+        //和HotSpot的输出一致：private static final为26，abstract static为1032，
+        //嵌套接口为abstract static interface，数组是final abstract并继承元素类型的可见性
+        let expected = [
+            "ReflectHierarchyTest$Square super=ReflectHierarchyTest$AbstractShape interfaces=[] \
+             interface=false array=false modifiers=26",
+            "ReflectHierarchyTest$AbstractShape super=java.lang.Object \
+             interfaces=[ReflectHierarchyTest$Shape,java.lang.Comparable] \
+             interface=false array=false modifiers=1032",
+            "ReflectHierarchyTest$Shape super=null interfaces=[] interface=true array=false \
+             modifiers=1544",
+            "java.lang.Object super=null interfaces=[] interface=false array=false modifiers=1",
+            "[LReflectHierarchyTest$Square; super=java.lang.Object \
+             interfaces=[java.lang.Cloneable,java.io.Serializable] interface=false array=true \
+             modifiers=1042",
+            "[[I super=java.lang.Object interfaces=[java.lang.Cloneable,java.io.Serializable] \
+             interface=false array=true modifiers=1041",
+        ];
+        assert_eq!(hierarchy.lines().collect::<Vec<_>>(), expected);
+        let result = vm.call(call_stack, class_name, "chain", descriptor, None, vec![]);
+        assert_eq!(
+            result.unwrap().unwrap().get_string().unwrap(),
+            "ReflectHierarchyTest$Square;ReflectHierarchyTest$AbstractShape;java.lang.Object;"
+        );
+        let list_class = vm
+            .get_class_by_name(call_stack, "java/util/AbstractList")
+            .unwrap();
+        let list_class = vm
+            .get_java_lang_class_object(call_stack, list_class)
+            .unwrap();
+        let result = vm.call(
+            call_stack,
+            class_name,
+            "isAbstractPublic",
+            "(Ljava/lang/Class;)Z",
+            None,
+            vec![Value::ObjectRef(list_class)],
+        );
+        assert_eq!(result.unwrap(), Some(Value::Int(1)));
+        //getName直接返回name字段中缓存的字符串，不会每次创建新的对象
+        let name = vm
+            .call(
+                call_stack,
+                "java/lang/Class",
+                "getName",
+                descriptor,
+                Some(Value::ObjectRef(list_class)),
+                vec![],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(name.get_string().unwrap(), "java.util.AbstractList");
+        assert_eq!(name, list_class.get_field_by_name("name").unwrap());
+        assert_eq!(call_stack.depth(), 0);
+    }
+
+    #[test]
+    fn test_call_varargs() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_error::VmError;
+        use crate::jvm_values::{ArrayElement, PrimaryType, ReferenceValue, Value};
+        use crate::test_common::create_vm;
+
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "VarargsTest")
+            .unwrap();
+        assert!(class_ref.get_method("foo", "([I)I").unwrap().is_varargs());
+        assert!(!class_ref.get_method("<init>", "()V").unwrap().is_varargs());
+
+        //多出的参数打包成int[]
+        let result = vm
+            .call_static(
+                call_stack,
+                "VarargsTest",
+                "foo",
+                &[
+                    Value::Int(1).into(),
+                    Value::Int(2).into(),
+                    Value::Int(3).into(),
+                ],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(6)));
+        let result = vm
+            .call_static(call_stack, "VarargsTest", "foo", &[])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(0)));
+        //直接传入数组时不再打包
+        let array = vm
+            .new_array(call_stack, ArrayElement::PrimaryValue(PrimaryType::Int), 2)
+            .unwrap();
+        array.set_field_by_offset(0, &Value::Int(4)).unwrap();
+        array.set_field_by_offset(1, &Value::Int(5)).unwrap();
+        let result = vm
+            .call_static(
+                call_stack,
+                "VarargsTest",
+                "foo",
+                &[Value::ArrayRef(array).into()],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(9)));
+        //元素类型不匹配
+        let result = vm.call_static(
+            call_stack,
+            "VarargsTest",
+            "foo",
+            &[Value::Int(1).into(), Value::Long(2).into()],
+        );
+        assert!(matches!(
+            result,
+            Err(MethodCallError::InternalError(
+                VmError::MethodNotFoundException(_, _)
+            ))
+        ));
+
+        let result = vm
+            .call_static(
+                call_stack,
+                "VarargsTest",
+                "join",
+                &["-".into(), "a".into(), "b".into(), "c".into()],
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(result.get_string().unwrap(), "a-b-c");
+
+        let receiver = vm
+            .new_object_by_class_name(call_stack, "VarargsTest")
+            .unwrap();
+        let result = vm
+            .call_instance(call_stack, receiver, "count", &["x".into(), "y".into()])
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(2)));
+    }
+
+    #[test]
+    fn test_primitive_class_objects() {
+        use crate::jvm_values::Value;
+        use crate::stack::CallStack;
+        use crate::test_common::{create_vm_with, RESOURCES_DIR};
+        use crate::virtual_machine::VirtualMachine;
+        let mut vm = create_vm_with(1024000, RESOURCES_DIR, true);
+        let call_stack = &mut vm.create_call_stack();
+        fn call<'a>(
+            vm: &mut VirtualMachine<'a>,
+            call_stack: &mut CallStack<'a>,
+            method: &str,
+            descriptor: &str,
+        ) -> Value<'a> {
+            vm.call(
+                call_stack,
+                "PrimitiveClassTest",
+                method,
+                descriptor,
+                None,
+                vec![],
+            )
+            .unwrap()
+            .unwrap()
         }
-
-        let native_method = self.native_method_area.get_method(
-            &class_ref.name,
-            &method_ref.name,
-            &method_ref.descriptor,
+        let string = "()Ljava/lang/String;";
+        assert_eq!(
+            call(&mut vm, call_stack, "intName", string)
+                .get_string()
+                .unwrap(),
+            "int"
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "voidName", string)
+                .get_string()
+                .unwrap(),
+            "void"
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "intIsPrimitive", "()Z"),
+            Value::Int(1)
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "integerIsPrimitive", "()Z"),
+            Value::Int(0)
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "distinctFromWrapper", "()Z"),
+            Value::Int(1)
+        );
+        //和HotSpot的输出一致
+        assert_eq!(
+            call(&mut vm, call_stack, "describeLong", string)
+                .get_string()
+                .unwrap(),
+            "long 1041 true false false 0"
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "describeRunnable", string)
+                .get_string()
+                .unwrap(),
+            "interface java.lang.Runnable 1537 true false true 0"
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "fieldTypeIsIntClass", "()Z"),
+            Value::Int(1)
+        );
+        assert_eq!(
+            call(&mut vm, call_stack, "primitiveFieldMissing", string)
+                .get_string()
+                .unwrap(),
+            "value"
         );
-        native_method.unwrap()(self, call_stack, object.map(|e| e.as_value()), args)
-    }
 
-    pub fn new_exception_stack_trace_element(
-        &mut self,
-        call_stack: &mut CallStack<'a>,
-        stack_trace_element: &Vec<StackTraceElement>,
-    ) -> ArrayReference<'a> {
-        let stack_trace_class = self
-            .lookup_class_and_initialize(call_stack, "java/lang/StackTraceElement")
+        //Integer.TYPE和宿主程序取得的是同一个对象
+        let int_class = vm
+            .get_primitive_class_object(call_stack, "int")
+            .unwrap()
             .unwrap();
-        let reference = self.new_array(
-            ArrayElement::ClassReference(stack_trace_class),
-            stack_trace_element.len(),
+        let integer_class = vm
+            .lookup_class_and_initialize(call_stack, "java/lang/Integer")
+            .unwrap();
+        assert_eq!(
+            vm.get_static(integer_class, "TYPE").cloned(),
+            Some(Value::ObjectRef(int_class))
         );
-        for (index, trace) in stack_trace_element.iter().enumerate() {
-            let trace_ele = self.new_object(stack_trace_class);
-            trace_ele
-                .set_field_by_name(
-                    "declaringClass",
-                    &Value::ObjectRef(
-                        self.new_java_lang_class_object(call_stack, trace.declaring_class.as_str())
-                            .unwrap(),
-                    ),
-                )
-                .unwrap();
-            reference
-                .set_field_by_offset(index, &Value::ObjectRef(trace_ele))
-                .unwrap();
-        }
-
-        reference
+        assert!(vm.is_primitive_class_object(int_class));
+        let wrapper = vm
+            .new_java_lang_class_object(call_stack, "java/lang/Integer")
+            .unwrap();
+        assert_ne!(wrapper, int_class);
+        assert!(!vm.is_primitive_class_object(wrapper));
+        assert!(vm
+            .get_primitive_class_object(call_stack, "java/lang/Integer")
+            .unwrap()
+            .is_none());
     }
 
-    pub fn invoke_method(
-        &mut self,
-        call_stack: &mut CallStack<'a>,
-        class_ref: ClassRef<'a>,
-        method_ref: MethodRef<'a>,
-        object: Option<impl ReferenceValue<'a>>,
-        args: Vec<Value<'a>>,
-    ) -> InvokeMethodResult<'a> {
-        if method_ref.is_native() {
-            return self.invoke_native_method(call_stack, class_ref, method_ref, object, args);
-        }
-        let mut frame = call_stack.new_frame(class_ref, method_ref, object, args)?;
-        let result = frame.as_mut().execute(self, call_stack);
-        if let Err(MethodCallError::ExceptionThrown(exception)) = result {
-            let mut stack_trace = Vec::new();
-            error!(
-                "Exception in thread \"main\" {}",
-                exception.get_class().name.replace('/', ".")
-            );
-            //调用栈回退
-            let mut current_frame = call_stack.pop_frame();
-            while current_frame.is_some() {
-                let stack_trace_element = current_frame.unwrap().as_ref().to_stack_trace();
-                error!("{}", stack_trace_element);
-                stack_trace.push(stack_trace_element);
-                current_frame = call_stack.pop_frame();
-            }
-            let stack_trace_array_ref =
-                self.new_exception_stack_trace_element(call_stack, &stack_trace);
-            exception
-                .set_field_by_name("stackTrace", &Value::ArrayRef(stack_trace_array_ref))
-                .expect("")
-        } else {
-            call_stack.pop_frame();
+    #[test]
+    fn test_initialize_system_class() {
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
+        let system_class = vm
+            .lookup_class_and_initialize(call_stack, "java/lang/System")
+            .unwrap();
+        //System初始化完成后执行initializeSystemClass，不需要run_main也能拿到out/err
+        for name in ["out", "err"] {
+            assert!(matches!(
+                vm.get_static(system_class, name),
+                Some(Value::ObjectRef(_))
+            ));
         }
-        result
+        let separator = match vm.get_static(system_class, "lineSeparator") {
+            Some(Value::ObjectRef(obj)) => *obj,
+            other => panic!("unexpected lineSeparator {:?}", other),
+        };
+        assert_eq!(vm.get_string_content(separator).unwrap(), "\n");
     }
 
-    pub fn clone_value(&mut self, value: &Value<'a>) -> Value<'a> {
-        match value {
-            Value::ObjectRef(obj) => {
-                let class_ref = obj.get_class();
-                let new_ref = self.new_object(class_ref);
-                obj.copy_to(&new_ref);
-                Value::ObjectRef(new_ref)
+    #[test]
+    fn test_print_stream_outside_system_out() {
+        use crate::test_common::create_vm_without_rt_jar;
+        use std::cell::RefCell;
+        use std::io::Write;
+        use std::rc::Rc;
+        struct Captured(Rc<RefCell<Vec<u8>>>);
+        impl Write for Captured {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().write(buf)
             }
-            Value::ArrayRef(arr) => {
-                let header = arr.get_array_header();
-                let new_ref = self.new_array(header.element, header.array_size);
-                arr.copy_to(&new_ref);
-                Value::ArrayRef(new_ref)
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
             }
-
-            _ => value.clone(),
         }
+        let output = Rc::new(RefCell::new(Vec::new()));
+        let mut vm = create_vm_without_rt_jar();
+        vm.set_stdout(Box::new(Captured(output.clone())));
+        let call_stack = &mut vm.create_call_stack();
+        //核心类中PrintStream的方法体为空，自己创建的PrintStream不会输出到stdout
+        vm.call(call_stack, "PrintStreamTest", "print", "()V", None, vec![])
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(output.borrow().clone()).unwrap(),
+            "shown\n"
+        );
     }
 
-    pub fn allocate_call_stack(&mut self) -> &'a mut CallStack<'a> {
-        let stack = self.vm_stacks.alloc(CallStack::new());
-        unsafe {
-            let stack_ptr: *mut CallStack<'a> = stack;
-            &mut *stack_ptr
-        }
+    #[test]
+    fn test_out_of_memory_error() {
+        use crate::java_exception::MethodCallError;
+        use crate::jvm_values::Value;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        let result = vm
+            .call(
+                call_stack,
+                "OutOfMemoryTest",
+                "catchOutOfMemory",
+                "(I)I",
+                None,
+                vec![Value::Int(1 << 20)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(-1)));
+        //堆满之后小的分配仍然可以成功
+        let result = vm
+            .call(
+                call_stack,
+                "OutOfMemoryTest",
+                "catchOutOfMemory",
+                "(I)I",
+                None,
+                vec![Value::Int(4)],
+            )
+            .unwrap();
+        assert_eq!(result, Some(Value::Int(4)));
+
+        let mut allocate = || match vm.call(
+            call_stack,
+            "OutOfMemoryTest",
+            "allocate",
+            "(I)[I",
+            None,
+            vec![Value::Int(1 << 20)],
+        ) {
+            Err(MethodCallError::ExceptionThrown(error)) => error,
+            other => panic!("unexpected result {:?}", other),
+        };
+        let error = allocate();
+        assert_eq!(error.get_class().name, "java/lang/OutOfMemoryError");
+        //同一个预先分配的对象
+        assert_eq!(allocate(), error);
+        assert_eq!(
+            vm.format_exception(error),
+            "java.lang.OutOfMemoryError: Java heap space"
+        );
+        assert_eq!(call_stack.depth(), 0);
     }
-}
 
-mod tests {
+    #[test]
+    fn test_ldc_string_out_of_memory() {
+        use crate::java_exception::MethodCallError;
+        use crate::test_common::create_vm_without_rt_jar;
+        let mut vm = create_vm_without_rt_jar();
+        let call_stack = &mut vm.create_call_stack();
+        vm.call(
+            call_stack,
+            "OutOfMemoryTest",
+            "fillHeap",
+            "()V",
+            None,
+            vec![],
+        )
+        .unwrap();
+        //堆已经满了，ldc创建新的String时抛出OutOfMemoryError而不是panic
+        match vm.call(
+            call_stack,
+            "OutOfMemoryTest",
+            "loadString",
+            "()Ljava/lang/String;",
+            None,
+            vec![],
+        ) {
+            Err(MethodCallError::ExceptionThrown(error)) => {
+                assert_eq!(error.get_class().name, "java/lang/OutOfMemoryError")
+            }
+            other => panic!("unexpected result {:?}", other),
+        }
+        assert_eq!(call_stack.depth(), 0);
+    }
 
     #[test]
-    fn test_thread() {
-        env_logger::init();
-        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
-        use crate::jvm_values::ObjectReference;
-        use crate::loaded_class::ClassStatus;
-        use crate::virtual_machine::VirtualMachine;
-        let mut vm = VirtualMachine::new(102400);
-        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
-        vm.add_class_path(Box::new(file_system_path));
-        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
-        let call_stack = vm.allocate_call_stack();
-        vm.add_class_path(Box::new(rt_jar_path));
+    fn test_record_execution() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::replay::{diff, RecordedValue};
+        use crate::test_common::create_vm;
+        let mut vm = create_vm();
+        let call_stack = &mut vm.create_call_stack();
         let class_ref = vm
-            .lookup_class_and_initialize(call_stack, "ThreadTest")
+            .lookup_class_and_initialize(call_stack, "ReplayTest")
             .unwrap();
-        assert_eq!(class_ref.status, ClassStatus::Initialized);
+        let method_ref = class_ref.get_method("fibonacci", "(I)I").unwrap();
 
-        let method_ref = class_ref
-            .get_method("main", "([Ljava/lang/String;)V")
+        vm.record_execution(true);
+        let result = vm
+            .invoke_method(
+                call_stack,
+                class_ref,
+                method_ref,
+                None::<ObjectReference>,
+                vec![Value::Int(10)],
+            )
             .unwrap();
+        assert_eq!(result, Some(Value::Int(55)));
+        let first = vm.take_recording();
+        //fibonacci(10)共调用177次，其中89次n<2执行5条指令，88次递归执行13条指令
+        assert_eq!(first.len(), 89 * 5 + 88 * 13);
+        let last = first.last().unwrap();
+        assert_eq!(last.method_name, "fibonacci");
+        assert_eq!(last.pc, 20);
+        assert_eq!(last.opcode, 0xac);
+
         vm.invoke_method(
             call_stack,
             class_ref,
             method_ref,
             None::<ObjectReference>,
-            Vec::new(),
+            vec![Value::Int(10)],
+        )
+        .unwrap();
+        let second = vm.take_recording();
+        assert_eq!(diff(&first, &second), None);
+
+        //参数不同，第一次出现差异的是iload_0加载的参数
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            vec![Value::Int(9)],
         )
         .unwrap();
+        let third = vm.take_recording();
+        let divergence = diff(&first, &third).unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(
+            divergence.right.unwrap().top_of_stack,
+            Some(RecordedValue::Int(9))
+        );
+
+        vm.record_execution(false);
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            method_ref,
+            None::<ObjectReference>,
+            vec![Value::Int(10)],
+        )
+        .unwrap();
+        assert!(vm.take_recording().is_empty());
     }
 
     #[test]
-    fn test_field_value() {
-        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
-        use crate::jvm_values::ObjectReference;
-        use crate::jvm_values::ReferenceValue;
-        use crate::jvm_values::Value;
-        use crate::loaded_class::ClassStatus;
-        use crate::virtual_machine::VirtualMachine;
-        let mut vm = VirtualMachine::new(102400);
-        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
-        vm.add_class_path(Box::new(file_system_path));
-        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
-        let call_stack = vm.allocate_call_stack();
-        vm.add_class_path(Box::new(rt_jar_path));
+    fn test_set_trace() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use crate::trace_filter::CollectingTraceSink;
+        let sink = CollectingTraceSink::default();
+        let mut vm = create_vm();
+        vm.set_trace_sink(Box::new(sink.clone()));
+        //修改级别不会替换已经设置的TraceSink
+        vm.set_trace_level(log::Level::Debug);
+        vm.set_trace(false);
+        let call_stack = &mut vm.create_call_stack();
         let class_ref = vm
-            .lookup_class_and_initialize(call_stack, "FieldTest")
-            .unwrap();
-        assert_eq!(class_ref.status, ClassStatus::Initialized);
-        //测试初始化数据
-        //由ConstantValue设置的初始值
-        let name = vm.get_static(class_ref, "NAME").unwrap();
-        assert_eq!(name.get_string().unwrap(), "static");
-        //由<clinit>的初始值
-        let an_int = vm
-            .get_static(class_ref, "anInt")
-            .unwrap()
-            .get_int()
+            .lookup_class_and_initialize(call_stack, "ReplayTest")
             .unwrap();
-        assert_eq!(2, an_int);
-        //初始化对象
-        let object_ref = vm.new_object(class_ref);
-        let a = object_ref.get_field_by_name("a").unwrap();
-        assert_eq!(a.get_int().unwrap(), 0i32);
-        let b = object_ref.get_field_by_name("b").unwrap();
-        assert_eq!(b, Value::Null);
-
-        let field_double = object_ref.get_field_by_name("fieldDouble").unwrap();
-        assert_eq!(field_double.get_double().unwrap(), 0f64);
-
-        //调用初始化方法
-        let init_method = class_ref.get_method("<init>", "()V").unwrap();
+        let method_ref = class_ref.get_method("fibonacci", "(I)I").unwrap();
+        //关闭追踪时即使类初始化执行了大量指令也不会有任何输出
         vm.invoke_method(
             call_stack,
             class_ref,
-            init_method,
-            Some(object_ref),
-            Vec::new(),
+            method_ref,
+            None::<ObjectReference>,
+            vec![Value::Int(3)],
         )
         .unwrap();
-        let field_double = object_ref.get_field_by_name("fieldDouble").unwrap();
-        //初始化后fieldDouble应该是100
-        assert_eq!(field_double.get_double().unwrap(), 100f64);
-        let field_float = object_ref.get_field_by_name("fieldFloat").unwrap();
-        //初始化后fieldFloat应该是50
-        assert_eq!(field_float.get_float().unwrap(), 50f32);
+        assert!(sink.lines().is_empty());
 
-        //测试方法调用
-        let main_method = class_ref.get_method("increaseInt", "()V").unwrap();
+        vm.set_trace(true);
         vm.invoke_method(
             call_stack,
             class_ref,
-            main_method,
+            method_ref,
             None::<ObjectReference>,
-            Vec::new(),
+            vec![Value::Int(3)],
         )
         .unwrap();
-        let an_int = vm.get_static(class_ref, "anInt");
-        assert!(matches!(an_int, Some(Value::Int(3))));
+        let lines = sink.lines();
+        //fibonacci(3)共调用5次
+        assert_eq!(lines.iter().filter(|l| l.contains("=>")).count(), 5);
+        assert!(lines.iter().any(|l| l.ends_with("exec Iadd")));
     }
 
     #[test]
-    fn test_exception() {
-        use crate::class_finder::{FileSystemClassPath, JarFileClassPath};
-        use crate::java_exception::MethodCallError;
-        use crate::jvm_values::ReferenceValue;
-        use crate::jvm_values::Value;
-        use crate::loaded_class::ClassStatus;
-        use crate::virtual_machine::VirtualMachine;
-        env_logger::init();
-        let mut vm = VirtualMachine::new(102400);
-        let file_system_path = FileSystemClassPath::new("./resources").unwrap();
-        vm.add_class_path(Box::new(file_system_path));
-        let rt_jar_path = JarFileClassPath::new("./resources/rt.jar").unwrap();
-        let call_stack = vm.allocate_call_stack();
-        vm.add_class_path(Box::new(rt_jar_path));
-        let class_ref = vm
-            .lookup_class_and_initialize(call_stack, "ExceptionTest")
-            .unwrap();
-        assert_eq!(class_ref.status, ClassStatus::Initialized);
-        let obj_ref = vm.new_object(class_ref);
+    fn test_trace_sink_after_level() {
+        use crate::test_common::create_vm_without_rt_jar;
+        use crate::trace_filter::TraceSink;
+        use log::Level;
+        use std::cell::Cell;
+        use std::rc::Rc;
 
-        //测试异常try-catch
-        let method_recovery = class_ref.get_method("methodRecovery", "()I").unwrap();
-        let result = vm
-            .invoke_method(
-                call_stack,
-                class_ref,
-                method_recovery,
-                Some(obj_ref),
-                Vec::new(),
-            )
-            .unwrap()
-            .unwrap();
-        assert_eq!(result, Value::Int(2));
+        struct LevelSink(Rc<Cell<Option<Level>>>);
+        impl TraceSink for LevelSink {
+            fn write_line(&mut self, _line: &str) {}
 
-        //测试抛出异常
-        let throw_null_pointer_exception = class_ref
-            .get_method("throwNullPointException", "()I")
-            .unwrap();
-        let result = vm.invoke_method(
-            call_stack,
-            class_ref,
-            throw_null_pointer_exception,
-            Some(obj_ref),
-            Vec::new(),
-        );
-        if let Err(MethodCallError::ExceptionThrown(exp)) = result {
-            let x = exp.get_class();
-            assert_eq!(x.name, "java/lang/NullPointerException")
+            fn set_level(&mut self, level: Level) {
+                self.0.set(Some(level));
+            }
         }
+        let mut vm = create_vm_without_rt_jar();
+        //先设置级别，之后设置的TraceSink也要使用这个级别
+        vm.set_trace_level(Level::Debug);
+        let level = Rc::new(Cell::new(None));
+        vm.set_trace_sink(Box::new(LevelSink(level.clone())));
+        assert_eq!(level.get(), Some(Level::Debug));
+        vm.set_trace_level(Level::Info);
+        assert_eq!(level.get(), Some(Level::Info));
+    }
 
-        //测试异常堆栈信息
-        let throw_null_pointer_exception = class_ref
-            .get_method("methodStackTrace", "()[Ljava/lang/StackTraceElement;")
+    #[test]
+    fn test_trace_filter() {
+        use crate::jvm_values::{ObjectReference, Value};
+        use crate::test_common::create_vm;
+        use crate::trace_filter::{CollectingTraceSink, TraceFilter};
+        let sink = CollectingTraceSink::default();
+        let mut vm = create_vm().with_trace_filter(TraceFilter::new(&["SwitchTest::select"]));
+        vm.set_trace_sink(Box::new(sink.clone()));
+        let call_stack = &mut vm.create_call_stack();
+        let class_ref = vm
+            .lookup_class_and_initialize(call_stack, "SwitchTest")
             .unwrap();
-        let result = vm
-            .invoke_method(
-                call_stack,
-                class_ref,
-                throw_null_pointer_exception,
-                Some(obj_ref),
-                Vec::new(),
-            )
+        let select_method = class_ref
+            .get_method("select", "(Ljava/lang/String;)I")
             .unwrap();
-        if let Some(Value::ArrayRef(array_ref)) = result {
-            assert_eq!(array_ref.get_data_length(), 2);
-        } else {
-            panic!("should has stack trace element");
-        }
+        let string_ref = vm.new_java_lang_string_object(call_stack, "BB").unwrap();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            select_method,
+            None::<ObjectReference>,
+            vec![Value::ObjectRef(string_ref)],
+        )
+        .unwrap();
+
+        //只有select本身被追踪，String.hashCode/equals内部的指令不会输出
+        let lines = sink.lines();
+        let entries: Vec<&String> = lines.iter().filter(|l| l.contains("=>")).collect();
+        assert_eq!(entries.len(), 1);
+        assert!(entries[0].contains("SwitchTest:select(Ljava/lang/String;)I"));
+        assert!(lines.iter().any(|l| l.ends_with("exec Invokevirtual(7)")));
+        assert!(lines.iter().any(|l| l.contains("exec Lookupswitch")));
+        assert!(lines.iter().any(|l| l.contains("exec Ireturn")));
+        assert!(!lines.iter().any(|l| l.contains("exec Caload")));
+        assert!(!lines.iter().any(|l| l.contains("stack:")));
+
+        //打开快照后每条指令之后输出操作数栈和局部变量表
+        vm.set_trace_filter(Some(
+            TraceFilter::new(&["SwitchTest::sel*"]).with_snapshots(true),
+        ));
+        let previous_len = sink.lines().len();
+        vm.invoke_method(
+            call_stack,
+            class_ref,
+            select_method,
+            None::<ObjectReference>,
+            vec![Value::ObjectRef(string_ref)],
+        )
+        .unwrap();
+        let lines = sink.lines()[previous_len..].to_vec();
+        let instructions = lines.iter().filter(|l| l.contains("exec ")).count();
+        let snapshots = lines.iter().filter(|l| l.contains("stack:")).count();
+        assert!(instructions > 0);
+        assert_eq!(instructions, snapshots);
     }
 }
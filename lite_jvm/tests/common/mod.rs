@@ -0,0 +1,33 @@
+//! 单元测试和集成测试共用的虚拟机构造函数。
+//! lib.rs在测试时通过#[path]引入这个文件，所以这里只使用lite_jvm::开头的路径
+#![allow(dead_code)]
+
+use lite_jvm::class_finder::{FileSystemClassPath, JarFileClassPath};
+use lite_jvm::virtual_machine::VirtualMachine;
+
+pub const RESOURCES_DIR: &str = "./resources";
+pub const RT_JAR: &str = "./resources/rt.jar";
+pub const HEAP_SIZE: usize = 102400;
+
+/// 类路径为resources目录和rt.jar
+pub fn create_vm<'a>() -> VirtualMachine<'a> {
+    create_vm_with(HEAP_SIZE, RESOURCES_DIR, true)
+}
+
+/// 类路径只有resources目录，JDK的类使用内置的核心类
+pub fn create_vm_without_rt_jar<'a>() -> VirtualMachine<'a> {
+    create_vm_with(HEAP_SIZE, RESOURCES_DIR, false)
+}
+
+pub fn create_vm_with<'a>(
+    heap_size: usize,
+    class_path: &str,
+    with_rt_jar: bool,
+) -> VirtualMachine<'a> {
+    let mut vm = VirtualMachine::new(heap_size);
+    vm.add_class_path(Box::new(FileSystemClassPath::new(class_path).unwrap()));
+    if with_rt_jar {
+        vm.add_class_path(Box::new(JarFileClassPath::new(RT_JAR).unwrap()));
+    }
+    vm
+}
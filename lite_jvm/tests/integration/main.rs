@@ -0,0 +1,3 @@
+#[path = "../common/mod.rs"]
+mod common;
+mod test_golden_output;
@@ -0,0 +1,108 @@
+use crate::common::create_vm_with;
+use lite_jvm::virtual_machine::VirtualMachine;
+use std::cell::RefCell;
+use std::io::Write;
+use std::rc::Rc;
+
+//.expected是HotSpot执行同一个class文件的标准输出
+const GOLDEN_DIR: &str = "./tests/resources/golden";
+
+#[derive(Clone, Default)]
+struct CapturedOutput(Rc<RefCell<Vec<u8>>>);
+
+impl Write for CapturedOutput {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl CapturedOutput {
+    //取出已经捕获的输出并清空
+    fn take(&self) -> String {
+        String::from_utf8(std::mem::take(&mut *self.0.borrow_mut())).unwrap()
+    }
+}
+
+//without_rt_jar时只能使用内置的核心类
+fn create_vm<'a>(without_rt_jar: bool, output: &CapturedOutput) -> VirtualMachine<'a> {
+    let mut vm = create_vm_with(1024 * 1024, GOLDEN_DIR, !without_rt_jar);
+    vm.set_stdout(Box::new(output.clone()));
+    vm
+}
+
+fn run_program(class_name: &str, args: &[&str], without_rt_jar: bool) -> String {
+    let output = CapturedOutput::default();
+    let mut vm = create_vm(without_rt_jar, &output);
+    let call_stack = &mut vm.create_call_stack();
+    if let Err(e) = vm.run_main(call_stack, class_name, args) {
+        panic!("{} failed: {:?}", class_name, e);
+    }
+    output.take()
+}
+
+fn assert_golden(class_name: &str, args: &[&str]) {
+    let expected =
+        std::fs::read_to_string(format!("{}/{}.expected", GOLDEN_DIR, class_name)).unwrap();
+    assert_eq!(
+        run_program(class_name, args, false),
+        expected,
+        "{}",
+        class_name
+    );
+}
+
+//只依赖内置核心类的程序，不使用rt.jar也要得到相同的输出
+fn assert_golden_standalone(class_name: &str, args: &[&str]) {
+    assert_golden(class_name, args);
+    let expected =
+        std::fs::read_to_string(format!("{}/{}.expected", GOLDEN_DIR, class_name)).unwrap();
+    assert_eq!(
+        run_program(class_name, args, true),
+        expected,
+        "{} without rt.jar",
+        class_name
+    );
+}
+
+#[test]
+fn test_arithmetic() {
+    assert_golden_standalone("Arithmetic", &[]);
+}
+
+#[test]
+fn test_arrays() {
+    assert_golden("ArrayOps", &["lite", "jvm"]);
+}
+
+#[test]
+fn test_exceptions() {
+    assert_golden_standalone("Exceptions", &[]);
+}
+
+#[test]
+fn test_string_building() {
+    assert_golden_standalone("StringBuilding", &[]);
+}
+
+//reset之后重新执行同一个main，静态字段和<clinit>的输出都和第一次执行一样
+#[test]
+fn test_reset_between_runs() {
+    let expected = std::fs::read_to_string(format!("{}/StaticState.expected", GOLDEN_DIR)).unwrap();
+    for without_rt_jar in [false, true] {
+        let output = CapturedOutput::default();
+        let mut vm = create_vm(without_rt_jar, &output);
+        for run in 0..3 {
+            let call_stack = &mut vm.create_call_stack();
+            if let Err(e) = vm.run_main(call_stack, "StaticState", &[]) {
+                panic!("run {} failed: {:?}", run, e);
+            }
+            assert_eq!(output.take(), expected, "run {}", run);
+            vm.reset();
+        }
+    }
+}
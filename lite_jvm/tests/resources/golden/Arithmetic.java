@@ -0,0 +1,41 @@
+public class Arithmetic {
+    static int gcd(int a, int b) {
+        return b == 0 ? a : gcd(b, a % b);
+    }
+
+    static long factorial(int n) {
+        long result = 1;
+        for (int i = 2; i <= n; i++) {
+            result *= i;
+        }
+        return result;
+    }
+
+    public static void main(String[] args) {
+        int a = 1234567;
+        int b = -89;
+        System.out.println(a + b);
+        System.out.println(a * b);
+        System.out.println(a / b);
+        System.out.println(a % b);
+        System.out.println(b >> 2);
+        System.out.println(b >>> 28);
+        System.out.println(Integer.MAX_VALUE + 1);
+        System.out.println(factorial(20));
+        System.out.println(Long.MIN_VALUE / -1);
+        System.out.println(gcd(1071, 462));
+        System.out.println((byte) 200);
+        System.out.println((char) ('a' + 2));
+        System.out.println(7 > 3 && 2 > 5);
+        System.out.print("sum=");
+        System.out.print(a + b);
+        System.out.println();
+        System.out.println("hex " + Integer.toHexString(255) + " bin " + Long.toBinaryString(10L));
+        System.out.println(0.5);
+        System.out.println(1.0f / 3);
+        System.out.println(1e20);
+        System.out.println(-0.0);
+        System.out.println(Double.NaN);
+        System.out.println((int) 3.99e10);
+    }
+}
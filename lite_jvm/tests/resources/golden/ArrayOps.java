@@ -0,0 +1,44 @@
+import java.util.Arrays;
+
+public class ArrayOps {
+    static void bubbleSort(int[] values) {
+        for (int i = 0; i < values.length; i++) {
+            for (int j = 0; j + 1 < values.length - i; j++) {
+                if (values[j] > values[j + 1]) {
+                    int t = values[j];
+                    values[j] = values[j + 1];
+                    values[j + 1] = t;
+                }
+            }
+        }
+    }
+
+    public static void main(String[] args) {
+        System.out.println(args.length);
+        for (String arg : args) {
+            System.out.println(arg);
+        }
+        int[] values = {5, -3, 9, 0, 12, 7};
+        bubbleSort(values);
+        System.out.println(Arrays.toString(values));
+        long[] squares = new long[4];
+        for (int i = 0; i < squares.length; i++) {
+            squares[i] = (long) i * i * 1000000000L;
+        }
+        System.out.println(squares[3] + squares[1]);
+        String[] words = new String[]{"lite", "jvm", "rust"};
+        StringBuilder builder = new StringBuilder();
+        for (int i = words.length - 1; i >= 0; i--) {
+            builder.append(words[i]).append(i == 0 ? "" : "-");
+        }
+        System.out.println(builder);
+        char[] chars = {'h', 'e', 'l', 'l', 'o'};
+        System.out.println(chars);
+        Object[] mixed = {"text", 42, null};
+        System.out.println(mixed[0] + " " + mixed[1] + " " + mixed[2]);
+        int[] copy = Arrays.copyOf(values, 3);
+        System.out.println(copy.length + " " + copy[2]);
+        boolean[] flags = new boolean[2];
+        System.out.println(flags[1]);
+    }
+}
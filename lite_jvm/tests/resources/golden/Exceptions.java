@@ -0,0 +1,58 @@
+public class Exceptions {
+    static class ValidationException extends Exception {
+        ValidationException(String message) {
+            super(message);
+        }
+    }
+
+    static void validate(int value) throws ValidationException {
+        if (value < 0) {
+            throw new ValidationException("negative: " + value);
+        }
+    }
+
+    static int depth(int n) {
+        try {
+            if (n == 0) {
+                throw new IllegalStateException("bottom");
+            }
+            return depth(n - 1) + 1;
+        } finally {
+            System.out.println("unwind " + n);
+        }
+    }
+
+    public static void main(String[] args) {
+        try {
+            validate(3);
+            validate(-7);
+            System.out.println("unreachable");
+        } catch (ValidationException e) {
+            System.out.println("caught " + e.getMessage());
+        }
+        try {
+            depth(2);
+        } catch (IllegalStateException e) {
+            System.out.println("caught " + e.getMessage());
+        }
+        try {
+            System.out.println(Integer.parseInt("12x"));
+        } catch (NumberFormatException e) {
+            System.out.println(e.getMessage());
+        }
+        try {
+            System.out.println("abc".substring(5));
+        } catch (IndexOutOfBoundsException e) {
+            System.out.println("index out of range");
+        }
+        try {
+            throw new UnsupportedOperationException();
+        } catch (IllegalStateException e) {
+            System.out.println("wrong handler");
+        } catch (RuntimeException e) {
+            System.out.println("runtime " + (e.getMessage() == null));
+        } finally {
+            System.out.println("finally");
+        }
+    }
+}
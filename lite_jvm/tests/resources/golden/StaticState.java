@@ -0,0 +1,25 @@
+public class StaticState {
+    static int counter = 100;
+    static final StringBuilder LOG = new StringBuilder();
+    static String lazy;
+    static int[] hits = new int[3];
+
+    static {
+        System.out.println("clinit");
+    }
+
+    public static void main(String[] args) {
+        counter++;
+        LOG.append("run;");
+        if (lazy == null) {
+            lazy = "first";
+        } else {
+            lazy = lazy + " again";
+        }
+        hits[counter % 3]++;
+        System.out.println("counter " + counter);
+        System.out.println("log " + LOG);
+        System.out.println("lazy " + lazy);
+        System.out.println("hits " + hits[0] + hits[1] + hits[2]);
+    }
+}
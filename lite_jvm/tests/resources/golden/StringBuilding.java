@@ -0,0 +1,45 @@
+public class StringBuilding {
+    static class Point {
+        private final int x;
+        private final int y;
+
+        Point(int x, int y) {
+            this.x = x;
+            this.y = y;
+        }
+
+        @Override
+        public String toString() {
+            return "(" + x + ", " + y + ")";
+        }
+    }
+
+    static String repeat(String s, int times) {
+        StringBuilder sb = new StringBuilder();
+        for (int i = 0; i < times; i++) {
+            if (i > 0) {
+                sb.append(',');
+            }
+            sb.append(s);
+        }
+        return sb.toString();
+    }
+
+    public static void main(String[] args) {
+        System.out.println(repeat("ab", 3));
+        StringBuilder sb = new StringBuilder("hello");
+        sb.append(' ').append(42).append(' ').append(-7L).append(' ').append(true);
+        System.out.println(sb);
+        System.out.println(sb.reverse());
+        System.out.println("point=" + new Point(3, -4));
+        System.out.println(1.5 + " " + 0.25f + " " + 1e10);
+        String text = "  Lite JVM  ".trim();
+        System.out.println(text.replace(' ', '_') + "|" + text.concat("!") + "|" + text.length());
+        System.out.println(text.substring(5) + " " + text.indexOf('J') + " " + text.charAt(0));
+        System.out.println("a,b".equals("a" + "," + "b") + " " + "abc".compareTo("abd"));
+        System.out.println(Integer.toHexString(255) + " " + Integer.toHexString(-1) + " " + Long.toString(Long.MIN_VALUE));
+        System.out.println(Integer.parseInt("-123") + Integer.valueOf(100) + " " + Long.parseLong("9000000000"));
+        System.out.println(Character.toUpperCase('q') + "" + Character.isDigit('7') + Boolean.valueOf(false));
+        System.out.println(Integer.MAX_VALUE + " " + Integer.MIN_VALUE + " " + (char) ('a' + 2));
+    }
+}
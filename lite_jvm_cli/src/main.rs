@@ -1,3 +1,40 @@
+use lite_jvm::java_exception::MethodCallError;
+use lite_jvm::virtual_machine::VirtualMachine;
+use std::process::exit;
+
+const HEAP_SIZE: usize = 64 * 1024 * 1024;
+
+//用法和java -cp一样：lite_jvm_cli <classpath> <主类> [参数...]
 fn main() {
-    println!("Hello, world!");
+    let args: Vec<String> = std::env::args().collect();
+    if args.len() < 3 {
+        eprintln!("usage: {} <classpath> <main class> [args...]", args[0]);
+        exit(2);
+    }
+    exit(run(&args[1], &args[2], &args[3..]));
+}
+
+//返回进程的退出状态：main正常返回是0，调用System.exit时是传入的状态，未捕获的异常和虚拟机错误是1
+fn run(class_path: &str, main_class: &str, args: &[String]) -> i32 {
+    let mut vm = VirtualMachine::new(HEAP_SIZE);
+    if let Err(e) = vm.add_class_path_str(class_path) {
+        eprintln!("Error: invalid class path {}: {}", class_path, e);
+        return 1;
+    }
+    let call_stack = &mut vm.create_call_stack();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match vm.run_main(call_stack, &main_class.replace('.', "/"), &args) {
+        Ok(status) => status,
+        Err(MethodCallError::ExceptionThrown(exception)) => {
+            eprintln!(
+                "Exception in thread \"main\" {}",
+                vm.format_exception(exception)
+            );
+            1
+        }
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            1
+        }
+    }
 }